@@ -0,0 +1,47 @@
+use crate::parse::Span;
+use std::fmt::Display;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The input could not be assembled
+    Error,
+    /// Extra context for an error, such as where something was first defined
+    Note,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A message about the input, in a form an editor can show next to the offending code
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Where the problem is, if it can be traced to a line
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Option<Span>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+        }
+    }
+
+    pub fn note(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity: Severity::Note,
+            message: message.into(),
+            span: Some(span),
+        }
+    }
+}
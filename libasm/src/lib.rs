@@ -1,8 +1,10 @@
+mod diagnostic;
 pub mod linker;
 pub mod parse;
 #[cfg(test)]
 mod test;
 
+pub use diagnostic::{Diagnostic, Severity};
 use linker::link_symbols;
 use parse::{tokenize, Lines};
 use std::{fmt::Display, io::Cursor, path::Path};
@@ -14,6 +16,17 @@ pub enum Error {
     Io(std::io::Error),
 }
 
+impl Error {
+    /// Describes the error as diagnostics which point at the offending lines
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            Error::Linker(e) => e.diagnostics(),
+            Error::Parser(e) => vec![e.diagnostic()],
+            Error::Io(e) => vec![Diagnostic::error(e.to_string(), None)],
+        }
+    }
+}
+
 impl From<linker::error::Error> for Error {
     fn from(value: linker::error::Error) -> Self {
         Self::Linker(value)
@@ -1,4 +1,5 @@
-use crate::parse::Span;
+use crate::{parse::Span, Diagnostic};
+use libseis::types::Word;
 use std::fmt::Display;
 
 #[derive(Debug)]
@@ -42,6 +43,71 @@ pub enum Error {
     MisalignedCode {
         span: Span,
     },
+    EmptyRandomRange {
+        span: Span,
+    },
+    ExistingEntry {
+        first: Span,
+        repeat: Span,
+    },
+    UnpopulatedChecksumRange {
+        start: Word,
+        end: Word,
+        span: Span,
+    },
+    SpacePastEnd {
+        span: Span,
+    },
+}
+
+impl Error {
+    /// Where the error happened
+    pub fn span(&self) -> &Span {
+        use Error::*;
+
+        match self {
+            ExistingLabel { repeat, .. }
+            | ExistingConstant { repeat, .. }
+            | ExistingEntry { repeat, .. } => repeat,
+            NonExistingLabel { usage, .. } | NonExistingConstant { usage, .. } => usage,
+            WritingToZeroPage { span }
+            | WritingToStack { span }
+            | JumpTooLong { span, .. }
+            | IntTypeMismatch { span, .. }
+            | ConstTooLong { span, .. }
+            | MisalignedCode { span }
+            | EmptyRandomRange { span }
+            | UnpopulatedChecksumRange { span, .. }
+            | SpacePastEnd { span } => span,
+        }
+    }
+
+    /// Describes the error as diagnostics, followed by a note on where something
+    /// defined twice was first defined
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        use Error::*;
+
+        let error = Diagnostic::error(self.to_string(), Some(self.span().clone()));
+
+        match self {
+            ExistingLabel { name, first, .. } => vec![
+                error,
+                Diagnostic::note(format!("Label {name} is first defined here"), first.clone()),
+            ],
+            ExistingConstant { name, first, .. } => vec![
+                error,
+                Diagnostic::note(
+                    format!("Constant {name} is first defined here"),
+                    first.clone(),
+                ),
+            ],
+            ExistingEntry { first, .. } => vec![
+                error,
+                Diagnostic::note("The entry point is first set here", first.clone()),
+            ],
+            _ => vec![error],
+        }
+    }
 }
 
 impl std::error::Error for Error {}
@@ -85,6 +151,10 @@ impl Display for Error {
             IntTypeMismatch { name, span } => write!(f, "Tried to load {name} as an integer at {span}"),
             ConstTooLong { name, span } => write!(f, "The constant {name} at {span} is too long to be put in the immediate field"),
             MisalignedCode { span } => write!(f, "The code at {span} is not aligned to a word boundary."),
+            EmptyRandomRange { span } => write!(f, "The randomized data block at {span} has a lower bound greater than its upper bound"),
+            ExistingEntry { first, repeat } => write!(f, "The entry point has already been set at {first} and got set again at {repeat}"),
+            UnpopulatedChecksumRange { start, end, span } => write!(f, "The checksum at {span} covers {start:#010X} to {end:#010X}, which is not entirely filled by code or data placed before it"),
+            SpacePastEnd { span } => write!(f, "The space reserved at {span} runs past the end of the address space"),
         }
     }
 }
@@ -5,9 +5,10 @@ mod labels;
 use self::{constants::Constant, error::Error};
 use crate::{
     linker::labels::Label,
-    parse::{Instruction, Lines, Span, StackOp},
+    parse::{Endian, Instruction, Lines, Span, StackOp},
 };
 use libseis::{
+    image::{self, Segment},
     instruction_set::Encode,
     pages::{PAGE_SIZE, STACK_PAGE, ZERO_PAGE},
     types::{Byte, SWord, Short, Word},
@@ -47,20 +48,152 @@ impl Page {
     }
 }
 
+/// A range of addresses written by the linker, and the flags of the segment it belongs to
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    start: Word,
+    end: Word,
+    flags: Word,
+}
+
 #[derive(Debug)]
-pub struct PageSet(HashMap<Short, Page>);
+pub struct PageSet {
+    pages: HashMap<Short, Page>,
+    regions: Vec<Region>,
+    entry: Word,
+    symbols: HashMap<String, Word>,
+}
 
 impl PageSet {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            pages: HashMap::new(),
+            regions: vec![],
+            entry: 0,
+            symbols: HashMap::new(),
+        }
+    }
+
+    /// The address execution begins at, set by an `entry` directive
+    pub fn entry(&self) -> Word {
+        self.entry
+    }
+
+    /// The address of each label in the program
+    pub fn symbols(&self) -> &HashMap<String, Word> {
+        &self.symbols
+    }
+
+    /// The number of pages written to
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// The number of bytes of instructions
+    pub fn code_size(&self) -> Word {
+        self.size(true)
+    }
+
+    /// The number of bytes of data blocks
+    pub fn data_size(&self) -> Word {
+        self.size(false)
+    }
+
+    /// Totals the lengths of the segments which do or do not hold instructions
+    fn size(&self, executable: bool) -> Word {
+        self.segments()
+            .iter()
+            .filter(|(segment, _)| segment.is_executable() == executable)
+            .map(|(segment, _)| segment.length)
+            .sum()
     }
 
     fn page(&mut self, page_id: Short) -> &mut Page {
-        if !self.0.contains_key(&page_id) {
-            self.0.insert(page_id, Default::default());
+        if !self.pages.contains_key(&page_id) {
+            self.pages.insert(page_id, Default::default());
+        }
+
+        self.pages.get_mut(&page_id).unwrap()
+    }
+
+    fn byte(&self, address: Word) -> u8 {
+        self.pages
+            .get(&((address >> 16) as Short))
+            .map_or(0, |page| page.data[(address & 0xFFFF) as usize])
+    }
+
+    /// Returns true if every address from `start` up to `end` has been written to
+    fn is_populated(&self, start: Word, end: Word) -> bool {
+        let mut regions = self.regions.clone();
+        regions.sort_by_key(|region| region.start);
+
+        let mut next = start;
+
+        for region in regions {
+            if region.start <= next && next < region.end {
+                next = region.end;
+            }
+        }
+
+        next >= end
+    }
+
+    /// Records that `length` bytes were written at `address`, for use in a segmented image
+    fn mark(&mut self, address: Word, length: Word, flags: Word) {
+        if length > 0 {
+            self.regions.push(Region {
+                start: address,
+                end: address.saturating_add(length),
+                flags,
+            });
+        }
+    }
+
+    /// Merges the written regions into segments, returning the segments and their contents
+    ///
+    /// Adjacent or overlapping regions with the same flags become a single segment.
+    pub fn segments(&self) -> Vec<(Segment, Vec<u8>)> {
+        let mut regions = self.regions.clone();
+        regions.sort_by_key(|region| (region.start, region.flags));
+
+        let mut merged: Vec<Region> = vec![];
+
+        for region in regions {
+            match merged.last_mut() {
+                Some(last) if last.flags == region.flags && region.start <= last.end => {
+                    last.end = last.end.max(region.end)
+                }
+                _ => merged.push(region),
+            }
         }
 
-        self.0.get_mut(&page_id).unwrap()
+        merged
+            .into_iter()
+            .map(|region| {
+                (
+                    Segment {
+                        address: region.start,
+                        length: region.end - region.start,
+                        flags: region.flags,
+                    },
+                    (region.start..region.end).map(|a| self.byte(a)).collect(),
+                )
+            })
+            .collect()
+    }
+
+    /// Writes the pages as a [segmented image](libseis::image)
+    ///
+    /// Code is placed in read-only segments, and data in writable segments.
+    pub fn write_segmented<W: Write>(self, mut destination: W) -> std::io::Result<()> {
+        let segments = self.segments();
+
+        destination.write_all(&image::encode(
+            self.entry,
+            segments
+                .iter()
+                .map(|(segment, data)| (segment.address, segment.flags, data.as_slice())),
+        ))
     }
 
     fn page_of(&mut self, address: Word) -> &mut Page {
@@ -68,10 +201,23 @@ impl PageSet {
         self.page(page_id)
     }
 
+    /// Writes the pages as a flat image
+    ///
+    /// Flat images always begin at address zero, so any other entry point is an error.
     pub fn write<W: Write + Seek>(self, mut destination: W) -> std::io::Result<()> {
         use std::io::SeekFrom::Start;
 
-        for (page_number, page) in self.0 {
+        if self.entry != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "The entry point {:#010X} can only be recorded in a segmented image",
+                    self.entry
+                ),
+            ));
+        }
+
+        for (page_number, page) in self.pages {
             let base_address = (page_number as u64) << 16;
             destination.seek(Start(base_address))?;
             destination.write(page.data())?;
@@ -80,6 +226,68 @@ impl PageSet {
     }
 }
 
+/// The seed used by randomized data blocks which do not provide one, so that builds
+/// are reproducible by default
+pub const DEFAULT_SEED: u64 = 0x5E15_535A;
+
+/// Generates `count` values within `[low, high]` for a randomized data block
+fn random_values<T>(
+    low: T,
+    high: T,
+    count: usize,
+    seed: Option<u64>,
+    span: &Span,
+) -> Result<Vec<T>, Error>
+where
+    T: rand::distributions::uniform::SampleUniform + PartialOrd,
+{
+    use rand::{distributions::Distribution, rngs::StdRng, SeedableRng};
+    use std::cmp::Ordering;
+
+    // Also rejects NaN bounds, which have no ordering
+    if !matches!(
+        low.partial_cmp(&high),
+        Some(Ordering::Less | Ordering::Equal)
+    ) {
+        return Err(Error::EmptyRandomRange { span: span.clone() });
+    }
+
+    let rng = StdRng::seed_from_u64(seed.unwrap_or(DEFAULT_SEED));
+    let dist = rand::distributions::Uniform::new_inclusive(low, high);
+
+    Ok(dist.sample_iter(rng).take(count).collect())
+}
+
+/// Flattens the big-endian bytes of data values into the byte order selected by an
+/// `endian` directive
+fn order_bytes<const N: usize>(
+    values: impl IntoIterator<Item = [Byte; N]>,
+    endian: Endian,
+) -> Vec<Byte> {
+    values
+        .into_iter()
+        .flat_map(|mut bytes| {
+            if endian == Endian::Little {
+                bytes.reverse();
+            }
+            bytes
+        })
+        .collect()
+}
+
+/// The CRC-32 of `bytes`, using the same polynomial as zlib and Ethernet
+fn crc32(bytes: impl IntoIterator<Item = Byte>) -> Word {
+    !bytes.into_iter().fold(!0, |crc, byte| {
+        (0..8).fold(crc ^ byte as Word, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
 pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
     let mut pages: PageSet = PageSet::new();
     let mut constants: HashMap<String, Constant> = HashMap::new();
@@ -131,8 +339,13 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
     let mut labels: HashMap<String, Label> = HashMap::new();
     let mut expanded = LinkedList::<(Instruction, Word, Span)>::new();
     let mut data = LinkedList::<(Vec<Byte>, Word, Span)>::new();
+    let mut checksums = Vec::<(Word, Word, Word, Endian, Span)>::new();
 
     let mut ip = 0;
+    let mut endian = Endian::Big;
+    let mut entry: Option<(String, Span)> = None;
+    // A space reaching the very end of the address space, after which nothing can be placed
+    let mut space_at_end: Option<Span> = None;
 
     // MARK: resolution and expansion
     // Step 2: resolve labels, expand LOAD instructions
@@ -141,6 +354,14 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
     for line in non_const {
         use crate::parse::LineType as T;
 
+        if let Some(span) = &space_at_end {
+            use crate::parse::Directive::*;
+
+            if !matches!(line, T::Directive(Location(_) | Endian(_) | Entry(_), _)) {
+                return Err(Error::SpacePastEnd { span: span.clone() });
+            }
+        }
+
         match line {
             T::Instruction(value, span) => {
                 if ip % 4 != 0 {
@@ -284,8 +505,8 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
 
             T::Directive(value, span) => {
                 use crate::parse::Directive::*;
-                match &value {
-                    &Location(address) => {
+                match value {
+                    Location(address) => {
                         if address & 0xFFFF_0000 == STACK_PAGE {
                             return Err(Error::WritingToStack { span });
                         }
@@ -294,7 +515,33 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
                         }
 
                         ip = address;
+                        space_at_end = None;
                     }
+                    Endian(order) => endian = order,
+                    Entry(label) => {
+                        if let Some((_, first)) = entry {
+                            return Err(Error::ExistingEntry {
+                                first,
+                                repeat: span,
+                            });
+                        }
+
+                        entry = Some((label, span));
+                    }
+                    Crc32 { start, end } => {
+                        checksums.push((start, end, ip, endian, span));
+                        ip += 4;
+                    }
+                    // Nothing is written, so the space is never marked as populated and
+                    // holds whatever memory starts with
+                    Space(length) => match ip.checked_add(length) {
+                        Some(end) => ip = end,
+                        None if ip.wrapping_add(length) == 0 => {
+                            ip = 0;
+                            space_at_end = Some(span);
+                        }
+                        None => return Err(Error::SpacePastEnd { span }),
+                    },
                 }
             }
 
@@ -320,20 +567,19 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
                         ip += len;
                     }
                     D::Short(shorts) => {
-                        let bytes: Vec<_> =
-                            shorts.into_iter().flat_map(Short::to_be_bytes).collect();
+                        let bytes = order_bytes(shorts.into_iter().map(Short::to_be_bytes), endian);
                         let len = bytes.len() as Word;
                         data.push_back((bytes, ip, span));
                         ip += len;
                     }
                     D::Word(words) => {
-                        let bytes: Vec<_> = words.into_iter().flat_map(Word::to_be_bytes).collect();
+                        let bytes = order_bytes(words.into_iter().map(Word::to_be_bytes), endian);
                         let len = bytes.len() as Word;
                         data.push_back((bytes, ip, span));
                         ip += len;
                     }
                     D::Float(floats) => {
-                        let bytes: Vec<_> = floats.into_iter().flat_map(f32::to_be_bytes).collect();
+                        let bytes = order_bytes(floats.into_iter().map(f32::to_be_bytes), endian);
                         let len = bytes.len() as Word;
                         data.push_back((bytes, ip, span));
                         ip += len;
@@ -371,65 +617,40 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
 
             T::RandomData(content, span) => {
                 use crate::parse::RandomData as D;
-                use rand::distributions::{Distribution, Uniform};
-                use rand::{random, rngs::StdRng, SeedableRng};
 
-                match content {
+                let bytes: Vec<_> = match content {
                     D::Byte(low, high, count, seed) => {
-                        let rng = StdRng::seed_from_u64(seed.unwrap_or_else(random));
-                        let dist = Uniform::new_inclusive(low, high);
-
-                        let bytes = dist.sample_iter(rng).take(count).collect::<Vec<u8>>();
-
-                        let len = bytes.len() as Word;
-                        data.push_back((bytes, ip, span));
-                        ip += len;
+                        random_values(low, high, count, seed, &span)?
                     }
                     D::Short(low, high, count, seed) => {
-                        let rng = StdRng::seed_from_u64(seed.unwrap_or_else(random));
-                        let dist = Uniform::new_inclusive(low, high);
-
-                        let bytes: Vec<_> = dist
-                            .sample_iter(rng)
-                            .take(count)
-                            .flat_map(Short::to_be_bytes)
-                            .collect();
-                        let len = bytes.len() as Word;
-                        data.push_back((bytes, ip, span));
-                        ip += len;
+                        let values: Vec<Short> = random_values(low, high, count, seed, &span)?;
+                        order_bytes(values.into_iter().map(Short::to_be_bytes), endian)
                     }
                     D::Word(low, high, count, seed) => {
-                        let rng = StdRng::seed_from_u64(seed.unwrap_or_else(random));
-                        let dist = Uniform::new_inclusive(low, high);
-
-                        let bytes: Vec<_> = dist
-                            .sample_iter(rng)
-                            .take(count)
-                            .flat_map(Word::to_be_bytes)
-                            .collect();
-                        let len = bytes.len() as Word;
-                        data.push_back((bytes, ip, span));
-                        ip += len;
+                        let values: Vec<Word> = random_values(low, high, count, seed, &span)?;
+                        order_bytes(values.into_iter().map(Word::to_be_bytes), endian)
                     }
                     D::Float(low, high, count, seed) => {
-                        let rng = StdRng::seed_from_u64(seed.unwrap_or_else(random));
-                        let dist = Uniform::new_inclusive(low, high);
-
-                        let bytes: Vec<_> = dist
-                            .sample_iter(rng)
-                            .take(count)
-                            .flat_map(f32::to_be_bytes)
-                            .collect();
-                        let len = bytes.len() as Word;
-                        data.push_back((bytes, ip, span));
-                        ip += len;
+                        let values: Vec<f32> = random_values(low, high, count, seed, &span)?;
+                        order_bytes(values.into_iter().map(f32::to_be_bytes), endian)
                     }
-                }
+                };
+
+                let len = bytes.len() as Word;
+                data.push_back((bytes, ip, span));
+                ip += len;
             }
             _ => unreachable!("constants are all removed from the list"),
         }
     }
 
+    if let Some((name, usage)) = entry {
+        match labels.get(&name) {
+            Some(label) => pages.entry = label.address,
+            None => return Err(Error::NonExistingLabel { name, usage }),
+        }
+    }
+
     // MARK: write instructions
     // Stage 3: transform into instructions
 
@@ -452,6 +673,14 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
             println!("At {span}:\nBy writing to address {address:#x}, you may potentially be overwriting code or data. It is recommended that you move the code or data elsewhere.")
         }
 
+        // Loads of labels are the only instructions which are still two words long
+        let length = if matches!(instruction, I::Load(_)) {
+            8
+        } else {
+            4
+        };
+        pages.mark(address, length, Segment::READ_ONLY | Segment::EXECUTABLE);
+
         let write = |(b, a)| pages.page_of(a).write(a, b);
 
         macro_rules! transform {
@@ -459,7 +688,7 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
                 match $j {
                     J::Absolute(reg) => Ok(Jump::Register(reg)),
                     J::Relative(rel) => Ok(Jump::Relative((rel as SWord) << 2)),
-                    J::Label(label_name) => {
+                    J::Label(label_name, usage) => {
                         if let Some(label) = labels.get(&label_name) {
                             let laddr = label.address;
                             let dist = (laddr.wrapping_sub(address)) as SWord;
@@ -474,7 +703,7 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
                         } else {
                             Err(Error::NonExistingLabel {
                                 name: label_name,
-                                usage: span.clone(),
+                                usage,
                             })
                         }
                     }
@@ -684,8 +913,10 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
                         address,
                         destination,
                         volatile,
+                        non_temporal,
                     } => Ok(register::ReadOp::Indirect {
                         volatile,
+                        non_temporal,
                         address,
                         destination,
                     }),
@@ -694,22 +925,26 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
                         offset,
                         destination,
                         volatile,
+                        non_temporal,
                     } => Ok(register::ReadOp::OffsetIndirect {
                         address,
                         offset,
                         destination,
                         volatile,
+                        non_temporal,
                     }),
                     MLO::Indexed {
                         address,
                         index,
                         destination,
                         volatile,
+                        non_temporal,
                     } => Ok(register::ReadOp::IndexedIndirect {
                         address,
                         index,
                         destination,
                         volatile,
+                        non_temporal,
                     }),
                     MLO::Stack {
                         offset,
@@ -760,8 +995,10 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
                         address,
                         source,
                         volatile,
+                        non_temporal,
                     } => Ok(register::WriteOp::Indirect {
                         volatile,
+                        non_temporal,
                         address,
                         source,
                     }),
@@ -770,22 +1007,26 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
                         offset,
                         source,
                         volatile,
+                        non_temporal,
                     } => Ok(register::WriteOp::OffsetIndirect {
                         address,
                         offset,
                         source,
                         volatile,
+                        non_temporal,
                     }),
                     MSO::Indexed {
                         address,
                         index,
                         source,
                         volatile,
+                        non_temporal,
                     } => Ok(register::WriteOp::IndexedIndirect {
                         address,
                         index,
                         source,
                         volatile,
+                        non_temporal,
                     }),
                     MSO::Stack { offset, source } => {
                         Ok(register::WriteOp::StackOffset { offset, source })
@@ -861,6 +1102,12 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
                 .into_iter()
                 .zip(address..)
                 .for_each(write),
+            I::Syscall => Control(Syscall)
+                .encode()
+                .to_be_bytes()
+                .into_iter()
+                .zip(address..)
+                .for_each(write),
 
             I::Add(b) => Integer(Add(transform!(ibo b)?))
                 .encode()
@@ -1098,6 +1345,12 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
             .into_iter()
             .zip(address..)
             .for_each(write),
+            I::Rdcyc(destination) => Register(Rdcyc(destination))
+                .encode()
+                .to_be_bytes()
+                .into_iter()
+                .zip(address..)
+                .for_each(write),
             I::Ldr(l) => {
                 use crate::parse::ImmediateLoadOp as L;
                 use libseis::instruction_set::register::ImmOp::*;
@@ -1162,7 +1415,11 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
                 .for_each(write)
             }
 
-            I::Load(Label { ident, destination }) => {
+            I::Load(Label {
+                ident,
+                span: usage,
+                destination,
+            }) => {
                 if let Some(label) = labels.get(&ident) {
                     use register::ImmOp::Immediate;
                     let left = (label.address & 0xFFFF_0000) >> 16;
@@ -1191,10 +1448,7 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
                     .zip(address..)
                     .for_each(write)
                 } else {
-                    return Err(Error::NonExistingLabel {
-                        name: ident,
-                        usage: span,
-                    });
+                    return Err(Error::NonExistingLabel { name: ident, usage });
                 }
             }
 
@@ -1209,10 +1463,40 @@ pub fn link_symbols(lines: Lines) -> Result<PageSet, Error> {
             println!("At {span}:\nBy writing to address {address:#x}, you may potentially be overwriting code or data. It is recommended that you move the code or data elsewhere.")
         }
 
+        pages.mark(address, data.len() as Word, 0);
+
         data.into_iter()
             .zip(address..)
             .for_each(|(b, a)| pages.page_of(a).write(a, b));
     }
 
+    // MARK: checksums
+    // Step 4: compute checksums over everything placed before them, in order, so a checksum may
+    // cover the ones before it
+
+    for (start, end, address, endian, span) in checksums {
+        if !pages.is_populated(start, end) {
+            return Err(Error::UnpopulatedChecksumRange { start, end, span });
+        }
+
+        if pages.page_of(address).len > (address & 0x0000_FFFF) {
+            println!("At {span}:\nBy writing to address {address:#x}, you may potentially be overwriting code or data. It is recommended that you move the code or data elsewhere.")
+        }
+
+        let crc = crc32((start..end).map(|a| pages.byte(a)));
+
+        pages.mark(address, 4, 0);
+
+        order_bytes([crc.to_be_bytes()], endian)
+            .into_iter()
+            .zip(address..)
+            .for_each(|(b, a)| pages.page_of(a).write(a, b));
+    }
+
+    pages.symbols = labels
+        .into_iter()
+        .map(|(name, label)| (name, label.address))
+        .collect();
+
     Ok(pages)
 }
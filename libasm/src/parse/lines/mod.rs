@@ -18,24 +18,39 @@ use std::{
 pub struct Span {
     pub file: PathBuf,
     pub line: u64,
+    /// The column the code starts at, counting from 1
+    pub start: u64,
+    /// The column just past the end of the code, or past the end of its first line
+    pub end: u64,
 }
 
 impl Span {
-    pub fn new(file: &Path, line: u64) -> Self {
+    pub fn new(file: &Path, line: u64, start: u64, end: u64) -> Self {
         Self {
             file: file.to_owned(),
             line,
+            start,
+            end,
         }
     }
+
+    /// Finds where a piece of parsed code lies in its file
+    pub fn from_pest(file: &Path, span: pest::Span) -> Self {
+        let (line, start) = span.start_pos().line_col();
+        let length = span.as_str().lines().next().unwrap_or("").chars().count();
+
+        Self::new(file, line as u64, start as u64, (start + length) as u64)
+    }
 }
 
 impl Display for Span {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{}:{}",
+            "{}:{}:{}",
             self.file.file_name().unwrap().to_string_lossy(),
-            self.line
+            self.line,
+            self.start
         )
     }
 }
@@ -106,16 +121,35 @@ pub struct Constant {
     pub value: ConstantValue,
 }
 
+/// The byte order used when emitting data blocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endian {
+    #[default]
+    Big,
+    Little,
+}
+
 #[derive(Debug)]
 pub enum Directive {
     Location(Word),
+    Endian(Endian),
+    /// The label execution begins at
+    Entry(String),
+    /// Emits the CRC-32 of the bytes from `start` up to (but not including) `end` as a word
+    Crc32 {
+        start: Word,
+        end: Word,
+    },
+    /// Reserves a number of bytes, which are left unwritten
+    Space(Word),
 }
 
 #[derive(Debug)]
 pub enum Jump {
     Relative(SWord),
     Absolute(Register),
-    Label(String),
+    /// Jumps to a label, found where its name lies in the source
+    Label(String, Span),
 }
 
 #[derive(Debug)]
@@ -141,6 +175,7 @@ pub enum Instruction {
     Jge(Jump),
     Jle(Jump),
     Ret,
+    Syscall,
 
     Add(IntBinaryOp),
     Sub(IntBinaryOp),
@@ -184,4 +219,5 @@ pub enum Instruction {
     Tfr(Register, Register),
     Ldr(ImmediateLoadOp),
     Load(ExpandableLoadOp),
+    Rdcyc(Register),
 }
@@ -1,3 +1,4 @@
+use super::Span;
 use libseis::types::{Byte, Register, Short};
 
 #[derive(Debug)]
@@ -20,18 +21,21 @@ pub enum MemoryLoadOp {
         address: Register,
         destination: Register,
         volatile: bool,
+        non_temporal: bool,
     },
     Offset {
         address: Register,
         offset: Short,
         destination: Register,
         volatile: bool,
+        non_temporal: bool,
     },
     Indexed {
         address: Register,
         index: Register,
         destination: Register,
         volatile: bool,
+        non_temporal: bool,
     },
     Stack {
         offset: Short,
@@ -53,18 +57,21 @@ pub enum MemoryStoreOp {
         address: Register,
         source: Register,
         volatile: bool,
+        non_temporal: bool,
     },
     Offset {
         address: Register,
         offset: Short,
         source: Register,
         volatile: bool,
+        non_temporal: bool,
     },
     Indexed {
         address: Register,
         index: Register,
         source: Register,
         volatile: bool,
+        non_temporal: bool,
     },
     Stack {
         offset: Short,
@@ -102,6 +109,8 @@ pub enum ExpandableLoadOp {
     },
     Label {
         ident: String,
+        /// Where the name of the label lies in the source
+        span: Span,
         destination: Register,
     },
     ConstantVal {
@@ -0,0 +1,318 @@
+//! Expansion of assembly macros, which runs over the source before it is parsed.
+//!
+//! A macro is defined by the lines between `.macro NAME PARAM...` and `.endm`. A line
+//! starting with the name of a macro is replaced by its body, with each parameter
+//! replaced by the matching argument wherever it appears as a whole identifier.
+//! Arguments, like parameters, are separated by commas or whitespace.
+//!
+//! The expansion remembers where each of its lines came from, so errors found while
+//! parsing it can be reported against the source.
+
+use pest::{
+    error::{Error as PestError, InputLocation},
+    Position, RuleType, Span,
+};
+use std::collections::HashMap;
+
+/// A macro defined in the source
+struct Macro<'a> {
+    params: Vec<&'a str>,
+    /// The lines of the body, each with its byte offset into the source
+    body: Vec<(usize, &'a str)>,
+}
+
+/// Where a line of the expanded source came from
+#[derive(Debug, Clone, Copy)]
+enum Origin {
+    /// The line of the source starting at this byte offset
+    Source(usize),
+    /// A line of a macro's body, expanded from the invocation between these byte offsets
+    Invocation(usize, usize),
+}
+
+/// The source with its macros expanded
+#[derive(Debug)]
+pub struct Expansion {
+    pub text: String,
+    /// The byte offset into `text` that each line starts at, with where it came from
+    lines: Vec<(usize, Origin)>,
+}
+
+impl Expansion {
+    fn push(&mut self, line: &str, origin: Origin) {
+        self.lines.push((self.text.len(), origin));
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    /// Maps a byte offset into the expanded text back to `source`
+    ///
+    /// An offset into a line expanded from a macro maps to the start of its invocation,
+    /// or to the end if the offset `ends` a span.
+    fn source_offset(&self, source: &str, offset: usize, ends: bool) -> usize {
+        // The end of a span belongs to the line holding the byte before it
+        let within = if ends {
+            offset.saturating_sub(1)
+        } else {
+            offset
+        };
+        let i = self
+            .lines
+            .partition_point(|&(start, _)| start <= within)
+            .saturating_sub(1);
+
+        let offset = match self.lines.get(i) {
+            Some(&(start, Origin::Source(line))) => line + offset - start,
+            Some(&(_, Origin::Invocation(start, _))) if !ends => start,
+            Some(&(_, Origin::Invocation(_, end))) => end,
+            None => offset,
+        };
+
+        offset.min(source.len())
+    }
+
+    /// Maps a span of the expanded text back to `source`
+    pub fn source_span<'i>(&self, source: &'i str, span: Span) -> Span<'i> {
+        self.source_range(source, span.start(), span.end())
+    }
+
+    fn source_range<'i>(&self, source: &'i str, start: usize, end: usize) -> Span<'i> {
+        let start = self.source_offset(source, start, false);
+        let end = self.source_offset(source, end, true).max(start);
+
+        Span::new(source, start, end).unwrap()
+    }
+
+    /// Moves an error found while parsing the expanded text to where it lies in `source`
+    pub fn source_error<R: RuleType>(&self, source: &str, error: PestError<R>) -> PestError<R> {
+        match error.location {
+            InputLocation::Pos(pos) => {
+                let offset = self.source_offset(source, pos, false);
+                PestError::new_from_pos(error.variant, Position::new(source, offset).unwrap())
+            }
+            InputLocation::Span((start, end)) => {
+                PestError::new_from_span(error.variant, self.source_range(source, start, end))
+            }
+        }
+    }
+}
+
+/// An error found while expanding macros
+#[derive(Debug)]
+pub struct ExpansionError {
+    /// The byte offset into the source of the line at fault
+    pub offset: usize,
+    pub message: String,
+}
+
+/// Shorthand for returning an [`ExpansionError`]
+macro_rules! fail {
+    ($offset:expr, $($message:tt)*) => {
+        return Err(ExpansionError {
+            offset: $offset,
+            message: format!($($message)*),
+        })
+    };
+}
+
+/// Strips the comment from a line
+fn code(line: &str) -> &str {
+    let end = [line.find(';'), line.find("//")]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(line.len());
+
+    &line[..end]
+}
+
+/// Splits a line into words separated by commas or whitespace
+fn split_words(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+}
+
+fn is_ident(word: &str) -> bool {
+    let mut chars = word.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Replaces each whole identifier in `line` which names a parameter with its argument
+///
+/// Strings and the names of constants (`.name`) are left as they are.
+fn substitute(line: &str, params: &[&str], args: &[&str]) -> String {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    let mut result = String::with_capacity(line.len());
+    let mut chars = line.char_indices().peekable();
+    let mut quoted = false;
+
+    while let Some((start, c)) = chars.next() {
+        if quoted || !is_word(c) {
+            result.push(c);
+
+            match c {
+                '"' => quoted = !quoted,
+                // An escaped quote does not end the string
+                '\\' if quoted => result.extend(chars.next().map(|(_, c)| c)),
+                _ => (),
+            }
+
+            continue;
+        }
+
+        let mut end = start + c.len_utf8();
+        while let Some(&(i, c)) = chars.peek().filter(|&&(_, c)| is_word(c)) {
+            end = i + c.len_utf8();
+            chars.next();
+        }
+
+        let word = &line[start..end];
+        match params.iter().position(|&param| param == word) {
+            Some(i) if !result.ends_with('.') => result.push_str(args[i]),
+            _ => result.push_str(word),
+        }
+    }
+
+    result
+}
+
+/// Expands the macros defined in `data`, returning the source to parse
+///
+/// The lines of each definition are left blank, so the lines before the first
+/// invocation keep their numbers.
+pub fn expand(data: &str) -> Result<Expansion, ExpansionError> {
+    let mut macros = HashMap::new();
+    let mut output = Expansion {
+        text: String::with_capacity(data.len()),
+        lines: vec![],
+    };
+    let mut lines = data.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line.trim_end_matches(['\r', '\n'])))
+    });
+
+    while let Some((offset, line)) = lines.next() {
+        let mut words = split_words(code(line));
+
+        match words.next() {
+            Some(word) if word.eq_ignore_ascii_case(".macro") => {
+                let Some(name) = words.next() else {
+                    fail!(offset, "\".macro\" expects the name of the macro");
+                };
+                if !is_ident(name) {
+                    fail!(offset, "\"{name}\" is not a valid macro name");
+                }
+                if macros.contains_key(name) {
+                    fail!(offset, "Macro \"{name}\" is already defined");
+                }
+
+                let mut params: Vec<&str> = vec![];
+                for param in words {
+                    if !is_ident(param) {
+                        fail!(offset, "\"{param}\" is not a valid parameter name");
+                    }
+                    if params.contains(&param) {
+                        fail!(offset, "Parameter \"{param}\" is repeated");
+                    }
+                    params.push(param);
+                }
+
+                let mut body = vec![];
+                output.push("", Origin::Source(offset));
+
+                loop {
+                    let Some((line_offset, line)) = lines.next() else {
+                        fail!(offset, "Macro \"{name}\" is never ended by \".endm\"");
+                    };
+                    output.push("", Origin::Source(line_offset));
+
+                    match split_words(code(line)).next() {
+                        Some(word) if word.eq_ignore_ascii_case(".endm") => break,
+                        Some(word) if word.eq_ignore_ascii_case(".macro") => {
+                            fail!(line_offset, "Macros cannot be defined inside another macro");
+                        }
+                        _ => body.push((line_offset, line)),
+                    }
+                }
+
+                macros.insert(name, Macro { params, body });
+            }
+            Some(word) if word.eq_ignore_ascii_case(".endm") => {
+                fail!(offset, "\".endm\" without a macro");
+            }
+            _ => expand_line(
+                &macros,
+                offset,
+                line,
+                Origin::Source(offset),
+                &mut vec![],
+                &mut output,
+            )?,
+        }
+    }
+
+    Ok(output)
+}
+
+/// Writes a line to the output, expanding it first if it invokes a macro
+///
+/// `stack` holds the macros being expanded, so one which invokes itself, directly or
+/// through other macros, is caught instead of expanding forever.
+fn expand_line<'a>(
+    macros: &HashMap<&'a str, Macro<'a>>,
+    offset: usize,
+    line: &str,
+    origin: Origin,
+    stack: &mut Vec<&'a str>,
+    output: &mut Expansion,
+) -> Result<(), ExpansionError> {
+    let code = code(line);
+    let mut words = split_words(code);
+
+    let Some((&name, definition)) = words.next().and_then(|word| macros.get_key_value(word)) else {
+        output.push(line, origin);
+        return Ok(());
+    };
+
+    if stack.contains(&name) {
+        fail!(
+            offset,
+            "Macro \"{name}\" expands recursively: {} -> {name}",
+            stack.join(" -> ")
+        );
+    }
+
+    let args: Vec<&str> = words.collect();
+    if args.len() != definition.params.len() {
+        fail!(
+            offset,
+            "Macro \"{name}\" expects {} arguments, found {}",
+            definition.params.len(),
+            args.len()
+        );
+    }
+
+    // Lines expanded from a macro are reported at the invocation in the source
+    let origin = match origin {
+        Origin::Source(_) => Origin::Invocation(
+            offset + code.len() - code.trim_start().len(),
+            offset + code.trim_end().len(),
+        ),
+        origin => origin,
+    };
+
+    stack.push(name);
+    for &(offset, line) in &definition.body {
+        let line = substitute(line, &definition.params, &args);
+        expand_line(macros, offset, &line, origin, stack, output)?;
+    }
+    stack.pop();
+
+    Ok(())
+}
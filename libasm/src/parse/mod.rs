@@ -1,16 +1,20 @@
 mod asm_parser;
 mod error;
 mod lines;
+mod macros;
 
 use asm_parser::AsmParser;
 use asm_parser::Rule;
 pub use error::{Error, ErrorSource};
-use libseis::{registers, types::SWord};
+use libseis::{
+    registers,
+    types::{SWord, Word},
+};
 pub use lines::*;
 use pest::{
     error::{Error as PestError, ErrorVariant},
     iterators::{Pair, Pairs},
-    Parser,
+    Parser, Position,
 };
 use std::{
     fs::File,
@@ -25,6 +29,33 @@ macro_rules! parse_integer {
     };
 }
 
+/// Expands to a [`parse_integer`] of the immediate of a comparison or test, which is
+/// sign-extended from 15 bits, returning an error if it does not fit.
+///
+/// The sign of an integer is a separate pair, so whether it was negative is passed in.
+macro_rules! parse_comparison_immediate {
+    ($i:expr, $negative:expr) => {{
+        let pair = $i;
+        let span = pair.as_span();
+        let value: i64 = parse_integer!(pair);
+        let value = if $negative { -value } else { value };
+
+        if !(-0x4000..0x4000).contains(&value) {
+            return Err(PestError::new_from_span(
+                ErrorVariant::CustomError {
+                    message: format!(
+                        "Value {value} is too long to fit in a signed 15-bit immediate field"
+                    ),
+                },
+                span,
+            )
+            .into());
+        }
+
+        value as Word
+    }};
+}
+
 /// The value passed must be any one of [`Rule::oct`], [`Rule::dec`], or [`Rule::hex`].
 ///
 /// Converts the integer into a base-10 representation.
@@ -63,19 +94,50 @@ fn convert_base(pair: Pair<Rule>) -> Result<String, ErrorSource> {
 
 fn tokenize_data_block(mut pair: Pairs<Rule>) -> Result<Data, ErrorSource> {
     match pair.next().unwrap().as_str().to_lowercase().as_str() {
-        "byte" => pair
-            .map(|b| match b.as_rule() {
-                Rule::dec | Rule::oct | Rule::hex => Ok(parse_integer!(b)),
-                _ => Err(PestError::new_from_span(
-                    ErrorVariant::CustomError {
-                        message: "Byte blocks can only store integers".into(),
-                    },
-                    b.as_span(),
-                )
-                .into()),
-            })
-            .collect::<Result<_, _>>()
-            .map(Data::Byte),
+        "byte" => {
+            let mut bytes = vec![];
+
+            for b in pair {
+                match b.as_rule() {
+                    Rule::dec | Rule::oct | Rule::hex => bytes.push(parse_integer!(b)),
+                    Rule::hexstring => {
+                        let str = b.as_str();
+                        let digits = &str[2..str.len() - 1];
+
+                        // Each pair of digits is a byte
+                        if digits.len() % 2 != 0 {
+                            return Err(PestError::new_from_span(
+                                ErrorVariant::CustomError {
+                                    message: format!(
+                                        "Hex strings must have an even number of digits, found {}",
+                                        digits.len()
+                                    ),
+                                },
+                                b.as_span(),
+                            )
+                            .into());
+                        }
+
+                        bytes.extend((0..digits.len()).step_by(2).map(|i| {
+                            u8::from_str_radix(&digits[i..i + 2], 16)
+                                .expect("Hex strings only contain hex digits")
+                        }));
+                    }
+                    _ => {
+                        return Err(PestError::new_from_span(
+                            ErrorVariant::CustomError {
+                                message: "Byte blocks can only store integers and hex strings"
+                                    .into(),
+                            },
+                            b.as_span(),
+                        )
+                        .into())
+                    }
+                }
+            }
+
+            Ok(Data::Byte(bytes))
+        }
         "short" => pair
             .map(|b| match b.as_rule() {
                 Rule::dec | Rule::oct | Rule::hex => Ok(parse_integer!(b)),
@@ -158,8 +220,22 @@ fn tokenize_constant(mut pair: Pairs<Rule>) -> Result<Constant, ErrorSource> {
 fn tokenize_directive(mut pair: Pairs<Rule>) -> Result<Directive, ErrorSource> {
     let ident = pair.next().unwrap();
     let value = pair.next();
+    let second = pair.next();
+    let name = ident.as_str().to_lowercase();
+
+    if let Some(second) = &second {
+        if name != "crc32" {
+            return Err(PestError::new_from_span(
+                ErrorVariant::CustomError {
+                    message: format!("\"{name}\" expects a single value"),
+                },
+                second.as_span(),
+            )
+            .into());
+        }
+    }
 
-    match ident.as_str().to_lowercase().as_str() {
+    match name.as_str() {
         "location" => {
             let value = value.ok_or_else(|| {
                 PestError::new_from_pos(
@@ -176,6 +252,108 @@ fn tokenize_directive(mut pair: Pairs<Rule>) -> Result<Directive, ErrorSource> {
                 .unwrap())))
         }
 
+        "endian" => {
+            let message = "\"endian\" expects \"big\" or \"little\"".to_owned();
+            let Some(value) = value else {
+                return Err(PestError::new_from_pos(
+                    ErrorVariant::CustomError { message },
+                    ident.as_span().end_pos(),
+                )
+                .into());
+            };
+
+            match value.as_str().to_lowercase().as_str() {
+                "\"big\"" => Ok(Directive::Endian(Endian::Big)),
+                "\"little\"" => Ok(Directive::Endian(Endian::Little)),
+                _ => Err(PestError::new_from_span(
+                    ErrorVariant::CustomError { message },
+                    value.as_span(),
+                )
+                .into()),
+            }
+        }
+
+        "entry" => {
+            let message = "\"entry\" expects the name of a label".to_owned();
+            let Some(value) = value else {
+                return Err(PestError::new_from_pos(
+                    ErrorVariant::CustomError { message },
+                    ident.as_span().end_pos(),
+                )
+                .into());
+            };
+
+            match value.as_rule() {
+                Rule::string => Ok(Directive::Entry(
+                    value.as_str().trim_matches('"').to_owned(),
+                )),
+                _ => Err(PestError::new_from_span(
+                    ErrorVariant::CustomError { message },
+                    value.as_span(),
+                )
+                .into()),
+            }
+        }
+
+        "space" => {
+            let message = "\"space\" expects a number of bytes".to_owned();
+            let Some(value) = value else {
+                return Err(PestError::new_from_pos(
+                    ErrorVariant::CustomError { message },
+                    ident.as_span().end_pos(),
+                )
+                .into());
+            };
+
+            match value.as_rule() {
+                Rule::integer => Ok(Directive::Space(parse_integer!(value
+                    .into_inner()
+                    .next()
+                    .unwrap()))),
+                _ => Err(PestError::new_from_span(
+                    ErrorVariant::CustomError { message },
+                    value.as_span(),
+                )
+                .into()),
+            }
+        }
+
+        "crc32" => {
+            let message = "\"crc32\" expects the start and end addresses of a range".to_owned();
+            let (Some(start), Some(end)) = (value, second) else {
+                return Err(PestError::new_from_pos(
+                    ErrorVariant::CustomError { message },
+                    ident.as_span().end_pos(),
+                )
+                .into());
+            };
+
+            if start.as_rule() != Rule::integer {
+                return Err(PestError::new_from_span(
+                    ErrorVariant::CustomError { message },
+                    start.as_span(),
+                )
+                .into());
+            }
+
+            let span = end.as_span();
+            let start: Word = parse_integer!(start.into_inner().next().unwrap());
+            let end: Word = parse_integer!(end.into_inner().next().unwrap());
+
+            if end < start {
+                return Err(PestError::new_from_span(
+                    ErrorVariant::CustomError {
+                        message: "The end of a \"crc32\" range must not come before its start"
+                            .to_owned(),
+                    },
+                    span,
+                )
+                .into());
+            }
+
+            Ok(Directive::Crc32 { start, end })
+        }
+
         x => Err(PestError::new_from_span(
             ErrorVariant::CustomError {
                 message: format!("Did not recognize directive \"{x}\""),
@@ -186,13 +364,29 @@ fn tokenize_directive(mut pair: Pairs<Rule>) -> Result<Directive, ErrorSource> {
     }
 }
 
-fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSource> {
+/// The error for a non-temporal hint on an addressing mode which cannot hold it
+fn non_temporal_error(span: pest::Span) -> ErrorSource {
+    PestError::new_from_span(
+        ErrorVariant::CustomError {
+            message: "The non-temporal hint only applies to indirect addressing".into(),
+        },
+        span,
+    )
+    .into()
+}
+
+/// Tokenizes an instruction, where `span_of` finds where a piece of it lies in the source
+fn tokenize_instruction(
+    mut pair: Pairs<Rule>,
+    span_of: &dyn Fn(pest::Span) -> Span,
+) -> Result<Instruction, ErrorSource> {
     let instruction = pair.next().unwrap();
 
     match instruction.as_rule() {
         Rule::halt => Ok(Instruction::Halt),
         Rule::nop => Ok(Instruction::Nop),
         Rule::ret => Ok(Instruction::Ret),
+        Rule::syscall => Ok(Instruction::Syscall),
         // The method of decoding a Jump is the same; simply determine the rule later.
         x @ (Rule::jmp
         | Rule::jsr
@@ -207,7 +401,7 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
             let inner = instruction.into_inner().next().unwrap();
             let ispan = inner.as_span();
             let mode = match inner.as_rule() {
-                Rule::ident => Jump::Label(inner.as_str().to_owned()),
+                Rule::ident => Jump::Label(inner.as_str().to_owned(), span_of(ispan)),
                 Rule::relative => {
                     let value: SWord = parse_integer!(inner.into_inner().next().unwrap());
                     if value > -8388608 || value < 8388607 {
@@ -224,7 +418,9 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
                         .into());
                     }
                 }
-                Rule::absolute => Jump::Absolute(registers::get_id(inner.as_str()).unwrap()),
+                Rule::absolute => {
+                    Jump::Absolute(registers::get_id_with_aliases(inner.as_str()).unwrap())
+                }
                 _ => unreachable!(),
             };
 
@@ -262,15 +458,16 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
             };
 
             let mut inner = instruction.into_inner();
-            let source = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let source = registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
             let opt = inner.next().unwrap();
             inner.next();
-            let destination = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let destination =
+                registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
 
             let mode = match opt.as_rule() {
                 Rule::vareg => RegReg {
                     source,
-                    opt: registers::get_id(opt.as_str()).unwrap(),
+                    opt: registers::get_id_with_aliases(opt.as_str()).unwrap(),
                     destination,
                 },
                 Rule::dec | Rule::oct | Rule::hex => {
@@ -331,7 +528,7 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
                 "word" => 2,
                 _ => unreachable!(),
             };
-            let register = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let register = registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
 
             Ok(Sxt(IntSignExtendOp { register, width }))
         }
@@ -339,9 +536,10 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
             use Instruction::Not;
 
             let mut inner = instruction.into_inner();
-            let source = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let source = registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
             inner.next();
-            let destination = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let destination =
+                registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
 
             Ok(Not(IntUnaryOp {
                 source,
@@ -356,25 +554,33 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
             let (signed, left) = {
                 let pair = inner.next().unwrap();
                 match pair.as_rule() {
-                    Rule::vareg => (false, registers::get_id(pair.as_str()).unwrap()),
+                    Rule::vareg => (
+                        false,
+                        registers::get_id_with_aliases(pair.as_str()).unwrap(),
+                    ),
                     Rule::sig_flag => (
                         true,
-                        registers::get_id(inner.next().unwrap().as_str()).unwrap(),
+                        registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap(),
                     ),
                     _ => unreachable!(),
                 }
             };
-            let right = inner.next().unwrap();
+            let (negative, right) = match inner.next().unwrap() {
+                sign if sign.as_rule() == Rule::sign => {
+                    (sign.as_str() == "-", inner.next().unwrap())
+                }
+                right => (false, right),
+            };
 
             let mode = match right.as_rule() {
                 Rule::dec | Rule::oct | Rule::hex => RegImm {
                     left,
-                    right: parse_integer!(right),
+                    right: parse_comparison_immediate!(right, negative),
                     signed,
                 },
                 Rule::vareg => RegReg {
                     left,
-                    right: registers::get_id(right.as_str()).unwrap(),
+                    right: registers::get_id_with_aliases(right.as_str()).unwrap(),
                     signed,
                 },
                 Rule::ident => RegConst {
@@ -393,17 +599,22 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
             use Instruction::Tst;
 
             let mut inner = instruction.into_inner();
-            let left = registers::get_id(inner.next().unwrap().as_str()).unwrap();
-            let right = inner.next().unwrap();
+            let left = registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
+            let (negative, right) = match inner.next().unwrap() {
+                sign if sign.as_rule() == Rule::sign => {
+                    (sign.as_str() == "-", inner.next().unwrap())
+                }
+                right => (false, right),
+            };
 
             let mode = match right.as_rule() {
-                Rule::integer => RegImm {
+                Rule::dec | Rule::oct | Rule::hex => RegImm {
                     left,
-                    right: parse_integer!(right.into_inner().next().unwrap()),
+                    right: parse_comparison_immediate!(right, negative),
                 },
                 Rule::vareg => RegReg {
                     left,
-                    right: registers::get_id(right.as_str()).unwrap(),
+                    right: registers::get_id_with_aliases(right.as_str()).unwrap(),
                 },
                 Rule::ident => RegConst {
                     left,
@@ -419,10 +630,11 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
             use Instruction::{Fadd, Fdiv, Fmod, Fmul, Fsub};
 
             let mut inner = instruction.into_inner();
-            let source = registers::get_id(inner.next().unwrap().as_str()).unwrap();
-            let opt = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let source = registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
+            let opt = registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
             inner.next();
-            let destination = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let destination =
+                registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
 
             Ok(match x {
                 Rule::fadd => Fadd(FloatBinaryOp {
@@ -457,8 +669,8 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
             use Instruction::Fcmp;
 
             let mut inner = instruction.into_inner();
-            let left = registers::get_id(inner.next().unwrap().as_str()).unwrap();
-            let right = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let left = registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
+            let right = registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
 
             Ok(Fcmp(FloatCompOp { left, right }))
         }
@@ -466,9 +678,10 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
             use Instruction::{Fneg, Frec, Ftoi, Itof};
 
             let mut inner = instruction.into_inner();
-            let source = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let source = registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
             inner.next();
-            let destination = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let destination =
+                registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
 
             Ok(match x {
                 Rule::fneg => Fneg(FloatUnaryOp {
@@ -494,7 +707,8 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
             use Instruction::Fchk;
 
             let register =
-                registers::get_id(instruction.into_inner().next().unwrap().as_str()).unwrap();
+                registers::get_id_with_aliases(instruction.into_inner().next().unwrap().as_str())
+                    .unwrap();
 
             Ok(Fchk(register))
         }
@@ -510,7 +724,7 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
                     let mut regs = vec![];
 
                     for reg in mode.into_inner() {
-                        let regid = registers::get_id(reg.as_str()).unwrap();
+                        let regid = registers::get_id_with_aliases(reg.as_str()).unwrap();
 
                         // Registers cannot appear more than once at a time
                         if regs.contains(&regid) {
@@ -549,10 +763,17 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
             use lines::MemoryLoadOp::*;
             use Instruction::{Lbr, Llr, Lsr};
 
-            let mut inner = instruction.into_inner();
+            let span = instruction.as_span();
+            let mut inner = instruction.into_inner().peekable();
+            let non_temporal = inner.next_if(|p| p.as_rule() == Rule::nthint).is_some();
             let mode = inner.next().unwrap();
             let volatile = inner.next().unwrap().as_rule() == Rule::volassign;
-            let destination = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let destination =
+                registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
+
+            if non_temporal && matches!(mode.as_rule(), Rule::zpgaddr | Rule::stackoff) {
+                return Err(non_temporal_error(span));
+            }
 
             let mode = match mode.as_rule() {
                 Rule::zpgaddr => {
@@ -571,7 +792,8 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
                 }
                 Rule::offsetind => {
                     let mut inner = mode.into_inner();
-                    let address = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+                    let address =
+                        registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
                     let offset = parse_integer!(inner.next().unwrap());
 
                     Offset {
@@ -579,27 +801,32 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
                         offset,
                         destination,
                         volatile,
+                        non_temporal,
                     }
                 }
                 Rule::indexind => {
                     let mut inner = mode.into_inner();
-                    let address = registers::get_id(inner.next().unwrap().as_str()).unwrap();
-                    let index = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+                    let address =
+                        registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
+                    let index =
+                        registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
 
                     Indexed {
                         address,
                         index,
                         destination,
                         volatile,
+                        non_temporal,
                     }
                 }
                 Rule::vareg => {
-                    let address = registers::get_id(mode.as_str()).unwrap();
+                    let address = registers::get_id_with_aliases(mode.as_str()).unwrap();
 
                     Indirect {
                         address,
                         destination,
                         volatile,
+                        non_temporal,
                     }
                 }
                 Rule::stackoff => {
@@ -624,11 +851,17 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
             use lines::MemoryStoreOp::*;
             use Instruction::{Sbr, Slr, Ssr};
 
-            let mut inner = instruction.into_inner();
-            let source = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let span = instruction.as_span();
+            let mut inner = instruction.into_inner().peekable();
+            let non_temporal = inner.next_if(|p| p.as_rule() == Rule::nthint).is_some();
+            let source = registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
             let volatile = inner.next().unwrap().as_rule() == Rule::volassign;
             let mode = inner.next().unwrap();
 
+            if non_temporal && matches!(mode.as_rule(), Rule::zpgaddr | Rule::stackoff) {
+                return Err(non_temporal_error(span));
+            }
+
             let mode = match mode.as_rule() {
                 Rule::zpgaddr => {
                     let inner = mode.into_inner().next().unwrap();
@@ -646,7 +879,8 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
                 }
                 Rule::offsetind => {
                     let mut inner = mode.into_inner();
-                    let address = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+                    let address =
+                        registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
                     let offset = parse_integer!(inner.next().unwrap());
 
                     Offset {
@@ -654,27 +888,32 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
                         offset,
                         source,
                         volatile,
+                        non_temporal,
                     }
                 }
                 Rule::indexind => {
                     let mut inner = mode.into_inner();
-                    let address = registers::get_id(inner.next().unwrap().as_str()).unwrap();
-                    let index = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+                    let address =
+                        registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
+                    let index =
+                        registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
 
                     Indexed {
                         address,
                         index,
                         source,
                         volatile,
+                        non_temporal,
                     }
                 }
                 Rule::vareg => {
-                    let address = registers::get_id(mode.as_str()).unwrap();
+                    let address = registers::get_id_with_aliases(mode.as_str()).unwrap();
 
                     Indirect {
                         address,
                         source,
                         volatile,
+                        non_temporal,
                     }
                 }
                 Rule::stackoff => {
@@ -696,12 +935,22 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
             use Instruction::Tfr;
 
             let mut inner = instruction.into_inner();
-            let source = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let source = registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
             inner.next();
-            let destination = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let destination =
+                registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
 
             Ok(Tfr(source, destination))
         }
+        Rule::rdcyc => {
+            use Instruction::Rdcyc;
+
+            let register =
+                registers::get_id_with_aliases(instruction.into_inner().next().unwrap().as_str())
+                    .unwrap();
+
+            Ok(Rdcyc(register))
+        }
         Rule::ldr => {
             use lines::ImmediateLoadOp::*;
             use Instruction::Ldr;
@@ -713,7 +962,8 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
                     let mut inner = mode.into_inner();
                     let value = inner.next().unwrap();
                     inner.next();
-                    let destination = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+                    let destination =
+                        registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
                     let part = inner.next();
 
                     if let Some(part) = part {
@@ -736,7 +986,8 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
                     let mut inner = mode.into_inner();
                     let address = inner.next().unwrap();
                     inner.next();
-                    let destination = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+                    let destination =
+                        registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
 
                     Ok(Ldr(match address.as_rule() {
                         Rule::ident => ConstZpgAddr {
@@ -760,7 +1011,8 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
 
             let opt = inner.next().unwrap();
             inner.next();
-            let destination = registers::get_id(inner.next().unwrap().as_str()).unwrap();
+            let destination =
+                registers::get_id_with_aliases(inner.next().unwrap().as_str()).unwrap();
 
             Ok(Load(match opt.as_rule() {
                 Rule::float => Float {
@@ -773,6 +1025,7 @@ fn tokenize_instruction(mut pair: Pairs<Rule>) -> Result<Instruction, ErrorSourc
                 },
                 Rule::ident => Label {
                     ident: opt.as_str().to_owned(),
+                    span: span_of(opt.as_span()),
                     destination,
                 },
                 Rule::r#const => ConstantVal {
@@ -828,12 +1081,19 @@ fn tokenize_randomized_data_block(mut pair: Pairs<Rule>) -> Result<RandomData, E
     }
 }
 
-fn tokenize_line(line: Pair<'_, Rule>, span: Span) -> Result<Option<LineType>, ErrorSource> {
+fn tokenize_line(
+    line: Pair<'_, Rule>,
+    span: Span,
+    span_of: &dyn Fn(pest::Span) -> Span,
+) -> Result<Option<LineType>, ErrorSource> {
     use LineType::*;
 
     Ok(match line.as_rule() {
         Rule::constant => Some(Constant(tokenize_constant(line.into_inner())?, span)),
-        Rule::instruction => Some(Instruction(tokenize_instruction(line.into_inner())?, span)),
+        Rule::instruction => Some(Instruction(
+            tokenize_instruction(line.into_inner(), span_of)?,
+            span,
+        )),
         Rule::directive => Some(Directive(tokenize_directive(line.into_inner())?, span)),
         Rule::label => Some(Label(
             line.into_inner().next().unwrap().as_str().to_owned(),
@@ -864,18 +1124,40 @@ pub fn tokenize_file<T: AsRef<Path>>(path: T) -> Result<Lines, Error> {
     tokenize(&content, path)
 }
 
+/// Macros are expanded before the input is parsed.
 pub fn tokenize(data: &str, filename: &Path) -> Result<Lines, Error> {
-    let parsed =
-        AsmParser::parse(Rule::program, data).map_err(|e| Error::new(filename, e.into()))?;
+    let expansion = macros::expand(data).map_err(|e| {
+        let position = Position::new(data, e.offset).unwrap();
+        let error =
+            PestError::new_from_pos(ErrorVariant::CustomError { message: e.message }, position);
+
+        Error::new(filename, error.into())
+    })?;
+
+    // Errors are found in the expanded text, but reported where they lie in the source
+    let error = |e: ErrorSource| {
+        let e = match e {
+            ErrorSource::Pest(e) => expansion.source_error(data, e).into(),
+            e => e,
+        };
+
+        Error::new(filename, e)
+    };
+
+    let parsed = AsmParser::parse(Rule::program, &expansion.text).map_err(|e| error(e.into()))?;
 
     let mut lines = Lines::new();
+    let span_of =
+        |span: pest::Span<'_>| Span::from_pest(filename, expansion.source_span(data, span));
+
+    for line in parsed {
+        let span = span_of(line.as_span());
 
-    for (line, number) in parsed.zip(1..) {
-        match tokenize_line(line, Span::new(filename, number)) {
+        match tokenize_line(line, span, &span_of) {
             Ok(Some(result)) => lines.push_back(result),
             Ok(None) => break,
 
-            Err(e) => return Err(Error::new(filename, e)),
+            Err(e) => return Err(error(e)),
         }
     }
 
@@ -175,3 +175,826 @@ main:
 
     Ok(())
 }
+
+#[test]
+fn register_aliases() -> Result<(), Box<dyn Error>> {
+    const ALIASED_CODE: &str = r#"
+main:
+    ldr 1, ARG0
+    add ret, arg1, Ret
+    push {arg7, sp}
+    jsr ARG2
+argue:
+    jmp argue
+    halt
+"#;
+    const CANONICAL_CODE: &str = r#"
+main:
+    ldr 1, v1
+    add v0, v2, v0
+    push {v8, sp}
+    jsr v3
+argue:
+    jmp argue
+    halt
+"#;
+
+    let aliased = crate::compile([crate::Input {
+        data: ALIASED_CODE,
+        path: "aliased.asm",
+    }])?;
+    let canonical = crate::compile([crate::Input {
+        data: CANONICAL_CODE,
+        path: "canonical.asm",
+    }])?;
+
+    assert_eq!(aliased, canonical);
+
+    Ok(())
+}
+
+#[test]
+fn segments() -> Result<(), Box<dyn Error>> {
+    const SEGMENTED_CODE: &str = r#"
+#[location = 0x00000000]
+main:
+    ldr 1, v0
+    halt
+
+#[location = 0x00030000]
+#word! { 1, 2 }
+"#;
+
+    let lines = crate::parse::tokenize(SEGMENTED_CODE, Path::new("segmented.asm"))?;
+    let segments = link_symbols(lines)?.segments();
+
+    assert_eq!(segments.len(), 2);
+
+    let (code, code_data) = &segments[0];
+    assert_eq!(code.address, 0x0000_0000);
+    assert_eq!(code.length, 8);
+    assert!(code.is_read_only() && code.is_executable());
+    assert_eq!(&code_data[4..], encode(Control(Halt)).to_be_bytes());
+
+    let (data, data_data) = &segments[1];
+    assert_eq!(data.address, 0x0003_0000);
+    assert!(!data.is_read_only() && !data.is_executable());
+    assert_eq!(data_data, &[0, 0, 0, 1, 0, 0, 0, 2]);
+
+    Ok(())
+}
+
+#[test]
+fn sizes_and_symbols() -> Result<(), Box<dyn Error>> {
+    const CODE: &str = r#"
+#[location = 0x00000000]
+main:
+    load numbers, v0
+    halt
+
+#[location = 0x00030000]
+numbers:
+#word! { 1, 2 }
+#byte! { 3 }
+"#;
+
+    let lines = crate::parse::tokenize(CODE, Path::new("sizes.asm"))?;
+    let linked = link_symbols(lines)?;
+
+    // Loads of labels take two words
+    assert_eq!(linked.code_size(), 12);
+    assert_eq!(linked.data_size(), 9);
+    assert_eq!(linked.page_count(), 2);
+    assert_eq!(
+        linked.symbols(),
+        &[
+            ("main".to_owned(), 0x0000_0000),
+            ("numbers".to_owned(), 0x0003_0000)
+        ]
+        .into()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn entry_point() -> Result<(), Box<dyn Error>> {
+    const ENTRY_CODE: &str = r#"
+#[entry = "main"]
+helper:
+    ret
+main:
+    jsr helper
+    halt
+"#;
+
+    let lines = crate::parse::tokenize(ENTRY_CODE, Path::new("entry.asm"))?;
+    let mut image = vec![];
+    link_symbols(lines)?.write_segmented(&mut image)?;
+
+    let entry = libseis::image::entry(&image)?;
+    assert_eq!(entry, 0x0000_0004);
+
+    let (code, code_data) = &libseis::image::decode(&image)?[0];
+    let offset = (entry - code.address) as usize;
+    assert_eq!(
+        code_data[offset + 4..offset + 8],
+        encode(Control(Halt)).to_be_bytes()
+    );
+
+    // Without an entry directive, execution begins at the start of memory
+    let lines = crate::parse::tokenize("main:\n    halt\n", Path::new("entry.asm"))?;
+    let mut image = vec![];
+    link_symbols(lines)?.write_segmented(&mut image)?;
+
+    assert_eq!(libseis::image::entry(&image)?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn invalid_entry_point() -> Result<(), Box<dyn Error>> {
+    use crate::linker::error::Error as LinkerError;
+
+    let link = |code: &str| -> Result<_, Box<dyn Error>> {
+        Ok(link_symbols(crate::parse::tokenize(
+            code,
+            Path::new("entry.asm"),
+        )?))
+    };
+
+    assert!(matches!(
+        link("#[entry = \"missing\"]\nmain:\n    halt\n")?,
+        Err(LinkerError::NonExistingLabel { .. })
+    ));
+    assert!(matches!(
+        link("#[entry = \"main\"]\n#[entry = \"main\"]\nmain:\n    halt\n")?,
+        Err(LinkerError::ExistingEntry { .. })
+    ));
+
+    // Flat images have nowhere to record an entry point other than zero
+    let pages = link("#[entry = \"main\"]\n    nop\nmain:\n    halt\n")??;
+    assert!(pages.write(std::io::Cursor::new(vec![])).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn random_data() -> Result<(), Box<dyn Error>> {
+    const RANDOM_CODE: &str = r#"
+#[location = 0x00030000]
+#word?[100, 200; 16 %1234]
+#byte?[3, 5; 9]
+"#;
+
+    let compile = || {
+        crate::compile([crate::Input {
+            data: RANDOM_CODE,
+            path: "random.asm",
+        }])
+    };
+
+    let first = compile()?;
+    let second = compile()?;
+
+    // Both the seeded and unseeded blocks must be reproducible
+    assert_eq!(first, second);
+
+    let data = &first[0x0003_0000..];
+    assert_eq!(data.len(), 16 * 4 + 9);
+
+    let (words, bytes) = data.split_at(16 * 4);
+    for word in words.chunks_exact(4) {
+        let word = Word::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        assert!((100..=200).contains(&word), "{word}");
+    }
+    for byte in bytes {
+        assert!((3..=5).contains(byte), "{byte}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn random_data_seeds() -> Result<(), Box<dyn Error>> {
+    let compile = |seed: &str| {
+        crate::compile([crate::Input {
+            data: &format!("#[location = 0x00030000]\n#word?[0, 0xFFFFFFFF; 8{seed}]\n"),
+            path: "random.asm",
+        }])
+    };
+
+    assert_eq!(compile(" %7")?, compile(" %7")?);
+    assert_ne!(compile(" %7")?, compile(" %8")?);
+    assert_eq!(
+        compile("")?,
+        compile(&format!(" %{}", crate::linker::DEFAULT_SEED))?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn random_data_empty_range() {
+    let result = crate::compile([crate::Input {
+        data: "#[location = 0x00030000]\n#short?[10, 9; 4]\n",
+        path: "random.asm",
+    }]);
+
+    assert!(matches!(
+        result,
+        Err(crate::Error::Linker(
+            crate::linker::error::Error::EmptyRandomRange { .. }
+        ))
+    ));
+}
+
+#[test]
+fn data_byte_order() -> Result<(), Box<dyn Error>> {
+    const ENDIAN_CODE: &str = r#"
+#[location = 0x00030000]
+#short! { 0x1234 }
+#word! { 0x12345678 }
+#[endian = "little"]
+#short! { 0x1234 }
+#word! { 0x12345678 }
+#float! { 1.0 }
+#[endian = "big"]
+#float! { 1.0 }
+"#;
+
+    let result = crate::compile([crate::Input {
+        data: ENDIAN_CODE,
+        path: "endian.asm",
+    }])?;
+
+    assert_eq!(
+        &result[0x0003_0000..],
+        &[
+            0x12, 0x34, 0x12, 0x34, 0x56, 0x78, // big-endian
+            0x34, 0x12, 0x78, 0x56, 0x34, 0x12, // little-endian
+            0x00, 0x00, 0x80, 0x3F, // little-endian 1.0
+            0x3F, 0x80, 0x00, 0x00, // big-endian 1.0
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn hex_strings() -> Result<(), Box<dyn Error>> {
+    let compile = |block: &str| {
+        crate::compile([crate::Input {
+            data: &format!("#[location = 0x00030000]\n{block}\n"),
+            path: "hex.asm",
+        }])
+    };
+
+    let hex = compile(r#"#byte! { x"DEADbeef", 1, X"0A", x"" }"#)?;
+    let list = compile("#byte! { 0xDE, 0xAD, 0xBE, 0xEF, 1, 0x0A }")?;
+
+    assert_eq!(&hex[0x0003_0000..], &[0xDE, 0xAD, 0xBE, 0xEF, 0x01, 0x0A]);
+    assert_eq!(hex, list);
+
+    let commented = compile(
+        r#"#byte! { // magic
+    x"DEADBEEF", ; the magic number
+    1, x"0A" // trailing
+}"#,
+    )?;
+
+    assert_eq!(commented, list);
+
+    for block in [r#"#byte! { x"ABC" }"#, r#"#word! { x"ABCD" }"#] {
+        assert!(
+            matches!(compile(block), Err(crate::Error::Parser(_))),
+            "{block}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn crc32() -> Result<(), Box<dyn Error>> {
+    const CRC_CODE: &str = r#"
+#[location = 0x00030000]
+#byte! { x"313233343536373839" }
+#[crc32 = 0x00030000, 0x00030009]
+#[endian = "little"]
+#[crc32 = 0x00030000, 0x00030009]
+#[crc32 = 0x00030000, 0x0003000D]
+"#;
+
+    let result = crate::compile([crate::Input {
+        data: CRC_CODE,
+        path: "crc.asm",
+    }])?;
+
+    // The CRC-32 of "123456789" is its standard check value
+    assert_eq!(
+        &result[0x0003_0009..0x0003_000D],
+        &0xCBF4_3926u32.to_be_bytes()
+    );
+    assert_eq!(
+        &result[0x0003_000D..0x0003_0011],
+        &0xCBF4_3926u32.to_le_bytes()
+    );
+
+    // Checksums may cover the ones before them
+    let mut reference = !0u32;
+    for byte in &result[0x0003_0000..0x0003_000D] {
+        reference ^= *byte as u32;
+        for _ in 0..8 {
+            reference = if reference & 1 != 0 {
+                (reference >> 1) ^ 0xEDB8_8320
+            } else {
+                reference >> 1
+            };
+        }
+    }
+    assert_eq!(
+        &result[0x0003_0011..0x0003_0015],
+        &(!reference).to_le_bytes()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn invalid_crc32() {
+    for code in [
+        // Nothing has been written there
+        "#[location = 0x00030000]\n#[crc32 = 0x00040000, 0x00040004]\n",
+        // The checksum would cover itself
+        "#[location = 0x00030000]\n#byte! { 1, 2 }\n#[crc32 = 0x00030000, 0x00030006]\n",
+    ] {
+        let result = crate::compile([crate::Input {
+            data: code,
+            path: "crc.asm",
+        }]);
+
+        assert!(
+            matches!(
+                result,
+                Err(crate::Error::Linker(
+                    crate::linker::error::Error::UnpopulatedChecksumRange { .. }
+                ))
+            ),
+            "{code}"
+        );
+    }
+
+    for code in [
+        "#[crc32 = 0x00030004, 0x00030000]\n",
+        "#[crc32 = 0x00030000]\n",
+        "#[location = 0x00030000, 0x00030004]\n",
+    ] {
+        let result = crate::compile([crate::Input {
+            data: code,
+            path: "crc.asm",
+        }]);
+
+        assert!(matches!(result, Err(crate::Error::Parser(_))), "{code}");
+    }
+}
+
+#[test]
+fn reserved_space() -> Result<(), Box<dyn Error>> {
+    const CODE: &str = r#"
+#[location = 0x00030000]
+before:
+#word! { 1 }
+buffer:
+#[space = 0x100]
+after:
+#byte! { 2 }
+"#;
+
+    let lines = crate::parse::tokenize(CODE, Path::new("space.asm"))?;
+    let linked = link_symbols(lines)?;
+
+    assert_eq!(linked.data_size(), 5);
+    assert_eq!(
+        linked.symbols(),
+        &[
+            ("after".to_owned(), 0x0003_0104),
+            ("before".to_owned(), 0x0003_0000),
+            ("buffer".to_owned(), 0x0003_0004),
+        ]
+        .into()
+    );
+
+    // The space is left out of the image
+    let segments: Vec<_> = linked
+        .segments()
+        .into_iter()
+        .map(|(segment, _)| (segment.address, segment.length))
+        .collect();
+    assert_eq!(segments, [(0x0003_0000, 4), (0x0003_0104, 1)]);
+
+    // Space may run up to the end of the address space, as long as nothing follows it
+    for code in [
+        "#[location = 0xFFFFFF00]\n#[space = 0x100]\n",
+        "#[location = 0xFFFFFF00]\n#[space = 0x100]\n#[location = 0x00030000]\nnop\n",
+    ] {
+        let result = crate::compile([crate::Input {
+            data: code,
+            path: "space.asm",
+        }]);
+
+        assert!(result.is_ok(), "{code}");
+    }
+
+    for code in [
+        "#[location = 0xFFFFFF00]\n#[space = 0xFF]\n#[space = 2]\n",
+        "#[location = 0xFFFFFFF0]\n#[space = 0x20]\n",
+        "#[location = 0xFFFFFF00]\n#[space = 0x100]\nnop\n",
+        "#[location = 0xFFFFFF00]\n#[space = 0x100]\nend:\n",
+    ] {
+        let result = crate::compile([crate::Input {
+            data: code,
+            path: "space.asm",
+        }]);
+
+        assert!(
+            matches!(
+                result,
+                Err(crate::Error::Linker(
+                    crate::linker::error::Error::SpacePastEnd { .. }
+                ))
+            ),
+            "{code}"
+        );
+    }
+
+    for code in ["#[space]\n", "#[space = \"big\"]\n", "#[space = 1, 2]\n"] {
+        let result = crate::compile([crate::Input {
+            data: code,
+            path: "space.asm",
+        }]);
+
+        assert!(matches!(result, Err(crate::Error::Parser(_))), "{code}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn random_data_byte_order() -> Result<(), Box<dyn Error>> {
+    let compile = |endian: &str| {
+        crate::compile([crate::Input {
+            data: &format!(
+                "#[location = 0x00030000]\n#[endian = \"{endian}\"]\n#word?[0, 0xFFFFFFFF; 8]\n"
+            ),
+            path: "random.asm",
+        }])
+    };
+
+    let big = compile("big")?;
+    let little = compile("little")?;
+
+    for (big, little) in big[0x0003_0000..]
+        .chunks_exact(4)
+        .zip(little[0x0003_0000..].chunks_exact(4))
+    {
+        let mut swapped = big.to_vec();
+        swapped.reverse();
+        assert_eq!(swapped, little);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn invalid_endian() {
+    let result = crate::compile([crate::Input {
+        data: "#[endian = \"middle\"]\n",
+        path: "endian.asm",
+    }]);
+
+    assert!(matches!(result, Err(crate::Error::Parser(_))));
+}
+
+#[test]
+fn negative_comparison_immediates() -> Result<(), Box<dyn Error>> {
+    use libseis::instruction_set::integer::{
+        CompOp,
+        IntegerOp::{Cmp, Tst},
+        TestOp,
+    };
+
+    const COMPARISON_CODE: &str = r#"
+cmp v0, -5
+cmp s v1, -0x10
+tst v2, -1
+tst v3, +7
+cmp v4, -16384
+"#;
+
+    let result = crate::compile([crate::Input {
+        data: COMPARISON_CODE,
+        path: "comparison.asm",
+    }])?;
+
+    let expected = [
+        Integer(Cmp(CompOp::Immediate(V[0], -5i32 as Word, false))),
+        Integer(Cmp(CompOp::Immediate(V[1], -0x10i32 as Word, true))),
+        Integer(Tst(TestOp::Immediate(V[2], Word::MAX))),
+        Integer(Tst(TestOp::Immediate(V[3], 7))),
+        Integer(Cmp(CompOp::Immediate(V[4], -0x4000i32 as Word, false))),
+    ];
+
+    for (word, expected) in result.chunks_exact(4).zip(expected) {
+        let word = Word::from_be_bytes(word.try_into()?);
+        assert_eq!(
+            libseis::instruction_set::decode::<Instruction>(word)?,
+            expected
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn comparison_immediate_too_long() {
+    for code in ["cmp v0, 16384\n", "tst v0, -16385\n"] {
+        let result = crate::compile([crate::Input {
+            data: code,
+            path: "comparison.asm",
+        }]);
+
+        assert!(matches!(result, Err(crate::Error::Parser(_))), "{code}");
+    }
+}
+
+#[test]
+fn macros() -> Result<(), Box<dyn Error>> {
+    use libseis::instruction_set::integer::IntegerOp::Sub;
+
+    const MACRO_CODE: &str = r#"
+.macro scale src, amount, dst
+    add src, amount, dst
+    mul dst, 2, dst ; dst is doubled
+.endm
+
+.macro scale_down src dst
+    scale src, 1, dst
+    sub dst, src, dst
+.endm
+
+scale v1, 3, v2
+scale_down v2, v5
+halt
+"#;
+
+    let result = crate::compile([crate::Input {
+        data: MACRO_CODE,
+        path: "macros.asm",
+    }])?;
+
+    let expected = [
+        Integer(Add(Immediate(V[1], 3, V[2]))),
+        Integer(Mul(Immediate(V[2], 2, V[2]))),
+        Integer(Add(Immediate(V[2], 1, V[5]))),
+        Integer(Mul(Immediate(V[5], 2, V[5]))),
+        Integer(Sub(Registers(V[5], V[2], V[5]))),
+        Control(Halt),
+    ];
+
+    assert_eq!(result.len(), expected.len() * 4);
+    for (word, expected) in result.chunks_exact(4).zip(expected) {
+        let word = Word::from_be_bytes(word.try_into()?);
+        assert_eq!(
+            libseis::instruction_set::decode::<Instruction>(word)?,
+            expected
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn invalid_macros() {
+    for code in [
+        // Recursion through another macro
+        ".macro a x\nb x\n.endm\n.macro b x\na x\n.endm\na v1\n",
+        ".macro twice x\nadd x, 1, x\n.endm\ntwice v1, v2\n",
+        ".macro unended\nhalt\n",
+        ".endm\n",
+    ] {
+        let result = crate::compile([crate::Input {
+            data: code,
+            path: "macros.asm",
+        }]);
+
+        assert!(matches!(result, Err(crate::Error::Parser(_))), "{code}");
+    }
+}
+
+#[test]
+fn errors_after_macros() {
+    use crate::parse::ErrorSource;
+    use pest::error::LineColLocation;
+
+    const PARSE_ERROR_CODE: &str = r#".macro twice reg
+    add reg, 1, reg
+    add reg, 1, reg
+.endm
+twice v1
+twice v2
+    #[bogus = 1]
+"#;
+
+    let result = crate::compile([crate::Input {
+        data: PARSE_ERROR_CODE,
+        path: "macros.asm",
+    }]);
+
+    let Err(crate::Error::Parser(error)) = result else {
+        panic!("expected a parse error");
+    };
+    let ErrorSource::Pest(pest) = &error.source else {
+        panic!("expected a pest error");
+    };
+
+    assert_eq!(pest.line_col, LineColLocation::Span((7, 7), (7, 12)));
+    assert!(
+        error.to_string().contains("7 |     #[bogus = 1]"),
+        "{error}"
+    );
+
+    const LINK_ERROR_CODE: &str = r#".macro jump_away
+    nop
+    jmp nowhere
+.endm
+main:
+    jump_away
+"#;
+
+    let result = crate::compile([crate::Input {
+        data: LINK_ERROR_CODE,
+        path: "macros.asm",
+    }]);
+
+    let Err(crate::Error::Linker(crate::linker::error::Error::NonExistingLabel { usage, .. })) =
+        result
+    else {
+        panic!("expected a link error");
+    };
+
+    assert_eq!((usage.line, usage.start, usage.end), (6, 5, 14));
+}
+
+#[test]
+fn non_temporal_hint() -> Result<(), Box<dyn Error>> {
+    use libseis::instruction_set::register::{
+        ReadOp,
+        RegisterOp::{Lbr, Llr, Slr},
+        WriteOp,
+    };
+
+    const HINT_CODE: &str = r#"
+llr.nt v1 => v2
+lbr.NT v1 + 4 =>> v2
+slr.nt v3 => v1[v2]
+llr v1 => v2
+"#;
+
+    let result = crate::compile([crate::Input {
+        data: HINT_CODE,
+        path: "hint.asm",
+    }])?;
+
+    let expected = [
+        Register(Llr(ReadOp::Indirect {
+            volatile: false,
+            non_temporal: true,
+            address: V[1],
+            destination: V[2],
+        })),
+        Register(Lbr(ReadOp::OffsetIndirect {
+            volatile: true,
+            non_temporal: true,
+            address: V[1],
+            offset: 4,
+            destination: V[2],
+        })),
+        Register(Slr(WriteOp::IndexedIndirect {
+            volatile: false,
+            non_temporal: true,
+            address: V[1],
+            index: V[2],
+            source: V[3],
+        })),
+        Register(Llr(ReadOp::Indirect {
+            volatile: false,
+            non_temporal: false,
+            address: V[1],
+            destination: V[2],
+        })),
+    ];
+
+    assert_eq!(result.len(), expected.len() * 4);
+    for (word, expected) in result.chunks_exact(4).zip(expected) {
+        let word = Word::from_be_bytes(word.try_into()?);
+        assert_eq!(
+            libseis::instruction_set::decode::<Instruction>(word)?,
+            expected
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn non_temporal_hint_needs_a_register_address() {
+    for code in ["llr.nt @0x10 => v1\n", "slr.nt v1 => %4\n"] {
+        let result = crate::compile([crate::Input {
+            data: code,
+            path: "hint.asm",
+        }]);
+
+        assert!(matches!(result, Err(crate::Error::Parser(_))), "{code}");
+    }
+}
+
+#[test]
+fn parse_error_diagnostics() {
+    let result = crate::compile([crate::Input {
+        data: "main:\n    nop\n#[bogus = 1]\n    halt\n",
+        path: "bogus.asm",
+    }]);
+
+    let Err(error) = result else {
+        panic!("expected a parse error");
+    };
+    let diagnostics = error.diagnostics();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, crate::Severity::Error);
+    assert_eq!(
+        diagnostics[0].message,
+        "Did not recognize directive \"bogus\""
+    );
+    assert_eq!(diagnostics[0].span.as_ref().unwrap().line, 3);
+}
+
+#[test]
+fn link_error_diagnostics() {
+    let result = crate::compile([crate::Input {
+        data: "main:\n    nop\nmain:\n    halt\n",
+        path: "twice.asm",
+    }]);
+
+    let Err(error) = result else {
+        panic!("expected a link error");
+    };
+    let diagnostics = error.diagnostics();
+
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].severity, crate::Severity::Error);
+    assert_eq!(diagnostics[1].severity, crate::Severity::Note);
+    assert_eq!(diagnostics[1].message, "Label main is first defined here");
+}
+
+#[test]
+fn diagnostic_columns() {
+    let result = crate::compile([crate::Input {
+        data: "main:\n    nop\n    #[bogus = 1]\n",
+        path: "bogus.asm",
+    }]);
+
+    let Err(error) = result else {
+        panic!("expected a parse error");
+    };
+    let span = error.diagnostics().remove(0).span.unwrap();
+
+    assert_eq!((span.line, span.start, span.end), (3, 7, 12));
+    assert_eq!(span.to_string(), "bogus.asm:3:7");
+
+    let result = crate::compile([crate::Input {
+        data: "main:\n    nop\n\n  jmp nowhere ; comment\n",
+        path: "nowhere.asm",
+    }]);
+
+    let Err(error) = result else {
+        panic!("expected a link error");
+    };
+    let span = error.diagnostics().remove(0).span.unwrap();
+
+    // Missing labels are reported at their name, not the whole statement
+    assert_eq!((span.line, span.start, span.end), (4, 7, 14));
+
+    let result = crate::compile([crate::Input {
+        data: "main:\n    load nowhere, v0\n",
+        path: "nowhere.asm",
+    }]);
+
+    let Err(error) = result else {
+        panic!("expected a link error");
+    };
+    let span = error.diagnostics().remove(0).span.unwrap();
+
+    assert_eq!((span.line, span.start, span.end), (2, 10, 17));
+}
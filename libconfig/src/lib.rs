@@ -0,0 +1,212 @@
+//! # SEIS Configuration Library
+//!
+//! Reads the configuration files of seis-sim and seis-bench, which may inherit from a
+//! base file.
+//!
+//! A configuration may set a `base` key naming another file, relative to its own
+//! directory. Every key which the configuration does not set is taken from its base,
+//! merging nested tables key by key. Bases may have bases of their own, but may not
+//! form a cycle.
+#![warn(missing_docs)]
+
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+/// Reads a configuration file, resolving its `base` key
+pub fn read_toml(file: &Path) -> Result<toml::Table, Box<dyn Error>> {
+    read_with_bases(file, &mut Vec::new())
+}
+
+/// Resolves the `base` key of a configuration, whose file is found relative to `dir`
+pub fn resolve_base(table: toml::Table, dir: &Path) -> Result<toml::Table, Box<dyn Error>> {
+    resolve_with_bases(table, dir, &mut Vec::new())
+}
+
+/// Resolves the `base` key of a table nested within `file`, such as one entry of an array
+/// of tables, relative to the directory of `file`
+///
+/// A chain of bases which leads back to `file` is a cycle.
+pub fn resolve_nested_base(table: toml::Table, file: &Path) -> Result<toml::Table, Box<dyn Error>> {
+    let canonical = file
+        .canonicalize()
+        .map_err(|e| format!("Could not read {}: {e}", file.display()))?;
+    let dir = canonical.parent().unwrap_or(Path::new("")).to_owned();
+
+    resolve_with_bases(table, &dir, &mut vec![canonical])
+}
+
+/// Reads a configuration file, where `chain` holds the files which named it as a base
+fn read_with_bases(file: &Path, chain: &mut Vec<PathBuf>) -> Result<toml::Table, Box<dyn Error>> {
+    let canonical = file
+        .canonicalize()
+        .map_err(|e| format!("Could not read {}: {e}", file.display()))?;
+
+    if let Some(start) = chain.iter().position(|f| *f == canonical) {
+        let cycle = chain[start..]
+            .iter()
+            .chain([&canonical])
+            .map(|f| f.display().to_string())
+            .collect::<Vec<_>>();
+
+        return Err(format!("Base configurations form a cycle: {}", cycle.join(" -> ")).into());
+    }
+
+    let table = toml::from_str(&std::fs::read_to_string(&canonical)?)
+        .map_err(|e| format!("Could not parse {}: {e}", file.display()))?;
+    let dir = canonical.parent().unwrap_or(Path::new("")).to_owned();
+
+    chain.push(canonical);
+    resolve_with_bases(table, &dir, chain)
+}
+
+fn resolve_with_bases(
+    mut table: toml::Table,
+    dir: &Path,
+    chain: &mut Vec<PathBuf>,
+) -> Result<toml::Table, Box<dyn Error>> {
+    let base = match table.remove("base") {
+        None => return Ok(table),
+        Some(toml::Value::String(base)) => dir.join(base),
+        Some(value) => {
+            return Err(format!("Key \"base\" must be a string, found {}", value.type_str()).into())
+        }
+    };
+
+    let mut merged = read_with_bases(&base, chain)?;
+    merge(&mut merged, table);
+
+    Ok(merged)
+}
+
+/// Overrides the keys of `base` with those set in `overrides`, merging nested tables
+fn merge(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge(base, overrides)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes configuration files into a fresh directory, returning its path
+    fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("libconfig-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (name, content) in files {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+
+        dir
+    }
+
+    #[test]
+    fn base_is_inherited() {
+        let dir = write_files(
+            "base_is_inherited",
+            &[
+                (
+                    "base.toml",
+                    "miss_penalty = 100\nwritethrough = false\n[cache.data]\nset_bits = 2\nways = 2\n",
+                ),
+                (
+                    "sweep.toml",
+                    "base = \"base.toml\"\nmiss_penalty = 50\n[cache.data]\nways = 4\n",
+                ),
+            ],
+        );
+
+        let sweep = read_toml(&dir.join("sweep.toml"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let expected: toml::Table = toml::from_str(
+            "miss_penalty = 50\nwritethrough = false\n[cache.data]\nset_bits = 2\nways = 4\n",
+        )
+        .unwrap();
+        assert_eq!(sweep.unwrap(), expected);
+    }
+
+    #[test]
+    fn base_chains() {
+        let dir = write_files(
+            "base_chains",
+            &[
+                ("base.toml", "miss_penalty = 100\nvolatile_penalty = 20\n"),
+                (
+                    "middle.toml",
+                    "base = \"base.toml\"\nvolatile_penalty = 7\n",
+                ),
+                ("top.toml", "base = \"middle.toml\"\nwritethrough = true\n"),
+            ],
+        );
+
+        let top = read_toml(&dir.join("top.toml"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let expected: toml::Table =
+            toml::from_str("miss_penalty = 100\nvolatile_penalty = 7\nwritethrough = true\n")
+                .unwrap();
+        assert_eq!(top.unwrap(), expected);
+    }
+
+    #[test]
+    fn base_cycles() {
+        let dir = write_files(
+            "base_cycles",
+            &[
+                ("a.toml", "base = \"b.toml\"\n"),
+                ("b.toml", "base = \"a.toml\"\n"),
+            ],
+        );
+
+        let error = read_toml(&dir.join("a.toml")).unwrap_err().to_string();
+        let a = dir.join("a.toml").canonicalize().unwrap();
+        let b = dir.join("b.toml").canonicalize().unwrap();
+
+        assert_eq!(
+            error,
+            format!(
+                "Base configurations form a cycle: {} -> {} -> {}",
+                a.display(),
+                b.display(),
+                a.display()
+            )
+        );
+
+        // A nested table whose base leads back to the file holding it
+        let error = resolve_nested_base(
+            toml::from_str("base = \"b.toml\"").unwrap(),
+            &dir.join("a.toml"),
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert_eq!(
+            error,
+            format!(
+                "Base configurations form a cycle: {} -> {} -> {}",
+                a.display(),
+                b.display(),
+                a.display()
+            )
+        );
+
+        let error = resolve_base(toml::from_str("base = 1").unwrap(), &dir)
+            .unwrap_err()
+            .to_string();
+
+        assert_eq!(error, "Key \"base\" must be a string, found integer");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -55,6 +55,9 @@ pub struct Args {
     pub manual_clock: bool,
 
     /// The file to read for memory instructions
+    ///
+    /// Anything following a '#' is a comment, and the lines between "loop <COUNT>" and
+    /// "end" are repeated COUNT times
     #[arg(short, long)]
     #[arg(value_hint = FilePath)]
     pub cmd_file: Option<PathBuf>,
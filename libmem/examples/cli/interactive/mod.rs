@@ -2,7 +2,7 @@ mod autocomplete;
 
 use self::autocomplete::{ArgumentField, StringCompleter};
 use autocomplete::Node;
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 use inquire::Autocomplete;
 use libseis::types::{SWord, Word};
 use std::{fmt::Display, mem::take, sync::Arc};
@@ -71,6 +71,17 @@ pub enum Type {
     Word,
 }
 
+impl Type {
+    /// The number of bytes taken by a value of the type
+    pub fn width(self) -> Word {
+        match self {
+            Type::Byte => 1,
+            Type::Short => 2,
+            Type::Word => 4,
+        }
+    }
+}
+
 impl Display for Type {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use Type::*;
@@ -209,10 +220,118 @@ pub enum Command {
         #[arg(short)]
         pretty: bool,
     },
+    /// Read memory in a generated access pattern, reporting the hit rate
+    Pattern {
+        #[command(subcommand)]
+        pattern: Pattern,
+    },
     /// Stop the runtime
     Exit,
 }
 
+#[derive(Subcommand, Debug, Clone, Copy)]
+pub enum Pattern {
+    /// Read consecutive values
+    Sequential {
+        /// The address of the first value
+        #[clap(value_parser = address_parser)]
+        start: Word,
+        /// The number of values to read
+        count: usize,
+
+        /// Specify the width of the type
+        #[clap(name = "TYPE", default_value_t = Type::Byte)]
+        ty: Type,
+    },
+    /// Read values a fixed number of bytes apart
+    Strided {
+        /// The address of the first value
+        #[clap(value_parser = address_parser)]
+        start: Word,
+        /// The number of bytes from one value to the next
+        #[clap(value_parser = address_parser)]
+        stride: Word,
+        /// The number of values to read
+        count: usize,
+
+        /// Specify the width of the type
+        #[clap(name = "TYPE", default_value_t = Type::Byte)]
+        ty: Type,
+    },
+    /// Read values at random addresses within a range, aligned to the width of the type
+    Random {
+        /// The lowest address to read from
+        #[clap(value_parser = address_parser)]
+        start: Word,
+        /// The address following the range to read from
+        #[clap(value_parser = address_parser)]
+        end: Word,
+        /// The number of values to read
+        count: usize,
+
+        /// Specify the width of the type
+        #[clap(name = "TYPE", default_value_t = Type::Byte)]
+        ty: Type,
+        /// The seed of the random addresses, so a pattern can be repeated
+        #[arg(short, long, default_value_t = 0)]
+        seed: u64,
+    },
+}
+
+impl Pattern {
+    /// The type of the values read by the pattern
+    pub fn ty(&self) -> Type {
+        match *self {
+            Pattern::Sequential { ty, .. }
+            | Pattern::Strided { ty, .. }
+            | Pattern::Random { ty, .. } => ty,
+        }
+    }
+
+    /// Generates the addresses read by the pattern, in order
+    pub fn addresses(&self) -> Result<Vec<Word>, String> {
+        use rand::{Rng, SeedableRng};
+        use rand_chacha::ChaCha8Rng;
+
+        match *self {
+            Pattern::Sequential { start, count, ty } => Ok(strided(start, ty.width(), count)),
+            Pattern::Strided {
+                start,
+                stride,
+                count,
+                ..
+            } => Ok(strided(start, stride, count)),
+            Pattern::Random {
+                start,
+                end,
+                count,
+                ty,
+                seed,
+            } => {
+                let mask = !(ty.width() - 1);
+                let start = start.saturating_add(ty.width() - 1) & mask;
+
+                if start >= end & mask {
+                    return Err(format!("There is no {ty} to read in the range"));
+                }
+
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+                Ok((0..count)
+                    .map(|_| rng.gen_range(start..end & mask) & mask)
+                    .collect())
+            }
+        }
+    }
+}
+
+/// Generates `count` addresses `stride` bytes apart
+fn strided(start: Word, stride: Word, count: usize) -> Vec<Word> {
+    (0..count as Word)
+        .map(|i| start.wrapping_add(i.wrapping_mul(stride)))
+        .collect()
+}
+
 impl Command {
     /// Constructs a completer tree and returns a CommandCompleter
     pub fn autocompleter() -> CommandCompleter {
@@ -339,6 +458,61 @@ impl Command {
                     string: "json",
                     subtree: Arc::new([Arc::new("-p")]),
                 }),
+                Arc::new(StringCompleter {
+                    string: "pattern",
+                    subtree: Arc::new([
+                        Arc::new(StringCompleter {
+                            string: "sequential",
+                            subtree: Arc::new([Arc::new(ArgumentField {
+                                string: "<start>",
+                                subtree: Arc::new([Arc::new(ArgumentField {
+                                    string: "<count>",
+                                    subtree: types
+                                        .iter()
+                                        .map(|t| t.clone() as Arc<dyn Node>)
+                                        .collect(),
+                                })]),
+                            })]),
+                        }),
+                        Arc::new(StringCompleter {
+                            string: "strided",
+                            subtree: Arc::new([Arc::new(ArgumentField {
+                                string: "<start>",
+                                subtree: Arc::new([Arc::new(ArgumentField {
+                                    string: "<stride>",
+                                    subtree: Arc::new([Arc::new(ArgumentField {
+                                        string: "<count>",
+                                        subtree: types
+                                            .iter()
+                                            .map(|t| t.clone() as Arc<dyn Node>)
+                                            .collect(),
+                                    })]),
+                                })]),
+                            })]),
+                        }),
+                        Arc::new(StringCompleter {
+                            string: "random",
+                            subtree: Arc::new([Arc::new(ArgumentField {
+                                string: "<start>",
+                                subtree: Arc::new([Arc::new(ArgumentField {
+                                    string: "<end>",
+                                    subtree: Arc::new([Arc::new(ArgumentField {
+                                        string: "<count>",
+                                        subtree: types
+                                            .iter()
+                                            .map(|s| -> Arc<dyn Node> {
+                                                Arc::new(StringCompleter {
+                                                    string: s.string,
+                                                    subtree: Arc::new([Arc::new("--seed")]),
+                                                })
+                                            })
+                                            .collect(),
+                                    })]),
+                                })]),
+                            })]),
+                        }),
+                    ]),
+                }),
                 Arc::new(StringCompleter {
                     string: "clock",
                     subtree: Arc::new([Arc::new(ArgumentField {
@@ -356,3 +530,40 @@ impl Command {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(input: &str) -> Pattern {
+        match Command::try_parse_from(input.split_whitespace()).unwrap() {
+            Command::Pattern { pattern } => pattern,
+            command => panic!("Expected a pattern, found {command:?}"),
+        }
+    }
+
+    #[test]
+    fn pattern_addresses() {
+        assert_eq!(
+            pattern("pattern sequential 0x10 3 short").addresses(),
+            Ok(vec![0x10, 0x12, 0x14])
+        );
+        assert_eq!(
+            pattern("pattern strided 0x10 0x100 3").addresses(),
+            Ok(vec![0x10, 0x110, 0x210])
+        );
+
+        let random = pattern("pattern random 0x101 0x200 64 word -s 7");
+        let addresses = random.addresses().unwrap();
+
+        assert_eq!(addresses.len(), 64);
+        assert!(addresses
+            .iter()
+            .all(|&a| (0x104..0x200).contains(&a) && a & 0b11 == 0));
+        assert_eq!(random.addresses().unwrap(), addresses);
+
+        assert!(pattern("pattern random 0x101 0x104 1 word")
+            .addresses()
+            .is_err());
+    }
+}
@@ -1,5 +1,6 @@
 mod cli;
 mod interactive;
+mod script;
 
 use clap::Parser;
 use cli::Args;
@@ -21,7 +22,7 @@ use serde::Serialize;
 use std::{
     error::Error,
     fs::File,
-    io::{BufRead, BufReader},
+    io::{BufRead, BufReader, BufWriter},
 };
 
 fn process_input(
@@ -438,6 +439,55 @@ fn process_input(
             }
             Some(true)
         }
+        Command::Pattern { pattern } => {
+            let addresses = match pattern.addresses() {
+                Ok(addresses) => addresses,
+                Err(e) => {
+                    println!("{e}");
+                    return Some(false);
+                }
+            };
+
+            let hits = module.cache_hits();
+            let misses = module.total_misses();
+            let mut clocks = 0;
+
+            for (i, &address) in addresses.iter().enumerate() {
+                // Each access takes a clock as if it were entered as its own command, where
+                // the clock of the last is that taken by this command
+                if i != 0 {
+                    clocks += 1;
+                }
+
+                while let Err(Status::Busy(wait)) = match pattern.ty() {
+                    Type::Byte => module.read_byte(address).map(drop),
+                    Type::Short => module.read_short(address).map(drop),
+                    Type::Word => module.read_word(address).map(drop),
+                } {
+                    module.clock(wait);
+                    clocks += wait;
+                }
+            }
+            *total_clocks += clocks;
+
+            let hits = module.cache_hits() - hits;
+            let misses = module.total_misses() - misses;
+            let rate = if hits + misses == 0 {
+                0.0
+            } else {
+                hits as f64 / (hits + misses) as f64 * 100.0
+            };
+
+            println!(
+                "Read {} {}, taking {clocks} {}: the cache was hit {hits} {} and missed {misses} {} ({rate:.2}% hit rate)",
+                addresses.len(),
+                if addresses.len() == 1 { "value" } else { "values" },
+                if clocks == 1 { "clock" } else { "clocks" },
+                if hits == 1 { "time" } else { "times" },
+                if misses == 1 { "time" } else { "times" },
+            );
+            Some(true)
+        }
         Command::ShowCache => {
             for CacheData { name, lines } in module.cache_state() {
                 println!("{name}: ");
@@ -561,6 +611,28 @@ fn process_input(
     }
 }
 
+/// Runs the commands expanded from a command file, each with the line it came from
+fn run_script(
+    commands: &[(usize, String)],
+    module: &mut dyn MemoryModule,
+    total_clocks: &mut usize,
+) {
+    for (line, command) in commands {
+        print!("{line:>3} > ");
+        if command.trim().is_empty() {
+            println!();
+        } else {
+            process_input(
+                Command::parse_from(command.split_whitespace()),
+                module,
+                total_clocks,
+                false,
+            );
+            *total_clocks += 1;
+        }
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let mut args = Args::parse();
 
@@ -611,25 +683,9 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     if let Some(file) = args.cmd_file {
         let reader = BufReader::new(File::open(file)?);
+        let commands = script::expand(reader.lines().map(|l| l.expect("Failed to read line")))?;
 
-        for (i, line) in reader
-            .lines()
-            .map(|l| l.expect("Failed to read line"))
-            .enumerate()
-        {
-            print!("{:>3} > ", i + 1);
-            if line.is_empty() {
-                println!();
-            } else {
-                process_input(
-                    Command::parse_from(line.split_whitespace()),
-                    module.as_mut(),
-                    &mut total_clocks,
-                    false,
-                );
-                total_clocks += 1;
-            }
-        }
+        run_script(&commands, module.as_mut(), &mut total_clocks);
     } else {
         loop {
             if let Some(input) = Text::new("")
@@ -664,9 +720,83 @@ fn main() -> Result<(), Box<dyn Error>> {
         )?))?;
     module
         .memory()
-        .serialize(&mut serde_json::Serializer::pretty(File::create(
-            "./memory.json",
-        )?))?;
+        .write_json(BufWriter::new(File::create("./memory.json")?))?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module() -> SingleLevel {
+        SingleLevel::new(
+            Box::new(Associative::new(2, 2)),
+            Box::new(Associative::new(2, 2)),
+            Memory::new(3),
+            10,
+            2,
+            false,
+        )
+    }
+
+    /// Runs a command file, returning the total clocks taken
+    fn clocks(script: &str) -> usize {
+        let commands = script::expand(script.lines().map(str::to_owned)).unwrap();
+        let mut total_clocks = 0;
+
+        run_script(&commands, &mut module(), &mut total_clocks);
+
+        total_clocks
+    }
+
+    #[test]
+    fn patterns_match_individual_reads() {
+        let pattern = clocks("pattern strided 0x00 0x20 8 word");
+        let reads = clocks(
+            "read 0x00 word
+read 0x20 word
+read 0x40 word
+read 0x60 word
+read 0x80 word
+read 0xA0 word
+read 0xC0 word
+read 0xE0 word",
+        );
+
+        assert_eq!(pattern, reads);
+    }
+
+    #[test]
+    fn loops_match_unrolled_commands() {
+        let looped = clocks(
+            "# Alternate between conflicting lines
+loop 3
+    read 0x00 word
+    read 0x40 word # same set
+    loop 2
+        write 0x04 1 byte
+    end
+end
+clock 5",
+        );
+        let unrolled = clocks(
+            "read 0x00 word
+read 0x40 word
+write 0x04 1 byte
+write 0x04 1 byte
+read 0x00 word
+read 0x40 word
+write 0x04 1 byte
+write 0x04 1 byte
+read 0x00 word
+read 0x40 word
+write 0x04 1 byte
+write 0x04 1 byte
+clock 5",
+        );
+
+        assert_eq!(looped, unrolled);
+        assert!(looped > 13);
+    }
+}
@@ -0,0 +1,117 @@
+//! Reading of command files, which may hold comments and repeated blocks of commands
+
+/// A block of commands which is repeated once it ends
+struct Block {
+    /// The line the block started on
+    line: usize,
+    /// How many times to repeat the block
+    count: usize,
+    /// The commands in the block, with the lines they came from
+    commands: Vec<(usize, String)>,
+}
+
+/// Expands the lines of a command file into the commands to run, each with the number
+/// of the line it came from.
+///
+/// Anything following a `#` is a comment. The lines between `loop N` and `end` are
+/// repeated `N` times, and loops may be nested. Blank lines are kept, but lines only
+/// holding a comment are dropped.
+pub fn expand(lines: impl IntoIterator<Item = String>) -> Result<Vec<(usize, String)>, String> {
+    let mut blocks = vec![Block {
+        line: 0,
+        count: 1,
+        commands: vec![],
+    }];
+
+    for (i, line) in lines.into_iter().enumerate() {
+        let number = i + 1;
+
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let line = match line.split_once('#') {
+            Some((command, _)) => command.to_owned(),
+            None => line,
+        };
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            Some("loop") => {
+                let count = match (words.next().map(str::parse), words.next()) {
+                    (Some(Ok(count)), None) => count,
+                    _ => return Err(format!("Line {number}: expected \"loop <COUNT>\"")),
+                };
+
+                blocks.push(Block {
+                    line: number,
+                    count,
+                    commands: vec![],
+                });
+            }
+            Some("end") if blocks.len() > 1 => {
+                let block = blocks.pop().unwrap();
+                let parent = blocks.last_mut().unwrap();
+
+                for _ in 0..block.count {
+                    parent.commands.extend_from_slice(&block.commands);
+                }
+            }
+            Some("end") => return Err(format!("Line {number}: \"end\" without a loop")),
+            _ => blocks.last_mut().unwrap().commands.push((number, line)),
+        }
+    }
+
+    match blocks.pop() {
+        Some(block) if blocks.is_empty() => Ok(block.commands),
+        Some(block) => Err(format!("Line {}: loop is never ended", block.line)),
+        None => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(script: &str) -> Vec<String> {
+        script.lines().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn comments() {
+        let commands = expand(lines("# setup\nread 0 word # first\n\nclock 1")).unwrap();
+
+        assert_eq!(
+            commands,
+            [
+                (2, "read 0 word ".to_owned()),
+                (3, "".to_owned()),
+                (4, "clock 1".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn loops() {
+        let commands = expand(lines("loop 2\nread 0 word\nloop 3\nclock 1\nend\nend")).unwrap();
+        let lines: Vec<_> = commands.iter().map(|(line, _)| *line).collect();
+
+        assert_eq!(lines, [2, 4, 4, 4, 2, 4, 4, 4]);
+    }
+
+    #[test]
+    fn malformed_loops() {
+        assert_eq!(
+            expand(lines("loop\nclock 1\nend")).unwrap_err(),
+            "Line 1: expected \"loop <COUNT>\""
+        );
+        assert_eq!(
+            expand(lines("clock 1\nend")).unwrap_err(),
+            "Line 2: \"end\" without a loop"
+        );
+        assert_eq!(
+            expand(lines("loop 2\nloop 2\nclock 1\nend")).unwrap_err(),
+            "Line 1: loop is never ended"
+        );
+    }
+}
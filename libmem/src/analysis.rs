@@ -0,0 +1,98 @@
+//! Offline analysis of the addresses accessed by a program.
+//!
+//! [`reuse_distances`] builds a [`ReuseHistogram`] out of a stream of addresses, such as
+//! the [miss log](crate::module::MemoryModule::miss_log) of a memory module. Only a
+//! stream of every access predicts hit rates; a stream of misses leaves out the hits
+//! which would have shortened each distance.
+
+use libseis::types::Word;
+use std::collections::{BTreeMap, HashMap};
+
+/// How many accesses had each reuse distance, where the reuse distance of an access is
+/// the number of distinct addresses accessed since the last access to the same address
+///
+/// A fully associative LRU cache holding `n` lines hits every access with a distance
+/// below `n`, so the histogram predicts the hit rate of any cache size at once.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReuseHistogram {
+    /// The number of accesses with each finite distance
+    pub distances: BTreeMap<usize, usize>,
+    /// The number of accesses to addresses never accessed before, whose distance is
+    /// infinite
+    pub cold: usize,
+}
+
+impl ReuseHistogram {
+    /// The total number of accesses
+    pub fn accesses(&self) -> usize {
+        self.cold + self.distances.values().sum::<usize>()
+    }
+
+    /// The number of accesses which would hit in a fully associative LRU cache of
+    /// `lines` lines
+    ///
+    /// This only holds for a histogram of every access to the cache, not one of its
+    /// misses alone.
+    pub fn hits_with(&self, lines: usize) -> usize {
+        self.distances.range(..lines).map(|(_, count)| count).sum()
+    }
+}
+
+/// Computes the reuse distance of every access to an address in `addresses`
+///
+/// Addresses are compared as they are, so pass the base address of each line to find
+/// distances in lines rather than bytes. This runs in `O(n log n)` for `n` accesses.
+pub fn reuse_distances(addresses: impl IntoIterator<Item = Word>) -> ReuseHistogram {
+    let addresses: Vec<Word> = addresses.into_iter().collect();
+
+    let mut histogram = ReuseHistogram::default();
+    // When each address was last accessed
+    let mut last = HashMap::new();
+    // Marks the times which are the last access of their address, so the distinct
+    // addresses accessed since a time are the marks after it
+    let mut marks = Fenwick::new(addresses.len());
+
+    for (time, address) in addresses.into_iter().enumerate() {
+        match last.insert(address, time) {
+            Some(previous) => {
+                let distance = marks.sum(time) - marks.sum(previous + 1);
+                *histogram.distances.entry(distance).or_default() += 1;
+                marks.add(previous, -1);
+            }
+            None => histogram.cold += 1,
+        }
+
+        marks.add(time, 1);
+    }
+
+    histogram
+}
+
+/// A binary indexed tree, for prefix sums of a list which changes
+struct Fenwick(Vec<isize>);
+
+impl Fenwick {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len + 1])
+    }
+
+    /// Adds `value` to the element at `index`
+    fn add(&mut self, index: usize, value: isize) {
+        let mut i = index + 1;
+        while i < self.0.len() {
+            self.0[i] += value;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Sums the elements before `end`
+    fn sum(&self, end: usize) -> usize {
+        let mut total = 0;
+        let mut i = end;
+        while i > 0 {
+            total += self.0[i];
+            i -= i & i.wrapping_neg();
+        }
+        total as usize
+    }
+}
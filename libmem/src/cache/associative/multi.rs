@@ -394,8 +394,13 @@ impl Cache for MultiAssociative {
                 s.dirty = true;
                 t.dirty = true;
 
-                self.set_mut(set)[..=i].rotate_right(1);
-                self.set_mut(oset)[..=j].rotate_right(1);
+                let mut sets = self.set_mut(set);
+                sets[..=i].rotate_right(1);
+                sets[0] = Some(s);
+
+                sets = self.set_mut(oset);
+                sets[..=j].rotate_right(1);
+                sets[0] = Some(t);
 
                 Status::Hit
             } else {
@@ -460,6 +465,24 @@ impl Cache for MultiAssociative {
         }
     }
 
+    fn demote_line(&mut self, address: Word) -> bool {
+        let (tag, set, _) = self.split_address(address);
+        let set = self.set_mut(set);
+
+        // Empty ways are always at the end of the set, behind the least recently used line
+        let used = set.iter().take_while(|s| s.is_some()).count();
+
+        if let Some(i) = set[..used]
+            .iter()
+            .position(|s| matches!(s, Some(s) if s.tag == tag))
+        {
+            set[i..used].rotate_left(1);
+            true
+        } else {
+            false
+        }
+    }
+
     fn get_line(&mut self, address: Word, memory: &mut Memory) -> LineReadStatus {
         if self.check_address(address).is_hit() {
             return LineReadStatus::Skipped;
@@ -480,10 +503,9 @@ impl Cache for MultiAssociative {
                 let construct_address =
                     construct_address(line.tag, set as u32, 0, set_bits, off_bits);
 
-                line.data
-                    .iter()
-                    .zip(construct_address..)
-                    .for_each(|(&byte, address)| memory.write_byte(address, byte));
+                line.data.iter().enumerate().for_each(|(offset, &byte)| {
+                    memory.write_byte(construct_address.wrapping_add(offset as Word), byte)
+                });
             }
 
             line.tag = tag;
@@ -526,10 +548,9 @@ impl Cache for MultiAssociative {
             .map(|(i, s)| {
                 let addr = construct_address(s.tag, i as u32, 0, set_bits, off_bits);
 
-                s.data
-                    .iter()
-                    .zip(addr..)
-                    .for_each(|(&byte, address)| memory.write_byte(address, byte));
+                s.data.iter().enumerate().for_each(|(offset, &byte)| {
+                    memory.write_byte(addr.wrapping_add(offset as Word), byte)
+                });
 
                 s.dirty = false;
             })
@@ -543,16 +564,26 @@ impl Cache for MultiAssociative {
             .count()
     }
 
+    fn is_dirty(&self, address: Word) -> bool {
+        let (tag, set, _) = self.split_address(address);
+
+        self.set(set)
+            .iter()
+            .any(|s| matches!(s, Some(s) if s.tag == tag && s.dirty))
+    }
+
     fn get_lines(&self) -> Vec<Option<LineData>> {
         self.sets
             .chunks(self.ways)
             .zip(0..)
             .flat_map(|(line, set)| {
-                line.iter().map(move |line| {
+                line.iter().enumerate().map(move |(way, line)| {
                     line.as_ref().map(|line| LineData {
                         base_address: self.construct_address(line.tag, set, 0),
                         dirty: line.dirty,
                         data: line.data.as_ref(),
+                        set_index: set as usize,
+                        way_index: way,
                     })
                 })
             })
@@ -259,6 +259,11 @@ impl Cache for Associative {
         }
     }
 
+    fn demote_line(&mut self, address: Word) -> bool {
+        // Each set holds a single line, which is always the next to be evicted
+        self.check_address(address).is_hit()
+    }
+
     fn get_line(&mut self, address: Word, memory: &mut Memory) -> LineReadStatus {
         if self.check_address(address).is_hit() {
             return LineReadStatus::Skipped;
@@ -272,10 +277,9 @@ impl Cache for Associative {
             if line.dirty {
                 let addr = self.construct_address(line.tag, set as u32, 0);
 
-                line.data
-                    .iter()
-                    .zip(addr..)
-                    .for_each(|(&byte, address)| memory.write_byte(address, byte));
+                line.data.iter().enumerate().for_each(|(offset, &byte)| {
+                    memory.write_byte(addr.wrapping_add(offset as Word), byte)
+                });
             }
 
             line.tag = tag;
@@ -309,10 +313,9 @@ impl Cache for Associative {
                 Some(s) if s.dirty => {
                     let addr = construct_address(s.tag, i as u32, 0, set_bits, off_bits);
 
-                    s.data
-                        .iter()
-                        .zip(addr..)
-                        .for_each(|(&byte, address)| memory.write_byte(address, byte));
+                    s.data.iter().enumerate().for_each(|(offset, &byte)| {
+                        memory.write_byte(addr.wrapping_add(offset as Word), byte)
+                    });
 
                     s.dirty = false;
 
@@ -330,6 +333,12 @@ impl Cache for Associative {
             .count()
     }
 
+    fn is_dirty(&self, address: Word) -> bool {
+        let (tag, set, _) = self.split_address(address);
+
+        matches!(&self.sets[set], Some(line) if line.tag == tag && line.dirty)
+    }
+
     fn get_lines(&self) -> Vec<Option<LineData>> {
         self.sets
             .iter()
@@ -339,6 +348,8 @@ impl Cache for Associative {
                     base_address: self.construct_address(line.tag, set, 0),
                     dirty: line.dirty,
                     data: line.data.as_ref(),
+                    set_index: set as usize,
+                    way_index: 0,
                 })
             })
             .collect()
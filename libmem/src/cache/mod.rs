@@ -28,14 +28,22 @@ pub struct LineData<'a> {
     pub dirty: bool,
     /// The data contained in the line
     pub data: &'a [u8],
+    /// The set the line belongs to
+    pub set_index: usize,
+    /// The way within its set which holds the line
+    pub way_index: usize,
 }
 
-impl<'a> From<(Word, bool, &'a [u8])> for LineData<'a> {
-    fn from((base_address, dirty, data): (Word, bool, &'a [u8])) -> Self {
+impl<'a> From<(Word, bool, &'a [u8], usize, usize)> for LineData<'a> {
+    fn from(
+        (base_address, dirty, data, set_index, way_index): (Word, bool, &'a [u8], usize, usize),
+    ) -> Self {
         Self {
             base_address,
             dirty,
             data,
+            set_index,
+            way_index,
         }
     }
 }
@@ -86,6 +94,12 @@ pub trait Cache: Debug {
     /// Returns true if a line *has* been invalidated.
     fn invalidate_line(&mut self, address: Word) -> bool;
 
+    /// Moves a line to the least recently used position of its set, making it the next
+    /// to be evicted (say, after a non-temporal access)
+    ///
+    /// Returns true if the cache holds the line.
+    fn demote_line(&mut self, address: Word) -> bool;
+
     /// Fetches the data to be stored in the cache from main memory.
     ///
     /// Writes any evicted lines back and returns true if an eviction occurred.
@@ -99,7 +113,10 @@ pub trait Cache: Debug {
     /// Returns the number of dirty lines.
     fn dirty_lines(&self) -> usize;
 
-    /// Gets all the lines available in the cache.
+    /// Returns whether the line holding the address is cached and dirty.
+    fn is_dirty(&self, address: Word) -> bool;
+
+    /// Gets all the lines available in the cache, ordered by set and then by way.
     ///
     /// The data stored is useful to provide information about what the cache is doing.
     fn get_lines(&self) -> Vec<Option<LineData>>;
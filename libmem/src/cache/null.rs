@@ -85,6 +85,10 @@ impl Cache for NullCache {
         false
     }
 
+    fn demote_line(&mut self, _: Word) -> bool {
+        false
+    }
+
     fn get_line(&mut self, address: Word, memory: &mut Memory) -> LineReadStatus {
         self.0 = Populated(address, memory.read_word(address).to_be_bytes());
         LineReadStatus::Disabled
@@ -98,6 +102,10 @@ impl Cache for NullCache {
         0
     }
 
+    fn is_dirty(&self, _: Word) -> bool {
+        false
+    }
+
     fn get_lines(&self) -> Vec<Option<LineData>> {
         vec![]
     }
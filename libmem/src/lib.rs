@@ -1,6 +1,8 @@
 //! A library containing definitions and code for the memory
 //! unit of the simulated processor.
 //!
+//! [`analysis`] contains tools for studying the addresses a program accesses.
+//!
 //! [`cache`] contains the [`Cache`](cache::Cache) trait and
 //! datastructures implementing the trait.
 //!
@@ -11,6 +13,7 @@
 
 #![warn(missing_docs)]
 
+pub mod analysis;
 pub mod cache;
 pub mod memory;
 pub mod module;
@@ -4,15 +4,21 @@
 //! which are allocated on write.
 
 use libseis::{
-    pages::PAGE_SIZE,
+    image::{self, ImageError, Segment},
+    pages::{MAX_PAGES, PAGE_SIZE},
     types::{Byte, Short, Word},
 };
-use serde::{ser::SerializeSeq, Serialize};
+use serde::{
+    de::Error as _,
+    ser::{SerializeSeq, SerializeStruct},
+    Deserialize, Serialize,
+};
 use std::{
     fmt::Debug,
+    io::{self, Read, Write},
     iter::{Enumerate, FlatMap, Map},
     mem::take,
-    ops::Deref,
+    ops::{Deref, Range},
     slice::Iter,
 };
 
@@ -30,6 +36,9 @@ impl<'a> From<(usize, &'a [u8])> for AllocatedPage<'a> {
     }
 }
 
+/// The size of the regions tracked by [`Memory::take_dirty`] (16 KiB, a quarter of a page)
+pub const REGION_SIZE: usize = PAGE_SIZE / 4;
+
 /// A page of data (an array of [`PAGE_SIZE`] [Bytes](Byte))
 type Page = [Byte; PAGE_SIZE];
 /// An iterator over the pages of the [`Memory`] datastructure
@@ -56,10 +65,79 @@ impl<'a> Debug for AllocDebugWrapper<'a> {
     }
 }
 
+/// The size of the values compared by [`Memory::search`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Width {
+    /// Compare single bytes
+    Byte,
+    /// Compare big-endian shorts
+    Short,
+    /// Compare big-endian words
+    Word,
+}
+
+impl Width {
+    /// The number of bytes in a value of this width
+    pub fn bytes(self) -> usize {
+        match self {
+            Width::Byte => 1,
+            Width::Short => 2,
+            Width::Word => 4,
+        }
+    }
+}
+
+/// Computes the 32-bit FNV-1a hash of a slice of bytes
+fn fnv1a(data: &[u8]) -> Word {
+    data.iter().fold(0x811C_9DC5, |hash: Word, &byte| {
+        (hash ^ byte as Word).wrapping_mul(0x0100_0193)
+    })
+}
+
 fn allocate_page() -> Box<Page> {
     Box::new([0; PAGE_SIZE])
 }
 
+/// Generates the eight bytes at `address` of a memory [filled](Memory::new_filled) with
+/// `seed`, where `address` is a multiple of eight
+///
+/// This is SplitMix64 keyed by the address, so any byte can be found without generating
+/// the rest of its page.
+fn fill_bytes(seed: u64, address: usize) -> [Byte; 8] {
+    let mut z = seed.wrapping_add((address as u64 >> 3).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (z ^ (z >> 31)).to_be_bytes()
+}
+
+/// Splits the addresses in `range` below `limit` by page, yielding the ID of each page
+/// and the offsets covered within it
+fn page_spans(range: Range<Word>, limit: u64) -> impl Iterator<Item = (usize, Range<usize>)> {
+    let end = (range.end as u64).min(limit);
+    let mut start = range.start as u64;
+
+    std::iter::from_fn(move || {
+        if start >= end {
+            return None;
+        }
+
+        let id = start >> 16;
+        let page_end = ((id + 1) << 16).min(end);
+        let offsets = (start - (id << 16)) as usize..(page_end - (id << 16)) as usize;
+        start = page_end;
+
+        Some((id as usize, offsets))
+    })
+}
+
+/// Marks a run of unallocated pages in the output of [`Memory::write_bytes`]
+const UNALLOCATED: Byte = 0;
+/// Marks an allocated page in the output of [`Memory::write_bytes`]
+const ALLOCATED: Byte = 1;
+/// Marks the seed of a [filled](Memory::new_filled) memory in the output of
+/// [`Memory::write_bytes`]
+const FILLED: Byte = 2;
+
 /// Memory representation that only allocates pages when they're written to.
 ///
 /// Cannot add new pages after construction -- addressing out of bounds will lead to a [`panic`].
@@ -68,6 +146,17 @@ fn allocate_page() -> Box<Page> {
 pub struct Memory {
     /// The pages of memory in this memory datastructure
     pages: Box<[Option<Box<Page>>]>,
+    /// The ranges of addresses loaded from read-only segments
+    ///
+    /// Held as [`u64`] so a range may end past the last address of a full memory
+    read_only: Vec<Range<u64>>,
+    /// A bitset of the [regions](REGION_SIZE) written since the last [`Memory::take_dirty`]
+    dirty: Box<[u64]>,
+    /// A bitset of every byte ever written, if the memory is [poisoned](Memory::new_poisoned)
+    written: Option<Box<[u64]>>,
+    /// The seed of the contents of unallocated pages, if the memory is
+    /// [filled](Memory::new_filled)
+    fill: Option<u64>,
 }
 
 impl std::fmt::Debug for Memory {
@@ -77,6 +166,9 @@ impl std::fmt::Debug for Memory {
                 "pages",
                 &self.pages.iter().map(AllocDebugWrapper).collect::<Vec<_>>(),
             )
+            .field("read_only", &self.read_only)
+            .field("poisoned", &self.is_poisoned())
+            .field("fill", &self.fill)
             .finish()
     }
 }
@@ -85,15 +177,152 @@ impl Memory {
     /// Create a new memory datastructure containing up to `count` pages
     pub fn new(count: usize) -> Self {
         assert!(count > 0, "Count must be greater than 0");
+        assert!(count <= MAX_PAGES, "Count must be at most {MAX_PAGES}");
+
+        let regions = count * PAGE_SIZE / REGION_SIZE;
 
         Self {
             pages: vec![None; count].into_boxed_slice(),
+            read_only: vec![],
+            dirty: vec![0; regions.div_ceil(64)].into_boxed_slice(),
+            written: None,
+            fill: None,
+        }
+    }
+
+    /// Create a new memory datastructure containing up to `count` pages, where bytes
+    /// which were never written hold pseudo-random values generated from `seed`
+    ///
+    /// Pages are still only allocated when they are written to, at which point they are
+    /// filled with the same values they read as beforehand. Two memories with the same
+    /// seed always hold the same values, so runs stay reproducible while programs which
+    /// read uninitialized memory stop seeing zeroes.
+    pub fn new_filled(count: usize, seed: u64) -> Self {
+        let mut memory = Self::new(count);
+        memory.fill = Some(seed);
+        memory
+    }
+
+    /// Gets the seed of the values in unallocated memory, or `None` if it reads as zero
+    pub fn fill_seed(&self) -> Option<u64> {
+        self.fill
+    }
+
+    /// Gets the byte at `address` in an unallocated page
+    fn unallocated_byte(&self, address: usize) -> Byte {
+        match self.fill {
+            Some(seed) => fill_bytes(seed, address & !7)[address & 7],
+            None => 0,
         }
     }
 
+    /// Allocates the page with ID `page`, holding the values it reads as while unallocated
+    fn allocate(&self, page: usize) -> Box<Page> {
+        let mut alloc = allocate_page();
+
+        if let Some(seed) = self.fill {
+            for (offset, chunk) in alloc.chunks_exact_mut(8).enumerate() {
+                chunk.copy_from_slice(&fill_bytes(seed, (page << 16) + offset * 8));
+            }
+        }
+
+        alloc
+    }
+
+    /// Create a new memory datastructure containing up to `count` pages, which remembers
+    /// every byte written to it
+    ///
+    /// Reads still return 0 for bytes which were never written, but
+    /// [`Memory::read_byte_checked`] tells them apart from bytes which were written as
+    /// 0. This costs an extra bit for every byte of memory.
+    pub fn new_poisoned(count: usize) -> Self {
+        let mut memory = Self::new(count);
+        memory.written = Some(vec![0; (count << 16) / 64].into_boxed_slice());
+        memory
+    }
+
+    /// Returns true if this memory remembers which bytes were written to it
+    pub fn is_poisoned(&self) -> bool {
+        self.written.is_some()
+    }
+
+    /// Marks the `length` bytes starting at `address` as written, wrapping around the end
+    /// of memory
+    ///
+    /// `address` must already be wrapped to the size of the memory.
+    fn mark_written(&mut self, address: usize, length: usize) {
+        let size = self.pages.len() << 16;
+
+        if let Some(written) = &mut self.written {
+            for address in (address..address + length).map(|a| a % size) {
+                written[address / 64] |= 1 << (address % 64);
+            }
+        }
+    }
+
+    /// Marks every byte in a page as never written
+    fn clear_written(&mut self, page: usize) {
+        if let Some(written) = &mut self.written {
+            written[(page << 16) / 64..((page + 1) << 16) / 64].fill(0);
+        }
+    }
+
+    /// Read a byte from memory, along with whether it was ever written
+    ///
+    /// Only a [poisoned](Memory::new_poisoned) memory keeps track of this; any other
+    /// memory reports every byte as written.
+    pub fn read_byte_checked(&self, address: Word) -> (Byte, bool) {
+        let index = address as usize % (self.pages.len() << 16);
+        let written = self
+            .written
+            .as_ref()
+            .is_none_or(|w| w[index / 64] & (1 << (index % 64)) != 0);
+
+        (self.read_byte(address), written)
+    }
+
+    /// Marks the region holding `address` as dirty
+    ///
+    /// `address` must already be wrapped to the size of the memory.
+    fn mark_dirty(&mut self, address: usize) {
+        let region = address / REGION_SIZE;
+        self.dirty[region / 64] |= 1 << (region % 64);
+    }
+
+    /// Marks every region of a page as dirty
+    fn mark_page_dirty(&mut self, page: usize) {
+        for region in 0..PAGE_SIZE / REGION_SIZE {
+            self.mark_dirty((page << 16) + region * REGION_SIZE);
+        }
+    }
+
+    /// Lists the IDs of the [regions](REGION_SIZE) written since the last call (or since
+    /// the memory was created), in ascending order, and marks every region clean
+    ///
+    /// The region with ID `i` holds the addresses from `i * REGION_SIZE` up to
+    /// `(i + 1) * REGION_SIZE`. Writes mark their regions even if they leave the
+    /// contents unchanged.
+    pub fn take_dirty(&mut self) -> Vec<usize> {
+        self.dirty
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(i, bits)| {
+                let set = take(bits);
+                (0..64)
+                    .filter(move |bit| set & (1 << bit) != 0)
+                    .map(move |bit| i * 64 + bit)
+            })
+            .collect()
+    }
+
+    /// The number of pages this memory may hold
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
     /// The largest possible address that can be accessed
     pub fn max_address(&self) -> Word {
-        ((self.pages.len() as Word) << 16) - 1
+        ((self.pages.len() << 16) - 1) as Word
     }
 
     /// Read a byte from memory
@@ -105,7 +334,7 @@ impl Memory {
         if let Some(page) = &self.pages[page] {
             page[byte]
         } else {
-            0
+            self.unallocated_byte(address)
         }
     }
 
@@ -124,22 +353,26 @@ impl Memory {
                     bytes.copy_from_slice(&page[byte..byte + 2]);
                     Short::from_be_bytes(bytes)
                 } else {
-                    0
+                    Short::from_be_bytes([
+                        self.unallocated_byte(address),
+                        self.unallocated_byte(address + 1),
+                    ])
                 }
             }
             false => {
                 let page = (address & 0xFFFF_0000) >> 16;
+                let next = (page + 1) % self.pages.len();
                 let mut bytes = [0; 2];
 
                 bytes[0] = if let Some(page) = &self.pages[page as usize] {
                     page[0xFFFF]
                 } else {
-                    0
+                    self.unallocated_byte(address)
                 };
-                bytes[1] = if let Some(page) = &self.pages[page as usize + 1] {
+                bytes[1] = if let Some(page) = &self.pages[next] {
                     page[0]
                 } else {
-                    0
+                    self.unallocated_byte(next << 16)
                 };
 
                 Short::from_be_bytes(bytes)
@@ -161,20 +394,30 @@ impl Memory {
                     bytes.copy_from_slice(&page[byte..byte + 4]);
                     Word::from_be_bytes(bytes)
                 } else {
-                    0
+                    Word::from_be_bytes(std::array::from_fn(|i| self.unallocated_byte(address + i)))
                 }
             }
             // crosses a page boundary
             false => {
                 let page = (address & 0xFFFF_0000) >> 16;
+                let next = (page + 1) % self.pages.len();
                 let byte = address & 0xFFFF;
+                let first = PAGE_SIZE - byte;
                 let mut bytes = [0; 4];
 
                 if let Some(page) = &self.pages[page] {
-                    bytes[..byte & 0x3].copy_from_slice(&page[byte..]);
+                    bytes[..first].copy_from_slice(&page[byte..]);
+                } else {
+                    for (i, byte) in bytes[..first].iter_mut().enumerate() {
+                        *byte = self.unallocated_byte(address + i);
+                    }
                 }
-                if let Some(page) = &self.pages[page + 1] {
-                    bytes[byte & 0x3..].copy_from_slice(&page[..byte & 3]);
+                if let Some(page) = &self.pages[next] {
+                    bytes[first..].copy_from_slice(&page[..4 - first]);
+                } else {
+                    for (i, byte) in bytes[first..].iter_mut().enumerate() {
+                        *byte = self.unallocated_byte((next << 16) + i);
+                    }
                 }
 
                 Word::from_be_bytes(bytes)
@@ -189,10 +432,13 @@ impl Memory {
         let page = (address & 0xFFFF_0000) >> 16;
         let byte = address & 0xFFFF;
 
+        self.mark_dirty(address);
+        self.mark_written(address, 1);
+
         if let Some(page) = &mut self.pages[page] {
             page[byte] = value;
         } else {
-            let mut alloc = allocate_page();
+            let mut alloc = self.allocate(page);
 
             alloc[byte] = value;
             self.pages[page] = Some(alloc)
@@ -206,6 +452,10 @@ impl Memory {
         let byte = address & 0xFFFF;
         let v = value.to_be_bytes();
 
+        self.mark_dirty(address);
+        self.mark_dirty((address + 1) % (self.pages.len() << 16));
+        self.mark_written(address, 2);
+
         match address & 0xFFFF != 0xFFFF {
             // does not cross a page boundary
             true => {
@@ -213,7 +463,7 @@ impl Memory {
                     page[byte] = v[0];
                     page[byte + 1] = v[1];
                 } else {
-                    let mut alloc = allocate_page();
+                    let mut alloc = self.allocate(page);
                     alloc[byte] = v[0];
                     alloc[byte + 1] = v[1];
                     self.pages[page] = Some(alloc);
@@ -221,14 +471,15 @@ impl Memory {
             }
             // crosses a page boundary
             false => {
-                let mut first = take(&mut self.pages[page]).unwrap_or_else(allocate_page);
-                let mut second = take(&mut self.pages[page + 1]).unwrap_or_else(allocate_page);
+                let next = (page + 1) % self.pages.len();
 
+                let mut first = take(&mut self.pages[page]).unwrap_or_else(|| self.allocate(page));
                 first[byte] = v[0];
-                second[0] = v[1];
-
                 self.pages[page] = Some(first);
-                self.pages[page + 1] = Some(second);
+
+                let mut second = take(&mut self.pages[next]).unwrap_or_else(|| self.allocate(next));
+                second[0] = v[1];
+                self.pages[next] = Some(second);
             }
         }
     }
@@ -240,27 +491,33 @@ impl Memory {
         let byte = address & 0xFFFF;
         let v = value.to_be_bytes();
 
+        self.mark_dirty(address);
+        self.mark_dirty((address + 3) % (self.pages.len() << 16));
+        self.mark_written(address, 4);
+
         match address & 0xFFFF < 0xFFFD {
             // is a word
             true => {
                 if let Some(page) = &mut self.pages[page] {
                     page[byte..byte + 4].copy_from_slice(&v);
                 } else {
-                    let mut alloc = allocate_page();
+                    let mut alloc = self.allocate(page);
                     alloc[byte..byte + 4].copy_from_slice(&v);
                     self.pages[page] = Some(alloc);
                 }
             }
             // crosses a page boundary
             false => {
-                let mut first = take(&mut self.pages[page]).unwrap_or_else(allocate_page);
-                let mut second = take(&mut self.pages[page + 1]).unwrap_or_else(allocate_page);
-
-                first[byte..].copy_from_slice(&v[..byte & 3]);
-                second[..byte & 3].copy_from_slice(&v[byte & 3..]);
+                let next = (page + 1) % self.pages.len();
+                let split = PAGE_SIZE - byte;
 
+                let mut first = take(&mut self.pages[page]).unwrap_or_else(|| self.allocate(page));
+                first[byte..].copy_from_slice(&v[..split]);
                 self.pages[page] = Some(first);
-                self.pages[page + 1] = Some(second);
+
+                let mut second = take(&mut self.pages[next]).unwrap_or_else(|| self.allocate(next));
+                second[..4 - split].copy_from_slice(&v[split..]);
+                self.pages[next] = Some(second);
             }
         }
     }
@@ -279,10 +536,66 @@ impl Memory {
             .for_each(|(i, a)| to[i] = self.read_byte(a))
     }
 
-    /// Erases the entire memory space
+    /// Writes a buffer into memory starting at `address`, allocating pages as needed
+    ///
+    /// Like [`Memory::read_words_to`], this stops at the end of the 32-bit address space rather
+    /// than wrapping around to address zero.
+    pub fn write_words(&mut self, address: Word, data: &[u8]) {
+        (address..(address.saturating_add(data.len() as Word)))
+            .zip(data)
+            .for_each(|(a, &byte)| self.write_byte(a, byte))
+    }
+
+    /// Erases the entire memory space, along with its read-only ranges and which bytes
+    /// were written
     pub fn erase(&mut self) {
-        for page in self.pages.iter_mut() {
-            *page = None;
+        for id in 0..self.pages.len() {
+            if take(&mut self.pages[id]).is_some() {
+                self.mark_page_dirty(id);
+            }
+            self.clear_written(id);
+        }
+
+        self.read_only.clear();
+    }
+
+    /// Writes zeroes to every address in a range, leaving pages allocated
+    ///
+    /// Unallocated pages already read as zero, so they are not allocated unless the memory
+    /// is [filled](Memory::new_filled). Every address in the range counts as written.
+    pub fn zero_range(&mut self, range: Range<Word>) {
+        for (id, offsets) in page_spans(range, self.max_address() as u64 + 1) {
+            self.mark_written((id << 16) + offsets.start, offsets.len());
+
+            if self.fill.is_some() && self.pages[id].is_none() {
+                self.pages[id] = Some(self.allocate(id));
+            }
+
+            if let Some(page) = &mut self.pages[id] {
+                page[offsets.clone()].fill(0);
+
+                // Stepping by a region touches every region in the span but the last
+                let start = (id << 16) + offsets.start;
+                let end = (id << 16) + offsets.end;
+                for address in (start..end).step_by(REGION_SIZE).chain([end - 1]) {
+                    self.mark_dirty(address);
+                }
+            }
+        }
+    }
+
+    /// Frees every page which lies entirely within a range, reclaiming its memory
+    ///
+    /// Pages the range only partially covers keep their contents, and read-only ranges
+    /// are left as they are. Freed pages count as never written.
+    pub fn free_pages_in(&mut self, range: Range<Word>) {
+        for (id, offsets) in page_spans(range, self.max_address() as u64 + 1) {
+            if offsets == (0..PAGE_SIZE) {
+                if take(&mut self.pages[id]).is_some() {
+                    self.mark_page_dirty(id);
+                }
+                self.clear_written(id);
+            }
         }
     }
 
@@ -300,16 +613,29 @@ impl Memory {
             .flat_map(|(i, p)| p.map(|p| AllocatedPage::from((i, p))))
     }
 
+    /// Gets whether each page is allocated, where the entry for the page with ID `i` is
+    /// at index `i`
+    pub fn allocation_bitmap(&self) -> Vec<bool> {
+        self.pages.iter().map(Option::is_some).collect()
+    }
+
     /// Sets the data in a page
+    ///
+    /// Only the bytes in `data` count as written; the rest of the page holds what it would
+    /// while unallocated.
     pub fn set_page(&mut self, address: Word, data: &[u8]) {
         assert!(data.len() <= PAGE_SIZE);
 
         let page_id = ((address as usize) & 0xFFFF_0000) >> 16;
 
+        self.mark_page_dirty(page_id);
+        self.clear_written(page_id);
+        self.mark_written(page_id << 16, data.len());
+
         if data.len() == 0 {
             self.pages[page_id] = None;
         } else {
-            let mut page = allocate_page();
+            let mut page = self.allocate(page_id);
             page[..data.len()].copy_from_slice(&data);
             self.pages[page_id] = Some(page);
         }
@@ -319,19 +645,387 @@ impl Memory {
     pub fn get_page(&self, index: usize) -> Option<&Page> {
         self.pages[index].as_ref().map(|p| p.as_ref())
     }
+
+    /// Loads an image produced by the assembler into memory
+    ///
+    /// Segmented images have each segment placed at its address, and the addresses of
+    /// [read-only](Segment::READ_ONLY) segments are recorded. Anything else is treated
+    /// as a flat image and copied into memory page by page, starting at address zero.
+    ///
+    /// Returns the segments that were loaded. A flat image is loaded as one segment.
+    pub fn load_image(&mut self, data: &[u8]) -> Result<Vec<Segment>, ImageError> {
+        let limit = self.max_address() as u64 + 1;
+
+        if !image::is_segmented(data) {
+            let segment = Segment {
+                address: 0,
+                length: data.len() as Word,
+                flags: 0,
+            };
+
+            if data.len() as u64 > limit {
+                return Err(ImageError::SegmentOverflow(segment));
+            }
+
+            for (page, data) in data.chunks(PAGE_SIZE).enumerate() {
+                self.set_page((page << 16) as Word, data)
+            }
+
+            return Ok(vec![segment]);
+        }
+
+        let segments = image::decode(data)?;
+
+        if let Some((segment, _)) = segments
+            .iter()
+            .find(|(s, _)| s.address as u64 + s.length as u64 > limit)
+        {
+            return Err(ImageError::SegmentOverflow(*segment));
+        }
+
+        Ok(segments
+            .into_iter()
+            .map(|(segment, data)| {
+                for (address, &byte) in (segment.address..=Word::MAX).zip(data) {
+                    self.write_byte(address, byte);
+                }
+
+                if segment.is_read_only() && segment.length > 0 {
+                    let start = segment.address as u64;
+                    self.read_only.push(start..start + segment.length as u64);
+                }
+
+                segment
+            })
+            .collect())
+    }
+
+    /// Finds the addresses within `range` where a value of the given width equals `pattern`
+    ///
+    /// Values are matched at every byte offset, not only aligned ones, and may span two
+    /// pages. Only values with at least one byte in an allocated page are compared, so
+    /// the contents of unallocated memory are never matched. Values which would wrap past
+    /// the end of memory are ignored.
+    pub fn search(&self, pattern: Word, width: Width, range: Range<Word>) -> Vec<Word> {
+        let width_bytes = width.bytes() as u64;
+        let end = (range.end as u64).min(self.max_address() as u64 + 2 - width_bytes);
+
+        let mut matches = vec![];
+        // The first address which has not been compared yet, to avoid comparing an
+        // address twice when consecutive pages are both allocated
+        let mut next = range.start as u64;
+
+        for page in self.allocated_pages() {
+            let page_start = (page.id as u64) << 16;
+            let start = next.max((page_start + 1).saturating_sub(width_bytes));
+            let stop = end.min(page_start + PAGE_SIZE as u64);
+
+            for address in start..stop {
+                let address = address as Word;
+                // Values are built a byte at a time, as they may cross into the next page
+                let value = (0..width_bytes as Word).fold(0, |value, offset| {
+                    value << 8 | self.read_byte(address + offset) as Word
+                });
+
+                if value == pattern {
+                    matches.push(address);
+                }
+            }
+
+            next = next.max(stop);
+        }
+
+        matches
+    }
+
+    /// Computes a checksum of every page, where the checksum of the page with ID `i` is
+    /// at index `i`.
+    ///
+    /// Unallocated pages are checksummed as the values they read as, so two memories
+    /// compare equal as long as their contents do.
+    pub fn checksum_all(&self) -> Vec<Word> {
+        static ZEROES: Page = [0; PAGE_SIZE];
+
+        self.pages()
+            .enumerate()
+            .map(|(id, page)| match (page, self.fill) {
+                (Some(page), _) => fnv1a(page),
+                (None, Some(_)) => fnv1a(self.allocate(id).as_ref()),
+                (None, None) => fnv1a(&ZEROES),
+            })
+            .collect()
+    }
+
+    /// Marks a range of addresses as read-only
+    ///
+    /// The memory itself does not enforce this; it is up to the processor to check
+    /// writes with [`Memory::is_read_only`].
+    pub fn protect(&mut self, range: Range<Word>) {
+        if !range.is_empty() {
+            self.read_only.push(range.start as u64..range.end as u64);
+        }
+    }
+
+    /// Marks a range of addresses as writable, explicitly allowing writes to them even
+    /// if they are part of a range marked with [`Memory::protect`]
+    pub fn unprotect(&mut self, range: Range<Word>) {
+        let range = range.start as u64..range.end as u64;
+
+        self.read_only = take(&mut self.read_only)
+            .into_iter()
+            .flat_map(|protected| {
+                [
+                    protected.start..protected.end.min(range.start),
+                    protected.start.max(range.end)..protected.end,
+                ]
+            })
+            .filter(|protected| !protected.is_empty())
+            .collect();
+    }
+
+    /// Returns true if any of the `length` bytes starting at `address` are read-only
+    pub fn is_read_only(&self, address: Word, length: Word) -> bool {
+        let address = address as u64;
+        let end = address + length as u64;
+
+        self.read_only
+            .iter()
+            .any(|range| range.start < end && address < range.end)
+    }
+
+    /// The ranges of addresses marked as read-only
+    pub fn read_only_ranges(&self) -> &[Range<u64>] {
+        &self.read_only
+    }
+
+    /// Writes the pages as JSON, one page at a time
+    ///
+    /// The output matches the [`Serialize`] implementation, holding `null` for each
+    /// unallocated page, but never holds more than a page of text at once.
+    pub fn write_json<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        use std::fmt::Write as _;
+
+        let mut text = String::with_capacity(PAGE_SIZE * 4);
+
+        if let Some(seed) = self.fill {
+            write!(writer, "{{\"fill\":{seed},\"pages\":")?;
+        }
+
+        writer.write_all(b"[")?;
+
+        for (id, page) in self.pages().enumerate() {
+            if id > 0 {
+                writer.write_all(b",")?;
+            }
+
+            let Some(page) = page else {
+                writer.write_all(b"null")?;
+                continue;
+            };
+
+            text.clear();
+            text.push('[');
+            for (i, byte) in page.iter().enumerate() {
+                if i > 0 {
+                    text.push(',');
+                }
+                // Writing to a string never fails
+                let _ = write!(text, "{byte}");
+            }
+            text.push(']');
+
+            writer.write_all(text.as_bytes())?;
+        }
+
+        writer.write_all(b"]")?;
+
+        if self.fill.is_some() {
+            writer.write_all(b"}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the pages in a compact binary format, one page at a time
+    ///
+    /// The output starts with the number of pages as a big-endian word, then [`FILLED`]
+    /// and the seed as a big-endian double word if the memory is
+    /// [filled](Memory::new_filled). Each allocated page follows as [`ALLOCATED`] and its
+    /// contents, and each run of unallocated pages as [`UNALLOCATED`] and the length of
+    /// the run as a big-endian word. Read it back with [`Memory::read_bytes`].
+    pub fn write_bytes<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        fn end_run(writer: &mut impl Write, run: &mut Word) -> io::Result<()> {
+            if *run > 0 {
+                writer.write_all(&[UNALLOCATED])?;
+                writer.write_all(&take(run).to_be_bytes())?;
+            }
+
+            Ok(())
+        }
+
+        writer.write_all(&(self.pages.len() as Word).to_be_bytes())?;
+
+        if let Some(seed) = self.fill {
+            writer.write_all(&[FILLED])?;
+            writer.write_all(&seed.to_be_bytes())?;
+        }
+
+        let mut run = 0;
+
+        for page in self.pages() {
+            match page {
+                Some(page) => {
+                    end_run(&mut writer, &mut run)?;
+                    writer.write_all(&[ALLOCATED])?;
+                    writer.write_all(page)?;
+                }
+                None => run += 1,
+            }
+        }
+
+        end_run(&mut writer, &mut run)
+    }
+
+    /// Reads pages written by [`Memory::write_bytes`]
+    ///
+    /// Read-only ranges are not part of the format, so none are set.
+    pub fn read_bytes<R: Read>(mut reader: R) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut word = [0; 4];
+        reader.read_exact(&mut word)?;
+        let count = Word::from_be_bytes(word) as usize;
+
+        if count == 0 {
+            return Err(invalid("Memory must hold at least one page".into()));
+        }
+
+        if count > MAX_PAGES {
+            return Err(invalid(format!(
+                "Memory holds at most {MAX_PAGES} pages, but {count} were expected"
+            )));
+        }
+
+        let mut memory = Self::new(count);
+        let mut id = 0;
+
+        while id < count {
+            let mut tag = [0];
+            reader.read_exact(&mut tag)?;
+
+            match tag[0] {
+                UNALLOCATED => {
+                    reader.read_exact(&mut word)?;
+                    id += Word::from_be_bytes(word) as usize;
+                }
+                ALLOCATED => {
+                    let mut page = allocate_page();
+                    reader.read_exact(page.as_mut())?;
+                    memory.pages[id] = Some(page);
+                    id += 1;
+                }
+                FILLED if id == 0 && memory.fill.is_none() => {
+                    let mut seed = [0; 8];
+                    reader.read_exact(&mut seed)?;
+                    memory.fill = Some(u64::from_be_bytes(seed));
+                }
+                tag => return Err(invalid(format!("Unknown page tag {tag}"))),
+            }
+        }
+
+        if id > count {
+            return Err(invalid(format!(
+                "Found {id} pages, but only {count} were expected"
+            )));
+        }
+
+        Ok(memory)
+    }
 }
 
-impl Serialize for Memory {
+/// Serializes the pages of a [`Memory`], holding `null` for each unallocated page
+struct Pages<'a>(&'a Memory);
+
+impl Serialize for Pages<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        let mut seq = serializer.serialize_seq(Some(self.pages.len()))?;
+        let mut seq = serializer.serialize_seq(Some(self.0.pages.len()))?;
 
-        for page in self.pages() {
+        for page in self.0.pages() {
             seq.serialize_element(&page)?;
         }
 
         seq.end()
     }
 }
+
+/// Serializes as the list of pages, or as the fill seed and the list of pages if the
+/// memory is [filled](Memory::new_filled)
+impl Serialize for Memory {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let Some(seed) = self.fill else {
+            return Pages(self).serialize(serializer);
+        };
+
+        let mut map = serializer.serialize_struct("Memory", 2)?;
+        map.serialize_field("fill", &seed)?;
+        map.serialize_field("pages", &Pages(self))?;
+        map.end()
+    }
+}
+
+/// The forms a serialized [`Memory`] takes
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Serialized {
+    Pages(Vec<Option<Vec<Byte>>>),
+    Filled {
+        fill: u64,
+        pages: Vec<Option<Vec<Byte>>>,
+    },
+}
+
+impl<'de> Deserialize<'de> for Memory {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let (fill, pages) = match Serialized::deserialize(deserializer)? {
+            Serialized::Pages(pages) => (None, pages),
+            Serialized::Filled { fill, pages } => (Some(fill), pages),
+        };
+
+        if pages.is_empty() {
+            return Err(D::Error::invalid_length(0, &"at least one page"));
+        }
+
+        if pages.len() > MAX_PAGES {
+            return Err(D::Error::invalid_length(
+                pages.len(),
+                &format!("at most {MAX_PAGES} pages").as_str(),
+            ));
+        }
+
+        let mut memory = Self::new(pages.len());
+        memory.fill = fill;
+
+        for (id, data) in pages.into_iter().enumerate() {
+            let Some(data) = data else { continue };
+
+            if data.len() != PAGE_SIZE {
+                return Err(D::Error::invalid_length(data.len(), &"a full page"));
+            }
+
+            let mut page = allocate_page();
+            page.copy_from_slice(&data);
+            memory.pages[id] = Some(page);
+        }
+
+        Ok(memory)
+    }
+}
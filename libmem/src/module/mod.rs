@@ -17,7 +17,7 @@ use crate::{
 use libseis::types::{Byte, Short, Word};
 use serde::Serialize;
 pub use single_level::SingleLevel;
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::VecDeque, fmt::Debug};
 
 /// Represents the data in a cache.
 #[derive(Debug, Serialize)]
@@ -52,6 +52,19 @@ pub enum Status {
 /// Represents the result of a cache operation
 pub type Result<T> = std::result::Result<T, Status>;
 
+/// Why an access missed the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MissKind {
+    /// The line the access needed had no data in it yet
+    Cold,
+    /// The line the access needed held data for another address
+    Conflict,
+}
+
+/// The most recent misses of a memory module, oldest first, with the address of each and
+/// the name of the cache it missed, as given by [`MemoryModule::caches`]
+pub type MissLog = VecDeque<(Word, MissKind, &'static str)>;
+
 /// Represents a memory module containing a cache and a DRAM memory.
 pub trait MemoryModule: Debug {
     /// Clocks the module, decrementing any counters.
@@ -91,6 +104,12 @@ pub trait MemoryModule: Debug {
     /// Writes a word to memory, bypassing the cache
     fn write_word_volatile(&mut self, addr: Word, value: Word) -> Status;
 
+    /// Leaves the data cache line holding the address as the next to be evicted from its set,
+    /// so that a non-temporal access does not push out lines which are reused. A line which
+    /// was already cached is demoted all the same, and a line which the pending write is yet
+    /// to fill is demoted once the write fills it.
+    fn demote(&mut self, addr: Word);
+
     /// Returns the number of cold misses that have occurred for the duration of the runtime.
     fn cold_misses(&self) -> usize;
     /// Returns the number of conflict misses that have occurred for the duration of the runtime.
@@ -105,6 +124,16 @@ pub trait MemoryModule: Debug {
     fn accesses(&self) -> usize;
     /// Returns the total number of times a cache line was evicted
     fn evictions(&self) -> usize;
+    /// Returns the number of cached data accesses which straddled two cache lines.
+    fn misaligned_accesses(&self) -> usize;
+    /// Returns the number of times an instruction read and a data access hit the same
+    /// cache bank in one clock, delaying one of them.
+    fn bank_conflicts(&self) -> usize;
+    /// Returns the number of times an access evicted a dirty line while the write buffer
+    /// was full, stalling until it drained.
+    fn write_buffer_stalls(&self) -> usize;
+    /// Returns the log of the most recent misses, or `None` if misses are not logged.
+    fn miss_log(&self) -> Option<&MissLog>;
 
     /// Get the memory structure
     fn memory(&self) -> &Memory;
@@ -112,18 +141,22 @@ pub trait MemoryModule: Debug {
     /// Get a mutable reference to the memory structure
     fn memory_mut(&mut self) -> &mut Memory;
 
-    /// Gets a hashmap containing the caches in the memory module
-    fn caches(&self) -> HashMap<&str, &dyn Cache>;
+    /// Gets the caches in the memory module along with their names
+    ///
+    /// The order is fixed: caches nearer the processor come first, and at each level the
+    /// instruction cache comes before the data cache.
+    fn caches(&self) -> Vec<(&str, &dyn Cache)>;
 
-    /// Gets a hashmap containing mutable references to the caches in the memory module
-    fn caches_mut(&mut self) -> HashMap<&str, &mut dyn Cache>;
+    /// Gets mutable references to the caches in the memory module along with their
+    /// names, in the same order as [`MemoryModule::caches`]
+    fn caches_mut(&mut self) -> Vec<(&str, &mut dyn Cache)>;
 
     /// Writes the cache back to memory. Returns [`Status::Idle`] on success.
     fn immediate_writeback(&mut self) -> Status;
 
     /// Get the state of the cache structures
     ///
-    /// Provides the names of the caches as well
+    /// Provides the names of the caches as well, in the order of [`MemoryModule::caches`]
     fn cache_state(&self) -> Vec<CacheData> {
         self.caches()
             .into_iter()
@@ -134,19 +167,23 @@ pub trait MemoryModule: Debug {
             .collect()
     }
 
-    /// Get the names of the caches in this module
+    /// Get the names of the caches in this module, in the order of [`MemoryModule::caches`]
     fn get_cache_names(&self) -> Vec<&str> {
-        self.caches().into_keys().collect()
+        self.caches().into_iter().map(|(name, _)| name).collect()
     }
 
     /// Gets a reference to a cache by name
     fn get_cache(&self, name: &str) -> Option<&dyn Cache> {
-        self.caches().remove(name)
+        self.caches()
+            .into_iter()
+            .find_map(|(n, cache)| (n == name).then_some(cache))
     }
 
     /// Gets a mutable reference to a cache by name
     fn get_cache_mut(&mut self, name: &str) -> Option<&mut dyn Cache> {
-        self.caches_mut().remove(name)
+        self.caches_mut()
+            .into_iter()
+            .find_map(|(n, cache)| (n == name).then_some(cache))
     }
 
     /// Gets a reference to the data cache (L1)
@@ -154,4 +191,11 @@ pub trait MemoryModule: Debug {
 
     /// Flush the contents of cache into memory
     fn flush_cache(&mut self) -> Status;
+
+    /// Loads the lines holding each address into every cache, as if they had been read
+    ///
+    /// Warming takes no clocks and is not counted towards the hit, miss, or eviction
+    /// statistics. Newly loaded lines are clean; lines already in a cache are left as
+    /// they are.
+    fn warm(&mut self, addresses: &[Word]);
 }
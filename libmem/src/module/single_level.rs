@@ -1,13 +1,18 @@
-use super::{MemoryModule, Result, Status};
+use super::{MemoryModule, MissKind, MissLog, Result, Status};
 use crate::{
     cache::{self, Cache},
     memory::Memory,
 };
 use libseis::types::{Byte, Short, Word};
-use std::collections::HashMap;
+use std::mem::take;
 use Status::Busy;
 use Transaction::*;
 
+/// The index of instruction reads in [`SingleLevel::bank_used`]
+const INSTRUCTION: usize = 0;
+/// The index of data accesses in [`SingleLevel::bank_used`]
+const DATA: usize = 1;
+
 /// Represents a memory transaction.
 #[derive(Debug, Clone)]
 enum Transaction {
@@ -38,6 +43,8 @@ enum Transaction {
     ReadWordV(Word),
     /// The cache is being flushed to memory
     FlushCache,
+    /// Waiting for the write buffer to make room for lines evicted by the last access
+    DrainWriteBuffer,
 }
 
 impl Transaction {
@@ -52,6 +59,24 @@ impl Transaction {
     pub fn is_busy(&self) -> bool {
         !matches!(self, Idle)
     }
+
+    /// Whether this is a cached write of the byte at `addr`
+    pub fn writes_to(&self, addr: Word) -> bool {
+        match *self {
+            WriteByte(address, _, false) => addr == address,
+            WriteShort(address, _, false) => addr.wrapping_sub(address) < 2,
+            WriteWord(address, _, false) => addr.wrapping_sub(address) < 4,
+            _ => false,
+        }
+    }
+
+    /// The address of a cached data read
+    pub fn read_address(&self) -> Option<Word> {
+        match *self {
+            ReadByte(addr) | ReadShort(addr) | ReadWord(addr) => Some(addr),
+            _ => None,
+        }
+    }
 }
 
 /// Represents a memory module with a single level of cache.
@@ -69,127 +94,76 @@ pub struct SingleLevel {
     current_transaction: Transaction,
     clocks: usize,
 
+    /// The number of data reads which may fill lines at once
+    ports: usize,
+    /// Data reads filling lines alongside the current transaction, with their remaining clocks
+    reads: Vec<(Transaction, usize)>,
+
+    /// The number of banks the caches are split into, or zero if banks are not modeled
+    banks: usize,
+    /// The banks hit by an instruction read and a data access this clock
+    bank_used: [Option<usize>; 2],
+
+    /// The number of evicted dirty lines the write buffer holds while they drain to memory
+    write_buffer_depth: usize,
+    /// The evicted dirty lines in the write buffer, including any waiting for room
+    write_buffer: usize,
+    /// The clocks left until the oldest line in the write buffer reaches memory
+    drain_clocks: usize,
+
     cold_misses: usize,
     conflict_misses: usize,
     hits: usize,
     accesses: usize,
-    last_access_miss: bool,
     evictions: usize,
+    misaligned: usize,
+    bank_conflicts: usize,
+    write_buffer_stalls: usize,
+
+    /// The most recent misses of either cache, if they are being logged
+    miss_log: Option<MissLog>,
+    /// The number of misses the log holds before it drops the oldest
+    miss_log_capacity: usize,
+
+    /// The addresses of data reads which missed, whose retries are not counted again
+    missed_reads: Vec<Word>,
+    /// The address of the last instruction read which missed or bypassed the cache
+    missed_instruction: Option<Word>,
+    /// The addresses of a pending write's lines to demote once the write fills them
+    demote_on_fill: Vec<Word>,
 }
 
 impl MemoryModule for SingleLevel {
     fn clock(&mut self, amount: usize) {
         self.clocks = self.clocks.saturating_sub(amount);
+        self.bank_used = [None; 2];
+        self.drain(amount);
 
-        if self.clocks == 0 {
-            match self.current_transaction {
-                WriteByte(addr, value, volatile) => {
-                    if volatile {
-                        self.data_cache.invalidate_line(addr);
-                        self.memory.write_byte(addr, value);
-                    } else if self.writethrough {
-                        self.memory.write_byte(addr, value);
-                    } else {
-                        if self.data_cache.get_line(addr, &mut self.memory).evicted() {
-                            self.evictions += 1;
-                        }
-
-                        self.data_cache.write_byte(addr, value);
-                    }
-                }
-                WriteShort(addr, value, volatile) => {
-                    if volatile {
-                        self.data_cache.invalidate_line(addr);
-                        self.memory.write_short(addr, value);
-                    } else if self.writethrough {
-                        self.memory.write_short(addr, value);
-                    } else {
-                        if self.data_cache.get_line(addr, &mut self.memory).evicted() {
-                            self.evictions += 1;
-                        }
-                        if self.data_cache.check_address(addr + 1).is_miss() {
-                            if self
-                                .data_cache
-                                .get_line(addr + 1, &mut self.memory)
-                                .evicted()
-                            {
-                                self.evictions += 1;
-                            }
-                        }
-
-                        self.data_cache.write_short(addr, value);
-                    }
-                }
-                WriteWord(addr, value, volatile) => {
-                    if volatile {
-                        self.data_cache.invalidate_line(addr);
-                        self.memory.write_word(addr, value);
-                    } else if self.writethrough {
-                        self.memory.write_word(addr, value);
-                    } else {
-                        if self.data_cache.get_line(addr, &mut self.memory).evicted() {
-                            self.evictions += 1;
-                        }
-                        if self.data_cache.check_address(addr + 3).is_miss() {
-                            if self
-                                .data_cache
-                                .get_line(addr + 3, &mut self.memory)
-                                .evicted()
-                            {
-                                self.evictions += 1;
-                            }
-                        }
-
-                        self.data_cache.write_word(addr, value);
-                    }
-                }
-
-                ReadByte(addr) => {
-                    if self.data_cache.get_line(addr, &mut self.memory).evicted() {
-                        self.evictions += 1;
-                    }
-                }
-                ReadShort(addr) => {
-                    if self.data_cache.get_line(addr, &mut self.memory).evicted() {
-                        self.evictions += 1;
-                    }
-                    if self.data_cache.check_address(addr + 1).is_miss() {
-                        if self
-                            .data_cache
-                            .get_line(addr + 1, &mut self.memory)
-                            .evicted()
-                        {
-                            self.evictions += 1;
-                        }
-                    }
-                }
-                ReadWord(addr) => {
-                    if self.data_cache.get_line(addr, &mut self.memory).evicted() {
-                        self.evictions += 1;
-                    }
-                    if self.data_cache.check_address(addr + 3).is_miss() {
-                        if self
-                            .data_cache
-                            .get_line(addr + 3, &mut self.memory)
-                            .evicted()
-                        {
-                            self.evictions += 1;
-                        }
-                    }
-                }
-                ReadInstruction(addr) => {
-                    self.instruction_cache.get_line(addr, &mut self.memory);
-                }
+        for (_, clocks) in &mut self.reads {
+            *clocks = clocks.saturating_sub(amount);
+        }
 
-                FlushCache => {
-                    self.data_cache.flush(&mut self.memory);
-                }
+        let (done, reads) = take(&mut self.reads)
+            .into_iter()
+            .partition::<Vec<_>, _>(|&(_, clocks)| clocks == 0);
+        self.reads = reads;
 
-                _ => return,
-            }
+        for (transaction, _) in done {
+            self.complete(transaction);
+        }
 
+        if self.clocks == 0 && self.complete(self.current_transaction.clone()) {
             self.current_transaction = Idle;
         }
+
+        let stall = self.write_buffer_wait();
+        if stall > self.clocks {
+            if !self.current_transaction.is_busy() {
+                self.current_transaction = DrainWriteBuffer;
+            }
+            self.clocks = stall;
+            self.write_buffer_stalls += 1;
+        }
     }
 
     fn wait_time(&self) -> usize {
@@ -197,88 +171,48 @@ impl MemoryModule for SingleLevel {
     }
 
     fn read_byte(&mut self, addr: Word) -> Result<Byte> {
-        if self.current_transaction.busy_data() {
-            return Err(Busy(self.clocks));
+        if let Some(clocks) = self.read_wait(addr) {
+            return Err(Busy(clocks));
+        }
+
+        if let Some(clocks) = self.bank_conflict(addr, DATA) {
+            return Err(Busy(clocks));
         }
 
         match self.data_cache.get_byte(addr) {
             Ok(value) => {
-                self.hit();
+                self.claim_bank(addr, DATA);
+                self.read_hit(addr, 1);
 
                 Ok(value)
             }
-            Err(cache::Status::Cold) => {
-                self.cold();
-
-                Err(self.set_if_idle(ReadByte(addr), self.read_miss_penalty))
-            }
-            Err(cache::Status::Conflict) => {
-                self.conflict();
-
-                Err(self.set_if_idle(ReadByte(addr), self.read_miss_penalty))
-            }
             Err(cache::Status::Disabled) => {
                 Err(self.set_if_idle(ReadByte(addr), self.volatile_penalty))
             }
+            Err(_) => {
+                let penalty = self.read_miss(addr, 1);
 
-            _ => unreachable!("No hits allowed!"),
+                Err(self.start_read(ReadByte(addr), penalty))
+            }
         }
     }
 
     fn read_short(&mut self, addr: Word) -> Result<Short> {
-        if self.current_transaction.busy_data() {
-            return Err(Busy(self.clocks));
+        if let Some(clocks) = self.read_wait(addr) {
+            return Err(Busy(clocks));
+        }
+
+        if let Some(clocks) = self.bank_conflict(addr, DATA) {
+            return Err(Busy(clocks));
         }
 
         match self.data_cache.get_short(addr) {
             Ok(value) => {
-                self.hit();
-                if addr % 2 != 0 {
-                    self.hit();
-                }
+                self.claim_bank(addr, DATA);
+                self.read_hit(addr, 2);
 
                 Ok(value)
             }
-            Err(cache::Status::Conflict) => {
-                self.conflict();
-                let mut penalty = self.read_miss_penalty;
-                if addr % 4 != 0 {
-                    penalty += 1;
-                    match self.data_cache.check_address(addr + 1) {
-                        cache::Status::Conflict => {
-                            self.conflict();
-                            penalty += self.read_miss_penalty;
-                        }
-                        cache::Status::Cold => {
-                            self.cold();
-                            penalty += self.read_miss_penalty;
-                        }
-                        _ => (),
-                    }
-                }
-
-                Err(self.set_if_idle(ReadShort(addr), penalty))
-            }
-            Err(cache::Status::Cold) => {
-                self.cold();
-                let mut penalty = self.read_miss_penalty;
-                if addr % 4 != 0 {
-                    penalty += 1;
-                    match self.data_cache.check_address(addr + 1) {
-                        cache::Status::Conflict => {
-                            self.conflict();
-                            penalty += self.read_miss_penalty;
-                        }
-                        cache::Status::Cold => {
-                            self.cold();
-                            penalty += self.read_miss_penalty;
-                        }
-                        _ => (),
-                    }
-                }
-
-                Err(self.set_if_idle(ReadShort(addr), penalty))
-            }
             Err(cache::Status::Disabled) => {
                 let mut penalty = self.volatile_penalty;
                 if addr % 2 != 0 {
@@ -287,78 +221,43 @@ impl MemoryModule for SingleLevel {
 
                 Err(self.set_if_idle(ReadShort(addr), penalty))
             }
+            Err(_) => {
+                let penalty = self.read_miss(addr, 2);
 
-            _ => unreachable!("No hits allowed!"),
+                Err(self.start_read(ReadShort(addr), penalty))
+            }
         }
     }
 
     fn read_word(&mut self, addr: Word) -> Result<Word> {
-        if self.current_transaction.busy_data() {
-            return Err(Busy(self.clocks));
+        if let Some(clocks) = self.read_wait(addr) {
+            return Err(Busy(clocks));
+        }
+
+        if let Some(clocks) = self.bank_conflict(addr, DATA) {
+            return Err(Busy(clocks));
         }
 
         match self.data_cache.get_word(addr) {
             Ok(value) => {
-                self.hit();
-                if addr % 4 != 0 {
-                    self.hit();
-                }
+                self.claim_bank(addr, DATA);
+                self.read_hit(addr, 4);
 
                 Ok(value)
             }
-            Err(cache::Status::Conflict) => {
-                self.conflict();
-
-                let mut penalty = self.read_miss_penalty;
-                if addr % 4 != 0 {
-                    penalty += 1;
-                    match self.data_cache.check_address(addr + 3) {
-                        cache::Status::Conflict => {
-                            self.conflict();
-                            penalty += self.read_miss_penalty;
-                        }
-                        cache::Status::Cold => {
-                            self.cold();
-                            penalty += self.read_miss_penalty;
-                        }
-                        _ => (),
-                    }
-                }
-
-                Err(self.set_if_idle(ReadWord(addr), penalty))
-            }
-            Err(cache::Status::Cold) => {
-                self.cold();
-
-                let mut penalty = self.read_miss_penalty;
-                if addr % 4 != 0 {
-                    penalty += 1;
-                    match self.data_cache.check_address(addr + 3) {
-                        cache::Status::Conflict => {
-                            self.conflict();
-                            penalty += self.read_miss_penalty;
-                        }
-                        cache::Status::Cold => {
-                            self.cold();
-                            penalty += self.read_miss_penalty;
-                        }
-                        _ => (),
-                    }
-                }
-
-                Err(self.set_if_idle(ReadWord(addr), penalty))
-            }
             Err(cache::Status::Disabled) => {
                 let mut penalty = self.volatile_penalty;
                 if addr % 4 != 0 {
-                    self.hit();
                     penalty += self.volatile_penalty;
                 }
 
                 Err(self.set_if_idle(ReadWord(addr), penalty))
             }
+            Err(_) => {
+                let penalty = self.read_miss(addr, 4);
 
-            _ => unreachable!("No hits allowed!"),
+                Err(self.start_read(ReadWord(addr), penalty))
+            }
         }
     }
 
@@ -416,67 +315,62 @@ impl MemoryModule for SingleLevel {
             return Err(Busy(self.clocks));
         }
 
+        if let Some(clocks) = self.bank_conflict(addr, INSTRUCTION) {
+            return Err(Busy(clocks));
+        }
+
         match self.instruction_cache.get_word(addr) {
             Ok(value) => {
-                self.hit();
+                self.claim_bank(addr, INSTRUCTION);
+
+                // The retry of a missed fetch was already counted, and an uncached one is
+                // never counted
+                if self.missed_instruction.take() != Some(addr) {
+                    self.accesses += 1;
+                    self.hits += 1;
+                }
 
                 Ok(value)
             }
-            Err(cache::Status::Cold) => {
-                self.cold();
+            Err(cache::Status::Disabled) => {
+                if !self.current_transaction.is_busy() {
+                    self.missed_instruction = Some(addr);
+                }
 
-                Err(self.set_if_idle(ReadInstruction(addr), self.read_miss_penalty))
+                Err(self.set_if_idle(ReadInstruction(addr), self.volatile_penalty))
             }
-            Err(cache::Status::Conflict) => {
-                self.conflict();
+            Err(status) => {
+                if !self.current_transaction.is_busy() {
+                    self.count(addr, status, "instruction");
+                    self.missed_instruction = Some(addr);
+                }
 
                 Err(self.set_if_idle(ReadInstruction(addr), self.read_miss_penalty))
             }
-            Err(cache::Status::Disabled) => {
-                Err(self.set_if_idle(ReadInstruction(addr), self.volatile_penalty))
-            }
-
-            _ => unreachable!("No hits allowed!"),
         }
     }
 
     fn write_byte(&mut self, addr: Word, value: Byte) -> Status {
         if self.current_transaction.busy_data() {
             Busy(self.clocks)
+        } else if let Some(clocks) = self.bank_conflict(addr, DATA) {
+            Busy(clocks)
         } else {
             match self.data_cache.write_byte(addr, value) {
                 cache::Status::Hit => {
-                    self.hit();
+                    self.claim_bank(addr, DATA);
+                    self.hit(addr, 1);
+                    self.invalidate_instructions(addr, 1);
 
                     Status::Idle
                 }
-                cache::Status::Conflict => {
-                    self.conflict();
-
-                    if !self.current_transaction.is_busy() {
-                        self.set_if_idle(WriteByte(addr, value, false), self.write_miss_penalty);
-                        Status::Idle
-                    } else {
-                        self.set_if_idle(WriteByte(addr, value, false), self.write_miss_penalty)
-                    }
-                }
-                cache::Status::Cold => {
-                    self.cold();
-
-                    if !self.current_transaction.is_busy() {
-                        self.set_if_idle(WriteByte(addr, value, false), self.write_miss_penalty);
-                        Status::Idle
-                    } else {
-                        self.set_if_idle(WriteByte(addr, value, false), self.write_miss_penalty)
-                    }
-                }
                 cache::Status::Disabled => {
-                    if !self.current_transaction.is_busy() {
-                        self.set_if_idle(WriteByte(addr, value, false), self.volatile_penalty);
-                        Status::Idle
-                    } else {
-                        self.set_if_idle(WriteByte(addr, value, false), self.volatile_penalty)
-                    }
+                    self.set_write(WriteByte(addr, value, false), self.volatile_penalty)
+                }
+                _ => {
+                    let penalty = self.write_miss(addr, 1);
+
+                    self.set_write(WriteByte(addr, value, false), penalty)
                 }
             }
         }
@@ -485,78 +379,29 @@ impl MemoryModule for SingleLevel {
     fn write_short(&mut self, addr: Word, value: Short) -> Status {
         if self.current_transaction.busy_data() {
             Busy(self.clocks)
+        } else if let Some(clocks) = self.bank_conflict(addr, DATA) {
+            Busy(clocks)
         } else {
             match self.data_cache.write_short(addr, value) {
                 cache::Status::Hit => {
-                    self.hit();
-                    if !self.data_cache.within_line(addr, 2) {
-                        self.hit();
-                    }
+                    self.claim_bank(addr, DATA);
+                    self.hit(addr, 2);
+                    self.invalidate_instructions(addr, 2);
 
                     Status::Idle
                 }
-                cache::Status::Conflict => {
-                    self.conflict();
-                    let mut penalty = self.write_miss_penalty;
-                    if addr % 2 != 0 {
-                        penalty += 1;
-                        match self.data_cache.check_address(addr + 1) {
-                            cache::Status::Conflict => {
-                                self.conflict();
-                                penalty = self.write_miss_penalty;
-                            }
-                            cache::Status::Cold => {
-                                self.cold();
-                                penalty = self.write_miss_penalty;
-                            }
-                            _ => (),
-                        }
-                    }
-
-                    if !self.current_transaction.is_busy() {
-                        self.set_if_idle(WriteShort(addr, value, false), penalty);
-                        Status::Idle
-                    } else {
-                        self.set_if_idle(WriteShort(addr, value, false), penalty)
-                    }
-                }
-                cache::Status::Cold => {
-                    self.cold();
-                    let mut penalty = self.write_miss_penalty;
-                    if addr % 2 != 0 {
-                        penalty += 1;
-                        match self.data_cache.check_address(addr + 1) {
-                            cache::Status::Conflict => {
-                                self.conflict();
-                                penalty = self.write_miss_penalty;
-                            }
-                            cache::Status::Cold => {
-                                self.cold();
-                                penalty = self.write_miss_penalty;
-                            }
-                            _ => (),
-                        }
-                    }
-
-                    if !self.current_transaction.is_busy() {
-                        self.set_if_idle(WriteShort(addr, value, false), penalty);
-                        Status::Idle
-                    } else {
-                        self.set_if_idle(WriteShort(addr, value, false), penalty)
-                    }
-                }
                 cache::Status::Disabled => {
                     let mut penalty = self.volatile_penalty;
                     if addr % 2 != 0 {
                         penalty += self.volatile_penalty;
                     }
 
-                    if !self.current_transaction.is_busy() {
-                        self.set_if_idle(WriteShort(addr, value, false), penalty);
-                        Status::Idle
-                    } else {
-                        self.set_if_idle(WriteShort(addr, value, false), penalty)
-                    }
+                    self.set_write(WriteShort(addr, value, false), penalty)
+                }
+                _ => {
+                    let penalty = self.write_miss(addr, 2);
+
+                    self.set_write(WriteShort(addr, value, false), penalty)
                 }
             }
         }
@@ -565,79 +410,29 @@ impl MemoryModule for SingleLevel {
     fn write_word(&mut self, addr: Word, value: Word) -> Status {
         if self.current_transaction.busy_data() {
             Busy(self.clocks)
+        } else if let Some(clocks) = self.bank_conflict(addr, DATA) {
+            Busy(clocks)
         } else {
             match self.data_cache.write_word(addr, value) {
                 cache::Status::Hit => {
-                    self.hit();
-                    if !self.data_cache.within_line(addr, 4) {
-                        self.hit();
-                    }
+                    self.claim_bank(addr, DATA);
+                    self.hit(addr, 4);
+                    self.invalidate_instructions(addr, 4);
 
                     Status::Idle
                 }
-                cache::Status::Conflict => {
-                    self.conflict();
-                    let mut penalty = self.write_miss_penalty;
-                    if addr % 4 != 0 {
-                        penalty += 1;
-
-                        match self.data_cache.check_address(addr + 3) {
-                            cache::Status::Conflict => {
-                                self.conflict();
-                                penalty = self.write_miss_penalty;
-                            }
-                            cache::Status::Cold => {
-                                self.cold();
-                                penalty = self.write_miss_penalty;
-                            }
-                            _ => (),
-                        }
-                    }
-
-                    if !self.current_transaction.is_busy() {
-                        self.set_if_idle(WriteWord(addr, value, false), penalty);
-                        Status::Idle
-                    } else {
-                        self.set_if_idle(WriteWord(addr, value, false), penalty)
-                    }
-                }
-                cache::Status::Cold => {
-                    self.cold();
-                    let mut penalty = self.write_miss_penalty;
-                    if addr % 4 != 0 {
-                        penalty += 1;
-                        match self.data_cache.check_address(addr + 3) {
-                            cache::Status::Conflict => {
-                                self.conflict();
-                                penalty = self.write_miss_penalty;
-                            }
-                            cache::Status::Cold => {
-                                self.cold();
-                                penalty = self.write_miss_penalty;
-                            }
-                            _ => (),
-                        }
-                    }
-
-                    if !self.current_transaction.is_busy() {
-                        self.set_if_idle(WriteWord(addr, value, false), penalty);
-                        Status::Idle
-                    } else {
-                        self.set_if_idle(WriteWord(addr, value, false), penalty)
-                    }
-                }
                 cache::Status::Disabled => {
                     let mut penalty = self.volatile_penalty;
                     if addr % 4 != 0 {
                         penalty += 1 + self.volatile_penalty;
                     }
 
-                    if !self.current_transaction.is_busy() {
-                        self.set_if_idle(WriteWord(addr, value, false), penalty);
-                        Status::Idle
-                    } else {
-                        self.set_if_idle(WriteWord(addr, value, false), penalty)
-                    }
+                    self.set_write(WriteWord(addr, value, false), penalty)
+                }
+                _ => {
+                    let penalty = self.write_miss(addr, 4);
+
+                    self.set_write(WriteWord(addr, value, false), penalty)
                 }
             }
         }
@@ -677,6 +472,13 @@ impl MemoryModule for SingleLevel {
         }
     }
 
+    fn demote(&mut self, addr: Word) {
+        // A write which missed fills its line when it completes, so the line is demoted then
+        if !self.data_cache.demote_line(addr) && self.current_transaction.writes_to(addr) {
+            self.demote_on_fill.push(addr);
+        }
+    }
+
     fn cold_misses(&self) -> usize {
         self.cold_misses
     }
@@ -697,6 +499,22 @@ impl MemoryModule for SingleLevel {
         self.evictions
     }
 
+    fn misaligned_accesses(&self) -> usize {
+        self.misaligned
+    }
+
+    fn bank_conflicts(&self) -> usize {
+        self.bank_conflicts
+    }
+
+    fn write_buffer_stalls(&self) -> usize {
+        self.write_buffer_stalls
+    }
+
+    fn miss_log(&self) -> Option<&MissLog> {
+        self.miss_log.as_ref()
+    }
+
     fn memory(&self) -> &Memory {
         &self.memory
     }
@@ -705,23 +523,21 @@ impl MemoryModule for SingleLevel {
         &mut self.memory
     }
 
-    fn caches(&self) -> HashMap<&'static str, &dyn Cache> {
-        [
-            ("data", self.data_cache.as_ref() as &dyn Cache),
+    fn caches(&self) -> Vec<(&str, &dyn Cache)> {
+        vec![
             ("instruction", self.instruction_cache.as_ref() as &dyn Cache),
+            ("data", self.data_cache.as_ref() as &dyn Cache),
         ]
-        .into()
     }
 
-    fn caches_mut(&mut self) -> HashMap<&'static str, &mut dyn Cache> {
-        [
-            ("data", self.data_cache.as_mut() as &mut dyn Cache),
+    fn caches_mut(&mut self) -> Vec<(&str, &mut dyn Cache)> {
+        vec![
             (
                 "instruction",
                 self.instruction_cache.as_mut() as &mut dyn Cache,
             ),
+            ("data", self.data_cache.as_mut() as &mut dyn Cache),
         ]
-        .into()
     }
 
     fn immediate_writeback(&mut self) -> Status {
@@ -753,6 +569,14 @@ impl MemoryModule for SingleLevel {
             }
         }
     }
+
+    fn warm(&mut self, addresses: &[Word]) {
+        for &address in addresses {
+            self.data_cache.get_line(address, &mut self.memory);
+            self.sync_instruction_line(address);
+            self.instruction_cache.get_line(address, &mut self.memory);
+        }
+    }
 }
 
 impl SingleLevel {
@@ -769,7 +593,8 @@ impl SingleLevel {
     ///
     /// # Notes
     ///
-    /// - A misaligned access has a clock penalty of 1 plus another miss penalty (multiple accesses)
+    /// - An access straddling two cache lines checks both lines, with a clock penalty of 1
+    ///   plus the miss penalty of each line which missed
     pub fn new(
         data_cache: Box<dyn Cache + Send + Sync>,
         instruction_cache: Box<dyn Cache + Send + Sync>,
@@ -795,12 +620,248 @@ impl SingleLevel {
             current_transaction: Idle,
             clocks: 0,
 
+            ports: 1,
+            reads: Vec::new(),
+
+            banks: 0,
+            bank_used: [None; 2],
+
+            write_buffer_depth: usize::MAX,
+            write_buffer: 0,
+            drain_clocks: 0,
+
             cold_misses: 0,
             conflict_misses: 0,
             hits: 0,
             accesses: 0,
-            last_access_miss: false,
             evictions: 0,
+            misaligned: 0,
+            bank_conflicts: 0,
+            write_buffer_stalls: 0,
+
+            miss_log: None,
+            miss_log_capacity: 0,
+
+            missed_reads: Vec::new(),
+            missed_instruction: None,
+            demote_on_fill: Vec::new(),
+        }
+    }
+
+    /// Lets the module fill lines for up to `ports` data reads at once, so reads which
+    /// miss back to back overlap instead of waiting on each other
+    ///
+    /// Only reads overlap: writes, volatile accesses, and instruction reads wait for
+    /// every port. A module has a single port unless set otherwise.
+    pub fn with_ports(mut self, ports: usize) -> Self {
+        self.ports = ports.max(1);
+        self
+    }
+
+    /// Splits the caches into `banks` banks, chosen by the address bits above the offset
+    /// into the longer of the two caches' lines
+    ///
+    /// An instruction read and a data access which hit the same bank in one clock
+    /// conflict, and the later of the two waits a clock. A module has unlimited banks,
+    /// and so no conflicts, unless set otherwise; zero also means unlimited.
+    pub fn with_banks(mut self, banks: usize) -> Self {
+        self.banks = banks;
+        self
+    }
+
+    /// Gives the module a write buffer holding up to `depth` evicted dirty lines, each of
+    /// which takes the miss penalty to drain to memory, one after another
+    ///
+    /// An access which evicts a dirty line while the buffer is full stalls until the
+    /// buffer has room for it. A module's buffer is unbounded, and so never stalls,
+    /// unless set otherwise.
+    pub fn with_write_buffer(mut self, depth: usize) -> Self {
+        self.write_buffer_depth = depth;
+        self
+    }
+
+    /// Logs the address and [kind](MissKind) of every miss counted by either cache, along
+    /// with the name of that cache, keeping the most recent `capacity` of them
+    ///
+    /// The log is read with [`MemoryModule::miss_log`]. A module does not log its misses
+    /// unless set otherwise, since the log costs memory for every miss it holds.
+    pub fn with_miss_log(mut self, capacity: usize) -> Self {
+        self.miss_log = Some(MissLog::with_capacity(capacity.min(1 << 16)));
+        self.miss_log_capacity = capacity;
+        self
+    }
+
+    /// Finishes a transaction once its clocks have run out
+    ///
+    /// Returns false if the transaction is finished by the access itself instead.
+    fn complete(&mut self, transaction: Transaction) -> bool {
+        match transaction {
+            WriteByte(addr, value, volatile) => {
+                self.invalidate_instructions(addr, 1);
+
+                if volatile {
+                    self.data_cache.invalidate_line(addr);
+                    self.memory.write_byte(addr, value);
+                } else if self.writethrough {
+                    self.memory.write_byte(addr, value);
+                } else {
+                    self.fill_line(addr);
+
+                    self.data_cache.write_byte(addr, value);
+                }
+
+                self.demote_filled();
+            }
+            WriteShort(addr, value, volatile) => {
+                self.invalidate_instructions(addr, 2);
+
+                if volatile {
+                    self.data_cache.invalidate_line(addr);
+                    self.memory.write_short(addr, value);
+                } else if self.writethrough {
+                    self.memory.write_short(addr, value);
+                } else {
+                    self.fill_line(addr);
+                    let last = addr.wrapping_add(1);
+                    if self.data_cache.check_address(last).is_miss() {
+                        self.fill_line(last);
+                    }
+
+                    self.data_cache.write_short(addr, value);
+                }
+
+                self.demote_filled();
+            }
+            WriteWord(addr, value, volatile) => {
+                self.invalidate_instructions(addr, 4);
+
+                if volatile {
+                    self.data_cache.invalidate_line(addr);
+                    self.memory.write_word(addr, value);
+                } else if self.writethrough {
+                    self.memory.write_word(addr, value);
+                } else {
+                    self.fill_line(addr);
+                    let last = addr.wrapping_add(3);
+                    if self.data_cache.check_address(last).is_miss() {
+                        self.fill_line(last);
+                    }
+
+                    self.data_cache.write_word(addr, value);
+                }
+
+                self.demote_filled();
+            }
+
+            ReadByte(addr) => {
+                self.fill_line(addr);
+            }
+            ReadShort(addr) => {
+                self.fill_line(addr);
+                let last = addr.wrapping_add(1);
+                if self.data_cache.check_address(last).is_miss() {
+                    self.fill_line(last);
+                }
+            }
+            ReadWord(addr) => {
+                self.fill_line(addr);
+                let last = addr.wrapping_add(3);
+                if self.data_cache.check_address(last).is_miss() {
+                    self.fill_line(last);
+                }
+            }
+            ReadInstruction(addr) => {
+                self.sync_instruction_line(addr);
+                self.instruction_cache.get_line(addr, &mut self.memory);
+            }
+
+            FlushCache => {
+                self.data_cache.flush(&mut self.memory);
+            }
+            DrainWriteBuffer => {}
+
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Fills the data cache line holding an address, counting any eviction and queueing
+    /// an evicted dirty line in the write buffer
+    fn fill_line(&mut self, addr: Word) {
+        let dirty = self.data_cache.dirty_lines();
+
+        if self.data_cache.get_line(addr, &mut self.memory).evicted() {
+            self.evictions += 1;
+
+            if self.data_cache.dirty_lines() < dirty {
+                if self.write_buffer == 0 {
+                    self.drain_clocks = self.read_miss_penalty;
+                }
+                self.write_buffer += 1;
+            }
+        }
+    }
+
+    /// Demotes the lines of a non-temporal write which were filled by its completion
+    fn demote_filled(&mut self) {
+        for addr in take(&mut self.demote_on_fill) {
+            self.data_cache.demote_line(addr);
+        }
+    }
+
+    /// Drains the write buffer by a number of clocks
+    fn drain(&mut self, mut amount: usize) {
+        while self.write_buffer > 0 && amount >= self.drain_clocks {
+            amount -= self.drain_clocks;
+            self.write_buffer -= 1;
+            self.drain_clocks = self.read_miss_penalty;
+        }
+
+        if self.write_buffer > 0 {
+            self.drain_clocks -= amount;
+        } else {
+            self.drain_clocks = 0;
+        }
+    }
+
+    /// The clocks until every line waiting for room in the write buffer has entered it
+    fn write_buffer_wait(&self) -> usize {
+        match self.write_buffer.checked_sub(self.write_buffer_depth) {
+            Some(waiting) if waiting > 0 => {
+                self.drain_clocks + (waiting - 1) * self.read_miss_penalty
+            }
+            _ => 0,
+        }
+    }
+
+    /// Invalidates the instruction cache lines touched by a write of `length` bytes, so
+    /// that code written over is fetched again
+    fn invalidate_instructions(&mut self, addr: Word, length: usize) {
+        self.instruction_cache.invalidate_line(addr);
+        self.instruction_cache
+            .invalidate_line(addr.wrapping_add(length as Word - 1));
+    }
+
+    /// Copies any dirty data cached for the instruction line holding `addr` to memory
+    /// before the line is filled, so that the fill sees writes not yet written back
+    ///
+    /// Clean lines already match memory, so they are left alone rather than marking
+    /// their bytes as written.
+    fn sync_instruction_line(&mut self, addr: Word) {
+        let (base, length) = match self.instruction_cache.line_len() {
+            0 => (addr, 4),
+            length => (addr & !(length as Word - 1), length),
+        };
+
+        for address in (0..length as Word).map(|offset| base.wrapping_add(offset)) {
+            if !self.data_cache.is_dirty(address) {
+                continue;
+            }
+
+            if let Some(byte) = self.data_cache.byte_at(address) {
+                self.memory.write_byte(address, byte);
+            }
         }
     }
 
@@ -813,34 +874,214 @@ impl SingleLevel {
         Busy(self.clocks)
     }
 
-    /// Increments the hit counter if the last access was not a miss, otherwise unsets
-    /// the miss flag
-    fn hit(&mut self) {
-        if self.last_access_miss {
-            self.last_access_miss = false;
+    /// Checks whether a data read has to wait, returning the clocks left if so
+    ///
+    /// A read waits on its own line fill, and on any other transaction unless it is a
+    /// read and a port is free for this one.
+    fn read_wait(&self, addr: Word) -> Option<usize> {
+        if let Some(&(_, clocks)) = self
+            .reads
+            .iter()
+            .find(|(read, _)| read.read_address() == Some(addr))
+        {
+            return Some(clocks);
+        }
+
+        match self.current_transaction.read_address() {
+            Some(address) if address != addr && self.reads.len() + 1 < self.ports => None,
+            _ if self.current_transaction.busy_data() => Some(self.clocks),
+            _ => None,
+        }
+    }
+
+    /// Whether a data read which missed can start filling its lines
+    fn port_free(&self) -> bool {
+        match self.current_transaction {
+            Idle => true,
+            ReadByte(_) | ReadShort(_) | ReadWord(_) => self.reads.len() + 1 < self.ports,
+            _ => false,
+        }
+    }
+
+    /// Starts a data read on a free port
+    fn start_read(&mut self, transaction: Transaction, clocks: usize) -> Status {
+        if self.current_transaction.is_busy() && self.port_free() {
+            self.reads.push((transaction, clocks));
+            Busy(clocks)
         } else {
-            self.accesses += 1;
-            self.hits += 1;
+            self.set_if_idle(transaction, clocks)
         }
     }
 
-    /// Increments the cold miss counter and sets the miss flag if the memory subsystem
-    /// is not busy reading data
-    fn cold(&mut self) {
+    /// Sets a write transaction, letting the pipeline continue if the module was idle
+    fn set_write(&mut self, transaction: Transaction, clocks: usize) -> Status {
         if !self.current_transaction.is_busy() {
-            self.accesses += 1;
-            self.cold_misses += 1;
-            self.last_access_miss = true;
+            self.set_if_idle(transaction, clocks);
+            Status::Idle
+        } else {
+            self.set_if_idle(transaction, clocks)
         }
     }
 
-    /// Increments the conflict miss counter and sets the miss flag if the memory
-    /// subsystem is not busy reading data
-    fn conflict(&mut self) {
-        if !self.current_transaction.is_busy() {
-            self.accesses += 1;
-            self.conflict_misses += 1;
-            self.last_access_miss = true;
+    /// Gets the addresses within the lines of the data cache touched by an access of
+    /// `length` bytes, which is two when the access straddles a line boundary
+    fn lines(&self, addr: Word, length: usize) -> ([Word; 2], usize) {
+        let last = addr.wrapping_add(length as Word - 1);
+        let lines = if self.data_cache.within_line(addr, length) {
+            1
+        } else {
+            2
+        };
+
+        ([addr, last], lines)
+    }
+
+    /// The bank holding `addr`, if banks are modeled
+    ///
+    /// Both caches interleave their banks by the longer of their line lengths, so an
+    /// address is in the same bank whichever cache it is read through.
+    fn bank(&self, addr: Word) -> Option<usize> {
+        let interleave = self
+            .instruction_cache
+            .line_len()
+            .max(self.data_cache.line_len());
+
+        if self.banks == 0 || interleave == 0 {
+            None
+        } else {
+            Some((addr as usize / interleave) % self.banks)
+        }
+    }
+
+    /// Checks whether an instruction read or data access which would hit conflicts with
+    /// the other kind of access this clock, returning the clocks to wait if so
+    ///
+    /// This is checked before the cache is accessed, so an access which has to wait
+    /// leaves the cache as it was. Misses go to memory, and so never conflict.
+    fn bank_conflict(&mut self, addr: Word, side: usize) -> Option<usize> {
+        let cache = match side {
+            INSTRUCTION => &self.instruction_cache,
+            _ => &self.data_cache,
+        };
+
+        if !cache.check_address(addr).is_hit() {
+            return None;
+        }
+
+        let bank = self.bank(addr)?;
+
+        if self.bank_used[1 - side] == Some(bank) {
+            self.bank_conflicts += 1;
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    /// Claims the bank of a hit for an instruction read or data access this clock
+    fn claim_bank(&mut self, addr: Word, side: usize) {
+        if let Some(bank) = self.bank(addr) {
+            self.bank_used[side] = Some(bank);
+        }
+    }
+
+    /// Counts the hits of a read, unless it is the retry of a read which missed (and so
+    /// has already been counted)
+    fn read_hit(&mut self, addr: Word, length: usize) {
+        match self.missed_reads.iter().position(|&missed| missed == addr) {
+            Some(index) => {
+                self.missed_reads.swap_remove(index);
+            }
+            None => self.hit(addr, length),
+        }
+    }
+
+    /// Counts a hit for each line touched by an access
+    fn hit(&mut self, addr: Word, length: usize) {
+        let (_, lines) = self.lines(addr, length);
+
+        if lines > 1 {
+            self.misaligned += 1;
+        }
+
+        self.accesses += lines;
+        self.hits += lines;
+    }
+
+    /// Counts a read which missed and remembers it, so its retry is not counted again
+    ///
+    /// Returns the clock penalty of the read.
+    fn read_miss(&mut self, addr: Word, length: usize) -> usize {
+        let counted = self.port_free();
+
+        if counted {
+            self.missed_reads.push(addr);
+        }
+
+        self.miss(addr, length, self.read_miss_penalty, counted)
+    }
+
+    /// Counts a write which missed, returning its clock penalty
+    fn write_miss(&mut self, addr: Word, length: usize) -> usize {
+        let counted = !self.current_transaction.is_busy();
+
+        self.miss(addr, length, self.write_miss_penalty, counted)
+    }
+
+    /// Checks each line touched by an access which missed, counting the outcome of
+    /// each if the access will be started
+    ///
+    /// An access straddling two lines is modeled as two sub-accesses: every line which
+    /// misses costs the miss penalty, and joining the two halves costs another clock.
+    fn miss(&mut self, addr: Word, length: usize, miss_penalty: usize, counted: bool) -> usize {
+        let (addresses, lines) = self.lines(addr, length);
+
+        let mut penalty = lines - 1;
+
+        if counted && lines > 1 {
+            self.misaligned += 1;
+        }
+
+        for &address in &addresses[..lines] {
+            let status = self.data_cache.check_address(address);
+
+            if status.is_miss() {
+                penalty += miss_penalty;
+            }
+
+            if counted {
+                self.count(address, status, "data");
+            }
+        }
+
+        penalty
+    }
+
+    /// Counts an access of the line holding an address by the outcome of its lookup,
+    /// logging it if it missed
+    fn count(&mut self, address: Word, status: cache::Status, cache: &'static str) {
+        self.accesses += 1;
+
+        let kind = match status {
+            cache::Status::Cold => {
+                self.cold_misses += 1;
+                MissKind::Cold
+            }
+            cache::Status::Conflict => {
+                self.conflict_misses += 1;
+                MissKind::Conflict
+            }
+            _ => {
+                self.hits += 1;
+                return;
+            }
+        };
+
+        if let Some(log) = &mut self.miss_log {
+            log.push_back((address, kind, cache));
+            if log.len() > self.miss_log_capacity {
+                log.pop_front();
+            }
         }
     }
 }
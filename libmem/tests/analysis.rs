@@ -0,0 +1,36 @@
+use libmem::analysis::{reuse_distances, ReuseHistogram};
+
+#[test]
+fn reuse_distances_of_known_sequence() {
+    // a b c a b b d a c
+    let histogram = reuse_distances([1, 2, 3, 1, 2, 2, 4, 1, 3]);
+
+    // The second a sees {b, c}, the second b {c, a}, the third b nothing, the third a
+    // {b, d}, and the second c {a, b, d}
+    assert_eq!(histogram.cold, 4);
+    assert_eq!(
+        histogram.distances,
+        [(0, 1), (2, 3), (3, 1)].into_iter().collect()
+    );
+    assert_eq!(histogram.accesses(), 9);
+
+    assert_eq!(histogram.hits_with(1), 1);
+    assert_eq!(histogram.hits_with(3), 4);
+    assert_eq!(histogram.hits_with(4), 5);
+}
+
+#[test]
+fn reuse_distances_of_nothing() {
+    assert_eq!(reuse_distances([]), ReuseHistogram::default());
+}
+
+#[test]
+fn cyclic_sequence_thrashes_smaller_caches() {
+    // Eight lines read round-robin only ever hit in a cache holding all of them
+    let histogram = reuse_distances((0..64).map(|n| n % 8 * 0x40));
+
+    assert_eq!(histogram.cold, 8);
+    assert_eq!(histogram.distances, [(7, 56)].into_iter().collect());
+    assert_eq!(histogram.hits_with(7), 0);
+    assert_eq!(histogram.hits_with(8), 56);
+}
@@ -1,5 +1,9 @@
-use libmem::memory::Memory;
-use libseis::types::Byte;
+use libmem::memory::{Memory, Width};
+use libseis::{
+    image::{encode, ImageError, Segment},
+    pages::MAX_PAGES,
+    types::{Byte, Word},
+};
 
 #[test]
 fn read_uninit() {
@@ -171,3 +175,500 @@ fn write_word_boundary() {
         );
     }
 }
+
+#[test]
+fn access_wraps_past_last_page() {
+    let mut mem = Memory::new(1);
+
+    mem.write_short(0x0000_FFFF, 0x0102);
+    assert_eq!(mem.read_byte(0x0000_FFFF), 0x01);
+    assert_eq!(mem.read_byte(0x0000_0000), 0x02);
+    assert_eq!(mem.read_short(0x0000_FFFF), 0x0102);
+
+    mem.write_word(0x0000_FFFD, 0x0A0B_0C0D);
+    assert_eq!(mem.read_byte(0x0000_FFFF), 0x0C);
+    assert_eq!(mem.read_byte(0x0000_0000), 0x0D);
+    assert_eq!(mem.read_word(0x0000_FFFD), 0x0A0B_0C0D);
+}
+
+#[test]
+fn write_words() {
+    let mut mem = Memory::new(2);
+
+    mem.write_words(0x0000_FFFE, &[0x01, 0x02, 0x03, 0x04, 0x05]);
+
+    assert_eq!(mem.read_words(0x0000_FFFE, 5).as_ref(), &[1, 2, 3, 4, 5]);
+    assert_eq!(
+        mem.read_word(0x0000_FFFE),
+        0x0102_0304,
+        "Page-crossing bytes"
+    );
+    assert_eq!(mem.read_byte(0x0000_FFFD), 0x00, "Before the buffer");
+    assert_eq!(mem.read_byte(0x0001_0003), 0x00, "After the buffer");
+}
+
+#[test]
+fn load_flat_image() {
+    let mut mem = Memory::new(2);
+    let data: Vec<Byte> = (0..0x0001_0004).map(|i| (i % 0xFF) as Byte).collect();
+
+    let segments = mem.load_image(&data).unwrap();
+
+    assert_eq!(segments.len(), 1);
+    assert_eq!(mem.read_word(0x0001_0000), 0x0102_0304);
+    assert!(!mem.is_read_only(0x0000_0000, 4));
+    assert!(matches!(
+        Memory::new(1).load_image(&data),
+        Err(ImageError::SegmentOverflow(_))
+    ));
+}
+
+#[test]
+fn flat_image_fills_memory() {
+    let mut mem = Memory::new(2);
+    let data = vec![0xAB; mem.max_address() as usize + 1];
+
+    let segments = mem.load_image(&data).unwrap();
+
+    assert_eq!(segments[0].length, 0x0002_0000);
+    assert_eq!(mem.read_byte(mem.max_address()), 0xAB);
+    assert!(matches!(
+        mem.load_image(&[data, vec![0xAB]].concat()),
+        Err(ImageError::SegmentOverflow(_))
+    ));
+}
+
+#[test]
+fn every_address_fits() {
+    let mut mem = Memory::new(MAX_PAGES);
+
+    assert_eq!(mem.page_count(), MAX_PAGES);
+    assert_eq!(mem.max_address(), Word::MAX);
+
+    mem.write_word(0xFFFF_FFFC, 0x0102_0304);
+    assert_eq!(mem.read_word(0xFFFF_FFFC), 0x0102_0304);
+}
+
+#[test]
+fn load_segmented_image() {
+    let mut mem = Memory::new(4);
+    let image = encode(
+        0,
+        [
+            (0x0000_0000, Segment::READ_ONLY, &[1, 2, 3, 4][..]),
+            (0x0003_FFFE, 0, &[5, 6][..]),
+        ],
+    );
+
+    let segments = mem.load_image(&image).unwrap();
+
+    assert_eq!(segments.len(), 2);
+    assert_eq!(mem.read_word(0x0000_0000), 0x0102_0304);
+    assert_eq!(mem.read_short(0x0003_FFFE), 0x0506);
+
+    // Nothing between the segments is allocated
+    assert_eq!(
+        mem.allocated_pages().map(|p| p.id).collect::<Vec<_>>(),
+        [0, 3]
+    );
+
+    assert!(mem.is_read_only(0x0000_0003, 1));
+    assert!(mem.is_read_only(0x0000_0002, 4));
+    assert!(!mem.is_read_only(0x0000_0004, 4));
+    assert!(!mem.is_read_only(0x0003_FFFE, 2));
+
+    mem.erase();
+    assert!(!mem.is_read_only(0x0000_0000, 4));
+}
+
+#[test]
+fn unprotect_part_of_a_segment() {
+    let mut mem = Memory::new(1);
+    mem.protect(0x0000_0000..0x0000_0010);
+
+    mem.unprotect(0x0000_0004..0x0000_0008);
+
+    assert!(mem.is_read_only(0x0000_0000, 4));
+    assert!(!mem.is_read_only(0x0000_0004, 4));
+    assert!(
+        mem.is_read_only(0x0000_0006, 4),
+        "Ends in the protected half"
+    );
+    assert_eq!(
+        mem.read_only_ranges(),
+        [0x0000_0000..0x0000_0004, 0x0000_0008..0x0000_0010]
+    );
+
+    mem.unprotect(0x0000_0000..0x0001_0000);
+    assert!(mem.read_only_ranges().is_empty());
+}
+
+#[test]
+fn load_segment_ending_at_last_address() {
+    let mut mem = Memory::new(MAX_PAGES);
+    let image = encode(0, [(0xFFFF_FFFC, Segment::READ_ONLY, &[1, 2, 3, 4][..])]);
+
+    mem.load_image(&image).unwrap();
+
+    assert_eq!(mem.read_word(0xFFFF_FFFC), 0x0102_0304);
+    assert!(mem.is_read_only(0xFFFF_FFFF, 1));
+    assert_eq!(mem.read_only_ranges(), [0xFFFF_FFFC..0x1_0000_0000]);
+}
+
+#[test]
+fn load_segmented_image_out_of_range() {
+    let mut mem = Memory::new(1);
+    let image = encode(0, [(0x0000_FFFF, 0, &[1, 2][..])]);
+
+    assert!(matches!(
+        mem.load_image(&image),
+        Err(ImageError::SegmentOverflow(Segment {
+            address: 0x0000_FFFF,
+            length: 2,
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn search_values() {
+    let mut mem = Memory::new(2);
+
+    mem.write_word(0x0000_0010, 0xDEAD_BEEF);
+    mem.write_word(0x0000_0021, 0xDEAD_BEEF);
+
+    assert_eq!(
+        mem.search(0xDEAD_BEEF, Width::Word, 0..Word::MAX),
+        [0x0000_0010, 0x0000_0021]
+    );
+    assert_eq!(
+        mem.search(0xBEEF, Width::Short, 0..Word::MAX),
+        [0x0000_0012, 0x0000_0023]
+    );
+    assert_eq!(
+        mem.search(0xAD, Width::Byte, 0..Word::MAX),
+        [0x0000_0011, 0x0000_0022]
+    );
+}
+
+#[test]
+fn search_range() {
+    let mut mem = Memory::new(2);
+
+    for address in [0x0000_0010, 0x0000_0020, 0x0000_0030, 0x0001_0000] {
+        mem.write_byte(address, 0xAA);
+    }
+
+    assert_eq!(
+        mem.search(0xAA, Width::Byte, 0x0000_0011..0x0000_0031),
+        [0x0000_0020, 0x0000_0030]
+    );
+    assert_eq!(
+        mem.search(0xAA, Width::Byte, 0x0000_0030..0x0001_0000),
+        [0x0000_0030]
+    );
+    assert!(mem
+        .search(0xAA, Width::Byte, 0x0000_0031..0x0001_0000)
+        .is_empty());
+}
+
+#[test]
+fn search_page_boundary() {
+    let mut mem = Memory::new(3);
+
+    // Only the second page is allocated
+    mem.write_byte(0x0001_0000, 0x12);
+    mem.write_word(0x0001_FFFE, 0xDEAD_BEEF);
+
+    // Values starting in an unallocated page match if they end in an allocated one
+    assert_eq!(
+        mem.search(0x0000_0012, Width::Word, 0..Word::MAX),
+        [0x0000_FFFD]
+    );
+
+    // Values may span two pages, whether or not the second was allocated
+    assert_eq!(
+        mem.search(0xDEAD_BEEF, Width::Word, 0..Word::MAX),
+        [0x0001_FFFE]
+    );
+    assert_eq!(
+        mem.search(0xADBE, Width::Short, 0..Word::MAX),
+        [0x0001_FFFF]
+    );
+
+    // The zeroes of unallocated pages are never matched
+    assert!(mem
+        .search(0, Width::Byte, 0..Word::MAX)
+        .iter()
+        .all(|&address| (0x0001_0000..0x0003_0000).contains(&address)));
+    assert_eq!(mem.search(0, Width::Word, 0..0x0000_FFFD), [0; 0]);
+}
+
+#[test]
+fn search_ignores_wrapping_values() {
+    let mut mem = Memory::new(1);
+
+    mem.write_byte(0x0000_FFFF, 0x01);
+    mem.write_byte(0x0000_0000, 0x02);
+
+    assert!(mem.search(0x0102, Width::Short, 0..Word::MAX).is_empty());
+}
+
+#[test]
+fn checksum_pages() {
+    let mut left = Memory::new(4);
+    let mut right = Memory::new(4);
+
+    assert_eq!(left.checksum_all().len(), 4);
+
+    // An allocated page of zeroes matches an unallocated page
+    right.write_byte(0x0002_0000, 0);
+    assert_eq!(left.checksum_all(), right.checksum_all());
+
+    left.write_word(0x0001_0010, 0xDEAD_BEEF);
+    let checksums = left.checksum_all();
+    let others = right.checksum_all();
+    assert_eq!(checksums[0], others[0]);
+    assert_ne!(checksums[1], others[1]);
+
+    right.write_word(0x0001_0010, 0xDEAD_BEEF);
+    assert_eq!(left.checksum_all(), right.checksum_all());
+}
+
+/// Sets up a memory with allocated pages around a run of unallocated ones
+fn sparse_memory() -> Memory {
+    let mut mem = Memory::new(6);
+    mem.write_word(0x0000_0010, 0x0102_0304);
+    mem.write_word(0x0003_FFFC, 0x0506_0708);
+    mem
+}
+
+#[test]
+fn write_json_matches_serialize() {
+    let mem = sparse_memory();
+
+    let mut streamed = vec![];
+    mem.write_json(&mut streamed).unwrap();
+
+    assert_eq!(streamed, serde_json::to_vec(&mem).unwrap());
+
+    let read: Memory = serde_json::from_slice(&streamed).unwrap();
+    assert!(read.pages().eq(mem.pages()));
+}
+
+#[test]
+fn write_bytes_round_trip() {
+    let mem = sparse_memory();
+
+    let mut streamed = vec![];
+    mem.write_bytes(&mut streamed).unwrap();
+
+    // The page count, two allocated pages, and the runs of unallocated pages between
+    // and after them
+    assert_eq!(streamed.len(), 4 + 2 * (1 + 0x1_0000) + 2 * (1 + 4));
+
+    let read = Memory::read_bytes(streamed.as_slice()).unwrap();
+    assert!(read.pages().eq(mem.pages()));
+    assert_eq!(read.read_word(0x0003_FFFC), 0x0506_0708);
+}
+
+#[test]
+fn filled_memory_round_trip() {
+    let mut mem = Memory::new_filled(4, 0x1234);
+    mem.write_word(0x0001_0010, 0xDEAD_BEEF);
+
+    let mut streamed = vec![];
+    mem.write_bytes(&mut streamed).unwrap();
+    let read = Memory::read_bytes(streamed.as_slice()).unwrap();
+
+    assert_eq!(read.fill_seed(), Some(0x1234));
+    assert!(read.pages().eq(mem.pages()));
+    assert_eq!(read.read_word(0x0003_0000), mem.read_word(0x0003_0000));
+
+    let mut streamed = vec![];
+    mem.write_json(&mut streamed).unwrap();
+    assert_eq!(streamed, serde_json::to_vec(&mem).unwrap());
+
+    let read: Memory = serde_json::from_slice(&streamed).unwrap();
+    assert_eq!(read.fill_seed(), Some(0x1234));
+    assert!(read.pages().eq(mem.pages()));
+    assert_eq!(read.read_word(0x0003_0000), mem.read_word(0x0003_0000));
+}
+
+#[test]
+fn read_bytes_rejects_invalid_data() {
+    let mut streamed = vec![];
+    sparse_memory().write_bytes(&mut streamed).unwrap();
+
+    assert!(Memory::read_bytes(&streamed[..streamed.len() - 1]).is_err());
+
+    // A run of unallocated pages past the end of memory
+    let overrun = [0, 0, 0, 1, 0, 0, 0, 0, 2];
+    assert!(Memory::read_bytes(&overrun[..]).is_err());
+
+    assert!(Memory::read_bytes(&[0, 0, 0, 1, 7][..]).is_err());
+}
+
+#[test]
+fn oversized_page_counts_are_rejected() {
+    let count = (MAX_PAGES as Word + 1).to_be_bytes();
+    let error = Memory::read_bytes(&count[..]).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    let error = Memory::read_bytes(&Word::MAX.to_be_bytes()[..]).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    let pages = format!("[{}]", vec!["null"; MAX_PAGES + 1].join(","));
+    assert!(serde_json::from_str::<Memory>(&pages).is_err());
+}
+
+#[test]
+fn allocation_bitmap() {
+    let mem = sparse_memory();
+
+    assert_eq!(
+        mem.allocation_bitmap(),
+        [true, false, false, true, false, false]
+    );
+    assert_eq!(Memory::new(2).allocation_bitmap(), [false, false]);
+}
+
+#[test]
+fn zero_range() {
+    let mut mem = sparse_memory();
+    mem.write_word(0x0004_0000, 0x090A_0B0C);
+
+    mem.zero_range(0x0003_FFFE..0x0004_0002);
+
+    assert_eq!(mem.read_word(0x0003_FFFC), 0x0506_0000);
+    assert_eq!(mem.read_word(0x0004_0000), 0x0000_0B0C);
+    assert_eq!(mem.read_word(0x0000_0010), 0x0102_0304);
+
+    // Zeroing never allocates or frees pages
+    mem.zero_range(0x0000_0000..Word::MAX);
+    assert_eq!(
+        mem.allocation_bitmap(),
+        [true, false, false, true, true, false]
+    );
+    assert!((0..0x0006_0000).all(|address| mem.read_byte(address) == 0));
+}
+
+#[test]
+fn free_pages_in() {
+    let mut mem = sparse_memory();
+    mem.write_word(0x0004_0000, 0x090A_0B0C);
+
+    // Covers all of page 3, but only the start of page 4
+    mem.free_pages_in(0x0003_0000..0x0004_0002);
+
+    assert_eq!(
+        mem.allocation_bitmap(),
+        [true, false, false, false, true, false]
+    );
+    assert_eq!(mem.read_word(0x0003_FFFC), 0);
+    assert_eq!(mem.read_word(0x0004_0000), 0x090A_0B0C);
+
+    // Page 0 is only partially covered
+    mem.free_pages_in(0x0000_0001..0x0001_0000);
+    assert_eq!(mem.read_word(0x0000_0010), 0x0102_0304);
+
+    mem.free_pages_in(0x0000_0000..Word::MAX);
+    assert!(mem.allocation_bitmap().iter().all(|&allocated| !allocated));
+}
+
+#[test]
+fn take_dirty() {
+    let mut mem = Memory::new(5);
+    assert!(mem.take_dirty().is_empty());
+
+    mem.write_byte(0x0000_0010, 1);
+    mem.write_short(0x0000_8000, 2);
+    mem.write_word(0x0002_C000, 3);
+    // Crosses from region 1 into region 2 within page 0
+    mem.write_word(0x0000_7FFE, 4);
+    // Crosses from page 1 into page 2
+    mem.write_short(0x0001_FFFF, 5);
+    // Crosses from the last page back to the first
+    mem.write_word(0x0004_FFFE, 6);
+
+    assert_eq!(mem.take_dirty(), [0, 1, 2, 7, 8, 11, 19]);
+    assert!(mem.take_dirty().is_empty());
+
+    mem.set_page(0x0001_0000, &[1, 2, 3]);
+    assert_eq!(mem.take_dirty(), [4, 5, 6, 7]);
+
+    mem.zero_range(0x0000_3FFF..0x0000_4001);
+    assert_eq!(mem.take_dirty(), [0, 1]);
+
+    // Unallocated pages are already zero, so nothing changes
+    mem.zero_range(0x0003_0000..0x0003_1000);
+    assert!(mem.take_dirty().is_empty());
+}
+
+#[test]
+fn read_byte_checked() {
+    let mut mem = Memory::new_poisoned(2);
+    assert!(mem.is_poisoned());
+
+    assert_eq!(mem.read_byte_checked(0x0000_0010), (0, false));
+
+    mem.write_byte(0x0000_0010, 0);
+    assert_eq!(mem.read_byte_checked(0x0000_0010), (0, true));
+    // The rest of the page is allocated, but still never written
+    assert_eq!(mem.read_byte_checked(0x0000_0011), (0, false));
+
+    // Crosses from the last page back to the first
+    mem.write_word(0x0001_FFFE, 0x0102_0304);
+    assert_eq!(mem.read_byte_checked(0x0001_FFFD), (0, false));
+    assert_eq!(mem.read_byte_checked(0x0001_FFFE), (1, true));
+    assert_eq!(mem.read_byte_checked(0x0000_0001), (4, true));
+    assert_eq!(mem.read_byte_checked(0x0000_0002), (0, false));
+
+    mem.free_pages_in(0x0001_0000..0x0002_0000);
+    assert_eq!(mem.read_byte_checked(0x0001_FFFE), (0, false));
+
+    // Without poisoning, every byte counts as written
+    let mem = Memory::new(2);
+    assert!(!mem.is_poisoned());
+    assert_eq!(mem.read_byte_checked(0x0000_0010), (0, true));
+}
+
+#[test]
+fn filled_memory_is_deterministic() {
+    let mut first = Memory::new_filled(3, 0x5EED);
+    let second = Memory::new_filled(3, 0x5EED);
+    let other = Memory::new_filled(3, 0x0BAD_5EED);
+
+    // Covers the start, end, and page-crossing reads of each page
+    for address in [
+        0x0000_0000,
+        0x0000_0FF1,
+        0x0000_FFFE,
+        0x0001_FFFF,
+        0x0002_FFFD,
+    ] {
+        assert_eq!(first.read_byte(address), second.read_byte(address));
+        assert_eq!(first.read_short(address), second.read_short(address));
+        assert_eq!(first.read_word(address), second.read_word(address));
+    }
+    assert!((0..0x0003_0000).all(|a| first.read_byte(a) == second.read_byte(a)));
+    assert_ne!(first.read_words(0, 64), other.read_words(0, 64));
+    assert!((0..0x100).any(|address| first.read_byte(address) != 0));
+
+    // Allocating a page keeps the values it read as beforehand
+    let before = first.read_words(0x0001_0000, 0x100);
+    first.write_byte(0x0001_0080, 0xAA);
+    let after = first.read_words(0x0001_0000, 0x100);
+    assert_eq!(after[0x80], 0xAA);
+    assert_eq!(before[..0x80], after[..0x80]);
+    assert_eq!(before[0x81..], after[0x81..]);
+    assert_eq!(first.read_word(0x0001_FFFE), second.read_word(0x0001_FFFE));
+
+    // Zeroing allocates the page, since it no longer reads as zero otherwise
+    first.zero_range(0x0002_0000..0x0002_0010);
+    assert_eq!(first.read_word(0x0002_000C), 0);
+    assert_eq!(first.read_byte(0x0002_0010), second.read_byte(0x0002_0010));
+
+    first.free_pages_in(0x0000_0000..Word::MAX);
+    assert_eq!(first.checksum_all(), second.checksum_all());
+}
@@ -0,0 +1,120 @@
+use libmem::{
+    cache::{Cache, LineReadStatus, MultiAssociative, Status},
+    memory::Memory,
+};
+use libseis::types::Word;
+
+/// 16-byte lines in four sets, so addresses 64 bytes apart share a set
+const OFF_BITS: usize = 4;
+const SET_BITS: usize = 2;
+const SET_STRIDE: Word = 1 << (OFF_BITS + SET_BITS);
+
+/// Returns the base address of the `n`th distinct line mapping to `set`
+fn line(set: Word, n: Word) -> Word {
+    (set << OFF_BITS) + n * SET_STRIDE
+}
+
+#[test]
+fn odd_ways_hold_one_line_per_way() {
+    for ways in [3, 5, 6] {
+        let mut cache = MultiAssociative::new(OFF_BITS, SET_BITS, ways);
+        let mut memory = Memory::new(1);
+
+        for n in 0..ways as Word {
+            assert!(matches!(
+                cache.get_line(line(0, n), &mut memory),
+                LineReadStatus::Inserted
+            ));
+        }
+
+        for n in 0..ways as Word {
+            assert!(cache.check_address(line(0, n)).is_hit(), "{ways} ways");
+        }
+
+        // The neighbouring sets are untouched
+        for set in 1..1 << SET_BITS {
+            assert!(matches!(cache.check_address(line(set, 0)), Status::Cold));
+        }
+
+        assert!(matches!(
+            cache.check_address(line(0, ways as Word)),
+            Status::Conflict
+        ));
+        assert!(matches!(
+            cache.get_line(line(0, ways as Word), &mut memory),
+            LineReadStatus::Evicted
+        ));
+        assert_eq!(cache.get_lines().len(), ways << SET_BITS);
+    }
+}
+
+#[test]
+fn three_way_evicts_least_recently_used() {
+    let mut cache = MultiAssociative::new(OFF_BITS, SET_BITS, 3);
+    let mut memory = Memory::new(1);
+
+    for n in 0..3 {
+        cache.get_line(line(1, n), &mut memory);
+    }
+
+    // Touch the oldest line, leaving the second as the least recently used
+    assert!(cache.get_byte(line(1, 0)).is_ok());
+
+    assert!(cache.get_line(line(1, 3), &mut memory).evicted());
+
+    assert!(cache.check_address(line(1, 0)).is_hit());
+    assert!(matches!(cache.check_address(line(1, 1)), Status::Conflict));
+    assert!(cache.check_address(line(1, 2)).is_hit());
+    assert!(cache.check_address(line(1, 3)).is_hit());
+}
+
+#[test]
+fn three_way_writes_back_evicted_line() {
+    let mut cache = MultiAssociative::new(OFF_BITS, SET_BITS, 3);
+    let mut memory = Memory::new(1);
+
+    cache.get_line(line(2, 0), &mut memory);
+    assert!(cache.write_word(line(2, 0), 0xDEAD_BEEF).is_hit());
+    assert_eq!(cache.dirty_lines(), 1);
+
+    for n in 1..4 {
+        cache.get_line(line(2, n), &mut memory);
+    }
+
+    assert_eq!(cache.dirty_lines(), 0);
+    assert_eq!(memory.read_word(line(2, 0)), 0xDEAD_BEEF);
+}
+
+#[test]
+fn three_way_reports_lines_by_set() {
+    let mut cache = MultiAssociative::new(OFF_BITS, SET_BITS, 3);
+    let mut memory = Memory::new(1);
+
+    cache.get_line(line(3, 2), &mut memory);
+    cache.get_line(line(1, 1), &mut memory);
+
+    let lines = cache.get_lines();
+
+    assert_eq!(lines[3].as_ref().map(|l| l.base_address), Some(line(1, 1)));
+    assert_eq!(lines[9].as_ref().map(|l| l.base_address), Some(line(3, 2)));
+    assert_eq!(lines.iter().flatten().count(), 2);
+}
+
+#[test]
+fn three_way_write_across_lines_keeps_both_lines() {
+    let mut cache = MultiAssociative::new(OFF_BITS, SET_BITS, 3);
+    let mut memory = Memory::new(1);
+
+    // The last set wraps around to the first set of the next tag
+    for address in [line(0, 0), line(1, 0), line(3, 0), line(0, 1)] {
+        cache.get_line(address, &mut memory);
+    }
+
+    for address in [line(1, 0) - 2, line(0, 1) - 2] {
+        assert!(cache.write_word(address, 0x1234_5678).is_hit());
+        assert_eq!(cache.word_at(address), Some(0x1234_5678));
+    }
+
+    assert_eq!(cache.get_lines().iter().flatten().count(), 4);
+    assert_eq!(cache.dirty_lines(), 4);
+}
@@ -0,0 +1,558 @@
+use libmem::{
+    cache::{Associative, MultiAssociative, NullCache},
+    memory::Memory,
+    module::{MemoryModule, MissKind, SingleLevel, Status},
+};
+
+fn module() -> SingleLevel {
+    let mut memory = Memory::new(1);
+    memory.write_word(0x0000_0040, 0xDEAD_BEEF);
+
+    SingleLevel::new(
+        Box::new(MultiAssociative::new(4, 2, 3)),
+        Box::new(Associative::new(4, 2)),
+        memory,
+        10,
+        2,
+        false,
+    )
+}
+
+#[test]
+fn caches_are_listed_in_order() {
+    let mut module = module();
+
+    for _ in 0..8 {
+        assert_eq!(module.get_cache_names(), ["instruction", "data"]);
+        assert_eq!(
+            module
+                .cache_state()
+                .into_iter()
+                .map(|cache| cache.name)
+                .collect::<Vec<_>>(),
+            ["instruction", "data"]
+        );
+        assert_eq!(
+            module
+                .caches_mut()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            ["instruction", "data"]
+        );
+    }
+}
+
+#[test]
+fn warm_leaves_statistics_untouched() {
+    let mut module = module();
+
+    // More lines than the data cache can hold in one set, forcing evictions
+    module.warm(&[
+        0x0000_0000,
+        0x0000_0040,
+        0x0000_0080,
+        0x0000_00C0,
+        0x0000_0100,
+    ]);
+
+    assert_eq!(module.accesses(), 0);
+    assert_eq!(module.cache_hits(), 0);
+    assert_eq!(module.total_misses(), 0);
+    assert_eq!(module.evictions(), 0);
+}
+
+#[test]
+fn warm_loads_clean_lines() {
+    let mut module = module();
+
+    module.warm(&[0x0000_0040, 0x0000_0094]);
+
+    for (_, cache) in module.caches() {
+        assert!(cache.check_address(0x0000_0040).is_hit());
+        assert!(cache.check_address(0x0000_0090).is_hit());
+        assert_eq!(cache.dirty_lines(), 0);
+    }
+
+    assert!(matches!(module.read_word(0x0000_0040), Ok(0xDEAD_BEEF)));
+    assert_eq!(module.cache_hits(), 1);
+    assert_eq!(module.total_misses(), 0);
+}
+
+/// A module whose data straddles the line boundary at `0x50`
+fn straddling_module() -> SingleLevel {
+    let mut module = module();
+    module.memory_mut().write_word(0x0000_004C, 0x0102_0304);
+    module.memory_mut().write_word(0x0000_0050, 0x0506_0708);
+    module
+}
+
+#[test]
+fn straddling_read_misses_both_lines() {
+    let mut module = straddling_module();
+
+    assert!(matches!(
+        module.read_word(0x0000_004C),
+        Err(Status::Busy(10))
+    ));
+    module.clock(10);
+    assert!(matches!(module.read_word(0x0000_004C), Ok(0x0102_0304)));
+
+    assert!(matches!(
+        module.read_word(0x0000_004E),
+        Err(Status::Busy(11))
+    ));
+    module.clock(11);
+    assert!(matches!(module.read_word(0x0000_004E), Ok(0x0304_0506)));
+
+    let mut module = straddling_module();
+
+    assert!(matches!(
+        module.read_word(0x0000_004E),
+        Err(Status::Busy(21))
+    ));
+    module.clock(21);
+    assert!(matches!(module.read_word(0x0000_004E), Ok(0x0304_0506)));
+
+    assert_eq!(module.misaligned_accesses(), 1);
+    assert_eq!(module.total_misses(), 2);
+    assert_eq!(module.cache_hits(), 0);
+    assert_eq!(module.accesses(), 2);
+}
+
+#[test]
+fn straddling_read_hits_both_lines() {
+    let mut module = straddling_module();
+    module.warm(&[0x0000_004C, 0x0000_0050]);
+
+    assert!(matches!(module.read_short(0x0000_004F), Ok(0x0405)));
+    assert!(matches!(module.read_short(0x0000_004D), Ok(0x0203)));
+
+    assert_eq!(module.misaligned_accesses(), 1);
+    assert_eq!(module.cache_hits(), 3);
+    assert_eq!(module.accesses(), 3);
+}
+
+#[test]
+fn straddling_write_misses_both_lines() {
+    let mut module = straddling_module();
+
+    assert!(matches!(
+        module.write_word(0x0000_004E, 0xAABB_CCDD),
+        Status::Idle
+    ));
+    assert_eq!(module.wait_time(), 21);
+    module.clock(21);
+
+    assert!(matches!(module.read_word(0x0000_004C), Ok(0x0102_AABB)));
+    assert!(matches!(module.read_word(0x0000_0050), Ok(0xCCDD_0708)));
+
+    assert_eq!(module.misaligned_accesses(), 1);
+    assert_eq!(module.total_misses(), 2);
+    assert_eq!(module.cache_hits(), 2);
+}
+
+#[test]
+fn ports_overlap_read_misses() {
+    let mut module = straddling_module().with_ports(2);
+
+    assert!(matches!(
+        module.read_word(0x0000_004C),
+        Err(Status::Busy(10))
+    ));
+    assert!(matches!(
+        module.read_word(0x0000_0050),
+        Err(Status::Busy(10))
+    ));
+    module.clock(10);
+
+    assert!(matches!(module.read_word(0x0000_004C), Ok(0x0102_0304)));
+    assert!(matches!(module.read_word(0x0000_0050), Ok(0x0506_0708)));
+    assert_eq!(module.total_misses(), 2);
+    assert_eq!(module.cache_hits(), 0);
+}
+
+#[test]
+fn single_port_serializes_read_misses() {
+    let mut module = straddling_module();
+
+    assert!(matches!(
+        module.read_word(0x0000_004C),
+        Err(Status::Busy(10))
+    ));
+    assert!(matches!(
+        module.read_word(0x0000_0050),
+        Err(Status::Busy(10))
+    ));
+    module.clock(10);
+
+    assert!(matches!(module.read_word(0x0000_004C), Ok(0x0102_0304)));
+    assert!(matches!(
+        module.read_word(0x0000_0050),
+        Err(Status::Busy(10))
+    ));
+}
+
+#[test]
+fn reads_wait_for_pending_writes() {
+    let mut module = straddling_module().with_ports(2);
+
+    assert!(matches!(
+        module.write_word(0x0000_0050, 0xAABB_CCDD),
+        Status::Idle
+    ));
+    assert!(matches!(
+        module.read_word(0x0000_004C),
+        Err(Status::Busy(10))
+    ));
+    module.clock(10);
+
+    assert!(matches!(module.read_word(0x0000_0050), Ok(0xAABB_CCDD)));
+}
+
+#[test]
+fn uncached_instructions_never_hit() {
+    let mut memory = Memory::new(1);
+    memory.write_word(0x0000_0040, 0xDEAD_BEEF);
+
+    let mut module = SingleLevel::new(
+        Box::new(Associative::new(4, 2)),
+        Box::new(NullCache::new()),
+        memory,
+        10,
+        2,
+        false,
+    );
+
+    for _ in 0..2 {
+        assert!(matches!(
+            module.read_instruction(0x0000_0040),
+            Err(Status::Busy(2))
+        ));
+        module.clock(2);
+        assert!(matches!(
+            module.read_instruction(0x0000_0040),
+            Ok(0xDEAD_BEEF)
+        ));
+    }
+
+    assert_eq!(module.cache_hits(), 0);
+    assert_eq!(module.total_misses(), 0);
+
+    // The data cache still counts its own accesses
+    assert!(matches!(
+        module.read_word(0x0000_0040),
+        Err(Status::Busy(10))
+    ));
+    module.clock(10);
+    assert!(matches!(module.read_word(0x0000_0040), Ok(0xDEAD_BEEF)));
+    assert!(matches!(module.read_word(0x0000_0040), Ok(0xDEAD_BEEF)));
+
+    assert_eq!(module.cache_hits(), 1);
+    assert_eq!(module.total_misses(), 1);
+}
+
+#[test]
+fn same_bank_accesses_conflict() {
+    let mut module = module().with_banks(2);
+    module.warm(&[0x0000_0040, 0x0000_0050]);
+
+    assert!(matches!(module.read_word(0x0000_0040), Ok(0xDEAD_BEEF)));
+    assert!(matches!(
+        module.read_instruction(0x0000_0040),
+        Err(Status::Busy(1))
+    ));
+    assert_eq!(module.bank_conflicts(), 1);
+
+    // The next line is in the other bank
+    assert!(module.read_instruction(0x0000_0050).is_ok());
+
+    module.clock(1);
+    assert!(matches!(
+        module.read_instruction(0x0000_0040),
+        Ok(0xDEAD_BEEF)
+    ));
+    assert_eq!(module.bank_conflicts(), 1);
+    assert_eq!(module.cache_hits(), 3);
+}
+
+#[test]
+fn bank_conflicts_leave_the_cache_unchanged() {
+    let mut module = module().with_banks(2);
+    module.warm(&[0x0000_0040]);
+
+    assert!(module.read_instruction(0x0000_0040).is_ok());
+    assert!(matches!(
+        module.write_word(0x0000_0040, 0x1234_5678),
+        Status::Busy(1)
+    ));
+
+    // The write waited without reaching the cache
+    module.clock(1);
+    assert!(matches!(module.read_word(0x0000_0040), Ok(0xDEAD_BEEF)));
+
+    assert!(matches!(
+        module.write_word(0x0000_0040, 0x1234_5678),
+        Status::Idle
+    ));
+    assert!(matches!(module.read_word(0x0000_0040), Ok(0x1234_5678)));
+    assert_eq!(module.bank_conflicts(), 1);
+    assert_eq!(module.cache_hits(), 4);
+}
+
+#[test]
+fn banks_are_shared_by_both_caches() {
+    // Instruction lines are 32 bytes long, and data lines 16
+    let mut module = SingleLevel::new(
+        Box::new(Associative::new(4, 2)),
+        Box::new(Associative::new(5, 2)),
+        Memory::new(1),
+        10,
+        2,
+        false,
+    )
+    .with_banks(2);
+    module.warm(&[0x0000_0040, 0x0000_0050, 0x0000_0060]);
+
+    // Both halves of an instruction line are in the same bank
+    assert!(module.read_word(0x0000_0050).is_ok());
+    assert!(matches!(
+        module.read_instruction(0x0000_0040),
+        Err(Status::Busy(1))
+    ));
+    assert!(module.read_instruction(0x0000_0060).is_ok());
+    assert_eq!(module.bank_conflicts(), 1);
+}
+
+#[test]
+fn unlimited_banks_never_conflict() {
+    let mut module = module();
+    module.warm(&[0x0000_0040]);
+
+    assert!(matches!(module.read_word(0x0000_0040), Ok(0xDEAD_BEEF)));
+    assert!(matches!(
+        module.read_instruction(0x0000_0040),
+        Ok(0xDEAD_BEEF)
+    ));
+    assert_eq!(module.bank_conflicts(), 0);
+}
+
+/// Reads a word through the module, waiting out any misses, and demotes its line when
+/// the read is non-temporal
+fn stream_read(module: &mut SingleLevel, address: u32, non_temporal: bool) {
+    loop {
+        match module.read_word(address) {
+            Ok(_) => break,
+            Err(Status::Busy(clocks)) => module.clock(clocks),
+            Err(Status::Idle) => unreachable!(),
+        }
+    }
+
+    if non_temporal {
+        module.demote(address);
+    }
+}
+
+#[test]
+fn non_temporal_stream_keeps_hot_line() {
+    for non_temporal in [false, true] {
+        let mut module = module();
+        stream_read(&mut module, 0x0000_0040, false);
+
+        // Every line in the stream shares a set with the hot line
+        for n in 2..64 {
+            stream_read(&mut module, n * 0x40, non_temporal);
+        }
+
+        let hot = module
+            .get_cache("data")
+            .unwrap()
+            .check_address(0x0000_0040)
+            .is_hit();
+        assert_eq!(hot, non_temporal);
+    }
+}
+
+/// Writes a word through the module, demoting its line when the write is non-temporal,
+/// then waits out the write
+fn stream_write(module: &mut SingleLevel, address: u32, non_temporal: bool) {
+    loop {
+        match module.write_word(address, 0) {
+            Status::Idle => break,
+            Status::Busy(clocks) => module.clock(clocks),
+        }
+    }
+
+    if non_temporal {
+        module.demote(address);
+    }
+
+    module.clock(module.wait_time());
+}
+
+#[test]
+fn non_temporal_store_stream_keeps_hot_line() {
+    for non_temporal in [false, true] {
+        let mut module = module();
+        stream_read(&mut module, 0x0000_0040, false);
+
+        // Every line in the stream shares a set with the hot line, and misses
+        for n in 2..64 {
+            stream_write(&mut module, n * 0x40, non_temporal);
+        }
+
+        let hot = module
+            .get_cache("data")
+            .unwrap()
+            .check_address(0x0000_0040)
+            .is_hit();
+        assert_eq!(hot, non_temporal);
+    }
+}
+
+/// Dirties every line of the data cache, then reads twice as many new lines two at a
+/// time, so each pair of fills evicts two dirty lines at once
+///
+/// Returns the clocks the reads took.
+fn evict_dirty_lines(module: &mut SingleLevel) -> usize {
+    let line = |set: u32, n: u32| set * 0x10 + n * 0x40;
+
+    for set in 0..4 {
+        for n in 0..3 {
+            while let Status::Busy(clocks) = module.write_word(line(set, n), 1) {
+                module.clock(clocks);
+            }
+        }
+    }
+    module.clock(module.wait_time());
+    assert_eq!(module.data_cache().dirty_lines(), 12);
+
+    let mut clocks = 0;
+    for n in 3..6 {
+        for sets in [[0, 1], [2, 3]] {
+            // Both reads are started before either is waited on
+            while sets
+                .map(|set| module.read_word(line(set, n)).is_ok())
+                .contains(&false)
+            {
+                module.clock(1);
+                clocks += 1;
+            }
+        }
+    }
+
+    assert_eq!(module.data_cache().dirty_lines(), 0);
+    clocks
+}
+
+#[test]
+fn full_write_buffer_stalls() {
+    let mut unbounded = module().with_ports(2);
+    let unbounded_clocks = evict_dirty_lines(&mut unbounded);
+    assert_eq!(unbounded.write_buffer_stalls(), 0);
+
+    // Lines drain one at a time, slower than two ports evict them
+    let mut bounded = module().with_ports(2).with_write_buffer(2);
+    let bounded_clocks = evict_dirty_lines(&mut bounded);
+    assert!(bounded.write_buffer_stalls() > 0);
+    assert!(bounded_clocks > unbounded_clocks);
+}
+
+#[test]
+fn writes_invalidate_instruction_lines() {
+    for volatile in [false, true] {
+        let mut module = module();
+        module.warm(&[0x0000_0040]);
+
+        let status = if volatile {
+            module.write_word_volatile(0x0000_0040, 0x0102_0304)
+        } else {
+            module.write_word(0x0000_0040, 0x0102_0304)
+        };
+        if let Status::Busy(clocks) = status {
+            module.clock(clocks);
+        }
+        module.clock(module.wait_time());
+
+        assert!(module
+            .get_cache("instruction")
+            .unwrap()
+            .check_address(0x0000_0040)
+            .is_miss());
+
+        while let Err(Status::Busy(clocks)) = module.read_instruction(0x0000_0040) {
+            module.clock(clocks);
+        }
+        assert!(matches!(
+            module.read_instruction(0x0000_0040),
+            Ok(0x0102_0304)
+        ));
+    }
+}
+
+#[test]
+fn miss_log_records_thrashing() {
+    // Four lines sharing a set of three ways, read round-robin, so every read misses
+    let lines = [0x0000_0000, 0x0000_0040, 0x0000_0080, 0x0000_00C0];
+
+    let read_all = |mut module: SingleLevel| {
+        for &address in lines.iter().cycle().take(8) {
+            stream_read(&mut module, address, false);
+        }
+        module
+    };
+
+    assert!(read_all(module()).miss_log().is_none());
+
+    let logged = read_all(module().with_miss_log(16));
+
+    let expected: Vec<_> = lines
+        .iter()
+        .cycle()
+        .take(8)
+        .enumerate()
+        .map(|(n, &address)| {
+            let kind = if n < 3 {
+                MissKind::Cold
+            } else {
+                MissKind::Conflict
+            };
+            (address, kind, "data")
+        })
+        .collect();
+    assert_eq!(logged.total_misses(), 8);
+    assert!(logged.miss_log().unwrap().iter().eq(&expected));
+
+    // Only the most recent misses are kept
+    let logged = read_all(module().with_miss_log(2));
+    assert!(logged.miss_log().unwrap().iter().eq(&expected[6..]));
+}
+
+#[test]
+fn instruction_fills_copy_only_dirty_data() {
+    let mut module = module();
+    module.memory_mut().take_dirty();
+
+    // A clean data line already matches memory, so nothing is copied
+    while let Err(Status::Busy(clocks)) = module.read_word(0x0000_0040) {
+        module.clock(clocks);
+    }
+    while let Err(Status::Busy(clocks)) = module.read_instruction(0x0000_0040) {
+        module.clock(clocks);
+    }
+    assert!(module.memory_mut().take_dirty().is_empty());
+
+    if let Status::Busy(clocks) = module.write_word(0x0000_0044, 0x0102_0304) {
+        module.clock(clocks);
+    }
+    module.clock(module.wait_time());
+
+    while let Err(Status::Busy(clocks)) = module.read_instruction(0x0000_0044) {
+        module.clock(clocks);
+    }
+    assert!(matches!(
+        module.read_instruction(0x0000_0044),
+        Ok(0x0102_0304)
+    ));
+    assert!(!module.memory_mut().take_dirty().is_empty());
+}
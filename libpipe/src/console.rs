@@ -0,0 +1,256 @@
+//! A console mapped into memory, letting programs perform input and output with plain
+//! loads and stores instead of [system calls](libseis::instruction_set::ControlOp::Syscall).
+//!
+//! The console occupies a region of the address space, in which accesses are sent to
+//! a [`Host`] instead of the memory module it wraps:
+//!
+//! - Storing to the first word of the region writes its least significant byte to the
+//!   output.
+//! - Loading from the second word reads a byte from the input, or all ones (such as
+//!   [`syscall::FAILED`] for a word) once the input has ended.
+//!
+//! Every other access within the region reads zero and writes nothing. Accesses to the
+//! console complete at once, without touching the caches.
+
+use crate::Host;
+use libmem::{
+    cache::Cache,
+    memory::Memory,
+    module::{MemoryModule, MissLog, Result, Status},
+};
+use libseis::{
+    instruction_set::control::syscall,
+    types::{Byte, Short, Word},
+};
+
+/// A [`MemoryModule`] which maps a console into part of the address space of another
+#[derive(Debug)]
+pub struct Console {
+    inner: Box<dyn MemoryModule + Send + Sync>,
+    host: Option<Box<dyn Host + Send + Sync>>,
+    base: Word,
+    size: Word,
+}
+
+impl Console {
+    /// The offset of the output register from the start of the region
+    pub const OUTPUT: Word = 0;
+    /// The offset of the input register from the start of the region
+    pub const INPUT: Word = 4;
+
+    /// Maps a console into the `size` bytes of `inner` starting at `base`
+    ///
+    /// Until a host is set, output is discarded and the input has always ended.
+    pub fn new(inner: Box<dyn MemoryModule + Send + Sync>, base: Word, size: Word) -> Self {
+        Self {
+            inner,
+            host: None,
+            base,
+            size,
+        }
+    }
+
+    /// Sets the host which performs the input and output of the console
+    pub fn with_host(self, host: Box<dyn Host + Send + Sync>) -> Self {
+        Self {
+            host: Some(host),
+            ..self
+        }
+    }
+
+    /// Gets the offset of `addr` into the console, if it falls within it
+    fn offset(&self, addr: Word) -> Option<Word> {
+        Some(addr.wrapping_sub(self.base)).filter(|&offset| offset < self.size)
+    }
+
+    /// Reads from the console, where `ended` is the value read once the input has ended
+    fn read(&mut self, offset: Word, ended: Word) -> Word {
+        match offset {
+            Self::INPUT => self
+                .host
+                .as_mut()
+                .and_then(|host| host.read())
+                .map_or(ended, Word::from),
+            _ => 0,
+        }
+    }
+
+    /// Writes to the console
+    fn write(&mut self, offset: Word, value: Word) -> Status {
+        if let (Self::OUTPUT, Some(host)) = (offset, &mut self.host) {
+            host.write(value as Byte);
+        }
+
+        Status::Idle
+    }
+}
+
+impl MemoryModule for Console {
+    fn clock(&mut self, amount: usize) {
+        self.inner.clock(amount)
+    }
+
+    fn wait_time(&self) -> usize {
+        self.inner.wait_time()
+    }
+
+    fn read_byte(&mut self, addr: Word) -> Result<Byte> {
+        match self.offset(addr) {
+            Some(offset) => Ok(self.read(offset, Byte::MAX as Word) as Byte),
+            None => self.inner.read_byte(addr),
+        }
+    }
+
+    fn read_short(&mut self, addr: Word) -> Result<Short> {
+        match self.offset(addr) {
+            Some(offset) => Ok(self.read(offset, Short::MAX as Word) as Short),
+            None => self.inner.read_short(addr),
+        }
+    }
+
+    fn read_word(&mut self, addr: Word) -> Result<Word> {
+        match self.offset(addr) {
+            Some(offset) => Ok(self.read(offset, syscall::FAILED)),
+            None => self.inner.read_word(addr),
+        }
+    }
+
+    fn read_byte_volatile(&mut self, addr: Word) -> Result<Byte> {
+        match self.offset(addr) {
+            Some(offset) => Ok(self.read(offset, Byte::MAX as Word) as Byte),
+            None => self.inner.read_byte_volatile(addr),
+        }
+    }
+
+    fn read_short_volatile(&mut self, addr: Word) -> Result<Short> {
+        match self.offset(addr) {
+            Some(offset) => Ok(self.read(offset, Short::MAX as Word) as Short),
+            None => self.inner.read_short_volatile(addr),
+        }
+    }
+
+    fn read_word_volatile(&mut self, addr: Word) -> Result<Word> {
+        match self.offset(addr) {
+            Some(offset) => Ok(self.read(offset, syscall::FAILED)),
+            None => self.inner.read_word_volatile(addr),
+        }
+    }
+
+    fn read_instruction(&mut self, addr: Word) -> Result<Word> {
+        self.inner.read_instruction(addr)
+    }
+
+    fn write_byte(&mut self, addr: Word, value: Byte) -> Status {
+        match self.offset(addr) {
+            Some(offset) => self.write(offset, value as Word),
+            None => self.inner.write_byte(addr, value),
+        }
+    }
+
+    fn write_short(&mut self, addr: Word, value: Short) -> Status {
+        match self.offset(addr) {
+            Some(offset) => self.write(offset, value as Word),
+            None => self.inner.write_short(addr, value),
+        }
+    }
+
+    fn write_word(&mut self, addr: Word, value: Word) -> Status {
+        match self.offset(addr) {
+            Some(offset) => self.write(offset, value),
+            None => self.inner.write_word(addr, value),
+        }
+    }
+
+    fn write_byte_volatile(&mut self, addr: Word, value: Byte) -> Status {
+        match self.offset(addr) {
+            Some(offset) => self.write(offset, value as Word),
+            None => self.inner.write_byte_volatile(addr, value),
+        }
+    }
+
+    fn write_short_volatile(&mut self, addr: Word, value: Short) -> Status {
+        match self.offset(addr) {
+            Some(offset) => self.write(offset, value as Word),
+            None => self.inner.write_short_volatile(addr, value),
+        }
+    }
+
+    fn write_word_volatile(&mut self, addr: Word, value: Word) -> Status {
+        match self.offset(addr) {
+            Some(offset) => self.write(offset, value),
+            None => self.inner.write_word_volatile(addr, value),
+        }
+    }
+
+    fn demote(&mut self, addr: Word) {
+        self.inner.demote(addr)
+    }
+
+    fn cold_misses(&self) -> usize {
+        self.inner.cold_misses()
+    }
+
+    fn conflict_misses(&self) -> usize {
+        self.inner.conflict_misses()
+    }
+
+    fn cache_hits(&self) -> usize {
+        self.inner.cache_hits()
+    }
+
+    fn accesses(&self) -> usize {
+        self.inner.accesses()
+    }
+
+    fn evictions(&self) -> usize {
+        self.inner.evictions()
+    }
+
+    fn misaligned_accesses(&self) -> usize {
+        self.inner.misaligned_accesses()
+    }
+
+    fn bank_conflicts(&self) -> usize {
+        self.inner.bank_conflicts()
+    }
+
+    fn write_buffer_stalls(&self) -> usize {
+        self.inner.write_buffer_stalls()
+    }
+
+    fn miss_log(&self) -> Option<&MissLog> {
+        self.inner.miss_log()
+    }
+
+    fn memory(&self) -> &Memory {
+        self.inner.memory()
+    }
+
+    fn memory_mut(&mut self) -> &mut Memory {
+        self.inner.memory_mut()
+    }
+
+    fn caches(&self) -> Vec<(&str, &dyn Cache)> {
+        self.inner.caches()
+    }
+
+    fn caches_mut(&mut self) -> Vec<(&str, &mut dyn Cache)> {
+        self.inner.caches_mut()
+    }
+
+    fn immediate_writeback(&mut self) -> Status {
+        self.inner.immediate_writeback()
+    }
+
+    fn data_cache(&self) -> &dyn Cache {
+        self.inner.data_cache()
+    }
+
+    fn flush_cache(&mut self) -> Status {
+        self.inner.flush_cache()
+    }
+
+    fn warm(&mut self, addresses: &[Word]) {
+        self.inner.warm(addresses)
+    }
+}
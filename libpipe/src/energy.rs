@@ -0,0 +1,59 @@
+//! A rough model of the energy used by a program, which charges a cost for each
+//! instruction written back and each cache access of the memory module.
+
+use libmem::module::MemoryModule;
+use libseis::instruction_set::Instruction;
+
+/// The energy charged for each kind of event, in whatever unit the costs are given in
+///
+/// Every cost is zero by default, so nothing is charged unless set otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EnergyCosts {
+    /// Each control instruction written back
+    pub control: f64,
+    /// Each integer instruction written back
+    pub integer: f64,
+    /// Each floating-point instruction written back
+    pub floating_point: f64,
+    /// Each register instruction written back, which includes loads and stores
+    pub register: f64,
+    /// Each access of a cache line which hit
+    pub hit: f64,
+    /// Each access of a cache line which missed
+    pub miss: f64,
+    /// Each cache line evicted
+    pub eviction: f64,
+}
+
+impl EnergyCosts {
+    /// Whether any instruction costs energy, so that the instructions written back have
+    /// to be tracked
+    pub(crate) fn charges_instructions(&self) -> bool {
+        [
+            self.control,
+            self.integer,
+            self.floating_point,
+            self.register,
+        ]
+        .iter()
+        .any(|&cost| cost != 0.0)
+    }
+
+    /// Gets the energy charged for writing back an instruction
+    pub fn instruction(&self, instruction: Instruction) -> f64 {
+        match instruction {
+            Instruction::Control(_) => self.control,
+            Instruction::Integer(_) => self.integer,
+            Instruction::FloatingPoint(_) => self.floating_point,
+            Instruction::Register(_) => self.register,
+        }
+    }
+
+    /// Gets the energy charged for the cache accesses and evictions counted by a memory
+    /// module so far
+    pub fn memory(&self, module: &dyn MemoryModule) -> f64 {
+        module.cache_hits() as f64 * self.hit
+            + module.total_misses() as f64 * self.miss
+            + module.evictions() as f64 * self.eviction
+    }
+}
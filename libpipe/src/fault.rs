@@ -0,0 +1,168 @@
+//! Recoverable faults raised by the pipeline.
+//!
+//! A fault stops the processor in the same manner as a [`Halt`](libseis::instruction_set::control::ControlOp::Halt),
+//! but records why execution could not continue so the simulator may report it. With a
+//! [vector table](crate::PipelineOptions::vector_table), some faults instead call a handler.
+
+use libseis::{
+    registers::get_name,
+    types::{Register, Word},
+};
+use serde::Serialize;
+use std::fmt::Display;
+
+/// Represents a condition which caused the pipeline to stop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The stack pointer would have grown past the end of the stack page
+    StackOverflow {
+        /// The stack pointer seen by the faulting operation, as an address in the stack page
+        sp: Word,
+    },
+    /// The stack pointer would have shrunk past the start of the stack page
+    StackUnderflow {
+        /// The stack pointer seen by the faulting operation, as an address in the stack page
+        sp: Word,
+    },
+    /// A store would have written to a read-only segment
+    WriteProtected {
+        /// The address of the store
+        address: Word,
+    },
+    /// An instruction would have been fetched from an address which is not word-aligned
+    MisalignedFetch {
+        /// The value of the program counter when the fault was raised
+        pc: Word,
+    },
+    /// An instruction would have accessed a variable register which is not
+    /// [enabled](crate::PipelineOptions::registers)
+    DisabledRegister {
+        /// The lowest disabled register the instruction accesses
+        register: Register,
+        /// The address of the instruction
+        pc: Word,
+    },
+    /// A word which does not decode to any instruction would have been executed
+    IllegalInstruction {
+        /// The word which could not be decoded
+        word: Word,
+        /// The address the word was fetched from
+        pc: Word,
+    },
+}
+
+impl Fault {
+    /// The index of this fault's entry in the [vector table](crate::PipelineOptions::vector_table)
+    pub fn vector(self) -> Word {
+        match self {
+            Fault::StackOverflow { .. } => 0,
+            Fault::StackUnderflow { .. } => 1,
+            Fault::WriteProtected { .. } => 2,
+            Fault::MisalignedFetch { .. } => 3,
+            Fault::DisabledRegister { .. } => 4,
+            Fault::IllegalInstruction { .. } => 5,
+        }
+    }
+}
+
+impl Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fault::StackOverflow { sp } => write!(f, "stack overflow (SP = {sp:#010X})"),
+            Fault::StackUnderflow { sp } => write!(f, "stack underflow (SP = {sp:#010X})"),
+            Fault::WriteProtected { address } => {
+                write!(f, "write to read-only memory at {address:#010X}")
+            }
+            Fault::MisalignedFetch { pc } => {
+                write!(f, "misaligned instruction fetch (PC = {pc:#010X})")
+            }
+            Fault::DisabledRegister { register, pc } => write!(
+                f,
+                "access to disabled register {} (PC = {pc:#010X})",
+                get_name(*register).unwrap_or("<unknown>")
+            ),
+            Fault::IllegalInstruction { word, pc } => {
+                write!(f, "illegal instruction {word:#010X} (PC = {pc:#010X})")
+            }
+        }
+    }
+}
+
+impl Serialize for Fault {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(2))?;
+
+        match self {
+            Fault::StackOverflow { sp } => {
+                map.serialize_entry("fault", "stack_overflow")?;
+                map.serialize_entry("sp", sp)?;
+            }
+            Fault::StackUnderflow { sp } => {
+                map.serialize_entry("fault", "stack_underflow")?;
+                map.serialize_entry("sp", sp)?;
+            }
+            Fault::WriteProtected { address } => {
+                map.serialize_entry("fault", "write_protected")?;
+                map.serialize_entry("address", address)?;
+            }
+            Fault::MisalignedFetch { pc } => {
+                map.serialize_entry("fault", "misaligned_fetch")?;
+                map.serialize_entry("pc", pc)?;
+            }
+            Fault::DisabledRegister { register, pc } => {
+                map.serialize_entry("fault", "disabled_register")?;
+                map.serialize_entry("register", &get_name(*register))?;
+                map.serialize_entry("pc", pc)?;
+            }
+            Fault::IllegalInstruction { word, pc } => {
+                map.serialize_entry("fault", "illegal_instruction")?;
+                map.serialize_entry("word", word)?;
+                map.serialize_entry("pc", pc)?;
+            }
+        }
+
+        map.end()
+    }
+}
+
+/// The reason a pipeline stopped once it ran [dry](crate::ClockResult::Dry)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HaltReason {
+    /// A [`Halt`](libseis::instruction_set::control::ControlOp::Halt) was executed
+    Halt,
+    /// A fault stopped the pipeline
+    Fault(Fault),
+}
+
+impl Display for HaltReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HaltReason::Halt => write!(f, "halt"),
+            HaltReason::Fault(fault) => write!(f, "fault: {fault}"),
+        }
+    }
+}
+
+impl Serialize for HaltReason {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            HaltReason::Halt => serializer.collect_map([("reason", "halt")]),
+            HaltReason::Fault(fault) => {
+                use serde::ser::SerializeMap;
+
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("reason", "fault")?;
+                map.serialize_entry("fault", fault)?;
+                map.end()
+            }
+        }
+    }
+}
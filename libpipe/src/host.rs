@@ -0,0 +1,65 @@
+//! The host environment a program reaches through a [`Syscall`](libseis::instruction_set::ControlOp::Syscall).
+//!
+//! The pipeline performs a call when the instruction is written back, so calls happen
+//! once, in program order, and never for squashed instructions. The calls themselves
+//! are listed in [`syscall`].
+
+use libseis::{instruction_set::control::syscall, types::Word};
+use std::{
+    fmt::Debug,
+    io::{stdin, stdout, Read, Write},
+};
+
+/// Performs the input and output of a program on behalf of the pipeline
+pub trait Host: Debug {
+    /// Writes a byte to the output
+    fn write(&mut self, byte: u8);
+
+    /// Reads a byte from the input, or returns [`None`] once the input has ended
+    fn read(&mut self) -> Option<u8>;
+}
+
+/// A [`Host`] which writes to the standard output and reads from the standard input
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stdio;
+
+impl Host for Stdio {
+    fn write(&mut self, byte: u8) {
+        // The program has no way to learn of the error, so the byte is simply lost
+        let _ = stdout().write_all(&[byte]);
+    }
+
+    fn read(&mut self) -> Option<u8> {
+        // Show whatever the program wrote before waiting on it
+        let _ = stdout().flush();
+
+        let mut byte = [0];
+        match stdin().read(&mut byte) {
+            Ok(1) => Some(byte[0]),
+            _ => None,
+        }
+    }
+}
+
+/// Performs the call selected by `number`, returning the value to leave in V0
+///
+/// Without a host, output is discarded and the input has always ended.
+pub(crate) fn call(
+    host: &mut Option<Box<dyn Host + Send + Sync>>,
+    number: Word,
+    argument: Word,
+) -> Word {
+    match number {
+        syscall::WRITE_BYTE => {
+            if let Some(host) = host {
+                host.write(argument as u8);
+            }
+            0
+        }
+        syscall::READ_BYTE => host
+            .as_mut()
+            .and_then(|host| host.read())
+            .map_or(syscall::FAILED, Word::from),
+        _ => syscall::FAILED,
+    }
+}
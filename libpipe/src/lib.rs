@@ -6,20 +6,32 @@
 
 #![warn(missing_docs)]
 
+mod console;
+mod energy;
+mod fault;
+mod host;
+mod perf;
 mod piped;
+mod profile;
 mod reg_locks;
 mod registers;
 mod regmap;
 mod stages;
 mod unpiped;
 
+pub use console::Console;
+pub use energy::EnergyCosts;
+pub use fault::{Fault, HaltReason};
+pub use host::{Host, Stdio};
 use libmem::module::MemoryModule;
+use libseis::{registers::RegisterFlags, types::Word};
+pub use perf::{PerfCounters, StallCause};
 pub use piped::Pipelined;
 pub use reg_locks::Locks;
-pub use registers::Registers;
+pub use registers::{DetailedRegisters, Registers};
 use serde::Serialize;
-pub use stages::*;
-use std::fmt::Debug;
+pub use stages::{execute::Latencies, *};
+use std::{collections::HashMap, fmt::Debug};
 pub use unpiped::Unpipelined;
 
 /// Represents the major stages in a [`Pipeline`]
@@ -31,6 +43,8 @@ pub struct PipelineStages<'a> {
     pub decode: &'a Decode,
     /// The [`execute`](Execute) stage
     pub execute: &'a Execute,
+    /// The extra execute sub-stages, in pipeline order
+    pub buffers: &'a [Buffer],
     /// The [`memory`](Memory) stage
     pub memory: &'a Memory,
     /// The [`writeback`](Writeback) stage
@@ -44,11 +58,12 @@ impl<'a> Serialize for PipelineStages<'a> {
     {
         use serde::ser::SerializeMap;
 
-        let mut map = serializer.serialize_map(Some(5))?;
+        let mut map = serializer.serialize_map(Some(6))?;
 
         map.serialize_entry("fetch", self.fetch)?;
         map.serialize_entry("decode", self.decode)?;
         map.serialize_entry("execute", self.execute)?;
+        map.serialize_entry("buffers", self.buffers)?;
         map.serialize_entry("memory", self.memory)?;
         map.serialize_entry("writeback", self.writeback)?;
 
@@ -56,6 +71,92 @@ impl<'a> Serialize for PipelineStages<'a> {
     }
 }
 
+/// Optional behaviour of a [`Pipeline`]
+///
+/// Every option is disabled by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PipelineOptions {
+    /// Raise a [`Fault`] when a stack operation would move the stack
+    /// pointer out of the stack page, rather than letting it wrap
+    ///
+    /// A push or call into the last word of the page would leave SP at the end of the
+    /// page, so it faults without writing, leaving SP at that word.
+    pub stack_checks: bool,
+    /// Raise a [`Fault`] when a store would write to a read-only segment
+    /// of the loaded image
+    ///
+    /// Writes to part of a segment may be allowed with
+    /// [`Memory::unprotect`](libmem::memory::Memory::unprotect).
+    pub write_checks: bool,
+    /// Raise a [`Fault`] when an instruction would be fetched from an
+    /// address which is not word-aligned, rather than rounding jump
+    /// targets down to a word boundary
+    pub alignment_checks: bool,
+    /// Fetch instructions again when a store writes over them after they were fetched,
+    /// rather than executing the bytes which were read
+    ///
+    /// The fetch and decode stages are squashed, and fetching resumes from the oldest of
+    /// their instructions once the memory module has finished writing. Each time this
+    /// happens is counted by [`Pipeline::smc_hazards`]. Only used by [`Pipelined`].
+    pub smc_checks: bool,
+    /// The number of [buffer](Buffer) stages to insert after the execute stage
+    ///
+    /// Each one adds a clock to the branch penalty. Only used by [`Pipelined`].
+    pub extra_execute_stages: usize,
+    /// The number of clocks taken by the multi-cycle operations of the execute stage
+    pub latencies: Latencies,
+    /// The number of multi-cycle integer and floating-point operations the execute
+    /// stage may keep in flight, letting independent instructions after them proceed
+    /// and complete first
+    ///
+    /// Instructions which write a register written by an operation in flight wait
+    /// for it to complete. Only used by [`Pipelined`].
+    pub max_in_flight: usize,
+    /// The number of loads the memory stage may have waiting on the memory module
+    /// at once, letting back-to-back loads which miss overlap
+    ///
+    /// Loads still complete in order, and any other memory operation waits for the
+    /// loads before it. Overlapping also needs a memory module with as many ports,
+    /// such as [`SingleLevel::with_ports`](libmem::module::SingleLevel::with_ports).
+    /// Zero and one both keep loads waiting on each other. Only used by [`Pipelined`].
+    pub memory_ports: usize,
+    /// The number of instructions the fetch stage may read in a clock
+    ///
+    /// At two, the instruction after the one at PC is read alongside it when both lie in
+    /// the same aligned pair of words, and the two go through the rest of the pipeline
+    /// together if neither is a multi-cycle, memory, or control instruction and the second
+    /// does not read a register written by the first. Otherwise they go one at a time.
+    /// Zero and one both fetch a single instruction, and larger widths behave as two.
+    /// Only used by [`Pipelined`].
+    pub fetch_width: usize,
+    /// The number of fetched instructions which may wait for the decode stage, letting
+    /// the fetch stage run ahead while it is busy
+    ///
+    /// Instructions are decoded in the order they were fetched. Zero and one both keep
+    /// the fetch stage waiting on the decode stage. Only used by [`Pipelined`].
+    pub fetch_queue_depth: usize,
+    /// The address of the fault vector table, which holds an instruction for each kind
+    /// of [`Fault`], in the order of [`Fault::vector`]
+    ///
+    /// When set, every fault other than a misaligned fetch no longer stops the pipeline.
+    /// The faulting instruction is abandoned and its entry is called as though by a
+    /// `JSR`, pushing LP and BP onto the stack and putting the address of the next
+    /// instruction in LP, so the handler may resume the program with `RET`. That `RET`
+    /// also restores the LP which was pushed.
+    pub vector_table: Option<Word>,
+    /// The number of variable registers, starting from V0, which programs may use
+    ///
+    /// An instruction which reads or writes any of the others raises a [`Fault`] in
+    /// the decode stage, which may be [trapped](PipelineOptions::vector_table). Every
+    /// register is enabled if this is unset or at least 16.
+    pub registers: Option<usize>,
+    /// Count the instructions written back at each address, for [`Pipeline::profile`]
+    pub profile: bool,
+    /// The energy charged for each instruction written back and each cache access, for
+    /// [`Pipeline::energy`]
+    pub energy: EnergyCosts,
+}
+
 /// The result of clocking the pipeline.
 #[derive(Debug)]
 pub enum ClockResult {
@@ -88,7 +189,91 @@ pub trait Pipeline: Debug {
     fn registers(&self) -> &Registers;
     /// Get a mutable reference to the registers
     fn registers_mut(&mut self) -> &mut Registers;
+    /// Gets the registers locked by instructions which have not been written back yet,
+    /// as the stages will see them on the next clock
+    fn locked_registers(&self) -> RegisterFlags;
 
     /// Gets references to the pipeline stages
     fn stages(&self) -> PipelineStages;
+
+    /// Sets the host which performs the system calls made by the program
+    ///
+    /// Until one is set, output is discarded and input has always ended.
+    fn set_host(&mut self, host: Box<dyn Host + Send + Sync>);
+
+    /// Gets the fault which stopped the pipeline, if any
+    fn fault(&self) -> Option<Fault> {
+        let stages = self.stages();
+
+        stages
+            .memory
+            .fault()
+            .or_else(|| stages.decode.fault())
+            .or_else(|| stages.fetch.fault())
+    }
+
+    /// Gets the reason the pipeline stopped, or [`None`] if it has not run [dry](ClockResult::Dry)
+    fn halt_reason(&self) -> Option<HaltReason> {
+        if !self.stages().memory.is_halted() {
+            return None;
+        }
+
+        Some(self.fault().map_or(HaltReason::Halt, HaltReason::Fault))
+    }
+
+    /// Gets the number of instructions which have been written back, excluding squashed ones
+    fn retired_instructions(&self) -> usize {
+        self.stages().writeback.retired()
+    }
+
+    /// Gets the number of stores which wrote over instructions already fetched, which
+    /// were then fetched again
+    ///
+    /// This is zero unless the pipeline was created with
+    /// [self-modifying code checks](PipelineOptions::smc_checks) enabled.
+    fn smc_hazards(&self) -> usize {
+        0
+    }
+
+    /// Gets the performance counters of the pipeline
+    ///
+    /// Pipelines which do not track performance report every counter as zero.
+    fn perf_counters(&self) -> PerfCounters {
+        PerfCounters::default()
+    }
+
+    /// Gets the number of instructions written back at each address
+    ///
+    /// The profile is empty unless the pipeline was created with
+    /// [profiling](PipelineOptions::profile) enabled.
+    fn profile(&self) -> HashMap<Word, u64> {
+        HashMap::new()
+    }
+
+    /// Gets the number of taken branches written back from each address to each target,
+    /// keyed by `(source, target)`. Jumps, calls, and returns all count as branches.
+    ///
+    /// The profile is empty unless the pipeline was created with
+    /// [profiling](PipelineOptions::profile) enabled.
+    fn branch_profile(&self) -> HashMap<(Word, Word), u64> {
+        HashMap::new()
+    }
+
+    /// Gets the number of instructions written back of each kind, keyed by their
+    /// category and mnemonic, such as `integer/ADD`
+    ///
+    /// The mix is empty unless the pipeline was created with
+    /// [profiling](PipelineOptions::profile) enabled.
+    fn instruction_mix(&self) -> HashMap<String, u64> {
+        HashMap::new()
+    }
+
+    /// Gets the energy used so far, charging the [costs](PipelineOptions::energy) of the
+    /// instructions written back and of the cache accesses of the memory module
+    ///
+    /// This is a rough estimate, and is zero unless the pipeline was created with costs
+    /// which are not.
+    fn energy(&self) -> f64 {
+        0.0
+    }
 }
@@ -0,0 +1,125 @@
+//! Performance counters kept by a pipeline.
+
+use crate::{
+    buffer, decode, execute, fetch, memory, memory::MemoryResult, PipelineStage, PipelineStages,
+};
+use serde::Serialize;
+
+/// The reason the pipeline failed to complete an instruction on a clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StallCause {
+    /// An instruction is waiting on a register locked by an earlier instruction
+    DataHazard,
+    /// A stage is waiting on the memory subsystem
+    Memory,
+    /// Instructions were squashed after a jump
+    ControlHazard,
+    /// A multi-clock operation is occupying the execute stage
+    Structural,
+}
+
+impl StallCause {
+    /// Finds the dominant cause of a stall from the state of the stages after a clock.
+    ///
+    /// The stall lasts through the clocks applied next, which may be more than one when
+    /// the caller skips ahead by the clocks a [stall](crate::ClockResult::Stall) requires.
+    ///
+    /// Returns [`None`] if the writeback stage received an instruction to complete,
+    /// or if the bubble has no cause (such as while the pipeline is filling or halting).
+    ///
+    /// Causes are checked from the longest-lasting to the shortest, so only one is
+    /// reported even when several apply. Every stage holds an operation for at least
+    /// one clock, so only operations needing more than that count as memory or
+    /// structural stalls.
+    pub fn classify(stages: &PipelineStages) -> Option<Self> {
+        if !matches!(
+            stages.writeback.get_state(),
+            None | Some(MemoryResult::Squashed { .. })
+        ) {
+            return None;
+        }
+
+        let memory_waiting = matches!(
+            stages.memory.get_state(),
+            memory::State::Reading { clocks, .. }
+            | memory::State::Writing { clocks, .. }
+            | memory::State::Pushing { clocks, .. }
+            | memory::State::Popping { clocks, .. }
+            | memory::State::DummyPop { clocks, .. }
+            | memory::State::JsrPrep { clocks, .. }
+            | memory::State::RetPrep { clocks, .. }
+            | memory::State::Trapping { clocks, .. }
+            | memory::State::TrapReturn { clocks, .. } if *clocks > 1
+        );
+        let fetch_waiting = matches!(stages.fetch.get_state(), fetch::State::Waiting { .. });
+
+        let squashed = matches!(stages.fetch.get_state(), fetch::State::Squashed { .. })
+            || matches!(
+                stages.decode.get_state(),
+                decode::State::Squashed | decode::State::PrevSquash
+            )
+            || matches!(stages.execute.get_state(), execute::State::Squashed { .. })
+            || stages.buffers.iter().any(|b| {
+                matches!(
+                    b.get_state(),
+                    buffer::State::Holding(execute::ExecuteResult::Squash { .. })
+                )
+            })
+            || matches!(stages.memory.get_state(), memory::State::Squashed { .. })
+            || matches!(
+                stages.writeback.get_state(),
+                Some(MemoryResult::Squashed { .. })
+            );
+
+        let execute_busy = matches!(
+            stages.execute.get_state(),
+            execute::State::Executing { clocks, .. } if *clocks > 1
+        );
+        let decode_held = matches!(stages.decode.get_state(), decode::State::Ready { .. });
+
+        if memory_waiting || fetch_waiting {
+            Some(Self::Memory)
+        } else if squashed {
+            Some(Self::ControlHazard)
+        } else if execute_busy {
+            Some(Self::Structural)
+        } else if decode_held {
+            Some(Self::DataHazard)
+        } else {
+            None
+        }
+    }
+}
+
+/// Counters describing how a pipeline has performed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PerfCounters {
+    /// Clocks stalled on a [data hazard](StallCause::DataHazard)
+    pub data_hazard_stalls: usize,
+    /// Clocks stalled on [memory](StallCause::Memory)
+    pub memory_stalls: usize,
+    /// Clocks stalled on a [control hazard](StallCause::ControlHazard)
+    pub control_hazard_stalls: usize,
+    /// Clocks stalled on a [structural hazard](StallCause::Structural)
+    pub structural_stalls: usize,
+}
+
+impl PerfCounters {
+    /// Attributes `clocks` stalled clocks to the cause
+    pub fn record_stall(&mut self, cause: StallCause, clocks: usize) {
+        match cause {
+            StallCause::DataHazard => self.data_hazard_stalls += clocks,
+            StallCause::Memory => self.memory_stalls += clocks,
+            StallCause::ControlHazard => self.control_hazard_stalls += clocks,
+            StallCause::Structural => self.structural_stalls += clocks,
+        }
+    }
+
+    /// The number of stalled clocks across every cause
+    pub fn total_stalls(&self) -> usize {
+        self.data_hazard_stalls
+            + self.memory_stalls
+            + self.control_hazard_stalls
+            + self.structural_stalls
+    }
+}
@@ -3,11 +3,14 @@
 //! The [`Pipeline`] trait simply enables consistent interfacing.
 
 use crate::{
-    stages::{self, Clock, Status},
-    ClockResult, Locks, PipelineStages,
+    profile::Profiler,
+    stages::{self, execute::ExecuteResult, Clock, Status},
+    ClockResult, Locks, PerfCounters, PipelineOptions, PipelineStages, StallCause,
 };
-use crate::{Pipeline, Registers};
+use crate::{Host, Pipeline, Registers};
 use libmem::module::MemoryModule;
+use libseis::{registers::RegisterFlags, types::Word};
+use std::collections::HashMap;
 
 /// Represents a pipelined processor
 #[derive(Debug)]
@@ -19,15 +22,35 @@ pub struct Pipelined {
     fetch: stages::Fetch,
     decode: stages::Decode,
     execute: stages::Execute,
+    buffers: Vec<stages::Buffer>,
     memory: stages::Memory,
     writeback: stages::Writeback,
+
+    perf: PerfCounters,
+    stall: Option<StallCause>,
+    smc_checks: bool,
+    /// The number of stores which wrote over instructions already fetched
+    smc_hazards: usize,
+    /// Whether the fetch stage was squashed by a store writing over its instructions,
+    /// and has yet to begin reading them again
+    smc_refetch: bool,
+    /// The number of clocks applied so far
+    clocks: usize,
+    profiler: Profiler,
 }
 
 impl Pipeline for Pipelined {
     fn clock(&mut self, clocks: usize) -> ClockResult {
+        // The stall found after the previous clock lasts for the clocks applied now
+        if let Some(cause) = self.stall.take() {
+            self.perf.record_stall(cause, clocks);
+        }
+
         self.memory_module.clock(clocks);
+        self.clocks += clocks;
+        self.execute.set_clocks(self.clocks);
 
-        Clock::begin(
+        let clock = Clock::begin(
             clocks,
             &mut self.writeback,
             &mut self.registers,
@@ -39,31 +62,56 @@ impl Pipeline for Pipelined {
             &mut self.registers,
             &mut self.locks,
             self.memory_module.as_mut(),
-        )
-        .then(
-            &mut self.execute,
-            &mut self.registers,
-            &mut self.locks,
-            self.memory_module.as_mut(),
-        )
-        .then(
+        );
+
+        let clock = self
+            .buffers
+            .iter_mut()
+            .rev()
+            .fold(clock, |clock, buffer| {
+                clock.then(
+                    buffer,
+                    &mut self.registers,
+                    &mut self.locks,
+                    self.memory_module.as_mut(),
+                )
+            })
+            .then(
+                &mut self.execute,
+                &mut self.registers,
+                &mut self.locks,
+                self.memory_module.as_mut(),
+            );
+
+        let clock = self.check_smc(clock).then(
             &mut self.decode,
             &mut self.registers,
             &mut self.locks,
             self.memory_module.as_mut(),
-        )
-        .finally(
+        );
+
+        self.hold_refetch(clock).finally(
             &mut self.fetch,
             &mut self.registers,
             &mut self.locks,
             self.memory_module.as_mut(),
         );
 
-        Status::begin(&mut self.fetch)
+        let status = Status::begin(&mut self.fetch)
             .then(&mut self.decode)
-            .then(&mut self.execute)
+            .then(&mut self.execute);
+
+        let result = self
+            .buffers
+            .iter_mut()
+            .fold(status, |status, buffer| status.then(buffer))
             .then(&mut self.memory)
-            .finally(&mut self.writeback)
+            .finally(&mut self.writeback);
+
+        self.stall = StallCause::classify(&self.stages());
+        self.profiler.update(&mut self.execute, &self.writeback);
+
+        result
     }
 
     fn memory_module(&self) -> &dyn MemoryModule {
@@ -82,11 +130,44 @@ impl Pipeline for Pipelined {
         &mut self.registers
     }
 
+    fn locked_registers(&self) -> RegisterFlags {
+        self.locks.locked()
+    }
+
+    fn perf_counters(&self) -> PerfCounters {
+        self.perf
+    }
+
+    fn smc_hazards(&self) -> usize {
+        self.smc_hazards
+    }
+
+    fn profile(&self) -> HashMap<Word, u64> {
+        self.profiler.counts().clone()
+    }
+
+    fn branch_profile(&self) -> HashMap<(Word, Word), u64> {
+        self.profiler.edges().clone()
+    }
+
+    fn instruction_mix(&self) -> HashMap<String, u64> {
+        self.profiler.mix().clone()
+    }
+
+    fn energy(&self) -> f64 {
+        self.profiler.energy(self.memory_module.as_ref())
+    }
+
+    fn set_host(&mut self, host: Box<dyn Host + Send + Sync>) {
+        self.writeback.set_host(host);
+    }
+
     fn stages(&self) -> PipelineStages {
         PipelineStages {
             fetch: &self.fetch,
             decode: &self.decode,
             execute: &self.execute,
+            buffers: &self.buffers,
             memory: &self.memory,
             writeback: &self.writeback,
         }
@@ -96,15 +177,1913 @@ impl Pipeline for Pipelined {
 impl Pipelined {
     /// Creates a new pipelined processor
     pub fn new(memory_module: Box<dyn MemoryModule + Send + Sync>) -> Self {
+        Self::with_options(memory_module, PipelineOptions::default())
+    }
+
+    /// Creates a new pipelined processor with the provided options
+    pub fn with_options(
+        memory_module: Box<dyn MemoryModule + Send + Sync>,
+        options: PipelineOptions,
+    ) -> Self {
         Self {
             memory_module,
             registers: Default::default(),
             locks: Default::default(),
-            fetch: Default::default(),
-            decode: Default::default(),
-            execute: Default::default(),
-            memory: Default::default(),
-            writeback: Default::default(),
+            fetch: stages::Fetch::with_options(&options),
+            decode: stages::Decode::with_options(&options),
+            execute: stages::Execute::with_options(&options),
+            buffers: (0..options.extra_execute_stages)
+                .map(|_| Default::default())
+                .collect(),
+            memory: stages::Memory::with_options(&options),
+            writeback: stages::Writeback::with_options(&options),
+            perf: Default::default(),
+            stall: None,
+            smc_checks: options.smc_checks,
+            smc_hazards: 0,
+            smc_refetch: false,
+            clocks: 0,
+            profiler: Profiler::with_options(&options),
+        }
+    }
+
+    /// Squashes the fetch and decode stages if the store the execute stage is about to
+    /// forward writes over any of their instructions, so they are fetched again
+    ///
+    /// `clock` is the signal the execute stage sends to the decode stage.
+    fn check_smc(&mut self, clock: Clock) -> Clock {
+        if !self.smc_checks || !matches!(clock, Clock::Ready(_)) {
+            return clock;
+        }
+
+        let Some((address, length)) = self.execute.pending().and_then(ExecuteResult::store) else {
+            return clock;
+        };
+
+        let in_flight: Vec<Word> = self
+            .decode
+            .in_flight()
+            .into_iter()
+            .chain(self.fetch.in_flight(self.registers.pc))
+            .collect();
+
+        // Each instruction is a word, so they overlap the store unless one ends first
+        let overwritten = in_flight
+            .iter()
+            .any(|&pc| pc.wrapping_sub(address) < length || address.wrapping_sub(pc) < 4);
+
+        match in_flight.first() {
+            Some(&oldest) if overwritten => {
+                self.registers.pc = oldest;
+                self.smc_hazards += 1;
+                self.smc_refetch = true;
+                clock.to_squash()
+            }
+            _ => clock,
+        }
+    }
+
+    /// Holds the fetch stage after a squash by [`check_smc`](Self::check_smc) until the
+    /// memory module has finished writing, so none of the old bytes are read from the
+    /// instruction cache
+    ///
+    /// `clock` is the signal the decode stage sends to the fetch stage.
+    fn hold_refetch(&mut self, clock: Clock) -> Clock {
+        if !self.smc_refetch || !matches!(clock, Clock::Ready(_)) || !self.fetch.is_resuming() {
+            clock
+        } else if self.memory_module.wait_time() > 0 {
+            clock.to_block()
+        } else {
+            self.smc_refetch = false;
+            clock
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Console, EnergyCosts, Fault, HaltReason, Host, Latencies, Pipeline};
+    use libmem::{
+        cache::{Associative, MultiAssociative},
+        memory::Memory,
+        module::SingleLevel,
+    };
+    use libseis::{
+        image::{self, Segment},
+        instruction_set::{
+            control::{syscall, Jump},
+            encode, floating_point,
+            integer::{BinaryOp, CompOp, TestOp},
+            register::{ImmOp, ReadOp, RegOp, WriteOp},
+            ControlOp, FloatingPointOp, Instruction, IntegerOp, RegisterOp,
+        },
+        pages::ZERO_PAGE,
+        registers::{RegisterFlags, COUNT, PC, V},
+        types::{SWord, Word},
+    };
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    /// Sets up a memory module with the program loaded at address zero
+    fn memory_module(program: &[Instruction]) -> Box<SingleLevel> {
+        let program: Vec<u8> = program
+            .iter()
+            .flat_map(|&i| encode(i).to_be_bytes())
+            .collect();
+
+        let mut memory = Memory::new(4);
+        memory.set_page(0x0000_0000, &program);
+
+        Box::new(SingleLevel::new(
+            Box::new(Associative::new(3, 2)),
+            Box::new(Associative::new(3, 2)),
+            memory,
+            10,
+            2,
+            false,
+        ))
+    }
+
+    /// Sets up a pipeline with the program loaded at address zero
+    fn load(program: &[Instruction], options: PipelineOptions) -> Pipelined {
+        Pipelined::with_options(memory_module(program), options)
+    }
+
+    /// Clocks the pipeline until it runs dry, returning the number of clocks taken
+    fn run(pipeline: &mut Pipelined) -> usize {
+        let mut clocks = 0;
+        while !matches!(pipeline.clock(1), ClockResult::Dry) {
+            clocks += 1;
+            assert!(clocks < 10_000, "The pipeline should have stopped");
+        }
+        clocks
+    }
+
+    /// Clocks the pipeline until it runs dry, skipping over stalls as the simulator does
+    fn run_skipping(pipeline: &mut Pipelined) -> usize {
+        let mut clocks = 0;
+        let mut required = 1;
+        loop {
+            clocks += required;
+            match pipeline.clock(required) {
+                ClockResult::Stall(n) => required = n,
+                ClockResult::Flow => required = 1,
+                ClockResult::Dry => break clocks,
+            }
+        }
+    }
+
+    /// Sets up a pipeline running a program which pushes to the stack forever
+    fn stack_loop(options: PipelineOptions) -> Pipelined {
+        let mut pipeline = load(
+            &[
+                Instruction::Register(RegisterOp::Push(V[0])),
+                Instruction::Control(ControlOp::Jmp(Jump::Relative(-4))),
+            ],
+            options,
+        );
+
+        // Start near the top of the stack page so the overflow happens quickly
+        pipeline.registers_mut().sp = 0xFFE0;
+
+        pipeline
+    }
+
+    /// A loop which counts V0 down from 10
+    fn countdown_program() -> [Instruction; 5] {
+        [
+            Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                zero: true,
+                shift: 0,
+                immediate: 10,
+                destination: V[0],
+            })),
+            Instruction::Integer(IntegerOp::Sub(BinaryOp::Immediate(V[0], 1, V[0]))),
+            Instruction::Integer(IntegerOp::Cmp(CompOp::Immediate(V[0], 0, false))),
+            Instruction::Control(ControlOp::Jne(Jump::Relative(-8))),
+            Instruction::Control(ControlOp::Halt),
+        ]
+    }
+
+    /// Sets up a pipeline running [`countdown_program`]
+    fn countdown(options: PipelineOptions) -> Pipelined {
+        load(&countdown_program(), options)
+    }
+
+    /// A host with fixed input, which keeps its output where the test can read it
+    #[derive(Debug, Default)]
+    struct MockHost {
+        input: VecDeque<u8>,
+        output: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl Host for MockHost {
+        fn write(&mut self, byte: u8) {
+            self.output.lock().unwrap().push(byte);
+        }
+
+        fn read(&mut self) -> Option<u8> {
+            self.input.pop_front()
+        }
+    }
+
+    /// A program which copies its input to its output until the input ends
+    fn echo_program() -> [Instruction; 9] {
+        let ldr = |immediate| {
+            Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                zero: true,
+                shift: 0,
+                immediate,
+                destination: V[0],
+            }))
+        };
+
+        [
+            ldr(syscall::READ_BYTE as u16),
+            Instruction::Control(ControlOp::Syscall),
+            Instruction::Integer(IntegerOp::Cmp(CompOp::Immediate(
+                V[0],
+                syscall::FAILED,
+                false,
+            ))),
+            Instruction::Control(ControlOp::Jeq(Jump::Relative(20))),
+            Instruction::Register(RegisterOp::Tfr(RegOp {
+                source: V[0],
+                destination: V[1],
+            })),
+            ldr(syscall::WRITE_BYTE as u16),
+            Instruction::Control(ControlOp::Syscall),
+            Instruction::Control(ControlOp::Jmp(Jump::Relative(-28))),
+            Instruction::Control(ControlOp::Halt),
+        ]
+    }
+
+    /// A program which writes a string to a console at the end of the zero page, then
+    /// reads a byte of input into V1 and a word of input into V2
+    fn console_program() -> Vec<Instruction> {
+        let output = Console::OUTPUT as u16 | 0xFF00;
+        let input = Console::INPUT as u16 | 0xFF00;
+
+        b"Hi!\n"
+            .iter()
+            .flat_map(|&byte| {
+                [
+                    Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                        zero: true,
+                        shift: 0,
+                        immediate: byte as u16,
+                        destination: V[0],
+                    })),
+                    Instruction::Register(RegisterOp::Sbr(WriteOp::ZeroPage {
+                        address: output,
+                        source: V[0],
+                    })),
+                ]
+            })
+            .chain([
+                Instruction::Register(RegisterOp::Lbr(ReadOp::ZeroPage {
+                    address: input,
+                    destination: V[1],
+                })),
+                Instruction::Register(RegisterOp::Llr(ReadOp::ZeroPage {
+                    address: input,
+                    destination: V[2],
+                })),
+                Instruction::Control(ControlOp::Halt),
+            ])
+            .collect()
+    }
+
+    /// Sets up a pipeline running a program which stores over its own code, with the
+    /// code marked as read-only
+    fn self_modifying(options: PipelineOptions) -> Pipelined {
+        let mut pipeline = load(
+            &[
+                Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                    zero: true,
+                    shift: 0,
+                    immediate: 4,
+                    destination: V[1],
+                })),
+                Instruction::Register(RegisterOp::Slr(WriteOp::Indirect {
+                    volatile: false,
+                    non_temporal: false,
+                    address: V[1],
+                    source: V[0],
+                })),
+                Instruction::Control(ControlOp::Halt),
+            ],
+            options,
+        );
+
+        pipeline
+            .memory_module_mut()
+            .memory_mut()
+            .protect(0x0000_0000..0x0000_000C);
+
+        pipeline
+    }
+
+    /// Sets up a pipeline running a program which multiplies V0 by 3
+    fn multiply(options: PipelineOptions) -> Pipelined {
+        load(
+            &[
+                Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                    zero: true,
+                    shift: 0,
+                    immediate: 10,
+                    destination: V[0],
+                })),
+                Instruction::Integer(IntegerOp::Mul(BinaryOp::Immediate(V[0], 3, V[1]))),
+                Instruction::Control(ControlOp::Halt),
+            ],
+            options,
+        )
+    }
+
+    /// Sets up a pipeline running a program which divides 0.0 by 2.0 into V2, setting
+    /// ZF, then increments V3 into V4, clearing it. The divide takes 20 clocks.
+    fn divide_then_add(options: PipelineOptions) -> Pipelined {
+        let options = PipelineOptions {
+            latencies: Latencies {
+                float_divide: 20,
+                ..options.latencies
+            },
+            ..options
+        };
+        let mut pipeline = load(
+            &[
+                Instruction::FloatingPoint(FloatingPointOp::Fdiv(floating_point::BinaryOp {
+                    left: V[0],
+                    right: V[1],
+                    destination: V[2],
+                })),
+                Instruction::Integer(IntegerOp::Add(BinaryOp::Immediate(V[3], 1, V[4]))),
+                Instruction::Control(ControlOp::Halt),
+            ],
+            options,
+        );
+
+        pipeline.registers_mut().v[1] = 2.0f32.to_bits();
+        pipeline.registers_mut().v[2] = u32::MAX;
+
+        pipeline
+    }
+
+    /// Sets up a pipeline running a program which loads V3 and V4 from two lines which
+    /// both miss the data cache, then adds them into V5
+    fn two_loads(options: PipelineOptions) -> Pipelined {
+        let load = |address, destination| {
+            Instruction::Register(RegisterOp::Llr(ReadOp::Indirect {
+                volatile: false,
+                non_temporal: false,
+                address,
+                destination,
+            }))
+        };
+        let program = [
+            load(V[1], V[3]),
+            load(V[2], V[4]),
+            Instruction::Integer(IntegerOp::Add(BinaryOp::Registers(V[3], V[4], V[5]))),
+            Instruction::Control(ControlOp::Halt),
+        ];
+
+        let mut memory_module = memory_module(&program);
+        memory_module
+            .memory_mut()
+            .write_word(0x0001_0000, 0x0000_0007);
+        memory_module
+            .memory_mut()
+            .write_word(0x0001_0108, 0x0000_0009);
+
+        let memory_module = Box::new(memory_module.with_ports(options.memory_ports));
+        let mut pipeline = Pipelined::with_options(memory_module, options);
+        pipeline.registers_mut().v[1] = 0x0001_0000;
+        pipeline.registers_mut().v[2] = 0x0001_0108;
+
+        pipeline
+    }
+
+    /// A program which pops from the empty stack, then stores over its own code, with a
+    /// vector table at 0x10 whose handlers count the faults in V2 and V3 and return
+    fn fault_handlers() -> [Instruction; 12] {
+        let count = |register| {
+            Instruction::Integer(IntegerOp::Add(BinaryOp::Immediate(register, 1, register)))
+        };
+
+        [
+            Instruction::Register(RegisterOp::Pop(V[0])),
+            Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                zero: true,
+                shift: 0,
+                immediate: 4,
+                destination: V[1],
+            })),
+            Instruction::Register(RegisterOp::Slr(WriteOp::Indirect {
+                volatile: false,
+                non_temporal: false,
+                address: V[1],
+                source: V[0],
+            })),
+            Instruction::Control(ControlOp::Halt),
+            // The vector table
+            Instruction::Control(ControlOp::Halt),
+            Instruction::Control(ControlOp::Jmp(Jump::Relative(0x0C))),
+            Instruction::Control(ControlOp::Jmp(Jump::Relative(0x10))),
+            Instruction::Control(ControlOp::Halt),
+            // The handlers
+            count(V[2]),
+            Instruction::Control(ControlOp::Ret),
+            count(V[3]),
+            Instruction::Control(ControlOp::Ret),
+        ]
+    }
+
+    /// Sets up a pipeline running a program which jumps to an odd address
+    fn misaligned_jump(options: PipelineOptions) -> Pipelined {
+        load(
+            &[
+                Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                    zero: true,
+                    shift: 0,
+                    immediate: 0x0000_000D,
+                    destination: V[1],
+                })),
+                Instruction::Control(ControlOp::Jmp(Jump::Register(V[1]))),
+                Instruction::Control(ControlOp::Halt),
+            ],
+            options,
+        )
+    }
+
+    #[test]
+    fn memory_ports_overlap_loads_which_miss() {
+        let run_with = |memory_ports| {
+            let mut pipeline = two_loads(PipelineOptions {
+                memory_ports,
+                ..Default::default()
+            });
+            let clocks = run(&mut pipeline);
+
+            assert_eq!(pipeline.registers()[V[3]], 7);
+            assert_eq!(pipeline.registers()[V[4]], 9);
+            assert_eq!(pipeline.registers()[V[5]], 16);
+            assert!((0..COUNT).all(|register| pipeline.locks.is_unlocked(register as _)));
+
+            (clocks, pipeline.perf_counters().memory_stalls)
+        };
+
+        let (serial_clocks, serial_stalls) = run_with(1);
+        let (overlapped_clocks, overlapped_stalls) = run_with(2);
+
+        // The second miss overlaps most of the first
+        assert!(
+            overlapped_clocks + 5 <= serial_clocks,
+            "{overlapped_clocks} vs {serial_clocks}"
+        );
+        assert!(overlapped_stalls < serial_stalls);
+    }
+
+    #[test]
+    fn loaded_registers_stay_locked_until_writeback() {
+        let mut pipeline = two_loads(PipelineOptions::default());
+        assert_eq!(pipeline.locked_registers(), RegisterFlags::default());
+
+        let mut clocks = 0;
+        while !pipeline.locked_registers().has_register(V[3]) {
+            pipeline.clock(1);
+            clocks += 1;
+            assert!(clocks < 100, "The load should have been issued");
+        }
+        assert_eq!(pipeline.locked_registers(), pipeline.locks.locked());
+
+        // The lock is held for as long as the loaded value has not been written back
+        while pipeline.registers()[V[3]] != 7 {
+            assert!(pipeline.locked_registers().has_register(V[3]));
+            assert!(!matches!(pipeline.clock(1), ClockResult::Dry));
+        }
+        assert!(!pipeline.locked_registers().has_register(V[3]));
+
+        run(&mut pipeline);
+        assert_eq!(pipeline.locked_registers(), RegisterFlags::default());
+    }
+
+    #[test]
+    fn memory_ports_match_in_order_results_when_skipping() {
+        let [mut serial, mut overlapped] = [1, 2].map(|memory_ports| {
+            two_loads(PipelineOptions {
+                memory_ports,
+                ..Default::default()
+            })
+        });
+
+        run_skipping(&mut serial);
+        run_skipping(&mut overlapped);
+
+        assert!(overlapped
+            .registers()
+            .diff(serial.registers())
+            .iter()
+            .all(|&(register, ..)| register == PC));
+    }
+
+    #[test]
+    fn volatile_loads_are_never_demoted() {
+        let load = |volatile, non_temporal, address, destination| {
+            Instruction::Register(RegisterOp::Llr(ReadOp::Indirect {
+                volatile,
+                non_temporal,
+                address,
+                destination,
+            }))
+        };
+        let program: Vec<u8> = [
+            load(false, false, V[1], V[4]),
+            load(false, false, V[2], V[5]),
+            // Skips the cache, so the line of V2 stays the most recently used
+            load(true, true, V[2], V[6]),
+            load(false, false, V[3], V[7]),
+            Instruction::Control(ControlOp::Halt),
+        ]
+        .iter()
+        .flat_map(|&i| encode(i).to_be_bytes())
+        .collect();
+
+        let mut memory = Memory::new(4);
+        memory.set_page(0x0000_0000, &program);
+
+        // A single set of two ways, which the three loads share
+        let memory_module = Box::new(SingleLevel::new(
+            Box::new(MultiAssociative::new(3, 0, 2)),
+            Box::new(Associative::new(3, 2)),
+            memory,
+            10,
+            2,
+            false,
+        ));
+        let mut pipeline = Pipelined::with_options(memory_module, PipelineOptions::default());
+        pipeline.registers_mut().v[1] = 0x0001_0000;
+        pipeline.registers_mut().v[2] = 0x0001_0100;
+        pipeline.registers_mut().v[3] = 0x0001_0200;
+
+        run(&mut pipeline);
+
+        let cache = pipeline.memory_module().data_cache();
+        assert!(cache.check_address(0x0001_0100).is_hit());
+        assert!(!cache.check_address(0x0001_0000).is_hit());
+    }
+
+    #[test]
+    fn fetch_width_pairs_independent_instructions() {
+        // Straight-line code in which no instruction reads a register written before it
+        let program: Vec<Instruction> = (0..64)
+            .map(|i| match i % 2 {
+                0 => Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                    zero: true,
+                    shift: 0,
+                    immediate: i as u16,
+                    destination: V[i % 8],
+                })),
+                _ => Instruction::Integer(IntegerOp::Add(BinaryOp::Immediate(
+                    V[15],
+                    i as Word,
+                    V[i % 8],
+                ))),
+            })
+            .chain([Instruction::Control(ControlOp::Halt)])
+            .collect();
+        let addresses: Vec<Word> = (0..program.len() as Word).map(|i| i * 4).collect();
+
+        let [narrow, wide] = [1, 2].map(|fetch_width| {
+            let mut memory = Memory::new(4);
+            memory.set_page(
+                0x0000_0000,
+                &program
+                    .iter()
+                    .flat_map(|&i| encode(i).to_be_bytes())
+                    .collect::<Vec<u8>>(),
+            );
+            let mut memory_module = SingleLevel::new(
+                Box::new(Associative::new(5, 4)),
+                Box::new(Associative::new(5, 4)),
+                memory,
+                10,
+                2,
+                false,
+            );
+            memory_module.warm(&addresses);
+
+            let mut pipeline = Pipelined::with_options(
+                Box::new(memory_module),
+                PipelineOptions {
+                    fetch_width,
+                    ..Default::default()
+                },
+            );
+            let clocks = run(&mut pipeline);
+            let ipc = pipeline.retired_instructions() as f64 / clocks as f64;
+
+            (pipeline, ipc)
+        });
+
+        assert_eq!(narrow.0.retired_instructions(), 64);
+        assert_eq!(wide.0.retired_instructions(), 64);
+        assert_eq!(narrow.0.registers().v, wide.0.registers().v);
+        assert!(narrow.1 < 1.0, "{}", narrow.1);
+        assert!(wide.1 > 1.75, "{}", wide.1);
+    }
+
+    #[test]
+    fn fetch_width_falls_back_on_hazards_and_branches() {
+        let mut pipeline = countdown(PipelineOptions {
+            fetch_width: 2,
+            ..Default::default()
+        });
+
+        run(&mut pipeline);
+
+        // Every instruction in the loop reads the one before it, or branches
+        assert_eq!(pipeline.registers()[V[0]], 0);
+        assert_eq!(pipeline.retired_instructions(), 1 + 10 * 3);
+    }
+
+    #[test]
+    fn fetch_queue_hides_decode_stalls() {
+        // The add waits in the decode stage for the multiplication, while the instructions
+        // after it miss the instruction cache
+        let program: Vec<Instruction> = [
+            Instruction::Integer(IntegerOp::Mul(BinaryOp::Immediate(V[1], 3, V[2]))),
+            Instruction::Integer(IntegerOp::Add(BinaryOp::Immediate(V[2], 1, V[3]))),
+        ]
+        .into_iter()
+        .chain((0..6).map(|i| {
+            Instruction::Integer(IntegerOp::Add(BinaryOp::Immediate(
+                V[4],
+                i,
+                V[5 + i as usize],
+            )))
+        }))
+        .chain([Instruction::Control(ControlOp::Halt)])
+        .collect();
+
+        let [default, shallow, deep] = [0, 1, 4].map(|fetch_queue_depth| {
+            let mut pipeline = load(
+                &program,
+                PipelineOptions {
+                    latencies: Latencies {
+                        multiply: 20,
+                        ..Default::default()
+                    },
+                    fetch_queue_depth,
+                    ..Default::default()
+                },
+            );
+            pipeline.registers_mut().v[1] = 5;
+            let clocks = run(&mut pipeline);
+
+            (pipeline, clocks)
+        });
+
+        assert_eq!(default.1, shallow.1);
+        assert_eq!(shallow.0.registers().v, deep.0.registers().v);
+        assert_eq!(deep.0.registers()[V[3]], 16);
+        // At least one of the misses is taken while the add waits
+        assert!(deep.1 + 10 <= shallow.1, "{} !< {}", deep.1, shallow.1);
+    }
+
+    #[test]
+    fn stack_overflow_wraps_by_default() {
+        let mut pipeline = stack_loop(PipelineOptions::default());
+
+        for _ in 0..2000 {
+            assert!(!matches!(pipeline.clock(1), ClockResult::Dry));
+        }
+
+        assert!(pipeline.registers().sp < 0xFFE0, "SP should have wrapped");
+        assert_eq!(pipeline.fault(), None);
+    }
+
+    #[test]
+    fn stack_overflow_faults() {
+        let mut pipeline = stack_loop(PipelineOptions {
+            stack_checks: true,
+            ..Default::default()
+        });
+
+        pipeline.registers_mut().v[0] = 0x1234_5678;
+
+        run(&mut pipeline);
+
+        // The push into the last word of the page would wrap SP, so it faults at that
+        // word without writing it, while every push before it completes
+        assert_eq!(
+            pipeline.fault(),
+            Some(Fault::StackOverflow { sp: 0x0001_FFFC })
+        );
+        assert_eq!(pipeline.registers().sp, 0xFFFC);
+
+        let memory = pipeline.memory_module().memory();
+        assert_eq!(memory.read_word(0x0001_FFF8), 0x1234_5678);
+        assert_eq!(memory.read_word(0x0001_FFFC), 0);
+    }
+
+    #[test]
+    fn extra_execute_stages_run_correctly() {
+        for extra_execute_stages in 0..4 {
+            let mut pipeline = countdown(PipelineOptions {
+                extra_execute_stages,
+                ..Default::default()
+            });
+
+            run(&mut pipeline);
+
+            assert_eq!(pipeline.registers()[V[0]], 0);
+            assert_eq!(pipeline.stages().buffers.len(), extra_execute_stages);
+        }
+    }
+
+    #[test]
+    fn extra_execute_stages_increase_branch_penalty() {
+        let clocks: Vec<usize> = (0..4)
+            .map(|extra_execute_stages| {
+                run(&mut countdown(PipelineOptions {
+                    extra_execute_stages,
+                    ..Default::default()
+                }))
+            })
+            .collect();
+
+        // Every taken branch waits for one more clock per extra stage
+        for pair in clocks.windows(2) {
+            assert!(pair[1] >= pair[0] + 10, "{clocks:?}");
+        }
+    }
+
+    #[test]
+    fn squashed_instructions_are_not_retired() {
+        for extra_execute_stages in 0..2 {
+            let mut pipeline = countdown(PipelineOptions {
+                extra_execute_stages,
+                ..Default::default()
+            });
+
+            run(&mut pipeline);
+
+            // The load, then ten passes through the loop. The halt is never written back.
+            assert_eq!(pipeline.retired_instructions(), 1 + 10 * 3);
+        }
+    }
+
+    #[test]
+    fn profile_counts_retired_instructions() {
+        let options = PipelineOptions {
+            profile: true,
+            ..Default::default()
+        };
+        let pipelines: [Box<dyn Pipeline>; 2] = [
+            Box::new(countdown(options)),
+            Box::new(crate::Unpipelined::with_options(
+                memory_module(&countdown_program()),
+                options,
+            )),
+        ];
+
+        for mut pipeline in pipelines {
+            while !matches!(pipeline.clock(1), ClockResult::Dry) {}
+
+            // The loop body ran ten times, and the load before it once; the halt and
+            // the squashed instructions after the branch never retire
+            let profile = pipeline.profile();
+            assert_eq!(profile, [(0, 1), (4, 10), (8, 10), (12, 10)].into());
+            assert_eq!(
+                profile.values().sum::<u64>(),
+                pipeline.retired_instructions() as u64
+            );
+        }
+    }
+
+    #[test]
+    fn instruction_mix_counts_mnemonics() {
+        let options = PipelineOptions {
+            profile: true,
+            ..Default::default()
+        };
+        let pipelines: [Box<dyn Pipeline>; 2] = [
+            Box::new(countdown(options)),
+            Box::new(crate::Unpipelined::with_options(
+                memory_module(&countdown_program()),
+                options,
+            )),
+        ];
+
+        for mut pipeline in pipelines {
+            while !matches!(pipeline.clock(1), ClockResult::Dry) {}
+
+            let mix = pipeline.instruction_mix();
+            assert_eq!(
+                mix,
+                [
+                    ("register/LDR".to_owned(), 1),
+                    ("integer/SUB".to_owned(), 10),
+                    ("integer/CMP".to_owned(), 10),
+                    ("control/JNE".to_owned(), 10),
+                ]
+                .into()
+            );
+        }
+    }
+
+    #[test]
+    fn profile_is_empty_unless_enabled() {
+        let mut pipeline = countdown(PipelineOptions::default());
+        run(&mut pipeline);
+
+        assert!(pipeline.profile().is_empty());
+        assert!(pipeline.instruction_mix().is_empty());
+    }
+
+    #[test]
+    fn energy_sums_configured_costs() {
+        let instructions_only = EnergyCosts {
+            control: 1.0,
+            integer: 10.0,
+            register: 100.0,
+            floating_point: 1000.0,
+            ..Default::default()
+        };
+        let everything = EnergyCosts {
+            hit: 0.5,
+            miss: 2.0,
+            eviction: 0.25,
+            ..instructions_only
+        };
+
+        for costs in [instructions_only, everything] {
+            let options = PipelineOptions {
+                energy: costs,
+                ..Default::default()
+            };
+            let pipelines: [Box<dyn Pipeline>; 2] = [
+                Box::new(countdown(options)),
+                Box::new(crate::Unpipelined::with_options(
+                    memory_module(&countdown_program()),
+                    options,
+                )),
+            ];
+
+            for mut pipeline in pipelines {
+                while !matches!(pipeline.clock(1), ClockResult::Dry) {}
+
+                // One load, twenty integer operations, and ten branches are written back
+                let module = pipeline.memory_module();
+                let memory = module.cache_hits() as f64 * costs.hit
+                    + module.total_misses() as f64 * costs.miss
+                    + module.evictions() as f64 * costs.eviction;
+                assert_eq!(pipeline.energy(), 100.0 + 20.0 * 10.0 + 10.0 + memory);
+
+                // Charging energy does not turn on profiling
+                assert!(pipeline.profile().is_empty());
+            }
+        }
+
+        let mut pipeline = countdown(PipelineOptions::default());
+        run(&mut pipeline);
+        assert_eq!(pipeline.energy(), 0.0);
+    }
+
+    #[test]
+    fn branch_profile_counts_taken_edges() {
+        let countdown = |count, register| {
+            [
+                Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                    zero: true,
+                    shift: 0,
+                    immediate: count,
+                    destination: register,
+                })),
+                Instruction::Integer(IntegerOp::Sub(BinaryOp::Immediate(register, 1, register))),
+                Instruction::Integer(IntegerOp::Cmp(CompOp::Immediate(register, 0, false))),
+                Instruction::Control(ControlOp::Jne(Jump::Relative(-8))),
+            ]
+        };
+        let program: Vec<_> = countdown(3, V[0])
+            .into_iter()
+            .chain(countdown(5, V[1]))
+            .chain([
+                Instruction::Control(ControlOp::Jmp(Jump::Relative(8))),
+                Instruction::Control(ControlOp::Halt),
+                Instruction::Control(ControlOp::Halt),
+            ])
+            .collect();
+
+        let options = PipelineOptions {
+            profile: true,
+            ..Default::default()
+        };
+        let pipelines: [Box<dyn Pipeline>; 2] = [
+            Box::new(load(&program, options)),
+            Box::new(crate::Unpipelined::with_options(
+                memory_module(&program),
+                options,
+            )),
+        ];
+
+        for mut pipeline in pipelines {
+            while !matches!(pipeline.clock(1), ClockResult::Dry) {}
+
+            // Each back-edge is taken once less than its loop runs, and the branches
+            // which fall out of the loops are not counted
+            assert_eq!(
+                pipeline.branch_profile(),
+                [((0x0C, 0x04), 2), ((0x1C, 0x14), 4), ((0x20, 0x28), 1)].into()
+            );
+        }
+    }
+
+    #[test]
+    fn disabled_registers_fault() {
+        let add = |source, destination| {
+            Instruction::Integer(IntegerOp::Add(BinaryOp::Immediate(source, 1, destination)))
+        };
+        let program = [
+            Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                zero: true,
+                shift: 0,
+                immediate: 1,
+                destination: V[0],
+            })),
+            Instruction::Control(ControlOp::Jmp(Jump::Relative(8))),
+            // Decoded by the pipelined processor before being squashed
+            add(V[8], V[8]),
+            add(V[0], V[3]),
+            add(V[3], V[5]),
+            Instruction::Control(ControlOp::Halt),
+        ];
+
+        let options = PipelineOptions {
+            registers: Some(4),
+            ..Default::default()
+        };
+        let pipelines: [Box<dyn Pipeline>; 2] = [
+            Box::new(load(&program, options)),
+            Box::new(crate::Unpipelined::with_options(
+                memory_module(&program),
+                options,
+            )),
+        ];
+
+        for mut pipeline in pipelines {
+            while !matches!(pipeline.clock(1), ClockResult::Dry) {}
+
+            assert_eq!(
+                pipeline.halt_reason(),
+                Some(HaltReason::Fault(Fault::DisabledRegister {
+                    register: V[5],
+                    pc: 0x10
+                }))
+            );
+            assert_eq!(pipeline.registers()[V[3]], 2);
+            assert_eq!(pipeline.registers()[V[5]], 0);
+        }
+
+        let mut pipeline = load(&program, PipelineOptions::default());
+        run(&mut pipeline);
+
+        assert_eq!(pipeline.halt_reason(), Some(HaltReason::Halt));
+        assert_eq!(pipeline.registers()[V[5]], 3);
+    }
+
+    /// A word whose op-type is not that of any instruction
+    const ILLEGAL: Word = 0xE000_0000;
+
+    /// Clocks both pipelines through `program`, with [`ILLEGAL`] written over each of
+    /// the instructions at `illegal`, until they stop
+    fn run_illegal(
+        program: &[Instruction],
+        illegal: &[Word],
+        options: PipelineOptions,
+    ) -> [Box<dyn Pipeline>; 2] {
+        let mut pipelines: [Box<dyn Pipeline>; 2] = [
+            Box::new(load(program, options)),
+            Box::new(crate::Unpipelined::with_options(
+                memory_module(program),
+                options,
+            )),
+        ];
+
+        for pipeline in &mut pipelines {
+            for &address in illegal {
+                let memory = pipeline.memory_module_mut().memory_mut();
+                memory.write_word(address, ILLEGAL);
+            }
+
+            let mut clocks = 0;
+            while !matches!(pipeline.clock(1), ClockResult::Dry) {
+                clocks += 1;
+                assert!(clocks < 10_000, "The pipeline should have stopped");
+            }
+        }
+
+        pipelines
+    }
+
+    #[test]
+    fn illegal_instructions_fault() {
+        let program = [
+            Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                zero: true,
+                shift: 0,
+                immediate: 1,
+                destination: V[0],
+            })),
+            Instruction::Control(ControlOp::Jmp(Jump::Relative(8))),
+            // Decoded by the pipelined processor before being squashed
+            Instruction::Control(ControlOp::Nop),
+            Instruction::Control(ControlOp::Nop),
+            Instruction::Integer(IntegerOp::Add(BinaryOp::Immediate(V[0], 1, V[0]))),
+            Instruction::Control(ControlOp::Halt),
+        ];
+
+        for pipeline in run_illegal(&program, &[0x08, 0x0C], PipelineOptions::default()) {
+            assert_eq!(
+                pipeline.halt_reason(),
+                Some(HaltReason::Fault(Fault::IllegalInstruction {
+                    word: ILLEGAL,
+                    pc: 0x0C
+                }))
+            );
+            assert_eq!(pipeline.registers()[V[0]], 1);
+        }
+    }
+
+    #[test]
+    fn illegal_instructions_call_their_handler() {
+        let count = |register| {
+            Instruction::Integer(IntegerOp::Add(BinaryOp::Immediate(register, 1, register)))
+        };
+        let program = [
+            Instruction::Control(ControlOp::Nop),
+            count(V[0]),
+            Instruction::Control(ControlOp::Halt),
+            Instruction::Control(ControlOp::Halt),
+            // The vector table, with the handler in the last entry
+            Instruction::Control(ControlOp::Halt),
+            Instruction::Control(ControlOp::Halt),
+            Instruction::Control(ControlOp::Halt),
+            Instruction::Control(ControlOp::Halt),
+            Instruction::Control(ControlOp::Halt),
+            count(V[1]),
+            Instruction::Control(ControlOp::Ret),
+        ];
+        let options = PipelineOptions {
+            vector_table: Some(0x0000_0010),
+            ..Default::default()
+        };
+
+        for pipeline in run_illegal(&program, &[0x00], options) {
+            // The handler ran once, and returned to the instruction after the fault
+            assert_eq!(pipeline.halt_reason(), Some(HaltReason::Halt));
+            assert_eq!(pipeline.registers()[V[0]], 1);
+            assert_eq!(pipeline.registers()[V[1]], 1);
+            assert_eq!(pipeline.registers().sp, 0);
+        }
+    }
+
+    #[test]
+    fn stalls_are_attributed_once() {
+        let mut pipeline = countdown(PipelineOptions::default());
+
+        let clocks = run(&mut pipeline);
+        let perf = pipeline.perf_counters();
+
+        // The loop is taken nine times, squashing the instructions behind each jump
+        assert!(perf.control_hazard_stalls > 0, "{perf:?}");
+        // The first fetch misses the instruction cache
+        assert!(perf.memory_stalls > 0, "{perf:?}");
+        assert!(perf.total_stalls() <= clocks, "{perf:?} in {clocks} clocks");
+    }
+
+    #[test]
+    fn skipped_stalls_are_attributed() {
+        let mut stepped = countdown(PipelineOptions::default());
+        let mut skipped = countdown(PipelineOptions::default());
+
+        run(&mut stepped);
+        run_skipping(&mut skipped);
+
+        let (stepped, skipped) = (stepped.perf_counters(), skipped.perf_counters());
+
+        assert_eq!(stepped, skipped);
+    }
+
+    #[test]
+    fn read_only_writes_succeed_by_default() {
+        let mut pipeline = self_modifying(PipelineOptions::default());
+
+        run(&mut pipeline);
+
+        assert_eq!(pipeline.fault(), None);
+        assert_eq!(pipeline.memory_module().memory().read_word(0x0000_0004), 0);
+    }
+
+    #[test]
+    fn read_only_writes_fault() {
+        let mut pipeline = self_modifying(PipelineOptions {
+            write_checks: true,
+            ..Default::default()
+        });
+        let code = pipeline.memory_module().memory().read_word(0x0000_0004);
+
+        run(&mut pipeline);
+
+        assert_eq!(
+            pipeline.fault(),
+            Some(Fault::WriteProtected {
+                address: 0x0000_0004
+            })
+        );
+        assert_eq!(
+            pipeline.memory_module().memory().read_word(0x0000_0004),
+            code
+        );
+    }
+
+    /// Sets up a pipeline running a segmented image, as the linker lays it out, which
+    /// stores V0 at V1
+    ///
+    /// The code is in a read-only segment at address zero, and a word of data is in a
+    /// writable segment at `0x1000`.
+    fn linked_store(options: PipelineOptions) -> Pipelined {
+        let code: Vec<u8> = [
+            Instruction::Register(RegisterOp::Slr(WriteOp::Indirect {
+                volatile: false,
+                non_temporal: false,
+                address: V[1],
+                source: V[0],
+            })),
+            Instruction::Control(ControlOp::Halt),
+        ]
+        .iter()
+        .flat_map(|&i| encode(i).to_be_bytes())
+        .collect();
+        let image = image::encode(
+            0x0000_0000,
+            [
+                (
+                    0x0000_0000,
+                    Segment::READ_ONLY | Segment::EXECUTABLE,
+                    &code[..],
+                ),
+                (0x0000_1000, 0, &[0; 4][..]),
+            ],
+        );
+
+        let mut pipeline = load(&[], options);
+        pipeline
+            .memory_module_mut()
+            .memory_mut()
+            .load_image(&image)
+            .unwrap();
+        pipeline.registers_mut().v[0] = 0x1234_5678;
+
+        pipeline
+    }
+
+    #[test]
+    fn loaded_code_is_read_only() {
+        let mut pipeline = linked_store(PipelineOptions {
+            write_checks: true,
+            ..Default::default()
+        });
+        pipeline.registers_mut().v[1] = 0x0000_0004;
+
+        run(&mut pipeline);
+
+        assert_eq!(
+            pipeline.fault(),
+            Some(Fault::WriteProtected {
+                address: 0x0000_0004
+            })
+        );
+        assert_eq!(
+            pipeline.memory_module().memory().read_word(0x0000_0004),
+            encode(Instruction::Control(ControlOp::Halt))
+        );
+    }
+
+    #[test]
+    fn loaded_data_is_writable() {
+        let mut pipeline = linked_store(PipelineOptions {
+            write_checks: true,
+            ..Default::default()
+        });
+        pipeline.registers_mut().v[1] = 0x0000_1000;
+
+        run(&mut pipeline);
+        pipeline.memory_module_mut().immediate_writeback();
+
+        assert_eq!(pipeline.halt_reason(), Some(HaltReason::Halt));
+        assert_eq!(
+            pipeline.memory_module().memory().read_word(0x0000_1000),
+            0x1234_5678
+        );
+    }
+
+    #[test]
+    fn unprotected_code_is_writable() {
+        let mut pipeline = linked_store(PipelineOptions {
+            write_checks: true,
+            ..Default::default()
+        });
+        pipeline
+            .memory_module_mut()
+            .memory_mut()
+            .unprotect(0x0000_0004..0x0000_0008);
+        pipeline.registers_mut().v[1] = 0x0000_0004;
+
+        run(&mut pipeline);
+        pipeline.memory_module_mut().immediate_writeback();
+
+        assert_eq!(pipeline.fault(), None);
+        assert_eq!(
+            pipeline.memory_module().memory().read_word(0x0000_0004),
+            0x1234_5678
+        );
+    }
+
+    /// A program which stores V0 over the instruction at V1 a few instructions ahead,
+    /// which otherwise loads 1 into V2
+    fn overwrite_ahead_program() -> Vec<Instruction> {
+        [Instruction::Register(RegisterOp::Slr(WriteOp::Indirect {
+            volatile: false,
+            non_temporal: false,
+            address: V[1],
+            source: V[0],
+        }))]
+        .into_iter()
+        .chain([Instruction::Control(ControlOp::Nop); 5])
+        .chain([
+            Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                zero: true,
+                shift: 0,
+                immediate: 1,
+                destination: V[2],
+            })),
+            Instruction::Control(ControlOp::Halt),
+        ])
+        .collect()
+    }
+
+    #[test]
+    fn overwritten_code_is_fetched_again() {
+        let program = overwrite_ahead_program();
+        let pipelines: [Box<dyn Pipeline>; 2] = [
+            Box::new(load(&program, PipelineOptions::default())),
+            Box::new(crate::Unpipelined::new(memory_module(&program))),
+        ];
+
+        for mut pipeline in pipelines {
+            // The whole program starts out in the instruction cache
+            pipeline.memory_module_mut().warm(&[
+                0x0000_0000,
+                0x0000_0008,
+                0x0000_0010,
+                0x0000_0018,
+            ]);
+
+            pipeline.registers_mut().v[0] =
+                encode(Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                    zero: true,
+                    shift: 0,
+                    immediate: 2,
+                    destination: V[2],
+                })));
+            pipeline.registers_mut().v[1] = 0x0000_0018;
+
+            let mut clocks = 0;
+            while !matches!(pipeline.clock(1), ClockResult::Dry) {
+                clocks += 1;
+                assert!(clocks < 10_000, "The pipeline should have stopped");
+            }
+
+            assert_eq!(pipeline.halt_reason(), Some(HaltReason::Halt));
+            assert_eq!(pipeline.registers()[V[2]], 2);
+        }
+    }
+
+    #[test]
+    fn overwriting_the_next_instruction_is_a_hazard() {
+        // Stores V0 over the instruction after it, which otherwise loads 1 into V2
+        let program = [
+            Instruction::Register(RegisterOp::Slr(WriteOp::Indirect {
+                volatile: false,
+                non_temporal: false,
+                address: V[1],
+                source: V[0],
+            })),
+            Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                zero: true,
+                shift: 0,
+                immediate: 1,
+                destination: V[2],
+            })),
+            Instruction::Control(ControlOp::Halt),
+        ];
+        let checked = PipelineOptions {
+            smc_checks: true,
+            ..Default::default()
+        };
+
+        let cases = [
+            (PipelineOptions::default(), 0),
+            (checked, 1),
+            (
+                PipelineOptions {
+                    fetch_width: 2,
+                    fetch_queue_depth: 4,
+                    ..checked
+                },
+                1,
+            ),
+        ];
+
+        // A store which misses finishes writing after the fetch stage would resume
+        for ((options, hazards), warm) in cases
+            .into_iter()
+            .flat_map(|case| [(case, true), (case, false)])
+        {
+            let mut pipeline = load(&program, options);
+
+            if warm {
+                pipeline
+                    .memory_module_mut()
+                    .warm(&[0x0000_0000, 0x0000_0008]);
+            }
+
+            pipeline.registers_mut().v[0] =
+                encode(Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                    zero: true,
+                    shift: 0,
+                    immediate: 2,
+                    destination: V[2],
+                })));
+            pipeline.registers_mut().v[1] = 0x0000_0004;
+
+            run(&mut pipeline);
+
+            assert_eq!(pipeline.halt_reason(), Some(HaltReason::Halt));
+            assert_eq!(pipeline.smc_hazards(), hazards);
+            if hazards > 0 {
+                assert_eq!(pipeline.registers()[V[2]], 2);
+                assert_eq!(pipeline.retired_instructions(), 2);
+            }
+        }
+    }
+
+    #[test]
+    fn faults_call_their_handlers() {
+        let options = PipelineOptions {
+            stack_checks: true,
+            write_checks: true,
+            vector_table: Some(0x0000_0010),
+            ..Default::default()
+        };
+        let pipelines: [Box<dyn Pipeline>; 2] = [
+            Box::new(load(&fault_handlers(), options)),
+            Box::new(crate::Unpipelined::with_options(
+                memory_module(&fault_handlers()),
+                options,
+            )),
+        ];
+
+        for mut pipeline in pipelines {
+            let code = pipeline.memory_module().memory().read_word(0x0000_0004);
+            pipeline
+                .memory_module_mut()
+                .memory_mut()
+                .protect(0x0000_0000..0x0000_000C);
+
+            let mut clocks = 0;
+            while !matches!(pipeline.clock(1), ClockResult::Dry) {
+                clocks += 1;
+                assert!(clocks < 10_000, "The pipeline should have stopped");
+            }
+
+            // Each handler ran once, and returned to the instruction after its fault
+            assert_eq!(pipeline.halt_reason(), Some(HaltReason::Halt));
+            assert_eq!(pipeline.registers()[V[2]], 1);
+            assert_eq!(pipeline.registers()[V[3]], 1);
+            assert_eq!(pipeline.registers().sp, 0);
+            assert_eq!(
+                pipeline.memory_module().memory().read_word(0x0000_0004),
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn faults_in_leaf_routines_keep_their_return_address() {
+        let count = |register| {
+            Instruction::Integer(IntegerOp::Add(BinaryOp::Immediate(register, 1, register)))
+        };
+        let program = [
+            Instruction::Control(ControlOp::Jsr(Jump::Relative(0x10))),
+            count(V[4]),
+            Instruction::Control(ControlOp::Halt),
+            Instruction::Control(ControlOp::Halt),
+            // A leaf routine, which never saves LP, storing over the code
+            Instruction::Register(RegisterOp::Slr(WriteOp::Indirect {
+                volatile: false,
+                non_temporal: false,
+                address: V[1],
+                source: V[0],
+            })),
+            Instruction::Control(ControlOp::Ret),
+            // The vector table
+            Instruction::Control(ControlOp::Halt),
+            Instruction::Control(ControlOp::Halt),
+            Instruction::Control(ControlOp::Jmp(Jump::Relative(0x08))),
+            Instruction::Control(ControlOp::Halt),
+            // The handler
+            count(V[2]),
+            Instruction::Control(ControlOp::Ret),
+        ];
+        let options = PipelineOptions {
+            write_checks: true,
+            vector_table: Some(0x0000_0018),
+            ..Default::default()
+        };
+        let pipelines: [Box<dyn Pipeline>; 2] = [
+            Box::new(load(&program, options)),
+            Box::new(crate::Unpipelined::with_options(
+                memory_module(&program),
+                options,
+            )),
+        ];
+
+        for mut pipeline in pipelines {
+            pipeline
+                .memory_module_mut()
+                .memory_mut()
+                .protect(0x0000_0000..0x0000_0030);
+
+            let mut clocks = 0;
+            while !matches!(pipeline.clock(1), ClockResult::Dry) {
+                clocks += 1;
+                assert!(clocks < 10_000, "The pipeline should have stopped");
+            }
+
+            // The handler returned to the routine, which returned to its caller
+            assert_eq!(pipeline.halt_reason(), Some(HaltReason::Halt));
+            assert_eq!(pipeline.registers()[V[2]], 1);
+            assert_eq!(pipeline.registers()[V[4]], 1);
+            assert_eq!(pipeline.registers().lp, 0x0000_0004);
+            assert_eq!(pipeline.registers().sp, 0);
+        }
+    }
+
+    #[test]
+    fn syscalls_reach_the_host() {
+        let pipelines: [Box<dyn Pipeline>; 2] = [
+            Box::new(load(&echo_program(), PipelineOptions::default())),
+            Box::new(crate::Unpipelined::new(memory_module(&echo_program()))),
+        ];
+
+        for mut pipeline in pipelines {
+            let output = Arc::default();
+            pipeline.set_host(Box::new(MockHost {
+                input: b"Hello!".iter().copied().collect(),
+                output: Arc::clone(&output),
+            }));
+
+            let mut clocks = 0;
+            while !matches!(pipeline.clock(1), ClockResult::Dry) {
+                clocks += 1;
+                assert!(clocks < 10_000, "The pipeline should have stopped");
+            }
+
+            assert_eq!(pipeline.halt_reason(), Some(HaltReason::Halt));
+            assert_eq!(*output.lock().unwrap(), b"Hello!");
+            assert_eq!(pipeline.registers()[V[0]], syscall::FAILED);
+        }
+    }
+
+    #[test]
+    fn syscalls_without_a_host() {
+        let mut pipeline = load(&echo_program(), PipelineOptions::default());
+        run(&mut pipeline);
+
+        // The input has always ended, so the program stops at once
+        assert_eq!(pipeline.retired_instructions(), 4);
+        assert_eq!(pipeline.registers()[V[0]], syscall::FAILED);
+    }
+
+    #[test]
+    fn console_reaches_the_host() {
+        let output = Arc::<Mutex<Vec<u8>>>::default();
+        let console = || {
+            Box::new(
+                Console::new(memory_module(&console_program()), ZERO_PAGE | 0xFF00, 8).with_host(
+                    Box::new(MockHost {
+                        input: b"x".iter().copied().collect(),
+                        output: Arc::clone(&output),
+                    }),
+                ),
+            )
+        };
+        let pipelines: [Box<dyn Pipeline>; 2] = [
+            Box::new(Pipelined::new(console())),
+            Box::new(crate::Unpipelined::new(console())),
+        ];
+
+        for mut pipeline in pipelines {
+            output.lock().unwrap().clear();
+
+            let mut clocks = 0;
+            while !matches!(pipeline.clock(1), ClockResult::Dry) {
+                clocks += 1;
+                assert!(clocks < 10_000, "The pipeline should have stopped");
+            }
+
+            assert_eq!(*output.lock().unwrap(), b"Hi!\n");
+            assert_eq!(pipeline.registers()[V[1]], b'x' as Word);
+            assert_eq!(pipeline.registers()[V[2]], syscall::FAILED);
+
+            // Nothing reached the memory behind the console
+            let memory = pipeline.memory_module().memory();
+            assert_eq!(memory.read_word(ZERO_PAGE | 0xFF00), 0);
+        }
+    }
+
+    #[test]
+    fn misaligned_fetches_fault() {
+        let mut pipeline = misaligned_jump(PipelineOptions {
+            alignment_checks: true,
+            ..Default::default()
+        });
+
+        run(&mut pipeline);
+
+        assert_eq!(
+            pipeline.fault(),
+            Some(Fault::MisalignedFetch { pc: 0x0000_000D })
+        );
+        assert_eq!(pipeline.registers().pc, 0x0000_000D);
+    }
+
+    #[test]
+    fn halt_reason_reports_halts() {
+        let mut pipeline = countdown(PipelineOptions::default());
+
+        while !matches!(pipeline.clock(1), ClockResult::Dry) {
+            assert_eq!(pipeline.halt_reason(), None);
+        }
+
+        assert_eq!(pipeline.halt_reason(), Some(HaltReason::Halt));
+    }
+
+    #[test]
+    fn halt_reason_reports_faults() {
+        let mut pipeline = stack_loop(PipelineOptions {
+            stack_checks: true,
+            ..Default::default()
+        });
+
+        while !matches!(pipeline.clock(1), ClockResult::Dry) {
+            assert_eq!(pipeline.halt_reason(), None);
+        }
+
+        assert!(matches!(
+            pipeline.halt_reason(),
+            Some(HaltReason::Fault(Fault::StackOverflow { .. }))
+        ));
+    }
+
+    #[test]
+    fn halt_reason_matches_unpipelined() {
+        let options = PipelineOptions {
+            stack_checks: true,
+            ..Default::default()
+        };
+        let mut pipelined = stack_loop(options);
+        let mut unpipelined = crate::Unpipelined::with_options(
+            memory_module(&[
+                Instruction::Register(RegisterOp::Push(V[0])),
+                Instruction::Control(ControlOp::Jmp(Jump::Relative(-4))),
+            ]),
+            options,
+        );
+        unpipelined.registers_mut().sp = 0xFFE0;
+
+        run(&mut pipelined);
+        while !matches!(unpipelined.clock(1), ClockResult::Dry) {
+            assert_eq!(unpipelined.halt_reason(), None);
+        }
+
+        assert!(matches!(
+            unpipelined.halt_reason(),
+            Some(HaltReason::Fault(Fault::StackOverflow { .. }))
+        ));
+        assert_eq!(pipelined.halt_reason(), unpipelined.halt_reason());
+    }
+
+    #[test]
+    fn negative_comparison_immediates_set_flags() {
+        let values: [SWord; 6] = [0, 5, -5, -1, -0x4000, 0x8000];
+        let immediates: [SWord; 4] = [-5, -1, -0x4000, 7];
+
+        // Runs the comparison against V0, returning ZF and OF
+        let flags = |instruction, value: SWord| {
+            let mut pipeline = load(
+                &[
+                    Instruction::Integer(instruction),
+                    Instruction::Control(ControlOp::Halt),
+                ],
+                PipelineOptions::default(),
+            );
+            pipeline.registers_mut().v[0] = value as Word;
+            run(&mut pipeline);
+
+            (pipeline.registers().zf != 0, pipeline.registers().of != 0)
+        };
+
+        for value in values {
+            for immediate in immediates {
+                let (l, r) = (value as Word, immediate as Word);
+
+                assert_eq!(
+                    flags(IntegerOp::Cmp(CompOp::Immediate(V[0], r, false)), value),
+                    (l == r, l < r),
+                    "CMP {value}, {immediate}"
+                );
+                assert_eq!(
+                    flags(IntegerOp::Cmp(CompOp::Immediate(V[0], r, true)), value),
+                    (value == immediate, immediate > value),
+                    "CMP s {value}, {immediate}"
+                );
+                assert_eq!(
+                    flags(IntegerOp::Tst(TestOp::Immediate(V[0], r)), value),
+                    (l & r == l, false),
+                    "TST {value}, {immediate}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_unpipelined() {
+        let mut pipelined = countdown(PipelineOptions::default());
+        let mut unpipelined = crate::Unpipelined::with_options(
+            memory_module(&countdown_program()),
+            PipelineOptions::default(),
+        );
+
+        run(&mut pipelined);
+        while !matches!(unpipelined.clock(1), ClockResult::Dry) {}
+
+        // The pipelined processor fetches past the halt, so only PC may differ
+        assert!(pipelined
+            .registers()
+            .diff(unpipelined.registers())
+            .iter()
+            .all(|&(register, ..)| register == PC));
+        assert_eq!(
+            pipelined.memory_module().memory().checksum_all(),
+            unpipelined.memory_module().memory().checksum_all()
+        );
+
+        unpipelined.registers_mut().pc = pipelined.registers().pc;
+        unpipelined.registers_mut().v[3] = 7;
+        assert_eq!(
+            pipelined.registers().diff(unpipelined.registers()),
+            vec![(V[3], 0, 7)]
+        );
+    }
+
+    #[test]
+    fn multiply_latency_stalls() {
+        // The pipeline only runs dry once a fetch past the halt completes, so time the
+        // writeback of the product instead
+        let clocks_to_product = |options| {
+            let mut pipeline = multiply(options);
+            let mut clocks = 0;
+            while pipeline.registers().v[1] != 30 {
+                assert!(!matches!(pipeline.clock(1), ClockResult::Dry));
+                clocks += 1;
+            }
+            clocks
+        };
+
+        let single = clocks_to_product(PipelineOptions::default());
+        let slow = clocks_to_product(PipelineOptions {
+            latencies: Latencies {
+                multiply: 4,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        assert_eq!(slow, single + 3);
+    }
+
+    #[test]
+    fn cycle_counter_reads_bracket_latency() {
+        // Returns the clocks between two reads of the cycle counter either side of a
+        // multiply, with lines long enough that the fetches after the first all hit
+        let clocks_around_multiply = |multiply| {
+            let program: Vec<u8> = [
+                Instruction::Register(RegisterOp::Rdcyc(V[0])),
+                Instruction::Integer(IntegerOp::Mul(BinaryOp::Immediate(V[1], 3, V[2]))),
+                Instruction::Register(RegisterOp::Rdcyc(V[3])),
+                Instruction::Control(ControlOp::Halt),
+            ]
+            .into_iter()
+            .flat_map(|i| encode(i).to_be_bytes())
+            .collect();
+
+            let mut memory = Memory::new(4);
+            memory.set_page(0x0000_0000, &program);
+
+            let mut pipeline = Pipelined::with_options(
+                Box::new(SingleLevel::new(
+                    Box::new(Associative::new(5, 2)),
+                    Box::new(Associative::new(5, 2)),
+                    memory,
+                    10,
+                    2,
+                    false,
+                )),
+                PipelineOptions {
+                    latencies: Latencies {
+                        multiply,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            );
+            run(&mut pipeline);
+
+            let v = pipeline.registers().v;
+            assert_ne!(v[0], 0, "The counter should start counting from one");
+            v[3] - v[0]
+        };
+
+        // Each read executes a clock after the instruction before it finishes executing
+        for multiply in 1..=5 {
+            assert_eq!(clocks_around_multiply(multiply), multiply as Word + 1);
+        }
+    }
+
+    #[test]
+    fn independent_instructions_pass_in_flight_operations() {
+        // Returns the clocks taken to write back the quotient and the sum
+        let completion_clocks = |options| {
+            let mut pipeline = divide_then_add(options);
+            let (mut quotient, mut sum) = (None, None);
+            let mut clocks = 0;
+
+            while quotient.is_none() || sum.is_none() {
+                assert!(!matches!(pipeline.clock(1), ClockResult::Dry));
+                clocks += 1;
+
+                if pipeline.registers().v[2] == 0 {
+                    quotient.get_or_insert(clocks);
+                }
+                if pipeline.registers().v[4] == 1 {
+                    sum.get_or_insert(clocks);
+                }
+            }
+
+            (quotient.unwrap(), sum.unwrap())
+        };
+
+        let (quotient, sum) = completion_clocks(PipelineOptions::default());
+        assert!(sum > quotient, "{sum} should follow {quotient}");
+
+        let (quotient, sum) = completion_clocks(PipelineOptions {
+            max_in_flight: 1,
+            ..Default::default()
+        });
+        assert!(sum < quotient, "{sum} should precede {quotient}");
+    }
+
+    #[test]
+    fn in_flight_operations_preserve_results() {
+        let mut in_order = divide_then_add(PipelineOptions::default());
+        let mut stepped = divide_then_add(PipelineOptions {
+            max_in_flight: 1,
+            ..Default::default()
+        });
+        let mut skipped = divide_then_add(PipelineOptions {
+            max_in_flight: 1,
+            ..Default::default()
+        });
+
+        run(&mut in_order);
+        run(&mut stepped);
+        run_skipping(&mut skipped);
+
+        assert_eq!(in_order.registers().zf, 0);
+
+        for pipeline in [&stepped, &skipped] {
+            // The flags are those of the add, even though the divide finished last
+            assert!(pipeline
+                .registers()
+                .diff(in_order.registers())
+                .iter()
+                .all(|&(register, ..)| register == PC));
+            assert!(
+                (0..COUNT).all(|register| pipeline.locks.is_unlocked(register as _)),
+                "{:?}",
+                pipeline.locks
+            );
+        }
+    }
+
+    #[test]
+    fn overlapping_writes_wait_for_in_flight_operations() {
+        let mut pipeline = load(
+            &[
+                Instruction::FloatingPoint(FloatingPointOp::Fdiv(floating_point::BinaryOp {
+                    left: V[0],
+                    right: V[1],
+                    destination: V[2],
+                })),
+                Instruction::Integer(IntegerOp::Add(BinaryOp::Immediate(V[3], 1, V[2]))),
+                Instruction::Control(ControlOp::Halt),
+            ],
+            PipelineOptions {
+                max_in_flight: 1,
+                ..Default::default()
+            },
+        );
+
+        run(&mut pipeline);
+
+        assert_eq!(pipeline.registers().v[2], 1);
+    }
+
+    #[test]
+    fn division_by_zero_sets_overflow() {
+        for instruction in [
+            IntegerOp::Dvu(BinaryOp::Registers(V[0], V[1], V[2])),
+            IntegerOp::Dvs(BinaryOp::Registers(V[0], V[1], V[2])),
+            IntegerOp::Mod(BinaryOp::Registers(V[0], V[1], V[2])),
+        ] {
+            let mut pipeline = load(
+                &[
+                    Instruction::Integer(instruction),
+                    Instruction::Control(ControlOp::Halt),
+                ],
+                PipelineOptions::default(),
+            );
+            pipeline.registers_mut().v[0] = 7;
+            pipeline.registers_mut().v[2] = 3;
+            run(&mut pipeline);
+
+            assert_eq!(pipeline.registers().v[2], 0, "{instruction}");
+            assert_eq!(pipeline.registers().of, 1, "{instruction}");
+        }
+    }
+
+    #[test]
+    fn arbitrary_programs_never_panic() {
+        // A xorshift generator, so the programs are the same on every run
+        let mut state: Word = 0x2545_F491;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        let options = PipelineOptions {
+            stack_checks: true,
+            write_checks: true,
+            alignment_checks: true,
+            smc_checks: true,
+            vector_table: Some(0x100),
+            ..Default::default()
+        };
+
+        for _ in 0..500 {
+            let program: Vec<Instruction> = std::iter::repeat_with(&mut next)
+                .filter_map(|word| libseis::instruction_set::decode(word).ok())
+                .take(48)
+                .collect();
+
+            for options in [PipelineOptions::default(), options] {
+                let mut pipelined = load(&program, options);
+                let mut unpipelined =
+                    crate::Unpipelined::with_options(memory_module(&program), options);
+
+                for _ in 0..1000 {
+                    pipelined.clock(1);
+                    unpipelined.clock(1);
+                }
+            }
         }
     }
 }
@@ -0,0 +1,92 @@
+//! Counting of the instructions retired at each address, the branches taken between
+//! them, and the instructions retired of each kind, for finding the hot spots, the
+//! dynamic control flow, and the instruction mix of a program.
+
+use crate::{
+    stages::{Execute, Writeback},
+    EnergyCosts, PipelineOptions,
+};
+use libmem::module::MemoryModule;
+use libseis::types::Word;
+use std::collections::HashMap;
+
+/// Counts the instructions written back at each address, the taken branches written
+/// back between each pair of addresses, and the instructions written back with each
+/// mnemonic, along with the energy they used
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Profiler {
+    counts: HashMap<Word, u64>,
+    edges: HashMap<(Word, Word), u64>,
+    mix: HashMap<String, u64>,
+    /// The number of instructions written back when the counts were last updated
+    retired: usize,
+    /// Whether to count instructions, rather than only charge their energy
+    profile: bool,
+    costs: EnergyCosts,
+    /// The energy charged for the instructions written back
+    energy: f64,
+}
+
+impl Profiler {
+    /// Creates a profiler which counts instructions if
+    /// [profiling](PipelineOptions::profile), and charges them the
+    /// [energy costs](PipelineOptions::energy) of the options
+    pub(crate) fn with_options(options: &PipelineOptions) -> Self {
+        Self {
+            profile: options.profile,
+            costs: options.energy,
+            ..Default::default()
+        }
+    }
+
+    /// Counts the instructions written back since the last update
+    pub(crate) fn update(&mut self, execute: &mut Execute, writeback: &Writeback) {
+        let retired = writeback.retired();
+
+        if let Some(retiring) = execute.retiring_mut() {
+            for _ in self.retired..retired {
+                if let Some(instruction) = retiring.pop_front() {
+                    self.energy += self.costs.instruction(instruction.instruction);
+
+                    if !self.profile {
+                        continue;
+                    }
+
+                    let pc = instruction.pc;
+                    *self.counts.entry(pc).or_default() += 1;
+
+                    if let Some(target) = instruction.target {
+                        *self.edges.entry((pc, target)).or_default() += 1;
+                    }
+
+                    let instruction = instruction.instruction;
+                    let kind = format!("{}/{}", instruction.category(), instruction.mnemonic());
+                    *self.mix.entry(kind).or_default() += 1;
+                }
+            }
+        }
+
+        self.retired = retired;
+    }
+
+    /// Gets the number of instructions written back at each address
+    pub(crate) fn counts(&self) -> &HashMap<Word, u64> {
+        &self.counts
+    }
+
+    /// Gets the number of taken branches written back from each address to each target
+    pub(crate) fn edges(&self) -> &HashMap<(Word, Word), u64> {
+        &self.edges
+    }
+
+    /// Gets the number of instructions written back with each category and mnemonic
+    pub(crate) fn mix(&self) -> &HashMap<String, u64> {
+        &self.mix
+    }
+
+    /// Gets the energy charged for the instructions written back and the cache accesses
+    /// of `module`
+    pub(crate) fn energy(&self, module: &dyn MemoryModule) -> f64 {
+        self.energy + self.costs.memory(module)
+    }
+}
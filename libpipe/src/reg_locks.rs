@@ -4,7 +4,10 @@ use std::{
     fmt::Debug,
     ops::{Deref, DerefMut, Index, IndexMut},
 };
-use libseis::{registers::COUNT, types::Register};
+use libseis::{
+    registers::{RegisterFlags, COUNT},
+    types::Register,
+};
 use serde::Serialize;
 
 /// Registers by name
@@ -95,4 +98,11 @@ impl Locks {
     pub fn is_unlocked(&self, reg: Register) -> bool {
         self[reg] == 0
     }
+
+    /// Gets the set of registers with a lock count which is not zero
+    pub fn locked(&self) -> RegisterFlags {
+        (0..COUNT as Register)
+            .filter(|&reg| self.is_locked(reg))
+            .collect()
+    }
 }
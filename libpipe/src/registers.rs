@@ -4,12 +4,13 @@
 //! the registers by ID.
 //!
 //! This makes serialization and decoding trivially easy at runtime.
+//! [`DetailedRegisters`] serializes every way a register may be read at once.
 
 use libseis::{
-    registers::COUNT,
-    types::{Register, Word},
+    registers::{COUNT, NAME},
+    types::{Register, SWord, Word},
 };
-use serde::Serialize;
+use serde::{ser::SerializeMap, Serialize};
 use std::{
     fmt::Debug,
     ops::{Deref, DerefMut, Index, IndexMut},
@@ -57,6 +58,22 @@ impl Registers {
     pub fn iter(&self) -> std::slice::Iter<Word> {
         unsafe { self.by_id.iter() }
     }
+
+    /// Lists the registers whose values differ from those in `other`, as the register
+    /// ID followed by the value in `self` and the value in `other`
+    pub fn diff(&self, other: &Registers) -> Vec<(Register, Word, Word)> {
+        self.iter()
+            .zip(other.iter())
+            .enumerate()
+            .filter(|(_, (left, right))| left != right)
+            .map(|(id, (&left, &right))| (id as Register, left, right))
+            .collect()
+    }
+
+    /// Views the registers as their words, floats, and signed integers together
+    pub fn detailed(&self) -> DetailedRegisters {
+        DetailedRegisters(*self)
+    }
 }
 
 impl Default for Registers {
@@ -109,3 +126,73 @@ impl Serialize for Registers {
         unsafe { self.by_name.serialize(serializer) }
     }
 }
+
+/// The ways a single register may be read
+#[derive(Serialize, Debug, Clone, Copy)]
+struct Views {
+    word: Word,
+    float: f32,
+    signed: SWord,
+}
+
+/// Serializes each register by its [name](NAME) as its raw word, the [`f32`] with the
+/// same bits, and the signed integer with the same bits:
+///
+/// ```json
+/// { "V0": { "word": 3217031168, "float": -1.5, "signed": -1077936128 }, ... }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct DetailedRegisters(Registers);
+
+impl Serialize for DetailedRegisters {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(COUNT))?;
+
+        for (name, &word) in NAME.iter().zip(self.0.iter()) {
+            let views = Views {
+                word,
+                float: f32::from_bits(word),
+                signed: word as SWord,
+            };
+            map.serialize_entry(name, &views)?;
+        }
+
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use libseis::registers::{SP, V};
+
+    #[test]
+    fn detailed_views_share_bits() {
+        let mut registers = Registers::default();
+        registers[V[0]] = 1.5f32.to_bits();
+        registers[V[1]] = -7i32 as Word;
+        registers[SP] = 0xFFFF;
+
+        let detailed = serde_json::to_value(registers.detailed()).unwrap();
+
+        for (&name, &word) in NAME.iter().zip(registers.iter()) {
+            let views = &detailed[name];
+
+            assert_eq!(views["word"], word);
+            // JSON has no NaN, so serde_json writes them as null
+            let float = f32::from_bits(word);
+            if float.is_nan() {
+                assert!(views["float"].is_null());
+            } else {
+                assert_eq!(views["float"].as_f64(), Some(float as f64));
+            }
+            assert_eq!(views["signed"], word as SWord);
+        }
+
+        assert_eq!(detailed["V0"]["float"], 1.5);
+        assert_eq!(detailed["V1"]["signed"], -7);
+    }
+}
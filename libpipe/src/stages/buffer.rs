@@ -0,0 +1,112 @@
+//! Buffer stage. Holds the results of the execute stage for a clock
+//! before passing them on, lengthening the pipeline.
+
+use super::{execute::ExecuteResult, Clock, PipelineStage, Status};
+use crate::{Locks, Registers};
+use libmem::module::MemoryModule;
+use serde::Serialize;
+use std::mem::take;
+
+/// The state of a [`Buffer`]
+#[derive(Debug, Clone, Default)]
+pub enum State {
+    /// Awaiting the next result
+    #[default]
+    Idle,
+    /// Holding a result until the next stage accepts it
+    Holding(ExecuteResult),
+    /// This stage has passed a halt along
+    Halted,
+}
+use State::*;
+
+impl Serialize for State {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Idle => serializer.collect_map([("state", "idle")]),
+            Holding(_) => serializer.collect_map([("state", "holding")]),
+            Halted => serializer.collect_map([("state", "halted")]),
+        }
+    }
+}
+
+/// Represents an extra execute sub-stage, placed between the [execute](super::Execute)
+/// and [memory](super::Memory) stages
+#[derive(Debug, Default)]
+pub struct Buffer {
+    state: State,
+    forward: Option<ExecuteResult>,
+}
+
+impl Serialize for Buffer {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.state.serialize(serializer)
+    }
+}
+
+impl PipelineStage for Buffer {
+    type Prev = ExecuteResult;
+    type Next = ExecuteResult;
+    type State = State;
+
+    fn clock(
+        &mut self,
+        clock: Clock,
+        _: &mut Registers,
+        _: &mut Locks,
+        _: &mut dyn MemoryModule,
+    ) -> Clock {
+        match take(&mut self.state) {
+            Idle => clock.to_ready(),
+            Holding(result) if clock.is_ready() => {
+                self.state = if result.is_halt() { Halted } else { Idle };
+                self.forward = Some(result);
+                clock
+            }
+            Holding(result) => {
+                self.state = Holding(result);
+                clock.to_block()
+            }
+            Halted => {
+                self.state = Halted;
+                Clock::Halt
+            }
+        }
+    }
+
+    fn forward(&mut self, input: Status<Self::Prev>) -> Status<Self::Next> {
+        if matches!(self.state, Halted) && self.forward.is_none() {
+            return Status::Dry;
+        }
+
+        let (clocks, bubbles) = match input {
+            Status::Stall(clocks) => (clocks, true),
+            Status::Flow(result, b) => {
+                self.state = Holding(result);
+                (1, b)
+            }
+            Status::Ready(n, b) => (n, b),
+            Status::Squashed(clocks) => (clocks, true),
+            Status::Dry => (1, true),
+        };
+
+        match take(&mut self.forward) {
+            Some(result) => Status::Flow(result, bubbles),
+            None if matches!(self.state, Holding(ExecuteResult::Squash { .. })) => {
+                Status::Squashed(clocks)
+            }
+            None if matches!(self.state, Idle) => Status::Stall(clocks),
+            None => Status::Ready(clocks, bubbles),
+        }
+    }
+
+    fn get_state(&self) -> &State {
+        &self.state
+    }
+}
@@ -1,10 +1,13 @@
 //! Decode stage
 
-use crate::{reg_locks::Locks, regmap::RegMap, Clock, PipelineStage, Registers, Status};
+use crate::{
+    reg_locks::Locks, regmap::RegMap, Clock, Fault, PipelineOptions, PipelineStage, Registers,
+    Status,
+};
 use libmem::module::MemoryModule;
 use libseis::{
-    instruction_set::{decode, Info, Instruction},
-    registers::{RegisterFlags, BP, PC, SP},
+    instruction_set::{decode, Info, Instruction, IntegerOp, RegisterOp},
+    registers::{RegisterFlags, BP, PC, SP, V},
     types::Word,
 };
 use serde::Serialize;
@@ -18,6 +21,8 @@ pub enum State {
         word: Word,
         /// The location from which the value was fetched
         pc: Word,
+        /// The word fetched after this one, if they were fetched together
+        next: Option<Word>,
     },
     /// This stage is ready to forward a decoded instruction
     Ready {
@@ -25,6 +30,8 @@ pub enum State {
         word: Word,
         /// The location from which the value was fetched
         pc: Word,
+        /// The word fetched after this one, if they were fetched together
+        next: Option<Word>,
     },
     /// This stage is awaiting the next instruction
     #[default]
@@ -116,9 +123,44 @@ pub enum DecodeResult {
         regvals: RegMap,
         /// The registers that got locked
         reglocks: RegisterFlags,
+        /// The address the instruction was fetched from
+        pc: Word,
+    },
+    /// This stage is forwarding two independent instructions, which go through the
+    /// rest of the pipeline together
+    Pair {
+        /// The instructions that were decoded, in program order
+        instructions: [Instruction; 2],
+        /// The values of the registers read by each instruction
+        regvals: [RegMap; 2],
+        /// The registers that got locked, once for both instructions
+        reglocks: RegisterFlags,
+        /// The address the first instruction was fetched from
+        pc: Word,
     },
     /// This stage is forwarding a squashed instruction
     Squashed,
+    /// This stage is forwarding an instruction which raised a fault, in its place
+    Fault {
+        /// The fault which was raised
+        fault: Fault,
+        /// The address the instruction was fetched from
+        pc: Word,
+    },
+}
+
+/// Returns true if an instruction only writes registers other than PC, in a single
+/// clock, so that it may be [paired](DecodeResult::Pair) with another
+fn is_pairable(instruction: Instruction) -> bool {
+    use IntegerOp::{Dvs, Dvu, Mod, Mul};
+
+    match instruction {
+        Instruction::Integer(Mul(_) | Dvu(_) | Dvs(_) | Mod(_)) => false,
+        Instruction::Integer(_) | Instruction::Register(RegisterOp::Ldr(_)) => {
+            !instruction.get_write_regs().has_register(PC)
+        }
+        _ => false,
+    }
 }
 
 /// The decode stage
@@ -126,6 +168,11 @@ pub enum DecodeResult {
 pub struct Decode {
     state: State,
     forward: Option<DecodeResult>,
+    /// The registers which instructions may not access
+    disabled: RegisterFlags,
+    /// Whether faults call their handlers, rather than stopping the pipeline
+    trapped: bool,
+    fault: Option<Fault>,
 }
 
 impl Serialize for Decode {
@@ -138,10 +185,92 @@ impl Serialize for Decode {
 }
 
 impl Decode {
+    /// Creates a decode stage which faults on accesses to the variable registers not
+    /// [enabled](PipelineOptions::registers) by the provided options, leaving the fault
+    /// to its handler if they have a [vector table](PipelineOptions::vector_table)
+    pub fn with_options(options: &PipelineOptions) -> Self {
+        let all = RegisterFlags::variable(V.len());
+        let enabled = RegisterFlags::variable(options.registers.unwrap_or(V.len()));
+
+        Self {
+            disabled: RegisterFlags(all.0 & !enabled.0),
+            trapped: options.vector_table.is_some(),
+            ..Default::default()
+        }
+    }
+
+    /// Gets the fault raised by an instruction, if it does not decode or accesses a
+    /// disabled register
+    fn check(&self, word: Word, pc: Word) -> Result<Instruction, Fault> {
+        let instruction: Instruction =
+            decode(word).map_err(|_| Fault::IllegalInstruction { word, pc })?;
+
+        let accessed = instruction.get_write_regs().0 | instruction.get_read_regs().0;
+        match RegisterFlags(accessed & self.disabled.0).registers().next() {
+            Some(register) => Err(Fault::DisabledRegister { register, pc }),
+            None => Ok(instruction),
+        }
+    }
+
+    /// Gets the instruction in `word`, fetched from `pc` after `first`, if the two may go
+    /// through the pipeline together: neither may take more than a clock or do more than
+    /// write registers, and the second may not read a register written by the first
+    fn pair(
+        &self,
+        first: Instruction,
+        word: Word,
+        pc: Word,
+        reg_locks: &Locks,
+    ) -> Option<Instruction> {
+        let second = self.check(word, pc).ok()?;
+        let reads = second.get_read_regs();
+
+        (is_pairable(first)
+            && is_pairable(second)
+            && reads.0 & first.get_write_regs().0 == 0
+            && reads.registers().all(|reg| reg_locks.is_unlocked(reg)))
+        .then_some(second)
+    }
+
+    /// Reads the values of the registers read by an instruction fetched from `pc`
+    fn read(registers: &Registers, reads: RegisterFlags, pc: Word) -> RegMap {
+        reads
+            .registers()
+            .map(|r| {
+                if r == PC {
+                    // PC must equal location of where instruction was fetched -- always one word behind
+                    (PC, pc)
+                } else if r == SP || r == BP {
+                    (r, (registers[r] & 0x0000_FFFF) | 0x0001_0000)
+                } else {
+                    (r, registers[r])
+                }
+            })
+            .collect()
+    }
+
     /// Gets the state of this stage
     pub fn get_state(&self) -> &State {
         &self.state
     }
+
+    /// Gets the fault raised by this stage, if any
+    pub fn fault(&self) -> Option<Fault> {
+        self.fault
+    }
+
+    /// Gets the addresses of the instructions held by this stage, oldest first
+    pub(crate) fn in_flight(&self) -> Vec<Word> {
+        match self.state {
+            Decoding { pc, next, .. } | Ready { pc, next, .. } => {
+                [Some(pc), next.map(|_| pc.wrapping_add(4))]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
 }
 
 impl PipelineStage for Decode {
@@ -163,48 +292,95 @@ impl PipelineStage for Decode {
         } else if clock.is_squash() {
             self.forward = None;
             self.state = Squashed;
+            self.fault = None;
             clock
         } else if self.state.is_halted() {
             Clock::Halt
         } else {
-            if let Decoding { word, pc } = self.state {
-                self.state = Ready { word, pc }
+            if let Decoding { word, pc, next } = self.state {
+                self.state = Ready { word, pc, next }
             }
 
             match self.state {
-                Ready { word, pc } => {
+                Ready { word, pc, next } => {
                     if clock.is_ready() {
-                        let instruction: Instruction = decode(word).unwrap_or_default();
+                        let instruction = match self.check(word, pc) {
+                            Ok(instruction) => instruction,
+                            Err(fault) => {
+                                // The execute stage stops the pipeline or calls the
+                                // handler in place of the instruction. A squash clears
+                                // the fault, as it was decoded down a path never taken.
+                                if !self.trapped {
+                                    self.fault = Some(fault);
+                                }
+
+                                self.forward = Some(DecodeResult::Fault { fault, pc });
+                                self.state = Idle;
+                                return clock.to_ready();
+                            }
+                        };
 
                         let write = instruction.get_write_regs();
                         let reads = instruction.get_read_regs();
 
                         if reads.registers().all(|reg| reg_locks.is_unlocked(reg)) {
+                            let next = next.map(|word| (word, pc.wrapping_add(4)));
+                            let paired = next
+                                .and_then(|(word, pc)| self.pair(instruction, word, pc, reg_locks));
+
                             for reg in write {
                                 reg_locks[reg] += 1;
                             }
 
+                            if let Some(paired) = paired {
+                                // Registers written by both instructions are locked once, as
+                                // the pair is written back at once
+                                let both = RegisterFlags(write.0 | paired.get_write_regs().0);
+                                for reg in RegisterFlags(both.0 & !write.0) {
+                                    reg_locks[reg] += 1;
+                                }
+
+                                self.forward = Some(DecodeResult::Pair {
+                                    instructions: [instruction, paired],
+                                    regvals: [
+                                        Self::read(registers, reads, pc),
+                                        Self::read(
+                                            registers,
+                                            paired.get_read_regs(),
+                                            pc.wrapping_add(4),
+                                        ),
+                                    ],
+                                    reglocks: both,
+                                    pc,
+                                });
+                                self.state = Idle;
+
+                                return clock.to_ready();
+                            }
+
                             self.forward = Some(DecodeResult::Forward {
                                 instruction,
-                                regvals: reads
-                                    .registers()
-                                    .map(|r| {
-                                        if r == PC {
-                                            // PC must equal location of where instruction was fetched -- always one word behind
-                                            (PC, pc)
-                                        } else if r == SP || r == BP {
-                                            (r, (registers[r] & 0x0000_FFFF) | 0x0001_0000)
-                                        } else {
-                                            (r, registers[r])
-                                        }
-                                    })
-                                    .collect(),
+                                regvals: Self::read(registers, reads, pc),
                                 reglocks: write,
+                                pc,
                             });
 
-                            self.state = Idle;
-
-                            clock.to_ready()
+                            match next {
+                                Some((word, pc)) => {
+                                    // The instruction fetched with this one is decoded on its
+                                    // own, holding back the fetch stage until then
+                                    self.state = Ready {
+                                        word,
+                                        pc,
+                                        next: None,
+                                    };
+                                    clock.to_block()
+                                }
+                                None => {
+                                    self.state = Idle;
+                                    clock.to_ready()
+                                }
+                            }
                         } else {
                             clock.to_block()
                         }
@@ -239,7 +415,25 @@ impl PipelineStage for Decode {
         } else {
             let (clocks, bubbles) = match input {
                 Status::Flow(FetchResult::Ready { word, pc }, b) => {
-                    self.state = Decoding { word, pc };
+                    self.state = Decoding {
+                        word,
+                        pc,
+                        next: None,
+                    };
+                    (1, b)
+                }
+                Status::Flow(
+                    FetchResult::Pair {
+                        words: [word, next],
+                        pc,
+                    },
+                    b,
+                ) => {
+                    self.state = Decoding {
+                        word,
+                        pc,
+                        next: Some(next),
+                    };
                     (1, b)
                 }
                 Status::Flow(FetchResult::Squashed, b) => {
@@ -326,7 +520,8 @@ mod test {
             decode,
             Decode {
                 forward: None,
-                state: State::Idle
+                state: State::Idle,
+                ..
             }
         ));
 
@@ -352,7 +547,8 @@ mod test {
                 state: State::Decoding {
                     word: 0x0000_0000,
                     ..
-                }
+                },
+                ..
             }
         ));
 
@@ -370,6 +566,7 @@ mod test {
             Decode {
                 forward: Some(_),
                 state: State::Idle,
+                ..
             }
         ));
 
@@ -380,7 +577,8 @@ mod test {
             Status::Flow(DecodeResult::Forward {
                 instruction: Instruction::Control(ControlOp::Nop), // Nop
                 regvals, // No register values
-                reglocks: RegisterFlags(0) // No register locks
+                reglocks: RegisterFlags(0), // No register locks
+                pc: 0
             }, _) if regvals.len() == 0
         ));
     }
@@ -5,16 +5,20 @@ mod resolver;
 mod tests;
 
 use super::decode::DecodeResult;
-use crate::{reg_locks::Locks, regmap::RegMap, Clock, PipelineStage, Registers, Status};
+use crate::{
+    reg_locks::Locks, regmap::RegMap, Clock, Fault, PipelineOptions, PipelineStage, Registers,
+    Status,
+};
 use libmem::module::MemoryModule;
 use libseis::{
-    instruction_set::Instruction,
-    registers::RegisterFlags,
+    instruction_set::{Instruction, RegisterOp},
+    pages::{PAGE_SIZE, STACK_PAGE},
+    registers::{get_name, RegisterFlags, COUNT, EPS, INF, NAN, OF, ZF},
     types::{Byte, Register, Short, Word},
 };
 use resolver::Resolver;
 use serde::Serialize;
-use std::mem::take;
+use std::{collections::VecDeque, mem::take};
 
 /// Represents the steps that must be taken by the next stage of the pipeline
 /// to complete an instruction
@@ -71,6 +75,13 @@ pub enum ExecuteResult {
         /// INF register state
         inf: bool,
     },
+    /// Write a value back to the register without setting the flag registers
+    WriteRegNoStatus {
+        /// The destination register
+        destination: Register,
+        /// What to write to the destination register
+        value: Word,
+    },
     /// Write back a sequence of bits to the status registers
     WriteStatus {
         /// ZF register state
@@ -94,6 +105,8 @@ pub enum ExecuteResult {
         value: Byte,
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted
+        non_temporal: bool,
     },
     /// Write a value from a register to a location in memory
     ///
@@ -105,6 +118,8 @@ pub enum ExecuteResult {
         value: Short,
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted
+        non_temporal: bool,
     },
     /// Write a value from a register to a location in memory
     WriteMemWord {
@@ -114,6 +129,8 @@ pub enum ExecuteResult {
         value: Word,
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted
+        non_temporal: bool,
     },
     /// Read a byte from a location in memory into a register
     ReadMemByte {
@@ -123,6 +140,8 @@ pub enum ExecuteResult {
         destination: Register,
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted
+        non_temporal: bool,
     },
     /// Read a short from a location in memory into a register
     ReadMemShort {
@@ -132,6 +151,8 @@ pub enum ExecuteResult {
         destination: Register,
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted
+        non_temporal: bool,
     },
     /// Read a word from a location in memory into a register
     ReadMemWord {
@@ -141,6 +162,8 @@ pub enum ExecuteResult {
         destination: Register,
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted
+        non_temporal: bool,
     },
     /// Read a register from the stack
     ReadRegStack {
@@ -171,6 +194,97 @@ pub enum ExecuteResult {
         /// The current stack pointer value
         sp: Word,
     },
+    /// Ask the host to perform a system call
+    Syscall {
+        /// The call to perform
+        number: Word,
+        /// The argument of the call
+        argument: Word,
+    },
+    /// Write back the registers of two instructions which executed together
+    Pair {
+        /// The registers written by the first instruction
+        first: RegisterWrite,
+        /// The registers written by the second instruction
+        second: RegisterWrite,
+        /// The set of all register locks owned by the two instructions
+        regs: RegisterFlags,
+        /// The second instruction, which was fetched after the first
+        instruction: Instruction,
+    },
+    /// Abandon an instruction which faulted and call the handler of the fault
+    Trap {
+        /// The fault which was raised
+        fault: Fault,
+        /// The entry in the vector table to call
+        address: Word,
+        /// The address of the instruction after the one which faulted
+        link: Word,
+        /// The set of all register locks owned by the abandoned instruction
+        regs: RegisterFlags,
+    },
+}
+
+/// The registers written by one instruction of a [pair](ExecuteResult::Pair)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RegisterWrite {
+    /// The register written and its new value, if any
+    pub register: Option<(Register, Word)>,
+    /// The new state of ZF, OF, EPS, NAN, and INF, if they are written
+    pub flags: Option<[bool; 5]>,
+}
+
+impl From<ExecuteResult> for RegisterWrite {
+    fn from(result: ExecuteResult) -> Self {
+        // Only instructions which do nothing but write registers are paired
+        match result {
+            ExecuteResult::WriteReg {
+                destination,
+                value,
+                zf,
+                of,
+                eps,
+                nan,
+                inf,
+            } => Self {
+                register: Some((destination, value)),
+                flags: Some([zf, of, eps, nan, inf]),
+            },
+            ExecuteResult::WriteRegNoStatus { destination, value } => Self {
+                register: Some((destination, value)),
+                flags: None,
+            },
+            ExecuteResult::WriteStatus {
+                zf,
+                of,
+                eps,
+                nan,
+                inf,
+            } => Self {
+                register: None,
+                flags: Some([zf, of, eps, nan, inf]),
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+impl Serialize for RegisterWrite {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry(
+            "destination",
+            &self.register.map(|(register, _)| get_name(register)),
+        )?;
+        map.serialize_entry("value", &self.register.map(|(_, value)| value))?;
+        map.serialize_entry("flags", &self.flags)?;
+        map.end()
+    }
 }
 
 impl ExecuteResult {
@@ -179,7 +293,7 @@ impl ExecuteResult {
     pub fn should_squash(&self) -> bool {
         matches!(
             self,
-            Self::JumpTo { .. } | Self::Subroutine { .. } | Self::Return { .. }
+            Self::JumpTo { .. } | Self::Subroutine { .. } | Self::Return { .. } | Self::Trap { .. }
         )
     }
 
@@ -188,6 +302,50 @@ impl ExecuteResult {
     pub fn is_halt(&self) -> bool {
         matches!(self, ExecuteResult::Halt)
     }
+
+    /// Gets the fault raised if this is a stack operation which would leave the stack page
+    pub(crate) fn stack_fault(&self) -> Option<Fault> {
+        let offset = |sp: Word| sp.wrapping_sub(STACK_PAGE) as usize;
+
+        // Every stack operation moves the stack pointer by a single word. A push or call
+        // which would move it to the end of the page faults there, since it would wrap
+        // to the start of the page.
+        match *self {
+            ExecuteResult::WriteRegStack { sp, .. } | ExecuteResult::Subroutine { sp, .. }
+                if offset(sp) + 4 >= PAGE_SIZE =>
+            {
+                Some(Fault::StackOverflow { sp })
+            }
+            ExecuteResult::ReadRegStack { sp, .. }
+            | ExecuteResult::PopStack { sp }
+            | ExecuteResult::Return { bp: sp, .. }
+                if offset(sp) < 4 =>
+            {
+                Some(Fault::StackUnderflow { sp })
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the address and the number of bytes written, if this is a store
+    pub(crate) fn store(&self) -> Option<(Word, Word)> {
+        match *self {
+            ExecuteResult::WriteMemByte { address, .. } => Some((address, 1)),
+            ExecuteResult::WriteMemShort { address, .. } => Some((address, 2)),
+            ExecuteResult::WriteMemWord { address, .. } => Some((address, 4)),
+            _ => None,
+        }
+    }
+
+    /// Gets the fault raised if this is a store to a read-only segment of memory
+    fn write_fault(&self, memory: &dyn MemoryModule) -> Option<Fault> {
+        let (address, length) = self.store()?;
+
+        memory
+            .memory()
+            .is_read_only(address, length)
+            .then_some(Fault::WriteProtected { address })
+    }
 }
 
 /// Represents the current state of the execute stage
@@ -206,6 +364,18 @@ pub enum State {
         rvals: RegMap,
         /// The number of clocks required before the instruction is finished executing
         clocks: usize,
+        /// The address the instruction was fetched from
+        pc: Word,
+        /// The instruction after this one and the values it read, if the two are
+        /// executed together
+        paired: Option<(Instruction, RegMap)>,
+    },
+    /// Replacing an instruction which raised a fault in the decode stage
+    Faulted {
+        /// The fault which was raised
+        fault: Fault,
+        /// The address the instruction was fetched from
+        pc: Word,
     },
     /// This stage is ready to forward a result
     Ready {
@@ -213,6 +383,10 @@ pub enum State {
         result: ExecuteResult,
         /// The registers owned by this instruction
         wregs: RegisterFlags,
+        /// The address the instruction was fetched from
+        pc: Word,
+        /// The instruction which produced the result
+        instruction: Instruction,
     },
     /// This stage has been squashed and is waiting to send the signal forward
     Squashed {
@@ -242,13 +416,29 @@ impl Serialize for State {
                 wregs,
                 rvals,
                 clocks,
+                pc,
+                paired,
             } => {
-                let mut map = serializer.serialize_map(Some(5))?;
+                let mut map = serializer.serialize_map(Some(7))?;
                 map.serialize_entry("state", "executing")?;
                 map.serialize_entry("instruction", &instruction.to_string())?;
                 map.serialize_entry("write_regs", wregs)?;
                 map.serialize_entry("reg_values", rvals)?;
                 map.serialize_entry("clocks", clocks)?;
+                map.serialize_entry("pc", pc)?;
+                map.serialize_entry(
+                    "paired",
+                    &paired
+                        .as_ref()
+                        .map(|(instruction, _)| instruction.to_string()),
+                )?;
+                map.end()
+            }
+            Faulted { fault, pc } => {
+                let mut map = serializer.serialize_map(Some(3))?;
+                map.serialize_entry("state", "faulted")?;
+                map.serialize_entry("fault", fault)?;
+                map.serialize_entry("pc", pc)?;
                 map.end()
             }
             Ready { wregs, .. } => {
@@ -298,11 +488,353 @@ impl State {
     }
 }
 
+/// The number of clocks for which multi-cycle instructions hold the execute stage
+///
+/// By default, every operation takes a single clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Latencies {
+    /// Integer multiplication (`MUL`)
+    pub multiply: usize,
+    /// Integer division (`DVU`, `DVS`, and `MOD`)
+    pub divide: usize,
+    /// Floating-point operations other than division
+    pub float: usize,
+    /// Floating-point division (`FDIV`, `FMOD`, and `FREC`)
+    pub float_divide: usize,
+}
+
+impl Default for Latencies {
+    fn default() -> Self {
+        Self {
+            multiply: 1,
+            divide: 1,
+            float: 1,
+            float_divide: 1,
+        }
+    }
+}
+
+/// The flag registers, which are written by every integer and floating-point operation
+const FLAGS: RegisterFlags = RegisterFlags(1 << ZF | 1 << OF | 1 << EPS | 1 << NAN | 1 << INF);
+
+/// Returns true if the two sets of registers share a register other than the flags
+fn overlaps(left: RegisterFlags, right: RegisterFlags) -> bool {
+    left.0 & right.0 & !FLAGS.0 != 0
+}
+
+/// A multi-cycle operation which no longer holds the execute stage
+#[derive(Debug, Clone)]
+struct InFlight {
+    /// The instruction being executed
+    instruction: Instruction,
+    /// The registers owned by this instruction
+    wregs: RegisterFlags,
+    /// The values read by the decode stage
+    rvals: RegMap,
+    /// The number of clocks required before the instruction is finished executing
+    clocks: usize,
+    /// The address the instruction was fetched from
+    pc: Word,
+    /// Whether a later instruction has already been issued that writes the flags,
+    /// in which case the flags computed by this instruction are discarded
+    superseded: bool,
+}
+
+impl InFlight {
+    /// Computes the result of the operation, releasing the locks on the flags if they
+    /// were superseded by a later instruction
+    fn complete(self, locks: &mut Locks) -> ExecuteResult {
+        let result = self.instruction.execute(self.rvals);
+
+        if !self.superseded {
+            return result;
+        }
+
+        for reg in self
+            .wregs
+            .registers()
+            .filter(|&reg| FLAGS.has_register(reg))
+        {
+            locks[reg] -= 1;
+        }
+
+        match result {
+            ExecuteResult::WriteReg {
+                destination, value, ..
+            } => ExecuteResult::WriteRegNoStatus { destination, value },
+            ExecuteResult::WriteStatus { .. } => ExecuteResult::Nop,
+            result => result,
+        }
+    }
+}
+
 /// Represents the execute pipeline stage
 #[derive(Debug, Default)]
 pub struct Execute {
     state: State,
     forward: Option<ExecuteResult>,
+    latencies: Latencies,
+    /// Multi-cycle operations issued ahead of the instruction in [`state`](Self::state),
+    /// oldest first
+    in_flight: Vec<InFlight>,
+    max_in_flight: usize,
+    stack_checks: bool,
+    write_checks: bool,
+    vector_table: Option<Word>,
+    /// The number of clocks the processor has run for, as read by [`RegisterOp::Rdcyc`]
+    clocks: usize,
+    /// The instructions forwarded by this stage which have yet to be written back,
+    /// oldest first, if [profiling](PipelineOptions::profile) or charging
+    /// [energy](PipelineOptions::energy) for them
+    retiring: Option<VecDeque<Retiring>>,
+}
+
+/// An instruction forwarded by the execute stage which has yet to be written back
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Retiring {
+    /// The address the instruction was fetched from
+    pub pc: Word,
+    /// Where the instruction branched to, if it is a branch which was taken
+    pub target: Option<Word>,
+    pub instruction: Instruction,
+}
+
+impl Execute {
+    /// Creates an execute stage which uses the [latencies](PipelineOptions::latencies)
+    /// and [in-flight operations](PipelineOptions::max_in_flight) of the provided options,
+    /// trapping faults if they have a [vector table](PipelineOptions::vector_table)
+    pub fn with_options(options: &PipelineOptions) -> Self {
+        Self {
+            latencies: options.latencies,
+            max_in_flight: options.max_in_flight,
+            stack_checks: options.stack_checks,
+            write_checks: options.write_checks,
+            vector_table: options.vector_table,
+            retiring: (options.profile || options.energy.charges_instructions())
+                .then(VecDeque::new),
+            ..Default::default()
+        }
+    }
+
+    /// Gets the instructions forwarded by this stage which have yet to be written back,
+    /// oldest first, if profiling or charging energy for them
+    ///
+    /// Every stage after this one keeps instructions in order, so the oldest is the next
+    /// instruction to retire.
+    pub(crate) fn retiring_mut(&mut self) -> Option<&mut VecDeque<Retiring>> {
+        self.retiring.as_mut()
+    }
+
+    /// Gets the result this stage will forward once every stage has been clocked, if any
+    pub(crate) fn pending(&self) -> Option<&ExecuteResult> {
+        self.forward.as_ref()
+    }
+
+    /// Forwards the result of `instruction`, fetched from `pc`
+    fn send(&mut self, result: ExecuteResult, pc: Word, instruction: Instruction) {
+        // Neither squashed instructions, those abandoned by a trap, nor the halt which
+        // stops the pipeline are written back
+        if let Some(retiring) = &mut self.retiring {
+            // Branches which are not taken resolve to nothing, so every jump here is taken
+            let target = match result {
+                ExecuteResult::Squash { .. } | ExecuteResult::Trap { .. } | ExecuteResult::Halt => {
+                    None
+                }
+                ExecuteResult::JumpTo { address } | ExecuteResult::Subroutine { address, .. } => {
+                    Some(Some(address))
+                }
+                ExecuteResult::Return { link, .. } => Some(Some(link)),
+                _ => Some(None),
+            };
+
+            if let Some(target) = target {
+                retiring.push_back(Retiring {
+                    pc,
+                    target,
+                    instruction,
+                });
+            }
+
+            if let ExecuteResult::Pair {
+                instruction: paired,
+                ..
+            } = result
+            {
+                retiring.push_back(Retiring {
+                    pc: pc.wrapping_add(4),
+                    target: None,
+                    instruction: paired,
+                });
+            }
+        }
+
+        self.forward = Some(result);
+    }
+
+    /// Sets the number of clocks the processor has run for, including the clocks
+    /// about to be applied to this stage
+    pub(crate) fn set_clocks(&mut self, clocks: usize) {
+        self.clocks = clocks;
+    }
+
+    /// Executes an instruction, reading the cycle counter for [`RegisterOp::Rdcyc`]
+    fn resolve(&self, instruction: Instruction, rvals: RegMap) -> ExecuteResult {
+        match instruction {
+            Instruction::Register(RegisterOp::Rdcyc(destination))
+                if destination < COUNT as Register =>
+            {
+                // The counter is only as wide as a register, so it wraps around
+                ExecuteResult::WriteRegNoStatus {
+                    destination,
+                    value: self.clocks as Word,
+                }
+            }
+            _ => instruction.execute(rvals),
+        }
+    }
+
+    /// Replaces a result which would fault in the memory stage with a trap to the
+    /// handler of the fault, if there is a vector table to find it in
+    fn trap(
+        &self,
+        result: ExecuteResult,
+        pc: Word,
+        wregs: RegisterFlags,
+        memory: &dyn MemoryModule,
+    ) -> ExecuteResult {
+        if self.vector_table.is_none() {
+            return result;
+        }
+
+        let fault = self
+            .stack_checks
+            .then(|| result.stack_fault())
+            .flatten()
+            .or_else(|| {
+                self.write_checks
+                    .then(|| result.write_fault(memory))
+                    .flatten()
+            });
+
+        fault
+            .and_then(|fault| self.vector(fault, pc, wregs))
+            .unwrap_or(result)
+    }
+
+    /// Gets the trap to the handler of `fault`, raised by the instruction at `pc`, if
+    /// there is a vector table to find it in
+    fn vector(&self, fault: Fault, pc: Word, wregs: RegisterFlags) -> Option<ExecuteResult> {
+        self.vector_table.map(|base| ExecuteResult::Trap {
+            fault,
+            address: base.wrapping_add(4 * fault.vector()),
+            link: pc.wrapping_add(4),
+            regs: wregs,
+        })
+    }
+
+    /// Forwards a result which has finished executing, or holds it until it may be,
+    /// signalling a squash at once if the result requires one
+    fn finish(
+        &mut self,
+        clock: Clock,
+        result: ExecuteResult,
+        wregs: RegisterFlags,
+        pc: Word,
+        instruction: Instruction,
+    ) -> Clock {
+        let squash = result.should_squash();
+
+        if clock.is_ready() && !self.waits_for_in_flight(&result, wregs) {
+            let is_halt = result.is_halt();
+
+            self.send(result, pc, instruction);
+            if is_halt {
+                self.state = Halted;
+            } else {
+                self.state = Idle;
+            }
+
+            if squash {
+                clock.to_squash()
+            } else {
+                clock.to_ready()
+            }
+        } else {
+            self.state = Ready {
+                result,
+                wregs,
+                pc,
+                instruction,
+            };
+
+            if squash {
+                clock.to_squash()
+            } else {
+                clock.to_block()
+            }
+        }
+    }
+
+    /// Returns true if a result must wait for the operations in flight to complete
+    /// before it is forwarded, either because it writes one of their registers or
+    /// because it would stop the pipeline before they are written back
+    fn waits_for_in_flight(&self, result: &ExecuteResult, wregs: RegisterFlags) -> bool {
+        (result.is_halt() && !self.in_flight.is_empty())
+            || self.in_flight.iter().any(|op| overlaps(op.wregs, wregs))
+    }
+
+    /// Accepts an instruction from the decode stage, along with the instruction paired
+    /// with it if any, issuing it as an operation in flight if it may complete out of order
+    fn issue(
+        &mut self,
+        instruction: Instruction,
+        wregs: RegisterFlags,
+        rvals: RegMap,
+        pc: Word,
+        paired: Option<(Instruction, RegMap)>,
+    ) -> usize {
+        let clocks = instruction.clock_requirement(&self.latencies);
+
+        // Flags written by earlier operations in flight would be overwritten by this
+        // instruction in order, so they must not be written back after it
+        if wregs.0 & FLAGS.0 != 0 {
+            for op in &mut self.in_flight {
+                op.superseded = true;
+            }
+        }
+
+        let independent = !self.in_flight.iter().any(|op| overlaps(op.wregs, wregs));
+
+        if clocks > 1
+            && independent
+            && self.in_flight.len() < self.max_in_flight
+            && matches!(
+                instruction,
+                Instruction::Integer(_) | Instruction::FloatingPoint(_)
+            )
+        {
+            self.in_flight.push(InFlight {
+                instruction,
+                wregs,
+                rvals,
+                clocks,
+                pc,
+                superseded: false,
+            });
+            // The next instruction may enter on the following clock
+            1
+        } else {
+            self.state = State::Executing {
+                instruction,
+                wregs,
+                rvals,
+                clocks,
+                pc,
+                paired,
+            };
+            clocks
+        }
+    }
 }
 
 impl Serialize for Execute {
@@ -321,11 +853,27 @@ impl PipelineStage for Execute {
 
     fn clock(
         &mut self,
-        clock: Clock,
+        mut clock: Clock,
         _: &mut Registers,
-        _: &mut Locks,
-        _: &mut dyn MemoryModule,
+        locks: &mut Locks,
+        memory: &mut dyn MemoryModule,
     ) -> Clock {
+        for op in &mut self.in_flight {
+            op.clocks = op.clocks.saturating_sub(clock.clocks());
+        }
+
+        if matches!(clock, Clock::Ready(_)) {
+            if let Some(index) = self.in_flight.iter().position(|op| op.clocks == 0) {
+                let op = self.in_flight.remove(index);
+                let (pc, instruction) = (op.pc, op.instruction);
+                let result = op.complete(locks);
+                self.send(result, pc, instruction);
+                // Only one result is forwarded per clock, so the instruction in this
+                // stage must wait
+                clock = clock.to_block();
+            }
+        }
+
         match take(&mut self.state) {
             Idle => clock.to_ready(),
             Executing {
@@ -333,6 +881,8 @@ impl PipelineStage for Execute {
                 wregs,
                 rvals,
                 mut clocks,
+                pc,
+                paired,
             } => {
                 if clock.is_squash() {
                     self.forward = None;
@@ -341,53 +891,61 @@ impl PipelineStage for Execute {
                 } else {
                     clocks = clocks.saturating_sub(clock.clocks());
                     if clocks == 0 {
-                        let result = instruction.execute(rvals);
-                        let squash = result.should_squash();
-
-                        if clock.is_ready() {
-                            let is_halt = result.is_halt();
-
-                            self.forward = Some(result);
-                            if is_halt {
-                                self.state = Halted;
-                            } else {
-                                self.state = Idle;
-                            }
-
-                            if squash {
-                                clock.to_squash()
-                            } else {
-                                clock.to_ready()
-                            }
-                        } else {
-                            self.state = Ready { result, wregs };
-
-                            if squash {
-                                clock.to_squash()
-                            } else {
-                                clock.to_block()
-                            }
-                        }
+                        let result = match paired {
+                            // Paired instructions only write registers, so neither can trap
+                            Some((paired, paired_rvals)) => ExecuteResult::Pair {
+                                first: self.resolve(instruction, rvals).into(),
+                                second: self.resolve(paired, paired_rvals).into(),
+                                regs: wregs,
+                                instruction: paired,
+                            },
+                            None => self.trap(self.resolve(instruction, rvals), pc, wregs, memory),
+                        };
+                        self.finish(clock, result, wregs, pc, instruction)
                     } else {
                         self.state = Executing {
                             instruction,
                             wregs,
                             rvals,
                             clocks,
+                            pc,
+                            paired,
                         };
                         clock.to_block()
                     }
                 }
             }
-            Ready { result, wregs } => {
+            Faulted { fault, pc } => {
+                if clock.is_squash() {
+                    self.forward = None;
+                    self.state = Squashed {
+                        wregs: Default::default(),
+                    };
+                    clock
+                } else {
+                    // Without a handler, the fault stops the pipeline in the manner of a
+                    // halt. The decode stage locked no registers for the instruction, and
+                    // neither result is written back, so the instruction is never seen.
+                    let result = self
+                        .vector(fault, pc, Default::default())
+                        .unwrap_or(ExecuteResult::Halt);
+                    self.finish(clock, result, Default::default(), pc, Default::default())
+                }
+            }
+            Ready {
+                result,
+                wregs,
+                pc,
+                instruction,
+            } => {
                 if clock.is_squash() {
                     self.forward = None;
                     self.state = Squashed { wregs };
                     clock
-                } else if clock.is_ready() {
+                } else if clock.is_ready() && !self.waits_for_in_flight(&result, wregs) {
                     let is_halt = result.is_halt();
 
-                    self.forward = Some(result);
+                    self.send(result, pc, instruction);
                     if is_halt {
                         self.state = Halted;
                     } else {
@@ -395,7 +953,12 @@ impl PipelineStage for Execute {
                     }
                     clock
                 } else {
-                    self.state = Ready { result, wregs };
+                    self.state = Ready {
+                        result,
+                        wregs,
+                        pc,
+                        instruction,
+                    };
                     clock.to_block()
                 }
             }
@@ -427,17 +990,31 @@ impl PipelineStage for Execute {
                         instruction,
                         regvals,
                         reglocks,
+                        pc,
+                    },
+                    b,
+                ) => (self.issue(instruction, reglocks, regvals, pc, None), b),
+                Status::Flow(
+                    DecodeResult::Pair {
+                        instructions: [instruction, paired],
+                        regvals: [regvals, paired_regvals],
+                        reglocks,
+                        pc,
                     },
                     b,
-                ) => {
-                    let clocks = instruction.clock_requirement();
-                    self.state = State::Executing {
+                ) => (
+                    self.issue(
                         instruction,
-                        wregs: reglocks,
-                        rvals: regvals,
-                        clocks,
-                    };
-                    (clocks, b)
+                        reglocks,
+                        regvals,
+                        pc,
+                        Some((paired, paired_regvals)),
+                    ),
+                    b,
+                ),
+                Status::Flow(DecodeResult::Fault { fault, pc }, b) => {
+                    self.state = Faulted { fault, pc };
+                    (1, b)
                 }
                 Status::Flow(DecodeResult::Squashed, b) => {
                     self.state = Squashed {
@@ -450,16 +1027,25 @@ impl PipelineStage for Execute {
                 Status::Dry => unreachable!(),
             };
 
+            // The clocks until the next operation in flight finishes
+            let in_flight = self
+                .in_flight
+                .iter()
+                .map(|op| op.clocks)
+                .min()
+                .unwrap_or(usize::MAX);
+
             match take(&mut self.forward) {
                 Some(xr) => Status::Flow(xr, bubbles),
+                None if in_flight == 0 => Status::Ready(clocks, bubbles),
                 None if self.state.is_waiting() && bubbles => {
-                    Status::Stall(clocks.max(self.state.wait_time()))
+                    Status::Stall(clocks.max(self.state.wait_time()).min(in_flight))
                 }
                 None if self.state.is_waiting() => {
-                    Status::Stall(clocks.min(self.state.wait_time()))
+                    Status::Stall(clocks.min(self.state.wait_time()).min(in_flight))
                 }
                 None if self.state.is_squashed() => Status::Squashed(clocks),
-                None if self.state.is_idle() => Status::Stall(clocks),
+                None if self.state.is_idle() => Status::Stall(clocks.min(in_flight)),
                 None => Status::Ready(clocks, bubbles),
             }
         }
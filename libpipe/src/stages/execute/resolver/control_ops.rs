@@ -1,8 +1,8 @@
-use super::Resolver;
+use super::{Latencies, Resolver};
 use crate::{regmap::RegMap, stages::execute::ExecuteResult};
 use libseis::{
     instruction_set::{control::Jump, ControlOp},
-    registers::{BP, LP, OF, PC, SP, ZF},
+    registers::{BP, LP, OF, PC, SP, V, ZF},
 };
 
 use ExecuteResult::*;
@@ -13,6 +13,10 @@ impl Resolver for ControlOp {
         match self {
             ControlOp::Nop => Nop,
             ControlOp::Halt => Halt,
+            ControlOp::Syscall => Syscall {
+                number: regvals[V[0]],
+                argument: regvals[V[1]],
+            },
             ControlOp::Ret => Return {
                 link: regvals[LP],
                 bp: regvals[BP],
@@ -147,7 +151,7 @@ impl Resolver for ControlOp {
     }
 
     #[inline]
-    fn clock_requirement(self) -> usize {
+    fn clock_requirement(self, _: &Latencies) -> usize {
         1
     }
 }
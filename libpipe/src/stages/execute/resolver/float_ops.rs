@@ -1,4 +1,4 @@
-use super::Resolver;
+use super::{Latencies, Resolver};
 use crate::{
     regmap::RegMap,
     stages::execute::ExecuteResult::{self, WriteReg, WriteStatus},
@@ -202,8 +202,12 @@ impl Resolver for FloatingPointOp {
     }
 
     #[inline]
-    fn clock_requirement(self) -> usize {
-        // I arbitrarily selected 20 to be the number of clocks required for a floating-point operation to finish
-        20
+    fn clock_requirement(self, latencies: &Latencies) -> usize {
+        match self {
+            FloatingPointOp::Fdiv(_) | FloatingPointOp::Fmod(_) | FloatingPointOp::Frec(_) => {
+                latencies.float_divide
+            }
+            _ => latencies.float,
+        }
     }
 }
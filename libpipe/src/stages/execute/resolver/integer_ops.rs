@@ -1,4 +1,4 @@
-use super::Resolver;
+use super::{Latencies, Resolver};
 use crate::{regmap::RegMap, stages::execute::ExecuteResult};
 use libseis::{
     instruction_set::integer::{
@@ -54,9 +54,10 @@ fn mul(l: Word, r: Word, destination: Register) -> ExecuteResult {
     }
 }
 
+// Dividing by zero gives zero and sets the overflow flag
 #[inline]
 fn div_unsigned(l: Word, r: Word, destination: Register) -> ExecuteResult {
-    let (value, of) = l.overflowing_div(r);
+    let (value, of) = l.checked_div(r).map_or((0, true), |value| (value, false));
 
     WriteReg {
         destination,
@@ -71,7 +72,10 @@ fn div_unsigned(l: Word, r: Word, destination: Register) -> ExecuteResult {
 
 #[inline]
 fn div_signed(l: Word, r: Word, destination: Register) -> ExecuteResult {
-    let (value, of) = (l as SWord).overflowing_div(r as SWord);
+    let (value, of) = match r {
+        0 => (0, true),
+        _ => (l as SWord).overflowing_div(r as SWord),
+    };
     WriteReg {
         destination,
         value: value as Word,
@@ -85,7 +89,7 @@ fn div_signed(l: Word, r: Word, destination: Register) -> ExecuteResult {
 
 #[inline]
 fn modulo(l: Word, r: Word, destination: Register) -> ExecuteResult {
-    let (value, of) = l.overflowing_rem(r);
+    let (value, of) = l.checked_rem(r).map_or((0, true), |value| (value, false));
 
     WriteReg {
         destination,
@@ -438,7 +442,11 @@ impl Resolver for IntegerOp {
     }
 
     #[inline]
-    fn clock_requirement(self) -> usize {
-        1
+    fn clock_requirement(self, latencies: &Latencies) -> usize {
+        match self {
+            IntegerOp::Mul(_) => latencies.multiply,
+            IntegerOp::Dvu(_) | IntegerOp::Dvs(_) | IntegerOp::Mod(_) => latencies.divide,
+            _ => 1,
+        }
     }
 }
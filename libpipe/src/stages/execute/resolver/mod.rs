@@ -3,6 +3,7 @@ mod float_ops;
 mod integer_ops;
 mod register_ops;
 
+use super::Latencies;
 use crate::regmap::RegMap;
 use libseis::instruction_set::Instruction;
 
@@ -13,7 +14,7 @@ use libseis::instruction_set::Instruction;
 pub trait Resolver {
     fn execute(self, regvals: RegMap) -> super::ExecuteResult;
 
-    fn clock_requirement(self) -> usize;
+    fn clock_requirement(self, latencies: &Latencies) -> usize;
 }
 
 impl Resolver for Instruction {
@@ -28,12 +29,12 @@ impl Resolver for Instruction {
     }
 
     #[inline]
-    fn clock_requirement(self) -> usize {
+    fn clock_requirement(self, latencies: &Latencies) -> usize {
         match self {
-            Instruction::Control(c) => c.clock_requirement(),
-            Instruction::Integer(i) => i.clock_requirement(),
-            Instruction::FloatingPoint(f) => f.clock_requirement(),
-            Instruction::Register(r) => r.clock_requirement(),
+            Instruction::Control(c) => c.clock_requirement(latencies),
+            Instruction::Integer(i) => i.clock_requirement(latencies),
+            Instruction::FloatingPoint(f) => f.clock_requirement(latencies),
+            Instruction::Register(r) => r.clock_requirement(latencies),
         }
     }
 }
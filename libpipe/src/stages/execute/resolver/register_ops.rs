@@ -1,4 +1,4 @@
-use super::Resolver;
+use super::{Latencies, Resolver};
 use crate::{regmap::RegMap, stages::execute::ExecuteResult};
 use libseis::{
     instruction_set::{
@@ -10,19 +10,21 @@ use libseis::{
     types::{Byte, Register, Short, Word},
 };
 
-fn compute_read_address(op: ReadOp, regvals: RegMap) -> (Word, Register, bool) {
+fn compute_read_address(op: ReadOp, regvals: RegMap) -> (Word, Register, bool, bool) {
     match op {
         ReadOp::ZeroPage {
             address,
             destination,
-        } => ((ZERO_PAGE | (address as Word)), destination, false),
+        } => ((ZERO_PAGE | (address as Word)), destination, false, false),
         ReadOp::Indirect {
             volatile,
+            non_temporal,
             address,
             destination,
-        } => (regvals[address], destination, volatile),
+        } => (regvals[address], destination, volatile, non_temporal),
         ReadOp::OffsetIndirect {
             volatile,
+            non_temporal,
             address,
             offset,
             destination,
@@ -30,9 +32,11 @@ fn compute_read_address(op: ReadOp, regvals: RegMap) -> (Word, Register, bool) {
             regvals[address].wrapping_add(offset as Word),
             destination,
             volatile,
+            non_temporal,
         ),
         ReadOp::IndexedIndirect {
             volatile,
+            non_temporal,
             address,
             index,
             destination,
@@ -40,26 +44,37 @@ fn compute_read_address(op: ReadOp, regvals: RegMap) -> (Word, Register, bool) {
             regvals[address].wrapping_add(regvals[index]),
             destination,
             volatile,
+            non_temporal,
         ),
         ReadOp::StackOffset {
             offset,
             destination,
-        } => (regvals[BP].wrapping_add(offset as Word), destination, false),
+        } => (
+            regvals[BP].wrapping_add(offset as Word),
+            destination,
+            false,
+            false,
+        ),
     }
 }
 
-fn compute_write_address(op: WriteOp, regvals: RegMap) -> (Word, Word, bool) {
+fn compute_write_address(op: WriteOp, regvals: RegMap) -> (Word, Word, bool, bool) {
     match op {
-        WriteOp::ZeroPage { address, source } => {
-            ((ZERO_PAGE | (address as Word)), regvals[source], false)
-        }
+        WriteOp::ZeroPage { address, source } => (
+            (ZERO_PAGE | (address as Word)),
+            regvals[source],
+            false,
+            false,
+        ),
         WriteOp::Indirect {
             volatile,
+            non_temporal,
             address,
             source,
-        } => (regvals[address], regvals[source], volatile),
+        } => (regvals[address], regvals[source], volatile, non_temporal),
         WriteOp::OffsetIndirect {
             volatile,
+            non_temporal,
             address,
             offset,
             source,
@@ -67,9 +82,11 @@ fn compute_write_address(op: WriteOp, regvals: RegMap) -> (Word, Word, bool) {
             regvals[address].wrapping_add(offset as Word),
             regvals[source],
             volatile,
+            non_temporal,
         ),
         WriteOp::IndexedIndirect {
             volatile,
+            non_temporal,
             address,
             index,
             source,
@@ -77,11 +94,13 @@ fn compute_write_address(op: WriteOp, regvals: RegMap) -> (Word, Word, bool) {
             regvals[address].wrapping_add(regvals[index]),
             regvals[source],
             volatile,
+            non_temporal,
         ),
         WriteOp::StackOffset { offset, source } => (
             regvals[BP].wrapping_add(offset as Word),
             regvals[source],
             false,
+            false,
         ),
     }
 }
@@ -91,51 +110,60 @@ impl Resolver for RegisterOp {
     fn execute(self, regvals: RegMap) -> ExecuteResult {
         match self {
             RegisterOp::Lbr(op) => {
-                let (address, destination, volatile) = compute_read_address(op, regvals);
+                let (address, destination, volatile, non_temporal) =
+                    compute_read_address(op, regvals);
                 ExecuteResult::ReadMemByte {
                     address,
                     destination,
                     volatile,
+                    non_temporal,
                 }
             }
             RegisterOp::Lsr(op) => {
-                let (address, destination, volatile) = compute_read_address(op, regvals);
+                let (address, destination, volatile, non_temporal) =
+                    compute_read_address(op, regvals);
                 ExecuteResult::ReadMemShort {
                     address,
                     destination,
                     volatile,
+                    non_temporal,
                 }
             }
             RegisterOp::Llr(op) => {
-                let (address, destination, volatile) = compute_read_address(op, regvals);
+                let (address, destination, volatile, non_temporal) =
+                    compute_read_address(op, regvals);
                 ExecuteResult::ReadMemWord {
                     address,
                     destination,
                     volatile,
+                    non_temporal,
                 }
             }
             RegisterOp::Sbr(op) => {
-                let (address, value, volatile) = compute_write_address(op, regvals);
+                let (address, value, volatile, non_temporal) = compute_write_address(op, regvals);
                 ExecuteResult::WriteMemByte {
                     address,
                     value: (value as Byte),
                     volatile,
+                    non_temporal,
                 }
             }
             RegisterOp::Ssr(op) => {
-                let (address, value, volatile) = compute_write_address(op, regvals);
+                let (address, value, volatile, non_temporal) = compute_write_address(op, regvals);
                 ExecuteResult::WriteMemShort {
                     address,
                     value: (value as Short),
                     volatile,
+                    non_temporal,
                 }
             }
             RegisterOp::Slr(op) => {
-                let (address, value, volatile) = compute_write_address(op, regvals);
+                let (address, value, volatile, non_temporal) = compute_write_address(op, regvals);
                 ExecuteResult::WriteMemWord {
                     address,
                     value,
                     volatile,
+                    non_temporal,
                 }
             }
             RegisterOp::Tfr(RegOp {
@@ -241,11 +269,15 @@ impl Resolver for RegisterOp {
                     }
                 }
             }
+
+            // The execute stage owns the cycle counter, so it only reaches this point
+            // with an invalid destination
+            RegisterOp::Rdcyc(_) => ExecuteResult::Nop,
         }
     }
 
     #[inline]
-    fn clock_requirement(self) -> usize {
+    fn clock_requirement(self, _: &Latencies) -> usize {
         match self {
             RegisterOp::Lbr(r) | RegisterOp::Lsr(r) | RegisterOp::Llr(r) => match r {
                 ReadOp::Indirect { .. } | ReadOp::ZeroPage { .. } | ReadOp::StackOffset { .. } => 1,
@@ -257,7 +289,11 @@ impl Resolver for RegisterOp {
                 | WriteOp::StackOffset { .. } => 1,
                 _ => 2,
             },
-            RegisterOp::Push(_) | RegisterOp::Pop(_) | RegisterOp::Tfr(_) | RegisterOp::Ldr(_) => 1,
+            RegisterOp::Push(_)
+            | RegisterOp::Pop(_)
+            | RegisterOp::Tfr(_)
+            | RegisterOp::Ldr(_)
+            | RegisterOp::Rdcyc(_) => 1,
         }
     }
 }
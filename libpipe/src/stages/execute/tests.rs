@@ -51,7 +51,8 @@ fn clock_basic() {
         execute,
         Execute {
             state: State::Idle,
-            forward: None
+            forward: None,
+            ..
         }
     ));
 
@@ -59,7 +60,8 @@ fn clock_basic() {
         execute.forward(Status::Flow(DecodeResult::Forward {
             instruction: Instruction::Control(ControlOp::Nop),
             regvals: RegMap::default(),
-            reglocks: RegisterFlags::default()
+            reglocks: RegisterFlags::default(),
+            pc: 0
         }, false)),
         Status::Stall(1)
     ));
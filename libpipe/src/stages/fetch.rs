@@ -1,9 +1,13 @@
 //! Fetch stage
 
-use crate::{reg_locks::Locks, Clock, PipelineStage, Status};
-use libmem::module::Status::Busy;
-use libseis::types::Word;
+use crate::{reg_locks::Locks, Clock, Fault, PipelineOptions, PipelineStage, Registers, Status};
+use libmem::module::{MemoryModule, Status::Busy};
+use libseis::{
+    instruction_set::{encode, ControlOp, Instruction},
+    types::Word,
+};
 use serde::Serialize;
+use std::collections::VecDeque;
 
 /// The state of the [`Fetch`] object
 #[derive(Debug, Clone, Copy, Default)]
@@ -103,6 +107,13 @@ pub enum FetchResult {
         /// Where this instruction was located
         pc: Word,
     },
+    /// The next two instructions are available, to be decoded together
+    Pair {
+        /// The words to be decoded, in program order
+        words: [Word; 2],
+        /// Where the first instruction was located
+        pc: Word,
+    },
     /// No new instructions are available
     Squashed,
 }
@@ -112,6 +123,15 @@ pub enum FetchResult {
 pub struct Fetch {
     state: State,
     forward: Option<FetchResult>,
+    squash_clocks: usize,
+    alignment_checks: bool,
+    /// The number of instructions which may be read in a clock
+    width: usize,
+    /// Instructions read while the decode stage was busy, oldest first
+    queue: VecDeque<FetchResult>,
+    /// The number of instructions this stage may hold while the decode stage is busy
+    queue_depth: usize,
+    fault: Option<Fault>,
 }
 
 impl Serialize for Fetch {
@@ -128,8 +148,115 @@ impl Default for Fetch {
         Self {
             state: Idle,
             forward: None,
+            squash_clocks: 2,
+            alignment_checks: false,
+            width: 1,
+            queue: VecDeque::new(),
+            queue_depth: 1,
+            fault: None,
+        }
+    }
+}
+
+impl Fetch {
+    /// Creates a fetch stage which enforces the provided options
+    ///
+    /// After a squash, fetching resumes only once the jump has reached writeback,
+    /// which takes longer with each of the [extra execute stages](PipelineOptions::extra_execute_stages).
+    pub fn with_options(options: &PipelineOptions) -> Self {
+        Self {
+            squash_clocks: 2 + options.extra_execute_stages,
+            alignment_checks: options.alignment_checks,
+            width: options.fetch_width.clamp(1, 2),
+            queue_depth: options.fetch_queue_depth.max(1),
+            ..Default::default()
+        }
+    }
+
+    /// Takes `word`, read from PC, along with the word after it if it is in the same
+    /// aligned pair of words and may be read without waiting, moving PC past them
+    ///
+    /// The word after is only read if it is already in the instruction cache, so that
+    /// trying to pair it never starts a line fill or counts a miss.
+    fn take(
+        &mut self,
+        word: Word,
+        registers: &mut Registers,
+        memory: &mut dyn MemoryModule,
+    ) -> FetchResult {
+        let pc = registers.pc;
+        let next = pc.wrapping_add(4);
+        let cached = memory
+            .get_cache("instruction")
+            .is_some_and(|cache| cache.check_address(next).is_hit());
+
+        let next = if self.width > 1 && pc & 0b100 == 0 && cached {
+            memory.read_instruction(next).ok()
+        } else {
+            None
+        };
+
+        self.state = Idle;
+        registers.pc = pc.wrapping_add(if next.is_some() { 8 } else { 4 });
+
+        match next {
+            Some(next) => FetchResult::Pair {
+                words: [word, next],
+                pc,
+            },
+            None => FetchResult::Ready { word, pc },
+        }
+    }
+
+    /// Forwards `word`, read from PC, if `ready`. Otherwise, it is queued if there is
+    /// room, or else held until there is.
+    fn accept(
+        &mut self,
+        word: Word,
+        ready: bool,
+        registers: &mut Registers,
+        memory: &mut dyn MemoryModule,
+    ) {
+        if ready {
+            self.forward = Some(self.take(word, registers, memory));
+        } else if self.queue.len() + 1 < self.queue_depth {
+            let result = self.take(word, registers, memory);
+            self.queue.push_back(result);
+        } else {
+            self.state = Ready { word };
         }
     }
+
+    /// Gets the fault raised by this stage, if any
+    pub fn fault(&self) -> Option<Fault> {
+        self.fault
+    }
+
+    /// Gets the addresses of the instructions read by this stage which have yet to be
+    /// forwarded, oldest first, including any being read from `pc`
+    pub(crate) fn in_flight(&self, pc: Word) -> Vec<Word> {
+        let mut in_flight: Vec<Word> = self
+            .queue
+            .iter()
+            .flat_map(|result| match *result {
+                FetchResult::Ready { pc, .. } => vec![pc],
+                FetchResult::Pair { pc, .. } => vec![pc, pc.wrapping_add(4)],
+                FetchResult::Squashed => vec![],
+            })
+            .collect();
+
+        if matches!(self.state, Ready { .. } | Waiting { .. }) {
+            in_flight.push(pc);
+        }
+
+        in_flight
+    }
+
+    /// Whether this stage has finished waiting out a squash, and will begin reading
+    /// instructions on the next clock it is ready
+    pub(crate) fn is_resuming(&self) -> bool {
+        matches!(self.state, Squashed { clocks: 0 })
+    }
 }
 
 impl PipelineStage for Fetch {
@@ -140,44 +267,50 @@ impl PipelineStage for Fetch {
     fn clock(
         &mut self,
         clock: Clock,
-        registers: &mut crate::Registers,
+        registers: &mut Registers,
         _: &mut Locks,
-        memory: &mut dyn libmem::module::MemoryModule,
+        memory: &mut dyn MemoryModule,
     ) -> Clock {
         if clock.is_halt() {
             self.state = Halted;
             self.forward = None;
+            self.queue.clear();
             return Clock::Halt;
         } else if clock.is_squash() {
-            self.state = Squashed { clocks: 2 };
+            self.state = Squashed {
+                clocks: self.squash_clocks,
+            };
             self.forward = None;
+            self.queue.clear();
+            self.fault = None;
             return clock;
         }
 
+        // Queued instructions are forwarded first, in the order they were read, so a word
+        // read while any are queued joins the back of the queue
+        let ready = clock.is_ready() && self.queue.is_empty();
+        if clock.is_ready() && !ready {
+            self.forward = self.queue.pop_front();
+        }
+
         match self.state {
-            Ready { word } if clock.is_ready() => {
-                self.forward = Some(FetchResult::Ready {
-                    word,
-                    pc: registers.pc,
-                });
-                self.state = Idle;
-                registers.pc = registers.pc.wrapping_add(4);
+            Ready { word } if ready => {
+                self.accept(word, ready, registers, memory);
                 clock.to_ready()
             }
+            Ready { word } if self.queue.len() + 1 < self.queue_depth => {
+                self.accept(word, ready, registers, memory);
+                clock.to_block()
+            }
             Ready { .. } => clock.to_block(),
             Waiting { .. } => match memory.read_instruction(registers.pc) {
-                Ok(word) if clock.is_ready() => {
-                    self.forward = Some(FetchResult::Ready {
-                        word,
-                        pc: registers.pc,
-                    });
-                    self.state = Idle;
-                    registers.pc = registers.pc.wrapping_add(4);
-                    clock.to_ready()
-                }
                 Ok(word) => {
-                    self.state = Ready { word };
-                    clock.to_block()
+                    self.accept(word, ready, registers, memory);
+                    if ready {
+                        clock.to_ready()
+                    } else {
+                        clock.to_block()
+                    }
                 }
                 Err(Busy(clocks)) => {
                     self.state = Waiting { clocks };
@@ -199,19 +332,31 @@ impl PipelineStage for Fetch {
 
             Squashed { .. } => clock.to_block(),
 
-            Idle => match memory.read_instruction(registers.pc) {
-                Ok(word) if clock.is_ready() => {
+            Idle if self.alignment_checks && registers.pc & 0b11 != 0 => {
+                // Stop the pipeline in place of the misaligned instruction, leaving PC
+                // where it is. A squash clears the fault, as the fetch was down a path
+                // never taken.
+                self.fault = Some(Fault::MisalignedFetch { pc: registers.pc });
+
+                if ready {
                     self.forward = Some(FetchResult::Ready {
-                        word,
+                        word: encode(Instruction::Control(ControlOp::Halt)),
                         pc: registers.pc,
                     });
-                    self.state = Idle;
-                    registers.pc = registers.pc.wrapping_add(4);
                     clock.to_ready()
+                } else {
+                    clock.to_block()
                 }
+            }
+
+            Idle => match memory.read_instruction(registers.pc) {
                 Ok(word) => {
-                    self.state = Ready { word };
-                    clock.to_block()
+                    self.accept(word, ready, registers, memory);
+                    if ready {
+                        clock.to_ready()
+                    } else {
+                        clock.to_block()
+                    }
                 }
                 Err(Busy(clocks)) => {
                     self.state = Waiting { clocks };
@@ -228,6 +373,7 @@ impl PipelineStage for Fetch {
                 self.forward = None;
                 Status::Flow(instruction, false)
             }
+            None if !self.queue.is_empty() => Status::Ready(1, false),
             None if self.state.is_waiting() => Status::Stall(self.state.clocks()),
             None if self.state.is_squashed() => Status::Squashed(1),
             None if self.state.is_halted() => Status::Dry,
@@ -345,6 +491,61 @@ mod test {
             })
     }
 
+    #[test]
+    fn pairing_never_misses() {
+        let mut memory = Memory::new(1);
+        memory.set_page(
+            0x0000_0000,
+            &from_fn::<Byte, PAGE_SIZE, _>(|i| (i & 0xFF) as u8),
+        );
+
+        // Each line holds a single instruction, so the second of a pair is never read
+        // along with the first
+        let mut mem = SingleLevel::new(
+            Box::new(Associative::new(2, 2)),
+            Box::new(Associative::new(2, 2)),
+            memory,
+            10,
+            2,
+            false,
+        );
+        let (mut reg, mut lock) = (Registers::default(), Locks::default());
+        let mut fetch = Fetch::with_options(&PipelineOptions {
+            fetch_width: 2,
+            ..Default::default()
+        });
+
+        fetch.clock(Clock::Ready(1), &mut reg, &mut lock, &mut mem);
+        mem.clock(10);
+        fetch.clock(Clock::Ready(10), &mut reg, &mut lock, &mut mem);
+
+        assert!(matches!(
+            fetch.forward,
+            Some(FetchResult::Ready { pc: 0, .. })
+        ));
+        assert_eq!(mem.total_misses(), 1);
+        assert_eq!(reg.pc, 4);
+
+        // Once the second is cached, both are taken together
+        mem.warm(&[0x0000_000C]);
+        reg.pc = 8;
+        fetch = Fetch::with_options(&PipelineOptions {
+            fetch_width: 2,
+            ..Default::default()
+        });
+
+        fetch.clock(Clock::Ready(1), &mut reg, &mut lock, &mut mem);
+        mem.clock(10);
+        fetch.clock(Clock::Ready(10), &mut reg, &mut lock, &mut mem);
+
+        assert!(matches!(
+            fetch.forward,
+            Some(FetchResult::Pair { pc: 8, .. })
+        ));
+        assert_eq!(mem.total_misses(), 2);
+        assert_eq!(reg.pc, 16);
+    }
+
     #[test]
     fn clock_block() {
         let (mut mem, mut reg, mut lock) = basic_setup();
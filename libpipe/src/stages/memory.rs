@@ -1,10 +1,10 @@
 //! Memory stage
 
-use super::execute::ExecuteResult;
-use crate::{Clock, Locks, PipelineStage, Registers, Status};
+use super::execute::{ExecuteResult, RegisterWrite};
+use crate::{Clock, Fault, Locks, PipelineOptions, PipelineStage, Registers, Status};
 use libmem::module::{MemoryModule, Status as MemStatus};
 use libseis::{
-    registers::{get_name, RegisterFlags, BP, EPS, INF, LP, NAN, OF, PC, SP, ZF},
+    registers::{get_name, RegisterFlags, BP, EPS, INF, LP, NAN, OF, PC, SP, V, ZF},
     types::{Byte, Register, Short, Word},
 };
 use serde::Serialize;
@@ -18,6 +18,8 @@ pub enum ReadMode {
         address: Word,
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted
+        non_temporal: bool,
     },
     /// Reading a short from memory
     ReadShort {
@@ -25,6 +27,8 @@ pub enum ReadMode {
         address: Word,
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted
+        non_temporal: bool,
     },
     /// Reading a word from memory
     ReadWord {
@@ -32,6 +36,8 @@ pub enum ReadMode {
         address: Word,
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted
+        non_temporal: bool,
     },
 }
 
@@ -41,23 +47,38 @@ impl Serialize for ReadMode {
         S: serde::Serializer,
     {
         use serde::ser::SerializeMap;
-        let mut map = serializer.serialize_map(Some(3))?;
+        let mut map = serializer.serialize_map(Some(4))?;
 
         match self {
-            ReadByte { address, volatile } => {
+            ReadByte {
+                address,
+                volatile,
+                non_temporal,
+            } => {
                 map.serialize_entry("mode", "byte")?;
                 map.serialize_entry("address", address)?;
                 map.serialize_entry("volatile", volatile)?;
+                map.serialize_entry("non_temporal", non_temporal)?;
             }
-            ReadShort { address, volatile } => {
+            ReadShort {
+                address,
+                volatile,
+                non_temporal,
+            } => {
                 map.serialize_entry("mode", "short")?;
                 map.serialize_entry("address", address)?;
                 map.serialize_entry("volatile", volatile)?;
+                map.serialize_entry("non_temporal", non_temporal)?;
             }
-            ReadWord { address, volatile } => {
+            ReadWord {
+                address,
+                volatile,
+                non_temporal,
+            } => {
                 map.serialize_entry("mode", "word")?;
                 map.serialize_entry("address", address)?;
                 map.serialize_entry("volatile", volatile)?;
+                map.serialize_entry("non_temporal", non_temporal)?;
             }
         }
 
@@ -67,8 +88,58 @@ impl Serialize for ReadMode {
 use ReadMode::*;
 
 impl ReadMode {
-    fn execute(self, mem: &mut dyn MemoryModule) -> Result<Word, usize> {
+    /// Whether the read skips the cache
+    fn volatile(self) -> bool {
+        match self {
+            ReadByte { volatile, .. } | ReadShort { volatile, .. } | ReadWord { volatile, .. } => {
+                volatile
+            }
+        }
+    }
+
+    /// Whether the read leaves its line as the next to be evicted
+    ///
+    /// Volatile accesses skip the cache, so they never touch its lines.
+    fn non_temporal(self) -> bool {
         match self {
+            ReadByte {
+                non_temporal,
+                volatile,
+                ..
+            }
+            | ReadShort {
+                non_temporal,
+                volatile,
+                ..
+            }
+            | ReadWord {
+                non_temporal,
+                volatile,
+                ..
+            } => non_temporal && !volatile,
+        }
+    }
+
+    /// The address being read from
+    fn address(self) -> Word {
+        match self {
+            ReadByte { address, .. } | ReadShort { address, .. } | ReadWord { address, .. } => {
+                address
+            }
+        }
+    }
+
+    /// The number of bytes being read
+    fn length(self) -> Word {
+        match self {
+            ReadByte { .. } => 1,
+            ReadShort { .. } => 2,
+            ReadWord { .. } => 4,
+        }
+    }
+
+    fn execute(self, mem: &mut dyn MemoryModule) -> Result<Word, usize> {
+        let result = match self {
             ReadByte {
                 address, volatile, ..
             } => {
@@ -104,10 +175,22 @@ impl ReadMode {
                 Err(MemStatus::Busy(clocks)) => Err(clocks),
                 Err(MemStatus::Idle) => unreachable!(),
             },
+        };
+
+        if result.is_ok() && self.non_temporal() {
+            demote(mem, self.address(), self.length());
         }
+
+        result
     }
 }
 
+/// Leaves the lines touched by a non-temporal access as the next to be evicted
+fn demote(mem: &mut dyn MemoryModule, address: Word, length: Word) {
+    mem.demote(address);
+    mem.demote(address.wrapping_add(length - 1));
+}
+
 /// Represents the kind of write being done
 #[derive(Debug, Clone, Copy)]
 pub enum WriteMode {
@@ -119,6 +202,8 @@ pub enum WriteMode {
         value: Byte,
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted
+        non_temporal: bool,
     },
     /// Writing a short to memory
     WriteShort {
@@ -128,6 +213,8 @@ pub enum WriteMode {
         value: Short,
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted
+        non_temporal: bool,
     },
     /// Writing a word to memory
     WriteWord {
@@ -137,6 +224,8 @@ pub enum WriteMode {
         value: Word,
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted
+        non_temporal: bool,
     },
 }
 use WriteMode::*;
@@ -147,38 +236,44 @@ impl Serialize for WriteMode {
         S: serde::Serializer,
     {
         use serde::ser::SerializeMap;
-        let mut map = serializer.serialize_map(Some(4))?;
+        let mut map = serializer.serialize_map(Some(5))?;
 
         match self {
             WriteByte {
                 address,
                 value,
                 volatile,
+                non_temporal,
             } => {
                 map.serialize_entry("mode", "byte")?;
                 map.serialize_entry("value", value)?;
                 map.serialize_entry("address", address)?;
                 map.serialize_entry("volatile", volatile)?;
+                map.serialize_entry("non_temporal", non_temporal)?;
             }
             WriteShort {
                 address,
                 value,
                 volatile,
+                non_temporal,
             } => {
                 map.serialize_entry("mode", "short")?;
                 map.serialize_entry("value", value)?;
                 map.serialize_entry("address", address)?;
                 map.serialize_entry("volatile", volatile)?;
+                map.serialize_entry("non_temporal", non_temporal)?;
             }
             WriteWord {
                 address,
                 value,
                 volatile,
+                non_temporal,
             } => {
                 map.serialize_entry("mode", "word")?;
                 map.serialize_entry("value", value)?;
                 map.serialize_entry("address", address)?;
                 map.serialize_entry("volatile", volatile)?;
+                map.serialize_entry("non_temporal", non_temporal)?;
             }
         }
 
@@ -187,12 +282,54 @@ impl Serialize for WriteMode {
 }
 
 impl WriteMode {
-    fn execute(self, mem: &mut dyn MemoryModule) -> Option<usize> {
+    /// The address being written to
+    fn address(self) -> Word {
+        match self {
+            WriteByte { address, .. } | WriteShort { address, .. } | WriteWord { address, .. } => {
+                address
+            }
+        }
+    }
+
+    /// The number of bytes being written
+    fn length(self) -> Word {
+        match self {
+            WriteByte { .. } => 1,
+            WriteShort { .. } => 2,
+            WriteWord { .. } => 4,
+        }
+    }
+
+    /// Whether the write leaves its line as the next to be evicted
+    ///
+    /// Volatile accesses skip the cache, so they never touch its lines.
+    fn non_temporal(self) -> bool {
         match self {
+            WriteByte {
+                non_temporal,
+                volatile,
+                ..
+            }
+            | WriteShort {
+                non_temporal,
+                volatile,
+                ..
+            }
+            | WriteWord {
+                non_temporal,
+                volatile,
+                ..
+            } => non_temporal && !volatile,
+        }
+    }
+
+    fn execute(self, mem: &mut dyn MemoryModule) -> Option<usize> {
+        let result = match self {
             WriteByte {
                 address,
                 value,
                 volatile,
+                ..
             } => match if volatile {
                 mem.write_byte_volatile(address, value)
             } else {
@@ -205,6 +342,7 @@ impl WriteMode {
                 address,
                 value,
                 volatile,
+                ..
             } => match if volatile {
                 mem.write_short_volatile(address, value)
             } else {
@@ -217,6 +355,7 @@ impl WriteMode {
                 address,
                 value,
                 volatile,
+                ..
             } => match if volatile {
                 mem.write_word_volatile(address, value)
             } else {
@@ -225,7 +364,13 @@ impl WriteMode {
                 MemStatus::Busy(clocks) => Some(clocks),
                 MemStatus::Idle => None,
             },
+        };
+
+        if result.is_none() && self.non_temporal() {
+            demote(mem, self.address(), self.length());
         }
+
+        result
     }
 }
 
@@ -298,6 +443,32 @@ pub enum State {
         /// Clock requirement metadata
         clocks: usize,
     },
+    /// Pushing the current LP and BP values before calling the handler of a fault
+    Trapping {
+        /// The fault which was raised
+        fault: Fault,
+        /// The entry in the vector table to call
+        address: Word,
+        /// Where the handler returns to
+        link: Word,
+        /// The registers owned by the abandoned instruction
+        wregs: RegisterFlags,
+        /// Whether LP has been pushed, leaving BP
+        lp_pushed: bool,
+        /// Clock requirement metadata
+        clocks: usize,
+    },
+    /// Reading back the LP and BP values pushed by a trap, returning from its handler
+    TrapReturn {
+        /// Where to return to
+        link: Word,
+        /// Current BP value
+        bp: Word,
+        /// The LP value read back, once it has been
+        lp: Option<Word>,
+        /// Clock requirement metadata
+        clocks: usize,
+    },
     /// Ready to forward the result
     Ready {
         /// The result to forward
@@ -394,6 +565,31 @@ impl Serialize for State {
                 map.serialize_entry("clocks", clocks)?;
                 map.end()
             }
+            Trapping {
+                fault,
+                address,
+                link,
+                clocks,
+                ..
+            } => {
+                let mut map = serializer.serialize_map(Some(5))?;
+                map.serialize_entry("state", "trapping")?;
+                map.serialize_entry("fault", fault)?;
+                map.serialize_entry("address", address)?;
+                map.serialize_entry("link", link)?;
+                map.serialize_entry("clocks", clocks)?;
+                map.end()
+            }
+            TrapReturn {
+                link, bp, clocks, ..
+            } => {
+                let mut map = serializer.serialize_map(Some(4))?;
+                map.serialize_entry("state", "trap_return")?;
+                map.serialize_entry("link", link)?;
+                map.serialize_entry("bp", bp)?;
+                map.serialize_entry("clocks", clocks)?;
+                map.end()
+            }
             Ready { .. } => serializer.collect_map([("state", "ready")]),
             Squashed { wregs } => {
                 let mut map = serializer.serialize_map(Some(2))?;
@@ -421,6 +617,8 @@ impl State {
                 | Popping { .. }
                 | JsrPrep { .. }
                 | RetPrep { .. }
+                | Trapping { .. }
+                | TrapReturn { .. }
                 | Halting(..)
         )
     }
@@ -434,6 +632,8 @@ impl State {
             | DummyPop { clocks, .. }
             | JsrPrep { clocks, .. }
             | RetPrep { clocks, .. }
+            | Trapping { clocks, .. }
+            | TrapReturn { clocks, .. }
             | Halting(clocks) => clocks,
             _ => 1,
         }
@@ -456,9 +656,13 @@ impl State {
             Popping { destination, .. } => [destination, SP, ZF, OF, EPS, NAN, INF].into(),
             DummyPop { .. } => [SP, ZF, OF, EPS, NAN, INF].into(),
             JsrPrep { .. } => [PC, SP, BP, LP].into(),
-            RetPrep { .. } => [PC, BP, SP].into(),
+            RetPrep { .. } | TrapReturn { .. } => [PC, BP, SP, LP].into(),
+            Trapping { wregs, .. } => wregs,
             Ready { result } => match result {
-                MemoryResult::Squashed { wregs } | MemoryResult::Ignore { wregs } => wregs,
+                MemoryResult::Squashed { wregs }
+                | MemoryResult::Ignore { wregs }
+                | MemoryResult::Trap { wregs, .. }
+                | MemoryResult::Pair { wregs, .. } => wregs,
                 MemoryResult::WriteReg1 { destination, .. } => {
                     [destination, ZF, OF, EPS, NAN, INF].into()
                 }
@@ -468,8 +672,9 @@ impl State {
                 } => [register, SP, ZF, OF, EPS, NAN, INF].into(),
                 MemoryResult::JumpSubroutine { .. } => [PC, SP, BP, LP].into(),
                 MemoryResult::Jump { .. } => [PC].into(),
-                MemoryResult::Return { .. } => [SP, BP, PC].into(),
+                MemoryResult::Return { .. } => [SP, BP, PC, LP].into(),
                 MemoryResult::Halt => [].into(),
+                MemoryResult::Syscall { .. } => [V[0]].into(),
                 MemoryResult::WriteStatus { .. } => [ZF, OF, EPS, NAN, INF].into(),
                 _ => [].into(),
             },
@@ -558,6 +763,26 @@ pub enum MemoryResult {
         bp: Word,
         /// The new SP value
         sp: Word,
+        /// The LP value restored when returning from the handler of a fault
+        lp: Option<Word>,
+    },
+    /// Call the handler of a fault in place of the instruction which raised it
+    Trap {
+        /// The entry in the vector table to call
+        address: Word,
+        /// Where the handler returns to
+        link: Word,
+        /// The new SP and BP value
+        sp: Word,
+        /// The registers owned by the abandoned instruction
+        wregs: RegisterFlags,
+    },
+    /// Ask the host to perform a system call
+    Syscall {
+        /// The call to perform
+        number: Word,
+        /// The argument of the call
+        argument: Word,
     },
     /// Stop execution
     Halt,
@@ -579,6 +804,15 @@ pub enum MemoryResult {
         /// Infinity flag state
         inf: bool,
     },
+    /// Write back the registers of two instructions, in order
+    Pair {
+        /// The registers written by the first instruction
+        first: RegisterWrite,
+        /// The registers written by the second instruction
+        second: RegisterWrite,
+        /// The held register locks
+        wregs: RegisterFlags,
+    },
 }
 
 impl Serialize for MemoryResult {
@@ -655,11 +889,40 @@ impl Serialize for MemoryResult {
             MemoryResult::Jump { address } => {
                 serializer.collect_map([("job", "jump"), ("address", &format!("{address:#010X}"))])
             }
-            MemoryResult::Return { address, bp, sp } => serializer.collect_map([
-                ("job", "return"),
-                ("address", &format!("{address:#010X}")),
-                ("bp", bp.to_string().as_str()),
-                ("sp", sp.to_string().as_str()),
+            MemoryResult::Return {
+                address,
+                bp,
+                sp,
+                lp,
+            } => {
+                let mut map = serializer.serialize_map(Some(5))?;
+                map.serialize_entry("job", "return")?;
+                map.serialize_entry("address", &format!("{address:#010X}"))?;
+                map.serialize_entry("bp", &bp.to_string())?;
+                map.serialize_entry("sp", &sp.to_string())?;
+                if let Some(lp) = lp {
+                    map.serialize_entry("lp", &lp.to_string())?;
+                }
+                map.end()
+            }
+            MemoryResult::Trap {
+                address,
+                link,
+                sp,
+                wregs,
+            } => {
+                let mut map = serializer.serialize_map(Some(5))?;
+                map.serialize_entry("job", "trap")?;
+                map.serialize_entry("address", &format!("{address:#010X}"))?;
+                map.serialize_entry("link", link)?;
+                map.serialize_entry("sp", sp)?;
+                map.serialize_entry("wregs", wregs)?;
+                map.end()
+            }
+            MemoryResult::Syscall { number, argument } => serializer.collect_map([
+                ("job", "system call"),
+                ("number", number.to_string().as_str()),
+                ("argument", argument.to_string().as_str()),
             ]),
             MemoryResult::Halt => serializer.collect_map([("job", "halt")]),
             MemoryResult::Ignore { wregs } => {
@@ -682,15 +945,126 @@ impl Serialize for MemoryResult {
                 ("nan", nan.to_string().as_str()),
                 ("inf", inf.to_string().as_str()),
             ]),
+            MemoryResult::Pair {
+                first,
+                second,
+                wregs,
+            } => {
+                let mut map = serializer.serialize_map(Some(4))?;
+                map.serialize_entry("job", "write registers of a pair")?;
+                map.serialize_entry("first", first)?;
+                map.serialize_entry("second", second)?;
+                map.serialize_entry("wregs", wregs)?;
+                map.end()
+            }
         }
     }
 }
 
+/// A load which missed the cache, waiting on the memory module while a later load
+/// enters the stage
+#[derive(Debug, Clone, Copy)]
+struct Outstanding {
+    mode: ReadMode,
+    destination: Register,
+    /// The clocks left until the memory module has the value
+    clocks: usize,
+    /// The value read, once available
+    value: Option<Word>,
+}
+
 /// Represents the memory pipeline stage
 #[derive(Debug, Default)]
 pub struct Memory {
     state: State,
     forward: Option<MemoryResult>,
+    stack_checks: bool,
+    write_checks: bool,
+    fault: Option<Fault>,
+    /// Loads which missed, oldest first
+    outstanding: Vec<Outstanding>,
+    ports: usize,
+    /// The BP set by each trap whose handler has yet to return, innermost last
+    traps: Vec<Word>,
+}
+
+impl Memory {
+    /// Creates a memory stage which optionally checks that stack operations
+    /// stay within the stack page, and that stores avoid read-only memory
+    pub fn with_options(options: &PipelineOptions) -> Self {
+        Self {
+            stack_checks: options.stack_checks,
+            write_checks: options.write_checks,
+            ports: options.memory_ports,
+            ..Default::default()
+        }
+    }
+
+    /// The result of a load
+    fn load_result(destination: Register, value: Word) -> MemoryResult {
+        MemoryResult::WriteReg1 {
+            destination,
+            value,
+            zf: value == 0,
+            of: false,
+            eps: false,
+            nan: false,
+            inf: false,
+        }
+    }
+
+    /// Retries the outstanding loads, forwarding the oldest once its value is read
+    ///
+    /// Loads are forwarded in order, so a later instruction never overwrites the
+    /// registers of an earlier one.
+    fn clock_outstanding(&mut self, clock: Clock, memory: &mut dyn MemoryModule) {
+        for load in self.outstanding.iter_mut().filter(|l| l.value.is_none()) {
+            match load.mode.execute(memory) {
+                Ok(value) => load.value = Some(value),
+                Err(clocks) => load.clocks = clocks,
+            }
+        }
+
+        if clock.is_ready() {
+            if let Some(&Outstanding {
+                destination,
+                value: Some(value),
+                ..
+            }) = self.outstanding.first()
+            {
+                self.outstanding.remove(0);
+                self.forward = Some(Self::load_result(destination, value));
+            }
+        }
+    }
+
+    /// The clocks until the next outstanding load has its value
+    fn outstanding_wait(&self) -> usize {
+        self.outstanding
+            .iter()
+            .map(|l| if l.value.is_some() { 1 } else { l.clocks })
+            .min()
+            .unwrap_or(1)
+    }
+
+    /// Gets the fault raised by this stage, if any
+    pub fn fault(&self) -> Option<Fault> {
+        self.fault
+    }
+
+    /// Whether a halt has passed through this stage, after which the pipeline is dry
+    pub fn is_halted(&self) -> bool {
+        self.state.is_halted()
+    }
+
+    /// Checks whether a stack operation would leave the stack page
+    fn check_stack(&self, job: &ExecuteResult) -> Option<Fault> {
+        if !self.stack_checks {
+            return None;
+        }
+
+        job.stack_fault()
+    }
 }
 
 impl Serialize for Memory {
@@ -722,7 +1096,7 @@ impl PipelineStage for Memory {
     fn clock(
         &mut self,
         clock: Clock,
-        _: &mut Registers,
+        registers: &mut Registers,
         _: &mut Locks,
         memory: &mut dyn MemoryModule,
     ) -> Clock {
@@ -736,6 +1110,21 @@ impl PipelineStage for Memory {
                 self.forward = None;
                 clock
             } else {
+                let clock = if self.outstanding.is_empty() {
+                    clock
+                } else {
+                    self.clock_outstanding(clock, memory);
+
+                    // Nothing is forwarded ahead of the outstanding loads, and only other
+                    // loads may start before they finish
+                    let clock = clock.to_block();
+                    if !matches!(self.state, Idle | Reading { .. } | Ready { .. }) {
+                        return clock;
+                    }
+
+                    clock
+                };
+
                 match self.state {
                     Idle => clock.to_ready(),
                     Reading {
@@ -743,32 +1132,29 @@ impl PipelineStage for Memory {
                     } => match mode.execute(memory) {
                         Ok(value) => {
                             if clock.is_ready() {
-                                self.forward = Some(MemoryResult::WriteReg1 {
-                                    destination,
-                                    value,
-                                    zf: value == 0,
-                                    of: false,
-                                    eps: false,
-                                    nan: false,
-                                    inf: false,
-                                });
+                                self.forward = Some(Self::load_result(destination, value));
                                 self.state = Idle;
                                 clock.to_ready()
                             } else {
                                 self.state = Ready {
-                                    result: MemoryResult::WriteReg1 {
-                                        destination,
-                                        value,
-                                        zf: value == 0,
-                                        of: false,
-                                        eps: false,
-                                        nan: false,
-                                        inf: false,
-                                    },
+                                    result: Self::load_result(destination, value),
                                 };
                                 clock.to_block()
                             }
                         }
+                        Err(clocks)
+                            if !mode.volatile() && self.outstanding.len() + 1 < self.ports =>
+                        {
+                            // Let the next load in while this one waits on its line
+                            self.outstanding.push(Outstanding {
+                                mode,
+                                destination,
+                                clocks,
+                                value: None,
+                            });
+                            self.state = Idle;
+                            clock.to_ready()
+                        }
                         Err(clocks) => {
                             self.state = Reading {
                                 mode,
@@ -778,6 +1164,17 @@ impl PipelineStage for Memory {
                             clock.to_block()
                         }
                     },
+                    Writing { mode, .. }
+                        if self.write_checks
+                            && memory.memory().is_read_only(mode.address(), mode.length()) =>
+                    {
+                        // Stop before the store reaches memory
+                        self.fault = Some(Fault::WriteProtected {
+                            address: mode.address(),
+                        });
+                        self.state = Halting(1);
+                        clock.to_block()
+                    }
                     Writing { mode, .. } => match mode.execute(memory) {
                         Some(clocks) => {
                             self.state = Writing { mode, clocks };
@@ -939,6 +1336,7 @@ impl PipelineStage for Memory {
                                     address: link,
                                     bp: value,
                                     sp: bp.wrapping_sub(4),
+                                    lp: None,
                                 });
                                 clock.to_ready()
                             } else {
@@ -947,6 +1345,7 @@ impl PipelineStage for Memory {
                                         address: link,
                                         bp: value,
                                         sp: bp.wrapping_sub(4),
+                                        lp: None,
                                     },
                                 };
                                 clock.to_block()
@@ -958,6 +1357,125 @@ impl PipelineStage for Memory {
                         }
                         Err(MemStatus::Idle) => unreachable!(),
                     },
+                    // Every instruction before this one has been written back, so the
+                    // registers hold the values the handler must save. LP is pushed first,
+                    // then BP, which the handler's return reads back in the same manner.
+                    Trapping {
+                        fault,
+                        address,
+                        link,
+                        wregs,
+                        lp_pushed,
+                        ..
+                    } => {
+                        let sp = registers.sp;
+                        let status = if lp_pushed {
+                            memory.write_word(stack_address!(sp + 4), registers.bp)
+                        } else {
+                            memory.write_word(stack_address!(sp), registers.lp)
+                        };
+
+                        match status {
+                            MemStatus::Idle if !lp_pushed => {
+                                self.state = Trapping {
+                                    fault,
+                                    address,
+                                    link,
+                                    wregs,
+                                    lp_pushed: true,
+                                    clocks: 1,
+                                };
+                                clock.to_block()
+                            }
+                            MemStatus::Idle => {
+                                let sp = stack_address!(sp + 8);
+                                let result = MemoryResult::Trap {
+                                    address,
+                                    link,
+                                    sp,
+                                    wregs,
+                                };
+                                self.traps.push(sp);
+
+                                if clock.is_ready() {
+                                    self.state = Idle;
+                                    self.forward = Some(result);
+                                    clock.to_ready()
+                                } else {
+                                    self.state = Ready { result };
+                                    clock.to_block()
+                                }
+                            }
+                            MemStatus::Busy(clocks) => {
+                                self.state = Trapping {
+                                    fault,
+                                    address,
+                                    link,
+                                    wregs,
+                                    lp_pushed,
+                                    clocks,
+                                };
+                                clock.to_block()
+                            }
+                        }
+                    }
+                    TrapReturn {
+                        link, bp, lp: None, ..
+                    } => match memory.read_word(stack_address!(bp - 8)) {
+                        Ok(lp) => {
+                            self.state = TrapReturn {
+                                link,
+                                bp,
+                                lp: Some(lp),
+                                clocks: 1,
+                            };
+                            clock.to_block()
+                        }
+                        Err(MemStatus::Busy(clocks)) => {
+                            self.state = TrapReturn {
+                                link,
+                                bp,
+                                lp: None,
+                                clocks,
+                            };
+                            clock.to_block()
+                        }
+                        Err(MemStatus::Idle) => unreachable!(),
+                    },
+                    TrapReturn {
+                        link,
+                        bp,
+                        lp: Some(lp),
+                        ..
+                    } => match memory.read_word(stack_address!(bp - 4)) {
+                        Ok(value) => {
+                            let result = MemoryResult::Return {
+                                address: link,
+                                bp: value,
+                                sp: bp.wrapping_sub(8),
+                                lp: Some(lp),
+                            };
+
+                            if clock.is_ready() {
+                                self.state = Idle;
+                                self.forward = Some(result);
+                                clock.to_ready()
+                            } else {
+                                self.state = Ready { result };
+                                clock.to_block()
+                            }
+                        }
+                        Err(MemStatus::Busy(clocks)) => {
+                            self.state = TrapReturn {
+                                link,
+                                bp,
+                                lp: Some(lp),
+                                clocks,
+                            };
+                            clock.to_block()
+                        }
+                        Err(MemStatus::Idle) => unreachable!(),
+                    },
                     Ready { result } => {
                         if clock.is_ready() {
                             if matches!(result, MemoryResult::Halt) {
@@ -1013,8 +1531,17 @@ impl PipelineStage for Memory {
         if self.state.is_halted() {
             Status::Dry
         } else {
+            if let Status::Flow(job, _) = &input {
+                self.fault = self.fault.or_else(|| self.check_stack(job));
+            }
+
             let (clocks, bubbles) = match input {
                 Status::Stall(clocks) => (clocks, true),
+                Status::Flow(_, b) if self.fault.is_some() => {
+                    // Stop instead of letting the stack pointer wrap around
+                    self.state = Halting(1);
+                    (1, b)
+                }
                 Status::Flow(input, b) => match input {
                     ExecuteResult::Nop => {
                         self.state = Ready {
@@ -1044,11 +1571,22 @@ impl PipelineStage for Memory {
                         (1, b)
                     }
                     ExecuteResult::Return { link, bp } => {
-                        self.state = RetPrep {
-                            bp,
-                            link,
-                            clocks: 1,
-                        };
+                        // Returning with the BP a trap set returns from its handler
+                        if self.traps.last() == Some(&stack_address!(bp)) {
+                            self.traps.pop();
+                            self.state = TrapReturn {
+                                link,
+                                bp,
+                                lp: None,
+                                clocks: 1,
+                            };
+                        } else {
+                            self.state = RetPrep {
+                                bp,
+                                link,
+                                clocks: 1,
+                            };
+                        }
                         (1, b)
                     }
                     ExecuteResult::WriteReg {
@@ -1073,6 +1611,12 @@ impl PipelineStage for Memory {
                         };
                         (1, b)
                     }
+                    ExecuteResult::WriteRegNoStatus { destination, value } => {
+                        self.state = Ready {
+                            result: MemoryResult::WriteRegNoStatus { destination, value },
+                        };
+                        (1, b)
+                    }
                     ExecuteResult::WriteStatus {
                         zf,
                         of,
@@ -1095,12 +1639,14 @@ impl PipelineStage for Memory {
                         address,
                         value,
                         volatile,
+                        non_temporal,
                     } => {
                         self.state = Writing {
                             mode: WriteByte {
                                 address,
                                 value,
                                 volatile,
+                                non_temporal,
                             },
                             clocks: 1,
                         };
@@ -1110,12 +1656,14 @@ impl PipelineStage for Memory {
                         address,
                         value,
                         volatile,
+                        non_temporal,
                     } => {
                         self.state = Writing {
                             mode: WriteShort {
                                 address,
                                 value,
                                 volatile,
+                                non_temporal,
                             },
                             clocks: 1,
                         };
@@ -1125,12 +1673,14 @@ impl PipelineStage for Memory {
                         address,
                         value,
                         volatile,
+                        non_temporal,
                     } => {
                         self.state = Writing {
                             mode: WriteWord {
                                 address,
                                 value,
                                 volatile,
+                                non_temporal,
                             },
                             clocks: 1,
                         };
@@ -1140,9 +1690,14 @@ impl PipelineStage for Memory {
                         address,
                         destination,
                         volatile,
+                        non_temporal,
                     } => {
                         self.state = Reading {
-                            mode: ReadByte { address, volatile },
+                            mode: ReadByte {
+                                address,
+                                volatile,
+                                non_temporal,
+                            },
                             destination,
                             clocks: 1,
                         };
@@ -1152,9 +1707,14 @@ impl PipelineStage for Memory {
                         address,
                         destination,
                         volatile,
+                        non_temporal,
                     } => {
                         self.state = Reading {
-                            mode: ReadShort { address, volatile },
+                            mode: ReadShort {
+                                address,
+                                volatile,
+                                non_temporal,
+                            },
                             destination,
                             clocks: 1,
                         };
@@ -1164,9 +1724,14 @@ impl PipelineStage for Memory {
                         address,
                         destination,
                         volatile,
+                        non_temporal,
                     } => {
                         self.state = Reading {
-                            mode: ReadWord { address, volatile },
+                            mode: ReadWord {
+                                address,
+                                volatile,
+                                non_temporal,
+                            },
                             destination,
                             clocks: 1,
                         };
@@ -1200,14 +1765,51 @@ impl PipelineStage for Memory {
                         };
                         (1, b)
                     }
+                    ExecuteResult::Pair {
+                        first,
+                        second,
+                        regs,
+                        ..
+                    } => {
+                        self.state = Ready {
+                            result: MemoryResult::Pair {
+                                first,
+                                second,
+                                wregs: regs,
+                            },
+                        };
+                        (1, b)
+                    }
                     ExecuteResult::Halt => {
                         self.state = Halting(1);
                         (1, b)
                     }
+                    ExecuteResult::Syscall { number, argument } => {
+                        self.state = Ready {
+                            result: MemoryResult::Syscall { number, argument },
+                        };
+                        (1, b)
+                    }
                     ExecuteResult::PopStack { sp } => {
                         self.state = DummyPop { sp, clocks: 1 };
                         (1, b)
                     }
+                    ExecuteResult::Trap {
+                        fault,
+                        address,
+                        link,
+                        regs,
+                    } => {
+                        self.state = Trapping {
+                            fault,
+                            address,
+                            link,
+                            wregs: regs,
+                            lp_pushed: false,
+                            clocks: 1,
+                        };
+                        (1, b)
+                    }
                 },
                 Status::Ready(n, b) => (n, b),
                 Status::Squashed(clocks) => (clocks, true),
@@ -1216,6 +1818,15 @@ impl PipelineStage for Memory {
 
             match take(&mut self.forward) {
                 Some(result) => Status::Flow(result, bubbles),
+                None if !self.outstanding.is_empty() => {
+                    let wait = if self.state.is_waiting() {
+                        self.state.wait_time().min(self.outstanding_wait())
+                    } else {
+                        self.outstanding_wait()
+                    };
+
+                    Status::Stall(clocks.min(wait).max(1))
+                }
                 None if self.state.is_waiting() && bubbles => {
                     Status::Stall(clocks.max(self.state.wait_time()))
                 }
@@ -6,6 +6,7 @@
 //! This ensures that the stages may be consistently serialized for
 //! display to the user in the frontend.
 
+pub mod buffer;
 pub mod decode;
 pub mod execute;
 pub mod fetch;
@@ -17,6 +18,7 @@ use libmem::module::MemoryModule;
 use serde::Serialize;
 use std::fmt::Debug;
 
+pub use buffer::Buffer;
 pub use decode::Decode;
 pub use execute::Execute;
 pub use fetch::Fetch;
@@ -1,20 +1,47 @@
 //! Writeback stage
 
 use super::{memory::MemoryResult, PipelineStage};
-use crate::{Clock, Locks, Registers, Status};
+use crate::{host, Clock, Host, Locks, PipelineOptions, Registers, Status};
 use libmem::module::MemoryModule;
 use libseis::{
     pages::PAGE_SIZE,
-    registers::{BP, EPS, INF, LP, NAN, OF, PC, SP, ZF},
+    registers::{RegisterFlags, BP, EPS, INF, LP, NAN, OF, PC, SP, V, ZF},
     types::Word,
 };
 use serde::Serialize;
 use std::mem::take;
 
 /// Writeback pipeline stage
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Default)]
 pub struct Writeback {
     job: Option<MemoryResult>,
+    alignment_checks: bool,
+    retired: usize,
+    host: Option<Box<dyn Host + Send + Sync>>,
+}
+
+impl Writeback {
+    /// Creates a writeback stage which optionally leaves jump targets unaligned,
+    /// so that the [fetch](super::Fetch) stage may fault on them
+    pub fn with_options(options: &PipelineOptions) -> Self {
+        Self {
+            alignment_checks: options.alignment_checks,
+            ..Default::default()
+        }
+    }
+
+    /// The number of instructions written back by this stage, excluding squashed ones
+    /// and those abandoned by a trap
+    ///
+    /// The halt which stops the pipeline never reaches this stage, so it is not counted.
+    pub fn retired(&self) -> usize {
+        self.retired
+    }
+
+    /// Sets the host which performs system calls as they are written back
+    pub(crate) fn set_host(&mut self, host: Box<dyn Host + Send + Sync>) {
+        self.host = Some(host);
+    }
 }
 
 impl Serialize for Writeback {
@@ -42,6 +69,19 @@ impl PipelineStage for Writeback {
         _: &mut dyn MemoryModule,
     ) -> Clock {
         if let Some(job) = take(&mut self.job) {
+            // Neither squashed instructions nor those abandoned by a trap complete
+            if !matches!(
+                job,
+                MemoryResult::Squashed { .. } | MemoryResult::Trap { .. }
+            ) {
+                self.retired += 1;
+            }
+
+            // Both instructions of a pair are written back at once
+            if matches!(job, MemoryResult::Pair { .. }) {
+                self.retired += 1;
+            }
+
             match job {
                 MemoryResult::Nop | MemoryResult::Halt => {}
                 MemoryResult::Squashed { wregs } | MemoryResult::Ignore { wregs } => {
@@ -82,19 +122,17 @@ impl PipelineStage for Writeback {
                     nan,
                     inf,
                 } => {
+                    // A destination which is itself a flag keeps the value written to it
+                    registers[ZF] = Word::from(zf);
+                    registers[OF] = Word::from(of);
+                    registers[EPS] = Word::from(eps);
+                    registers[NAN] = Word::from(nan);
+                    registers[INF] = Word::from(inf);
                     registers[register] = value;
-                    registers[ZF] = zf.then_some(1).unwrap_or(0);
-                    registers[OF] = of.then_some(1).unwrap_or(0);
-                    registers[EPS] = eps.then_some(1).unwrap_or(0);
-                    registers[NAN] = nan.then_some(1).unwrap_or(0);
-                    registers[INF] = inf.then_some(1).unwrap_or(0);
 
-                    locks[register] -= 1;
-                    locks[ZF] -= 1;
-                    locks[OF] -= 1;
-                    locks[EPS] -= 1;
-                    locks[NAN] -= 1;
-                    locks[INF] -= 1;
+                    for reg in RegisterFlags::from([register, ZF, OF, EPS, NAN, INF]) {
+                        locks[reg] -= 1;
+                    }
                 }
                 MemoryResult::WriteReg2 {
                     destination: register,
@@ -106,21 +144,18 @@ impl PipelineStage for Writeback {
                     nan,
                     inf,
                 } => {
-                    registers[register] = value;
+                    // Popping the stack pointer or a flag keeps the value popped
                     registers[SP] = sp;
-                    registers[ZF] = zf.then_some(1).unwrap_or(0);
-                    registers[OF] = of.then_some(1).unwrap_or(0);
-                    registers[EPS] = eps.then_some(1).unwrap_or(0);
-                    registers[NAN] = nan.then_some(1).unwrap_or(0);
-                    registers[INF] = inf.then_some(1).unwrap_or(0);
+                    registers[ZF] = Word::from(zf);
+                    registers[OF] = Word::from(of);
+                    registers[EPS] = Word::from(eps);
+                    registers[NAN] = Word::from(nan);
+                    registers[INF] = Word::from(inf);
+                    registers[register] = value;
 
-                    locks[register] -= 1;
-                    locks[SP] -= 1;
-                    locks[ZF] -= 1;
-                    locks[OF] -= 1;
-                    locks[EPS] -= 1;
-                    locks[NAN] -= 1;
-                    locks[INF] -= 1;
+                    for reg in RegisterFlags::from([register, SP, ZF, OF, EPS, NAN, INF]) {
+                        locks[reg] -= 1;
+                    }
                 }
                 MemoryResult::JumpSubroutine {
                     address,
@@ -138,27 +173,79 @@ impl PipelineStage for Writeback {
                     locks[SP] -= 1;
                     locks[BP] -= 1;
                 }
+                MemoryResult::Trap {
+                    address,
+                    link,
+                    sp,
+                    wregs,
+                } => {
+                    registers[PC] = address;
+                    registers[LP] = link;
+                    registers[SP] = sp;
+                    registers[BP] = sp;
+
+                    for reg in wregs {
+                        locks[reg] -= 1;
+                    }
+                }
+                MemoryResult::Syscall { number, argument } => {
+                    registers[V[0]] = host::call(&mut self.host, number, argument);
+
+                    locks[V[0]] -= 1;
+                }
                 MemoryResult::Jump { address } => {
                     registers[PC] = address;
 
                     locks[PC] -= 1;
                 }
-                MemoryResult::Return { address, bp, sp } => {
+                MemoryResult::Return {
+                    address,
+                    bp,
+                    sp,
+                    lp,
+                } => {
                     registers[PC] = address;
                     registers[BP] = bp;
                     registers[SP] = sp;
+                    if let Some(lp) = lp {
+                        registers[LP] = lp;
+                    }
 
                     locks[PC] -= 1;
                     locks[BP] -= 1;
                     locks[SP] -= 1;
+                    locks[LP] -= 1;
+                }
+                MemoryResult::Pair {
+                    first,
+                    second,
+                    wregs,
+                } => {
+                    for write in [first, second] {
+                        if let Some((register, value)) = write.register {
+                            registers[register] = value;
+                        }
+
+                        if let Some(flags) = write.flags {
+                            for (register, flag) in [ZF, OF, EPS, NAN, INF].into_iter().zip(flags) {
+                                registers[register] = Word::from(flag);
+                            }
+                        }
+                    }
+
+                    for reg in wregs {
+                        locks[reg] -= 1;
+                    }
                 }
             }
         }
 
         registers[SP] %= PAGE_SIZE as Word;
         registers[BP] %= PAGE_SIZE as Word;
-        registers[PC] &= 0xFFFF_FFFC;
-        registers[LP] &= 0xFFFF_FFFC;
+        if !self.alignment_checks {
+            registers[PC] &= 0xFFFF_FFFC;
+            registers[LP] &= 0xFFFF_FFFC;
+        }
 
         clock.to_ready()
     }
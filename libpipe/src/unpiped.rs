@@ -3,11 +3,14 @@
 //! The [`Pipeline`] trait simply enables consistent interfacing.
 
 use crate::{
+    profile::Profiler,
     stages::{self, Clock, PipelineStage, Status},
-    ClockResult, Locks, PipelineStages,
+    ClockResult, Locks, PipelineOptions, PipelineStages,
 };
-use crate::{Pipeline, Registers};
+use crate::{Host, Pipeline, Registers};
 use libmem::module::MemoryModule;
+use libseis::{registers::RegisterFlags, types::Word};
+use std::collections::HashMap;
 
 /// The current stage of the pipeline
 #[derive(Debug, Default, Clone)]
@@ -35,11 +38,17 @@ pub struct Unpipelined {
     execute: stages::Execute,
     memory: stages::Memory,
     writeback: stages::Writeback,
+
+    /// The number of clocks applied so far
+    clocks: usize,
+    profiler: Profiler,
 }
 
 impl Pipeline for Unpipelined {
     fn clock(&mut self, clocks: usize) -> ClockResult {
         self.memory_module.clock(clocks);
+        self.clocks += clocks;
+        self.execute.set_clocks(self.clocks);
 
         match self.stage {
             Stage::Fetch => {
@@ -129,6 +138,7 @@ impl Pipeline for Unpipelined {
                     &mut self.locks,
                     self.memory_module.as_mut(),
                 );
+                self.profiler.update(&mut self.execute, &self.writeback);
 
                 self.stage = Fetch;
 
@@ -159,11 +169,36 @@ impl Pipeline for Unpipelined {
         &mut self.registers
     }
 
+    fn locked_registers(&self) -> RegisterFlags {
+        self.locks.locked()
+    }
+
+    fn profile(&self) -> HashMap<Word, u64> {
+        self.profiler.counts().clone()
+    }
+
+    fn branch_profile(&self) -> HashMap<(Word, Word), u64> {
+        self.profiler.edges().clone()
+    }
+
+    fn instruction_mix(&self) -> HashMap<String, u64> {
+        self.profiler.mix().clone()
+    }
+
+    fn energy(&self) -> f64 {
+        self.profiler.energy(self.memory_module.as_ref())
+    }
+
+    fn set_host(&mut self, host: Box<dyn Host + Send + Sync>) {
+        self.writeback.set_host(host);
+    }
+
     fn stages(&self) -> PipelineStages {
         PipelineStages {
             fetch: &self.fetch,
             decode: &self.decode,
             execute: &self.execute,
+            buffers: &[],
             memory: &self.memory,
             writeback: &self.writeback,
         }
@@ -173,16 +208,36 @@ impl Pipeline for Unpipelined {
 impl Unpipelined {
     /// Create a new unpipelined processor
     pub fn new(memory_module: Box<dyn MemoryModule + Send + Sync>) -> Self {
+        Self::with_options(memory_module, PipelineOptions::default())
+    }
+
+    /// Create a new unpipelined processor with the provided options
+    pub fn with_options(
+        memory_module: Box<dyn MemoryModule + Send + Sync>,
+        options: PipelineOptions,
+    ) -> Self {
         Self {
             memory_module,
             registers: Default::default(),
             locks: Default::default(),
             stage: Default::default(),
-            fetch: Default::default(),
-            decode: Default::default(),
-            execute: Default::default(),
-            memory: Default::default(),
-            writeback: Default::default(),
+            fetch: stages::Fetch::with_options(&PipelineOptions {
+                fetch_width: 1,
+                fetch_queue_depth: 1,
+                ..options
+            }),
+            decode: stages::Decode::with_options(&options),
+            execute: stages::Execute::with_options(&PipelineOptions {
+                max_in_flight: 0,
+                ..options
+            }),
+            memory: stages::Memory::with_options(&PipelineOptions {
+                memory_ports: 1,
+                ..options
+            }),
+            writeback: stages::Writeback::with_options(&options),
+            clocks: 0,
+            profiler: Profiler::with_options(&options),
         }
     }
 }
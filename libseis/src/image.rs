@@ -0,0 +1,230 @@
+//! The segmented image format produced by the linker.
+//!
+//! A segmented image starts with a header holding the entry point and listing where
+//! each segment belongs, followed by the contents of every segment in the same order:
+//!
+//! | Field          | Size              |
+//! |----------------|-------------------|
+//! | [`MAGIC`]      | 4 bytes           |
+//! | Entry point    | 1 word            |
+//! | Segment count  | 1 word            |
+//! | Segment table  | 3 words each      |
+//! | Segment data   | the segment sizes |
+//!
+//! Each entry of the segment table holds the address, length, and flags of its
+//! segment. All words are big-endian, like the rest of the architecture.
+//!
+//! Images without the magic number are flat: the file is copied to memory starting at
+//! address zero, which is also where execution begins.
+
+use crate::types::Word;
+use std::{error::Error, fmt::Display};
+
+/// Marks the start of a segmented image
+pub const MAGIC: [u8; 4] = *b"SEIS";
+
+/// Describes where a segment of an image is placed in memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+    /// The address of the first byte of the segment
+    pub address: Word,
+    /// The number of bytes in the segment
+    pub length: Word,
+    /// The [flags](Segment::READ_ONLY) describing the segment
+    pub flags: Word,
+}
+
+impl Segment {
+    /// The segment may not be written to while the program runs
+    pub const READ_ONLY: Word = 0x0000_0001;
+    /// The segment contains instructions
+    pub const EXECUTABLE: Word = 0x0000_0002;
+
+    /// Size of an entry in the segment table, in bytes
+    const ENTRY_SIZE: usize = 12;
+
+    /// Returns true if the segment may not be written to
+    pub fn is_read_only(&self) -> bool {
+        self.flags & Self::READ_ONLY != 0
+    }
+
+    /// Returns true if the segment contains instructions
+    pub fn is_executable(&self) -> bool {
+        self.flags & Self::EXECUTABLE != 0
+    }
+}
+
+/// An error found while reading a segmented image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+    /// The data does not start with the [`MAGIC`] number
+    NotSegmented,
+    /// The image ended before the header or a segment was complete
+    Truncated,
+    /// A segment extends past the end of the address space
+    SegmentOverflow(Segment),
+}
+
+impl Error for ImageError {}
+
+impl Display for ImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::NotSegmented => write!(f, "The image is not segmented"),
+            ImageError::Truncated => write!(f, "The image is truncated"),
+            ImageError::SegmentOverflow(segment) => write!(
+                f,
+                "The segment at {:#010X} ({} bytes) extends past the end of memory",
+                segment.address, segment.length
+            ),
+        }
+    }
+}
+
+/// Returns true if the data starts with the [`MAGIC`] number of a segmented image
+pub fn is_segmented(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// Encodes the entry point, segments, and their contents into a segmented image
+///
+/// The length of each segment is taken from its contents.
+pub fn encode<'a, I>(entry: Word, segments: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = (Word, Word, &'a [u8])>,
+{
+    let segments: Vec<_> = segments.into_iter().collect();
+
+    let mut image = MAGIC.to_vec();
+    image.extend(entry.to_be_bytes());
+    image.extend((segments.len() as Word).to_be_bytes());
+
+    for &(address, flags, data) in &segments {
+        image.extend(address.to_be_bytes());
+        image.extend((data.len() as Word).to_be_bytes());
+        image.extend(flags.to_be_bytes());
+    }
+
+    for (.., data) in segments {
+        image.extend_from_slice(data);
+    }
+
+    image
+}
+
+/// Reads the big-endian word at `offset` of an image
+fn read_word(data: &[u8], offset: usize) -> Result<Word, ImageError> {
+    data.get(offset..offset + 4)
+        .map(|bytes| Word::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or(ImageError::Truncated)
+}
+
+/// Gets the address execution begins at
+///
+/// Flat images always begin at address zero.
+pub fn entry(data: &[u8]) -> Result<Word, ImageError> {
+    if is_segmented(data) {
+        read_word(data, MAGIC.len())
+    } else {
+        Ok(0)
+    }
+}
+
+/// Decodes a segmented image into its segments and their contents
+///
+/// The data must start with the [`MAGIC`] number; check with [`is_segmented`] first.
+pub fn decode(data: &[u8]) -> Result<Vec<(Segment, &[u8])>, ImageError> {
+    let word = |offset: usize| read_word(data, offset);
+
+    if !is_segmented(data) {
+        return Err(ImageError::NotSegmented);
+    }
+
+    let count = word(MAGIC.len() + 4)? as usize;
+    let table = MAGIC.len() + 8;
+    let mut contents = table + count * Segment::ENTRY_SIZE;
+
+    (0..count)
+        .map(|i| {
+            let entry = table + i * Segment::ENTRY_SIZE;
+            let segment = Segment {
+                address: word(entry)?,
+                length: word(entry + 4)?,
+                flags: word(entry + 8)?,
+            };
+
+            if segment.address as u64 + segment.length as u64 > 1 << 32 {
+                return Err(ImageError::SegmentOverflow(segment));
+            }
+
+            let bytes = data
+                .get(contents..contents + segment.length as usize)
+                .ok_or(ImageError::Truncated)?;
+            contents += segment.length as usize;
+
+            Ok((segment, bytes))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let code = [0x11, 0x22, 0x33, 0x44];
+        let data = [0xAA; 7];
+
+        let image = encode(
+            0x0000_0010,
+            [
+                (
+                    0x0000_0000,
+                    Segment::READ_ONLY | Segment::EXECUTABLE,
+                    &code[..],
+                ),
+                (0x0003_0010, 0, &data[..]),
+            ],
+        );
+
+        assert!(is_segmented(&image));
+        assert_eq!(entry(&image), Ok(0x0000_0010));
+        assert_eq!(
+            decode(&image),
+            Ok(vec![
+                (
+                    Segment {
+                        address: 0x0000_0000,
+                        length: 4,
+                        flags: Segment::READ_ONLY | Segment::EXECUTABLE,
+                    },
+                    &code[..]
+                ),
+                (
+                    Segment {
+                        address: 0x0003_0010,
+                        length: 7,
+                        flags: 0,
+                    },
+                    &data[..]
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let image = encode(0, [(0x0000_0100, 0, &[1, 2, 3, 4][..])]);
+
+        for len in 0..image.len() {
+            assert!(decode(&image[..len]).is_err(), "{len} bytes");
+        }
+    }
+
+    #[test]
+    fn entry_point() {
+        assert_eq!(entry(&[0x11, 0x22, 0x33, 0x44]), Ok(0));
+        assert_eq!(entry(&MAGIC), Err(ImageError::Truncated));
+    }
+}
@@ -2,17 +2,18 @@
 
 use super::{
     error::{DecodeError, DecodeResult},
-    Decode, Encode, Info,
+    reference::{Field, Form},
+    Addressing, Decode, Encode, Info, Operands,
 };
 use crate::{
     instruction_set::decode,
-    registers::RegisterFlags,
+    registers::{RegisterFlags, V},
     types::{self, Register, SWord, Word},
 };
 use std::fmt::Display;
 
 /// Jump operands
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Jump {
     /// Jumps to a location pointed to by a register
     Register(Register),
@@ -31,6 +32,26 @@ impl Jump {
     const REGISTER_MASK: Word = 0b0000_0000_0000_0000_0000_0000_1111_0000;
     /// Amount to shift register bits
     const REGISTER_SHIFT: Word = 4;
+
+    /// Describes the operand forms of a jump
+    fn forms() -> [Form; 2] {
+        [
+            Form::new(
+                "Vx",
+                [
+                    Field::fixed("relative", Self::REL_MODE_MASK, 0),
+                    Field::operand("address", Self::REGISTER_MASK),
+                ],
+            ),
+            Form::new(
+                "N",
+                [
+                    Field::fixed("relative", Self::REL_MODE_MASK, 1),
+                    Field::operand("offset", Self::RELATIVE_MASK),
+                ],
+            ),
+        ]
+    }
 }
 
 impl Decode for Jump {
@@ -79,7 +100,7 @@ impl Display for Jump {
 /// All relative jumps are in terms of words rather than bytes
 ///
 /// Absolute addresses ignore the least significant 2 bits
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ControlOp {
     /// Does nothing
     ///
@@ -172,6 +193,28 @@ pub enum ControlOp {
     /// JLE label ; Expands to N
     /// ```
     Jle(Jump),
+    /// Asks the host running the processor to perform an action
+    ///
+    /// V0 selects the [call](syscall) and receives its result, and V1 holds its
+    /// argument. Unknown calls leave [`syscall::FAILED`] in V0.
+    ///
+    /// ```seis
+    /// SYSCALL ; Perform the call selected by V0
+    /// ```
+    Syscall,
+}
+
+/// The calls made by [`ControlOp::Syscall`], selected by the value of V0
+pub mod syscall {
+    use crate::types::Word;
+
+    /// Writes the least significant byte of V1 to the output, leaving 0 in V0
+    pub const WRITE_BYTE: Word = 0;
+    /// Reads a byte from the input into V0, or [`FAILED`] once the input has ended
+    pub const READ_BYTE: Word = 1;
+
+    /// The result of a call which could not be performed
+    pub const FAILED: Word = Word::MAX;
 }
 
 impl ControlOp {
@@ -200,6 +243,10 @@ impl ControlOp {
     ///
     /// [`ControlOp::Ret`]
     const RET: Word = 0b0100;
+    /// SYSCALL bits
+    ///
+    /// [`ControlOp::Syscall`]
+    const SYSCALL: Word = 0b0101;
 
     /// JEQ bits
     ///
@@ -236,6 +283,7 @@ impl Decode for ControlOp {
             Self::HALT => Ok(Halt),
             Self::NOP => Ok(Nop),
             Self::RET => Ok(Ret),
+            Self::SYSCALL => Ok(Syscall),
             Self::JMP => Ok(Jmp(decode(word)?)),
             Self::JSR => Ok(Jsr(decode(word)?)),
             Self::JEQ => Ok(Jeq(decode(word)?)),
@@ -257,6 +305,7 @@ impl Encode for ControlOp {
             Halt => Self::HALT << Self::SHIFT,
             Nop => Self::NOP << Self::SHIFT,
             Ret => Self::RET << Self::SHIFT,
+            Syscall => Self::SYSCALL << Self::SHIFT,
             Jmp(jump) => (Self::JMP << Self::SHIFT) | jump.encode(),
             Jsr(jump) => (Self::JSR << Self::SHIFT) | jump.encode(),
             Jeq(jump) => (Self::JEQ << Self::SHIFT) | jump.encode(),
@@ -275,9 +324,11 @@ impl Info for ControlOp {
         use ControlOp::*;
 
         match self {
-            Ret => [PC, SP, BP].into(),
+            // A return from the handler of a fault also restores LP
+            Ret => [PC, SP, BP, LP].into(),
             Jmp(_) | Jeq(_) | Jne(_) | Jgt(_) | Jlt(_) | Jge(_) | Jle(_) => [PC].into(),
             Jsr(_) => [PC, LP, SP, BP].into(),
+            Syscall => [V[0]].into(),
 
             _ => [].into(),
         }
@@ -313,12 +364,76 @@ impl Info for ControlOp {
             Jsr(Register(r)) => [r, PC, SP, BP].into(),
             Jsr(Relative(_)) => [PC, SP, BP].into(),
             Ret => [LP, BP].into(),
+            Syscall => [V[0], V[1]].into(),
 
             _ => [].into(),
         }
     }
 }
 
+impl ControlOp {
+    /// Describes each control operation, for the [reference](super::reference())
+    pub(super) fn reference() -> Vec<(&'static str, Vec<Form>)> {
+        let op = |value| Field::fixed("op", Self::MASK, value);
+        let implied = |value| vec![Form::new("", [op(value)])];
+        let jump = |value| {
+            Jump::forms()
+                .into_iter()
+                .map(|form| form.with(op(value)))
+                .collect()
+        };
+
+        vec![
+            ("NOP", implied(Self::NOP)),
+            ("HALT", implied(Self::HALT)),
+            ("JMP", jump(Self::JMP)),
+            ("JSR", jump(Self::JSR)),
+            ("RET", implied(Self::RET)),
+            ("SYSCALL", implied(Self::SYSCALL)),
+            ("JEQ", jump(Self::JEQ)),
+            ("JNE", jump(Self::JNE)),
+            ("JGT", jump(Self::JGT)),
+            ("JLT", jump(Self::JLT)),
+            ("JGE", jump(Self::JGE)),
+            ("JLE", jump(Self::JLE)),
+        ]
+    }
+
+    /// Gets the address a relative jump at `address` goes to, or `None` if the
+    /// operation is not a relative jump
+    pub fn target(self, address: Word) -> Option<Word> {
+        use ControlOp::*;
+
+        match self {
+            Jmp(Jump::Relative(offset))
+            | Jsr(Jump::Relative(offset))
+            | Jeq(Jump::Relative(offset))
+            | Jne(Jump::Relative(offset))
+            | Jgt(Jump::Relative(offset))
+            | Jlt(Jump::Relative(offset))
+            | Jge(Jump::Relative(offset))
+            | Jle(Jump::Relative(offset)) => Some(address.wrapping_add_signed(offset)),
+            _ => None,
+        }
+    }
+
+    /// Gets the explicit operands of the operation
+    pub fn operands(self) -> Operands {
+        use ControlOp::*;
+
+        match self {
+            Nop | Halt | Ret | Syscall => Operands::IMPLIED,
+            Jmp(jump) | Jsr(jump) | Jeq(jump) | Jne(jump) | Jgt(jump) | Jlt(jump) | Jge(jump)
+            | Jle(jump) => match jump {
+                Jump::Register(r) => Operands::registers([r], []),
+                Jump::Relative(offset) => {
+                    Operands::IMPLIED.with_immediate(offset, Addressing::Relative)
+                }
+            },
+        }
+    }
+}
+
 impl Display for ControlOp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use ControlOp::*;
@@ -327,6 +442,7 @@ impl Display for ControlOp {
             Halt => write!(f, "HALT"),
             Nop => write!(f, "NOP"),
             Ret => write!(f, "RET"),
+            Syscall => write!(f, "SYSCALL"),
             Jmp(jump) => write!(f, "JMP {jump}"),
             Jsr(jump) => write!(f, "JSR {jump}"),
             Jeq(jump) => write!(f, "JEQ {jump}"),
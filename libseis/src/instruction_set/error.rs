@@ -31,6 +31,8 @@ pub enum DecodeError {
     InvalidPopOp(Word),
     /// Invalid register operation
     InvalidRegister(Register, Register),
+    /// A register which does not exist
+    UnknownRegister(Register),
 }
 
 impl Error for DecodeError {}
@@ -64,6 +66,7 @@ impl Display for DecodeError {
                     write!(f, "Invalid register: src={src}")
                 }
             }
+            &UnknownRegister(register) => write!(f, "Invalid register: {register}"),
         }
     }
 }
@@ -1,5 +1,8 @@
 //! Floating-point operations
-use super::{Decode, Encode, Info};
+use super::{
+    reference::{Field, Form},
+    Decode, Encode, Info, Operands,
+};
 use crate::{
     instruction_set::{decode, error::DecodeError},
     registers::{RegisterFlags, EPS, INF, NAN, OF, ZF},
@@ -8,7 +11,7 @@ use crate::{
 use std::fmt::Display;
 
 /// Binary floating-point operation
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct BinaryOp {
     /// Left register
     pub left: Register,
@@ -27,6 +30,18 @@ impl BinaryOp {
     ];
     /// Shifts for the source, option, and destination registers
     const REG_SHIFT: [Word; 3] = [8, 4, 0];
+
+    /// Describes the operand form of a binary operation
+    fn forms() -> Vec<Form> {
+        vec![Form::new(
+            "Vl, Vr => Vd",
+            [
+                Field::operand("left", Self::REG_MASK[0]),
+                Field::operand("right", Self::REG_MASK[1]),
+                Field::operand("destination", Self::REG_MASK[2]),
+            ],
+        )]
+    }
 }
 
 impl Decode for BinaryOp {
@@ -58,7 +73,7 @@ impl Display for BinaryOp {
 }
 
 /// Unary floating-point operation
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct UnaryOp {
     /// Source register
     pub source: Register,
@@ -72,6 +87,17 @@ impl UnaryOp {
         0b0000_0000_0000_0000_0000_0000_0000_1111,
     ];
     const REG_SHIFT: [Word; 2] = [4, 0];
+
+    /// Describes the operand form of a unary operation
+    fn forms() -> Vec<Form> {
+        vec![Form::new(
+            "Vs => Vd",
+            [
+                Field::operand("source", Self::REG_MASK[0]),
+                Field::operand("destination", Self::REG_MASK[1]),
+            ],
+        )]
+    }
 }
 
 impl Decode for UnaryOp {
@@ -97,7 +123,7 @@ impl Display for UnaryOp {
 }
 
 /// floating-point conversion operation
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ConversionOp {
     /// Source register
     pub source: Register,
@@ -111,6 +137,17 @@ impl ConversionOp {
         0b0000_0000_0000_0000_0000_0000_0000_1111,
     ];
     const REG_SHIFT: [Word; 2] = [4, 0];
+
+    /// Describes the operand form of a conversion
+    fn forms() -> Vec<Form> {
+        vec![Form::new(
+            "Vs => Vd",
+            [
+                Field::operand("source", Self::REG_MASK[0]),
+                Field::operand("destination", Self::REG_MASK[1]),
+            ],
+        )]
+    }
 }
 
 impl Decode for ConversionOp {
@@ -136,7 +173,7 @@ impl Display for ConversionOp {
 }
 
 /// Binary floating-point comparison
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CompOp {
     /// Left register
     pub left: Register,
@@ -150,6 +187,17 @@ impl CompOp {
         0b0000_0000_0000_0000_0000_1111_0000_0000,
     ];
     const REG_SHIFT: [Word; 2] = [4, 8];
+
+    /// Describes the operand form of a comparison
+    fn forms() -> Vec<Form> {
+        vec![Form::new(
+            "Vl, Vr",
+            [
+                Field::operand("left", Self::REG_MASK[0]),
+                Field::operand("right", Self::REG_MASK[1]),
+            ],
+        )]
+    }
 }
 
 impl Decode for CompOp {
@@ -174,11 +222,19 @@ impl Display for CompOp {
 }
 
 /// Floating-point check operation (NAN/INF)
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CheckOp(pub Register);
 
 impl CheckOp {
     const REG_MASK: Word = 0b0000_0000_0000_0000_0000_0000_0000_1111;
+
+    /// Describes the operand form of a check
+    fn forms() -> Vec<Form> {
+        vec![Form::new(
+            "Vx",
+            [Field::operand("register", Self::REG_MASK)],
+        )]
+    }
 }
 
 impl Decode for CheckOp {
@@ -200,7 +256,7 @@ impl Display for CheckOp {
 }
 
 /// Floating point instructions
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FloatingPointOp {
     /// Floating-point addition
     ///
@@ -387,7 +443,7 @@ impl Info for FloatingPointOp {
                 destination: r,
             }) => [r, ZF, OF, EPS, NAN, INF].into(),
 
-            _ => [].into(),
+            Fcmp(_) | Fchk(_) => [ZF, OF, EPS, NAN, INF].into(),
         }
     }
 
@@ -446,6 +502,83 @@ impl Info for FloatingPointOp {
     }
 }
 
+impl FloatingPointOp {
+    /// Describes each floating-point operation, for the [reference](super::reference())
+    pub(super) fn reference() -> Vec<(&'static str, Vec<Form>)> {
+        let op = |value, forms: Vec<Form>| -> Vec<Form> {
+            forms
+                .into_iter()
+                .map(|form| form.with(Field::fixed("op", Self::MASK, value)))
+                .collect()
+        };
+
+        vec![
+            ("FADD", op(Self::FADD, BinaryOp::forms())),
+            ("FSUB", op(Self::FSUB, BinaryOp::forms())),
+            ("FMUL", op(Self::FMUL, BinaryOp::forms())),
+            ("FDIV", op(Self::FDIV, BinaryOp::forms())),
+            ("FMOD", op(Self::FMOD, BinaryOp::forms())),
+            ("FCMP", op(Self::FCMP, CompOp::forms())),
+            ("FNEG", op(Self::FNEG, UnaryOp::forms())),
+            ("FREC", op(Self::FREC, UnaryOp::forms())),
+            ("ITOF", op(Self::ITOF, ConversionOp::forms())),
+            ("FTOI", op(Self::FTOI, ConversionOp::forms())),
+            ("FCHK", op(Self::FCHK, CheckOp::forms())),
+        ]
+    }
+
+    /// Gets the explicit operands of the operation
+    pub fn operands(self) -> Operands {
+        use FloatingPointOp::*;
+
+        match self {
+            Fadd(BinaryOp {
+                left,
+                right,
+                destination,
+            })
+            | Fsub(BinaryOp {
+                left,
+                right,
+                destination,
+            })
+            | Fmul(BinaryOp {
+                left,
+                right,
+                destination,
+            })
+            | Fdiv(BinaryOp {
+                left,
+                right,
+                destination,
+            })
+            | Fmod(BinaryOp {
+                left,
+                right,
+                destination,
+            }) => Operands::registers([left, right], [destination]),
+            Fneg(UnaryOp {
+                source,
+                destination,
+            })
+            | Frec(UnaryOp {
+                source,
+                destination,
+            })
+            | Itof(ConversionOp {
+                source,
+                destination,
+            })
+            | Ftoi(ConversionOp {
+                source,
+                destination,
+            }) => Operands::registers([source], [destination]),
+            Fcmp(CompOp { left, right }) => Operands::registers([left, right], []),
+            Fchk(CheckOp(r)) => Operands::registers([r], []),
+        }
+    }
+}
+
 impl Display for FloatingPointOp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use FloatingPointOp::*;
@@ -1,14 +1,18 @@
 //! Integer operations
-use super::{error::DecodeResult, Decode, Encode, Info};
+use super::{
+    error::DecodeResult,
+    reference::{Field, Form},
+    Addressing, Decode, Encode, Info, Operands,
+};
 use crate::{
     instruction_set::{decode, error::DecodeError},
     registers::{RegisterFlags, EPS, INF, NAN, OF, ZF},
-    types::{Register, Word},
+    types::{Register, SWord, Word},
 };
 use std::fmt::Display;
 
 /// Binary operation (two parameters)
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BinaryOp {
     /// Source register, 13-bit immediate parameter, and destination register
     Immediate(Register, Word, Register),
@@ -35,6 +39,35 @@ impl BinaryOp {
     const REG_PARAM_MASK: Word = 0b0000_0000_0000_0000_0000_1111_0000_0000;
     /// Parameter shift
     const PARAM_SHIFT: Word = 8;
+
+    /// Describes the operand forms of a binary operation
+    fn forms() -> Vec<Form> {
+        let registers = [
+            Field::operand("source", Self::SRC_REG_MASK),
+            Field::operand("destination", Self::DST_REG_MASK),
+        ];
+
+        vec![
+            Form::new(
+                "Vs, Vp => Vd",
+                [
+                    Field::fixed("immediate", Self::IMM_FLAG_MASK, 0),
+                    Field::operand("parameter", Self::REG_PARAM_MASK),
+                ]
+                .into_iter()
+                .chain(registers),
+            ),
+            Form::new(
+                "Vs, imm => Vd",
+                [
+                    Field::fixed("immediate", Self::IMM_FLAG_MASK, 1),
+                    Field::operand("parameter", Self::IMM_CONST_MASK),
+                ]
+                .into_iter()
+                .chain(registers),
+            ),
+        ]
+    }
 }
 
 impl Decode for BinaryOp {
@@ -89,13 +122,24 @@ impl Display for BinaryOp {
 }
 
 /// Sign extension operation
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SignExtendOp(pub Word, pub Register);
 
 impl SignExtendOp {
     const TGT_REG_MASK: Word = 0b0000_0000_0000_0000_0000_0000_0000_1111;
     const SXT_BIT_MASK: Word = 0b0000_0000_0000_0000_0000_0000_0011_0000;
     const SXT_BIT_SHIFT: Word = 4;
+
+    /// Describes the operand form of a sign extension
+    fn forms() -> Vec<Form> {
+        vec![Form::new(
+            "width Vx",
+            [
+                Field::operand("width", Self::SXT_BIT_MASK),
+                Field::operand("register", Self::TGT_REG_MASK),
+            ],
+        )]
+    }
 }
 
 impl Decode for SignExtendOp {
@@ -130,7 +174,7 @@ impl Display for SignExtendOp {
 }
 
 /// Unary operation (one parameter)
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct UnaryOp(pub Register, pub Register);
 
 impl UnaryOp {
@@ -142,6 +186,17 @@ impl UnaryOp {
     const DST_REG_MASK: Word = 0b0000_0000_0000_0000_0000_0000_0000_1111;
     /// Destination register shift
     const DST_REG_SHIFT: Word = 0;
+
+    /// Describes the operand form of a unary operation
+    fn forms() -> Vec<Form> {
+        vec![Form::new(
+            "Vs => Vd",
+            [
+                Field::operand("source", Self::SRC_REG_MASK),
+                Field::operand("destination", Self::DST_REG_MASK),
+            ],
+        )]
+    }
 }
 
 impl Decode for UnaryOp {
@@ -155,7 +210,7 @@ impl Decode for UnaryOp {
 
 impl Encode for UnaryOp {
     fn encode(self) -> Word {
-        ((self.0 as Word) << Self::SRC_REG_SHIFT) | ((self.1 as Word) << Self::DST_REG_MASK)
+        ((self.0 as Word) << Self::SRC_REG_SHIFT) | ((self.1 as Word) << Self::DST_REG_SHIFT)
     }
 }
 
@@ -166,11 +221,11 @@ impl Display for UnaryOp {
 }
 
 /// Comparison operation (explicitly different from [`UnaryOp`])
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompOp {
     /// Compare two registers
     Registers(Register, Register, bool),
-    /// Compare a register value to an immediate
+    /// Compare a register value to an immediate, sign-extended from 15 bits
     Immediate(Register, Word, bool),
 }
 
@@ -182,7 +237,10 @@ impl CompOp {
     const RIGHT_REG_MASK: Word = 0b0000_0000_0000_0000_0000_1111_0000_0000;
     /// Right immediate mask
     const RIGHT_IMM_MASK: Word = 0b0000_0000_0111_1111_1111_1111_0000_0000;
+    /// Right immediate sign bit
     const RIGHT_IMM_SIGN: Word = 0b0000_0000_0100_0000_0000_0000_0000_0000;
+    /// Bits set when sign-extending a negative right immediate
+    const RIGHT_IMM_EXTEND: Word = 0b1111_1111_1111_1111_1000_0000_0000_0000;
     /// Right register shift
     const RIGHT_PARAM_SHIFT: Word = 8;
 
@@ -192,6 +250,35 @@ impl CompOp {
     const LEFT_REG_SHIFT: Word = 4;
     /// Signed mode bit
     const SIGNED_MODE: Word = 0b0000_0000_0000_0000_0000_0000_0000_1000;
+
+    /// Describes the operand forms of a comparison
+    fn forms() -> Vec<Form> {
+        let common = [
+            Field::operand("left", Self::LEFT_REG_MASK),
+            Field::operand("signed", Self::SIGNED_MODE),
+        ];
+
+        vec![
+            Form::new(
+                "[s] Vl, Vr",
+                [
+                    Field::fixed("immediate", Self::IMMEDIATE_MASK, 0),
+                    Field::operand("right", Self::RIGHT_REG_MASK),
+                ]
+                .into_iter()
+                .chain(common),
+            ),
+            Form::new(
+                "[s] Vl, imm",
+                [
+                    Field::fixed("immediate", Self::IMMEDIATE_MASK, 1),
+                    Field::operand("right", Self::RIGHT_IMM_MASK),
+                ]
+                .into_iter()
+                .chain(common),
+            ),
+        ]
+    }
 }
 
 impl Decode for CompOp {
@@ -211,7 +298,7 @@ impl Decode for CompOp {
             let left = (word & Self::LEFT_REG_MASK) >> Self::LEFT_REG_SHIFT;
             let mut right = (word & Self::RIGHT_IMM_MASK) >> Self::RIGHT_PARAM_SHIFT;
             if word & Self::RIGHT_IMM_SIGN != 0 {
-                right |= 0b1111_1111_1000_0000_0000_0000_0000_0000;
+                right |= Self::RIGHT_IMM_EXTEND;
             }
 
             Ok(Immediate(
@@ -251,19 +338,22 @@ impl Display for CompOp {
             &Registers(left, right, signed) => {
                 write!(f, "{}V{left:X}, V{right:X}", if signed { "s " } else { "" })
             }
-            &Immediate(left, right, signed) => {
-                write!(f, "{}V{left:X}, {right}", if signed { "s " } else { "" })
-            }
+            &Immediate(left, right, signed) => write!(
+                f,
+                "{}V{left:X}, {}",
+                if signed { "s " } else { "" },
+                right as SWord
+            ),
         }
     }
 }
 
 /// Comparison operation (explicitly different from [`UnaryOp`])
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TestOp {
     /// Test two registers
     Registers(Register, Register),
-    /// Test a register against an immediate
+    /// Test a register against an immediate, sign-extended from 15 bits
     Immediate(Register, Word),
 }
 
@@ -275,7 +365,10 @@ impl TestOp {
     const RIGHT_REG_MASK: Word = 0b0000_0000_0000_0000_0000_1111_0000_0000;
     /// Right immediate mask
     const RIGHT_IMM_MASK: Word = 0b0000_0000_0111_1111_1111_1111_0000_0000;
+    /// Right immediate sign bit
     const RIGHT_IMM_SIGN: Word = 0b0000_0000_0100_0000_0000_0000_0000_0000;
+    /// Bits set when sign-extending a negative right immediate
+    const RIGHT_IMM_EXTEND: Word = 0b1111_1111_1111_1111_1000_0000_0000_0000;
     /// Right register shift
     const RIGHT_PARAM_SHIFT: Word = 8;
 
@@ -283,6 +376,30 @@ impl TestOp {
     const LEFT_REG_MASK: Word = 0b0000_0000_0000_0000_0000_0000_1111_0000;
     /// Left register shift
     const LEFT_REG_SHIFT: Word = 4;
+
+    /// Describes the operand forms of a test
+    fn forms() -> Vec<Form> {
+        let left = Field::operand("left", Self::LEFT_REG_MASK);
+
+        vec![
+            Form::new(
+                "Vl, Vr",
+                [
+                    Field::fixed("immediate", Self::IMMEDIATE_MASK, 0),
+                    Field::operand("right", Self::RIGHT_REG_MASK),
+                    left,
+                ],
+            ),
+            Form::new(
+                "Vl, imm",
+                [
+                    Field::fixed("immediate", Self::IMMEDIATE_MASK, 1),
+                    Field::operand("right", Self::RIGHT_IMM_MASK),
+                    left,
+                ],
+            ),
+        ]
+    }
 }
 
 impl Decode for TestOp {
@@ -298,7 +415,7 @@ impl Decode for TestOp {
             let left = (word & Self::LEFT_REG_MASK) >> Self::LEFT_REG_SHIFT;
             let mut right = (word & Self::RIGHT_IMM_MASK) >> Self::RIGHT_PARAM_SHIFT;
             if word & Self::RIGHT_IMM_SIGN != 0 {
-                right |= 0b1111_1111_1000_0000_0000_0000_0000_0000;
+                right |= Self::RIGHT_IMM_EXTEND;
             }
 
             Ok(Immediate(left as Register, right))
@@ -330,13 +447,13 @@ impl Display for TestOp {
 
         match self {
             Registers(left, right) => write!(f, "V{left:X}, V{right:X}"),
-            Immediate(left, right) => write!(f, "V{left:X}, {right}"),
+            Immediate(left, right) => write!(f, "V{left:X}, {}", *right as SWord),
         }
     }
 }
 
 /// Integer operations
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IntegerOp {
     /// Add
     ///
@@ -361,18 +478,24 @@ pub enum IntegerOp {
     /// ```seis
     /// DVU Vx, Vy, Vz
     /// ```
+    ///
+    /// Dividing by zero gives zero and sets the overflow flag.
     Dvu(BinaryOp),
     /// Divide signed
     ///
     /// ```seis
     /// DVS Vx, Vy, Vz
     /// ```
+    ///
+    /// Dividing by zero gives zero and sets the overflow flag.
     Dvs(BinaryOp),
     /// Modulo
     ///
     /// ```seis
     /// MOD Vx, Vy, Vz
     /// ```
+    ///
+    /// Dividing by zero gives zero and sets the overflow flag.
     Mod(BinaryOp),
     /// Compare
     ///
@@ -660,6 +783,63 @@ impl Info for IntegerOp {
     }
 }
 
+impl IntegerOp {
+    /// Describes each integer operation, for the [reference](super::reference())
+    pub(super) fn reference() -> Vec<(&'static str, Vec<Form>)> {
+        let op = |value, forms: Vec<Form>| -> Vec<Form> {
+            forms
+                .into_iter()
+                .map(|form| form.with(Field::fixed("op", Self::MASK, value)))
+                .collect()
+        };
+
+        vec![
+            ("ADD", op(Self::ADD, BinaryOp::forms())),
+            ("SUB", op(Self::SUB, BinaryOp::forms())),
+            ("MUL", op(Self::MUL, BinaryOp::forms())),
+            ("DVU", op(Self::DVU, BinaryOp::forms())),
+            ("DVS", op(Self::DVS, BinaryOp::forms())),
+            ("MOD", op(Self::MOD, BinaryOp::forms())),
+            ("AND", op(Self::AND, BinaryOp::forms())),
+            ("IOR", op(Self::IOR, BinaryOp::forms())),
+            ("XOR", op(Self::XOR, BinaryOp::forms())),
+            ("NOT", op(Self::NOT, UnaryOp::forms())),
+            ("SXT", op(Self::SXT, SignExtendOp::forms())),
+            ("BSL", op(Self::BSL, BinaryOp::forms())),
+            ("BSR", op(Self::BSR, BinaryOp::forms())),
+            ("ASR", op(Self::ASR, BinaryOp::forms())),
+            ("ROL", op(Self::ROL, BinaryOp::forms())),
+            ("ROR", op(Self::ROR, BinaryOp::forms())),
+            ("CMP", op(Self::CMP, CompOp::forms())),
+            ("TST", op(Self::TST, TestOp::forms())),
+        ]
+    }
+
+    /// Gets the explicit operands of the operation
+    pub fn operands(self) -> Operands {
+        use IntegerOp::*;
+
+        match self {
+            Add(b) | Sub(b) | Mul(b) | Dvu(b) | Dvs(b) | Mod(b) | And(b) | Ior(b) | Xor(b)
+            | Bsl(b) | Bsr(b) | Asr(b) | Rol(b) | Ror(b) => match b {
+                BinaryOp::Registers(r0, r1, dst) => Operands::registers([r0, r1], [dst]),
+                BinaryOp::Immediate(r, imm, dst) => Operands::registers([r], [dst])
+                    .with_immediate(imm as SWord, Addressing::Immediate),
+            },
+            Cmp(CompOp::Registers(r0, r1, _)) | Tst(TestOp::Registers(r0, r1)) => {
+                Operands::registers([r0, r1], [])
+            }
+            Cmp(CompOp::Immediate(r, imm, _)) | Tst(TestOp::Immediate(r, imm)) => {
+                Operands::registers([r], []).with_immediate(imm as SWord, Addressing::Immediate)
+            }
+            Not(UnaryOp(src, dst)) => Operands::registers([src], [dst]),
+            Sxt(SignExtendOp(width, r)) => {
+                Operands::registers([r], [r]).with_immediate(width as SWord, Addressing::Immediate)
+            }
+        }
+    }
+}
+
 impl Display for IntegerOp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use IntegerOp::*;
@@ -11,13 +11,20 @@ pub mod control;
 pub mod error;
 pub mod floating_point;
 pub mod integer;
+pub mod operands;
+pub mod reference;
 pub mod register;
 
+#[cfg(test)]
+mod tests;
+
 use crate::{registers::RegisterFlags, types::Word};
 pub use control::ControlOp;
 use error::{DecodeError, DecodeResult};
 pub use floating_point::FloatingPointOp;
 pub use integer::IntegerOp;
+pub use operands::{Addressing, Operands};
+pub use reference::reference;
 pub use register::RegisterOp;
 use std::fmt::Display;
 
@@ -56,7 +63,7 @@ pub fn encode<E: Encode>(e: E) -> Word {
 }
 
 /// Instruction categories
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Instruction {
     /// [Control operations](ControlOp)
     Control(ControlOp),
@@ -88,6 +95,39 @@ impl Instruction {
     const FLOATING_POINT: Word = 0b010;
     /// Register bits
     const REGISTER: Word = 0b011;
+
+    /// Gets the explicit operands of the instruction
+    pub fn operands(self) -> Operands {
+        use Instruction::*;
+
+        match self {
+            Control(c) => c.operands(),
+            Integer(i) => i.operands(),
+            FloatingPoint(f) => f.operands(),
+            Register(r) => r.operands(),
+        }
+    }
+
+    /// Gets the name of the category of the instruction
+    pub fn category(self) -> &'static str {
+        use Instruction::*;
+
+        match self {
+            Control(_) => "control",
+            Integer(_) => "integer",
+            FloatingPoint(_) => "floating_point",
+            Register(_) => "register",
+        }
+    }
+
+    /// Gets the mnemonic of the instruction, as it is disassembled
+    pub fn mnemonic(self) -> String {
+        let text = self.to_string();
+        text.split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_owned()
+    }
 }
 
 impl Decode for Instruction {
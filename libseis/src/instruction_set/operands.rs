@@ -0,0 +1,154 @@
+//! Structured operand information for an [`Instruction`](super::Instruction).
+//!
+//! Unlike [`Info`](super::Info), this only describes the operands written in
+//! the instruction itself; implicit registers such as the flags or the
+//! program counter are left out.
+
+use crate::{
+    registers::RegisterFlags,
+    types::{Byte, SWord},
+};
+use serde::Serialize;
+
+/// How an instruction addresses its non-register operand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Addressing {
+    /// The instruction takes no operands
+    Implied,
+    /// Every operand is a register
+    Register,
+    /// The immediate is used as a value
+    Immediate,
+    /// The immediate is loaded into a byte of the destination register
+    ShiftedImmediate {
+        /// How many bytes the immediate is shifted by
+        shift: Byte,
+        /// Whether the rest of the register is cleared
+        zero: bool,
+    },
+    /// The immediate is an offset from the program counter
+    Relative,
+    /// The immediate is an address in the zero page
+    ZeroPage,
+    /// The immediate is an offset from the stack base pointer
+    StackOffset,
+    /// The address is held in a register
+    Indirect {
+        /// Whether the access skips the cache
+        volatile: bool,
+    },
+    /// The address is held in a register, offset by the immediate
+    OffsetIndirect {
+        /// Whether the access skips the cache
+        volatile: bool,
+    },
+    /// The address is held in a register, offset by another register
+    IndexedIndirect {
+        /// Whether the access skips the cache
+        volatile: bool,
+    },
+    /// The operand is pushed to or popped from the stack
+    Stack,
+}
+
+impl Addressing {
+    /// Gets the name of the addressing mode
+    pub const fn name(self) -> &'static str {
+        use Addressing::*;
+
+        match self {
+            Implied => "implied",
+            Register => "register",
+            Immediate => "immediate",
+            ShiftedImmediate { .. } => "shifted_immediate",
+            Relative => "relative",
+            ZeroPage => "zero_page",
+            StackOffset => "stack_offset",
+            Indirect { .. } => "indirect",
+            OffsetIndirect { .. } => "offset_indirect",
+            IndexedIndirect { .. } => "indexed_indirect",
+            Stack => "stack",
+        }
+    }
+}
+
+/// The explicit operands of an instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operands {
+    /// The registers the instruction reads from
+    pub sources: RegisterFlags,
+    /// The registers the instruction writes to
+    pub destinations: RegisterFlags,
+    /// The immediate value, offset, or address, if any
+    pub immediate: Option<SWord>,
+    /// How the operands are addressed
+    pub addressing: Addressing,
+}
+
+impl Operands {
+    /// Operands of an instruction without any
+    pub const IMPLIED: Self = Self {
+        sources: RegisterFlags(0),
+        destinations: RegisterFlags(0),
+        immediate: None,
+        addressing: Addressing::Implied,
+    };
+
+    /// Creates the operands of an instruction which only addresses registers
+    pub fn registers(
+        sources: impl Into<RegisterFlags>,
+        destinations: impl Into<RegisterFlags>,
+    ) -> Self {
+        Self {
+            sources: sources.into(),
+            destinations: destinations.into(),
+            immediate: None,
+            addressing: Addressing::Register,
+        }
+    }
+
+    /// Attaches an immediate to the operands, using the provided addressing mode
+    pub fn with_immediate(self, immediate: SWord, addressing: Addressing) -> Self {
+        Self {
+            immediate: Some(immediate),
+            addressing,
+            ..self
+        }
+    }
+
+    /// Replaces the addressing mode of the operands
+    pub fn with_addressing(self, addressing: Addressing) -> Self {
+        Self { addressing, ..self }
+    }
+}
+
+impl Serialize for Operands {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+
+        map.serialize_entry("sources", &self.sources)?;
+        map.serialize_entry("destinations", &self.destinations)?;
+        map.serialize_entry("immediate", &self.immediate)?;
+        map.serialize_entry("addressing", self.addressing.name())?;
+
+        match self.addressing {
+            Addressing::ShiftedImmediate { shift, zero } => {
+                map.serialize_entry("shift", &shift)?;
+                map.serialize_entry("zero", &zero)?;
+            }
+            Addressing::Indirect { volatile }
+            | Addressing::OffsetIndirect { volatile }
+            | Addressing::IndexedIndirect { volatile } => {
+                map.serialize_entry("volatile", &volatile)?;
+            }
+            _ => {}
+        }
+
+        map.end()
+    }
+}
@@ -0,0 +1,128 @@
+//! A structured reference of the instruction set, for generating documentation.
+//!
+//! The layouts are built from the same masks and op-codes the encoders use, so
+//! the reference cannot drift away from what is actually encoded.
+
+use super::{ControlOp, FloatingPointOp, Instruction, IntegerOp, RegisterOp};
+use crate::types::Word;
+
+/// A range of bits in an encoded instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    /// What the bits hold
+    pub name: &'static str,
+    /// The least significant bit of the field
+    pub low: u32,
+    /// The most significant bit of the field
+    pub high: u32,
+    /// The value of the bits, if the form fixes them
+    pub value: Option<Word>,
+}
+
+impl Field {
+    /// Creates a field holding an operand, covering the bits set in `mask`
+    pub(super) const fn operand(name: &'static str, mask: Word) -> Self {
+        Self {
+            name,
+            low: mask.trailing_zeros(),
+            high: Word::BITS - 1 - mask.leading_zeros(),
+            value: None,
+        }
+    }
+
+    /// Creates a field covering the bits set in `mask`, which are always `value`
+    pub(super) const fn fixed(name: &'static str, mask: Word, value: Word) -> Self {
+        Self {
+            value: Some(value),
+            ..Self::operand(name, mask)
+        }
+    }
+
+    /// Gets the number of bits in the field
+    pub const fn width(self) -> u32 {
+        self.high - self.low + 1
+    }
+
+    /// Gets the mask selecting the bits of the field
+    pub const fn mask(self) -> Word {
+        (Word::MAX >> (Word::BITS - self.width())) << self.low
+    }
+}
+
+/// One operand form of an instruction, and how it is encoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Form {
+    /// The operands, as written after the mnemonic
+    pub syntax: &'static str,
+    /// The fields of the encoded word, from the most significant
+    pub fields: Vec<Field>,
+}
+
+impl Form {
+    /// Creates a form out of its fields
+    pub(super) fn new(syntax: &'static str, fields: impl IntoIterator<Item = Field>) -> Self {
+        let mut fields: Vec<Field> = fields.into_iter().collect();
+        fields.sort_by_key(|field| std::cmp::Reverse(field.high));
+
+        Self { syntax, fields }
+    }
+
+    /// Adds a field to the form
+    pub(super) fn with(self, field: Field) -> Self {
+        Self::new(self.syntax, self.fields.into_iter().chain([field]))
+    }
+
+    /// Checks whether `word` is encoded in this form: every fixed field holds its value,
+    /// and no bits are set outside of the fields
+    pub fn matches(&self, word: Word) -> bool {
+        let used = self
+            .fields
+            .iter()
+            .fold(0, |used, field| used | field.mask());
+
+        word & !used == 0
+            && self.fields.iter().all(|field| {
+                field
+                    .value
+                    .is_none_or(|value| (word & field.mask()) >> field.low == value)
+            })
+    }
+}
+
+/// The description of an instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionReference {
+    /// The mnemonic of the instruction
+    pub mnemonic: &'static str,
+    /// The name of the category, as given by [`Instruction::category`]
+    pub category: &'static str,
+    /// The operand forms of the instruction
+    pub forms: Vec<Form>,
+}
+
+/// Describes every instruction: its mnemonic, category, and the layout of each of its forms
+pub fn reference() -> Vec<InstructionReference> {
+    [
+        ("control", Instruction::CONTROL, ControlOp::reference()),
+        ("integer", Instruction::INTEGER, IntegerOp::reference()),
+        (
+            "floating_point",
+            Instruction::FLOATING_POINT,
+            FloatingPointOp::reference(),
+        ),
+        ("register", Instruction::REGISTER, RegisterOp::reference()),
+    ]
+    .into_iter()
+    .flat_map(|(category, op_type, instructions)| {
+        let op_type = Field::fixed("op_type", Instruction::MASK, op_type);
+
+        instructions
+            .into_iter()
+            .map(move |(mnemonic, forms)| InstructionReference {
+                mnemonic,
+                category,
+                forms: forms.into_iter().map(|form| form.with(op_type)).collect(),
+            })
+    })
+    .collect()
+}
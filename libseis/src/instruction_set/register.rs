@@ -1,19 +1,31 @@
 //! Register operations
 
-use super::{error::DecodeResult, Decode, Encode, Info};
+use super::{
+    error::DecodeResult,
+    reference::{Field, Form},
+    Addressing, Decode, Encode, Info, Operands,
+};
 use crate::registers::{get_name, RegisterFlags, EPS, INF, NAN, OF, SP, ZF};
 use crate::{
     instruction_set::{decode, error::DecodeError},
     registers,
-    types::{Byte, Register, Short, Word},
+    types::{Byte, Register, SWord, Short, Word},
 };
 use std::fmt::Display;
 
+/// Mask to extract the register of a [push](RegisterOp::Push), [pop](RegisterOp::Pop),
+/// or [cycle counter read](RegisterOp::Rdcyc)
+const REG_MASK: Word = 0b0000_0000_0000_0000_0000_0000_0001_1111;
+
 impl Decode for Register {
     fn decode(word: Word) -> DecodeResult<Self> {
-        const REG_MASK: Word = 0b0000_0000_0000_0000_0000_0000_0001_1111;
+        let register = (word & REG_MASK) as Register;
 
-        Ok((word & REG_MASK) as Register)
+        if (register as usize) < registers::COUNT {
+            Ok(register)
+        } else {
+            Err(DecodeError::UnknownRegister(register))
+        }
     }
 }
 
@@ -24,7 +36,7 @@ impl Encode for Register {
 }
 
 /// Represents an immediate load operation
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ImmOp {
     /// An immediate value, with a shift
     Immediate {
@@ -55,6 +67,32 @@ impl ImmOp {
     const IMM_SHIFT: Word = 4;
     const IMM_BSHIFT_MASK: Word = 0b0000_0000_0011_0000_0000_0000_0000_0000;
     const IMM_BSHIFT_SHIFT: Word = 20;
+
+    /// Describes the operand forms of an immediate load
+    fn forms() -> Vec<Form> {
+        let destination = Field::operand("destination", Self::DEST_REG_MASK);
+
+        vec![
+            Form::new(
+                "imm => Vx[.n]",
+                [
+                    Field::fixed("zero_page", Self::ZPG_TRANSLATE, 0),
+                    Field::operand("zero", Self::ZERO_FLAG),
+                    Field::operand("shift", Self::IMM_BSHIFT_MASK),
+                    Field::operand("immediate", Self::IMM_MASK),
+                    destination,
+                ],
+            ),
+            Form::new(
+                "&zpa => Vx",
+                [
+                    Field::fixed("zero_page", Self::ZPG_TRANSLATE, 1),
+                    Field::operand("address", Self::IMM_MASK),
+                    destination,
+                ],
+            ),
+        ]
+    }
 }
 
 impl Decode for ImmOp {
@@ -110,7 +148,7 @@ impl Encode for ImmOp {
                 let address = (address as Word) << Self::IMM_SHIFT;
                 let destination = destination as Word & Self::DEST_REG_MASK;
 
-                address | destination
+                Self::ZPG_TRANSLATE | address | destination
             }
         }
     }
@@ -148,7 +186,7 @@ impl Display for ImmOp {
 }
 
 /// Represents a register transfer operation
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RegOp {
     /// Where to read the value from
     pub source: Register,
@@ -159,6 +197,17 @@ pub struct RegOp {
 impl RegOp {
     const SRC_REGISTER_MASK: Word = 0b0000_0000_0000_0000_1111_1111_0000_0000;
     const DST_REGISTER_MASK: Word = 0b0000_0000_0000_0000_0000_0000_1111_1111;
+
+    /// Describes the operand form of a transfer
+    fn forms() -> Vec<Form> {
+        vec![Form::new(
+            "Vs => Vd",
+            [
+                Field::operand("source", Self::SRC_REGISTER_MASK),
+                Field::operand("destination", Self::DST_REGISTER_MASK),
+            ],
+        )]
+    }
 }
 
 impl Decode for RegOp {
@@ -196,7 +245,7 @@ impl Display for RegOp {
 
 /// Represents addressing modes.
 /// Includes translation for the zero-page.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WriteOp {
     /// Address with the zero-page ID in the upper bytes
     ZeroPage {
@@ -209,6 +258,8 @@ pub enum WriteOp {
     Indirect {
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted, for data which is not reused
+        non_temporal: bool,
         /// The register containing the effective address
         address: Register,
         /// The source register
@@ -218,6 +269,8 @@ pub enum WriteOp {
     OffsetIndirect {
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted, for data which is not reused
+        non_temporal: bool,
         /// The register from which the address comes from
         address: Register,
         /// The immediate offset
@@ -229,6 +282,8 @@ pub enum WriteOp {
     IndexedIndirect {
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted, for data which is not reused
+        non_temporal: bool,
         /// The register from which the address comes from
         address: Register,
         /// The offset from that address
@@ -269,6 +324,106 @@ impl WriteOp {
     const VOLATILE_OFFSET_MODE: Word = 0b101;
     const VOLATILE_INDEXED_MODE: Word = 0b110;
     const ZERO_PAGE_MODE: Word = 0b111;
+
+    const NON_TEMPORAL_FLAG: Word = 0b0000_0000_0001_0000_0000_0000_0000_0000;
+    const NON_TEMPORAL_SHIFT: Word = 20;
+
+    /// Whether the access leaves its line as the next to be evicted
+    pub fn non_temporal(self) -> bool {
+        matches!(
+            self,
+            Self::Indirect {
+                non_temporal: true,
+                ..
+            } | Self::OffsetIndirect {
+                non_temporal: true,
+                ..
+            } | Self::IndexedIndirect {
+                non_temporal: true,
+                ..
+            }
+        )
+    }
+
+    /// Describes the operand forms of a store, one for each addressing mode
+    fn forms() -> Vec<Form> {
+        let mode = |value| Field::fixed("mode", Self::ADDR_MODE_MASK, value);
+        let non_temporal = Field::operand("non_temporal", Self::NON_TEMPORAL_FLAG);
+        let address = Field::operand("address", Self::ADDRESS_REG_MASK);
+        let register = Field::operand("source", Self::SRC_REG_MASK);
+
+        vec![
+            Form::new(
+                "Vx => @zpg",
+                [
+                    mode(Self::ZERO_PAGE_MODE),
+                    Field::operand("address", Self::ZPG_ADDR_MASK),
+                    register,
+                ],
+            ),
+            Form::new(
+                "Vx => %bpo",
+                [
+                    mode(Self::STACK_OFFSET_MODE),
+                    Field::operand("offset", Self::OFFSET_MASK),
+                    register,
+                ],
+            ),
+            Form::new(
+                "Vx => Va",
+                [mode(Self::INDIRECT_MODE), non_temporal, address, register],
+            ),
+            Form::new(
+                "Vx => Va + n",
+                [
+                    mode(Self::OFFSET_MODE),
+                    non_temporal,
+                    address,
+                    Field::operand("offset", Self::OFFSET_MASK),
+                    register,
+                ],
+            ),
+            Form::new(
+                "Vx => Va[Vi]",
+                [
+                    mode(Self::INDEXED_MODE),
+                    non_temporal,
+                    address,
+                    Field::operand("index", Self::INDEX_REG_MASK),
+                    register,
+                ],
+            ),
+            Form::new(
+                "Vx =>> Va",
+                [
+                    mode(Self::VOLATILE_INDIRECT_MODE),
+                    non_temporal,
+                    address,
+                    register,
+                ],
+            ),
+            Form::new(
+                "Vx =>> Va + n",
+                [
+                    mode(Self::VOLATILE_OFFSET_MODE),
+                    non_temporal,
+                    address,
+                    Field::operand("offset", Self::OFFSET_MASK),
+                    register,
+                ],
+            ),
+            Form::new(
+                "Vx =>> Va[Vi]",
+                [
+                    mode(Self::VOLATILE_INDEXED_MODE),
+                    non_temporal,
+                    address,
+                    Field::operand("index", Self::INDEX_REG_MASK),
+                    register,
+                ],
+            ),
+        ]
+    }
 }
 
 impl Decode for WriteOp {
@@ -280,17 +435,20 @@ impl Decode for WriteOp {
         match addr_mode {
             Self::INDIRECT_MODE => Ok(Indirect {
                 volatile: false,
+                non_temporal: word & Self::NON_TEMPORAL_FLAG != 0,
                 address: ((word & Self::ADDRESS_REG_MASK) >> Self::ADDRESS_REG_SHIFT) as Register,
                 source,
             }),
             Self::OFFSET_MODE => Ok(OffsetIndirect {
                 volatile: false,
+                non_temporal: word & Self::NON_TEMPORAL_FLAG != 0,
                 address: ((word & Self::ADDRESS_REG_MASK) >> Self::ADDRESS_REG_SHIFT) as Register,
                 offset: ((word & Self::OFFSET_MASK) >> Self::OFFSET_SHIFT) as Short,
                 source,
             }),
             Self::INDEXED_MODE => Ok(IndexedIndirect {
                 volatile: false,
+                non_temporal: word & Self::NON_TEMPORAL_FLAG != 0,
                 address: ((word & Self::ADDRESS_REG_MASK) >> Self::ADDRESS_REG_SHIFT) as Register,
                 index: ((word & Self::INDEX_REG_MASK) >> Self::INDEX_REG_SHIFT) as Register,
                 source,
@@ -301,17 +459,20 @@ impl Decode for WriteOp {
             }),
             Self::VOLATILE_INDIRECT_MODE => Ok(Indirect {
                 volatile: true,
+                non_temporal: word & Self::NON_TEMPORAL_FLAG != 0,
                 address: ((word & Self::ADDRESS_REG_MASK) >> Self::ADDRESS_REG_SHIFT) as Register,
                 source,
             }),
             Self::VOLATILE_OFFSET_MODE => Ok(OffsetIndirect {
                 volatile: true,
+                non_temporal: word & Self::NON_TEMPORAL_FLAG != 0,
                 address: ((word & Self::ADDRESS_REG_MASK) >> Self::ADDRESS_REG_SHIFT) as Register,
                 offset: ((word & Self::OFFSET_MASK) >> Self::OFFSET_SHIFT) as Short,
                 source,
             }),
             Self::VOLATILE_INDEXED_MODE => Ok(IndexedIndirect {
                 volatile: true,
+                non_temporal: word & Self::NON_TEMPORAL_FLAG != 0,
                 address: ((word & Self::ADDRESS_REG_MASK) >> Self::ADDRESS_REG_SHIFT) as Register,
                 index: ((word & Self::INDEX_REG_MASK) >> Self::INDEX_REG_SHIFT) as Register,
                 source,
@@ -342,62 +503,74 @@ impl Encode for WriteOp {
             }
             Indirect {
                 volatile: false,
+                non_temporal,
                 address,
                 source,
             } => {
                 (Self::INDIRECT_MODE << Self::ADDR_MODE_SHIFT)
+                    | ((non_temporal as Word) << Self::NON_TEMPORAL_SHIFT)
                     | ((address as Word) << Self::ADDRESS_REG_SHIFT)
                     | (source as Word)
             }
             OffsetIndirect {
                 volatile: false,
+                non_temporal,
                 address,
                 offset,
                 source,
             } => {
                 (Self::OFFSET_MODE << Self::ADDR_MODE_SHIFT)
+                    | ((non_temporal as Word) << Self::NON_TEMPORAL_SHIFT)
                     | ((address as Word) << Self::ADDRESS_REG_SHIFT)
                     | ((offset as Word) << Self::OFFSET_SHIFT)
                     | (source as Word)
             }
             IndexedIndirect {
                 volatile: false,
+                non_temporal,
                 address,
                 index,
                 source,
             } => {
                 (Self::INDEXED_MODE << Self::ADDR_MODE_SHIFT)
+                    | ((non_temporal as Word) << Self::NON_TEMPORAL_SHIFT)
                     | ((address as Word) << Self::ADDRESS_REG_SHIFT)
                     | ((index as Word) << Self::INDEX_REG_SHIFT)
                     | (source as Word)
             }
             Indirect {
                 volatile: true,
+                non_temporal,
                 address,
                 source,
             } => {
                 (Self::VOLATILE_INDIRECT_MODE << Self::ADDR_MODE_SHIFT)
+                    | ((non_temporal as Word) << Self::NON_TEMPORAL_SHIFT)
                     | ((address as Word) << Self::ADDRESS_REG_SHIFT)
                     | (source as Word)
             }
             OffsetIndirect {
                 volatile: true,
+                non_temporal,
                 address,
                 offset,
                 source,
             } => {
                 (Self::VOLATILE_OFFSET_MODE << Self::ADDR_MODE_SHIFT)
+                    | ((non_temporal as Word) << Self::NON_TEMPORAL_SHIFT)
                     | ((address as Word) << Self::ADDRESS_REG_SHIFT)
                     | ((offset as Word) << Self::OFFSET_SHIFT)
                     | (source as Word)
             }
             IndexedIndirect {
                 volatile: true,
+                non_temporal,
                 address,
                 index,
                 source,
             } => {
                 (Self::VOLATILE_INDEXED_MODE << Self::ADDR_MODE_SHIFT)
+                    | ((non_temporal as Word) << Self::NON_TEMPORAL_SHIFT)
                     | ((address as Word) << Self::ADDRESS_REG_SHIFT)
                     | ((index as Word) << Self::INDEX_REG_SHIFT)
                     | (source as Word)
@@ -416,6 +589,7 @@ impl Display for WriteOp {
                 volatile,
                 address,
                 source,
+                ..
             } => write!(
                 f,
                 "V{source:X} {assign} V{address:X}",
@@ -426,6 +600,7 @@ impl Display for WriteOp {
                 address,
                 offset,
                 source,
+                ..
             } => write!(
                 f,
                 "V{source:X} {assign} V{address:X} + {offset}",
@@ -436,6 +611,7 @@ impl Display for WriteOp {
                 address,
                 index,
                 source,
+                ..
             } => write!(
                 f,
                 "V{source:X} {assign} V{address:X}[V{index:X}]",
@@ -448,7 +624,7 @@ impl Display for WriteOp {
 
 /// Represents addressing modes.
 /// Includes translation for the zero-page.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReadOp {
     /// Address with the zero-page ID in the upper bytes
     ZeroPage {
@@ -461,6 +637,8 @@ pub enum ReadOp {
     Indirect {
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted, for data which is not reused
+        non_temporal: bool,
         /// The register containing the effective address
         address: Register,
         /// Where to store the read value
@@ -470,6 +648,8 @@ pub enum ReadOp {
     OffsetIndirect {
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted, for data which is not reused
+        non_temporal: bool,
         /// The register containing an address
         address: Register,
         /// An immediate offset
@@ -481,6 +661,8 @@ pub enum ReadOp {
     IndexedIndirect {
         /// Whether to skip the cache
         volatile: bool,
+        /// Whether the line is left as the next to be evicted, for data which is not reused
+        non_temporal: bool,
         /// The register containing an address
         address: Register,
         /// The register containing the offset
@@ -521,6 +703,106 @@ impl ReadOp {
     const VOLATILE_OFFSET_MODE: Word = 0b101;
     const VOLATILE_INDEXED_MODE: Word = 0b110;
     const ZERO_PAGE_MODE: Word = 0b111;
+
+    const NON_TEMPORAL_FLAG: Word = 0b0000_0000_0001_0000_0000_0000_0000_0000;
+    const NON_TEMPORAL_SHIFT: Word = 20;
+
+    /// Whether the access leaves its line as the next to be evicted
+    pub fn non_temporal(self) -> bool {
+        matches!(
+            self,
+            Self::Indirect {
+                non_temporal: true,
+                ..
+            } | Self::OffsetIndirect {
+                non_temporal: true,
+                ..
+            } | Self::IndexedIndirect {
+                non_temporal: true,
+                ..
+            }
+        )
+    }
+
+    /// Describes the operand forms of a load, one for each addressing mode
+    fn forms() -> Vec<Form> {
+        let mode = |value| Field::fixed("mode", Self::ADDR_MODE_MASK, value);
+        let non_temporal = Field::operand("non_temporal", Self::NON_TEMPORAL_FLAG);
+        let address = Field::operand("address", Self::ADDRESS_REG_MASK);
+        let register = Field::operand("destination", Self::DST_REG_MASK);
+
+        vec![
+            Form::new(
+                "@zpg => Vx",
+                [
+                    mode(Self::ZERO_PAGE_MODE),
+                    Field::operand("address", Self::ZPG_ADDR_MASK),
+                    register,
+                ],
+            ),
+            Form::new(
+                "%bpo => Vx",
+                [
+                    mode(Self::STACK_OFFSET_MODE),
+                    Field::operand("offset", Self::OFFSET_MASK),
+                    register,
+                ],
+            ),
+            Form::new(
+                "Va => Vx",
+                [mode(Self::INDIRECT_MODE), non_temporal, address, register],
+            ),
+            Form::new(
+                "Va + n => Vx",
+                [
+                    mode(Self::OFFSET_MODE),
+                    non_temporal,
+                    address,
+                    Field::operand("offset", Self::OFFSET_MASK),
+                    register,
+                ],
+            ),
+            Form::new(
+                "Va[Vi] => Vx",
+                [
+                    mode(Self::INDEXED_MODE),
+                    non_temporal,
+                    address,
+                    Field::operand("index", Self::INDEX_REG_MASK),
+                    register,
+                ],
+            ),
+            Form::new(
+                "Va =>> Vx",
+                [
+                    mode(Self::VOLATILE_INDIRECT_MODE),
+                    non_temporal,
+                    address,
+                    register,
+                ],
+            ),
+            Form::new(
+                "Va + n =>> Vx",
+                [
+                    mode(Self::VOLATILE_OFFSET_MODE),
+                    non_temporal,
+                    address,
+                    Field::operand("offset", Self::OFFSET_MASK),
+                    register,
+                ],
+            ),
+            Form::new(
+                "Va[Vi] =>> Vx",
+                [
+                    mode(Self::VOLATILE_INDEXED_MODE),
+                    non_temporal,
+                    address,
+                    Field::operand("index", Self::INDEX_REG_MASK),
+                    register,
+                ],
+            ),
+        ]
+    }
 }
 
 impl Decode for ReadOp {
@@ -532,17 +814,20 @@ impl Decode for ReadOp {
         match addr_mode {
             Self::INDIRECT_MODE => Ok(Indirect {
                 volatile: false,
+                non_temporal: word & Self::NON_TEMPORAL_FLAG != 0,
                 address: ((word & Self::ADDRESS_REG_MASK) >> Self::ADDRESS_REG_SHIFT) as Register,
                 destination,
             }),
             Self::OFFSET_MODE => Ok(OffsetIndirect {
                 volatile: false,
+                non_temporal: word & Self::NON_TEMPORAL_FLAG != 0,
                 address: ((word & Self::ADDRESS_REG_MASK) >> Self::ADDRESS_REG_SHIFT) as Register,
                 offset: ((word & Self::OFFSET_MASK) >> Self::OFFSET_SHIFT) as Short,
                 destination,
             }),
             Self::INDEXED_MODE => Ok(IndexedIndirect {
                 volatile: false,
+                non_temporal: word & Self::NON_TEMPORAL_FLAG != 0,
                 address: ((word & Self::ADDRESS_REG_MASK) >> Self::ADDRESS_REG_SHIFT) as Register,
                 index: ((word & Self::INDEX_REG_MASK) >> Self::INDEX_REG_SHIFT) as Register,
                 destination,
@@ -553,17 +838,20 @@ impl Decode for ReadOp {
             }),
             Self::VOLATILE_INDIRECT_MODE => Ok(Indirect {
                 volatile: true,
+                non_temporal: word & Self::NON_TEMPORAL_FLAG != 0,
                 address: ((word & Self::ADDRESS_REG_MASK) >> Self::ADDRESS_REG_SHIFT) as Register,
                 destination,
             }),
             Self::VOLATILE_OFFSET_MODE => Ok(OffsetIndirect {
                 volatile: true,
+                non_temporal: word & Self::NON_TEMPORAL_FLAG != 0,
                 address: ((word & Self::ADDRESS_REG_MASK) >> Self::ADDRESS_REG_SHIFT) as Register,
                 offset: ((word & Self::OFFSET_MASK) >> Self::OFFSET_SHIFT) as Short,
                 destination,
             }),
             Self::VOLATILE_INDEXED_MODE => Ok(IndexedIndirect {
                 volatile: true,
+                non_temporal: word & Self::NON_TEMPORAL_FLAG != 0,
                 address: ((word & Self::ADDRESS_REG_MASK) >> Self::ADDRESS_REG_SHIFT) as Register,
                 index: ((word & Self::INDEX_REG_MASK) >> Self::INDEX_REG_SHIFT) as Register,
                 destination,
@@ -600,62 +888,74 @@ impl Encode for ReadOp {
             }
             Indirect {
                 volatile: false,
+                non_temporal,
                 address,
                 destination,
             } => {
                 (Self::INDIRECT_MODE << Self::ADDR_MODE_SHIFT)
+                    | ((non_temporal as Word) << Self::NON_TEMPORAL_SHIFT)
                     | ((address as Word) << Self::ADDRESS_REG_SHIFT)
                     | (destination as Word)
             }
             OffsetIndirect {
                 volatile: false,
+                non_temporal,
                 address,
                 offset,
                 destination,
             } => {
                 (Self::OFFSET_MODE << Self::ADDR_MODE_SHIFT)
+                    | ((non_temporal as Word) << Self::NON_TEMPORAL_SHIFT)
                     | ((address as Word) << Self::ADDRESS_REG_SHIFT)
                     | ((offset as Word) << Self::OFFSET_SHIFT)
                     | (destination as Word)
             }
             IndexedIndirect {
                 volatile: false,
+                non_temporal,
                 address,
                 index,
                 destination,
             } => {
                 (Self::INDEXED_MODE << Self::ADDR_MODE_SHIFT)
+                    | ((non_temporal as Word) << Self::NON_TEMPORAL_SHIFT)
                     | ((address as Word) << Self::ADDRESS_REG_SHIFT)
                     | ((index as Word) << Self::INDEX_REG_SHIFT)
                     | (destination as Word)
             }
             Indirect {
                 volatile: true,
+                non_temporal,
                 address,
                 destination,
             } => {
                 (Self::VOLATILE_INDIRECT_MODE << Self::ADDR_MODE_SHIFT)
+                    | ((non_temporal as Word) << Self::NON_TEMPORAL_SHIFT)
                     | ((address as Word) << Self::ADDRESS_REG_SHIFT)
                     | (destination as Word)
             }
             OffsetIndirect {
                 volatile: true,
+                non_temporal,
                 address,
                 offset,
                 destination,
             } => {
                 (Self::VOLATILE_OFFSET_MODE << Self::ADDR_MODE_SHIFT)
+                    | ((non_temporal as Word) << Self::NON_TEMPORAL_SHIFT)
                     | ((address as Word) << Self::ADDRESS_REG_SHIFT)
                     | ((offset as Word) << Self::OFFSET_SHIFT)
                     | (destination as Word)
             }
             IndexedIndirect {
                 volatile: true,
+                non_temporal,
                 address,
                 index,
                 destination,
             } => {
                 (Self::VOLATILE_INDEXED_MODE << Self::ADDR_MODE_SHIFT)
+                    | ((non_temporal as Word) << Self::NON_TEMPORAL_SHIFT)
                     | ((address as Word) << Self::ADDRESS_REG_SHIFT)
                     | ((index as Word) << Self::INDEX_REG_SHIFT)
                     | (destination as Word)
@@ -677,6 +977,7 @@ impl Display for ReadOp {
                 volatile,
                 address,
                 destination,
+                ..
             } => write!(
                 f,
                 "V{address:X} {assign} V{destination:X}",
@@ -687,6 +988,7 @@ impl Display for ReadOp {
                 address,
                 offset,
                 destination,
+                ..
             } => write!(
                 f,
                 "V{address:X} + {offset} {assign} V{destination:X}",
@@ -697,6 +999,7 @@ impl Display for ReadOp {
                 address,
                 index,
                 destination,
+                ..
             } => write!(
                 f,
                 "V{address:X}[V{index:X}] {assign} V{destination:X}",
@@ -716,7 +1019,12 @@ impl Display for ReadOp {
 /// operation for the Indirect, Offset Indirect, and Indexed Indirect addressing modes.
 ///
 /// For such operation, replace the last comma with `=>>`.
-#[derive(Debug, Clone, Copy)]
+///
+/// The same addressing modes support a non-temporal hint, written as a `.NT` suffix on the
+/// mnemonic (`LLR.NT Va => Vx`). Non-temporal accesses still go through the cache, but leave
+/// their line as the next to be evicted from its set, so streaming through data which is not
+/// reused does not push out the lines which are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RegisterOp {
     /// Load byte to register
     ///
@@ -805,6 +1113,17 @@ pub enum RegisterOp {
     /// LDR &zpa, Vx  ; Load zero page address
     /// ```
     Ldr(ImmOp),
+    /// Read the cycle counter
+    ///
+    /// ```seis
+    /// RDCYC Vx
+    /// ```
+    ///
+    /// Writes the number of clocks the processor has run for when the instruction
+    /// executes. The counter is only 32 bits wide, so it wraps around to zero after
+    /// 2^32 clocks, and the difference between two reads is only correct (with
+    /// wrapping subtraction) if fewer clocks than that passed between them.
+    Rdcyc(Register),
 }
 
 impl RegisterOp {
@@ -821,6 +1140,7 @@ impl RegisterOp {
     const SLR: Word = 0b0111;
     const TFR: Word = 0b1000;
     const LDR: Word = 0b1001;
+    const RDCYC: Word = 0b1010;
 }
 
 impl Decode for RegisterOp {
@@ -839,6 +1159,7 @@ impl Decode for RegisterOp {
             Self::SLR => Ok(Slr(decode(word)?)),
             Self::TFR => Ok(Tfr(decode(word)?)),
             Self::LDR => Ok(Ldr(decode(word)?)),
+            Self::RDCYC => Ok(Rdcyc(decode(word)?)),
             _ => Err(DecodeError::InvalidRegisterOp(reg_op)),
         }
     }
@@ -859,6 +1180,7 @@ impl Encode for RegisterOp {
             Push(p) => (Self::PUSH << Self::SHIFT) | p.encode(),
             Pop(p) => (Self::POP << Self::SHIFT) | p.encode(),
             Ldr(i) => (Self::LDR << Self::SHIFT) | i.encode(),
+            Rdcyc(r) => (Self::RDCYC << Self::SHIFT) | r.encode(),
         }
     }
 }
@@ -896,6 +1218,7 @@ impl Info for RegisterOp {
 
             Pop(reg) => [reg, SP, ZF, OF, EPS, NAN, INF].into(),
             Push(_) => [SP].into(),
+            Rdcyc(destination) => [destination].into(),
 
             _ => [].into(),
         }
@@ -939,21 +1262,151 @@ impl Info for RegisterOp {
     }
 }
 
+impl RegisterOp {
+    /// Describes each register operation, for the [reference](super::reference())
+    pub(super) fn reference() -> Vec<(&'static str, Vec<Form>)> {
+        let op = |value, forms: Vec<Form>| -> Vec<Form> {
+            forms
+                .into_iter()
+                .map(|form| form.with(Field::fixed("op", Self::MASK, value)))
+                .collect()
+        };
+        let register = |syntax| vec![Form::new(syntax, [Field::operand("register", REG_MASK)])];
+
+        vec![
+            ("PUSH", op(Self::PUSH, register("{Vx}"))),
+            ("POP", op(Self::POP, register("{Vx}"))),
+            ("LBR", op(Self::LBR, ReadOp::forms())),
+            ("SBR", op(Self::SBR, WriteOp::forms())),
+            ("LSR", op(Self::LSR, ReadOp::forms())),
+            ("SSR", op(Self::SSR, WriteOp::forms())),
+            ("LLR", op(Self::LLR, ReadOp::forms())),
+            ("SLR", op(Self::SLR, WriteOp::forms())),
+            ("TFR", op(Self::TFR, RegOp::forms())),
+            ("LDR", op(Self::LDR, ImmOp::forms())),
+            ("RDCYC", op(Self::RDCYC, register("Vx"))),
+        ]
+    }
+
+    /// Gets the explicit operands of the operation
+    pub fn operands(self) -> Operands {
+        use RegisterOp::*;
+
+        match self {
+            Lbr(r) | Lsr(r) | Llr(r) => match r {
+                ReadOp::ZeroPage {
+                    address,
+                    destination,
+                } => Operands::registers([], [destination])
+                    .with_immediate(address as SWord, Addressing::ZeroPage),
+                ReadOp::Indirect {
+                    volatile,
+                    address,
+                    destination,
+                    ..
+                } => Operands::registers([address], [destination])
+                    .with_addressing(Addressing::Indirect { volatile }),
+                ReadOp::OffsetIndirect {
+                    volatile,
+                    address,
+                    offset,
+                    destination,
+                    ..
+                } => Operands::registers([address], [destination])
+                    .with_immediate(offset as SWord, Addressing::OffsetIndirect { volatile }),
+                ReadOp::IndexedIndirect {
+                    volatile,
+                    address,
+                    index,
+                    destination,
+                    ..
+                } => Operands::registers([address, index], [destination])
+                    .with_addressing(Addressing::IndexedIndirect { volatile }),
+                ReadOp::StackOffset {
+                    offset,
+                    destination,
+                } => Operands::registers([], [destination])
+                    .with_immediate(offset as SWord, Addressing::StackOffset),
+            },
+            Sbr(w) | Ssr(w) | Slr(w) => match w {
+                WriteOp::ZeroPage { address, source } => Operands::registers([source], [])
+                    .with_immediate(address as SWord, Addressing::ZeroPage),
+                WriteOp::Indirect {
+                    volatile,
+                    address,
+                    source,
+                    ..
+                } => Operands::registers([address, source], [])
+                    .with_addressing(Addressing::Indirect { volatile }),
+                WriteOp::OffsetIndirect {
+                    volatile,
+                    address,
+                    offset,
+                    source,
+                    ..
+                } => Operands::registers([address, source], [])
+                    .with_immediate(offset as SWord, Addressing::OffsetIndirect { volatile }),
+                WriteOp::IndexedIndirect {
+                    volatile,
+                    address,
+                    index,
+                    source,
+                    ..
+                } => Operands::registers([address, index, source], [])
+                    .with_addressing(Addressing::IndexedIndirect { volatile }),
+                WriteOp::StackOffset { offset, source } => Operands::registers([source], [])
+                    .with_immediate(offset as SWord, Addressing::StackOffset),
+            },
+            Tfr(RegOp {
+                source,
+                destination,
+            }) => Operands::registers([source], [destination]),
+            Push(r) => Operands::registers([r], []).with_addressing(Addressing::Stack),
+            Pop(r) => Operands::registers([], [r]).with_addressing(Addressing::Stack),
+            Ldr(ImmOp::Immediate {
+                zero,
+                shift,
+                immediate,
+                destination,
+            }) => Operands::registers([], [destination]).with_immediate(
+                immediate as SWord,
+                Addressing::ShiftedImmediate { shift, zero },
+            ),
+            Ldr(ImmOp::ZeroPageTranslate {
+                address,
+                destination,
+            }) => Operands::registers([], [destination])
+                .with_immediate(address as SWord, Addressing::ZeroPage),
+            Rdcyc(r) => Operands::registers([], [r]),
+        }
+    }
+}
+
+/// The suffix of a load or store mnemonic which marks it as non-temporal
+fn hint(non_temporal: bool) -> &'static str {
+    if non_temporal {
+        ".NT"
+    } else {
+        ""
+    }
+}
+
 impl Display for RegisterOp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use RegisterOp::*;
 
         match self {
-            Lbr(m) => write!(f, "LBR {m}"),
-            Lsr(m) => write!(f, "LSR {m}"),
-            Llr(m) => write!(f, "LLR {m}"),
-            Sbr(m) => write!(f, "SBR {m}"),
-            Ssr(m) => write!(f, "SSR {m}"),
-            Slr(m) => write!(f, "SLR {m}"),
+            Lbr(m) => write!(f, "LBR{} {m}", hint(m.non_temporal())),
+            Lsr(m) => write!(f, "LSR{} {m}", hint(m.non_temporal())),
+            Llr(m) => write!(f, "LLR{} {m}", hint(m.non_temporal())),
+            Sbr(m) => write!(f, "SBR{} {m}", hint(m.non_temporal())),
+            Ssr(m) => write!(f, "SSR{} {m}", hint(m.non_temporal())),
+            Slr(m) => write!(f, "SLR{} {m}", hint(m.non_temporal())),
             Tfr(r) => write!(f, "TFR {r}"),
             &Push(r) => write!(f, "PUSH {{{}}}", get_name(r).unwrap_or("unknown")),
             &Pop(r) => write!(f, "POP {{{}}}", get_name(r).unwrap_or("unknown")),
             Ldr(i) => write!(f, "LDR {i}"),
+            &Rdcyc(r) => write!(f, "RDCYC {}", get_name(r).unwrap_or("unknown")),
         }
     }
 }
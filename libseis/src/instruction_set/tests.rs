@@ -0,0 +1,552 @@
+//! Round-trip tests: every encodable instruction must decode back to itself.
+//!
+//! New instructions should add their variants to one of the `*_ops` samplers
+//! below so that they are covered by [`assert_round_trip`].
+
+use super::{
+    control::Jump,
+    floating_point::{self, CheckOp, ConversionOp},
+    integer::{self, SignExtendOp, TestOp},
+    register::{ImmOp, ReadOp, RegOp, WriteOp},
+    *,
+};
+use crate::{
+    registers::{COUNT, SP, V},
+    types::{Register, SWord, Short},
+};
+
+/// A sample of the variable registers, including both extremes
+const VREGS: [Register; 4] = [V[0x0], V[0x5], V[0xA], V[0xF]];
+
+/// Samples a field of `bits` width: zero, one, a mixed pattern, and the largest value.
+///
+/// The mixed pattern is clamped to the width of the field.
+fn field(bits: u32) -> [Word; 4] {
+    let max = Word::MAX >> (Word::BITS - bits);
+
+    [0, 1, 0x5A5A_5A5A & max, max]
+}
+
+/// Asserts that every instruction decodes back to itself after being encoded
+fn assert_round_trip(instructions: impl IntoIterator<Item = Instruction>) {
+    for instruction in instructions {
+        let word = encode(instruction);
+
+        match decode::<Instruction>(word) {
+            Ok(decoded) => assert_eq!(
+                decoded, instruction,
+                "{instruction} encoded as {word:#010X} but decoded as {decoded}"
+            ),
+            Err(e) => panic!("{instruction} encoded as {word:#010X} but failed to decode: {e:?}"),
+        }
+    }
+}
+
+fn jumps() -> Vec<Jump> {
+    VREGS
+        .into_iter()
+        .map(Jump::Register)
+        // Relative jumps are in words, stored as a sign-extended 24-bit value
+        .chain(
+            [
+                0,
+                4,
+                -4,
+                0x1234 << 2,
+                -(0x1234 << 2),
+                0x7F_FFFF << 2,
+                -0x80_0000 << 2,
+            ]
+            .into_iter()
+            .map(|offset: SWord| Jump::Relative(offset)),
+        )
+        .collect()
+}
+
+fn control_ops() -> Vec<Instruction> {
+    use ControlOp::*;
+
+    let constructors: [fn(Jump) -> ControlOp; 8] = [Jmp, Jsr, Jeq, Jne, Jgt, Jlt, Jge, Jle];
+
+    [Nop, Halt, Ret, Syscall]
+        .into_iter()
+        .chain(
+            constructors
+                .into_iter()
+                .flat_map(|op| jumps().into_iter().map(op)),
+        )
+        .map(Instruction::Control)
+        .collect()
+}
+
+/// Samples the immediates of comparisons and tests, which are sign-extended from 15 bits
+fn comparison_immediates() -> impl Iterator<Item = Word> {
+    field(14)
+        .into_iter()
+        .chain([-1, -2, -0x1A5A, -0x4000].map(|imm: SWord| imm as Word))
+}
+
+fn integer_ops() -> Vec<Instruction> {
+    use IntegerOp::*;
+
+    let binary_ops: Vec<integer::BinaryOp> = VREGS
+        .into_iter()
+        .flat_map(|src| VREGS.into_iter().map(move |dst| (src, dst)))
+        .flat_map(|(src, dst)| {
+            field(15)
+                .into_iter()
+                .map(move |imm| integer::BinaryOp::Immediate(src, imm, dst))
+                .chain(
+                    VREGS
+                        .into_iter()
+                        .map(move |opt| integer::BinaryOp::Registers(src, opt, dst)),
+                )
+        })
+        .collect();
+
+    let comp_ops = VREGS.into_iter().flat_map(|left| {
+        [false, true].into_iter().flat_map(move |signed| {
+            VREGS
+                .into_iter()
+                .map(move |right| integer::CompOp::Registers(left, right, signed))
+                // Immediates are sign-extended from 15 bits
+                .chain(
+                    comparison_immediates()
+                        .map(move |imm| integer::CompOp::Immediate(left, imm, signed)),
+                )
+        })
+    });
+
+    let test_ops = VREGS.into_iter().flat_map(|left| {
+        VREGS
+            .into_iter()
+            .map(move |right| TestOp::Registers(left, right))
+            .chain(comparison_immediates().map(move |imm| TestOp::Immediate(left, imm)))
+    });
+
+    let unary_ops = VREGS
+        .into_iter()
+        .flat_map(|src| VREGS.into_iter().map(move |dst| integer::UnaryOp(src, dst)));
+
+    let sign_extend_ops = VREGS
+        .into_iter()
+        .flat_map(|reg| (0..3).map(move |width| SignExtendOp(width, reg)));
+
+    let constructors: [fn(integer::BinaryOp) -> IntegerOp; 14] = [
+        Add, Sub, Mul, Dvu, Dvs, Mod, And, Ior, Xor, Bsl, Bsr, Asr, Rol, Ror,
+    ];
+
+    constructors
+        .into_iter()
+        .flat_map(|op| binary_ops.iter().copied().map(op))
+        .chain(comp_ops.map(Cmp))
+        .chain(test_ops.map(Tst))
+        .chain(unary_ops.map(Not))
+        .chain(sign_extend_ops.map(Sxt))
+        .map(Instruction::Integer)
+        .collect()
+}
+
+fn floating_point_ops() -> Vec<Instruction> {
+    use FloatingPointOp::*;
+
+    let pairs = || {
+        VREGS
+            .into_iter()
+            .flat_map(|a| VREGS.into_iter().map(move |b| (a, b)))
+    };
+
+    let binary_ops: Vec<floating_point::BinaryOp> = pairs()
+        .flat_map(|(left, right)| {
+            VREGS
+                .into_iter()
+                .map(move |destination| floating_point::BinaryOp {
+                    left,
+                    right,
+                    destination,
+                })
+        })
+        .collect();
+
+    let constructors: [fn(floating_point::BinaryOp) -> FloatingPointOp; 5] =
+        [Fadd, Fsub, Fmul, Fdiv, Fmod];
+
+    constructors
+        .into_iter()
+        .flat_map(|op| binary_ops.iter().copied().map(op))
+        .chain(pairs().flat_map(|(source, destination)| {
+            [
+                Fneg(floating_point::UnaryOp {
+                    source,
+                    destination,
+                }),
+                Frec(floating_point::UnaryOp {
+                    source,
+                    destination,
+                }),
+                Itof(ConversionOp {
+                    source,
+                    destination,
+                }),
+                Ftoi(ConversionOp {
+                    source,
+                    destination,
+                }),
+                Fcmp(floating_point::CompOp {
+                    left: source,
+                    right: destination,
+                }),
+            ]
+        }))
+        .chain(VREGS.into_iter().map(|reg| Fchk(CheckOp(reg))))
+        .map(Instruction::FloatingPoint)
+        .collect()
+}
+
+fn read_ops() -> Vec<ReadOp> {
+    VREGS
+        .into_iter()
+        .flat_map(|destination| {
+            let zero_page = field(16).into_iter().map(move |address| ReadOp::ZeroPage {
+                address: address as Short,
+                destination,
+            });
+            let stack = field(12)
+                .into_iter()
+                .map(move |offset| ReadOp::StackOffset {
+                    offset: offset as Short,
+                    destination,
+                });
+            let indirect = [(false, false), (true, false), (false, true), (true, true)]
+                .into_iter()
+                .flat_map(move |(volatile, non_temporal)| {
+                    VREGS.into_iter().flat_map(move |address| {
+                        [ReadOp::Indirect {
+                            volatile,
+                            non_temporal,
+                            address,
+                            destination,
+                        }]
+                        .into_iter()
+                        .chain(
+                            field(12)
+                                .into_iter()
+                                .map(move |offset| ReadOp::OffsetIndirect {
+                                    volatile,
+                                    non_temporal,
+                                    address,
+                                    offset: offset as Short,
+                                    destination,
+                                }),
+                        )
+                        .chain(VREGS.into_iter().map(move |index| ReadOp::IndexedIndirect {
+                            volatile,
+                            non_temporal,
+                            address,
+                            index,
+                            destination,
+                        }))
+                    })
+                });
+
+            zero_page.chain(stack).chain(indirect)
+        })
+        .collect()
+}
+
+fn write_ops() -> Vec<WriteOp> {
+    VREGS
+        .into_iter()
+        .flat_map(|source| {
+            let zero_page = field(16).into_iter().map(move |address| WriteOp::ZeroPage {
+                address: address as Short,
+                source,
+            });
+            let stack = field(12)
+                .into_iter()
+                .map(move |offset| WriteOp::StackOffset {
+                    offset: offset as Short,
+                    source,
+                });
+            let indirect =
+                [(false, false), (true, false), (false, true), (true, true)]
+                    .into_iter()
+                    .flat_map(move |(volatile, non_temporal)| {
+                        VREGS.into_iter().flat_map(move |address| {
+                            [WriteOp::Indirect {
+                                volatile,
+                                non_temporal,
+                                address,
+                                source,
+                            }]
+                            .into_iter()
+                            .chain(field(12).into_iter().map(move |offset| {
+                                WriteOp::OffsetIndirect {
+                                    volatile,
+                                    non_temporal,
+                                    address,
+                                    offset: offset as Short,
+                                    source,
+                                }
+                            }))
+                            .chain(VREGS.into_iter().map(
+                                move |index| WriteOp::IndexedIndirect {
+                                    volatile,
+                                    non_temporal,
+                                    address,
+                                    index,
+                                    source,
+                                },
+                            ))
+                        })
+                    });
+
+            zero_page.chain(stack).chain(indirect)
+        })
+        .collect()
+}
+
+fn register_ops() -> Vec<Instruction> {
+    use RegisterOp::*;
+
+    // Transfers, pushes, and pops accept any register, not only the variable registers
+    let all_registers = [V[0x0], V[0xF], SP, (COUNT - 1) as Register];
+
+    let imm_ops = VREGS.into_iter().flat_map(|destination| {
+        field(16)
+            .into_iter()
+            .flat_map(move |immediate| {
+                [false, true].into_iter().flat_map(move |zero| {
+                    (0..4).map(move |shift| ImmOp::Immediate {
+                        zero,
+                        shift,
+                        immediate: immediate as Short,
+                        destination,
+                    })
+                })
+            })
+            .chain(
+                field(16)
+                    .into_iter()
+                    .map(move |address| ImmOp::ZeroPageTranslate {
+                        address: address as Short,
+                        destination,
+                    }),
+            )
+    });
+
+    let reg_ops = all_registers.into_iter().flat_map(move |source| {
+        all_registers.into_iter().map(move |destination| RegOp {
+            source,
+            destination,
+        })
+    });
+
+    let loads: [fn(ReadOp) -> RegisterOp; 3] = [Lbr, Lsr, Llr];
+    let stores: [fn(WriteOp) -> RegisterOp; 3] = [Sbr, Ssr, Slr];
+
+    loads
+        .into_iter()
+        .flat_map(|op| read_ops().into_iter().map(op))
+        .chain(
+            stores
+                .into_iter()
+                .flat_map(|op| write_ops().into_iter().map(op)),
+        )
+        .chain(reg_ops.map(Tfr))
+        .chain(all_registers.into_iter().map(Push))
+        .chain(all_registers.into_iter().map(Pop))
+        .chain(VREGS.into_iter().map(Rdcyc))
+        .chain(imm_ops.map(Ldr))
+        .map(Instruction::Register)
+        .collect()
+}
+
+#[test]
+fn control_round_trip() {
+    assert_round_trip(control_ops());
+}
+
+#[test]
+fn integer_round_trip() {
+    assert_round_trip(integer_ops());
+}
+
+#[test]
+fn floating_point_round_trip() {
+    assert_round_trip(floating_point_ops());
+}
+
+#[test]
+fn register_round_trip() {
+    assert_round_trip(register_ops());
+}
+
+#[test]
+fn operands() {
+    use crate::registers::RegisterFlags;
+
+    let add = Instruction::Integer(IntegerOp::Add(integer::BinaryOp::Immediate(V[1], 5, V[2])));
+    assert_eq!(
+        add.operands(),
+        Operands {
+            sources: [V[1]].into(),
+            destinations: [V[2]].into(),
+            immediate: Some(5),
+            addressing: Addressing::Immediate,
+        }
+    );
+
+    let jump = Instruction::Control(ControlOp::Jne(Jump::Relative(-8)));
+    assert_eq!(
+        jump.operands(),
+        Operands {
+            sources: RegisterFlags::default(),
+            destinations: RegisterFlags::default(),
+            immediate: Some(-8),
+            addressing: Addressing::Relative,
+        }
+    );
+
+    let store = Instruction::Register(RegisterOp::Slr(WriteOp::IndexedIndirect {
+        volatile: true,
+        non_temporal: false,
+        address: V[3],
+        index: V[4],
+        source: V[5],
+    }));
+    assert_eq!(
+        store.operands(),
+        Operands {
+            sources: [V[3], V[4], V[5]].into(),
+            destinations: RegisterFlags::default(),
+            immediate: None,
+            addressing: Addressing::IndexedIndirect { volatile: true },
+        }
+    );
+
+    assert_eq!(
+        Instruction::Control(ControlOp::Halt).operands(),
+        Operands::IMPLIED
+    );
+}
+
+#[test]
+fn jump_targets() {
+    assert_eq!(ControlOp::Jmp(Jump::Relative(-8)).target(0x40), Some(0x38));
+    assert_eq!(
+        ControlOp::Jsr(Jump::Relative(0x20)).target(0x40),
+        Some(0x60)
+    );
+    assert_eq!(
+        ControlOp::Jle(Jump::Relative(-4)).target(0),
+        Some(Word::MAX - 3)
+    );
+    assert_eq!(ControlOp::Jeq(Jump::Register(V[1])).target(0x40), None);
+    assert_eq!(ControlOp::Ret.target(0x40), None);
+}
+
+#[test]
+fn reference_fields_do_not_overlap() {
+    for instruction in reference() {
+        for form in &instruction.forms {
+            form.fields.iter().fold(0, |used: Word, field| {
+                assert_eq!(
+                    used & field.mask(),
+                    0,
+                    "the {} field of {} {} overlaps another field",
+                    field.name,
+                    instruction.mnemonic,
+                    form.syntax
+                );
+                if let Some(value) = field.value {
+                    assert!(
+                        value <= field.mask() >> field.low,
+                        "the {} field of {} {} cannot hold {value:#b}",
+                        field.name,
+                        instruction.mnemonic,
+                        form.syntax
+                    );
+                }
+
+                used | field.mask()
+            });
+        }
+    }
+}
+
+#[test]
+fn reference_describes_encodings() {
+    let reference = reference();
+
+    for instruction in control_ops()
+        .into_iter()
+        .chain(integer_ops())
+        .chain(floating_point_ops())
+        .chain(register_ops())
+    {
+        let word = encode(instruction);
+        // The reference uses the mnemonics the assembler accepts, while bit shifts are
+        // disassembled as LSL and LSR
+        let mnemonic = match instruction {
+            Instruction::Integer(IntegerOp::Bsl(_)) => "BSL".to_owned(),
+            Instruction::Integer(IntegerOp::Bsr(_)) => "BSR".to_owned(),
+            _ => instruction.mnemonic(),
+        };
+        let mnemonic = mnemonic.trim_end_matches(".NT");
+
+        let forms: Vec<_> = reference
+            .iter()
+            .flat_map(|entry| entry.forms.iter().map(move |form| (entry, form)))
+            .filter(|(_, form)| form.matches(word))
+            .collect();
+
+        match forms[..] {
+            [(entry, _)] => {
+                assert_eq!(
+                    entry.mnemonic, mnemonic,
+                    "{instruction} is described as another instruction"
+                );
+                assert_eq!(entry.category, instruction.category());
+            }
+            _ => panic!(
+                "{instruction} encoded as {word:#010X} matches {} forms of the reference",
+                forms.len()
+            ),
+        }
+    }
+}
+
+/// A xorshift generator, so arbitrary words can be sampled without a dependency
+fn random_words(mut state: Word) -> impl Iterator<Item = Word> {
+    std::iter::repeat_with(move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    })
+}
+
+#[test]
+fn arbitrary_words_never_panic() {
+    for word in random_words(0x2545_F491).take(1 << 20) {
+        let Ok(instruction) = decode::<Instruction>(word) else {
+            continue;
+        };
+
+        instruction.get_read_regs();
+        instruction.get_write_regs();
+        instruction.operands();
+        assert!(
+            !instruction.to_string().contains("unknown"),
+            "{word:#010X} decoded as {instruction}, which names a register that does not exist"
+        );
+
+        let reencoded = encode(instruction);
+        assert_eq!(
+            decode::<Instruction>(reencoded).ok(),
+            Some(instruction),
+            "{word:#010X} decoded as {instruction}, which encodes as {reencoded:#010X}"
+        );
+    }
+}
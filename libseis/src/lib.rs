@@ -7,6 +7,7 @@
 //! simulation works.
 #![warn(missing_docs)]
 
+pub mod image;
 pub mod instruction_set;
 pub mod pages;
 pub mod registers;
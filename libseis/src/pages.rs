@@ -3,6 +3,8 @@ use crate::types::Word;
 
 /// The size of a page
 pub const PAGE_SIZE: usize = 2usize.pow(16);
+/// The number of pages needed to cover every address
+pub const MAX_PAGES: usize = (Word::MAX as usize >> 16) + 1;
 /// The offset of the stack page
 pub const STACK_PAGE: Word = 0x0001_0000;
 /// The offset of the zero (short) page
@@ -4,7 +4,7 @@
 use serde::Serialize;
 use crate::types::{Register, Word};
 use std::{
-    fmt::{Display, Write},
+    fmt::{Debug, Display, Write},
     ops::{BitOr, BitOrAssign},
 };
 
@@ -60,6 +60,35 @@ pub fn get_id(name: &str) -> Option<Register> {
         .map(|(i, _)| i as Register)
 }
 
+/// ABI-style aliases for the variable registers
+///
+/// `RET` holds a subroutine's return value, and `ARG0` through `ARG7`
+/// hold its arguments.
+pub const ALIASES: [(&str, Register); 9] = [
+    ("RET", V[0x0]),
+    ("ARG0", V[0x1]),
+    ("ARG1", V[0x2]),
+    ("ARG2", V[0x3]),
+    ("ARG3", V[0x4]),
+    ("ARG4", V[0x5]),
+    ("ARG5", V[0x6]),
+    ("ARG6", V[0x7]),
+    ("ARG7", V[0x8]),
+];
+
+/// Transforms the register name or one of its [`ALIASES`] into its
+/// corresponding ID.
+pub fn get_id_with_aliases(name: &str) -> Option<Register> {
+    get_id(name).or_else(|| {
+        let target = name.to_uppercase();
+
+        ALIASES
+            .into_iter()
+            .find(|&(alias, _)| alias == target)
+            .map(|(_, reg)| reg)
+    })
+}
+
 /// An iterator over the flags of the register.
 ///
 /// Returns an integer ID representing the register.
@@ -100,9 +129,16 @@ impl Iterator for RegFlagIterator {
 impl ExactSizeIterator for RegFlagIterator {}
 
 /// A bitset representing the registers.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
 pub struct RegisterFlags(pub Word);
 
+/// Lists the name of every register in the set, through [`RegisterFlags::names`]
+impl Debug for RegisterFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.names())
+    }
+}
+
 impl From<Register> for RegisterFlags {
     fn from(value: Register) -> Self {
         Self(1 << value)
@@ -130,6 +166,19 @@ impl RegisterFlags {
         self.into_iter()
     }
 
+    /// Lists the name of every register in the set, as in `{V0, V3, SP, PC}`
+    ///
+    /// Unlike [`Display`], which only shows the variable registers, SP, BP and LP, this
+    /// includes PC and the flags.
+    pub fn names(self) -> String {
+        let names: Vec<_> = self
+            .registers()
+            .map(|reg| get_name(reg).unwrap_or("<?>"))
+            .collect();
+
+        format!("{{{}}}", names.join(", "))
+    }
+
     /// Transforms the bitset into a vector of integer IDs
     pub fn to_vec(self) -> Vec<Register> {
         self.into_iter().collect()
@@ -139,6 +188,12 @@ impl RegisterFlags {
     pub fn count(&self) -> usize {
         self.into_iter().count()
     }
+
+    /// Creates a bitset of the first `count` variable registers, or all of them if
+    /// there are fewer than `count`
+    pub fn variable(count: usize) -> Self {
+        V.into_iter().take(count).collect()
+    }
 }
 
 impl FromIterator<Register> for RegisterFlags {
@@ -237,3 +292,38 @@ impl<const COUNT: usize> From<[Register; COUNT]> for RegisterFlags {
         regs.into_iter().collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_flags_list_their_names() {
+        let flags: RegisterFlags = [V[0], V[3], SP, PC, INF].into();
+
+        assert_eq!(
+            flags.registers().collect::<Vec<_>>(),
+            [V[0], V[3], SP, PC, INF]
+        );
+        assert_eq!(flags.registers().len(), 5);
+        assert_eq!(flags.names(), "{V0, V3, SP, PC, INF}");
+        assert_eq!(RegisterFlags::default().names(), "{}");
+    }
+
+    #[test]
+    fn register_flags_debug_as_their_names() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Locked {
+            regs: RegisterFlags,
+        }
+
+        let flags: RegisterFlags = [V[1], BP, ZF].into();
+
+        assert_eq!(format!("{flags:?}"), "{V1, BP, ZF}");
+        assert_eq!(
+            format!("{:?}", Locked { regs: flags }),
+            "Locked { regs: {V1, BP, ZF} }"
+        );
+    }
+}
@@ -1,6 +1,18 @@
 use clap::{Parser, ValueHint::FilePath};
 use std::path::PathBuf;
 
+/// Parses a size written in decimal, or in hexadecimal with a `0x` prefix
+fn parse_size(input: &str) -> Result<u64, String> {
+    match input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => input.parse(),
+    }
+    .map_err(|e| e.to_string())
+}
+
 #[derive(Debug, Parser)]
 #[clap(version, about, long_about = None)]
 pub struct Command {
@@ -12,4 +24,21 @@ pub struct Command {
     /// a.out by default
     #[clap(short='o', value_hint=FilePath)]
     pub output: Option<PathBuf>,
+    /// Write a segmented image instead of a flat binary.
+    ///
+    /// Segmented images only hold the code and data of the program, and mark
+    /// the code as read-only.
+    #[clap(short = 's', long)]
+    pub segmented: bool,
+    /// Assemble and link the files without writing any output.
+    ///
+    /// Reports the size of the code and data, the number of pages they touch, and
+    /// the address of every label.
+    #[clap(short='c', long, conflicts_with_all=["output", "segmented", "pad_to"])]
+    pub check: bool,
+    /// Zero-extend the output to exactly this many bytes.
+    ///
+    /// Fails if the output is already larger. Useful for fixed-size ROM images.
+    #[clap(long, value_name="SIZE", value_parser=parse_size)]
+    pub pad_to: Option<u64>,
 }
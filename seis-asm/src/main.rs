@@ -1,9 +1,58 @@
 mod cli;
 
 use clap::Parser;
-use libasm::linker::link_symbols;
+use libasm::linker::{link_symbols, PageSet};
 use libasm::parse::{tokenize_file, Error, Lines};
-use std::fs::File;
+use std::fs::write;
+use std::io::{Cursor, Error as IoError, ErrorKind};
+
+/// Prints the sizes and symbols of a linked program
+fn report(linked: &PageSet) {
+    println!("Code: {} bytes", linked.code_size());
+    println!("Data: {} bytes", linked.data_size());
+    println!("Pages: {}", linked.page_count());
+
+    let mut symbols: Vec<_> = linked.symbols().iter().collect();
+    symbols.sort_unstable_by_key(|&(name, &address)| (address, name));
+
+    println!("Symbols:");
+    for (name, address) in symbols {
+        println!("  {address:#010X} {name}");
+    }
+}
+
+/// Writes the linked program, zero-extended to exactly `pad_to` bytes if given
+fn output_data(linked: PageSet, segmented: bool, pad_to: Option<u64>) -> std::io::Result<Vec<u8>> {
+    let mut data = Cursor::new(vec![]);
+
+    if segmented {
+        linked.write_segmented(&mut data)
+    } else {
+        linked.write(&mut data)
+    }?;
+
+    let mut data = data.into_inner();
+    if let Some(size) = pad_to {
+        pad(&mut data, size)?;
+    }
+
+    Ok(data)
+}
+
+/// Zero-extends the output to exactly `size` bytes
+fn pad(data: &mut Vec<u8>, size: u64) -> std::io::Result<()> {
+    let length = data.len() as u64;
+
+    if length > size {
+        return Err(IoError::new(
+            ErrorKind::InvalidInput,
+            format!("The output is {length} bytes, which does not fit in {size} bytes"),
+        ));
+    }
+
+    data.resize(size as usize, 0);
+    Ok(())
+}
 
 fn main() {
     let cli = cli::Command::parse();
@@ -21,7 +70,40 @@ fn main() {
 
     let linked = link_symbols(lines.into()).expect("Failed to link code");
 
-    linked
-        .write(File::create(output).expect("Could not open output file"))
-        .expect("Failed to write to file");
+    if cli.check {
+        report(&linked);
+        return;
+    }
+
+    // The output is checked before anything is written, so a failure leaves no file
+    let data = match output_data(linked, cli.segmented, cli.pad_to) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    write(output, data).expect("Failed to write to file");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pad_to_size() {
+        let lines = libasm::parse::tokenize("LDR 10 => V3\nHALT\n", "pad.asm".as_ref()).unwrap();
+        let data = output_data(link_symbols(lines).unwrap(), false, Some(64)).unwrap();
+
+        assert_eq!(data.len(), 64);
+        assert!(data[..8].iter().any(|&b| b != 0), "The program is kept");
+        assert!(data[8..].iter().all(|&b| b == 0), "The rest is zeroed");
+
+        let lines = libasm::parse::tokenize("LDR 10 => V3\nHALT\n", "pad.asm".as_ref()).unwrap();
+        assert!(
+            output_data(link_symbols(lines).unwrap(), false, Some(4)).is_err(),
+            "The output does not fit"
+        );
+    }
 }
@@ -4,47 +4,109 @@ use crate::{
     results::RunResult,
     run_benchmark,
 };
+use libseis::types::Word;
 use rayon::prelude::*;
 use std::{
-    sync::mpsc::{channel, Receiver},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver},
+        Arc,
+    },
     thread::{spawn, JoinHandle},
 };
 
 pub enum State {
     Started(usize),
     Finished(usize),
+    /// The run was stopped by an interrupt before it finished
+    Abandoned(usize),
 }
 use State::*;
 
 pub struct BenchmarkHelper(JoinHandle<Result<Vec<RunResult>, Error>>, Receiver<State>);
 
+/// A set of runs which share a thread pool
+type Batch<B, C> = (usize, Vec<(usize, B, C)>);
+
+/// Splits the runs into batches, each with the number of threads it is run with.
+///
+/// The runs of benchmarks which do not override the thread count share the first batch,
+/// which uses `n` threads. Each benchmark which does override it gets a batch of its own,
+/// in the order it first appears, so its runs never overlap with those of another.
+fn schedule<B: AsRef<Benchmark>, C>(runs: Vec<(usize, B, C)>, n: usize) -> Vec<Batch<B, C>> {
+    let mut shared = vec![];
+    let mut isolated: Vec<(String, Batch<B, C>)> = vec![];
+
+    for run in runs {
+        let bench = run.1.as_ref();
+
+        match bench.thread_override() {
+            None => shared.push(run),
+            Some(threads) => match isolated.iter_mut().find(|(name, _)| *name == bench.name) {
+                Some((_, (_, batch))) => batch.push(run),
+                None => isolated.push((bench.name.clone(), (threads, vec![run]))),
+            },
+        }
+    }
+
+    std::iter::once((n, shared))
+        .chain(isolated.into_iter().map(|(_, batch)| batch))
+        .filter(|(_, runs)| !runs.is_empty())
+        .collect()
+}
+
 impl BenchmarkHelper {
     pub fn new<
-        T: Send
-            + Sync
-            + IntoParallelIterator<
-                Item = (usize, impl AsRef<Benchmark>, impl AsRef<SimulationConfig>),
-            > + 'static,
+        B: AsRef<Benchmark> + Send + 'static,
+        C: AsRef<SimulationConfig> + Send + 'static,
     >(
-        configurations: T,
+        configurations: Vec<(usize, B, C)>,
         n: usize,
+        warm: Arc<[Word]>,
+        repeat: usize,
+        interrupted: Arc<AtomicBool>,
     ) -> std::thread::Result<Self> {
         let (tx, rx) = channel();
 
         let thread = spawn(move || {
-            rayon::ThreadPoolBuilder::new()
-                .num_threads(n)
-                .build_global()?;
-
-            configurations
-                .into_par_iter()
-                .map(|(i, bench, conf)| {
-                    tx.send(Started(i))?;
-                    let run = run_benchmark(bench.as_ref(), conf.as_ref())?;
-                    tx.send(Finished(i))?;
-                    Ok(run)
-                })
-                .collect()
+            let mut results = vec![];
+
+            for (threads, batch) in schedule(configurations, n) {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()?;
+
+                // Runs which have not started by the time of an interrupt are skipped
+                let runs = pool.install(|| {
+                    batch
+                        .into_par_iter()
+                        .map(|(i, bench, conf)| {
+                            if interrupted.load(Ordering::Relaxed) {
+                                return Ok(None);
+                            }
+
+                            tx.send(Started(i))?;
+                            let run = run_benchmark(
+                                bench.as_ref(),
+                                conf.as_ref(),
+                                &warm,
+                                repeat,
+                                &interrupted,
+                            )?;
+                            tx.send(if run.is_some() {
+                                Finished(i)
+                            } else {
+                                Abandoned(i)
+                            })?;
+                            Ok(run)
+                        })
+                        .collect::<Result<Vec<_>, Error>>()
+                })?;
+
+                results.extend(runs.into_iter().flatten());
+            }
+
+            Ok(results)
         });
 
         Ok(Self(thread, rx))
@@ -58,3 +120,93 @@ impl BenchmarkHelper {
         self.0.join().unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bench(name: &str, threads: Option<usize>, serial: bool) -> Arc<Benchmark> {
+        Arc::new(Benchmark {
+            name: name.into(),
+            threads,
+            serial,
+            ..Default::default()
+        })
+    }
+
+    /// The indices of the runs in each batch, with the threads each batch uses
+    fn batches(runs: &[Arc<Benchmark>], n: usize) -> Vec<(usize, Vec<usize>)> {
+        let runs = runs
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, b)| (i, b, ()))
+            .collect();
+
+        schedule(runs, n)
+            .into_iter()
+            .map(|(threads, batch)| (threads, batch.into_iter().map(|(i, ..)| i).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn runs_share_the_default_threads() {
+        let sort = bench("sort", None, false);
+        let matrix = bench("matrix", None, false);
+
+        assert_eq!(
+            batches(&[sort.clone(), sort, matrix.clone(), matrix], 4),
+            [(4, vec![0, 1, 2, 3])]
+        );
+    }
+
+    #[test]
+    fn overrides_are_run_on_their_own() {
+        let sort = bench("sort", None, false);
+        let matrix = bench("matrix", Some(2), false);
+        let fpmatrix = bench("fpmatrix", Some(8), true);
+
+        assert_eq!(
+            batches(
+                &[
+                    fpmatrix.clone(),
+                    sort.clone(),
+                    matrix.clone(),
+                    fpmatrix,
+                    sort,
+                    matrix
+                ],
+                4
+            ),
+            [(4, vec![1, 4]), (1, vec![0, 3]), (2, vec![2, 5])]
+        );
+    }
+
+    #[test]
+    fn serial_runs_never_overlap() {
+        let sort = bench("sort", None, true);
+        let runs = (0..8).map(|i| (i, sort.clone(), ())).collect();
+
+        let batches = schedule(runs, 4);
+        assert_eq!(batches.len(), 1);
+
+        let (threads, batch) = batches.into_iter().next().unwrap();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+
+        let running = std::sync::atomic::AtomicUsize::new(0);
+        let most = std::sync::atomic::AtomicUsize::new(0);
+        pool.install(|| {
+            batch.into_par_iter().for_each(|_| {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(5));
+                running.fetch_sub(1, Ordering::SeqCst);
+            })
+        });
+
+        assert_eq!(most.load(Ordering::SeqCst), 1);
+    }
+}
@@ -17,9 +17,24 @@ pub struct Cli {
 
     /// How many threads to spawn at most.
     ///
-    /// Not specifying this option will spawn the default number of threads (4)
+    /// Not specifying this option will spawn the default number of threads (4). Benchmarks
+    /// which set `threads` or `serial` are run afterwards with their own number of threads.
     #[arg(short = 'n', long = "threads")]
     pub threads: Option<usize>,
+
+    /// How many times to run each benchmark with each configuration.
+    ///
+    /// The reported runtime is the median of the runs. Not specifying this option will
+    /// run each pair once
+    #[arg(short, long)]
+    pub repeat: Option<usize>,
+
+    /// A file listing addresses to load into cache before each benchmark starts.
+    ///
+    /// Each line holds one address, in hexadecimal (prefixed with "0x") or decimal.
+    /// Blank lines and lines starting with '#' are ignored.
+    #[arg(short, long, value_hint = FilePath)]
+    pub warm: Option<PathBuf>,
 }
 
 impl Cli {
@@ -1,13 +1,17 @@
 //! Datastructures representing a configuration for the benchmarker to run.
+use crate::error::Error;
 use libmem::{
     cache::{Associative, Cache, MultiAssociative, NullCache},
     memory::Memory,
     module::SingleLevel,
 };
 use libpipe::{Pipeline, Pipelined, Unpipelined};
-use serde::Deserialize;
-use std::path::{Path, PathBuf};
-use crate::error::Error;
+use libseis::pages::MAX_PAGES;
+use serde::{de, Deserialize, Deserializer};
+use std::{
+    mem::take,
+    path::{Path, PathBuf},
+};
 
 /// A singular configuration for the benchmark.
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -23,6 +27,11 @@ pub struct SimulationConfig {
     pub volatile_penalty: usize,
     /// Whether to enable the pipeline
     pub pipeline: bool,
+    /// The number of pages of memory
+    ///
+    /// This field is optional in the file
+    #[serde(default = "default_pages", deserialize_with = "deserialize_pages")]
+    pub pages: usize,
     /// The configuration of a cache
     ///
     /// This field is optional in the file
@@ -38,7 +47,7 @@ impl SimulationConfig {
         let mem = Box::new(SingleLevel::new(
             data_cache,
             instruction_cache,
-            Memory::new(super::PAGES),
+            Memory::new(self.pages),
             self.miss_penalty,
             self.volatile_penalty,
             self.writethrough,
@@ -52,6 +61,26 @@ impl SimulationConfig {
     }
 }
 
+/// The number of pages of memory used when a configuration does not set one
+fn default_pages() -> usize {
+    super::PAGES
+}
+
+/// Reads a number of pages, which must be at least 1 and at most [`MAX_PAGES`]
+fn deserialize_pages<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let pages = usize::deserialize(deserializer)?;
+
+    if pages < 1 {
+        Err(de::Error::custom("Key \"pages\" must be at least 1"))
+    } else if pages > MAX_PAGES {
+        Err(de::Error::custom(format!(
+            "Key \"pages\" must be at most {MAX_PAGES}, which covers every address"
+        )))
+    } else {
+        Ok(pages)
+    }
+}
+
 /// The configuration of a singular cache module
 #[derive(Debug, Clone, Default, Deserialize)]
 pub struct CacheModuleConfig {
@@ -116,6 +145,33 @@ pub struct Benchmark {
     pub sources: Vec<String>,
     /// What to call the resulting binary file
     pub binary: String,
+    /// How many runs of this benchmark to have going at once, in place of `--threads`
+    ///
+    /// A benchmark which sets this is run on its own, after the benchmarks which do not,
+    /// so no other run competes with it for the processor. Clocks are deterministic no
+    /// matter how runs are scheduled, so this only changes how faithful `rtc` is.
+    ///
+    /// This field is optional in the file
+    #[serde(default)]
+    pub threads: Option<usize>,
+    /// Whether to run this benchmark one run at a time, with nothing else running
+    ///
+    /// This is the same as setting `threads` to 1, and takes precedence over it.
+    ///
+    /// This field is optional in the file
+    #[serde(default)]
+    pub serial: bool,
+}
+
+impl Benchmark {
+    /// The number of threads to run this benchmark with, if it overrides `--threads`
+    pub fn thread_override(&self) -> Option<usize> {
+        if self.serial {
+            Some(1)
+        } else {
+            self.threads
+        }
+    }
 }
 
 /// The entire configuration for the benchmarking tool
@@ -129,9 +185,134 @@ pub struct BenchmarkConfig {
 
 /// Read a configuration from a file.
 ///
-/// Reads the entire contents of the `file` to memory and deserializes it.
+/// Reads the entire contents of the `file` to memory and deserializes it. The file may
+/// inherit from a base file, and so may each of its configurations, relative to the
+/// directory of `file`.
 pub fn read_configuration(file: &Path) -> Result<BenchmarkConfig, Error> {
-    let content = std::fs::read_to_string(file)?;
+    let mut table = libconfig::read_toml(file).map_err(|e| e.to_string())?;
 
-    Ok(toml::from_str(&content)?)
+    if let Some(toml::Value::Array(configurations)) = table.get_mut("configuration") {
+        for configuration in configurations {
+            if let toml::Value::Table(configuration) = configuration {
+                *configuration = libconfig::resolve_nested_base(take(configuration), file)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    Ok(toml::Value::Table(table).try_into()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configurations_inherit_bases() {
+        let dir = std::env::temp_dir().join(format!("seis-bench-bases-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let files = [
+            (
+                "common.toml",
+                r#"
+writethrough = true
+miss_penalty = 100
+volatile_penalty = 20
+pipeline = true
+"#,
+            ),
+            (
+                "suite.toml",
+                r#"
+[[benchmark]]
+name = "sort"
+path = "sort"
+sources = []
+binary = "sort.bin"
+"#,
+            ),
+            (
+                "sweep.toml",
+                r#"
+base = "suite.toml"
+
+[[configuration]]
+base = "common.toml"
+name = "slow"
+
+[[configuration]]
+base = "common.toml"
+name = "fast"
+miss_penalty = 10
+"#,
+            ),
+        ];
+        for (name, content) in files {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+
+        let config = read_configuration(&dir.join("sweep.toml"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        let config = config.unwrap();
+
+        assert_eq!(config.benchmark.len(), 1);
+        assert_eq!(config.benchmark[0].name, "sort");
+
+        let [slow, fast] = config.configuration.as_slice() else {
+            panic!("Expected two configurations");
+        };
+        assert_eq!((slow.name.as_str(), slow.miss_penalty), ("slow", 100));
+        assert_eq!((fast.name.as_str(), fast.miss_penalty), ("fast", 10));
+        assert!(fast.writethrough && fast.pipeline);
+        assert_eq!(fast.volatile_penalty, 20);
+    }
+
+    #[test]
+    fn pages_are_bounded() {
+        let parse = |pages: &str| {
+            toml::from_str::<SimulationConfig>(&format!(
+                "name = \"a\"\nwritethrough = false\nmiss_penalty = 1\nvolatile_penalty = 1\npipeline = false\n{pages}"
+            ))
+        };
+
+        assert_eq!(parse("").unwrap().pages, super::super::PAGES);
+        assert_eq!(parse("pages = 64").unwrap().pages, 64);
+        assert!(parse("pages = 0")
+            .unwrap_err()
+            .to_string()
+            .contains("Key \"pages\" must be at least 1"));
+        assert!(parse("pages = 65537")
+            .unwrap_err()
+            .to_string()
+            .contains("Key \"pages\" must be at most 65536"));
+    }
+
+    #[test]
+    fn configuration_base_cycles_through_file() {
+        let dir = std::env::temp_dir().join(format!("seis-bench-cycle-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        std::fs::write(
+            dir.join("sweep.toml"),
+            "[[configuration]]\nbase = \"common.toml\"\nname = \"slow\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("common.toml"), "base = \"sweep.toml\"\n").unwrap();
+
+        let sweep = dir.join("sweep.toml").canonicalize().unwrap();
+        let common = dir.join("common.toml").canonicalize().unwrap();
+        let error = read_configuration(&sweep).map(|_| ());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            format!(
+                "Base configurations form a cycle: {} -> {} -> {}",
+                sweep.display(),
+                common.display(),
+                sweep.display()
+            )
+        );
+    }
 }
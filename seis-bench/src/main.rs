@@ -12,20 +12,27 @@ use crossterm::{
     cursor::{Hide, MoveToColumn, MoveToPreviousLine, Show},
     execute,
     style::{StyledContent, Stylize},
+    terminal::{Clear, ClearType},
 };
 use libmem::memory::Memory;
 use libpipe::ClockResult;
-use libseis::{pages::PAGE_SIZE, types::Word};
-use results::RunResult;
+use libseis::{image, types::Word};
+use results::{RunResult, Summary};
+use signal_hook::{consts::SIGINT, iterator::Signals};
 use std::{
     collections::{HashMap, HashSet},
     fs::File,
     io::{stdout, Write},
-    sync::Arc,
-    time::Instant,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
-/// The maximum number of pages allocated for each instance of the simulation.
+/// The maximum number of pages allocated for each instance of the simulation, unless
+/// its configuration sets another.
 ///
 /// This may not ever be reached by a given benchmark.
 const PAGES: usize = 16;
@@ -50,31 +57,58 @@ pub fn build_binary(benchmark: &Benchmark) -> Result<(), Error> {
 }
 
 /// Prepares a simulation. Loads benchmark to memory.
-pub fn prepare_sim(mem: &mut Memory, benchmark: &Benchmark) -> Result<(), Error> {
+///
+/// Returns the address execution begins at.
+pub fn prepare_sim(mem: &mut Memory, benchmark: &Benchmark) -> Result<Word, Error> {
     use std::fs::read;
     let path = benchmark.path.join(&benchmark.binary);
     let data = read(&path)?;
 
-    if data.len() >= PAGES << 16 {
-        return Err(format!("File too long: {}", path.display()).into());
-    }
+    mem.load_image(&data)
+        .map_err(|e| format!("Could not load {}: {e}", path.display()))?;
 
-    for (page, data) in data.chunks(PAGE_SIZE).enumerate() {
-        mem.set_page((page << 16) as Word, data)
-    }
+    Ok(image::entry(&data).map_err(|e| format!("Could not load {}: {e}", path.display()))?)
+}
 
-    Ok(())
+/// Reads the addresses to warm the cache with from a file.
+pub fn read_warm_addresses(path: &Path) -> Result<Vec<Word>, Error> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            let address = match line.strip_prefix("0x") {
+                Some(hex) => Word::from_str_radix(hex, 16),
+                None => line.parse(),
+            };
+
+            address.map_err(|e| {
+                format!(
+                    "{}:{}: invalid address \"{line}\" ({e})",
+                    path.display(),
+                    i + 1
+                )
+                .into()
+            })
+        })
+        .collect()
 }
 
-/// Runs a benchmark with a given configuration.
+/// Runs a benchmark once with a given configuration.
 ///
-/// The `benchmark` passed will be run with a provided `config`.
-fn run_benchmark<'a>(
-    benchmark: &'a Benchmark,
-    config: &'a SimulationConfig,
-) -> Result<RunResult, Error> {
+/// Returns the clocks elapsed, the number of retired instructions, and the time the
+/// run took, or [`None`] if the run was abandoned after an interrupt.
+fn simulate(
+    benchmark: &Benchmark,
+    config: &SimulationConfig,
+    warm: &[Word],
+    interrupted: &AtomicBool,
+) -> Result<Option<(usize, usize, Duration)>, Error> {
     let mut pipeline = config.build_config();
-    prepare_sim(pipeline.memory_module_mut().memory_mut(), benchmark)?;
+    pipeline.registers_mut().pc =
+        prepare_sim(pipeline.memory_module_mut().memory_mut(), benchmark)?;
+    pipeline.memory_module_mut().warm(warm);
 
     let mut clocks = 0;
     let mut clocks_required = 1;
@@ -82,6 +116,10 @@ fn run_benchmark<'a>(
 
     let start = Instant::now();
     while !finished {
+        if interrupted.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+
         clocks += clocks_required;
         match pipeline.clock(clocks_required) {
             ClockResult::Stall(clocks) => {
@@ -97,12 +135,72 @@ fn run_benchmark<'a>(
     }
     let end = Instant::now();
 
-    Ok(RunResult {
+    Ok(Some((clocks, pipeline.retired_instructions(), end - start)))
+}
+
+/// Runs a benchmark with a given configuration.
+///
+/// The `benchmark` passed will be run `repeat` times with a provided `config`, after
+/// loading the lines holding the `warm` addresses into cache. Every run must take the
+/// same number of clocks, as the simulation is deterministic.
+///
+/// Returns [`None`] if the runs were abandoned after an interrupt.
+fn run_benchmark<'a>(
+    benchmark: &'a Benchmark,
+    config: &'a SimulationConfig,
+    warm: &[Word],
+    repeat: usize,
+    interrupted: &AtomicBool,
+) -> Result<Option<RunResult>, Error> {
+    repeat_runs(benchmark, config, repeat, || {
+        simulate(benchmark, config, warm, interrupted)
+    })
+}
+
+/// Collects `repeat` runs made by `simulate` into a result, checking that every run
+/// took the same number of clocks.
+fn repeat_runs(
+    benchmark: &Benchmark,
+    config: &SimulationConfig,
+    repeat: usize,
+    mut simulate: impl FnMut() -> Result<Option<(usize, usize, Duration)>, Error>,
+) -> Result<Option<RunResult>, Error> {
+    let Some((clocks, retired, first)) = simulate()? else {
+        return Ok(None);
+    };
+    let mut times = vec![first];
+
+    for _ in 1..repeat {
+        let Some((repeat_clocks, _, time)) = simulate()? else {
+            return Ok(None);
+        };
+
+        if repeat_clocks != clocks {
+            return Err(format!(
+                "Benchmark {} with configuration {} is nondeterministic (ran for {clocks} clocks, then {repeat_clocks})",
+                benchmark.name, config.name
+            )
+            .into());
+        }
+
+        times.push(time);
+    }
+
+    let ipc = if clocks == 0 {
+        0.0
+    } else {
+        retired as f64 / clocks as f64
+    };
+
+    Ok(Some(RunResult {
         bench_name: benchmark.name.clone(),
         config_name: config.name.clone(),
         clocks,
-        rtc: end - start,
-    })
+        rtc: results::median(&mut times),
+        ipc,
+        rtc_min: times[0],
+        rtc_max: times[times.len() - 1],
+    }))
 }
 
 /// How wide to make the status field of a line
@@ -120,9 +218,53 @@ fn finished_status(text: &str) -> StyledContent<String> {
     format!("{text:>STATUS_WIDTH$}").green().bold()
 }
 
+/// Formats the text for the "interrupted" status. Sets color to yellow and makes
+/// the text boldface. It also right-aligns the text within a `STATUS_WIDTH` field.
+fn interrupted_status(text: &str) -> StyledContent<String> {
+    format!("{text:>STATUS_WIDTH$}").yellow().bold()
+}
+
+/// Shows the cursor again when dropped, so it is restored however `main` returns
+struct CursorGuard;
+
+impl CursorGuard {
+    /// Hides the cursor until the guard is dropped
+    fn hide() -> std::io::Result<Self> {
+        execute!(stdout(), Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for CursorGuard {
+    fn drop(&mut self) {
+        let _ = execute!(stdout(), Show);
+    }
+}
+
+/// Estimates the time left to finish every run by extrapolating from the mean time
+/// taken by each completed run
+fn estimate_remaining(elapsed: Duration, completed: usize, total: usize) -> String {
+    if completed == 0 {
+        return "unknown".to_owned();
+    }
+
+    let seconds = elapsed
+        .mul_f64((total - completed) as f64 / completed as f64)
+        .as_secs();
+
+    if seconds >= 60 {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{seconds}s")
+    }
+}
+
 fn run<'a: 'static>(
     n: usize,
     configurations: Vec<(usize, Arc<Benchmark>, Arc<SimulationConfig>)>,
+    warm: Arc<[Word]>,
+    repeat: usize,
+    interrupted: Arc<AtomicBool>,
 ) -> Result<Vec<RunResult>, Error> {
     let (bench_width, conf_width) = configurations
         .iter()
@@ -139,47 +281,67 @@ fn run<'a: 'static>(
     //
     // There is still an issue where benchmarks will go out of bounds of the console,
     // but that is not of significant concern at the moment.
-    let helper = BenchmarkHelper::new(configurations, n)?;
+    let total = configurations.len();
+    let helper = BenchmarkHelper::new(configurations, n, warm, repeat, interrupted)?;
     let mut running = HashSet::new();
+    let mut completed = 0;
+    let start = Instant::now();
+
+    // The number of lines at the bottom of the output which are redrawn on each update.
+    // The cursor starts on an empty line.
+    let mut redrawn_lines = 1;
 
     while let Some(state) = helper.next() {
+        // Clear the running and progress lines, leaving the cursor at the start of the first
+        if redrawn_lines > 1 {
+            execute!(stdout(), MoveToPreviousLine(redrawn_lines as u16 - 1))?;
+        }
+        execute!(stdout(), MoveToColumn(0), Clear(ClearType::FromCursorDown))?;
+
         match state {
             bench::State::Started(i) => {
-                execute!(stdout(), MoveToPreviousLine(running.len().max(1) as u16))?;
                 running.insert(i);
-
-                for i in running.iter() {
-                    let (bench, conf) = mappings.get(i).unwrap();
-                    print!(
-                        "\n{} {:>bench_width$} {:<conf_width$}",
-                        processing_status("Running"),
-                        bench.name,
-                        conf.name
-                    )
-                }
             }
             bench::State::Finished(i) => {
                 let (bench, conf) = mappings.get(&i).unwrap();
-                execute!(stdout(), MoveToPreviousLine(running.len() as u16))?;
-                print!(
-                    "\n{} {:>bench_width$} {:<conf_width$}",
+                println!(
+                    "{} {:>bench_width$} {:<conf_width$}",
                     finished_status("Finished"),
                     bench.name,
                     conf.name
                 );
                 running.remove(&i);
-
-                for i in running.iter() {
-                    let (bench, conf) = mappings.get(i).unwrap();
-                    print!(
-                        "\n{} {:>bench_width$} {:<conf_width$}",
-                        processing_status("Running"),
-                        bench.name,
-                        conf.name
-                    )
-                }
+                completed += 1;
+            }
+            bench::State::Abandoned(i) => {
+                let (bench, conf) = mappings.get(&i).unwrap();
+                println!(
+                    "{} {:>bench_width$} {:<conf_width$}",
+                    interrupted_status("Abandoned"),
+                    bench.name,
+                    conf.name
+                );
+                running.remove(&i);
             }
         }
+
+        for i in running.iter() {
+            let (bench, conf) = mappings.get(i).unwrap();
+            println!(
+                "{} {:>bench_width$} {:<conf_width$}",
+                processing_status("Running"),
+                bench.name,
+                conf.name
+            )
+        }
+
+        print!(
+            "{} {completed}/{total} (ETA {})",
+            processing_status("Progress"),
+            estimate_remaining(start.elapsed(), completed, total)
+        );
+        redrawn_lines = running.len() + 1;
+
         stdout().flush()?;
     }
 
@@ -189,6 +351,15 @@ fn run<'a: 'static>(
 fn main() -> Result<(), Error> {
     let cli = Cli::parse();
     let mut config = config::read_configuration(&cli.bench_conf)?;
+    let warm: Arc<[Word]> = match &cli.warm {
+        Some(path) => read_warm_addresses(path)?.into(),
+        None => Arc::new([]),
+    };
+    let repeat = cli.repeat.unwrap_or(1);
+
+    if repeat == 0 {
+        return Err("Each benchmark must be run at least once".to_owned().into());
+    }
 
     // Ensure there is at *least* one benchmark and one configuration.
     if config.benchmark.len() == 0 {
@@ -201,8 +372,34 @@ fn main() -> Result<(), Error> {
         return Ok(());
     }
 
+    if let Some(bench) = config.benchmark.iter().find(|b| b.threads == Some(0)) {
+        return Err(format!(
+            "Benchmark {} must be run with at least one thread",
+            bench.name
+        )
+        .into());
+    }
+
     // Hide the cursor
-    execute!(stdout(), Hide)?;
+    let _cursor = CursorGuard::hide()?;
+
+    // The handler is installed before the builds so an interrupt never leaves the cursor
+    // hidden. The first interrupt lets the runs finish early so the completed results are
+    // still written, and a second one exits immediately. Exiting skips destructors, so
+    // the cursor is shown here rather than by the guard.
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let mut signals = Signals::new([SIGINT])?;
+    std::thread::spawn({
+        let interrupted = interrupted.clone();
+        move || {
+            for _ in signals.forever() {
+                if interrupted.swap(true, Ordering::Relaxed) {
+                    let _ = execute!(stdout(), Show);
+                    std::process::exit(130);
+                }
+            }
+        }
+    });
 
     // Get the path of the configuration file and set the paths
     // of each benchmark correctly before building each binary.
@@ -223,6 +420,12 @@ fn main() -> Result<(), Error> {
             b.path = conf_path.join(&b.path);
             build_binary(b)?;
 
+            if interrupted.load(Ordering::Relaxed) {
+                return Err("Interrupted while building the benchmarks"
+                    .to_owned()
+                    .into());
+            }
+
             execute!(stdout(), MoveToColumn(0))?;
             println!(
                 "{} benchmark {}",
@@ -251,18 +454,28 @@ fn main() -> Result<(), Error> {
         .map(|(i, (b, c))| (i, b, c))
         .collect();
 
-    // Move to the previous line (as all runs will add a newline before printing text)
+    // Flush the output of the builds before the runs start redrawing the last lines
     stdout().flush()?;
 
     let n = cli.threads.unwrap_or(4);
+    let total = configurations.len();
 
-    let results = run(n, configurations)?;
+    let results = run(n, configurations, warm, repeat, interrupted.clone())?;
+    let interrupted = interrupted.load(Ordering::Relaxed);
 
-    println!(
-        "\n{} (took {:.2} seconds)",
-        finished_status("Done"),
-        results.iter().fold(0.0, |a, r| a + r.rtc.as_secs_f64())
-    );
+    if interrupted {
+        println!(
+            "\n{} after {} of {total} runs finished",
+            interrupted_status("Interrupted"),
+            results.len()
+        );
+    } else {
+        println!(
+            "\n{} (took {:.2} seconds)",
+            finished_status("Done"),
+            results.iter().fold(0.0, |a, r| a + r.rtc.as_secs_f64())
+        );
+    }
 
     let file = cli.output_file();
     println!(
@@ -273,12 +486,97 @@ fn main() -> Result<(), Error> {
 
     let mut file = File::create(file)?;
 
+    if interrupted {
+        writeln!(
+            file,
+            "# Incomplete results: interrupted after {} of {total} runs",
+            results.len()
+        )?;
+    }
+
     RunResult::write_headers(&mut file)?;
     results
-        .into_iter()
+        .iter()
         .try_for_each(|line| writeln!(file, "{}", line))?;
 
-    execute!(stdout(), Show)?;
+    // Summarize each configuration after a blank line
+    writeln!(file)?;
+    Summary::write_headers(&mut file)?;
+    Summary::aggregate(&results)
+        .into_iter()
+        .try_for_each(|line| writeln!(file, "{}", line))?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libseis::instruction_set::{encode, ControlOp, Instruction};
+
+    #[test]
+    fn runs_begin_at_the_entry_point() {
+        let dir = std::env::temp_dir().join(format!("seis-bench-entry-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Only the last two no-ops come at or after the entry point
+        let program: Vec<u8> = [ControlOp::Nop; 6]
+            .into_iter()
+            .chain([ControlOp::Halt])
+            .flat_map(|op| encode(Instruction::Control(op)).to_be_bytes())
+            .collect();
+        std::fs::write(
+            dir.join("entry.bin"),
+            image::encode(0x0000_0010, [(0x0000_0000, 0, &program[..])]),
+        )
+        .unwrap();
+
+        let benchmark = Benchmark {
+            path: dir.clone(),
+            binary: "entry.bin".into(),
+            ..Default::default()
+        };
+        let config = SimulationConfig {
+            pages: PAGES,
+            ..Default::default()
+        };
+
+        let mut memory = Memory::new(PAGES);
+        let entry = prepare_sim(&mut memory, &benchmark);
+        let run = simulate(&benchmark, &config, &[], &AtomicBool::new(false));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(entry.unwrap(), 0x0000_0010);
+        let (_, retired, _) = run.unwrap().unwrap();
+        assert_eq!(retired, 2);
+    }
+
+    #[test]
+    fn runs_which_disagree_are_nondeterministic() {
+        let benchmark = Benchmark {
+            name: "bench".into(),
+            ..Default::default()
+        };
+        let config = SimulationConfig {
+            name: "conf".into(),
+            ..Default::default()
+        };
+        let runs = |clocks: &'static [usize]| {
+            let mut clocks = clocks.iter();
+            move || {
+                Ok(clocks
+                    .next()
+                    .map(|&clocks| (clocks, clocks / 2, Duration::ZERO)))
+            }
+        };
+
+        let result = repeat_runs(&benchmark, &config, 3, runs(&[10, 10, 10]));
+        assert_eq!(result.unwrap().unwrap().clocks, 10);
+
+        let error = repeat_runs(&benchmark, &config, 3, runs(&[10, 10, 12])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Benchmark bench with configuration conf is nondeterministic (ran for 10 clocks, then 12)"
+        );
+    }
+}
@@ -11,25 +11,183 @@ pub struct RunResult {
 
     /// The number of clocks elapsed when running the benchmark and configuration
     pub clocks: usize,
-    /// The amount of time elapsed while running the benchmark, in real time
+    /// The median amount of time elapsed while running the benchmark, in real time
     pub rtc: Duration,
+    /// The number of instructions retired per clock
+    pub ipc: f64,
+    /// The least amount of time elapsed by a run of the benchmark
+    pub rtc_min: Duration,
+    /// The greatest amount of time elapsed by a run of the benchmark
+    pub rtc_max: Duration,
 }
 
 impl Display for RunResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{},{},{},{}",
+            "{},{},{},{},{},{},{}",
             self.bench_name,
             self.config_name,
             self.clocks,
-            self.rtc.as_secs_f64()
+            self.rtc.as_secs_f64(),
+            self.ipc,
+            self.rtc_min.as_secs_f64(),
+            self.rtc_max.as_secs_f64()
         )
     }
 }
 
 impl RunResult {
     pub fn write_headers<W: Write>(w: &mut W) -> std::io::Result<()> {
-        writeln!(w, "benchmark,configuration,clocks,rtc")
+        writeln!(w, "benchmark,configuration,clocks,rtc,ipc,rtc_min,rtc_max")
+    }
+}
+
+/// Computes the median of the times, or zero if there are none
+///
+/// The times are sorted in place. An even number of times has the mean of the middle
+/// two as its median.
+pub fn median(times: &mut [Duration]) -> Duration {
+    times.sort_unstable();
+
+    match times.len() {
+        0 => Duration::ZERO,
+        len if len & 1 == 1 => times[len / 2],
+        len => (times[len / 2 - 1] + times[len / 2]) / 2,
+    }
+}
+
+/// The statistics of a configuration aggregated across every benchmark it ran
+#[derive(Debug, Default, Clone)]
+pub struct Summary {
+    /// The name of the configuration
+    pub config_name: String,
+
+    /// The number of benchmarks run with the configuration
+    pub runs: usize,
+    /// The geometric mean of the clocks elapsed by each benchmark
+    pub clocks_geomean: f64,
+    /// The amount of time elapsed by every benchmark, in real time
+    pub total_rtc: Duration,
+    /// The arithmetic mean of the instructions retired per clock by each benchmark
+    pub mean_ipc: f64,
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{}",
+            self.config_name,
+            self.runs,
+            self.clocks_geomean,
+            self.total_rtc.as_secs_f64(),
+            self.mean_ipc
+        )
+    }
+}
+
+impl Summary {
+    pub fn write_headers<W: Write>(w: &mut W) -> std::io::Result<()> {
+        writeln!(w, "configuration,runs,geomean_clocks,total_rtc,mean_ipc")
+    }
+
+    /// Aggregates the results of each configuration, in the order the configurations
+    /// first appear in `results`
+    pub fn aggregate(results: &[RunResult]) -> Vec<Self> {
+        let mut names: Vec<&str> = Vec::new();
+
+        for result in results {
+            if !names.contains(&result.config_name.as_str()) {
+                names.push(&result.config_name);
+            }
+        }
+
+        names
+            .into_iter()
+            .map(|name| {
+                let runs: Vec<_> = results.iter().filter(|r| r.config_name == name).collect();
+
+                Self {
+                    config_name: name.to_owned(),
+                    runs: runs.len(),
+                    clocks_geomean: geometric_mean(runs.iter().map(|r| r.clocks as f64)),
+                    total_rtc: runs.iter().map(|r| r.rtc).sum(),
+                    mean_ipc: runs.iter().map(|r| r.ipc).sum::<f64>() / runs.len() as f64,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Computes the geometric mean of the values, or 0 if there are none
+///
+/// The mean is computed from the logarithms of the values, so that the product of many
+/// large clock counts cannot overflow.
+pub fn geometric_mean(values: impl IntoIterator<Item = f64>) -> f64 {
+    let (count, log_sum) = values.into_iter().fold((0, 0.0), |(count, sum), value| {
+        (count + 1, sum + value.ln())
+    });
+
+    if count == 0 {
+        0.0
+    } else {
+        (log_sum / count as f64).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(config_name: &str, clocks: usize, ipc: f64) -> RunResult {
+        RunResult {
+            bench_name: "bench".into(),
+            config_name: config_name.into(),
+            clocks,
+            rtc: Duration::from_millis(clocks as u64),
+            ipc,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn medians() {
+        let ms = Duration::from_millis;
+
+        assert_eq!(median(&mut []), Duration::ZERO);
+        assert_eq!(median(&mut [ms(5)]), ms(5));
+        assert_eq!(median(&mut [ms(9), ms(1), ms(4)]), ms(4));
+        assert_eq!(median(&mut [ms(9), ms(1), ms(4), ms(2)]), ms(3));
+    }
+
+    #[test]
+    fn geometric_means() {
+        assert_eq!(geometric_mean([]), 0.0);
+        assert!((geometric_mean([7.0]) - 7.0).abs() < 1e-9);
+        assert!((geometric_mean([2.0, 8.0]) - 4.0).abs() < 1e-9);
+        assert!((geometric_mean([1.0, 10.0, 100.0, 1000.0]) - 1000f64.sqrt()).abs() < 1e-9);
+        assert!((geometric_mean([1e300, 1e300]) - 1e300).abs() / 1e300 < 1e-9);
+    }
+
+    #[test]
+    fn aggregates_by_configuration() {
+        let summaries = Summary::aggregate(&[
+            run("pipe", 100, 0.5),
+            run("cache", 10, 0.25),
+            run("pipe", 400, 1.0),
+        ]);
+
+        assert_eq!(summaries.len(), 2);
+
+        assert_eq!(summaries[0].config_name, "pipe");
+        assert_eq!(summaries[0].runs, 2);
+        assert!((summaries[0].clocks_geomean - 200.0).abs() < 1e-9);
+        assert_eq!(summaries[0].total_rtc, Duration::from_millis(500));
+        assert_eq!(summaries[0].mean_ipc, 0.75);
+
+        assert_eq!(summaries[1].config_name, "cache");
+        assert_eq!(summaries[1].runs, 1);
+        assert!((summaries[1].clocks_geomean - 10.0).abs() < 1e-9);
     }
 }
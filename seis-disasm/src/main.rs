@@ -3,7 +3,12 @@ use libseis::{
     instruction_set::{decode, Instruction},
     types::Word,
 };
-use std::{fs::read, io::{stdin, stdout, Write}, path::PathBuf};
+use std::{
+    collections::BTreeSet,
+    fs::read,
+    io::{stdin, stdout, Write},
+    path::PathBuf,
+};
 
 #[derive(Parser, Debug, Clone)]
 pub struct Cli {
@@ -12,12 +17,50 @@ pub struct Cli {
 
     #[arg(short)]
     pub binary: bool,
+
+    /// Label the targets of relative jumps, and use the labels in place of offsets
+    #[arg(short, long)]
+    pub labels: bool,
+}
+
+/// Decodes the word at the start of each chunk of four bytes
+fn instructions(content: &[u8]) -> impl Iterator<Item = Option<Instruction>> + '_ {
+    content.chunks(4).map(|word| match word {
+        &[a, b, c, d] => decode(Word::from_be_bytes([a, b, c, d])).ok(),
+        _ => None,
+    })
+}
+
+/// Finds the addresses within the image which relative jumps go to
+fn jump_targets(content: &[u8]) -> BTreeSet<Word> {
+    instructions(content)
+        .zip((0..).step_by(4))
+        .filter_map(|(instruction, address)| match instruction {
+            Some(Instruction::Control(op)) => op.target(address),
+            _ => None,
+        })
+        .filter(|&target| (target as usize) < content.len())
+        .collect()
+}
+
+/// The name given to the label of a jump target
+fn label(address: Word) -> String {
+    format!("L_{address:#06X}")
 }
 
 fn main() {
-    let Cli { file, binary } = Cli::parse();
+    let Cli {
+        file,
+        binary,
+        labels,
+    } = Cli::parse();
 
     let content = read(file).expect("Failed to read file");
+    let targets = if labels {
+        jump_targets(&content)
+    } else {
+        BTreeSet::new()
+    };
 
     let header = if binary {
         format!(
@@ -43,7 +86,7 @@ fn main() {
         )
     };
 
-    for (i, word) in content.chunks(4).enumerate() {
+    for ((i, word), instruction) in content.chunks(4).enumerate().zip(instructions(&content)) {
         if i * 4 % 0x28 == 0 {
             if i != 0 {
                 print!("[PRESS ENTER]");
@@ -56,12 +99,23 @@ fn main() {
             println!("{header}");
         }
 
+        let address = (i * 4) as Word;
+        if targets.contains(&address) {
+            println!("{}:", label(address));
+        }
+
         if word.len() == 4 {
-            let instruction = match decode::<Instruction>(Word::from_be_bytes([
-                word[0], word[1], word[2], word[3],
-            ])) {
-                Ok(instruction) => instruction.to_string(),
-                Err(_) => "unknown".to_string(),
+            let instruction = match instruction {
+                Some(Instruction::Control(op)) => match op.target(address) {
+                    Some(target) if targets.contains(&target) => {
+                        let text = op.to_string();
+                        let (mnemonic, _) = text.split_once(' ').unwrap();
+                        format!("{mnemonic} {}", label(target))
+                    }
+                    _ => op.to_string(),
+                },
+                Some(instruction) => instruction.to_string(),
+                None => "unknown".to_string(),
             };
 
             if binary {
@@ -1,6 +1,36 @@
-use clap::{Args, Parser, ValueHint::FilePath};
+use clap::{Args, Parser, ValueEnum, ValueHint::FilePath};
+use libseis::types::Word;
 use std::path::PathBuf;
 
+/// A flat image to load at an address after the main image
+#[derive(Debug, Clone)]
+pub struct Load {
+    pub file: PathBuf,
+    pub address: Word,
+}
+
+/// Parses a load written as `file@address`, where the address is decimal, or hexadecimal
+/// with a `0x` prefix
+fn parse_load(input: &str) -> Result<Load, String> {
+    let (file, address) = input
+        .rsplit_once('@')
+        .ok_or_else(|| format!("Expected FILE@ADDRESS, found \"{input}\""))?;
+
+    let address = match address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+    {
+        Some(hex) => Word::from_str_radix(hex, 16),
+        None => address.parse(),
+    }
+    .map_err(|e| format!("Invalid address \"{address}\": {e}"))?;
+
+    Ok(Load {
+        file: file.into(),
+        address,
+    })
+}
+
 #[derive(Debug, Args)]
 #[group(multiple = false, required = true)]
 pub struct Configuration {
@@ -25,6 +55,19 @@ pub struct SimulatorConfig {
     /// Enables backend mode
     #[arg(short, long)]
     pub backend_mode: bool,
+
+    /// A flat image to load at an address, such as `data.bin@0x30000`. May be repeated.
+    #[arg(long = "load", value_name = "FILE@ADDRESS", value_parser = parse_load)]
+    pub loads: Vec<Load>,
+}
+
+/// How the taken branches of a profile are written out
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum EdgeFormat {
+    /// A Graphviz graph
+    Dot,
+    /// A JSON array of edges
+    Json,
 }
 
 /// Configure the simulation runtime.
@@ -42,6 +85,16 @@ pub enum Cli {
         output_file: Option<PathBuf>,
     },
 
+    /// Lists the preset configurations, or prints one by name
+    Presets {
+        /// The preset to print
+        name: Option<String>,
+
+        /// Where to store the preset
+        #[arg(value_hint = FilePath)]
+        output_file: Option<PathBuf>,
+    },
+
     Simulate {
         /// The binary image file to be used for the simulation
         #[arg(value_hint = FilePath)]
@@ -50,7 +103,76 @@ pub enum Cli {
         #[command(flatten)]
         configuration: Configuration,
 
+        /// A flat image to load at an address, such as `data.bin@0x30000`. May be repeated.
+        #[arg(long = "load", value_name = "FILE@ADDRESS", value_parser = parse_load)]
+        loads: Vec<Load>,
+
         #[clap(short, long)]
         clock_only: bool
     },
+
+    /// Runs the simulation on both the pipelined and unpipelined processors, and
+    /// reports where their final states differ
+    Validate {
+        /// The binary image file to be used for the simulation
+        #[arg(value_hint = FilePath)]
+        image_file: PathBuf,
+
+        #[command(flatten)]
+        configuration: Configuration,
+
+        /// A flat image to load at an address, such as `data.bin@0x30000`. May be repeated.
+        #[arg(long = "load", value_name = "FILE@ADDRESS", value_parser = parse_load)]
+        loads: Vec<Load>,
+    },
+
+    /// Runs two images under the same configuration, and reports where their final states
+    /// differ
+    Diff {
+        /// The first binary image file to compare
+        #[arg(value_hint = FilePath)]
+        image_a: PathBuf,
+
+        /// The second binary image file to compare
+        #[arg(value_hint = FilePath)]
+        image_b: PathBuf,
+
+        #[command(flatten)]
+        configuration: Configuration,
+
+        /// A flat image to load at an address for both runs, such as `data.bin@0x30000`.
+        /// May be repeated.
+        #[arg(long = "load", value_name = "FILE@ADDRESS", value_parser = parse_load)]
+        loads: Vec<Load>,
+    },
+
+    /// Runs the simulation and lists the addresses which retired the most instructions
+    Profile {
+        /// The binary image file to be used for the simulation
+        #[arg(value_hint = FilePath)]
+        image_file: PathBuf,
+
+        #[command(flatten)]
+        configuration: Configuration,
+
+        /// A flat image to load at an address, such as `data.bin@0x30000`. May be repeated.
+        #[arg(long = "load", value_name = "FILE@ADDRESS", value_parser = parse_load)]
+        loads: Vec<Load>,
+
+        /// How many addresses to list
+        #[arg(short = 'n', long, default_value_t = 10)]
+        top: usize,
+
+        /// Writes out how often each branch was taken instead of listing addresses
+        #[arg(short, long)]
+        edges: Option<EdgeFormat>,
+
+        /// Lists how many instructions of each kind retired instead of listing addresses
+        #[arg(short, long, conflicts_with = "edges")]
+        mix: bool,
+    },
+
+    /// Assembles each line typed in and runs it as the next instruction, showing the
+    /// registers it changed
+    Repl,
 }
@@ -1,10 +1,14 @@
 use crate::PAGES;
+pub use libconfig::{read_toml, resolve_base};
 use libmem::{
     cache::{Associative, Cache, MultiAssociative, NullCache},
     memory::Memory,
-    module::SingleLevel,
+    module::{MemoryModule, SingleLevel},
 };
-use libpipe::{Pipeline, Pipelined, Unpipelined};
+use libpipe::{
+    Console, EnergyCosts, Host, Latencies, Pipeline, PipelineOptions, Pipelined, Unpipelined,
+};
+use libseis::{pages::MAX_PAGES, registers::V, types::Word};
 use std::{collections::HashMap, error::Error, fmt::Display, str::FromStr};
 
 #[derive(Debug, Clone, Copy)]
@@ -35,30 +39,46 @@ impl CacheConfiguration {
         }
     }
 
-    pub fn from_toml(table: &toml::Table) -> Result<Self, Box<dyn Error>> {
-        match table
-            .get("mode")
-            .ok_or_else(|| "Key required: \"mode\"")?
-            .as_str()
-            .ok_or_else(|| "Expected string")?
-        {
+    /// Reads the configuration of a cache from the table at `path`
+    pub fn from_toml(table: &toml::Table, path: &str) -> Result<Self, Box<dyn Error>> {
+        check_keys(table, path, &["mode", "set_bits", "offset_bits", "ways"])?;
+
+        match required(get_str(table, path, "mode")?, path, "mode")? {
             "disabled" => Ok(Self::Disabled),
             "associative" => {
-                let set_bits = table
-                    .get("set_bits")
-                    .ok_or_else(|| "Key required: \"set_bits\"")?
-                    .as_integer()
-                    .ok_or_else(|| "Expected integer")? as usize;
-                let offset_bits = table
-                    .get("offset_bits")
-                    .ok_or_else(|| "Key required: \"offset_bits\"")?
-                    .as_integer()
-                    .ok_or_else(|| "Expected integer")? as usize;
-                let ways = table
-                    .get("ways")
-                    .ok_or_else(|| "Key required: \"ways\"")?
-                    .as_integer()
-                    .ok_or_else(|| "Expected integer")? as usize;
+                let set_bits = required(get_count(table, path, "set_bits", 0)?, path, "set_bits")?;
+                let mut offset_bits = required(
+                    get_count(table, path, "offset_bits", 0)?,
+                    path,
+                    "offset_bits",
+                )?;
+                let mut ways = required(get_count(table, path, "ways", 0)?, path, "ways")?;
+
+                // Older configurations may hold lines narrower than a word or no ways,
+                // which the caches cannot be built with
+                if offset_bits < 2 {
+                    warn(format!(
+                        "Key \"{}\" is {offset_bits}, so lines are widened to a word",
+                        key_name(path, "offset_bits")
+                    ));
+                    offset_bits = 2;
+                }
+                if ways == 0 {
+                    warn(format!(
+                        "Key \"{}\" is 0, so the cache has one way",
+                        key_name(path, "ways")
+                    ));
+                    ways = 1;
+                }
+
+                if set_bits + offset_bits > 32 {
+                    return Err(format!(
+                        "Keys \"{}\" and \"{}\" must add up to at most 32",
+                        key_name(path, "set_bits"),
+                        key_name(path, "offset_bits")
+                    )
+                    .into());
+                }
 
                 Ok(Self::Associative {
                     set_bits,
@@ -67,7 +87,11 @@ impl CacheConfiguration {
                 })
             }
 
-            mode => Err(format!("Unrecognized mode: {mode}").into()),
+            mode => Err(format!(
+                "Key \"{}\" must be \"disabled\" or \"associative\", found \"{mode}\"",
+                key_name(path, "mode")
+            )
+            .into()),
         }
     }
 
@@ -144,19 +168,118 @@ impl FromStr for PipelineMode {
     }
 }
 
+/// Where the memory-mapped [`Console`] sits in the address space
+#[derive(Debug, Clone, Copy)]
+pub struct ConsoleConfiguration {
+    pub base: Word,
+    pub size: Word,
+}
+
+impl ConsoleConfiguration {
+    /// Reads the configuration of the console from the table at `path`
+    pub fn from_toml(table: &toml::Table, path: &str) -> Result<Self, Box<dyn Error>> {
+        check_keys(table, path, &["base", "size"])?;
+
+        // The console needs room for both of its registers
+        let base = required(get_count(table, path, "base", 0)?, path, "base")?;
+        let size = get_count(table, path, "size", 8)?.unwrap_or(8);
+
+        match (Word::try_from(base), Word::try_from(size)) {
+            (Ok(base), Ok(size)) if base.checked_add(size - 1).is_some() => Ok(Self { base, size }),
+            _ => Err(format!(
+                "Keys \"{}\" and \"{}\" must keep the console within the address space",
+                key_name(path, "base"),
+                key_name(path, "size")
+            )
+            .into()),
+        }
+    }
+
+    pub fn to_toml(self) -> toml::Table {
+        toml::Table::from_iter([
+            ("base".to_string(), (self.base as i64).into()),
+            ("size".to_string(), (self.size as i64).into()),
+        ])
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct SimulationConfiguration {
+    /// The configuration of the `instruction` and `data` caches, each of which is built
+    /// from its own entry
     pub cache: HashMap<String, CacheConfiguration>,
 
     pub miss_penalty: usize,
     pub volatile_penalty: usize,
     pub writethrough: bool,
+    /// The number of 64 KiB pages of memory, starting from address zero
+    pub pages: usize,
+    /// The seed of the pseudo-random values memory holds before it is written, or `None`
+    /// for memory which starts zeroed
+    pub memory_seed: Option<u64>,
 
     pub pipelining: PipelineMode,
+    pub extra_execute_stages: usize,
+    pub latencies: Latencies,
+    /// The energy charged for each instruction and cache access, all zero unless set
+    pub energy: EnergyCosts,
+    pub max_in_flight: usize,
+    pub memory_ports: usize,
+    /// The number of instructions fetched per clock, of which only one and two differ
+    pub fetch_width: usize,
+    /// The number of fetched instructions which may wait for the decode stage
+    pub fetch_queue_depth: usize,
+    pub banks: usize,
+    /// The number of evicted dirty lines buffered while they drain to memory, or `None`
+    /// for an unbounded buffer
+    pub write_buffer_depth: Option<usize>,
+    /// The number of the most recent cache misses to log, or `None` to not log them
+    pub miss_log: Option<usize>,
+
+    pub stack_checks: bool,
+    pub write_checks: bool,
+    pub alignment_checks: bool,
+    /// Whether to fetch instructions again when a store writes over them after they
+    /// were fetched
+    pub smc_checks: bool,
+    pub vector_table: Option<Word>,
+    pub registers: Option<usize>,
+
+    pub console: Option<ConsoleConfiguration>,
+
+    /// Whether to count the instructions retired at each address. This is set by the
+    /// `profile` command rather than read from the configuration.
+    pub profile: bool,
 }
 
 impl SimulationConfiguration {
+    /// Creates the pipeline, discarding the output of the program and giving it no input
     pub fn into_boxed_pipeline(self) -> Box<dyn Pipeline> {
+        self.build(None)
+    }
+
+    /// Creates the pipeline, with `host` performing the input and output of the program,
+    /// whether through system calls or the console
+    pub fn into_hosted_pipeline<H>(self, host: H) -> Box<dyn Pipeline>
+    where
+        H: Host + Clone + Send + Sync + 'static,
+    {
+        let mut pipeline = self.build(Some(Box::new(host.clone())));
+        pipeline.set_host(Box::new(host));
+        pipeline
+    }
+
+    /// Creates the memory of the pipeline, before anything is loaded into it
+    pub fn memory(&self) -> Memory {
+        match self.memory_seed {
+            Some(seed) => Memory::new_filled(self.pages, seed),
+            None => Memory::new(self.pages),
+        }
+    }
+
+    /// Creates the pipeline, with `console_host` performing the input and output of the
+    /// console
+    fn build(self, console_host: Option<Box<dyn Host + Send + Sync>>) -> Box<dyn Pipeline> {
         let data_config = self
             .cache
             .get("data")
@@ -169,67 +292,209 @@ impl SimulationConfiguration {
         let mem = SingleLevel::new(
             data_config.into_boxed_cache(),
             instruction_config.into_boxed_cache(),
-            Memory::new(PAGES),
+            self.memory(),
             self.miss_penalty,
             self.volatile_penalty,
             self.writethrough,
-        );
+        )
+        .with_ports(self.memory_ports)
+        .with_banks(self.banks)
+        .with_write_buffer(self.write_buffer_depth.unwrap_or(usize::MAX));
+        let mem = match self.miss_log {
+            Some(capacity) => mem.with_miss_log(capacity),
+            None => mem,
+        };
+
+        let mem: Box<dyn MemoryModule + Send + Sync> = match self.console {
+            Some(ConsoleConfiguration { base, size }) => {
+                let console = Console::new(Box::new(mem), base, size);
+
+                match console_host {
+                    Some(host) => Box::new(console.with_host(host)),
+                    None => Box::new(console),
+                }
+            }
+            None => Box::new(mem),
+        };
+
+        let options = PipelineOptions {
+            stack_checks: self.stack_checks,
+            write_checks: self.write_checks,
+            alignment_checks: self.alignment_checks,
+            smc_checks: self.smc_checks,
+            extra_execute_stages: self.extra_execute_stages,
+            latencies: self.latencies,
+            energy: self.energy,
+            max_in_flight: self.max_in_flight,
+            memory_ports: self.memory_ports,
+            fetch_width: self.fetch_width,
+            fetch_queue_depth: self.fetch_queue_depth,
+            vector_table: self.vector_table,
+            registers: self.registers,
+            profile: self.profile,
+        };
 
         match self.pipelining {
-            PipelineMode::Disabled => Box::new(Unpipelined::new(Box::new(mem))),
-            PipelineMode::Enabled => Box::new(Pipelined::new(Box::new(mem))),
+            PipelineMode::Disabled => Box::new(Unpipelined::with_options(mem, options)),
+            PipelineMode::Enabled => Box::new(Pipelined::with_options(mem, options)),
         }
     }
 
     pub fn from_toml(table: &toml::Table) -> Result<Self, Box<dyn Error>> {
+        check_keys(
+            table,
+            "",
+            &[
+                "miss_penalty",
+                "volatile_penalty",
+                "writethrough",
+                "pages",
+                "memory_seed",
+                "pipelining",
+                "extra_execute_stages",
+                "max_in_flight",
+                "memory_ports",
+                "fetch_width",
+                "fetch_queue_depth",
+                "banks",
+                "write_buffer_depth",
+                "miss_log",
+                "latencies",
+                "energy",
+                "stack_checks",
+                "write_checks",
+                "alignment_checks",
+                "smc_checks",
+                "vector_table",
+                "registers",
+                "console",
+                "cache",
+                // Names the image to run in older configurations, and is ignored
+                "simulation",
+            ],
+        )?;
+
         let mut result = SimulationConfiguration::default();
 
-        result.miss_penalty = table
-            .get("miss_penalty")
-            .ok_or_else(|| "Key required: \"miss_penalty\"")?
-            .as_integer()
-            .ok_or_else(|| "Expected integer")? as usize;
-        result.volatile_penalty = table
-            .get("volatile_penalty")
-            .ok_or_else(|| "Key required: \"volatile_penalty\"")?
-            .as_integer()
-            .ok_or_else(|| "Expected integer")? as usize;
-        result.writethrough = table
-            .get("writethrough")
-            .ok_or_else(|| "Key required: \"writethrough\"")?
-            .as_bool()
-            .ok_or_else(|| "Expected boolean")?;
-        result.pipelining = if let Some(value) = table.get("pipelining") {
-            if let Some(str) = value.as_str() {
-                str.parse()?
-            } else if let Some(mode) = value.as_bool() {
-                if mode {
-                    PipelineMode::Enabled
-                } else {
-                    PipelineMode::Disabled
-                }
-            } else {
-                return Err("Key \"pipelining\" must be either a string or a boolean".into());
+        result.miss_penalty =
+            required(get_count(table, "", "miss_penalty", 0)?, "", "miss_penalty")?;
+        result.volatile_penalty = required(
+            get_count(table, "", "volatile_penalty", 0)?,
+            "",
+            "volatile_penalty",
+        )?;
+        result.writethrough = required(get_bool(table, "", "writethrough")?, "", "writethrough")?;
+        result.pages = get_count(table, "", "pages", 1)?.unwrap_or(PAGES);
+        if result.pages > MAX_PAGES {
+            return Err(format!(
+                "Key \"pages\" must be at most {MAX_PAGES}, which covers every address"
+            )
+            .into());
+        }
+        result.memory_seed = get_count(table, "", "memory_seed", 0)?.map(|seed| seed as u64);
+        result.pipelining = match table.get("pipelining") {
+            Some(toml::Value::String(mode)) => mode
+                .parse()
+                .map_err(|e| format!("Key \"pipelining\" is invalid: {e}"))?,
+            Some(&toml::Value::Boolean(true)) => PipelineMode::Enabled,
+            Some(&toml::Value::Boolean(false)) => PipelineMode::Disabled,
+            Some(value) => {
+                return Err(format!(
+                    "Key \"pipelining\" must be either a string or a boolean, found {}",
+                    value.type_str()
+                )
+                .into())
             }
-        } else {
-            PipelineMode::default()
+            None => PipelineMode::default(),
         };
+        result.extra_execute_stages = get_count(table, "", "extra_execute_stages", 0)?.unwrap_or(0);
+        result.max_in_flight = get_count(table, "", "max_in_flight", 0)?.unwrap_or(0);
+        result.memory_ports = get_count(table, "", "memory_ports", 1)?.unwrap_or(1);
+        result.fetch_width = get_count(table, "", "fetch_width", 1)?.unwrap_or(1);
+        result.fetch_queue_depth = get_count(table, "", "fetch_queue_depth", 1)?.unwrap_or(1);
+        result.banks = get_count(table, "", "banks", 0)?.unwrap_or(0);
+        result.write_buffer_depth = get_count(table, "", "write_buffer_depth", 0)?;
+        result.miss_log = get_count(table, "", "miss_log", 1)?;
+        if let Some(latencies) = get_table(table, "", "latencies")? {
+            check_keys(
+                latencies,
+                "latencies",
+                &["multiply", "divide", "float", "float_divide"],
+            )?;
+
+            let fields = [
+                ("multiply", &mut result.latencies.multiply),
+                ("divide", &mut result.latencies.divide),
+                ("float", &mut result.latencies.float),
+                ("float_divide", &mut result.latencies.float_divide),
+            ];
+
+            for (key, field) in fields {
+                if let Some(clocks) = get_count(latencies, "latencies", key, 1)? {
+                    *field = clocks;
+                }
+            }
+        }
+        if let Some(energy) = get_table(table, "", "energy")? {
+            let fields = [
+                ("control", &mut result.energy.control),
+                ("integer", &mut result.energy.integer),
+                ("floating_point", &mut result.energy.floating_point),
+                ("register", &mut result.energy.register),
+                ("hit", &mut result.energy.hit),
+                ("miss", &mut result.energy.miss),
+                ("eviction", &mut result.energy.eviction),
+            ];
 
-        result.cache = table
-            .get("cache")
-            .ok_or_else(|| "Key required: \"cache\"")?
-            .as_table()
-            .ok_or_else(|| "Expected table")?
-            .into_iter()
-            .map(|(key, value)| {
-                Ok((
-                    key.to_owned(),
-                    CacheConfiguration::from_toml(
-                        value.as_table().ok_or_else(|| "Expected table")?,
-                    )?,
-                ))
-            })
-            .collect::<Result<HashMap<String, CacheConfiguration>, Box<dyn Error>>>()?;
+            check_keys(
+                energy,
+                "energy",
+                &fields.iter().map(|&(key, _)| key).collect::<Vec<_>>(),
+            )?;
+
+            for (key, field) in fields {
+                if let Some(cost) = get_cost(energy, "energy", key)? {
+                    *field = cost;
+                }
+            }
+        }
+        result.stack_checks = get_bool(table, "", "stack_checks")?.unwrap_or(false);
+        result.write_checks = get_bool(table, "", "write_checks")?.unwrap_or(false);
+        result.alignment_checks = get_bool(table, "", "alignment_checks")?.unwrap_or(false);
+        result.smc_checks = get_bool(table, "", "smc_checks")?.unwrap_or(false);
+        result.vector_table = get_count(table, "", "vector_table", 0)?
+            .map(Word::try_from)
+            .transpose()
+            .map_err(|_| "Key \"vector_table\" must be an address")?;
+        result.registers = get_count(table, "", "registers", 1)?;
+        if result.registers.is_some_and(|count| count > V.len()) {
+            return Err(format!("Key \"registers\" must be at most {}", V.len()).into());
+        }
+        result.console = get_table(table, "", "console")?
+            .map(|console| ConsoleConfiguration::from_toml(console, "console"))
+            .transpose()?;
+
+        let caches = required(get_table(table, "", "cache")?, "", "cache")?;
+
+        for name in ["data", "instruction"] {
+            required(get_table(caches, "cache", name)?, "cache", name)?;
+        }
+
+        for name in caches.keys() {
+            let path = key_name("cache", name);
+            let cache = required(get_table(caches, "cache", name)?, "cache", name)?;
+
+            if name != "data" && name != "instruction" {
+                warn(format!(
+                    "Key \"{path}\" is neither the data nor the instruction cache, and is unused"
+                ));
+            }
+
+            result.cache.insert(
+                name.to_owned(),
+                CacheConfiguration::from_toml(cache, &path)?,
+            );
+        }
 
         Ok(result)
     }
@@ -246,6 +511,10 @@ impl SimulationConfiguration {
             (self.volatile_penalty as i64).into(),
         );
         table.insert("writethrough".to_string(), self.writethrough.into());
+        table.insert("pages".to_string(), (self.pages as i64).into());
+        if let Some(seed) = self.memory_seed {
+            table.insert("memory_seed".to_string(), (seed as i64).into());
+        }
         table.insert(
             "pipelining".to_string(),
             match self.pipelining {
@@ -254,6 +523,76 @@ impl SimulationConfiguration {
                 // _ => self.pipelining.to_string().into(),
             },
         );
+        table.insert(
+            "extra_execute_stages".to_string(),
+            (self.extra_execute_stages as i64).into(),
+        );
+        table.insert(
+            "max_in_flight".to_string(),
+            (self.max_in_flight as i64).into(),
+        );
+        table.insert(
+            "memory_ports".to_string(),
+            (self.memory_ports as i64).into(),
+        );
+        table.insert("fetch_width".to_string(), (self.fetch_width as i64).into());
+        table.insert(
+            "fetch_queue_depth".to_string(),
+            (self.fetch_queue_depth as i64).into(),
+        );
+        table.insert("banks".to_string(), (self.banks as i64).into());
+        if let Some(depth) = self.write_buffer_depth {
+            table.insert("write_buffer_depth".to_string(), (depth as i64).into());
+        }
+        if let Some(capacity) = self.miss_log {
+            table.insert("miss_log".to_string(), (capacity as i64).into());
+        }
+        table.insert(
+            "latencies".to_string(),
+            toml::Table::from_iter([
+                (
+                    "multiply".to_string(),
+                    (self.latencies.multiply as i64).into(),
+                ),
+                ("divide".to_string(), (self.latencies.divide as i64).into()),
+                ("float".to_string(), (self.latencies.float as i64).into()),
+                (
+                    "float_divide".to_string(),
+                    (self.latencies.float_divide as i64).into(),
+                ),
+            ])
+            .into(),
+        );
+        if self.energy != EnergyCosts::default() {
+            let energy = self.energy;
+
+            table.insert(
+                "energy".to_string(),
+                toml::Table::from_iter([
+                    ("control".to_string(), energy.control.into()),
+                    ("integer".to_string(), energy.integer.into()),
+                    ("floating_point".to_string(), energy.floating_point.into()),
+                    ("register".to_string(), energy.register.into()),
+                    ("hit".to_string(), energy.hit.into()),
+                    ("miss".to_string(), energy.miss.into()),
+                    ("eviction".to_string(), energy.eviction.into()),
+                ])
+                .into(),
+            );
+        }
+        table.insert("stack_checks".to_string(), self.stack_checks.into());
+        table.insert("write_checks".to_string(), self.write_checks.into());
+        table.insert("alignment_checks".to_string(), self.alignment_checks.into());
+        table.insert("smc_checks".to_string(), self.smc_checks.into());
+        if let Some(address) = self.vector_table {
+            table.insert("vector_table".to_string(), (address as i64).into());
+        }
+        if let Some(count) = self.registers {
+            table.insert("registers".to_string(), (count as i64).into());
+        }
+        if let Some(console) = self.console {
+            table.insert("console".to_string(), console.to_toml().into());
+        }
 
         let mut caches = toml::Table::new();
 
@@ -266,3 +605,675 @@ impl SimulationConfiguration {
         table
     }
 }
+
+/// A named configuration which is ready to use
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub build: fn() -> SimulationConfiguration,
+}
+
+/// The presets listed by the `presets` command
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "no-cache",
+        description: "Pipelined, with every access going to memory",
+        build: SimulationConfiguration::no_cache,
+    },
+    Preset {
+        name: "small-direct",
+        description: "Pipelined, with small direct-mapped instruction and data caches",
+        build: SimulationConfiguration::small_direct,
+    },
+    Preset {
+        name: "large-assoc",
+        description: "Pipelined, with large 4-way set associative caches and two memory ports",
+        build: SimulationConfiguration::large_assoc,
+    },
+    Preset {
+        name: "perfect-memory",
+        description: "Pipelined, with memory that never stalls",
+        build: SimulationConfiguration::perfect_memory,
+    },
+];
+
+impl SimulationConfiguration {
+    /// Finds the preset with the given name
+    pub fn preset(name: &str) -> Option<Self> {
+        PRESETS
+            .iter()
+            .find(|preset| preset.name == name)
+            .map(|preset| (preset.build)())
+    }
+
+    /// The settings shared by every preset, with both caches disabled
+    fn preset_base() -> Self {
+        Self {
+            cache: [
+                ("instruction".into(), CacheConfiguration::Disabled),
+                ("data".into(), CacheConfiguration::Disabled),
+            ]
+            .into(),
+            miss_penalty: 100,
+            volatile_penalty: 20,
+            writethrough: false,
+            pages: PAGES,
+            pipelining: PipelineMode::Enabled,
+            memory_ports: 1,
+            fetch_width: 1,
+            fetch_queue_depth: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn no_cache() -> Self {
+        Self::preset_base()
+    }
+
+    pub fn small_direct() -> Self {
+        let cache = CacheConfiguration::Associative {
+            set_bits: 4,
+            offset_bits: 4,
+            ways: 1,
+        };
+
+        Self {
+            cache: [("instruction".into(), cache), ("data".into(), cache)].into(),
+            ..Self::preset_base()
+        }
+    }
+
+    pub fn large_assoc() -> Self {
+        let cache = CacheConfiguration::Associative {
+            set_bits: 8,
+            offset_bits: 6,
+            ways: 4,
+        };
+
+        Self {
+            cache: [("instruction".into(), cache), ("data".into(), cache)].into(),
+            memory_ports: 2,
+            ..Self::preset_base()
+        }
+    }
+
+    pub fn perfect_memory() -> Self {
+        Self {
+            miss_penalty: 0,
+            volatile_penalty: 0,
+            ..Self::preset_base()
+        }
+    }
+}
+
+/// Names a key within the table at `path`, which is the root table if `path` is empty
+fn key_name(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Reports a configuration which is accepted, but likely not what was meant
+fn warn(message: String) {
+    eprintln!("Warning: {message}");
+}
+
+/// Fails on the first key of the table at `path` which is not one of `known`
+fn check_keys(table: &toml::Table, path: &str, known: &[&str]) -> Result<(), Box<dyn Error>> {
+    match table.keys().find(|key| !known.contains(&key.as_str())) {
+        Some(key) => Err(format!("Unrecognized key: \"{}\"", key_name(path, key)).into()),
+        None => Ok(()),
+    }
+}
+
+/// Fails if an optional key was not given
+fn required<T>(value: Option<T>, path: &str, key: &str) -> Result<T, Box<dyn Error>> {
+    value.ok_or_else(|| format!("Key required: \"{}\"", key_name(path, key)).into())
+}
+
+/// Reads an optional key, failing if its value is not of the `expected` type
+fn get_value<'a, T>(
+    table: &'a toml::Table,
+    path: &str,
+    key: &str,
+    expected: &str,
+    convert: impl FnOnce(&'a toml::Value) -> Option<T>,
+) -> Result<Option<T>, Box<dyn Error>> {
+    let Some(value) = table.get(key) else {
+        return Ok(None);
+    };
+
+    match convert(value) {
+        Some(value) => Ok(Some(value)),
+        None => Err(format!(
+            "Key \"{}\" must be {expected}, found {}",
+            key_name(path, key),
+            value.type_str()
+        )
+        .into()),
+    }
+}
+
+fn get_bool(table: &toml::Table, path: &str, key: &str) -> Result<Option<bool>, Box<dyn Error>> {
+    get_value(table, path, key, "a boolean", toml::Value::as_bool)
+}
+
+fn get_str<'a>(
+    table: &'a toml::Table,
+    path: &str,
+    key: &str,
+) -> Result<Option<&'a str>, Box<dyn Error>> {
+    get_value(table, path, key, "a string", toml::Value::as_str)
+}
+
+fn get_table<'a>(
+    table: &'a toml::Table,
+    path: &str,
+    key: &str,
+) -> Result<Option<&'a toml::Table>, Box<dyn Error>> {
+    get_value(table, path, key, "a table", toml::Value::as_table)
+}
+
+/// Reads an optional number key, which may be written as an integer, failing if it is
+/// negative
+fn get_cost(table: &toml::Table, path: &str, key: &str) -> Result<Option<f64>, Box<dyn Error>> {
+    let cost = get_value(table, path, key, "a number", |value| match value {
+        toml::Value::Float(cost) => Some(*cost),
+        toml::Value::Integer(cost) => Some(*cost as f64),
+        _ => None,
+    })?;
+
+    match cost {
+        Some(cost) if cost < 0.0 || cost.is_nan() => {
+            Err(format!("Key \"{}\" must not be negative", key_name(path, key)).into())
+        }
+        _ => Ok(cost),
+    }
+}
+
+/// Reads an optional integer key, failing if it is less than `minimum`
+fn get_count(
+    table: &toml::Table,
+    path: &str,
+    key: &str,
+    minimum: usize,
+) -> Result<Option<usize>, Box<dyn Error>> {
+    let Some(value) = get_value(table, path, key, "an integer", toml::Value::as_integer)? else {
+        return Ok(None);
+    };
+
+    match usize::try_from(value) {
+        Ok(count) if count >= minimum => Ok(Some(count)),
+        _ if minimum == 0 => {
+            Err(format!("Key \"{}\" must not be negative", key_name(path, key)).into())
+        }
+        _ => Err(format!("Key \"{}\" must be at least {minimum}", key_name(path, key)).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = r#"
+miss_penalty = 100
+volatile_penalty = 20
+writethrough = false
+pipelining = "enabled"
+
+[latencies]
+multiply = 3
+
+[cache.data]
+mode = "associative"
+set_bits = 2
+offset_bits = 2
+ways = 2
+
+[cache.instruction]
+mode = "disabled"
+"#;
+
+    /// Parses a configuration after replacing the first occurrence of `from` with `to`
+    fn parse(from: &str, to: &str) -> Result<SimulationConfiguration, Box<dyn Error>> {
+        let source = VALID.replacen(from, to, 1);
+        SimulationConfiguration::from_toml(&toml::from_str(&source).unwrap())
+    }
+
+    fn error(from: &str, to: &str) -> String {
+        parse(from, to).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn valid() {
+        let config = parse("", "").unwrap();
+
+        assert_eq!(config.miss_penalty, 100);
+        assert_eq!(config.volatile_penalty, 20);
+        assert!(!config.writethrough);
+        assert!(matches!(config.pipelining, PipelineMode::Enabled));
+        assert_eq!(config.latencies.multiply, 3);
+        assert_eq!(config.latencies.divide, Latencies::default().divide);
+        assert_eq!(config.memory_ports, 1);
+        assert_eq!(config.fetch_width, 1);
+        assert_eq!(config.fetch_queue_depth, 1);
+        assert_eq!(config.write_buffer_depth, None);
+        assert!(matches!(
+            config.cache["data"],
+            CacheConfiguration::Associative {
+                set_bits: 2,
+                offset_bits: 2,
+                ways: 2
+            }
+        ));
+        assert!(matches!(
+            config.cache["instruction"],
+            CacheConfiguration::Disabled
+        ));
+    }
+
+    #[test]
+    fn write_buffer_depth() {
+        let config = parse(
+            "writethrough = false",
+            "writethrough = false\nwrite_buffer_depth = 4",
+        )
+        .unwrap();
+        let reparsed = SimulationConfiguration::from_toml(&config.to_toml()).unwrap();
+
+        assert_eq!(config.write_buffer_depth, Some(4));
+        assert_eq!(reparsed.write_buffer_depth, Some(4));
+    }
+
+    #[test]
+    fn miss_log() {
+        let config = parse(
+            "writethrough = false",
+            "writethrough = false\nmiss_log = 1024",
+        )
+        .unwrap();
+        let reparsed = SimulationConfiguration::from_toml(&config.to_toml()).unwrap();
+
+        assert_eq!(config.miss_log, Some(1024));
+        assert_eq!(reparsed.miss_log, Some(1024));
+        assert!(config
+            .into_boxed_pipeline()
+            .memory_module()
+            .miss_log()
+            .is_some());
+        assert!(parse("", "")
+            .unwrap()
+            .into_boxed_pipeline()
+            .memory_module()
+            .miss_log()
+            .is_none());
+        assert_eq!(
+            error("writethrough = false", "writethrough = false\nmiss_log = 0"),
+            "Key \"miss_log\" must be at least 1"
+        );
+    }
+
+    #[test]
+    fn energy() {
+        let config = parse(
+            "[latencies]",
+            "[energy]\ninteger = 2\nmiss = 10.5\n\n[latencies]",
+        )
+        .unwrap();
+        let reparsed = SimulationConfiguration::from_toml(&config.to_toml()).unwrap();
+
+        assert_eq!(parse("", "").unwrap().energy, EnergyCosts::default());
+        assert!(!parse("", "").unwrap().to_toml().contains_key("energy"));
+        assert_eq!(
+            config.energy,
+            EnergyCosts {
+                integer: 2.0,
+                miss: 10.5,
+                ..Default::default()
+            }
+        );
+        assert_eq!(reparsed.energy, config.energy);
+        assert_eq!(
+            error("[latencies]", "[energy]\nhit = -1\n\n[latencies]"),
+            "Key \"energy.hit\" must not be negative"
+        );
+        assert_eq!(
+            error("[latencies]", "[energy]\nhit = \"one\"\n\n[latencies]"),
+            "Key \"energy.hit\" must be a number, found string"
+        );
+    }
+
+    #[test]
+    fn fetch_width() {
+        let config = parse(
+            "writethrough = false",
+            "writethrough = false\nfetch_width = 2",
+        )
+        .unwrap();
+        let reparsed = SimulationConfiguration::from_toml(&config.to_toml()).unwrap();
+
+        assert_eq!(config.fetch_width, 2);
+        assert_eq!(reparsed.fetch_width, 2);
+        assert_eq!(
+            error(
+                "writethrough = false",
+                "writethrough = false\nfetch_width = 0"
+            ),
+            "Key \"fetch_width\" must be at least 1"
+        );
+    }
+
+    #[test]
+    fn pages() {
+        let config = parse("writethrough = false", "writethrough = false\npages = 64").unwrap();
+        let reparsed = SimulationConfiguration::from_toml(&config.to_toml()).unwrap();
+
+        assert_eq!(parse("", "").unwrap().pages, PAGES);
+        assert_eq!(config.pages, 64);
+        assert_eq!(reparsed.pages, 64);
+        assert_eq!(
+            error("writethrough = false", "writethrough = false\npages = 0"),
+            "Key \"pages\" must be at least 1"
+        );
+        assert_eq!(
+            error(
+                "writethrough = false",
+                "writethrough = false\npages = 65537"
+            ),
+            "Key \"pages\" must be at most 65536, which covers every address"
+        );
+    }
+
+    #[test]
+    fn memory_seed() {
+        let config = parse(
+            "writethrough = false",
+            "writethrough = false\nmemory_seed = 1234",
+        )
+        .unwrap();
+        let reparsed = SimulationConfiguration::from_toml(&config.to_toml()).unwrap();
+
+        assert_eq!(parse("", "").unwrap().memory_seed, None);
+        assert_eq!(config.memory_seed, Some(1234));
+        assert_eq!(reparsed.memory_seed, Some(1234));
+        assert_eq!(config.memory().fill_seed(), Some(1234));
+        assert_eq!(
+            error(
+                "writethrough = false",
+                "writethrough = false\nmemory_seed = -1"
+            ),
+            "Key \"memory_seed\" must not be negative"
+        );
+    }
+
+    #[test]
+    fn fetch_queue_depth() {
+        let config = parse(
+            "writethrough = false",
+            "writethrough = false\nfetch_queue_depth = 4",
+        )
+        .unwrap();
+        let reparsed = SimulationConfiguration::from_toml(&config.to_toml()).unwrap();
+
+        assert_eq!(config.fetch_queue_depth, 4);
+        assert_eq!(reparsed.fetch_queue_depth, 4);
+    }
+
+    #[test]
+    fn round_trip() {
+        let config = parse("", "").unwrap();
+        let reparsed = SimulationConfiguration::from_toml(&config.to_toml()).unwrap();
+
+        assert_eq!(reparsed.to_toml(), config.to_toml());
+    }
+
+    #[test]
+    fn presets_round_trip() {
+        for preset in PRESETS {
+            let config = (preset.build)();
+            let reparsed = SimulationConfiguration::from_toml(&config.to_toml())
+                .unwrap_or_else(|e| panic!("Preset \"{}\" is invalid: {e}", preset.name));
+
+            assert_eq!(reparsed.to_toml(), config.to_toml(), "{}", preset.name);
+        }
+    }
+
+    #[test]
+    fn unknown_keys() {
+        assert_eq!(
+            error("miss_penalty", "mis_penalty"),
+            "Unrecognized key: \"mis_penalty\""
+        );
+        assert_eq!(
+            error("multiply", "multipy"),
+            "Unrecognized key: \"latencies.multipy\""
+        );
+        assert_eq!(error("ways", "way"), "Unrecognized key: \"cache.data.way\"");
+        assert_eq!(
+            error("[cache.instruction]", "[cache.instructions]"),
+            "Key required: \"cache.instruction\""
+        );
+    }
+
+    #[test]
+    fn legacy_caches() {
+        // Caches other than the data and instruction caches are kept, but unused
+        let config = parse(
+            "[cache.instruction]",
+            "[cache.unified]\nmode = \"disabled\"\n[cache.instruction]",
+        )
+        .unwrap();
+        assert!(matches!(
+            config.cache["unified"],
+            CacheConfiguration::Disabled
+        ));
+
+        // Lines narrower than a word, and caches with no ways, are widened
+        let config = parse("offset_bits = 2\nways = 2", "offset_bits = 0\nways = 0").unwrap();
+        assert!(matches!(
+            config.cache["data"],
+            CacheConfiguration::Associative {
+                offset_bits: 2,
+                ways: 1,
+                ..
+            }
+        ));
+        config.into_boxed_pipeline();
+    }
+
+    #[test]
+    fn legacy_simulation_table() {
+        let config = parse(
+            "[cache.data]",
+            "[simulation]\nbinary = \"matrix.bin\"\n[cache.data]",
+        )
+        .unwrap();
+
+        assert_eq!(config.to_toml(), parse("", "").unwrap().to_toml());
+    }
+
+    #[test]
+    fn missing_keys() {
+        assert_eq!(
+            error("volatile_penalty = 20", ""),
+            "Key required: \"volatile_penalty\""
+        );
+        assert_eq!(
+            error("set_bits = 2", ""),
+            "Key required: \"cache.data.set_bits\""
+        );
+        assert_eq!(
+            error("[cache.instruction]\nmode = \"disabled\"", ""),
+            "Key required: \"cache.instruction\""
+        );
+    }
+
+    #[test]
+    fn wrong_types() {
+        assert_eq!(
+            error("miss_penalty = 100", "miss_penalty = \"100\""),
+            "Key \"miss_penalty\" must be an integer, found string"
+        );
+        assert_eq!(
+            error("writethrough = false", "writethrough = 0"),
+            "Key \"writethrough\" must be a boolean, found integer"
+        );
+        assert_eq!(
+            error("pipelining = \"enabled\"", "pipelining = 1"),
+            "Key \"pipelining\" must be either a string or a boolean, found integer"
+        );
+        assert_eq!(
+            error("mode = \"associative\"", "mode = true"),
+            "Key \"cache.data.mode\" must be a string, found boolean"
+        );
+        assert_eq!(
+            error("[latencies]\nmultiply = 3", "latencies = 3"),
+            "Key \"latencies\" must be a table, found integer"
+        );
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(
+            error("miss_penalty = 100", "miss_penalty = -1"),
+            "Key \"miss_penalty\" must not be negative"
+        );
+        assert_eq!(
+            error("multiply = 3", "multiply = 0"),
+            "Key \"latencies.multiply\" must be at least 1"
+        );
+        assert_eq!(
+            error("ways = 2", "ways = -1"),
+            "Key \"cache.data.ways\" must not be negative"
+        );
+        assert_eq!(
+            error("set_bits = 2", "set_bits = 31"),
+            "Keys \"cache.data.set_bits\" and \"cache.data.offset_bits\" must add up to at most 32"
+        );
+        assert_eq!(
+            error("mode = \"associative\"", "mode = \"direct\""),
+            "Key \"cache.data.mode\" must be \"disabled\" or \"associative\", found \"direct\""
+        );
+        assert_eq!(
+            error("pipelining = \"enabled\"", "pipelining = \"maybe\""),
+            "Key \"pipelining\" is invalid: maybe is not a recognized pipelining mode"
+        );
+        assert_eq!(
+            error(
+                "writethrough = false",
+                "writethrough = false\nvector_table = 0x100000000"
+            ),
+            "Key \"vector_table\" must be an address"
+        );
+        assert_eq!(
+            error(
+                "writethrough = false",
+                "writethrough = false\nregisters = 17"
+            ),
+            "Key \"registers\" must be at most 16"
+        );
+        assert_eq!(
+            error(
+                "writethrough = false",
+                "writethrough = false\nregisters = 0"
+            ),
+            "Key \"registers\" must be at least 1"
+        );
+        assert_eq!(
+            error(
+                "[cache.data]",
+                "[console]\nbase = 0x2FF00\nsize = 4\n[cache.data]"
+            ),
+            "Key \"console.size\" must be at least 8"
+        );
+        assert_eq!(
+            error("[cache.data]", "[console]\nbase = 0xFFFFFFFC\n[cache.data]"),
+            "Keys \"console.base\" and \"console.size\" must keep the console within the address space"
+        );
+    }
+
+    #[test]
+    fn asymmetric_caches() {
+        // A direct-mapped instruction cache of 16-byte lines and a 4-way data cache of
+        // 32-byte lines
+        let source = VALID
+            .replacen("offset_bits = 2\nways = 2", "offset_bits = 5\nways = 4", 1)
+            .replacen(
+                "mode = \"disabled\"",
+                "mode = \"associative\"\nset_bits = 3\noffset_bits = 4\nways = 1",
+                1,
+            );
+        let config = SimulationConfiguration::from_toml(&toml::from_str(&source).unwrap()).unwrap();
+        let mut pipeline = config.into_boxed_pipeline();
+
+        // Both lines share a set in each cache, which only the data cache has room for
+        pipeline
+            .memory_module_mut()
+            .warm(&[0x0000_0000, 0x0000_0080]);
+
+        let caches = pipeline.memory_module().caches();
+        let [(_, instruction), (_, data)] = caches.as_slice() else {
+            panic!("Expected an instruction and a data cache");
+        };
+
+        assert_eq!(instruction.line_len(), 16);
+        assert_eq!(instruction.get_lines().len(), 8);
+        assert!(instruction.check_address(0x0000_0000).is_miss());
+        assert!(instruction.check_address(0x0000_0080).is_hit());
+
+        assert_eq!(data.line_len(), 32);
+        assert_eq!(data.get_lines().len(), 16);
+        assert!(data.check_address(0x0000_0000).is_hit());
+        assert!(data.check_address(0x0000_0080).is_hit());
+    }
+
+    #[test]
+    fn uncached_instructions_never_hit() {
+        /// Reads the word at `0x40` as an instruction or data, waiting out any misses
+        fn read(module: &mut dyn MemoryModule, instruction: bool) -> Word {
+            loop {
+                let result = if instruction {
+                    module.read_instruction(0x0000_0040)
+                } else {
+                    module.read_word(0x0000_0040)
+                };
+
+                match result {
+                    Ok(value) => break value,
+                    Err(libmem::module::Status::Busy(clocks)) => module.clock(clocks),
+                    Err(status) => panic!("Unexpected {status:?}"),
+                }
+            }
+        }
+
+        // Only the instruction cache is disabled
+        let mut pipeline = parse("", "").unwrap().into_boxed_pipeline();
+        let module = pipeline.memory_module_mut();
+        module.memory_mut().write_word(0x0000_0040, 0xDEAD_BEEF);
+
+        assert_eq!(read(module, true), 0xDEAD_BEEF);
+        assert_eq!(read(module, true), 0xDEAD_BEEF);
+        assert_eq!((module.cache_hits(), module.total_misses()), (0, 0));
+
+        // The data cache still counts its own accesses
+        assert_eq!(read(module, false), 0xDEAD_BEEF);
+        assert_eq!(read(module, false), 0xDEAD_BEEF);
+        assert_eq!((module.cache_hits(), module.total_misses()), (1, 1));
+    }
+
+    #[test]
+    fn console() {
+        assert!(parse("", "").unwrap().console.is_none());
+
+        let config = parse("[cache.data]", "[console]\nbase = 0x2FF00\n[cache.data]").unwrap();
+        let console = config.console.unwrap();
+
+        assert_eq!((console.base, console.size), (0x2FF00, 8));
+        assert_eq!(
+            error("[cache.data]", "[console]\nsize = 8\n[cache.data]"),
+            "Key required: \"console.base\""
+        );
+    }
+}
@@ -1,5 +1,7 @@
-use clap::{Parser, ValueEnum};
-use std::fmt::Display;
+use clap::{Arg, CommandFactory, Parser, ValueEnum};
+use libseis::types::{SWord, Word};
+use serde_json::{json, Value};
+use std::{any::TypeId, fmt::Display, path::PathBuf};
 
 #[derive(Debug, ValueEnum, Clone)]
 pub enum Info {
@@ -105,6 +107,78 @@ impl Display for Register {
     }
 }
 
+/// How the value of a register is shown
+#[derive(Debug, Default, ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterFormat {
+    #[default]
+    Unsigned,
+    Signed,
+    #[value(alias = "hexadecimal")]
+    Hex,
+    Float,
+}
+
+impl RegisterFormat {
+    /// The format after this one, wrapping back to the first
+    pub fn next(self) -> Self {
+        match self {
+            Self::Unsigned => Self::Signed,
+            Self::Signed => Self::Hex,
+            Self::Hex => Self::Float,
+            Self::Float => Self::Unsigned,
+        }
+    }
+
+    /// Reads `value` in this format as JSON, where hexadecimal values are strings
+    pub fn to_json(self, value: Word) -> Value {
+        match self {
+            Self::Unsigned => value.into(),
+            Self::Signed => (value as SWord).into(),
+            Self::Hex => format!("{value:#010X}").into(),
+            Self::Float => f32::from_bits(value).into(),
+        }
+    }
+
+    /// Reads `value` in this format as text
+    pub fn to_text(self, value: Word) -> String {
+        match self {
+            Self::Unsigned => value.to_string(),
+            Self::Signed => (value as SWord).to_string(),
+            Self::Hex => format!("{value:#010X}"),
+            Self::Float => format!("{:?}", f32::from_bits(value)),
+        }
+    }
+}
+
+#[derive(Debug, ValueEnum, Clone, Copy)]
+pub enum Width {
+    Byte,
+    Short,
+    Word,
+}
+
+impl From<Width> for libmem::memory::Width {
+    fn from(width: Width) -> Self {
+        match width {
+            Width::Byte => libmem::memory::Width::Byte,
+            Width::Short => libmem::memory::Width::Short,
+            Width::Word => libmem::memory::Width::Word,
+        }
+    }
+}
+
+/// Parses a word written in decimal, or in hexadecimal with a `0x` prefix
+fn parse_word(input: &str) -> Result<Word, String> {
+    match input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        Some(hex) => Word::from_str_radix(hex, 16),
+        None => input.parse(),
+    }
+    .map_err(|e| e.to_string())
+}
+
 #[derive(Debug, Parser, Clone)]
 #[clap(
     multicall = true,
@@ -128,6 +202,11 @@ pub enum Command {
     DisassemblePage {
         page: usize,
     },
+    /// Disassembles the instructions on either side of the one at PC
+    #[command(alias = "around")]
+    DisassembleAround {
+        count: usize,
+    },
     Clock {
         count: usize,
     },
@@ -137,6 +216,9 @@ pub enum Command {
     Stop {},
     #[command(alias = "regs")]
     ShowRegs {
+        /// How the values are shown, which must come before the registers
+        #[arg(short, long, value_enum, default_value_t = RegisterFormat::Unsigned)]
+        format: RegisterFormat,
         #[arg(trailing_var_arg = true)]
         regs: Option<Vec<Register>>,
     },
@@ -144,8 +226,193 @@ pub enum Command {
     ShowCache {},
     #[command(aliases = ["pipeline", "pipe"])]
     ShowPipeline {},
+    #[command(alias = "find")]
+    Search {
+        #[arg(value_parser = parse_word)]
+        pattern: Word,
+        #[arg(value_enum, default_value_t = Width::Word)]
+        width: Width,
+        /// The first address to search
+        #[arg(long, value_parser = parse_word)]
+        start: Option<Word>,
+        /// The address to stop searching at, exclusive
+        #[arg(long, value_parser = parse_word)]
+        end: Option<Word>,
+    },
     #[command(aliases = ["stats", "stat"])]
     Statistics {},
+    /// Writes the state of the caches to a file as JSON
+    DumpCache {
+        file: PathBuf,
+    },
+    /// Writes the bytes of a page to a file as JSON
+    DumpMemory {
+        file: PathBuf,
+        page: usize,
+    },
+    /// Lists every command and its arguments as JSON
+    #[command(alias = "completions")]
+    Commands {},
     #[command(alias = "exit")]
     Terminate {},
 }
+
+/// The kind of value an argument takes, so an editor knows what to complete
+fn argument_type(arg: &Arg) -> &'static str {
+    let parser = arg.get_value_parser();
+
+    if !arg.get_possible_values().is_empty() {
+        "enum"
+    } else if [
+        TypeId::of::<usize>(),
+        TypeId::of::<u64>(),
+        TypeId::of::<Word>(),
+    ]
+    .iter()
+    .any(|id| parser.type_id() == *id)
+    {
+        "integer"
+    } else if parser.type_id() == TypeId::of::<PathBuf>() {
+        "path"
+    } else {
+        "string"
+    }
+}
+
+/// Describes every command and its arguments, derived from the definition of [`Command`]
+pub fn command_tree() -> Value {
+    Command::command()
+        .get_subcommands()
+        .map(|command| {
+            let arguments: Vec<Value> = command
+                .get_arguments()
+                .map(|arg| {
+                    json!({
+                        "name": arg.get_id().as_str(),
+                        "type": argument_type(arg),
+                        "long": arg.get_long().map(|long| format!("--{long}")),
+                        "required": arg.is_required_set(),
+                        "multiple": arg.get_num_args().is_some_and(|n| n.max_values() > 1),
+                        "values": arg
+                            .get_possible_values()
+                            .iter()
+                            .map(|value| value.get_name())
+                            .collect::<Vec<_>>(),
+                        "default": arg
+                            .get_default_values()
+                            .first()
+                            .map(|value| value.to_string_lossy()),
+                        "help": arg.get_help().map(|help| help.to_string()),
+                    })
+                })
+                .collect();
+
+            json!({
+                "name": command.get_name(),
+                "aliases": command.get_all_aliases().collect::<Vec<_>>(),
+                "help": command.get_about().map(|about| about.to_string()),
+                "arguments": arguments,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashSet, mem::discriminant};
+
+    /// The name of the command each variant is parsed from, which fails to compile when a
+    /// variant is added without being listed here
+    fn name(command: &Command) -> &'static str {
+        match command {
+            Command::Decode { .. } => "decode",
+            Command::Information { .. } => "information",
+            Command::ReadPage { .. } => "read-page",
+            Command::DisassemblePage { .. } => "disassemble-page",
+            Command::DisassembleAround { .. } => "disassemble-around",
+            Command::Clock { .. } => "clock",
+            Command::Run { .. } => "run",
+            Command::Stop {} => "stop",
+            Command::ShowRegs { .. } => "show-regs",
+            Command::ShowCache {} => "show-cache",
+            Command::ShowPipeline {} => "show-pipeline",
+            Command::Search { .. } => "search",
+            Command::Statistics {} => "statistics",
+            Command::DumpCache { .. } => "dump-cache",
+            Command::DumpMemory { .. } => "dump-memory",
+            Command::Commands {} => "commands",
+            Command::Terminate {} => "terminate",
+        }
+    }
+
+    #[test]
+    fn register_formats() {
+        // -1.5 as a float, with the sign bit set
+        let value = 0xBFC0_0000;
+
+        assert_eq!(
+            RegisterFormat::Unsigned.to_json(value),
+            json!(3217031168u32)
+        );
+        assert_eq!(RegisterFormat::Signed.to_json(value), json!(-1077936128));
+        assert_eq!(RegisterFormat::Hex.to_json(value), json!("0xBFC00000"));
+        assert_eq!(RegisterFormat::Float.to_json(value), json!(-1.5));
+
+        assert_eq!(RegisterFormat::Unsigned.to_text(value), "3217031168");
+        assert_eq!(RegisterFormat::Signed.to_text(value), "-1077936128");
+        assert_eq!(RegisterFormat::Hex.to_text(value), "0xBFC00000");
+        assert_eq!(RegisterFormat::Float.to_text(value), "-1.5");
+    }
+
+    #[test]
+    fn show_regs_format() {
+        let parse = |line: &[&str]| match Command::try_parse_from(line).unwrap() {
+            Command::ShowRegs { format, regs } => (format, regs.map(|r| r.len())),
+            command => panic!("Parsed {command:?}"),
+        };
+
+        assert_eq!(parse(&["regs"]), (RegisterFormat::Unsigned, None));
+        assert_eq!(
+            parse(&["regs", "--format", "signed", "v0", "v1"]),
+            (RegisterFormat::Signed, Some(2))
+        );
+        assert_eq!(
+            parse(&["regs", "-f", "float"]),
+            (RegisterFormat::Float, None)
+        );
+    }
+
+    #[test]
+    fn command_tree_lists_every_command() {
+        let tree = command_tree();
+        let commands = tree.as_array().unwrap();
+        let mut variants = HashSet::new();
+
+        for command in commands {
+            let mut line = vec![command["name"].as_str().unwrap().to_owned()];
+
+            // Fill in every required argument with a value of the type it was listed with
+            for arg in command["arguments"].as_array().unwrap() {
+                if !arg["required"].as_bool().unwrap() {
+                    continue;
+                }
+
+                line.push(match arg["type"].as_str().unwrap() {
+                    "integer" => "1".to_owned(),
+                    "path" => "out.json".to_owned(),
+                    "enum" => arg["values"][0].as_str().unwrap().to_owned(),
+                    _ => "value".to_owned(),
+                });
+            }
+
+            let parsed = Command::try_parse_from(&line)
+                .unwrap_or_else(|e| panic!("Could not parse {line:?}: {e}"));
+
+            assert_eq!(name(&parsed), line[0]);
+            variants.insert(discriminant(&parsed));
+        }
+
+        assert_eq!(variants.len(), commands.len());
+    }
+}
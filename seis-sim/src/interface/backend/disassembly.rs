@@ -1,3 +1,9 @@
+use libmem::memory::Memory;
+use libseis::{
+    instruction_set::{Decode, Instruction},
+    pages::PAGE_SIZE,
+    types::Word,
+};
 use serde::Serialize;
 
 #[derive(Debug, Serialize)]
@@ -5,4 +11,103 @@ pub struct DisassemblyRow {
     pub address: String,
     pub bytes: [String; 4],
     pub instruction: String,
+    /// Whether this is the instruction at `PC`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub current: bool,
+}
+
+impl DisassemblyRow {
+    /// Decodes the instruction made up of `bytes`, found at `address`
+    pub fn new(address: Word, bytes: [u8; 4], current: bool) -> Self {
+        Self {
+            address: format!("{address:#010X}"),
+            bytes: bytes.map(|b| format!("{b:02X}")),
+            instruction: Instruction::decode(Word::from_be_bytes(bytes))
+                .unwrap_or_default()
+                .to_string(),
+            current,
+        }
+    }
+
+    /// Disassembles `count` instructions on either side of the one at `pc`, stopping early
+    /// at either end of the allocated memory around it
+    ///
+    /// Nothing is disassembled if the page holding `pc` was never allocated.
+    pub fn around(memory: &Memory, pc: Word, count: usize) -> Vec<Self> {
+        let allocated = |page: usize| page < memory.page_count() && memory.get_page(page).is_some();
+
+        let pc = pc & !3;
+        let page = pc as usize / PAGE_SIZE;
+        if !allocated(page) {
+            return vec![];
+        }
+
+        let low = (0..page)
+            .rev()
+            .take_while(|&p| allocated(p))
+            .last()
+            .unwrap_or(page);
+        let high = (page + 1..)
+            .take_while(|&p| allocated(p))
+            .last()
+            .unwrap_or(page);
+
+        let span = (count as Word).saturating_mul(4);
+        let first = pc.saturating_sub(span).max((low * PAGE_SIZE) as Word);
+        let last = pc
+            .saturating_add(span)
+            .min(((high + 1) * PAGE_SIZE - 4) as Word);
+
+        (first..=last)
+            .step_by(4)
+            .map(|address| {
+                let mut bytes = [0; 4];
+                memory.read_words_to(address, &mut bytes);
+                Self::new(address, bytes, address == pc)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses(rows: &[DisassemblyRow]) -> Vec<&str> {
+        rows.iter().map(|row| row.address.as_str()).collect()
+    }
+
+    #[test]
+    fn around_stops_at_allocated_memory() {
+        let mut memory = Memory::new(4);
+        memory.write_word(0x0001_0000, 0);
+        memory.write_word(0x0002_0000, 0);
+
+        let rows = DisassemblyRow::around(&memory, 0x0001_0004, 3);
+        assert_eq!(
+            addresses(&rows),
+            [
+                "0x00010000",
+                "0x00010004",
+                "0x00010008",
+                "0x0001000C",
+                "0x00010010"
+            ]
+        );
+        assert!(rows[1].current);
+
+        // Neighbouring pages which are both allocated are disassembled across
+        let rows = DisassemblyRow::around(&memory, 0x0001_FFFC, 1);
+        assert_eq!(addresses(&rows), ["0x0001FFF8", "0x0001FFFC", "0x00020000"]);
+
+        let rows = DisassemblyRow::around(&memory, 0x0002_FFFC, 2);
+        assert_eq!(addresses(&rows), ["0x0002FFF4", "0x0002FFF8", "0x0002FFFC"]);
+
+        // The last page of memory
+        memory.write_word(0x0003_FFFC, 0);
+        let rows = DisassemblyRow::around(&memory, 0x0003_FFFC, 1);
+        assert_eq!(addresses(&rows), ["0x0003FFF8", "0x0003FFFC"]);
+
+        assert!(DisassemblyRow::around(&memory, 0, 1).is_empty());
+    }
 }
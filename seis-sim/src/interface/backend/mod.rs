@@ -1,15 +1,13 @@
-mod cmd;
+pub(super) mod cmd;
 mod disassembly;
 mod input;
 
 use self::{
-    cmd::{Command, Info, Register},
+    cmd::{Command, Info, Register, RegisterFormat},
     input::InputHandler,
 };
 use super::Interface;
-use crate::{
-    config::SimulationConfiguration, interface::backend::disassembly::DisassemblyRow, PAGES,
-};
+use crate::{config::SimulationConfiguration, interface::backend::disassembly::DisassemblyRow};
 use clap::Parser;
 use libpipe::{ClockResult, Pipeline};
 use libseis::{
@@ -17,9 +15,12 @@ use libseis::{
     pages::PAGE_SIZE,
     types::Word,
 };
+use serde::Serialize;
 use serde_json as json;
 use std::{
     error::Error,
+    fs::File,
+    path::Path,
     time::{Duration, Instant},
 };
 
@@ -98,6 +99,10 @@ impl BackendState {
                 self.show_disassembled_page(page)?;
                 Ok(true)
             }
+            DisassembleAround { count } => {
+                self.show_disassembly_around(count)?;
+                Ok(true)
+            }
             Clock { mut count } => {
                 while count > 0 && !self.finished {
                     let min = self.clocks_required.min(count);
@@ -202,8 +207,8 @@ impl BackendState {
                 self.show_page(page)?;
                 Ok(true)
             }
-            ShowRegs { regs } => {
-                self.show_registers(regs)?;
+            ShowRegs { format, regs } => {
+                self.show_registers(format, regs)?;
                 Ok(true)
             }
             ShowCache {} => {
@@ -218,19 +223,61 @@ impl BackendState {
 
                 Ok(true)
             }
+            Search {
+                pattern,
+                width,
+                start,
+                end,
+            } => {
+                let matches = self.pipeline.memory_module().memory().search(
+                    pattern,
+                    width.into(),
+                    start.unwrap_or(0)..end.unwrap_or(Word::MAX),
+                );
+
+                println!("{}", json::to_string(&matches)?);
+                Ok(true)
+            }
             Statistics {} => {
                 self.statistics()?;
                 Ok(true)
             }
+            DumpCache { file } => {
+                let caches = self.pipeline.memory_module().cache_state();
+                Self::report_dump(&file, Self::dump(&file, &caches))?;
+                Ok(true)
+            }
+            DumpMemory { file, page } => {
+                let result = if page < self.config.pages {
+                    let bytes = self
+                        .pipeline
+                        .memory_module()
+                        .memory()
+                        .get_page(page)
+                        .map(|p| p.to_vec());
+                    Self::dump(&file, &bytes)
+                } else {
+                    Err(format!("Page {page} is out of range").into())
+                };
+
+                Self::report_dump(&file, result)?;
+                Ok(true)
+            }
+            Commands {} => {
+                println!("{}", json::to_string(&cmd::command_tree())?);
+                Ok(true)
+            }
             Terminate {} => Ok(false),
             Decode { value } => {
-                let data: json::Map<String, json::Value> = [(
-                    "decoded".to_string(),
-                    Instruction::decode(value)
-                        .map(|i| i.to_string())
-                        .ok()
-                        .into(),
-                )]
+                let decoded = Instruction::decode(value).ok();
+
+                let data: json::Map<String, json::Value> = [
+                    ("decoded".to_string(), decoded.map(|i| i.to_string()).into()),
+                    (
+                        "operands".to_string(),
+                        json::to_value(decoded.map(Instruction::operands))?,
+                    ),
+                ]
                 .into_iter()
                 .collect();
 
@@ -241,6 +288,25 @@ impl BackendState {
         }
     }
 
+    /// Writes a value to a file as pretty JSON
+    fn dump(file: &Path, value: &impl Serialize) -> Result<(), Box<dyn Error>> {
+        value.serialize(&mut json::Serializer::pretty(File::create(file)?))?;
+
+        Ok(())
+    }
+
+    /// Prints the outcome of a dump, reporting any error in the response instead of failing
+    fn report_dump(file: &Path, result: Result<(), Box<dyn Error>>) -> Result<(), Box<dyn Error>> {
+        let response = match result {
+            Ok(()) => json::json!({ "file": file.display().to_string() }),
+            Err(e) => json::json!({ "error": e.to_string() }),
+        };
+
+        println!("{}", json::to_string(&response)?);
+
+        Ok(())
+    }
+
     fn show_pipeline(&self) -> Result<(), Box<dyn Error>> {
         println!("{}", json::to_string(&self.pipeline.stages())?);
 
@@ -274,19 +340,8 @@ impl BackendState {
             .map(|p| {
                 p.chunks(4)
                     .enumerate()
-                    .map(|(i, b)| DisassemblyRow {
-                        address: format!("{:#010X}", i * 4),
-                        bytes: [
-                            format!("{:02X}", b[0]),
-                            format!("{:02X}", b[1]),
-                            format!("{:02X}", b[2]),
-                            format!("{:02X}", b[3]),
-                        ],
-                        instruction: Instruction::decode(Word::from_be_bytes([
-                            b[0], b[1], b[2], b[3],
-                        ]))
-                        .unwrap_or_default()
-                        .to_string(),
+                    .map(|(i, b)| {
+                        DisassemblyRow::new(i as Word * 4, [b[0], b[1], b[2], b[3]], false)
                     })
                     .collect::<Vec<_>>()
             })
@@ -297,40 +352,58 @@ impl BackendState {
         Ok(())
     }
 
-    fn show_registers(&self, regs: Option<Vec<Register>>) -> Result<(), Box<dyn Error>> {
+    /// Disassembles `count` instructions on either side of the one at `PC`, stopping early at
+    /// either end of allocated memory
+    fn show_disassembly_around(&self, count: usize) -> Result<(), Box<dyn Error>> {
+        let data = DisassemblyRow::around(
+            self.pipeline.memory_module().memory(),
+            self.pipeline.registers().pc,
+            count,
+        );
+
+        println!("{}", json::to_string(&data)?);
+
+        Ok(())
+    }
+
+    fn show_registers(
+        &self,
+        format: RegisterFormat,
+        regs: Option<Vec<Register>>,
+    ) -> Result<(), Box<dyn Error>> {
         let mut map = json::Map::new();
         let registers = self.pipeline.registers();
 
         if let Some(regs) = regs {
             for reg in regs.into_iter() {
-                map.insert(reg.to_string(), registers[reg.into()].into());
+                map.insert(reg.to_string(), format.to_json(registers[reg.into()]));
             }
         } else {
-            map.insert("v0".to_string(), registers.v[0].into());
-            map.insert("v1".to_string(), registers.v[1].into());
-            map.insert("v2".to_string(), registers.v[2].into());
-            map.insert("v3".to_string(), registers.v[3].into());
-            map.insert("v4".to_string(), registers.v[4].into());
-            map.insert("v5".to_string(), registers.v[5].into());
-            map.insert("v6".to_string(), registers.v[6].into());
-            map.insert("v7".to_string(), registers.v[7].into());
-            map.insert("v8".to_string(), registers.v[8].into());
-            map.insert("v9".to_string(), registers.v[9].into());
-            map.insert("va".to_string(), registers.v[10].into());
-            map.insert("vb".to_string(), registers.v[11].into());
-            map.insert("vc".to_string(), registers.v[12].into());
-            map.insert("vd".to_string(), registers.v[13].into());
-            map.insert("ve".to_string(), registers.v[14].into());
-            map.insert("vf".to_string(), registers.v[15].into());
-            map.insert("sp".to_string(), registers.sp.into());
-            map.insert("bp".to_string(), registers.bp.into());
-            map.insert("lp".to_string(), registers.lp.into());
-            map.insert("pc".to_string(), registers.pc.into());
-            map.insert("zf".to_string(), registers.zf.into());
-            map.insert("of".to_string(), registers.of.into());
-            map.insert("eps".to_string(), registers.eps.into());
-            map.insert("nan".to_string(), registers.nan.into());
-            map.insert("inf".to_string(), registers.inf.into());
+            map.insert("v0".to_string(), format.to_json(registers.v[0]));
+            map.insert("v1".to_string(), format.to_json(registers.v[1]));
+            map.insert("v2".to_string(), format.to_json(registers.v[2]));
+            map.insert("v3".to_string(), format.to_json(registers.v[3]));
+            map.insert("v4".to_string(), format.to_json(registers.v[4]));
+            map.insert("v5".to_string(), format.to_json(registers.v[5]));
+            map.insert("v6".to_string(), format.to_json(registers.v[6]));
+            map.insert("v7".to_string(), format.to_json(registers.v[7]));
+            map.insert("v8".to_string(), format.to_json(registers.v[8]));
+            map.insert("v9".to_string(), format.to_json(registers.v[9]));
+            map.insert("va".to_string(), format.to_json(registers.v[10]));
+            map.insert("vb".to_string(), format.to_json(registers.v[11]));
+            map.insert("vc".to_string(), format.to_json(registers.v[12]));
+            map.insert("vd".to_string(), format.to_json(registers.v[13]));
+            map.insert("ve".to_string(), format.to_json(registers.v[14]));
+            map.insert("vf".to_string(), format.to_json(registers.v[15]));
+            map.insert("sp".to_string(), format.to_json(registers.sp));
+            map.insert("bp".to_string(), format.to_json(registers.bp));
+            map.insert("lp".to_string(), format.to_json(registers.lp));
+            map.insert("pc".to_string(), format.to_json(registers.pc));
+            map.insert("zf".to_string(), format.to_json(registers.zf));
+            map.insert("of".to_string(), format.to_json(registers.of));
+            map.insert("eps".to_string(), format.to_json(registers.eps));
+            map.insert("nan".to_string(), format.to_json(registers.nan));
+            map.insert("inf".to_string(), format.to_json(registers.inf));
         }
 
         println!("{}", json::to_string(&map)?);
@@ -348,6 +421,24 @@ impl BackendState {
         map.insert("cold_misses".to_string(), mem.cold_misses().into());
         map.insert("conflict_misses".to_string(), mem.conflict_misses().into());
         map.insert("cache_hits".to_string(), mem.cache_hits().into());
+        map.insert(
+            "misaligned_accesses".to_string(),
+            mem.misaligned_accesses().into(),
+        );
+        map.insert("bank_conflicts".to_string(), mem.bank_conflicts().into());
+        map.insert(
+            "write_buffer_stalls".to_string(),
+            mem.write_buffer_stalls().into(),
+        );
+        map.insert("fault".to_string(), json::to_value(self.pipeline.fault())?);
+        map.insert(
+            "halt_reason".to_string(),
+            json::to_value(self.pipeline.halt_reason())?,
+        );
+        map.insert(
+            "stalls".to_string(),
+            json::to_value(self.pipeline.perf_counters())?,
+        );
 
         println!("{}", json::to_string(&map)?);
 
@@ -361,24 +452,22 @@ impl BackendState {
 
         match what {
             Pages => {
-                map.insert("page_count".to_string(), PAGES.into());
+                let allocated = self.pipeline.memory_module().memory().allocation_bitmap();
+
+                map.insert("page_count".to_string(), self.config.pages.into());
                 map.insert("page_size".to_string(), PAGE_SIZE.into());
                 map.insert(
                     "allocated_pages".to_string(),
-                    self.pipeline
-                        .memory_module()
-                        .memory()
-                        .allocated_pages()
-                        .count()
-                        .into(),
+                    allocated.iter().filter(|&&a| a).count().into(),
                 );
+                map.insert("allocation_bitmap".to_string(), allocated.into());
             }
             Cache => {
-                let caches = self.pipeline.memory_module().caches();
-                map.insert("cache_count".into(), caches.len().into());
+                let names = self.pipeline.memory_module().get_cache_names();
+                map.insert("cache_count".into(), names.len().into());
                 map.insert(
                     "cache_names".into(),
-                    caches.keys().map(ToString::to_string).collect(),
+                    names.into_iter().map(ToString::to_string).collect(),
                 );
             }
             Pipeline => {
@@ -392,9 +481,9 @@ impl BackendState {
                     "cache_configurations".into(),
                     self.pipeline
                         .memory_module()
-                        .caches()
-                        .keys()
-                        .map(|&k| {
+                        .get_cache_names()
+                        .into_iter()
+                        .map(|k| {
                             (
                                 k.to_string(),
                                 self.config.cache.get(k).unwrap().to_json().into(),
@@ -409,6 +498,50 @@ impl BackendState {
                     self.config.volatile_penalty.into(),
                 );
                 map.insert("writethrough".to_string(), self.config.writethrough.into());
+                map.insert(
+                    "extra_execute_stages".to_string(),
+                    self.config.extra_execute_stages.into(),
+                );
+                map.insert(
+                    "max_in_flight".to_string(),
+                    self.config.max_in_flight.into(),
+                );
+                map.insert("memory_ports".to_string(), self.config.memory_ports.into());
+                map.insert("fetch_width".to_string(), self.config.fetch_width.into());
+                map.insert(
+                    "fetch_queue_depth".to_string(),
+                    self.config.fetch_queue_depth.into(),
+                );
+                map.insert("banks".to_string(), self.config.banks.into());
+                map.insert(
+                    "write_buffer_depth".to_string(),
+                    self.config.write_buffer_depth.into(),
+                );
+                map.insert(
+                    "latencies".to_string(),
+                    json::json!({
+                        "multiply": self.config.latencies.multiply,
+                        "divide": self.config.latencies.divide,
+                        "float": self.config.latencies.float,
+                        "float_divide": self.config.latencies.float_divide,
+                    }),
+                );
+                map.insert("stack_checks".to_string(), self.config.stack_checks.into());
+                map.insert("write_checks".to_string(), self.config.write_checks.into());
+                map.insert(
+                    "alignment_checks".to_string(),
+                    self.config.alignment_checks.into(),
+                );
+                map.insert("smc_checks".to_string(), self.config.smc_checks.into());
+                map.insert("vector_table".to_string(), self.config.vector_table.into());
+                map.insert("registers".to_string(), self.config.registers.into());
+                map.insert(
+                    "console".to_string(),
+                    self.config
+                        .console
+                        .map(|console| json::json!({ "base": console.base, "size": console.size }))
+                        .into(),
+                );
             }
         }
 
@@ -1,8 +1,10 @@
 mod backend;
+mod repl;
 mod tui;
 
 pub use backend::Backend;
 use libpipe::Pipeline;
+pub use repl::Repl;
 use std::fmt::Debug;
 pub use tui::Tui;
 
@@ -0,0 +1,116 @@
+use super::Interface;
+use crate::config::SimulationConfiguration;
+use libasm::Input;
+use libpipe::{ClockResult, Pipeline};
+use libseis::{
+    instruction_set::{Decode, Instruction},
+    registers::get_name,
+    types::Word,
+};
+use std::{
+    error::Error,
+    io::{stdin, stdout, Write},
+};
+
+/// An interface which assembles each line typed into it and runs it as the next
+/// instruction, showing the registers it changed.
+///
+/// The pipeline should not be pipelined, so that every instruction retires before the
+/// next one is fetched.
+#[derive(Debug, Clone, Copy)]
+pub struct Repl;
+
+impl Interface for Repl {
+    type Ok = ();
+
+    type Error = Box<dyn Error>;
+
+    fn run(
+        self,
+        mut pipeline: Box<dyn Pipeline>,
+        _: SimulationConfiguration,
+    ) -> Result<Self::Ok, Self::Error> {
+        let mut lines = stdin().lines();
+
+        loop {
+            print!("{:#010X}> ", pipeline.registers().pc);
+            stdout().flush()?;
+
+            let Some(line) = lines.next() else {
+                println!();
+                return Ok(());
+            };
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let word = match assemble(&line) {
+                Ok(word) => word,
+                Err(e) => {
+                    eprintln!("{e}");
+                    continue;
+                }
+            };
+
+            let pc = pipeline.registers().pc;
+            pipeline
+                .memory_module_mut()
+                .memory_mut()
+                .write_word(pc, word);
+
+            if let Ok(instruction) = Instruction::decode(word) {
+                println!("{pc:#010X}: {instruction}");
+            }
+
+            let before = *pipeline.registers();
+            let halted = step(pipeline.as_mut());
+
+            for (register, old, new) in before.diff(pipeline.registers()) {
+                println!(
+                    "  {:>4}: {old:#010X} -> {new:#010X}",
+                    get_name(register).unwrap_or("<unknown>")
+                );
+            }
+
+            if halted {
+                if let Some(reason) = pipeline.halt_reason() {
+                    println!("Halt reason: {reason}");
+                }
+
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Assembles a line holding a single instruction into its encoding
+fn assemble(line: &str) -> Result<Word, Box<dyn Error>> {
+    let image = libasm::compile([Input {
+        data: line,
+        path: "<repl>",
+    }])?;
+
+    match image.as_slice() {
+        [a, b, c, d] => Ok(Word::from_be_bytes([*a, *b, *c, *d])),
+        _ => Err("Expected exactly one instruction".into()),
+    }
+}
+
+/// Clocks the pipeline until it retires an instruction, returning true if it ran dry
+/// instead
+fn step(pipeline: &mut dyn Pipeline) -> bool {
+    let retired = pipeline.retired_instructions();
+    let mut clocks_required = 1;
+
+    while pipeline.retired_instructions() == retired {
+        match pipeline.clock(clocks_required) {
+            ClockResult::Stall(clocks) => clocks_required = clocks,
+            ClockResult::Flow => clocks_required = 1,
+            ClockResult::Dry => return true,
+        }
+    }
+
+    false
+}
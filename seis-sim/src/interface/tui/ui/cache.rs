@@ -1,4 +1,21 @@
 use super::*;
+use libmem::cache::LineData;
+
+/// The width of a way's column in the cache grid
+const WAY_COLUMN_WIDTH: u16 = 12;
+/// The number of bytes in each row of the line detail pane
+const DETAIL_BYTES_PER_ROW: usize = 8;
+
+/// Moves `offset` so that `focus` lies within the `visible` entries starting at it
+fn keep_visible(offset: usize, focus: usize, visible: usize) -> usize {
+    if focus < offset {
+        focus
+    } else if focus >= offset + visible {
+        focus + 1 - visible
+    } else {
+        offset
+    }
+}
 
 impl<'a> Runtime<'a> {
     pub fn draw_cache_view(&mut self, chunks: &Rc<[Rect]>, buf: &mut Buffer) {
@@ -16,12 +33,11 @@ impl<'a> Runtime<'a> {
         let cache_lines = self
             .pipeline
             .memory_module()
-            .caches()
-            .get(cache_name.as_str())
+            .get_cache(cache_name)
             .unwrap()
             .get_lines();
 
-        let cache_ways = self
+        let ways = self
             .config
             .cache
             .get(cache_name)
@@ -31,74 +47,154 @@ impl<'a> Runtime<'a> {
             })
             .unwrap_or(0);
 
-        // TODO: handle scroll
-        self.cache_view.scroll = self.cache_view.scroll.clamp(
-            0,
-            cache_lines
-                .len()
-                .saturating_sub((splits[1].height) as usize),
+        if cache_lines.is_empty() || ways == 0 {
+            Paragraph::new("This cache is disabled")
+                .centered()
+                .render(splits[1], buf);
+            self.draw_cache_controls(chunks[2], buf, None);
+            return;
+        }
+
+        let sets = cache_lines.len() / ways;
+
+        // Place each line in the grid by its set and way
+        let mut grid = vec![None; sets * ways];
+        for line in cache_lines.iter().flatten() {
+            grid[line.set_index * ways + line.way_index] = Some(line);
+        }
+
+        let panes = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Length(DETAIL_BYTES_PER_ROW as u16 * 3 + 10),
+        ])
+        .split(splits[1]);
+
+        let view = &mut self.cache_view;
+        view.set = view.set.min(sets - 1);
+        view.way = view.way.min(ways - 1);
+
+        // One row is taken by the header, and one column by the set labels
+        let visible_sets = (panes[0].height as usize).saturating_sub(1).max(1);
+        let visible_ways = ((panes[0].width.saturating_sub(6) / WAY_COLUMN_WIDTH) as usize).max(1);
+        view.page_rows = visible_sets;
+        view.scroll = keep_visible(view.scroll, view.set, visible_sets).min(sets - 1);
+        view.way_scroll = keep_visible(view.way_scroll, view.way, visible_ways);
+
+        let way_range = view.way_scroll..(view.way_scroll + visible_ways).min(ways);
+
+        let mut columns = vec![Constraint::Length(5)];
+        columns.extend(
+            way_range
+                .clone()
+                .map(|_| Constraint::Length(WAY_COLUMN_WIDTH)),
         );
 
-        let data = cache_lines
-            .into_iter()
-            .skip(self.cache_view.scroll)
-            .take((splits[1].height) as usize)
-            .enumerate()
-            .map(|(i, line)| {
-                let row = if let Some(line) = line {
-                    Row::new(vec![
-                        format!("{}", i / cache_ways),
-                        format!("{}", i % cache_ways),
-                        format!("{}", line.dirty),
-                        format!("{:#010X}", line.base_address),
-                        format!("{:?}", line.data),
-                    ])
-                } else {
-                    Row::new(vec![
-                        format!("{}", i / cache_ways),
-                        format!("{}", i % cache_ways),
-                        format!(""),
-                        format!(""),
-                        format!("Invalid"),
-                    ])
+        let mut header = vec![Cell::from("Set")];
+        header.extend(way_range.clone().map(|w| Cell::from(format!("Way {w}"))));
+
+        let rows = (view.scroll..(view.scroll + visible_sets).min(sets)).map(|set| {
+            let mut cells = vec![Cell::from(set.to_string()).bold()];
+
+            cells.extend(way_range.clone().map(|way| {
+                let cell = match grid[set * ways + way] {
+                    Some(line) => Cell::from(format!(
+                        "{:#010X}{}",
+                        line.base_address,
+                        if line.dirty { "*" } else { "" }
+                    )),
+                    None => Cell::from("-").dim(),
                 };
 
-                if i % 2 == 0 {
-                    row.on_light_blue()
+                if (set, way) == (view.set, view.way) {
+                    cell.black().on_yellow()
                 } else {
-                    row.on_blue()
+                    cell
                 }
-            })
-            .collect::<Vec<_>>();
-
-        Table::new(
-            data,
-            vec![
-                Constraint::Max(3),
-                Constraint::Max(3),
-                Constraint::Max(5),
-                Constraint::Max(10),
-                Constraint::Fill(1),
-            ],
-        )
-        .header(
-            Row::new(vec!["Set", "Way", "Dirty", "Address", "Data"])
-                .on_blue()
-                .bold(),
-        )
-        .render(splits[1], buf);
+            }));
+
+            if set % 2 == 0 {
+                Row::new(cells).on_light_blue()
+            } else {
+                Row::new(cells).on_blue()
+            }
+        });
+
+        Table::new(rows, columns)
+            .header(Row::new(header).on_blue().bold())
+            .render(panes[0], buf);
+
+        let focused = grid[view.set * ways + view.way];
+        self.draw_line_detail(panes[1], buf, focused);
+
+        self.draw_cache_controls(
+            chunks[2],
+            buf,
+            Some(format!(
+                " set {}/{sets} | way {}/{ways} | page {}/{} ",
+                self.cache_view.set,
+                self.cache_view.way,
+                self.cache_view.set / visible_sets + 1,
+                sets.div_ceil(visible_sets),
+            )),
+        );
+    }
+
+    /// Shows every byte in the focused line
+    fn draw_line_detail(&self, area: Rect, buf: &mut Buffer, line: Option<&LineData>) {
+        let block = Block::new()
+            .borders(Borders::LEFT)
+            .padding(Padding::new(1, 1, 0, 0));
+
+        let Some(line) = line else {
+            Paragraph::new("Invalid line".dim())
+                .block(block)
+                .render(area, buf);
+            return;
+        };
+
+        let mut lines = vec![
+            Line::from(vec![
+                "Address: ".into(),
+                format!("{:#010X}", line.base_address).blue().bold(),
+            ]),
+            Line::from(vec!["Dirty: ".into(), line.dirty.to_string().blue().bold()]),
+            Line::default(),
+        ];
+
+        lines.extend(
+            line.data
+                .chunks(DETAIL_BYTES_PER_ROW)
+                .enumerate()
+                .map(|(i, bytes)| {
+                    let mut spans = vec![format!("+{:<4X}", i * DETAIL_BYTES_PER_ROW).bold()];
+                    spans.extend(bytes.iter().map(|b| format!("{b:02X} ").into()));
+                    Line::from(spans)
+                }),
+        );
+
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+
+    fn draw_cache_controls(&self, area: Rect, buf: &mut Buffer, position: Option<String>) {
+        let mut block = Block::new()
+            .padding(Padding::new(1, 1, 0, 0))
+            .borders(Borders::TOP);
+
+        if let Some(position) = position {
+            block = block.title(Title::from(position).alignment(Alignment::Right));
+        }
 
         Paragraph::new(Line::from(vec![
             "↕".blue().bold(),
-            " to scroll | ".into(),
+            " to move between sets | ".into(),
             "↔".blue().bold(),
+            " to move between ways | ".into(),
+            "PgUp/PgDn".blue().bold(),
+            " to page | ".into(),
+            "[ ]".blue().bold(),
             " to switch caches".into(),
         ]))
-        .block(
-            Block::new()
-                .padding(Padding::new(1, 1, 0, 0))
-                .borders(Borders::TOP),
-        )
-        .render(chunks[2], buf);
+        .block(block)
+        .render(area, buf);
     }
 }
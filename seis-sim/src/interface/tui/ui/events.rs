@@ -11,11 +11,7 @@ impl<'a> Runtime<'a> {
     }
 
     fn running_event_handler(&mut self) -> Result<bool, Box<dyn Error>> {
-        if event::poll(if self.running.slow_mode {
-            Duration::from_millis(100)
-        } else {
-            Duration::from_nanos(0)
-        })? {
+        if event::poll(self.running.delay.unwrap_or_default())? {
             if matches!(
                 event::read()?,
                 Event::Key(KeyEvent {
@@ -31,12 +27,9 @@ impl<'a> Runtime<'a> {
         }
 
         if self.clocks_required != 0 {
-            self.clocks += self.clocks_required;
-            self.clocks_required = match self.pipeline.clock(self.clocks_required) {
-                libpipe::ClockResult::Stall(clocks) => clocks,
-                libpipe::ClockResult::Flow => 1,
-                libpipe::ClockResult::Dry => self.pipeline.memory_module().wait_time(),
-            };
+            let pc = self.pipeline.registers().pc;
+            self.clock_pipeline(self.clocks_required);
+            self.check_breakpoints(pc);
         } else {
             self.running.enabled = false;
         }
@@ -44,12 +37,52 @@ impl<'a> Runtime<'a> {
         Ok(true)
     }
 
+    /// Stops a run if the program counter has moved onto a breakpoint since it was `pc`
+    fn check_breakpoints(&mut self, pc: Word) {
+        let new_pc = self.pipeline.registers().pc;
+
+        if new_pc != pc && self.breakpoints.contains(&new_pc) {
+            self.running.enabled = false;
+            self.palette.status = Some(Line::from(
+                format!("Stopped at the breakpoint at {new_pc:#010X}").bold(),
+            ));
+        }
+    }
+
     fn handle_event(&mut self) -> Result<bool, Box<dyn Error>> {
-        match event::read()? {
+        let event = event::read()?;
+
+        if matches!(event, Event::Key(_)) {
+            self.memory_view.status = None;
+            self.palette.status = None;
+        }
+
+        match event {
             Event::Key(key) => match key.kind {
                 KeyEventKind::Press => match key.code {
+                    code if self.palette.input.is_some() => Ok(self.handle_palette_input(code)),
+
+                    code if self.memory_view.goto.is_some() => {
+                        self.handle_goto_input(code);
+                        Ok(true)
+                    }
+
+                    code if self.watch_view.input.is_some() => {
+                        self.handle_watch_input(code);
+                        Ok(true)
+                    }
+
                     KeyCode::Char('q') => Ok(false),
 
+                    KeyCode::Char(':') => {
+                        self.palette.input = Some(String::new());
+                        Ok(true)
+                    }
+                    KeyCode::Char('/') => {
+                        self.palette.input = Some("search ".into());
+                        Ok(true)
+                    }
+
                     KeyCode::Char('1') => {
                         self.view = View::Registers;
                         Ok(true)
@@ -66,6 +99,10 @@ impl<'a> Runtime<'a> {
                         self.view = View::PipelineStages;
                         Ok(true)
                     }
+                    KeyCode::Char('5') => {
+                        self.view = View::Watch;
+                        Ok(true)
+                    }
 
                     KeyCode::Tab => {
                         if key.modifiers.contains(KeyModifiers::SHIFT) {
@@ -85,7 +122,9 @@ impl<'a> Runtime<'a> {
                                     PAGE_SIZE / if self.memory_view.disassembly { 4 } else { 8 },
                                 );
                         } else if matches!(self.view, View::Cache) {
-                            self.cache_view.scroll = self.cache_view.scroll.saturating_sub(1);
+                            self.cache_view.set = self.cache_view.set.saturating_sub(1);
+                        } else if matches!(self.view, View::Watch) {
+                            self.watch_view.selected = self.watch_view.selected.saturating_sub(1);
                         }
 
                         Ok(true)
@@ -98,7 +137,9 @@ impl<'a> Runtime<'a> {
                                     PAGE_SIZE / if self.memory_view.disassembly { 4 } else { 8 },
                                 );
                         } else if matches!(self.view, View::Cache) {
-                            self.cache_view.scroll = self.cache_view.scroll.saturating_add(1);
+                            self.cache_view.set = self.cache_view.set.saturating_add(1);
+                        } else if matches!(self.view, View::Watch) {
+                            self.watch_view.selected = self.watch_view.selected.saturating_add(1);
                         }
 
                         Ok(true)
@@ -111,6 +152,11 @@ impl<'a> Runtime<'a> {
                                     0,
                                     PAGE_SIZE / if self.memory_view.disassembly { 4 } else { 8 },
                                 );
+                        } else if matches!(self.view, View::Cache) {
+                            self.cache_view.set = self
+                                .cache_view
+                                .set
+                                .saturating_sub(self.cache_view.page_rows);
                         }
 
                         Ok(true)
@@ -122,6 +168,11 @@ impl<'a> Runtime<'a> {
                                     0,
                                     PAGE_SIZE / if self.memory_view.disassembly { 4 } else { 8 },
                                 );
+                        } else if matches!(self.view, View::Cache) {
+                            self.cache_view.set = self
+                                .cache_view
+                                .set
+                                .saturating_add(self.cache_view.page_rows);
                         }
 
                         Ok(true)
@@ -130,10 +181,13 @@ impl<'a> Runtime<'a> {
                     KeyCode::Left => {
                         if matches!(self.view, View::Memory) {
                             self.memory_view.page_offset = 0;
-                            self.memory_view.page =
-                                self.memory_view.page.saturating_sub(1).clamp(0, PAGES - 1);
+                            self.memory_view.page = self
+                                .memory_view
+                                .page
+                                .saturating_sub(1)
+                                .clamp(0, self.config.pages - 1);
                         } else if matches!(self.view, View::Cache) {
-                            self.cache_view.index = self.cache_view.index.saturating_sub(1);
+                            self.cache_view.way = self.cache_view.way.saturating_sub(1);
                         } else if matches!(self.view, View::PipelineStages) {
                             self.pipeline_view.stage = self.pipeline_view.stage.saturating_sub(1);
                         }
@@ -143,14 +197,13 @@ impl<'a> Runtime<'a> {
                     KeyCode::Right => {
                         if matches!(self.view, View::Memory) {
                             self.memory_view.page_offset = 0;
-                            self.memory_view.page =
-                                self.memory_view.page.saturating_add(1).clamp(0, PAGES - 1);
+                            self.memory_view.page = self
+                                .memory_view
+                                .page
+                                .saturating_add(1)
+                                .clamp(0, self.config.pages - 1);
                         } else if matches!(self.view, View::Cache) {
-                            self.cache_view.index = self.cache_view.index.saturating_add(1);
-
-                            if self.cache_view.index >= self.cache_view.count {
-                                self.cache_view.index = self.cache_view.count - 1;
-                            }
+                            self.cache_view.way = self.cache_view.way.saturating_add(1);
                         } else if matches!(self.view, View::PipelineStages) {
                             self.pipeline_view.stage = (self.pipeline_view.stage + 1)
                                 .clamp(0, 5 - PIPELINE_STAGE_VIEW_COUNT)
@@ -159,6 +212,22 @@ impl<'a> Runtime<'a> {
                         Ok(true)
                     }
 
+                    KeyCode::Char('[') => {
+                        if matches!(self.view, View::Cache) {
+                            self.cache_view.index = self.cache_view.index.saturating_sub(1);
+                        }
+
+                        Ok(true)
+                    }
+                    KeyCode::Char(']') => {
+                        if matches!(self.view, View::Cache) {
+                            self.cache_view.index =
+                                (self.cache_view.index + 1).min(self.cache_view.count - 1);
+                        }
+
+                        Ok(true)
+                    }
+
                     KeyCode::Char('d') => {
                         if matches!(self.view, View::Memory) {
                             self.memory_view.disassembly = !self.memory_view.disassembly;
@@ -199,30 +268,84 @@ impl<'a> Runtime<'a> {
                         Ok(true)
                     }
 
+                    KeyCode::Char('g') => {
+                        if matches!(self.view, View::Memory) {
+                            self.memory_view.goto = Some(String::new());
+                        }
+
+                        Ok(true)
+                    }
+
+                    KeyCode::Char('p') => {
+                        if matches!(self.view, View::Memory) {
+                            self.memory_view.follow_pc = !self.memory_view.follow_pc;
+
+                            if self.memory_view.follow_pc {
+                                self.memory_view.disassembly = true;
+                            }
+                        }
+
+                        Ok(true)
+                    }
+
+                    KeyCode::Char('n') => {
+                        if matches!(self.view, View::Memory) {
+                            self.show_match(self.memory_view.match_index + 1);
+                        }
+
+                        Ok(true)
+                    }
+                    KeyCode::Char('N') => {
+                        if matches!(self.view, View::Memory) {
+                            let count = self.memory_view.matches.len().max(1);
+                            self.show_match(self.memory_view.match_index + count - 1);
+                        }
+
+                        Ok(true)
+                    }
+
+                    KeyCode::Char('h') => {
+                        if matches!(self.view, View::Memory) {
+                            self.memory_view.cache_overlay = !self.memory_view.cache_overlay;
+                        }
+
+                        Ok(true)
+                    }
+
+                    KeyCode::Char('v') => {
+                        if matches!(self.view, View::Registers) {
+                            self.register_format = self.register_format.next();
+                        }
+
+                        Ok(true)
+                    }
+
+                    KeyCode::Char('a') => {
+                        if matches!(self.view, View::Watch) {
+                            self.watch_view.input = Some(String::new());
+                        }
+
+                        Ok(true)
+                    }
+
+                    KeyCode::Char('x') | KeyCode::Delete => {
+                        if matches!(self.view, View::Watch) {
+                            self.remove_watch();
+                        }
+
+                        Ok(true)
+                    }
+
                     KeyCode::Char('c') => {
                         if self.clocks_required != 0 {
-                            self.clocks_required = match self.pipeline.clock(1) {
-                                libpipe::ClockResult::Stall(clocks) => clocks,
-                                libpipe::ClockResult::Flow => 1,
-                                libpipe::ClockResult::Dry => {
-                                    self.pipeline.memory_module().wait_time()
-                                }
-                            };
-                            self.clocks += 1;
+                            self.clock_pipeline(1);
                         }
                         Ok(true)
                     }
 
                     KeyCode::Char('f') => {
                         if self.clocks_required != 0 {
-                            self.clocks += self.clocks_required;
-                            self.clocks_required = match self.pipeline.clock(self.clocks_required) {
-                                libpipe::ClockResult::Stall(clocks) => clocks,
-                                libpipe::ClockResult::Flow => 1,
-                                libpipe::ClockResult::Dry => {
-                                    self.pipeline.memory_module().wait_time()
-                                }
-                            };
+                            self.clock_pipeline(self.clocks_required);
                         }
 
                         Ok(true)
@@ -231,7 +354,7 @@ impl<'a> Runtime<'a> {
                     KeyCode::Char('r') => {
                         self.running.enabled = true;
                         self.running.progress_bar = 0;
-                        self.running.slow_mode = false;
+                        self.running.delay = None;
 
                         Ok(true)
                     }
@@ -239,7 +362,7 @@ impl<'a> Runtime<'a> {
                     KeyCode::Char('s') => {
                         self.running.enabled = true;
                         self.running.progress_bar = 0;
-                        self.running.slow_mode = true;
+                        self.running.delay = Some(SLOW_RUN_DELAY);
 
                         Ok(true)
                     }
@@ -253,4 +376,45 @@ impl<'a> Runtime<'a> {
             _ => Ok(true),
         }
     }
+
+    /// Edits the command in the palette, returning false if the command asked to exit
+    fn handle_palette_input(&mut self, code: KeyCode) -> bool {
+        let Some(input) = self.palette.input.as_mut() else {
+            return true;
+        };
+
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let input = self.palette.input.take().unwrap_or_default();
+                return self.run_command(&input);
+            }
+            KeyCode::Esc => self.palette.input = None,
+            _ => {}
+        }
+
+        true
+    }
+
+    fn handle_goto_input(&mut self, code: KeyCode) {
+        let Some(input) = self.memory_view.goto.as_mut() else {
+            return;
+        };
+
+        match code {
+            KeyCode::Char(c) if c.is_ascii_hexdigit() && input.len() < 8 => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let input = self.memory_view.goto.take().unwrap_or_default();
+                self.goto_address(&input);
+            }
+            KeyCode::Esc => self.memory_view.goto = None,
+            _ => {}
+        }
+    }
 }
@@ -1,7 +1,104 @@
 use super::*;
 
+/// The data cache lines resident when the memory view is drawn
+struct CachedLines {
+    base_addresses: HashSet<Word>,
+    mask: Word,
+}
+
+impl CachedLines {
+    fn contains(&self, address: Word) -> bool {
+        self.base_addresses.contains(&(address & self.mask))
+    }
+}
+
 impl<'a> Runtime<'a> {
+    /// Collects the lines of the data cache, if the overlay is enabled.
+    ///
+    /// Gathering the base addresses once per frame keeps the per-byte lookup cheap.
+    fn cached_lines(&self) -> Option<CachedLines> {
+        if !self.memory_view.cache_overlay {
+            return None;
+        }
+
+        let cache = self.pipeline.memory_module().data_cache();
+        let line_len = cache.line_len() as Word;
+
+        if line_len == 0 {
+            return None;
+        }
+
+        Some(CachedLines {
+            base_addresses: cache
+                .get_lines()
+                .into_iter()
+                .flatten()
+                .map(|line| line.base_address)
+                .collect(),
+            mask: !(line_len - 1),
+        })
+    }
+
+    fn byte_cell(&self, cached: &Option<CachedLines>, address: usize, value: u8) -> Cell<'static> {
+        let text = if self.memory_view.binary {
+            format!("{value:08b}")
+        } else {
+            format!("{value:02X}")
+        };
+
+        match cached {
+            Some(lines) if lines.contains(address as Word) => Cell::from(text).black().on_green(),
+            _ => Cell::from(text),
+        }
+    }
+
+    /// Moves the view to the page and row containing the hexadecimal address in `input`
+    pub fn goto_address(&mut self, input: &str) {
+        match Word::from_str_radix(input, 16) {
+            Ok(address) => self.show_address(address),
+            Err(_) => self.memory_view.status = Some(format!("Invalid address \"{input}\"")),
+        }
+    }
+
+    /// Moves the view to the page and row containing `address`
+    pub fn show_address(&mut self, address: Word) {
+        let address = address as usize;
+        let page = address / PAGE_SIZE;
+
+        if page >= self.config.pages {
+            self.memory_view.status = Some(format!("Address {address:#010X} is out of range"));
+            return;
+        }
+
+        let bytes_per_row = if self.memory_view.disassembly {
+            4
+        } else if self.memory_view.binary {
+            BYTES_PER_ROW_BINARY
+        } else {
+            BYTES_PER_ROW_HEXDEC
+        };
+
+        self.memory_view.follow_pc = false;
+        self.memory_view.page = page;
+        self.memory_view.page_offset = (address % PAGE_SIZE) / bytes_per_row;
+    }
+
+    /// Centers the disassembly view on the program counter, following it across pages
+    fn follow_pc(&mut self, height: usize) {
+        let pc = self.pipeline.registers().pc as usize;
+        let page = pc / PAGE_SIZE;
+
+        if page < self.config.pages {
+            self.memory_view.page = page;
+            self.memory_view.page_offset = ((pc % PAGE_SIZE) / 4).saturating_sub(height / 2);
+        }
+    }
+
     pub fn draw_memory_view(&mut self, chunks: &Rc<[Rect]>, buf: &mut Buffer) {
+        if self.memory_view.follow_pc && self.memory_view.disassembly {
+            self.follow_pc(chunks[1].height as usize);
+        }
+
         if self.memory_view.disassembly {
             self.memory_view.page_offset = self
                 .memory_view
@@ -21,29 +118,62 @@ impl<'a> Runtime<'a> {
             }
         }
 
-        Paragraph::new(Line::from(vec![
-            "↕".blue().bold(),
-            " to scroll | ".into(),
-            "↔".blue().bold(),
-            " to switch pages | ".into(),
-            "d".blue().bold(),
-            " to toggle disassembly view | ".into(),
-            "b".blue().bold(),
-            " to toggle binary view".into(),
-        ]))
-        .block(
-            Block::new()
-                .borders(Borders::TOP)
-                .padding(Padding::new(1, 1, 0, 0))
-                .title(
-                    Title::from(format!(
-                        " {} | {} ",
-                        self.memory_view.page, self.memory_view.page_offset
-                    ))
-                    .alignment(Alignment::Center),
-                ),
-        )
-        .render(chunks[2], buf);
+        let controls = if let Some(input) = &self.memory_view.goto {
+            Line::from(vec![
+                "Go to: 0x".into(),
+                input.clone().bold(),
+                "_".rapid_blink(),
+                " | ".into(),
+                "Enter".blue().bold(),
+                " to jump | ".into(),
+                "Esc".blue().bold(),
+                " to cancel".into(),
+            ])
+        } else if let Some(status) = &self.memory_view.status {
+            Line::from(status.clone().red().bold())
+        } else {
+            Line::from(vec![
+                "↕".blue().bold(),
+                " to scroll | ".into(),
+                "↔".blue().bold(),
+                " to switch pages | ".into(),
+                "d".blue().bold(),
+                " to toggle disassembly view | ".into(),
+                "b".blue().bold(),
+                " to toggle binary view | ".into(),
+                "h".blue().bold(),
+                " to toggle cache overlay | ".into(),
+                "g".blue().bold(),
+                " to go to an address | ".into(),
+                "p".blue().bold(),
+                " to follow PC | ".into(),
+                "/".blue().bold(),
+                " to search".into(),
+            ])
+        };
+
+        Paragraph::new(controls)
+            .block(
+                Block::new()
+                    .borders(Borders::TOP)
+                    .padding(Padding::new(1, 1, 0, 0))
+                    .title(
+                        Title::from(format!(
+                            " {} | {}{} ",
+                            self.memory_view.page,
+                            self.memory_view.page_offset,
+                            if self.memory_view.follow_pc {
+                                " | following PC"
+                            } else {
+                                ""
+                            }
+                        ))
+                        .alignment(Alignment::Center),
+                    ),
+            )
+            .render(chunks[2], buf);
+
+        let cached = self.cached_lines();
 
         if let Some(page) = self
             .pipeline
@@ -65,47 +195,42 @@ impl<'a> Runtime<'a> {
                 );
                 columns.push(Constraint::Fill(1));
 
-                let table = Table::new(
-                    page.chunks(bytes)
-                        .enumerate()
-                        .skip(self.memory_view.page_offset)
-                        .take(chunks[1].height as usize + 1)
-                        .enumerate()
-                        .map(|(rid, (i, row))| {
-                            let mut result = vec![format!(
-                                "{:#010X}",
-                                i * bytes + (self.memory_view.page << 16)
-                            )];
-                            result.extend(row.into_iter().map(|v| {
-                                if self.memory_view.binary {
-                                    format!("{v:08b}")
+                let table =
+                    Table::new(
+                        page.chunks(bytes)
+                            .enumerate()
+                            .skip(self.memory_view.page_offset)
+                            .take(chunks[1].height as usize + 1)
+                            .enumerate()
+                            .map(|(rid, (i, row))| {
+                                let address = i * bytes + (self.memory_view.page << 16);
+                                let mut result = vec![Cell::from(format!("{address:#010X}"))];
+                                result.extend(row.iter().enumerate().map(|(offset, &v)| {
+                                    self.byte_cell(&cached, address + offset, v)
+                                }));
+                                result.push(Cell::from(
+                                    decode::<libseis::instruction_set::Instruction>(
+                                        Word::from_be_bytes([row[0], row[1], row[2], row[3]]),
+                                    )
+                                    .map(|i| i.to_string())
+                                    .unwrap_or_default(),
+                                ));
+
+                                if i * bytes == pc as usize {
+                                    Row::new(result).on_red()
+                                } else if rid % 2 == 0 {
+                                    Row::new(result).on_light_blue()
                                 } else {
-                                    format!("{v:02X}")
+                                    Row::new(result).on_blue()
                                 }
-                            }));
-                            result.push(
-                                decode::<libseis::instruction_set::Instruction>(
-                                    Word::from_be_bytes([row[0], row[1], row[2], row[3]]),
-                                )
-                                .map(|i| i.to_string())
-                                .unwrap_or_default(),
-                            );
-
-                            if i * bytes == pc as usize {
-                                Row::new(result).on_red()
-                            } else if rid % 2 == 0 {
-                                Row::new(result).on_light_blue()
-                            } else {
-                                Row::new(result).on_blue()
-                            }
-                        }),
-                    columns,
-                )
-                .header(
-                    Row::new(headers.into_iter().map(|h| h.bold()))
-                        .on_blue()
-                        .bold(),
-                );
+                            }),
+                        columns,
+                    )
+                    .header(
+                        Row::new(headers.into_iter().map(|h| h.bold()))
+                            .on_blue()
+                            .bold(),
+                    );
 
                 table.render(chunks[1], buf);
             } else {
@@ -123,37 +248,32 @@ impl<'a> Runtime<'a> {
                         .map(|_| Constraint::Max(if self.memory_view.binary { 8 } else { 2 })),
                 );
 
-                let table = Table::new(
-                    page.chunks(bytes)
-                        .enumerate()
-                        .skip(self.memory_view.page_offset)
-                        .take(chunks[1].height as usize + 1)
-                        .enumerate()
-                        .map(|(rid, (i, row))| {
-                            let mut result = vec![format!(
-                                "{:#010X}",
-                                i * bytes + (self.memory_view.page << 16)
-                            )];
-                            result.extend(row.into_iter().map(|v| {
-                                if self.memory_view.binary {
-                                    format!("{v:08b}")
+                let table =
+                    Table::new(
+                        page.chunks(bytes)
+                            .enumerate()
+                            .skip(self.memory_view.page_offset)
+                            .take(chunks[1].height as usize + 1)
+                            .enumerate()
+                            .map(|(rid, (i, row))| {
+                                let address = i * bytes + (self.memory_view.page << 16);
+                                let mut result = vec![Cell::from(format!("{address:#010X}"))];
+                                result.extend(row.iter().enumerate().map(|(offset, &v)| {
+                                    self.byte_cell(&cached, address + offset, v)
+                                }));
+                                if rid % 2 == 0 {
+                                    Row::new(result).on_light_blue()
                                 } else {
-                                    format!("{v:02X}")
+                                    Row::new(result).on_blue()
                                 }
-                            }));
-                            if rid % 2 == 0 {
-                                Row::new(result).on_light_blue()
-                            } else {
-                                Row::new(result).on_blue()
-                            }
-                        }),
-                    columns,
-                )
-                .header(
-                    Row::new(headers.into_iter().map(|h| h.bold()))
-                        .on_blue()
-                        .bold(),
-                );
+                            }),
+                        columns,
+                    )
+                    .header(
+                        Row::new(headers.into_iter().map(|h| h.bold()))
+                            .on_blue()
+                            .bold(),
+                    );
 
                 table.render(chunks[1], buf);
             }
@@ -1,11 +1,14 @@
 mod cache;
 mod events;
 mod memory;
+mod palette;
 mod pipeline_stage;
 mod registers;
 mod render;
+mod watch;
 
-use crate::{config::SimulationConfiguration, PAGES};
+use crate::{config::SimulationConfiguration, interface::backend::cmd::RegisterFormat};
+use clap::ValueEnum;
 use crossterm::event;
 use libpipe::{Pipeline, PipelineStage, PipelineStages};
 use libseis::{
@@ -14,6 +17,7 @@ use libseis::{
     registers::{get_name, PC},
     types::{Register, Word},
 };
+use pipeline_stage::StageActivity;
 use ratatui::{
     layout::{Alignment, Constraint, Layout, Rect},
     prelude::{Buffer, CrosstermBackend, Stylize, Terminal},
@@ -21,12 +25,13 @@ use ratatui::{
     symbols::{self, border},
     text::Line,
     widgets::{
-        block::Title, canvas::Canvas, Block, BorderType, Borders, Clear, List, ListItem, Padding,
-        Paragraph, Row, Table, Tabs, Widget,
+        block::Title, canvas::Canvas, Block, BorderType, Borders, Cell, Clear, List, ListItem,
+        Padding, Paragraph, Row, Table, Tabs, Widget,
     },
     Frame,
 };
 use std::{
+    collections::{HashSet, VecDeque},
     error::Error,
     io::Stdout,
     rc::Rc,
@@ -39,14 +44,22 @@ const BYTES_PER_ROW_HEXDEC: usize = 32;
 const BYTES_PER_ROW_BINARY: usize = 8;
 
 const PIPELINE_STAGE_VIEW_COUNT: usize = 3;
+/// How many clocks of stage activity the pipeline view remembers
+const PIPELINE_HISTORY_LENGTH: usize = 64;
+/// The time between steps of a run started with the `s` key
+const SLOW_RUN_DELAY: Duration = Duration::from_millis(100);
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone, Copy, ValueEnum)]
 enum View {
     #[default]
+    #[value(alias = "regs")]
     Registers,
+    #[value(alias = "mem")]
     Memory,
     Cache,
+    #[value(name = "pipeline", alias = "pipe")]
     PipelineStages,
+    Watch,
 }
 
 impl View {
@@ -56,6 +69,7 @@ impl View {
             View::Memory => 1,
             View::Cache => 2,
             View::PipelineStages => 3,
+            View::Watch => 4,
         }
     }
 
@@ -64,16 +78,18 @@ impl View {
             Self::Registers => Self::Memory,
             Self::Memory => Self::Cache,
             Self::Cache => Self::PipelineStages,
-            Self::PipelineStages => Self::Registers,
+            Self::PipelineStages => Self::Watch,
+            Self::Watch => Self::Registers,
         }
     }
 
     fn previous(self) -> Self {
         match self {
-            Self::Registers => Self::PipelineStages,
+            Self::Registers => Self::Watch,
             Self::Memory => Self::Registers,
             Self::Cache => Self::Memory,
             Self::PipelineStages => Self::Cache,
+            Self::Watch => Self::PipelineStages,
         }
     }
 }
@@ -84,6 +100,17 @@ struct MemoryView {
     page_offset: usize,
     disassembly: bool,
     binary: bool,
+    cache_overlay: bool,
+    /// Whether the disassembly view is kept centered on the program counter
+    follow_pc: bool,
+    /// The address typed into the goto prompt, if it is open
+    goto: Option<String>,
+    /// A message to show in place of the controls until the next key press
+    status: Option<String>,
+    /// The addresses found by the last search
+    matches: Vec<Word>,
+    /// The match the view was last moved to
+    match_index: usize,
 }
 
 #[derive(Debug)]
@@ -91,19 +118,48 @@ struct CacheView {
     index: usize,
     count: usize,
     names: Vec<String>,
+    /// The first set shown in the grid
     scroll: usize,
+    /// The first way shown in the grid
+    way_scroll: usize,
+    /// The set of the focused line
+    set: usize,
+    /// The way of the focused line
+    way: usize,
+    /// The number of sets shown at once, used when paging
+    page_rows: usize,
 }
 
 #[derive(Debug)]
 struct PipelineView {
     stage: usize,
+    /// The activity of each stage over the last few clocks, oldest first
+    history: VecDeque<[StageActivity; 5]>,
+}
+
+#[derive(Debug)]
+struct WatchView {
+    /// The expressions being watched, kept for the rest of the session
+    entries: Vec<watch::WatchEntry>,
+    selected: usize,
+    /// The expression typed into the add prompt, if it is open
+    input: Option<String>,
+}
+
+#[derive(Debug)]
+struct CommandPalette {
+    /// The command being typed, if the palette is open
+    input: Option<String>,
+    /// The outcome of the last command, shown until the next key press
+    status: Option<Line<'static>>,
 }
 
 #[derive(Debug)]
 struct RunningView {
     enabled: bool,
     progress_bar: isize,
-    slow_mode: bool,
+    /// The time to wait between steps, or `None` to run at full speed
+    delay: Option<Duration>,
 }
 
 #[derive(Debug)]
@@ -115,19 +171,29 @@ pub struct Runtime<'a> {
     clocks: usize,
     clocks_required: usize,
 
+    /// How the register view shows values
+    register_format: RegisterFormat,
     memory_view: MemoryView,
     cache_view: CacheView,
     pipeline_view: PipelineView,
+    watch_view: WatchView,
 
     running: RunningView,
+    palette: CommandPalette,
+    /// Addresses which stop a run once the program counter reaches them
+    breakpoints: HashSet<Word>,
 
     last_update: Instant,
 }
 
 impl<'a> Runtime<'a> {
     pub fn new(pipeline: &'a mut dyn Pipeline, config: SimulationConfiguration) -> Self {
-        let mut caches: Vec<_> = config.cache.keys().map(|s| s.to_owned()).collect();
-        caches.sort();
+        let caches: Vec<_> = pipeline
+            .memory_module()
+            .get_cache_names()
+            .into_iter()
+            .map(|s| s.to_owned())
+            .collect();
         let cache_count = caches.len();
 
         Self {
@@ -138,25 +204,49 @@ impl<'a> Runtime<'a> {
             clocks: 0,
             clocks_required: 1,
 
+            register_format: RegisterFormat::default(),
             memory_view: MemoryView {
                 page: 0,
                 page_offset: 0,
                 disassembly: false,
                 binary: false,
+                cache_overlay: false,
+                follow_pc: false,
+                goto: None,
+                status: None,
+                matches: vec![],
+                match_index: 0,
             },
             cache_view: CacheView {
                 index: 0,
                 count: cache_count,
                 names: caches,
                 scroll: 0,
+                way_scroll: 0,
+                set: 0,
+                way: 0,
+                page_rows: 1,
+            },
+            pipeline_view: PipelineView {
+                stage: 0,
+                history: VecDeque::with_capacity(PIPELINE_HISTORY_LENGTH),
+            },
+            watch_view: WatchView {
+                entries: vec![],
+                selected: 0,
+                input: None,
             },
-            pipeline_view: PipelineView { stage: 0 },
 
             running: RunningView {
                 progress_bar: 0,
                 enabled: false,
-                slow_mode: false,
+                delay: None,
+            },
+            palette: CommandPalette {
+                input: None,
+                status: None,
             },
+            breakpoints: HashSet::new(),
 
             last_update: Instant::now(),
         }
@@ -184,6 +274,35 @@ impl<'a> Runtime<'a> {
         Ok(())
     }
 
+    /// Clocks the pipeline and records how each stage behaved
+    fn clock_pipeline(&mut self, clocks: usize) {
+        self.clocks += clocks;
+        self.clocks_required = match self.pipeline.clock(clocks) {
+            libpipe::ClockResult::Stall(clocks) => clocks,
+            libpipe::ClockResult::Flow => 1,
+            libpipe::ClockResult::Dry => self.pipeline.memory_module().wait_time(),
+        };
+
+        let activity = StageActivity::of(&self.pipeline.stages());
+        let history = &mut self.pipeline_view.history;
+
+        for _ in 0..clocks.min(PIPELINE_HISTORY_LENGTH) {
+            if history.len() == PIPELINE_HISTORY_LENGTH {
+                history.pop_front();
+            }
+            history.push_back(activity);
+        }
+    }
+
+    /// The number of instructions retired per clock so far
+    fn ipc(&self) -> f64 {
+        if self.clocks == 0 {
+            0.0
+        } else {
+            self.pipeline.retired_instructions() as f64 / self.clocks as f64
+        }
+    }
+
     fn draw<'f>(&mut self, frame: &mut Frame<'f>) {
         frame.render_widget(self, frame.size());
     }
@@ -0,0 +1,242 @@
+use super::*;
+use crate::interface::backend::cmd::Command;
+use clap::Parser;
+
+/// The commands accepted by the command palette
+#[derive(Debug, Parser)]
+#[clap(
+    multicall = true,
+    name = "",
+    disable_help_flag = true,
+    disable_help_subcommand = true
+)]
+enum PaletteCommand {
+    /// Moves the memory view to an address
+    #[command(alias = "g")]
+    Goto { address: String },
+    /// Switches to a view
+    #[command(alias = "v")]
+    View { view: View },
+    /// Toggles a breakpoint, which stops a run once PC reaches it
+    #[command(alias = "b")]
+    Break { address: String },
+    /// Adds an expression to the watch list, such as `*0x1000 as word`
+    #[command(alias = "w")]
+    Watch {
+        #[arg(required = true, num_args = 1..)]
+        expression: Vec<String>,
+    },
+    #[command(flatten)]
+    Backend(Command),
+}
+
+/// Parses a hexadecimal address, with or without the `0x` prefix
+pub fn parse_address(input: &str) -> Option<Word> {
+    let digits = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input);
+
+    Word::from_str_radix(digits, 16).ok()
+}
+
+impl<'a> Runtime<'a> {
+    /// Runs a command typed into the palette, returning false if the program should exit
+    pub fn run_command(&mut self, input: &str) -> bool {
+        if input.trim().is_empty() {
+            return true;
+        }
+
+        let command = match PaletteCommand::try_parse_from(input.split_whitespace()) {
+            Ok(command) => command,
+            Err(e) => {
+                let message = e.to_string();
+                self.palette_error(message.lines().next().unwrap_or_default().to_owned());
+                return true;
+            }
+        };
+
+        match command {
+            PaletteCommand::Goto { address } => match parse_address(&address) {
+                Some(address) => {
+                    self.view = View::Memory;
+                    self.show_address(address);
+                }
+                None => self.palette_error(format!("Invalid address \"{address}\"")),
+            },
+            PaletteCommand::View { view } => self.view = view,
+            PaletteCommand::Break { address } => match parse_address(&address) {
+                Some(address) if self.breakpoints.remove(&address) => {
+                    self.palette_message(format!("Removed the breakpoint at {address:#010X}"))
+                }
+                Some(address) => {
+                    self.breakpoints.insert(address);
+                    self.palette_message(format!("Set a breakpoint at {address:#010X}"))
+                }
+                None => self.palette_error(format!("Invalid address \"{address}\"")),
+            },
+            PaletteCommand::Watch { expression } => {
+                self.view = View::Watch;
+                self.add_watch(&expression.join(" "));
+            }
+            PaletteCommand::Backend(command) => return self.run_backend_command(command),
+        }
+
+        true
+    }
+
+    fn run_backend_command(&mut self, command: Command) -> bool {
+        match command {
+            Command::Clock { mut count } => {
+                while count > 0 && self.clocks_required != 0 {
+                    let clocks = self.clocks_required.min(count);
+                    count -= clocks;
+                    self.clock_pipeline(clocks);
+                }
+            }
+            Command::Run { clock_rate } => {
+                self.running.enabled = true;
+                self.running.progress_bar = 0;
+                self.running.delay = clock_rate.map(Duration::from_millis);
+            }
+            Command::Stop {} => self.running.enabled = false,
+            Command::ShowRegs { format, .. } => {
+                self.view = View::Registers;
+                self.register_format = format;
+            }
+            Command::ShowCache {} => self.view = View::Cache,
+            Command::ShowPipeline {} => self.view = View::PipelineStages,
+            Command::ReadPage { page } | Command::DisassemblePage { page }
+                if page >= self.config.pages =>
+            {
+                self.palette_error(format!("Page {page} is out of range"))
+            }
+            Command::ReadPage { page } => self.show_page(page, false),
+            Command::DisassemblePage { page } => self.show_page(page, true),
+            Command::DisassembleAround { .. } => {
+                self.view = View::Memory;
+                self.memory_view.follow_pc = true;
+                self.memory_view.disassembly = true;
+            }
+            Command::Search {
+                pattern,
+                width,
+                start,
+                end,
+            } => {
+                self.memory_view.matches = self.pipeline.memory_module().memory().search(
+                    pattern,
+                    width.into(),
+                    start.unwrap_or(0)..end.unwrap_or(Word::MAX),
+                );
+
+                self.show_match(0);
+            }
+            Command::Terminate {} => return false,
+            Command::Decode { .. }
+            | Command::Information { .. }
+            | Command::Statistics {}
+            | Command::DumpCache { .. }
+            | Command::DumpMemory { .. }
+            | Command::Commands {} => {
+                self.palette_error("This command is only available in the backend".into())
+            }
+        }
+
+        true
+    }
+
+    /// Moves the memory view to a match of the last search, wrapping around the matches
+    pub fn show_match(&mut self, index: usize) {
+        let count = self.memory_view.matches.len();
+
+        if count == 0 {
+            self.palette_error("No matches found".into());
+            return;
+        }
+
+        let index = index % count;
+        let address = self.memory_view.matches[index];
+
+        self.memory_view.match_index = index;
+        self.view = View::Memory;
+        self.show_address(address);
+        self.palette_message(format!("Match {} of {count} at {address:#010X}", index + 1));
+    }
+
+    /// Shows the start of a page in the memory view
+    fn show_page(&mut self, page: usize, disassembly: bool) {
+        self.view = View::Memory;
+        self.memory_view.follow_pc = false;
+        self.memory_view.disassembly = disassembly;
+        self.memory_view.page = page;
+        self.memory_view.page_offset = 0;
+    }
+
+    fn palette_message(&mut self, message: String) {
+        self.palette.status = Some(Line::from(message.bold()));
+    }
+
+    fn palette_error(&mut self, message: String) {
+        self.palette.status = Some(Line::from(message.red().bold()));
+    }
+
+    pub fn draw_palette(&self, area: Rect, buf: &mut Buffer) {
+        let line = if let Some(input) = &self.palette.input {
+            Line::from(vec![
+                ":".blue().bold(),
+                input.clone().bold(),
+                "_".rapid_blink(),
+            ])
+        } else if let Some(status) = &self.palette.status {
+            status.clone()
+        } else {
+            return;
+        };
+
+        Clear.render(area, buf);
+
+        Paragraph::new(line)
+            .block(
+                Block::new()
+                    .borders(Borders::TOP)
+                    .padding(Padding::new(1, 1, 0, 0)),
+            )
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_parses_its_rate() {
+        let rate = |input: &str| match PaletteCommand::try_parse_from(input.split_whitespace()) {
+            Ok(PaletteCommand::Backend(Command::Run { clock_rate })) => clock_rate,
+            command => panic!("Parsed {command:?}"),
+        };
+
+        assert_eq!(rate("run"), None);
+        assert_eq!(rate("run 250"), Some(250));
+        assert!(PaletteCommand::try_parse_from(["run", "fast"]).is_err());
+    }
+
+    #[test]
+    fn run_honors_its_rate() {
+        let config = SimulationConfiguration::small_direct();
+        let mut pipeline = config.clone().into_boxed_pipeline();
+        let mut runtime = Runtime::new(pipeline.as_mut(), config);
+
+        assert!(runtime.run_command("run 250"));
+        assert!(runtime.running.enabled);
+        assert_eq!(runtime.running.delay, Some(Duration::from_millis(250)));
+
+        assert!(runtime.run_command("stop"));
+        assert!(!runtime.running.enabled);
+
+        assert!(runtime.run_command("run"));
+        assert!(runtime.running.enabled);
+        assert_eq!(runtime.running.delay, None);
+    }
+}
@@ -1,6 +1,108 @@
 use super::*;
+use libpipe::memory::MemoryResult;
+
+/// What a stage was doing after a clock
+#[derive(Debug, Clone, Copy)]
+pub enum StageActivity {
+    /// The stage holds no instruction
+    Idle,
+    /// The stage is working on an instruction
+    Flowing,
+    /// The stage is waiting, either on memory or on the next stage
+    Stalled,
+    /// The stage holds a squashed instruction
+    Squashed,
+}
+
+impl StageActivity {
+    /// Classifies each stage, in pipeline order, from its state after a clock
+    ///
+    /// A stage still holding a finished result after forwarding is considered stalled.
+    pub fn of(stages: &PipelineStages) -> [Self; 5] {
+        use StageActivity::*;
+
+        let fetch = match stages.fetch.get_state() {
+            libpipe::fetch::State::Idle | libpipe::fetch::State::Halted => Idle,
+            libpipe::fetch::State::Waiting { .. } => Stalled,
+            libpipe::fetch::State::Ready { .. } => Flowing,
+            libpipe::fetch::State::Squashed { .. } => Squashed,
+        };
+
+        let decode = match stages.decode.get_state() {
+            libpipe::decode::State::Idle | libpipe::decode::State::Halted => Idle,
+            libpipe::decode::State::Decoding { .. } => Flowing,
+            libpipe::decode::State::Ready { .. } => Stalled,
+            libpipe::decode::State::Squashed | libpipe::decode::State::PrevSquash => Squashed,
+        };
+
+        let execute = match stages.execute.get_state() {
+            libpipe::execute::State::Idle | libpipe::execute::State::Halted => Idle,
+            libpipe::execute::State::Executing { clocks: 0, .. } => Flowing,
+            libpipe::execute::State::Faulted { .. } => Flowing,
+            libpipe::execute::State::Executing { .. } | libpipe::execute::State::Ready { .. } => {
+                Stalled
+            }
+            libpipe::execute::State::Squashed { .. } => Squashed,
+        };
+
+        let memory = match stages.memory.get_state() {
+            libpipe::memory::State::Idle
+            | libpipe::memory::State::Halting(_)
+            | libpipe::memory::State::Halted => Idle,
+            libpipe::memory::State::Reading { clocks, .. }
+            | libpipe::memory::State::Writing { clocks, .. }
+            | libpipe::memory::State::Pushing { clocks, .. }
+            | libpipe::memory::State::Popping { clocks, .. }
+            | libpipe::memory::State::DummyPop { clocks, .. }
+            | libpipe::memory::State::JsrPrep { clocks, .. }
+            | libpipe::memory::State::RetPrep { clocks, .. }
+            | libpipe::memory::State::Trapping { clocks, .. }
+            | libpipe::memory::State::TrapReturn { clocks, .. } => {
+                if *clocks == 0 {
+                    Flowing
+                } else {
+                    Stalled
+                }
+            }
+            libpipe::memory::State::Ready { .. } => Stalled,
+            libpipe::memory::State::Squashed { .. } => Squashed,
+        };
+
+        let writeback = match stages.writeback.get_state() {
+            None | Some(MemoryResult::Nop) => Idle,
+            Some(MemoryResult::Squashed { .. }) => Squashed,
+            Some(_) => Flowing,
+        };
+
+        [fetch, decode, execute, memory, writeback]
+    }
+
+    fn color(self) -> Color {
+        match self {
+            StageActivity::Idle => Color::DarkGray,
+            StageActivity::Flowing => Color::Green,
+            StageActivity::Stalled => Color::Yellow,
+            StageActivity::Squashed => Color::Magenta,
+        }
+    }
+}
 
 impl<'a> Runtime<'a> {
+    /// Draws the recent activity of a stage as a strip of colored cells, newest on the right
+    fn draw_stage_history(&self, stage: usize, area: Rect, buf: &mut Buffer) {
+        let history = &self.pipeline_view.history;
+        let width = (area.width as usize).min(history.len());
+
+        Line::from(
+            history
+                .iter()
+                .skip(history.len() - width)
+                .map(|activity| "▆".fg(activity[stage].color()))
+                .collect::<Vec<_>>(),
+        )
+        .render(area, buf);
+    }
+
     pub fn draw_pipeline_view(&mut self, chunks: Rc<[Rect]>, buf: &mut Buffer) {
         let stages = self.pipeline.stages();
 
@@ -27,6 +129,7 @@ impl<'a> Runtime<'a> {
             ),
         ]
         .into_iter()
+        .enumerate()
         .skip(self.pipeline_view.stage)
         .take(PIPELINE_STAGE_VIEW_COUNT)
         .zip(
@@ -34,7 +137,7 @@ impl<'a> Runtime<'a> {
                 .split(chunks[1])
                 .into_iter(),
         )
-        .for_each(|((name, pfn), &blk)| {
+        .for_each(|((stage, (name, pfn)), &blk)| {
             let block = Block::new()
                 .border_type(BorderType::Rounded)
                 .borders(Borders::ALL)
@@ -43,16 +146,47 @@ impl<'a> Runtime<'a> {
             let area = block.inner(blk);
             block.render(blk, buf);
 
-            pfn(self, &stages, area, buf);
+            let split = Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).split(area);
+
+            self.draw_stage_history(stage, split[0], buf);
+
+            pfn(self, &stages, split[1], buf);
         });
 
-        Paragraph::new(Line::from(vec!["↔".blue().bold(), " to scroll ".into()]))
-            .block(
-                Block::new()
-                    .padding(Padding::new(1, 1, 0, 0))
-                    .borders(Borders::TOP),
-            )
-            .render(chunks[2], buf);
+        let perf = self.pipeline.perf_counters();
+
+        Paragraph::new(Line::from(vec![
+            "↔".blue().bold(),
+            " to scroll | ".into(),
+            "▆".fg(StageActivity::Flowing.color()),
+            " flowing ".into(),
+            "▆".fg(StageActivity::Stalled.color()),
+            " stalled ".into(),
+            "▆".fg(StageActivity::Squashed.color()),
+            " squashed ".into(),
+            "▆".fg(StageActivity::Idle.color()),
+            " idle ".into(),
+        ]))
+        .block(
+            Block::new()
+                .padding(Padding::new(1, 1, 0, 0))
+                .borders(Borders::TOP)
+                .title(
+                    Title::from(Line::from(vec![
+                        " Stalls | Data: ".into(),
+                        perf.data_hazard_stalls.to_string().red().bold(),
+                        " | Memory: ".into(),
+                        perf.memory_stalls.to_string().red().bold(),
+                        " | Control: ".into(),
+                        perf.control_hazard_stalls.to_string().red().bold(),
+                        " | Structural: ".into(),
+                        perf.structural_stalls.to_string().red().bold(),
+                        " ".into(),
+                    ]))
+                    .alignment(Alignment::Center),
+                ),
+        )
+        .render(chunks[2], buf);
     }
 
     fn draw_pipe_fetch_view(&self, stages: &PipelineStages, chunk: Rect, buf: &mut Buffer) {
@@ -169,6 +303,7 @@ impl<'a> Runtime<'a> {
                 wregs,
                 rvals,
                 clocks,
+                ..
             } => List::new(
                 [
                     Line::from(vec!["State: ".into(), "Executing".red().bold()]),
@@ -256,6 +391,14 @@ impl<'a> Runtime<'a> {
                 .into_iter()
                 .map(ListItem::new),
             ),
+            libpipe::execute::State::Faulted { fault, .. } => List::new(
+                [
+                    Line::from(vec!["State: ".into(), "Faulted".red().bold()]),
+                    Line::from(vec!["Fault: ".into(), fault.to_string().red().bold()]),
+                ]
+                .into_iter()
+                .map(ListItem::new),
+            ),
             libpipe::execute::State::Halted => List::new(
                 [Line::from(vec!["State: ".into(), "Halted".red().bold()])]
                     .into_iter()
@@ -477,6 +620,48 @@ impl<'a> Runtime<'a> {
                 .into_iter()
                 .map(ListItem::new),
             ),
+            libpipe::memory::State::Trapping {
+                fault,
+                address,
+                link,
+                clocks,
+                ..
+            } => List::new(
+                [
+                    Line::from(vec!["State: ".into(), "Trapping".red().bold()]),
+                    Line::from(vec![
+                        "Expected wait time: ".into(),
+                        clocks.to_string().red().bold(),
+                    ]),
+                    Line::from(vec!["Fault: ".into(), fault.to_string().red().bold()]),
+                    Line::from(vec!["Handler: ".into(), address.to_string().red().bold()]),
+                    Line::from(vec!["Link: ".into(), link.to_string().red().bold()]),
+                ]
+                .into_iter()
+                .map(ListItem::new),
+            ),
+            libpipe::memory::State::TrapReturn {
+                link, bp, clocks, ..
+            } => List::new(
+                [
+                    Line::from(vec![
+                        "State: ".into(),
+                        "Returning from handler".red().bold(),
+                    ]),
+                    Line::from(vec![
+                        "Expected wait time: ".into(),
+                        clocks.to_string().red().bold(),
+                    ]),
+                    Line::from(vec!["Link: ".into(), link.to_string().red().bold()]),
+                    Line::from(vec![
+                        "BP".red().bold(),
+                        " = ".into(),
+                        bp.to_string().red().bold(),
+                    ]),
+                ]
+                .into_iter()
+                .map(ListItem::new),
+            ),
             libpipe::memory::State::Ready { .. } => List::new(
                 [Line::from(vec!["State: ".into(), "Ready".red().bold()])]
                     .into_iter()
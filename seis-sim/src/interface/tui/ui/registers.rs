@@ -4,18 +4,28 @@ impl<'a> Runtime<'a> {
     pub fn draw_registers_view(&mut self, chunks: &Rc<[Rect]>, buf: &mut Buffer) {
         let lines = chunks[1].height as usize;
         let splits = Layout::horizontal([Constraint::Min(16); 6]).split(chunks[1]);
+        let format = self.register_format;
+        let locked = self.pipeline.locked_registers();
 
         self.pipeline
             .registers()
             .iter()
             .enumerate()
-            .map(|(i, val)| {
+            .map(|(i, &val)| {
+                // PC is an address, so it is shown in hexadecimal unless asked otherwise
+                let value = if i as Register == PC && format == RegisterFormat::Unsigned {
+                    format!("{val:#010X}").blue().bold()
+                } else {
+                    format.to_text(val).blue().bold()
+                };
+
                 Line::from(vec![
                     format!("{:>3}: ", get_name(i as Register).unwrap()).into(),
-                    if i as Register == PC {
-                        format!("{val:#010X}").blue().bold()
+                    // Registers still waiting on writeback hold stale values
+                    if locked.has_register(i as Register) {
+                        value.not_bold().dim()
                     } else {
-                        format!("{val}").blue().bold()
+                        value
                     },
                 ])
             })
@@ -23,5 +33,25 @@ impl<'a> Runtime<'a> {
             .chunks(lines)
             .enumerate()
             .for_each(|(i, x)| List::new(x.into_iter().map(|x| x.clone())).render(splits[i], buf));
+
+        Paragraph::new(Line::from(vec![
+            "v".blue().bold(),
+            " to change how values are shown, ".into(),
+            "dimmed".dim(),
+            " registers are locked until writeback".into(),
+        ]))
+        .block(
+            Block::new()
+                .borders(Borders::TOP)
+                .padding(Padding::new(1, 1, 0, 0))
+                .title(
+                    Title::from(format!(
+                        " {} ",
+                        format.to_possible_value().unwrap().get_name()
+                    ))
+                    .alignment(Alignment::Right),
+                ),
+        )
+        .render(chunks[2], buf);
     }
 }
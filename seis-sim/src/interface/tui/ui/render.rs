@@ -16,7 +16,8 @@ impl<'a> Widget for &mut Runtime<'a> {
 
         self.draw_tabs(chunks[0], buf);
 
-        self.draw_view(chunks, buf);
+        self.draw_view(chunks.clone(), buf);
+        self.draw_palette(chunks[2], buf);
 
         if self.running.enabled {
             self.draw_running_window(area, buf);
@@ -77,7 +78,7 @@ impl<'a> Runtime<'a> {
     }
 
     fn draw_tabs(&self, area: Rect, buf: &mut Buffer) {
-        Tabs::new(["Registers", "Memory", "Cache", "Pipeline"])
+        Tabs::new(["Registers", "Memory", "Cache", "Pipeline", "Watch"])
             .block(Block::new().borders(Borders::BOTTOM))
             .style(Style::default().white())
             .highlight_style(Style::default().white().on_blue().bold())
@@ -93,6 +94,7 @@ impl<'a> Runtime<'a> {
             [" c".blue().bold(), " to clock ".into()],
             [" r".blue().bold(), " to run ".into()],
             [" s".blue().bold(), " for slow run ".into()],
+            [" :".blue().bold(), " for commands ".into()],
         ];
 
         let module = self.pipeline.memory_module();
@@ -114,6 +116,8 @@ impl<'a> Runtime<'a> {
                         module.accesses().to_string().red().bold(),
                         " | Evictions: ".into(),
                         module.evictions().to_string().red().bold(),
+                        " | IPC: ".into(),
+                        format!("{:.2}", self.ipc()).red().bold(),
                         " ".into(),
                     ])
                     .alignment(Alignment::Right),
@@ -138,6 +142,8 @@ impl<'a> Runtime<'a> {
                         module.accesses().to_string().red().bold(),
                         " | Evictions: ".into(),
                         module.evictions().to_string().red().bold(),
+                        " | IPC: ".into(),
+                        format!("{:.2}", self.ipc()).red().bold(),
                         " ".into(),
                     ])
                     .alignment(Alignment::Right),
@@ -167,6 +173,9 @@ impl<'a> Runtime<'a> {
             View::PipelineStages => {
                 self.draw_pipeline_view(chunks, buf);
             }
+            View::Watch => {
+                self.draw_watch_view(&chunks, buf);
+            }
         }
     }
 }
@@ -0,0 +1,300 @@
+use super::{palette::parse_address, *};
+use crossterm::event::KeyCode;
+use libseis::registers::get_id_with_aliases;
+
+/// How a watched value is read and shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Width {
+    Byte,
+    Short,
+    Word,
+    Float,
+}
+
+/// Where a watched value is read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Location {
+    Register(Register),
+    /// The memory at an address
+    Memory(Word),
+    /// The memory at the address held in a register
+    Indirect(Register),
+}
+
+/// A parsed watch expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watch {
+    location: Location,
+    width: Width,
+}
+
+impl Watch {
+    /// Parses expressions such as `v3`, `v3 as float`, `*0x1000 as word`, or `*sp as byte`
+    ///
+    /// Addresses are hexadecimal, with or without the `0x` prefix. Values are read as
+    /// words unless given a width.
+    fn parse(expression: &str) -> Result<Self, String> {
+        let tokens: Vec<&str> = expression.split_whitespace().collect();
+
+        let (target, width) = match tokens.as_slice() {
+            [target] => (*target, Width::Word),
+            [target, r#as, width] if r#as.eq_ignore_ascii_case("as") => {
+                let width = match width.to_lowercase().as_str() {
+                    "byte" | "b" => Width::Byte,
+                    "short" | "s" => Width::Short,
+                    "word" | "w" => Width::Word,
+                    "float" | "f" => Width::Float,
+                    _ => return Err(format!("\"{width}\" is not a valid width")),
+                };
+
+                (*target, width)
+            }
+            [] => return Err("Expected an expression".into()),
+            _ => return Err("Expected an expression like `*0x1000 as word`".into()),
+        };
+
+        let location = match target.strip_prefix('*') {
+            Some(target) => match get_id_with_aliases(target) {
+                Some(register) => Location::Indirect(register),
+                None => Location::Memory(
+                    parse_address(target).ok_or_else(|| format!("Invalid address \"{target}\""))?,
+                ),
+            },
+            None => Location::Register(
+                get_id_with_aliases(target)
+                    .ok_or_else(|| format!("Unknown register \"{target}\""))?,
+            ),
+        };
+
+        Ok(Self { location, width })
+    }
+}
+
+/// An expression in the watch list, along with the result of parsing it
+#[derive(Debug)]
+pub struct WatchEntry {
+    expression: String,
+    watch: Result<Watch, String>,
+}
+
+impl<'a> Runtime<'a> {
+    /// Adds an expression to the watch list, keeping it even if it is invalid so the error
+    /// can be shown alongside it
+    pub fn add_watch(&mut self, expression: &str) {
+        let expression = expression.trim();
+
+        if expression.is_empty() {
+            return;
+        }
+
+        self.watch_view.entries.push(WatchEntry {
+            expression: expression.to_owned(),
+            watch: Watch::parse(expression),
+        });
+        self.watch_view.selected = self.watch_view.entries.len() - 1;
+    }
+
+    /// Removes the selected entry from the watch list
+    pub fn remove_watch(&mut self) {
+        if self.watch_view.selected < self.watch_view.entries.len() {
+            self.watch_view.entries.remove(self.watch_view.selected);
+            self.watch_view.selected = self.watch_view.selected.saturating_sub(1);
+        }
+    }
+
+    pub fn handle_watch_input(&mut self, code: KeyCode) {
+        let Some(input) = self.watch_view.input.as_mut() else {
+            return;
+        };
+
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let input = self.watch_view.input.take().unwrap_or_default();
+                self.add_watch(&input);
+            }
+            KeyCode::Esc => self.watch_view.input = None,
+            _ => {}
+        }
+    }
+
+    /// Reads the value of a watch, returning the address it was read from (if any) and the
+    /// value as text
+    fn evaluate(&self, watch: Watch) -> (Option<Word>, String) {
+        let registers = self.pipeline.registers();
+        let memory = self.pipeline.memory_module().memory();
+
+        let read = |address: Word| match watch.width {
+            Width::Byte => memory.read_byte(address) as Word,
+            Width::Short => memory.read_short(address) as Word,
+            Width::Word | Width::Float => memory.read_word(address),
+        };
+
+        let (address, word) = match watch.location {
+            Location::Register(register) => (None, registers[register]),
+            Location::Memory(address) => (Some(address), read(address)),
+            Location::Indirect(register) => {
+                let address = registers[register];
+                (Some(address), read(address))
+            }
+        };
+
+        let value = match watch.width {
+            Width::Byte => format!("{0} ({0:#04X})", word as u8),
+            Width::Short => format!("{0} ({0:#06X})", word as u16),
+            Width::Word => format!("{word} ({word:#010X})"),
+            Width::Float => format!("{}", f32::from_bits(word)),
+        };
+
+        (address, value)
+    }
+
+    pub fn draw_watch_view(&mut self, chunks: &Rc<[Rect]>, buf: &mut Buffer) {
+        let count = self.watch_view.entries.len();
+        self.watch_view.selected = self.watch_view.selected.min(count.saturating_sub(1));
+
+        if count == 0 {
+            Paragraph::new("Nothing is being watched. Press `a` to add an expression.")
+                .centered()
+                .render(chunks[1], buf);
+        } else {
+            let rows = self
+                .watch_view
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    let (address, value) = match entry.watch {
+                        Ok(watch) => {
+                            let (address, value) = self.evaluate(watch);
+                            (
+                                address.map(|a| format!("{a:#010X}")).unwrap_or_default(),
+                                Cell::from(value.blue().bold()),
+                            )
+                        }
+                        Err(ref e) => (String::new(), Cell::from(e.clone().red().bold())),
+                    };
+
+                    let row = Row::new(vec![
+                        Cell::from(i.to_string()),
+                        Cell::from(entry.expression.clone()),
+                        Cell::from(address),
+                        value,
+                    ]);
+
+                    if i == self.watch_view.selected {
+                        row.reversed()
+                    } else {
+                        row
+                    }
+                });
+
+            Table::new(
+                rows,
+                [
+                    Constraint::Length(4),
+                    Constraint::Fill(1),
+                    Constraint::Length(12),
+                    Constraint::Fill(2),
+                ],
+            )
+            .header(
+                Row::new(["#", "Expression", "Address", "Value"])
+                    .on_blue()
+                    .bold(),
+            )
+            .render(chunks[1], buf);
+        }
+
+        let controls = if let Some(input) = &self.watch_view.input {
+            Line::from(vec![
+                "Watch: ".into(),
+                input.clone().bold(),
+                "_".rapid_blink(),
+                " | ".into(),
+                "Enter".blue().bold(),
+                " to add | ".into(),
+                "Esc".blue().bold(),
+                " to cancel".into(),
+            ])
+        } else {
+            Line::from(vec![
+                "↕".blue().bold(),
+                " to select | ".into(),
+                "a".blue().bold(),
+                " to add | ".into(),
+                "x".blue().bold(),
+                " to remove | ".into(),
+                "e.g. ".into(),
+                "v3".blue().bold(),
+                ", ".into(),
+                "v3 as float".blue().bold(),
+                ", ".into(),
+                "*0x1000 as word".blue().bold(),
+                ", ".into(),
+                "*sp as byte".blue().bold(),
+            ])
+        };
+
+        Paragraph::new(controls)
+            .block(
+                Block::new()
+                    .borders(Borders::TOP)
+                    .padding(Padding::new(1, 1, 0, 0)),
+            )
+            .render(chunks[2], buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libseis::registers::{SP, V};
+
+    #[test]
+    fn watches_parse() {
+        let watch = |location, width| Ok(Watch { location, width });
+
+        assert_eq!(
+            Watch::parse("v3"),
+            watch(Location::Register(V[3]), Width::Word)
+        );
+        assert_eq!(
+            Watch::parse("v3 as float"),
+            watch(Location::Register(V[3]), Width::Float)
+        );
+        assert_eq!(
+            Watch::parse("*0x1000 AS w"),
+            watch(Location::Memory(0x1000), Width::Word)
+        );
+        assert_eq!(
+            Watch::parse("  *1000   as   short "),
+            watch(Location::Memory(0x1000), Width::Short)
+        );
+        assert_eq!(
+            Watch::parse("*sp as byte"),
+            watch(Location::Indirect(SP), Width::Byte)
+        );
+    }
+
+    #[test]
+    fn invalid_watches_are_errors() {
+        let error = |expression| Watch::parse(expression).unwrap_err();
+
+        assert_eq!(error(""), "Expected an expression");
+        assert_eq!(
+            error("v3 as"),
+            "Expected an expression like `*0x1000 as word`"
+        );
+        assert_eq!(
+            error("v3 to word"),
+            "Expected an expression like `*0x1000 as word`"
+        );
+        assert_eq!(error("v3 as long"), "\"long\" is not a valid width");
+        assert_eq!(error("v99"), "Unknown register \"v99\"");
+        assert_eq!(error("*0xG000"), "Invalid address \"0xG000\"");
+    }
+}
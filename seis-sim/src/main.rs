@@ -3,46 +3,443 @@ mod config;
 mod interface;
 
 use clap::Parser;
-use cli::{Cli, Configuration, SimulatorConfig};
-use config::{CacheConfiguration, PipelineMode, SimulationConfiguration};
+use cli::{Cli, Configuration, EdgeFormat, Load, SimulatorConfig};
+use config::{CacheConfiguration, PipelineMode, SimulationConfiguration, PRESETS};
 use interface::Interface;
+use libmem::{
+    analysis::{reuse_distances, ReuseHistogram},
+    memory::Memory,
+    module::MemoryModule,
+};
 use libpipe::Pipeline;
-use libseis::{pages::PAGE_SIZE, types::Word};
-use std::{error::Error, fs::read, path::PathBuf};
+use libseis::{
+    image,
+    instruction_set::{decode, Instruction},
+    registers::{get_name, PC},
+    types::Word,
+};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs::read,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
 fn into_toml(config: Configuration) -> Result<toml::Table, Box<dyn Error>> {
     if let Some(f) = config.file {
-        Ok(toml::from_str(&std::fs::read_to_string(f)?)?)
+        config::read_toml(&f)
     } else if let Some(s) = config.inline {
-        Ok(toml::from_str(&s)?)
+        config::resolve_base(toml::from_str(&s)?, Path::new(""))
     } else {
         Err("Etiher a string or a file configuration is required".into())
     }
 }
 
-/// However many pages of memory are supported by the simulator
+/// The number of pages of memory simulated, unless the configuration sets another
 const PAGES: usize = 16;
 
 fn prepare_config(
     conf: toml::Table,
     bin: PathBuf,
+    loads: &[Load],
 ) -> Result<(Box<dyn Pipeline>, SimulationConfiguration), Box<dyn Error>> {
     let conf = SimulationConfiguration::from_toml(&conf)?;
-    let mut pipeline = conf.clone().into_boxed_pipeline();
+    let pipeline = load_pipeline(conf.clone(), &bin, loads)?;
 
+    Ok((pipeline, conf))
+}
+
+fn load_pipeline(
+    conf: SimulationConfiguration,
+    bin: &Path,
+    loads: &[Load],
+) -> Result<Box<dyn Pipeline>, Box<dyn Error>> {
+    load_image(conf.into_boxed_pipeline(), bin, loads)
+}
+
+fn load_image(
+    mut pipeline: Box<dyn Pipeline>,
+    bin: &Path,
+    loads: &[Load],
+) -> Result<Box<dyn Pipeline>, Box<dyn Error>> {
     let memory = pipeline.memory_module_mut().memory_mut();
 
-    let data = read(&bin)?;
+    pipeline.registers_mut().pc = load_images(memory, bin, loads)?;
+
+    Ok(pipeline)
+}
+
+/// Loads the image at `bin`, followed by each of the flat images in `loads` at its address,
+/// and returns the entry point of `bin`
+///
+/// None of the images may overlap, and each must fit in memory.
+fn load_images(memory: &mut Memory, bin: &Path, loads: &[Load]) -> Result<Word, Box<dyn Error>> {
+    let data = read(bin)?;
+
+    let segments = memory
+        .load_image(&data)
+        .map_err(|e| format!("Could not load {}: {e}", bin.display()))?;
+
+    // Ranges are 64-bit so an image ending at the top of the address space cannot overflow
+    let mut placed: Vec<(&Path, Range<u64>)> = segments
+        .iter()
+        .map(|s| (bin, s.address as u64..s.address as u64 + s.length as u64))
+        .collect();
+
+    for load in loads {
+        let contents =
+            read(&load.file).map_err(|e| format!("Could not read {}: {e}", load.file.display()))?;
+
+        if image::is_segmented(&contents) {
+            return Err(format!(
+                "{} is a segmented image, which already holds its own addresses; only flat images can be loaded at an address",
+                load.file.display()
+            )
+            .into());
+        }
+
+        let range = load.address as u64..load.address as u64 + contents.len() as u64;
+
+        if range.end > memory.max_address() as u64 + 1 {
+            return Err(format!(
+                "{} does not fit in memory when loaded at {:#010X}",
+                load.file.display(),
+                load.address
+            )
+            .into());
+        }
 
-    if data.len() >= PAGES << 16 {
-        return Err(format!("File too long: {}", bin.display()).into());
+        if let Some((other, overlap)) = placed
+            .iter()
+            .find(|(_, other)| other.start < range.end && range.start < other.end)
+        {
+            return Err(format!(
+                "{} ({:#010X} to {:#010X}) overlaps {} ({:#010X} to {:#010X})",
+                load.file.display(),
+                range.start,
+                range.end,
+                other.display(),
+                overlap.start,
+                overlap.end
+            )
+            .into());
+        }
+
+        memory.write_words(load.address, &contents);
+        placed.push((&load.file, range));
     }
 
-    for (page, data) in data.chunks(PAGE_SIZE).enumerate() {
-        memory.set_page((page << 16) as Word, data)
+    Ok(image::entry(&data)?)
+}
+
+/// Clocks the pipeline until it runs dry, skipping over stalls, and returns the number
+/// of clocks taken
+fn run_to_completion(pipeline: &mut dyn Pipeline) -> usize {
+    let mut clocks = 0;
+    let mut clocks_required = 1;
+
+    loop {
+        clocks += clocks_required;
+        match pipeline.clock(clocks_required) {
+            libpipe::ClockResult::Stall(n) => clocks_required = n,
+            libpipe::ClockResult::Flow => clocks_required = 1,
+            libpipe::ClockResult::Dry => break clocks,
+        }
     }
+}
 
-    Ok((pipeline, conf))
+/// Computes the reuse distances between the logged misses of each cache of a memory
+/// module, if it logs its misses
+///
+/// Only the logged misses are counted, so hits and misses dropped from a full log never
+/// shorten a distance, and the histograms do not predict hit rates the way a histogram
+/// of every access does. Each miss is taken to the line of the cache it missed.
+fn miss_reuse_distances(module: &dyn MemoryModule) -> Option<BTreeMap<&str, ReuseHistogram>> {
+    let log = module.miss_log()?;
+    let mut lines: BTreeMap<&str, Vec<Word>> = BTreeMap::new();
+
+    for &(address, _, cache) in log {
+        let line = match module.get_cache(cache).map(|c| c.line_len()) {
+            None | Some(0) => address,
+            Some(length) => address & !(length as Word - 1),
+        };
+
+        lines.entry(cache).or_default().push(line);
+    }
+
+    Some(
+        lines
+            .into_iter()
+            .map(|(cache, lines)| (cache, reuse_distances(lines)))
+            .collect(),
+    )
+}
+
+/// Runs the image on both the pipelined and unpipelined processors and compares their
+/// final states, returning an error if they differ
+fn validate(conf: toml::Table, bin: PathBuf, loads: &[Load]) -> Result<(), Box<dyn Error>> {
+    let conf = SimulationConfiguration::from_toml(&conf)?;
+    let initial = initial_memory(conf.memory(), &bin, loads)?;
+
+    let mut pipelined = load_pipeline(
+        SimulationConfiguration {
+            pipelining: PipelineMode::Enabled,
+            ..conf.clone()
+        },
+        &bin,
+        loads,
+    )?;
+    let mut unpipelined = load_pipeline(
+        SimulationConfiguration {
+            pipelining: PipelineMode::Disabled,
+            ..conf
+        },
+        &bin,
+        loads,
+    )?;
+
+    let pipelined_clocks = run_to_completion(pipelined.as_mut());
+    let unpipelined_clocks = run_to_completion(unpipelined.as_mut());
+
+    println!("Pipelined clocks: {pipelined_clocks}");
+    println!("Unpipelined clocks: {unpipelined_clocks}");
+
+    write_back(pipelined.as_mut());
+    write_back(unpipelined.as_mut());
+
+    let differences = differences(
+        ("pipelined", pipelined.as_ref(), &initial),
+        ("unpipelined", unpipelined.as_ref(), &initial),
+    );
+
+    if differences.is_empty() {
+        println!("The final states match");
+        Ok(())
+    } else {
+        differences.iter().for_each(|d| println!("{d}"));
+        Err("The pipelined and unpipelined processors diverged".into())
+    }
+}
+
+/// Runs two images to completion under the same configuration and lists where their
+/// final states differ
+fn diff(
+    conf: toml::Table,
+    bins: [&Path; 2],
+    loads: &[Load],
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let conf = SimulationConfiguration::from_toml(&conf)?;
+    let [a, b] = bins;
+    let (a_initial, b_initial) = (
+        initial_memory(conf.memory(), a, loads)?,
+        initial_memory(conf.memory(), b, loads)?,
+    );
+
+    let mut left = load_pipeline(conf.clone(), a, loads)?;
+    let mut right = load_pipeline(conf, b, loads)?;
+
+    let left_clocks = run_to_completion(left.as_mut());
+    let right_clocks = run_to_completion(right.as_mut());
+
+    println!("Clocks for {}: {left_clocks}", a.display());
+    println!("Clocks for {}: {right_clocks}", b.display());
+
+    write_back(left.as_mut());
+    write_back(right.as_mut());
+
+    let (a_name, b_name) = (a.display().to_string(), b.display().to_string());
+
+    Ok(differences(
+        (&a_name, left.as_ref(), &a_initial),
+        (&b_name, right.as_ref(), &b_initial),
+    ))
+}
+
+/// Writes the dirty lines of a finished run back to memory, since they are part of its
+/// final state
+fn write_back(pipeline: &mut dyn Pipeline) {
+    pipeline.memory_module_mut().immediate_writeback();
+}
+
+/// The memory of a run before it starts, which is `memory` with its images loaded
+fn initial_memory(
+    mut memory: Memory,
+    bin: &Path,
+    loads: &[Load],
+) -> Result<Memory, Box<dyn Error>> {
+    load_images(&mut memory, bin, loads)?;
+    Ok(memory)
+}
+
+/// Describes where the final states of two runs differ, each given with its name and
+/// initial memory
+///
+/// Memory which both runs left as it started is not compared, so that two runs of
+/// different images only differ in what they wrote.
+fn differences(
+    (left_name, left, left_initial): (&str, &dyn Pipeline, &Memory),
+    (right_name, right, right_initial): (&str, &dyn Pipeline, &Memory),
+) -> Vec<String> {
+    let mut differences = vec![];
+
+    if left.fault() != right.fault() {
+        let describe = |fault: Option<libpipe::Fault>| match fault {
+            Some(fault) => fault.to_string(),
+            None => "no fault".to_owned(),
+        };
+        differences.push(format!(
+            "Faults differ: {} ({left_name}) vs {} ({right_name})",
+            describe(left.fault()),
+            describe(right.fault())
+        ));
+    }
+
+    // The pipelined processor fetches past the halt, and two images may halt at different
+    // addresses, so the final PC is expected to differ
+    for (register, l, r) in left.registers().diff(right.registers()) {
+        if register == PC {
+            continue;
+        }
+
+        differences.push(format!(
+            "Register {} differs: {l:#010X} ({left_name}) vs {r:#010X} ({right_name})",
+            get_name(register).unwrap_or("<unknown>")
+        ));
+    }
+
+    let (l, r) = (
+        left.memory_module().memory(),
+        right.memory_module().memory(),
+    );
+    let pages = l.checksum_all().into_iter().zip(r.checksum_all());
+
+    for (page, _) in pages.enumerate().filter(|(_, (l, r))| l != r) {
+        let start = (page as Word) << 16;
+        let written = |address| {
+            l.read_byte(address) != left_initial.read_byte(address)
+                || r.read_byte(address) != right_initial.read_byte(address)
+        };
+
+        let Some(address) = (start..=start | 0xFFFF)
+            .find(|&address| l.read_byte(address) != r.read_byte(address) && written(address))
+        else {
+            continue;
+        };
+
+        let word = address & !3;
+        differences.push(format!(
+            "Memory differs in page {page}, first in the word at {word:#010X}: {:#010X} ({left_name}) vs {:#010X} ({right_name})",
+            l.read_word(word),
+            r.read_word(word)
+        ));
+    }
+
+    differences
+}
+
+/// Disassembles the instruction at `address`
+fn disassemble(pipeline: &dyn Pipeline, address: Word) -> String {
+    match decode::<Instruction>(pipeline.memory_module().memory().read_word(address)) {
+        Ok(instruction) => instruction.to_string(),
+        Err(_) => "<invalid>".to_owned(),
+    }
+}
+
+/// Runs the image and prints the `top` addresses which retired the most instructions,
+/// along with the instruction at each, every taken branch if `edges` is set, or the
+/// instruction mix if `mix` is set
+fn profile(
+    conf: toml::Table,
+    bin: PathBuf,
+    loads: &[Load],
+    top: usize,
+    edges: Option<EdgeFormat>,
+    mix: bool,
+) -> Result<(), Box<dyn Error>> {
+    let conf = SimulationConfiguration {
+        profile: true,
+        ..SimulationConfiguration::from_toml(&conf)?
+    };
+    let mut pipeline = load_pipeline(conf, &bin, loads)?;
+
+    run_to_completion(pipeline.as_mut());
+
+    if let Some(format) = edges {
+        print_edges(pipeline.as_ref(), format);
+        return Ok(());
+    }
+
+    let retired = pipeline.retired_instructions();
+    let percent = |count: u64| count as f64 * 100.0 / retired.max(1) as f64;
+
+    if mix {
+        let mut mix: Vec<_> = pipeline.instruction_mix().into_iter().collect();
+        mix.sort_unstable_by(|(a, left), (b, right)| right.cmp(left).then_with(|| a.cmp(b)));
+
+        println!("Retired instructions: {retired}");
+        for (kind, count) in mix {
+            println!("{count:>12} {:>6.2}%  {kind}", percent(count));
+        }
+
+        return Ok(());
+    }
+
+    let mut profile: Vec<_> = pipeline.profile().into_iter().collect();
+    // Ties are broken by address so the report is the same on every run
+    profile.sort_unstable_by_key(|&(address, count)| (std::cmp::Reverse(count), address));
+
+    println!("Retired instructions: {retired}");
+
+    for (address, count) in profile.into_iter().take(top) {
+        println!(
+            "{count:>12} {:>6.2}%  {address:#010X}  {}",
+            percent(count),
+            disassemble(pipeline.as_ref(), address)
+        );
+    }
+
+    Ok(())
+}
+
+/// Prints the taken branches of the profile, ordered by source and then target
+fn print_edges(pipeline: &dyn Pipeline, format: EdgeFormat) {
+    let mut edges: Vec<_> = pipeline.branch_profile().into_iter().collect();
+    edges.sort_unstable();
+
+    match format {
+        EdgeFormat::Dot => {
+            let mut nodes: Vec<_> = edges.iter().flat_map(|&((s, t), _)| [s, t]).collect();
+            nodes.sort_unstable();
+            nodes.dedup();
+
+            println!("digraph branches {{");
+            for address in nodes {
+                println!(
+                    "    \"{address:#010X}\" [label=\"{address:#010X}\\n{}\"];",
+                    disassemble(pipeline, address)
+                );
+            }
+            for ((source, target), count) in edges {
+                println!("    \"{source:#010X}\" -> \"{target:#010X}\" [label=\"{count}\"];");
+            }
+            println!("}}");
+        }
+        EdgeFormat::Json => {
+            let edges: Vec<_> = edges
+                .into_iter()
+                .map(|((source, target), count)| {
+                    serde_json::json!({
+                        "source": format!("{source:#010X}"),
+                        "target": format!("{target:#010X}"),
+                        "count": count,
+                    })
+                })
+                .collect();
+
+            println!("{}", serde_json::Value::Array(edges));
+        }
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -53,8 +450,9 @@ fn main() -> Result<(), Box<dyn Error>> {
             image_file,
             configuration,
             backend_mode,
+            loads,
         }) => {
-            let (pipeline, config) = prepare_config(into_toml(configuration)?, image_file)?;
+            let (pipeline, config) = prepare_config(into_toml(configuration)?, image_file, &loads)?;
 
             if backend_mode {
                 interface::Backend.run(pipeline, config)?;
@@ -65,12 +463,18 @@ fn main() -> Result<(), Box<dyn Error>> {
         Cli::Simulate {
             image_file,
             configuration,
+            loads,
             clock_only,
         } => {
-            let (mut pipeline, _) = prepare_config(into_toml(configuration)?, image_file)?;
+            let conf = SimulationConfiguration::from_toml(&into_toml(configuration)?)?;
+            let charges_energy = conf.energy != Default::default();
+            let mut pipeline = load_image(
+                conf.into_hosted_pipeline(libpipe::Stdio),
+                &image_file,
+                &loads,
+            )?;
 
             let mut clocks = 0;
-            let mut clocks_required = 1;
 
             let start = std::time::Instant::now();
             if clock_only {
@@ -83,20 +487,116 @@ fn main() -> Result<(), Box<dyn Error>> {
                     }
                 }
             } else {
-                loop {
-                    clocks += clocks_required;
-                    match pipeline.clock(clocks_required) {
-                        libpipe::ClockResult::Stall(n) => clocks_required = n,
-                        libpipe::ClockResult::Flow => clocks_required = 1,
-                        libpipe::ClockResult::Dry => break,
-                    }
-                }
+                clocks = run_to_completion(pipeline.as_mut());
             }
             let end = std::time::Instant::now();
 
             println!("Total clocks: {clocks}");
             println!("Total time: {} seconds", (end - start).as_secs_f64());
+
+            println!("Retired instructions: {}", pipeline.retired_instructions());
+
+            let perf = pipeline.perf_counters();
+            println!("Data hazard stalls: {}", perf.data_hazard_stalls);
+            println!("Memory stalls: {}", perf.memory_stalls);
+            println!("Control hazard stalls: {}", perf.control_hazard_stalls);
+            println!("Structural stalls: {}", perf.structural_stalls);
+            println!("Self-modifying code hazards: {}", pipeline.smc_hazards());
+
+            if charges_energy {
+                println!("Energy: {}", pipeline.energy());
+            }
+
+            if let Some(reason) = pipeline.halt_reason() {
+                println!("Halt reason: {reason}");
+            }
+
+            for (cache, histogram) in miss_reuse_distances(pipeline.memory_module())
+                .into_iter()
+                .flatten()
+            {
+                println!("Reuse distances between logged misses of the {cache} cache, in lines:");
+                for (distance, count) in &histogram.distances {
+                    println!("    {distance}: {count}");
+                }
+                println!("    first miss: {}", histogram.cold);
+            }
+        }
+        Cli::Validate {
+            image_file,
+            configuration,
+            loads,
+        } => validate(into_toml(configuration)?, image_file, &loads)?,
+        Cli::Diff {
+            image_a,
+            image_b,
+            configuration,
+            loads,
+        } => {
+            let differences = diff(into_toml(configuration)?, [&image_a, &image_b], &loads)?;
+
+            if !differences.is_empty() {
+                differences.iter().for_each(|d| println!("{d}"));
+                return Err("The final states differ".into());
+            }
+
+            println!("The final states match");
+        }
+        Cli::Profile {
+            image_file,
+            configuration,
+            loads,
+            top,
+            edges,
+            mix,
+        } => profile(
+            into_toml(configuration)?,
+            image_file,
+            &loads,
+            top,
+            edges,
+            mix,
+        )?,
+        Cli::Repl => {
+            // Without pipelining, each instruction retires before the next is fetched
+            let config = SimulationConfiguration {
+                cache: [
+                    ("instruction".into(), CacheConfiguration::Disabled),
+                    ("data".into(), CacheConfiguration::Disabled),
+                ]
+                .into(),
+                miss_penalty: 1,
+                volatile_penalty: 1,
+                pages: PAGES,
+                pipelining: PipelineMode::Disabled,
+                memory_ports: 1,
+                fetch_width: 1,
+                fetch_queue_depth: 1,
+                ..Default::default()
+            };
+
+            interface::Repl.run(config.clone().into_boxed_pipeline(), config)?;
         }
+        Cli::Presets { name, output_file } => match name {
+            Some(name) => {
+                let preset = SimulationConfiguration::preset(&name).ok_or_else(|| {
+                    format!("There is no preset named \"{name}\". Run `presets` to list them.")
+                })?;
+
+                if let Some(out) = output_file {
+                    std::fs::write(&out, preset.to_toml().to_string())?;
+                } else {
+                    println!("{}", preset.to_toml());
+                }
+            }
+            None => {
+                let width = PRESETS.iter().map(|p| p.name.len()).max().unwrap_or(0);
+
+                for preset in PRESETS {
+                    println!("{:width$}  {}", preset.name, preset.description);
+                }
+            }
+        },
         Cli::PrintExampleConfiguration { output_file } => {
             let example = SimulationConfiguration {
                 cache: [
@@ -114,7 +614,27 @@ fn main() -> Result<(), Box<dyn Error>> {
                 miss_penalty: 100,
                 volatile_penalty: 20,
                 writethrough: false,
+                pages: PAGES,
+                memory_seed: None,
                 pipelining: PipelineMode::Enabled,
+                extra_execute_stages: 0,
+                latencies: Default::default(),
+                energy: Default::default(),
+                max_in_flight: 0,
+                memory_ports: 1,
+                fetch_width: 1,
+                fetch_queue_depth: 1,
+                banks: 0,
+                write_buffer_depth: None,
+                miss_log: None,
+                stack_checks: false,
+                write_checks: false,
+                alignment_checks: false,
+                smc_checks: false,
+                vector_table: None,
+                registers: None,
+                console: None,
+                profile: false,
             };
 
             if let Some(out) = output_file {
@@ -127,3 +647,325 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libseis::{
+        instruction_set::{
+            control::ControlOp,
+            encode,
+            integer::{BinaryOp, IntegerOp},
+            register::{ImmOp, ReadOp, RegisterOp, WriteOp},
+        },
+        pages::PAGE_SIZE,
+        registers::V,
+    };
+    use std::fs::{remove_file, write};
+
+    /// Writes `data` to a file in the temporary directory, named after the test using it
+    fn temp_file(name: &str, data: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("seis-sim-{}-{name}", std::process::id()));
+        write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn load_two_images() {
+        let bin = temp_file("main.bin", &[0x11; 8]);
+        let loads = [
+            Load {
+                file: temp_file("first.bin", &[0x22; 4]),
+                address: 0x0000_1000,
+            },
+            Load {
+                file: temp_file("second.bin", &[0x33, 0x44]),
+                address: 0x0001_FFFE,
+            },
+        ];
+
+        let mut memory = Memory::new(2);
+        load_images(&mut memory, &bin, &loads).unwrap();
+
+        for file in [&bin, &loads[0].file, &loads[1].file] {
+            remove_file(file).unwrap();
+        }
+
+        assert_eq!(memory.read_word(0x0000_0004), 0x1111_1111);
+        assert_eq!(memory.read_word(0x0000_1000), 0x2222_2222);
+        assert_eq!(memory.read_short(0x0001_FFFE), 0x3344);
+        assert_eq!(memory.read_byte(0x0000_1004), 0x00, "After the first image");
+    }
+
+    #[test]
+    fn overlapping_images() {
+        let bin = temp_file("overlap-main.bin", &[0x11; 8]);
+        let first = Load {
+            file: temp_file("overlap-first.bin", &[0x22; 16]),
+            address: 0x0000_1000,
+        };
+
+        let overlaps_main = Load {
+            file: temp_file("overlap-main-2.bin", &[0x33; 4]),
+            address: 0x0000_0006,
+        };
+        let overlaps_first = Load {
+            file: temp_file("overlap-first-2.bin", &[0x33; 4]),
+            address: 0x0000_100E,
+        };
+        let too_large = Load {
+            file: temp_file("overlap-large.bin", &[0x33; 4]),
+            address: 0x0001_FFFE,
+        };
+        let files = [
+            bin.clone(),
+            first.file.clone(),
+            overlaps_main.file.clone(),
+            overlaps_first.file.clone(),
+            too_large.file.clone(),
+        ];
+
+        let error = load_images(&mut Memory::new(2), &bin, &[overlaps_main])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("overlaps"), "{error}");
+
+        let error = load_images(&mut Memory::new(2), &bin, &[first, overlaps_first])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("overlap-first.bin"), "{error}");
+
+        assert!(load_images(&mut Memory::new(2), &bin, &[too_large]).is_err());
+
+        for file in files {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn start_at_the_entry_point() {
+        let ldr = |immediate| {
+            encode(Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                zero: true,
+                shift: 0,
+                immediate,
+                destination: V[1],
+            })))
+        };
+        let halt = encode(Instruction::Control(ControlOp::Halt));
+
+        // Running from the start of memory would load 1 instead of 2
+        let code: Vec<u8> = [ldr(1), halt, ldr(2), halt]
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect();
+        let bin = temp_file(
+            "entry.bin",
+            &image::encode(0x0000_0008, [(0x0000_0000, 0, &code[..])]),
+        );
+
+        let mut pipeline =
+            load_pipeline(SimulationConfiguration::small_direct(), &bin, &[]).unwrap();
+        std::fs::remove_file(&bin).unwrap();
+
+        assert_eq!(pipeline.registers().pc, 0x0000_0008);
+
+        run_to_completion(pipeline.as_mut());
+
+        assert_eq!(pipeline.registers()[V[1]], 2);
+        assert_eq!(pipeline.halt_reason(), Some(libpipe::HaltReason::Halt));
+    }
+
+    #[test]
+    fn load_beyond_sixteen_pages() {
+        // A halt, followed by a marker in the twentieth page
+        let mut data = vec![0; 19 * PAGE_SIZE + 4];
+        data[..4].copy_from_slice(&encode(Instruction::Control(ControlOp::Halt)).to_be_bytes());
+        data[19 * PAGE_SIZE..].copy_from_slice(&[0x12, 0x34, 0x56, 0x78]);
+        let bin = temp_file("large.bin", &data);
+
+        let error = load_pipeline(SimulationConfiguration::small_direct(), &bin, &[])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Could not load"), "{error}");
+
+        let conf = SimulationConfiguration {
+            pages: 20,
+            ..SimulationConfiguration::small_direct()
+        };
+        let mut pipeline = load_pipeline(conf, &bin, &[]).unwrap();
+        remove_file(&bin).unwrap();
+
+        run_to_completion(pipeline.as_mut());
+
+        assert_eq!(
+            pipeline.memory_module().memory().read_word(0x0013_0000),
+            0x1234_5678
+        );
+        assert_eq!(pipeline.halt_reason(), Some(libpipe::HaltReason::Halt));
+    }
+
+    /// Writes a flat image holding `program` to a temporary file
+    fn program_file(name: &str, program: &[Instruction]) -> PathBuf {
+        let data: Vec<u8> = program
+            .iter()
+            .flat_map(|&i| encode(i).to_be_bytes())
+            .collect();
+
+        temp_file(name, &data)
+    }
+
+    /// A program which stores `V1 * 2` at `0x1000`, computing the product with `op`
+    fn doubling_program(op: IntegerOp) -> [Instruction; 5] {
+        let ldr = |immediate, destination| {
+            Instruction::Register(RegisterOp::Ldr(ImmOp::Immediate {
+                zero: true,
+                shift: 0,
+                immediate,
+                destination,
+            }))
+        };
+
+        [
+            ldr(3, V[1]),
+            Instruction::Integer(op),
+            ldr(0x1000, V[3]),
+            Instruction::Register(RegisterOp::Slr(WriteOp::Indirect {
+                volatile: false,
+                non_temporal: false,
+                address: V[3],
+                source: V[2],
+            })),
+            Instruction::Control(ControlOp::Halt),
+        ]
+    }
+
+    #[test]
+    fn validate_compares_written_back_memory() {
+        // Reading the word first brings its line into the data cache, which writes back,
+        // so the product is only in memory once the dirty lines are written back
+        let mut program =
+            doubling_program(IntegerOp::Mul(BinaryOp::Immediate(V[1], 2, V[2]))).to_vec();
+        program.insert(
+            3,
+            Instruction::Register(RegisterOp::Llr(ReadOp::Indirect {
+                volatile: false,
+                non_temporal: false,
+                address: V[3],
+                destination: V[4],
+            })),
+        );
+        let bin = program_file("validate-double.bin", &program);
+        let conf = SimulationConfiguration::small_direct();
+
+        let result = validate(conf.to_toml(), bin.clone(), &[]);
+
+        let mut finished = vec![];
+        for pipelining in [PipelineMode::Enabled, PipelineMode::Disabled] {
+            let mut pipeline = load_pipeline(
+                SimulationConfiguration {
+                    pipelining,
+                    ..conf.clone()
+                },
+                &bin,
+                &[],
+            )
+            .unwrap();
+            run_to_completion(pipeline.as_mut());
+            write_back(pipeline.as_mut());
+            finished.push(pipeline.memory_module().memory().read_word(0x0000_1000));
+        }
+
+        remove_file(bin).unwrap();
+
+        result.unwrap();
+        assert_eq!(finished, [6, 6]);
+    }
+
+    #[test]
+    fn equivalent_images_match() {
+        let a = program_file(
+            "diff-multiply.bin",
+            &doubling_program(IntegerOp::Mul(BinaryOp::Immediate(V[1], 2, V[2]))),
+        );
+        let b = program_file(
+            "diff-add.bin",
+            &doubling_program(IntegerOp::Add(BinaryOp::Registers(V[1], V[1], V[2]))),
+        );
+
+        let differences = diff(
+            SimulationConfiguration::small_direct().to_toml(),
+            [&a, &b],
+            &[],
+        );
+
+        for file in [a, b] {
+            remove_file(file).unwrap();
+        }
+
+        assert_eq!(differences.unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn different_images_differ() {
+        let a = program_file(
+            "diff-double.bin",
+            &doubling_program(IntegerOp::Mul(BinaryOp::Immediate(V[1], 2, V[2]))),
+        );
+        let b = program_file(
+            "diff-triple.bin",
+            &doubling_program(IntegerOp::Mul(BinaryOp::Immediate(V[1], 3, V[2]))),
+        );
+
+        let differences = diff(
+            SimulationConfiguration::small_direct().to_toml(),
+            [&a, &b],
+            &[],
+        );
+
+        for file in [a, b] {
+            remove_file(file).unwrap();
+        }
+
+        let differences = differences.unwrap();
+        assert_eq!(differences.len(), 2, "{differences:?}");
+        assert!(differences[0].starts_with("Register V2 differs: 0x00000006"));
+        assert!(differences[1].contains("first in the word at 0x00001000"));
+    }
+
+    #[test]
+    fn miss_lines_follow_their_cache() {
+        use libmem::{
+            cache::{Associative, MultiAssociative},
+            module::{SingleLevel, Status},
+        };
+
+        // Instructions are cached in lines of 4 bytes, and data in lines of 64
+        let mut module = SingleLevel::new(
+            Box::new(MultiAssociative::new(6, 2, 2)),
+            Box::new(Associative::new(2, 2)),
+            Memory::new(1),
+            10,
+            2,
+            false,
+        )
+        .with_miss_log(16);
+
+        for address in [0x0000_1000, 0x0000_1004] {
+            while let Err(Status::Busy(clocks)) = module.read_instruction(address) {
+                module.clock(clocks);
+            }
+        }
+
+        // The data cache misses the same line, which is its own first miss
+        while let Err(Status::Busy(clocks)) = module.read_word(0x0000_1000) {
+            module.clock(clocks);
+        }
+
+        let histograms = miss_reuse_distances(&module).unwrap();
+        assert_eq!(histograms["instruction"].cold, 2);
+        assert_eq!(histograms["instruction"].accesses(), 2);
+        assert_eq!(histograms["data"].cold, 1);
+        assert_eq!(histograms["data"].accesses(), 1);
+    }
+}
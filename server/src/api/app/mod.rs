@@ -1,11 +1,16 @@
 mod read;
+mod stream;
 
-use std::{str::FromStr, sync::Arc, time::Instant};
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex, PoisonError},
+    time::Instant,
+};
 
 use crate::{
     config::SimulationConfiguration,
     states::{Runtime, Runtimes},
-    PAGES,
+    SessionSettings, MAX_CLOCKS,
 };
 use libasm::{compile, Input};
 use libseis::pages::PAGE_SIZE;
@@ -29,9 +34,7 @@ async fn get_uuid(
 
     lock.get(&uuid)
         .map(|(r, i)| {
-            if let Ok(mut lock) = i.try_lock() {
-                *lock = Instant::now();
-            }
+            touch(i);
 
             r.clone()
         })
@@ -41,6 +44,31 @@ async fn get_uuid(
         ))
 }
 
+/// Updates the time since last use of the session corresponding to the `uuid`, if it still exists.
+///
+/// Used after long-running requests so the garbage collector measures the lifetime from when the request ended.
+async fn refresh_uuid(runtimes: &Runtimes, uuid: Uuid) {
+    if let Some((_, i)) = runtimes.read().await.get(&uuid) {
+        touch(i);
+    }
+}
+
+/// Updates the time since last use
+///
+/// The garbage collector only reads the time while holding the write lock on the sessions,
+/// which excludes every caller, so the lock is never contended. A poisoned lock still holds
+/// a whole time, which is overwritten.
+#[inline]
+fn touch(last_used: &Mutex<Instant>) {
+    *last_used.lock().unwrap_or_else(PoisonError::into_inner) = Instant::now();
+}
+
+/// Clamps the requested number of clocks to the server's ceiling, defaulting to the ceiling.
+#[inline]
+fn clock_budget(max_clocks: Option<usize>) -> usize {
+    max_clocks.unwrap_or(MAX_CLOCKS).min(MAX_CLOCKS)
+}
+
 #[inline]
 fn into_uuid(uuid: &str) -> Result<Uuid, (http::Status, String)> {
     Uuid::from_str(uuid).map_err(|e| (http::Status::BadRequest, e.to_string()))
@@ -49,6 +77,7 @@ fn into_uuid(uuid: &str) -> Result<Uuid, (http::Status, String)> {
 #[post("/", data = "<config>")]
 pub async fn init(
     runtimes: &State<Runtimes>,
+    settings: &State<SessionSettings>,
     config: Json<Value>,
 ) -> Result<RawText<String>, (http::Status, RawText<String>)> {
     let mut uuid = Uuid::new_v4();
@@ -103,6 +132,16 @@ pub async fn init(
     let bin =
         compile(input).map_err(|e| (http::Status::InternalServerError, RawText(e.to_string())))?;
 
+    if !settings.make_room(&mut lock, Instant::now()) {
+        return Err((
+            http::Status::ServiceUnavailable,
+            RawText(format!(
+                "The server already has as many sessions as it allows ({}); try again later",
+                settings.max_sessions()
+            )),
+        ));
+    }
+
     lock.insert(uuid, Runtime::new(uuid, config, bin));
 
     println!("Created a new configuration");
@@ -117,12 +156,19 @@ pub async fn dashboard(
 ) -> Result<RawHtml<String>, (http::Status, String)> {
     let uuid = into_uuid(uuid)?;
 
-    get_uuid(runtimes, uuid).await?;
+    let runtime_arc = get_uuid(runtimes, uuid).await?;
+    let pages = runtime_arc
+        .read()
+        .await
+        .state
+        .memory_module()
+        .memory()
+        .page_count();
 
     Ok(RawHtml(format!(
         include_str!("application.html"),
         uuid = uuid,
-        pages = PAGES,
+        pages = pages,
         page_size = PAGE_SIZE,
     )))
 }
@@ -138,36 +184,83 @@ pub async fn clock(
     let runtime_arc = get_uuid(runtimes, uuid).await?;
     let mut runtime = runtime_arc.write().await;
 
+    let done = match count {
+        Some(Json(0)) => runtime.is_done(),
+        Some(Json(1)) | None => runtime.clock(),
+        Some(Json(count)) => runtime.run_for(clock_budget(Some(count))),
+    };
+
+    drop(runtime);
+    refresh_uuid(runtimes, uuid).await;
+
+    Ok(Json(done))
+}
+
+#[post("/<uuid>/delta", data = "<count>")]
+pub async fn delta(
+    runtimes: &State<Runtimes>,
+    uuid: &str,
+    count: Option<Json<usize>>,
+) -> Result<Json<Value>, (http::Status, String)> {
+    let uuid = into_uuid(uuid)?;
+
+    let runtime_arc = get_uuid(runtimes, uuid).await?;
+    let mut runtime = runtime_arc.write().await;
+
     match count {
-        Some(Json(0)) => Ok(Json(runtime.is_done())),
-        Some(Json(1)) | None => Ok(Json(runtime.clock())),
-        Some(Json(count)) => Ok(Json(runtime.run_for(count))),
+        Some(Json(0)) => {}
+        Some(Json(1)) | None => {
+            runtime.clock();
+        }
+        Some(Json(count)) => {
+            runtime.run_for(clock_budget(Some(count)));
+        }
     }
+
+    let delta = runtime.delta();
+
+    drop(runtime);
+    refresh_uuid(runtimes, uuid).await;
+
+    Ok(Json(delta.to_json()))
 }
 
-#[post("/<uuid>/step")]
+#[post("/<uuid>/step?<max_clocks>")]
 pub async fn step(
     runtimes: &State<Runtimes>,
     uuid: &str,
+    max_clocks: Option<usize>,
 ) -> Result<Json<bool>, (http::Status, String)> {
     let uuid = into_uuid(uuid)?;
 
     let runtime_arc = get_uuid(runtimes, uuid).await?;
     let mut runtime = runtime_arc.write().await;
 
-    Ok(Json(runtime.step()))
+    let done = runtime.step(clock_budget(max_clocks));
+
+    drop(runtime);
+    refresh_uuid(runtimes, uuid).await;
+
+    Ok(Json(done))
 }
 
-#[post("/<uuid>/run")]
-pub async fn run(runtimes: &State<Runtimes>, uuid: &str) -> Result<String, (http::Status, String)> {
+#[post("/<uuid>/run?<max_clocks>")]
+pub async fn run(
+    runtimes: &State<Runtimes>,
+    uuid: &str,
+    max_clocks: Option<usize>,
+) -> Result<Json<bool>, (http::Status, String)> {
     let uuid = into_uuid(uuid)?;
 
     let runtime_arc = get_uuid(runtimes, uuid).await?;
     let mut runtime = runtime_arc.write().await;
 
-    runtime.run_to_end();
+    let done = runtime.run_for(clock_budget(max_clocks));
 
-    Ok("Finished".into())
+    drop(runtime);
+    refresh_uuid(runtimes, uuid).await;
+
+    Ok(Json(done))
 }
 
 pub fn exports() -> Vec<Route> {
@@ -175,6 +268,7 @@ pub fn exports() -> Vec<Route> {
         init,
         dashboard,
         clock,
+        delta,
         step,
         run,
         read::read_page,
@@ -187,5 +281,18 @@ pub fn exports() -> Vec<Route> {
         read::read_pipeline_state,
         read::read_cache_names,
         read::read_cache,
+        stream::stream,
     ]
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clock_budget_is_capped() {
+        assert_eq!(clock_budget(None), MAX_CLOCKS);
+        assert_eq!(clock_budget(Some(50)), 50);
+        assert_eq!(clock_budget(Some(MAX_CLOCKS + 1)), MAX_CLOCKS);
+    }
+}
@@ -0,0 +1,325 @@
+use super::{clock_budget, get_uuid, into_uuid, refresh_uuid};
+use crate::states::{Runtime, Runtimes};
+use rocket::{
+    futures::{SinkExt, StreamExt},
+    get, http, State,
+};
+use rocket_ws::{Channel, Message, WebSocket};
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+use tokio::time::{interval, Interval, MissedTickBehavior};
+use uuid::Uuid;
+
+/// The default time between steps during a run
+const RUN_INTERVAL: Duration = Duration::from_millis(100);
+/// The shortest time allowed between steps during a run, so a client cannot flood the server with updates
+const MIN_RUN_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A command sent by the client
+enum Command {
+    Clock(usize),
+    Step,
+    Run(Duration),
+    Pause,
+}
+
+impl Command {
+    fn parse(message: &str) -> Result<Self, String> {
+        let value: Value = serde_json::from_str(message).map_err(|e| e.to_string())?;
+
+        match value.get("command").and_then(Value::as_str) {
+            Some("clock") => Ok(Self::Clock(
+                value
+                    .get("count")
+                    .and_then(Value::as_u64)
+                    .map_or(1, |c| c as usize),
+            )),
+            Some("step") => Ok(Self::Step),
+            Some("run") => Ok(Self::Run(
+                value
+                    .get("interval")
+                    .and_then(Value::as_u64)
+                    .map_or(RUN_INTERVAL, Duration::from_millis)
+                    .max(MIN_RUN_INTERVAL),
+            )),
+            Some("pause") => Ok(Self::Pause),
+            Some(c) => Err(format!("\"{c}\" is not a valid command")),
+            None => Err("Expected key `command`".to_owned()),
+        }
+    }
+}
+
+/// Something the stream has to respond to
+enum Event {
+    Message(Option<Result<Message, rocket_ws::result::Error>>),
+    Tick,
+}
+
+/// Builds the update sent to the client after the simulation is clocked.
+fn update(runtime: &Runtime, running: bool) -> Message {
+    let cache: Map<String, Value> = runtime
+        .config
+        .cache
+        .keys()
+        .filter_map(|name| {
+            runtime
+                .state
+                .memory_module()
+                .get_cache(name.to_lowercase().as_str())
+                .map(|c| (name.clone(), serde_json::to_value(c.get_lines()).unwrap()))
+        })
+        .collect();
+
+    Message::Text(
+        json!({
+            "type": "update",
+            "clocks": runtime.clocks,
+            "done": runtime.is_done(),
+            "running": running,
+            "registers": runtime.read_regs(),
+            "pipeline": serde_json::to_value(runtime.state.stages()).unwrap(),
+            "cache": cache,
+        })
+        .to_string(),
+    )
+}
+
+fn error(message: impl ToString) -> Message {
+    Message::Text(
+        json!({
+            "type": "error",
+            "message": message.to_string(),
+        })
+        .to_string(),
+    )
+}
+
+/// Applies `f` to the session and builds an update from the result.
+///
+/// The session is only locked for the duration of the call, so the garbage collector is never blocked by an idle
+/// stream.
+async fn advance(
+    runtimes: &Runtimes,
+    uuid: Uuid,
+    running: bool,
+    f: impl FnOnce(&mut Runtime),
+) -> Result<(Message, bool), String> {
+    let runtime_arc = get_uuid(runtimes, uuid).await.map_err(|(_, e)| e)?;
+    let mut runtime = runtime_arc.write().await;
+
+    f(&mut runtime);
+
+    let done = runtime.is_done();
+    let message = update(&runtime, running && !done);
+
+    drop(runtime);
+    refresh_uuid(runtimes, uuid).await;
+
+    Ok((message, done))
+}
+
+#[get("/<uuid>/stream")]
+pub async fn stream(
+    ws: WebSocket,
+    runtimes: &State<Runtimes>,
+    uuid: &str,
+) -> Result<Channel<'static>, (http::Status, String)> {
+    let uuid = into_uuid(uuid)?;
+
+    get_uuid(runtimes, uuid).await?;
+
+    let runtimes = runtimes.inner().clone();
+
+    Ok(ws.channel(move |mut stream| {
+        Box::pin(async move {
+            let mut running: Option<Interval> = None;
+
+            loop {
+                let event = match running.as_mut() {
+                    Some(interval) => tokio::select! {
+                        message = stream.next() => Event::Message(message),
+                        _ = interval.tick() => Event::Tick,
+                    },
+                    None => Event::Message(stream.next().await),
+                };
+
+                let text = match event {
+                    Event::Tick => {
+                        let result = advance(&runtimes, uuid, true, |r| {
+                            r.step(clock_budget(None));
+                        })
+                        .await;
+
+                        match result {
+                            Ok((message, done)) => {
+                                if done {
+                                    running = None;
+                                }
+                                stream.send(message).await?;
+                            }
+                            Err(e) => {
+                                stream.send(error(e)).await?;
+                                break;
+                            }
+                        }
+
+                        continue;
+                    }
+                    Event::Message(Some(Ok(Message::Text(text)))) => text,
+                    Event::Message(Some(Ok(Message::Close(_)))) | Event::Message(None) => break,
+                    Event::Message(Some(Ok(_))) => continue,
+                    Event::Message(Some(Err(_))) => break,
+                };
+
+                let command = match Command::parse(&text) {
+                    Ok(command) => command,
+                    Err(e) => {
+                        stream.send(error(e)).await?;
+                        continue;
+                    }
+                };
+
+                let result = match command {
+                    Command::Clock(count) => {
+                        advance(&runtimes, uuid, running.is_some(), |r| match count {
+                            0 => {}
+                            1 => {
+                                r.clock();
+                            }
+                            count => {
+                                r.run_for(clock_budget(Some(count)));
+                            }
+                        })
+                        .await
+                    }
+                    Command::Step => {
+                        advance(&runtimes, uuid, running.is_some(), |r| {
+                            r.step(clock_budget(None));
+                        })
+                        .await
+                    }
+                    Command::Run(period) => {
+                        let mut ticker = interval(period);
+                        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                        running = Some(ticker);
+
+                        advance(&runtimes, uuid, true, |_| {}).await
+                    }
+                    Command::Pause => {
+                        running = None;
+
+                        advance(&runtimes, uuid, false, |_| {}).await
+                    }
+                };
+
+                match result {
+                    Ok((message, done)) => {
+                        if done {
+                            running = None;
+                        }
+                        stream.send(message).await?;
+                    }
+                    Err(e) => {
+                        stream.send(error(e)).await?;
+                        break;
+                    }
+                }
+            }
+
+            Ok(())
+        })
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::SimulationConfiguration;
+    use libasm::{compile, Input};
+
+    #[test]
+    fn commands_parse() {
+        assert!(matches!(
+            Command::parse(r#"{"command": "clock"}"#),
+            Ok(Command::Clock(1))
+        ));
+        assert!(matches!(
+            Command::parse(r#"{"command": "clock", "count": 20}"#),
+            Ok(Command::Clock(20))
+        ));
+        assert!(matches!(
+            Command::parse(r#"{"command": "step"}"#),
+            Ok(Command::Step)
+        ));
+        assert!(matches!(
+            Command::parse(r#"{"command": "pause"}"#),
+            Ok(Command::Pause)
+        ));
+
+        assert!(Command::parse(r#"{"command": "jump"}"#).is_err());
+        assert!(Command::parse(r#"{"count": 1}"#).is_err());
+        assert!(Command::parse("clock").is_err());
+    }
+
+    #[test]
+    fn run_intervals_are_limited() {
+        let interval = |message| match Command::parse(message) {
+            Ok(Command::Run(interval)) => interval,
+            _ => panic!("Expected a run command from {message}"),
+        };
+
+        assert_eq!(interval(r#"{"command": "run"}"#), RUN_INTERVAL);
+        assert_eq!(
+            interval(r#"{"command": "run", "interval": 250}"#),
+            Duration::from_millis(250)
+        );
+        assert_eq!(
+            interval(r#"{"command": "run", "interval": 1}"#),
+            MIN_RUN_INTERVAL
+        );
+    }
+
+    #[test]
+    fn updates_follow_the_schema() {
+        let config = SimulationConfiguration::from_json(&json!({
+            "miss_penalty": 1,
+            "volatile_penalty": 1,
+            "writethrough": false,
+            "cache": {
+                "data": { "mode": "disabled" },
+                "instruction": { "mode": "disabled" },
+            },
+        }))
+        .unwrap();
+        let bin = compile(vec![Input {
+            path: "test.asm",
+            data: "halt\n",
+        }])
+        .unwrap();
+
+        let (runtime, _) = Runtime::new(Uuid::nil(), config, bin);
+        let mut runtime = runtime.try_write().unwrap();
+        runtime.run_for(100);
+
+        let Message::Text(text) = update(&runtime, false) else {
+            panic!("Updates are sent as text");
+        };
+        let update: Value = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(update["type"], "update");
+        assert_eq!(update["clocks"], runtime.clocks);
+        assert_eq!(update["done"], true);
+        assert_eq!(update["running"], false);
+        assert_eq!(update["registers"], runtime.read_regs());
+        assert!(update["pipeline"].is_object());
+        assert!(update["cache"].is_object());
+
+        let Message::Text(text) = error("Expected key `command`") else {
+            panic!("Errors are sent as text");
+        };
+        assert_eq!(
+            serde_json::from_str::<Value>(&text).unwrap(),
+            json!({ "type": "error", "message": "Expected key `command`" })
+        );
+    }
+}
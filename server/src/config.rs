@@ -5,6 +5,7 @@ use libmem::{
     module::SingleLevel,
 };
 use libpipe::{Pipeline, Pipelined, Unpipelined};
+use libseis::pages::MAX_PAGES;
 use serde_json::{Map, Value as JSON};
 use std::{collections::HashMap, error::Error, fmt::Display, str::FromStr};
 
@@ -141,6 +142,8 @@ pub struct SimulationConfiguration {
     pub miss_penalty: usize,
     pub volatile_penalty: usize,
     pub writethrough: bool,
+    /// The number of 64 KiB pages of memory, starting from address zero
+    pub pages: usize,
 
     pub pipelining: PipelineMode,
 }
@@ -159,7 +162,7 @@ impl SimulationConfiguration {
         let mem = SingleLevel::new(
             data_config.into_boxed_cache(),
             instruction_config.into_boxed_cache(),
-            Memory::new(PAGES),
+            Memory::new(self.pages),
             self.miss_penalty,
             self.volatile_penalty,
             self.writethrough,
@@ -193,6 +196,13 @@ impl SimulationConfiguration {
             .ok_or_else(|| "Key required: \"writethrough\"")?
             .as_bool()
             .ok_or_else(|| "Expected boolean")?;
+        result.pages = match json.get("pages") {
+            Some(pages) => get_u64(pages).ok_or("Expected integer")? as usize,
+            None => PAGES,
+        };
+        if !(1..=MAX_PAGES).contains(&result.pages) {
+            return Err(format!("Key \"pages\" must be between 1 and {MAX_PAGES}").into());
+        }
         result.pipelining = if let Some(value) = json.get("pipelining") {
             if let Some(str) = value.as_str() {
                 str.parse()?
@@ -233,6 +243,7 @@ impl SimulationConfiguration {
             (self.volatile_penalty as u64).into(),
         );
         object.insert("writethrough".to_string(), self.writethrough.into());
+        object.insert("pages".to_string(), (self.pages as u64).into());
         object.insert(
             "pipelining".to_string(),
             match self.pipelining {
@@ -3,38 +3,175 @@ mod config;
 mod states;
 
 use std::{
+    collections::HashMap,
     error::Error,
+    str::FromStr,
+    sync::{Arc, PoisonError},
     time::{Duration, Instant},
 };
 
-use states::Runtimes;
+use states::{Runtimes, Session};
 use uuid::Uuid;
 
 const PAGES: usize = 16;
+/// The most clocks a single request may run a simulation for
+const MAX_CLOCKS: usize = 10_000_000;
 
-/// How long each session lasts (5 minutes)
+/// How long each session lasts by default (5 minutes)
 const LIFETIME: Duration = Duration::from_secs(5 * 60);
-/// How often the garbage collector runs (1 minute between runs)
+/// How often the garbage collector runs by default (1 minute between runs)
 const GC_FREQUENCY: Duration = Duration::from_secs(60);
 
-async fn gc(runtimes: Runtimes) {
+/// How many sessions may be live at once by default
+const MAX_SESSIONS: usize = 256;
+
+/// The environment variable setting how long each session lasts, in seconds
+const LIFETIME_VAR: &str = "SEIS_SESSION_LIFETIME";
+/// The environment variable setting how often the garbage collector runs, in seconds
+const GC_FREQUENCY_VAR: &str = "SEIS_GC_FREQUENCY";
+/// The environment variable setting how many sessions may be live at once
+const MAX_SESSIONS_VAR: &str = "SEIS_MAX_SESSIONS";
+/// The environment variable setting the [`OverloadPolicy`]
+const POLICY_VAR: &str = "SEIS_OVERLOAD_POLICY";
+
+/// What happens to a new session when the server already has as many sessions as it allows
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OverloadPolicy {
+    /// The new session is turned away
+    #[default]
+    Reject,
+    /// The session which has gone unused the longest is cleared to make room
+    EvictOldest,
+}
+
+impl FromStr for OverloadPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "reject" => Ok(Self::Reject),
+            "evict-oldest" => Ok(Self::EvictOldest),
+            _ => Err(format!(
+                "{POLICY_VAR} must be either \"reject\" or \"evict-oldest\", found \"{s}\""
+            )),
+        }
+    }
+}
+
+/// How long sessions last, how often expired ones are cleared, and how many may be live at once
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionSettings {
+    lifetime: Duration,
+    frequency: Duration,
+    max_sessions: usize,
+    policy: OverloadPolicy,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            lifetime: LIFETIME,
+            frequency: GC_FREQUENCY,
+            max_sessions: MAX_SESSIONS,
+            policy: OverloadPolicy::default(),
+        }
+    }
+}
+
+impl SessionSettings {
+    /// Reads the settings from the environment, keeping the default of any variable which is
+    /// not set
+    fn from_env() -> Result<Self, String> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Reads the settings through `var`, which looks up a variable by name
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let seconds = |name: &str, default: Duration| match var(name) {
+            Some(value) => match value.trim().parse::<f64>().map(Duration::try_from_secs_f64) {
+                Ok(Ok(duration)) if !duration.is_zero() => Ok(duration),
+                _ => Err(format!(
+                    "{name} must be a positive number of seconds, found \"{value}\""
+                )),
+            },
+            None => Ok(default),
+        };
+
+        let max_sessions = match var(MAX_SESSIONS_VAR) {
+            Some(value) => match value.trim().parse::<usize>() {
+                Ok(max) if max > 0 => Ok(max),
+                _ => Err(format!(
+                    "{MAX_SESSIONS_VAR} must be a positive number of sessions, found \"{value}\""
+                )),
+            },
+            None => Ok(MAX_SESSIONS),
+        }?;
+
+        Ok(Self {
+            lifetime: seconds(LIFETIME_VAR, LIFETIME)?,
+            frequency: seconds(GC_FREQUENCY_VAR, GC_FREQUENCY)?,
+            max_sessions,
+            policy: var(POLICY_VAR)
+                .map(|policy| policy.trim().parse())
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Makes room for a new session, returning false if there is none
+    ///
+    /// Expired sessions which have not been collected yet are not live, so they are cleared
+    /// first. If the sessions are still at the limit, the [`OverloadPolicy`] decides whether
+    /// to turn the new session away or to clear the session which has gone unused the
+    /// longest. A session in use by a request is never cleared.
+    pub fn make_room(&self, sessions: &mut HashMap<Uuid, Session>, now: Instant) -> bool {
+        if sessions.len() < self.max_sessions {
+            return true;
+        }
+
+        for uuid in expired(sessions, now, self.lifetime) {
+            sessions.remove(&uuid);
+        }
+
+        if sessions.len() < self.max_sessions {
+            return true;
+        }
+
+        let oldest = match self.policy {
+            OverloadPolicy::Reject => None,
+            OverloadPolicy::EvictOldest => sessions
+                .iter()
+                .filter(|(_, session)| idle(session))
+                .min_by_key(|&(&uuid, session)| (last_used(session), uuid))
+                .map(|(&uuid, _)| uuid),
+        };
+
+        match oldest {
+            Some(uuid) => {
+                sessions.remove(&uuid);
+                println!("Cleared the oldest session to make room for a new one");
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// How many sessions may be live at once
+    pub fn max_sessions(&self) -> usize {
+        self.max_sessions
+    }
+}
+
+async fn gc(runtimes: Runtimes, settings: SessionSettings) {
     loop {
-        tokio::time::sleep(GC_FREQUENCY).await;
+        tokio::time::sleep(settings.frequency).await;
 
         let mut lock = runtimes.write().await;
-        let now = Instant::now();
-
-        // Get a list of UUIDs for expired sessions (time since last usage MUST not exceed `LIFETIME`)
-        // First, try to write-lock the runtime to check if there are any active connections to it. Ignore sessions with active connections.
-        // Then, check how long the sessions were running for. Finally, store those UUIDs in a list to iterate through and remove sessions from.
-        let expired: Vec<Uuid> = lock
-            .values()
-            .flat_map(|(v, i)| v.try_write().ok().map(|v| (v, i)))
-            .filter_map(|(v, i)| match i.lock() {
-                Ok(last_used) if *last_used + LIFETIME > now => None,
-                _ => Some(v.uuid),
-            })
-            .collect();
+        let expired = expired(
+            &lock,
+            tokio::time::Instant::now().into_std(),
+            settings.lifetime,
+        );
 
         let count = expired.len();
 
@@ -48,16 +185,52 @@ async fn gc(runtimes: Runtimes) {
     }
 }
 
+/// Lists the sessions which have gone unused for longer than `lifetime`, in order of their UUIDs
+///
+/// Requests clone a session's runtime while holding the read lock on the sessions, so while the
+/// garbage collector holds the write lock, no request can start using a session. A session whose
+/// runtime is cloned anywhere else is still in use by a request, and never expires.
+fn expired(sessions: &HashMap<Uuid, Session>, now: Instant, lifetime: Duration) -> Vec<Uuid> {
+    let mut expired: Vec<Uuid> = sessions
+        .iter()
+        .filter(|(_, session)| idle(session) && last_used(session) + lifetime <= now)
+        .map(|(&uuid, _)| uuid)
+        .collect();
+
+    expired.sort();
+    expired
+}
+
+/// Whether no request holds onto a session
+fn idle((runtime, _): &Session) -> bool {
+    Arc::strong_count(runtime) == 1
+}
+
+/// When a session was last used
+fn last_used((_, last_used): &Session) -> Instant {
+    // A panic while touching the session cannot leave the time half-written
+    *last_used.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 #[rocket::main]
 async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     let runtimes = Runtimes::default();
 
-    let gc = tokio::spawn(gc(runtimes.clone()));
+    let settings = SessionSettings::from_env()?;
+    if settings.frequency >= settings.lifetime {
+        eprintln!(
+            "Warning: sessions are collected every {:?} but only last {:?}, so they may outlive their lifetime by most of a collection",
+            settings.frequency, settings.lifetime
+        );
+    }
+
+    let gc = tokio::spawn(gc(runtimes.clone(), settings));
 
     let rocket = rocket::build()
         .mount("/", api::web::exports())
         .mount("/simulation", api::app::exports())
         .manage(runtimes.clone())
+        .manage(settings)
         .launch();
 
     rocket.await?;
@@ -68,3 +241,298 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use config::SimulationConfiguration;
+    use serde_json::{json, Value};
+    use states::Runtime;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    /// Starts a session running an empty program, last used at `last_used`
+    fn session(uuid: Uuid, last_used: Instant) -> Session {
+        let config = SimulationConfiguration::from_json(&json!({
+            "miss_penalty": 1,
+            "volatile_penalty": 1,
+            "writethrough": false,
+            "cache": {
+                "data": { "mode": "disabled" },
+                "instruction": { "mode": "disabled" },
+            },
+        }))
+        .unwrap();
+
+        let (runtime, _) = Runtime::new(uuid, config, vec![]);
+        (runtime, last_used.into())
+    }
+
+    #[test]
+    fn in_use_sessions_survive() {
+        let now = Instant::now() + 2 * LIFETIME;
+        let old = now - 2 * LIFETIME;
+        let (a, b, c) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+
+        let sessions = HashMap::from([
+            (a, session(a, old)),
+            (b, session(b, old)),
+            (c, session(c, now)),
+        ]);
+
+        // A request is still holding onto the session
+        let request = sessions[&b].0.clone();
+        assert_eq!(expired(&sessions, now, LIFETIME), vec![a]);
+
+        drop(request);
+        assert_eq!(expired(&sessions, now, LIFETIME), vec![a, b]);
+    }
+
+    #[test]
+    fn poisoned_sessions_keep_their_time() {
+        let now = Instant::now() + 2 * LIFETIME;
+        let uuid = Uuid::from_u128(1);
+        let sessions = HashMap::from([(uuid, session(uuid, now))]);
+
+        let _ = catch_unwind(AssertUnwindSafe(|| {
+            let _lock = sessions[&uuid].1.lock();
+            panic!("Poisoning the time of last use");
+        }));
+        assert!(sessions[&uuid].1.is_poisoned());
+
+        assert!(expired(&sessions, now, LIFETIME).is_empty());
+        assert_eq!(expired(&sessions, now + LIFETIME, LIFETIME), vec![uuid]);
+    }
+
+    #[test]
+    fn lifetime_from_vars() {
+        let vars = |lifetime: Option<&str>, frequency: Option<&str>| {
+            SessionSettings::from_vars(|name| match name {
+                LIFETIME_VAR => lifetime.map(str::to_owned),
+                GC_FREQUENCY_VAR => frequency.map(str::to_owned),
+                _ => None,
+            })
+        };
+
+        assert_eq!(vars(None, None), Ok(SessionSettings::default()));
+        assert_eq!(
+            vars(Some("600"), Some("0.5")),
+            Ok(SessionSettings {
+                lifetime: Duration::from_secs(600),
+                frequency: Duration::from_millis(500),
+                ..Default::default()
+            })
+        );
+        assert_eq!(vars(Some("90"), None).unwrap().frequency, GC_FREQUENCY);
+
+        for invalid in ["0", "-1", "soon", "inf"] {
+            assert!(vars(None, Some(invalid)).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn session_limit_from_vars() {
+        let vars = |max: &str, policy: &str| {
+            SessionSettings::from_vars(|name| match name {
+                MAX_SESSIONS_VAR => Some(max.to_owned()),
+                POLICY_VAR => Some(policy.to_owned()),
+                _ => None,
+            })
+        };
+
+        let settings = vars("4", "Evict-Oldest").unwrap();
+        assert_eq!(settings.max_sessions, 4);
+        assert_eq!(settings.policy, OverloadPolicy::EvictOldest);
+
+        assert!(vars("0", "reject").is_err());
+        assert!(vars("4", "queue").is_err());
+    }
+
+    /// Makes room for and starts a session last used at `last_used`, as the server does
+    fn start(
+        settings: &SessionSettings,
+        sessions: &mut HashMap<Uuid, Session>,
+        uuid: Uuid,
+        last_used: Instant,
+    ) -> bool {
+        let admitted = settings.make_room(sessions, last_used);
+        if admitted {
+            sessions.insert(uuid, session(uuid, last_used));
+        }
+        admitted
+    }
+
+    #[test]
+    fn sessions_past_the_limit_are_rejected() {
+        let settings = SessionSettings {
+            max_sessions: 3,
+            ..Default::default()
+        };
+        let now = Instant::now();
+        let mut sessions = HashMap::new();
+
+        for n in 0..3 {
+            assert!(start(&settings, &mut sessions, Uuid::from_u128(n), now));
+        }
+        assert!(!start(&settings, &mut sessions, Uuid::from_u128(3), now));
+        assert_eq!(sessions.len(), 3);
+
+        // Expired sessions are not live, even before they are collected
+        let later = now + LIFETIME;
+        assert!(start(&settings, &mut sessions, Uuid::from_u128(3), later));
+        assert_eq!(sessions.keys().collect::<Vec<_>>(), [&Uuid::from_u128(3)]);
+    }
+
+    #[test]
+    fn oldest_idle_session_is_evicted() {
+        let settings = SessionSettings {
+            max_sessions: 3,
+            policy: OverloadPolicy::EvictOldest,
+            ..Default::default()
+        };
+        let now = Instant::now();
+        let mut sessions = HashMap::new();
+
+        for n in 0..3 {
+            let (uuid, last_used) = (Uuid::from_u128(n), now + Duration::from_secs(n as u64));
+            assert!(start(&settings, &mut sessions, uuid, last_used));
+        }
+
+        // The oldest session is in use, so the next oldest makes room
+        let request = sessions[&Uuid::from_u128(0)].0.clone();
+        assert!(start(&settings, &mut sessions, Uuid::from_u128(3), now));
+        assert!(!sessions.contains_key(&Uuid::from_u128(1)));
+        assert_eq!(sessions.len(), 3);
+
+        // Nothing can be evicted while every session is in use
+        let requests: Vec<_> = sessions.values().map(|(r, _)| r.clone()).collect();
+        assert!(!start(&settings, &mut sessions, Uuid::from_u128(4), now));
+
+        drop((request, requests));
+    }
+
+    #[rocket::async_test]
+    async fn full_server_responds_with_503() {
+        use rocket::{
+            http::{ContentType, Status},
+            local::asynchronous::Client,
+        };
+
+        let settings = SessionSettings {
+            max_sessions: 2,
+            ..Default::default()
+        };
+        let rocket = rocket::build()
+            .mount("/simulation", api::app::exports())
+            .manage(Runtimes::default())
+            .manage(settings);
+        let client = Client::tracked(rocket).await.unwrap();
+
+        let body = json!({
+            "files": { "main.asm": "halt\n" },
+            "miss_penalty": 1,
+            "volatile_penalty": 1,
+            "writethrough": false,
+            "cache": {
+                "data": { "mode": "disabled" },
+                "instruction": { "mode": "disabled" },
+            },
+        })
+        .to_string();
+        let create = || {
+            client
+                .post("/simulation")
+                .header(ContentType::JSON)
+                .body(&body)
+                .dispatch()
+        };
+
+        assert_eq!(create().await.status(), Status::Ok);
+        assert_eq!(create().await.status(), Status::Ok);
+        assert_eq!(create().await.status(), Status::ServiceUnavailable);
+    }
+
+    #[rocket::async_test]
+    async fn runs_stop_at_the_clock_budget() {
+        use rocket::{http::ContentType, local::asynchronous::Client};
+
+        let rocket = rocket::build()
+            .mount("/simulation", api::app::exports())
+            .manage(Runtimes::default())
+            .manage(SessionSettings::default());
+        let client = Client::tracked(rocket).await.unwrap();
+
+        let body = json!({
+            "files": { "main.asm": "spin:\n    jmp spin\n" },
+            "miss_penalty": 1,
+            "volatile_penalty": 1,
+            "writethrough": false,
+            "cache": {
+                "data": { "mode": "disabled" },
+                "instruction": { "mode": "disabled" },
+            },
+        })
+        .to_string();
+        let uuid = client
+            .post("/simulation")
+            .header(ContentType::JSON)
+            .body(&body)
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+
+        let run = |max_clocks: usize| {
+            client
+                .post(format!("/simulation/{uuid}/run?max_clocks={max_clocks}"))
+                .dispatch()
+        };
+        let clocks = || async {
+            let delta: Value = client
+                .post(format!("/simulation/{uuid}/delta"))
+                .header(ContentType::JSON)
+                .body("0")
+                .dispatch()
+                .await
+                .into_json()
+                .await
+                .unwrap();
+            delta["clocks"].as_u64().unwrap()
+        };
+
+        // The program never halts, so each request runs out of clocks
+        assert_eq!(run(50).await.into_string().await.unwrap(), "false");
+        assert_eq!(clocks().await, 50);
+
+        assert_eq!(run(25).await.into_string().await.unwrap(), "false");
+        assert_eq!(clocks().await, 75);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gc_runs_at_the_configured_frequency() {
+        let settings = SessionSettings::from_vars(|name| match name {
+            LIFETIME_VAR => Some("10".to_owned()),
+            GC_FREQUENCY_VAR => Some("3".to_owned()),
+            _ => None,
+        })
+        .unwrap();
+
+        let uuid = Uuid::from_u128(1);
+        let runtimes = Runtimes::default();
+        runtimes
+            .write()
+            .await
+            .insert(uuid, session(uuid, tokio::time::Instant::now().into_std()));
+
+        let gc = tokio::spawn(gc(runtimes.clone(), settings));
+
+        // The last collection before the session expires is at 9 seconds, and the next at 12
+        tokio::time::sleep(Duration::from_secs(11)).await;
+        assert!(runtimes.read().await.contains_key(&uuid));
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(!runtimes.read().await.contains_key(&uuid));
+
+        gc.abort();
+    }
+}
@@ -9,4 +9,7 @@ use std::{
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
-pub type Runtimes = Arc<RwLock<HashMap<Uuid, (Arc<RwLock<Runtime>>, Mutex<Instant>)>>>;
+/// A simulation, with when it was last used
+pub type Session = (Arc<RwLock<Runtime>>, Mutex<Instant>);
+
+pub type Runtimes = Arc<RwLock<HashMap<Uuid, Session>>>;
@@ -1,6 +1,7 @@
+use super::Session;
 use crate::config::SimulationConfiguration;
-use libpipe::Pipeline;
-use libseis::types::Word;
+use libpipe::{Pipeline, Registers};
+use libseis::types::{Register, Word};
 use serde::{de::Visitor, Deserialize, Serialize};
 use std::{
     collections::HashMap,
@@ -78,6 +79,49 @@ impl Serialize for AddressType {
     }
 }
 
+/// What changed in a simulation between two calls to [`Runtime::delta`]
+#[derive(Debug)]
+pub struct Delta {
+    /// The version of the previous delta
+    pub since: usize,
+    pub version: usize,
+    pub clocks: usize,
+    pub done: bool,
+    /// The registers which changed, as the register ID followed by the new and old values
+    pub registers: Vec<(Register, Word, Word)>,
+    /// The IDs of the [memory regions](libmem::memory::REGION_SIZE) which were written to
+    pub regions: Vec<usize>,
+    pub statistics: serde_json::Value,
+}
+
+impl Delta {
+    pub fn to_json(&self) -> serde_json::Value {
+        use libseis::registers::NAME;
+        use serde_json::{json, Map};
+
+        let registers: Map<String, serde_json::Value> = self
+            .registers
+            .iter()
+            .map(|&(id, val, _)| {
+                (
+                    NAME[id as usize].to_string(),
+                    json!({ "integer": val, "float": f32::from_bits(val) }),
+                )
+            })
+            .collect();
+
+        json!({
+            "since": self.since,
+            "version": self.version,
+            "clocks": self.clocks,
+            "done": self.done,
+            "registers": registers,
+            "pages": self.regions,
+            "statistics": self.statistics,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Runtime {
     pub uuid: Uuid,
@@ -90,15 +134,16 @@ pub struct Runtime {
     pub clocks: usize,
     pub clock_req: usize,
 
+    /// Incremented every time a [`Delta`] is taken
+    pub version: usize,
+    /// The registers as of the last [`Delta`]
+    last_registers: Registers,
+
     pub config: SimulationConfiguration,
 }
 
 impl Runtime {
-    pub fn new(
-        uuid: Uuid,
-        config: SimulationConfiguration,
-        bin: Vec<u8>,
-    ) -> (Arc<RwLock<Self>>, Mutex<Instant>) {
+    pub fn new(uuid: Uuid, config: SimulationConfiguration, bin: Vec<u8>) -> Session {
         let now = Instant::now();
 
         let mut state = config.into_boxed_pipeline();
@@ -109,6 +154,11 @@ impl Runtime {
             mem.write_byte(address as u32, byte);
         }
 
+        // Loading the program is not a change the client needs to hear about
+        mem.take_dirty();
+
+        let last_registers = *state.registers();
+
         (
             Arc::new(RwLock::new(Self {
                 uuid,
@@ -121,12 +171,49 @@ impl Runtime {
                 clocks: 0,
                 clock_req: 1,
 
+                version: 0,
+                last_registers,
+
                 config,
             })),
             Mutex::new(now),
         )
     }
 
+    /// Lists everything that changed since the last delta (or since the session was created), and starts tracking
+    /// changes anew.
+    pub fn delta(&mut self) -> Delta {
+        let registers = *self.state.registers();
+        let regions = self.state.memory_module_mut().memory_mut().take_dirty();
+
+        let memory = self.state.memory_module();
+        let statistics = serde_json::json!({
+            "clocks": self.clocks,
+            "retired": self.state.retired_instructions(),
+            "stalls": self.state.perf_counters(),
+            "accesses": memory.accesses(),
+            "cache_hits": memory.cache_hits(),
+            "cold_misses": memory.cold_misses(),
+            "conflict_misses": memory.conflict_misses(),
+            "evictions": memory.evictions(),
+        });
+
+        let delta = Delta {
+            since: self.version,
+            version: self.version + 1,
+            clocks: self.clocks,
+            done: self.is_done(),
+            registers: registers.diff(&self.last_registers),
+            regions,
+            statistics,
+        };
+
+        self.version += 1;
+        self.last_registers = registers;
+
+        delta
+    }
+
     pub fn read_watchlist(&self) -> HashMap<Word, String> {
         let mem = self.state.memory_module().memory();
 
@@ -181,22 +268,12 @@ impl Runtime {
         }
     }
 
-    /// Steps the simulation ahead one step (a step is defined as a movement in the pipeline).
+    /// Steps the simulation ahead one step (a step is defined as a movement in the pipeline), clocking at most
+    /// `budget` times. If the step needs more clocks than that, the remainder is carried over to the next request.
     ///
     /// Returns true if the simulation is finished.
-    pub fn step(&mut self) -> bool {
-        if !self.is_done() {
-            self.clocks += self.clock_req;
-            self.clock_req = match self.state.clock(self.clock_req) {
-                libpipe::ClockResult::Stall(clk) => clk,
-                libpipe::ClockResult::Flow => 1,
-                libpipe::ClockResult::Dry => 0,
-            };
-
-            self.is_done()
-        } else {
-            true
-        }
+    pub fn step(&mut self, budget: usize) -> bool {
+        self.run_for(self.clock_req.min(budget))
     }
 
     /// Ticks the simulation `clocks` times.
@@ -216,9 +293,55 @@ impl Runtime {
 
         self.is_done()
     }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use libasm::{compile, Input};
+    use libseis::registers::{PC, V};
+    use serde_json::json;
+
+    fn runtime(program: &str) -> Arc<RwLock<Runtime>> {
+        let config = SimulationConfiguration::from_json(&json!({
+            "miss_penalty": 1,
+            "volatile_penalty": 1,
+            "writethrough": false,
+            "pipelining": false,
+            "cache": {
+                "data": { "mode": "disabled" },
+                "instruction": { "mode": "disabled" },
+            },
+        }))
+        .unwrap();
+        let bin = compile(vec![Input {
+            path: "test.asm",
+            data: program,
+        }])
+        .unwrap();
+
+        Runtime::new(Uuid::nil(), config, bin).0
+    }
+
+    #[test]
+    fn delta_lists_modified_registers() {
+        let runtime = runtime("ldr 10, v3\nhalt\n");
+        let mut runtime = runtime.try_write().unwrap();
+
+        while runtime.state.retired_instructions() == 0 {
+            assert!(!runtime.clock());
+        }
+
+        let delta = runtime.delta();
+
+        assert_eq!((delta.since, delta.version), (0, 1));
+        assert_eq!(delta.registers, vec![(V[3], 10, 0), (PC, 4, 0)]);
+        assert!(delta.regions.is_empty());
+
+        // Nothing has been clocked since
+        let delta = runtime.delta();
 
-    /// Runs the simulation until it is done
-    pub fn run_to_end(&mut self) {
-        while !self.step() {}
+        assert_eq!((delta.since, delta.version), (1, 2));
+        assert!(delta.registers.is_empty());
     }
 }
@@ -675,12 +675,19 @@ export async function step() {
 export async function run() {
     disable_header_buttons();
 
-    let r = await fetch(make_request('run', 'POST'));
+    // Each request is bounded by the server's clock budget, so keep asking until the simulation finishes
+    let done = false;
 
-    if (!r.ok)
-        throw new Error(`Response: ${r.status} ${r.statusText}`);
+    while (!done) {
+        let r = await fetch(make_request('run', 'POST'));
 
-    console.log(await r.blob().then(b => b.text()));
+        if (!r.ok)
+            throw new Error(`Response: ${r.status} ${r.statusText}`);
+
+        done = await r.json();
+    }
+
+    console.log(done);
 
     let tasks = [ registers.update(), watchlist.update(), memory.update(), pipeline.update(), cache.update(false) ];
 
@@ -119,6 +119,8 @@ pub struct SimulationConfiguration {
     pub volatile_penalty: usize,
     pub writethrough: bool,
     pub pipelining: bool,
+    /// The number of 64 KiB pages of memory, starting from address zero
+    pub pages: usize,
 }
 
 #[wasm_bindgen]
@@ -148,6 +150,7 @@ impl SimulationConfiguration {
             volatile_penalty,
             writethrough,
             pipelining,
+            pages: PAGES,
         })
     }
 }
@@ -157,7 +160,7 @@ impl SimulationConfiguration {
         let mem = SingleLevel::new(
             self.data_cache.into_boxed_cache(),
             self.instruction_cache.into_boxed_cache(),
-            Memory::new(PAGES),
+            Memory::new(self.pages),
             self.miss_penalty,
             self.volatile_penalty,
             self.writethrough,
@@ -183,6 +186,7 @@ impl SimulationConfiguration {
         );
         object.insert("writethrough".to_string(), self.writethrough.into());
         object.insert("pipelining".to_string(), self.pipelining.into());
+        object.insert("pages".to_string(), (self.pages as u64).into());
 
         let mut caches = Map::new();
 
@@ -5,10 +5,11 @@ mod stats;
 
 pub use asm::*;
 pub use config::*;
+use libmem::memory::REGION_SIZE;
 use libpipe::Pipeline;
 use libseis::{
     instruction_set::{Decode, Instruction},
-    pages::PAGE_SIZE,
+    pages::{MAX_PAGES, PAGE_SIZE},
     types::Word,
 };
 use regfp32::RegsFp32;
@@ -34,8 +35,7 @@ fn to_object<'a>(value: &impl Serialize) -> Result<JsValue, <&'a JsSer as Serial
 }
 
 const PAGES: usize = 16;
-const REGIONS_PER_PAGE: usize = 4;
-const REGION_SIZE: usize = PAGE_SIZE / REGIONS_PER_PAGE;
+const REGIONS_PER_PAGE: usize = PAGE_SIZE / REGION_SIZE;
 
 #[wasm_bindgen]
 #[derive(Debug, Clone, Copy, Serialize)]
@@ -48,14 +48,20 @@ pub struct SimulatorMemoryConfiguration {
 
 #[wasm_bindgen]
 impl SimulatorMemoryConfiguration {
+    /// Gets the layout of memory with the default number of pages
     pub fn get() -> Self {
-        static CONF: SimulatorMemoryConfiguration = SimulatorMemoryConfiguration {
-            page_count: PAGES,
+        Self::with_pages(PAGES)
+    }
+}
+
+impl SimulatorMemoryConfiguration {
+    fn with_pages(page_count: usize) -> Self {
+        Self {
+            page_count,
             page_size: PAGE_SIZE,
-            regions: REGIONS_PER_PAGE * PAGES,
+            regions: REGIONS_PER_PAGE * page_count,
             region_size: REGION_SIZE,
-        };
-        CONF
+        }
     }
 }
 
@@ -83,9 +89,15 @@ pub struct SimulationState {
 impl SimulationState {
     #[wasm_bindgen(constructor)]
     pub fn new(config: SimulationConfiguration, asm: Vec<u8>) -> Result<SimulationState, JsError> {
+        if !(1..=MAX_PAGES).contains(&config.pages) {
+            return Err(JsError::new(&format!(
+                "The number of pages must be between 1 and {MAX_PAGES}"
+            )));
+        }
+
         let mut state = config.into_boxed_pipeline();
 
-        if asm.len() > PAGES * libseis::pages::PAGE_SIZE {
+        if asm.len() > config.pages * PAGE_SIZE {
             return Err(JsError::new("The assembly data is too large"));
         }
 
@@ -108,6 +120,11 @@ impl SimulationState {
         self.configuration.clone()
     }
 
+    /// Gets the layout of this simulation's memory, which has the configured number of pages
+    pub fn memory_configuration(&self) -> SimulatorMemoryConfiguration {
+        SimulatorMemoryConfiguration::with_pages(self.state.memory_module().memory().page_count())
+    }
+
     #[inline]
     pub fn is_done(&self) -> bool {
         self.clock_req == 0
@@ -165,7 +182,7 @@ impl SimulationState {
     pub fn read_address(&self, address: Word, memory_type: MemoryType) -> Result<JsValue, JsError> {
         let mem = self.state.memory_module().memory();
 
-        if PAGES * PAGE_SIZE > address as usize {
+        if mem.page_count() * PAGE_SIZE > address as usize {
             Ok(match memory_type {
                 MemoryType::Byte => JsValue::from(mem.read_byte(address)),
                 MemoryType::Short => JsValue::from(mem.read_short(address)),
@@ -202,6 +219,11 @@ impl SimulationState {
         to_object(&(RegsFp32::from(self.state.registers().clone()))).unwrap()
     }
 
+    /// Reads every register as its word, float, and signed integer in one call
+    pub fn read_registers_detailed(&self) -> JsValue {
+        to_object(&self.state.registers().detailed()).unwrap()
+    }
+
     pub fn get_region_hash(&self, region_id: usize) -> Result<String, JsError> {
         let page_id = region_id / REGIONS_PER_PAGE;
         let region_id = region_id % REGIONS_PER_PAGE;
@@ -220,6 +242,12 @@ impl SimulationState {
             .ok_or_else(|| JsError::new("Failed to get page"))
     }
 
+    /// Lists the regions written since the last call (or since the simulation was created), so only those need to be
+    /// read again
+    pub fn take_dirty_regions(&mut self) -> Vec<usize> {
+        self.state.memory_module_mut().memory_mut().take_dirty()
+    }
+
     pub fn read_region(&self, region_id: usize) -> Result<Vec<u8>, JsError> {
         let page_id = region_id / REGIONS_PER_PAGE;
         let region_id = region_id % REGIONS_PER_PAGE;